@@ -0,0 +1,65 @@
+// Writes the integrity digest into a release executable's embedded manifest
+// (see src-tauri/src/integrity.rs). `tauri build` runs this as its
+// beforeBundleCommand, after linking and before the installers are made.
+//
+// Only Linux builds are stamped: macOS and Windows releases are code-signed,
+// which rewrites the executable, and are checked against that signature
+// instead.
+//
+// Usage: bun scripts/stamp-integrity.mjs [path-to-executable]
+// Without a path, the executable of the build tauri is running is stamped:
+// target/<triple>/<profile>/pulsar, or target/<profile>/pulsar when no target
+// was given. When both exist the build is ambiguous and a path is required.
+
+import { createHash } from 'node:crypto';
+import { existsSync, readFileSync, writeFileSync } from 'node:fs';
+import { join } from 'node:path';
+
+const MAGIC = Buffer.from('PULSAR-INTEG-V1\0', 'latin1');
+const DIGEST_LEN = 32;
+
+function builtExecutable() {
+  const triple = process.env.TAURI_ENV_TARGET_TRIPLE;
+  if (!triple) {
+    throw new Error('TAURI_ENV_TARGET_TRIPLE is not set; pass the executable path explicitly');
+  }
+  const profile = process.env.TAURI_ENV_DEBUG === 'true' ? 'debug' : 'release';
+  const targetDir = process.env.CARGO_TARGET_DIR ?? join('src-tauri', 'target');
+  const candidates = [join(targetDir, triple, profile, 'pulsar'), join(targetDir, profile, 'pulsar')].filter(
+    (path) => existsSync(path)
+  );
+  if (candidates.length !== 1) {
+    throw new Error(
+      candidates.length === 0
+        ? `No ${profile} pulsar executable found for ${triple} under ${targetDir}`
+        : `Both ${candidates.join(' and ')} exist; pass the executable path explicitly`
+    );
+  }
+  return candidates[0];
+}
+
+const platform = process.env.TAURI_ENV_PLATFORM ?? process.platform;
+if (!process.argv[2] && platform !== 'linux') {
+  console.log(`Skipping integrity stamp: ${platform} builds are checked by their code signature`);
+  process.exit(0);
+}
+
+const exe = process.argv[2] ?? builtExecutable();
+const bytes = readFileSync(exe);
+
+// The runtime check uses the first occurrence of the marker, so must we.
+const offset = bytes.indexOf(MAGIC);
+if (offset < 0) {
+  throw new Error(`${exe} carries no integrity manifest`);
+}
+const slot = offset + MAGIC.length;
+if (slot + DIGEST_LEN > bytes.length) {
+  throw new Error(`${exe} has a truncated integrity manifest`);
+}
+
+bytes.fill(0, slot, slot + DIGEST_LEN);
+const digest = createHash('sha256').update(bytes).digest();
+digest.copy(bytes, slot);
+writeFileSync(exe, bytes);
+
+console.log(`Stamped ${exe} with ${digest.toString('hex')}`);
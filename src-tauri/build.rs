@@ -1,7 +1,48 @@
-use std::{env, path::Path};
+use std::collections::HashSet;
+use std::{env, fs, path::Path, path::PathBuf};
+
+include!("src/password_bloom.rs");
+
+/// Filter bits per listed password. With `BLOOM_HASHES` probes this gives
+/// fewer than one false positive per 2,000 unlisted passwords.
+const BLOOM_BITS_PER_ENTRY: u64 = 16;
+
+/// Builds the bloom filter `common_passwords.rs` checks passwords against.
+/// Release builds point `PULSAR_PASSWORD_LIST` at a large breach-derived
+/// list (see docs/SECURITY.md); other builds use the short bundled list.
+fn build_password_bloom() {
+    println!("cargo:rerun-if-env-changed=PULSAR_PASSWORD_LIST");
+    let list = env::var_os("PULSAR_PASSWORD_LIST")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("src/common_passwords.txt"));
+    println!("cargo:rerun-if-changed={}", list.display());
+    let text = fs::read(&list)
+        .unwrap_or_else(|e| panic!("cannot read password list {}: {e}", list.display()));
+    let words: HashSet<String> = String::from_utf8_lossy(&text)
+        .lines()
+        .map(|line| line.trim().to_lowercase())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+
+    let bits = (words.len() as u64 * BLOOM_BITS_PER_ENTRY)
+        .max(64)
+        .next_multiple_of(8);
+    let mut filter = vec![0u8; (bits / 8) as usize];
+    for word in &words {
+        for bit in bloom_positions(word, bits) {
+            filter[(bit / 8) as usize] |= 1 << (bit % 8);
+        }
+    }
+
+    let mut out = bits.to_le_bytes().to_vec();
+    out.extend_from_slice(&filter);
+    let dest = Path::new(&env::var("OUT_DIR").unwrap()).join("common_passwords.bloom");
+    fs::write(dest, out).expect("write common password filter");
+}
 
 fn main() {
     println!("cargo:rustc-env=SQLX_SQLITE_HAS_CODEC=1");
+    build_password_bloom();
 
     if cfg!(target_os = "windows") {
         let lib_dir = env::var("OPENSSL_LIB_DIR").ok().or_else(|| {
@@ -0,0 +1 @@
+ALTER TABLE password_items ADD COLUMN autotype_patterns TEXT;
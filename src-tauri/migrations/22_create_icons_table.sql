@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS icons (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    host_hash TEXT NOT NULL UNIQUE,
+    data TEXT NOT NULL,
+    fetched_at TEXT NOT NULL
+);
@@ -0,0 +1 @@
+ALTER TABLE password_items ADD COLUMN details TEXT;
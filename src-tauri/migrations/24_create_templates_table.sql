@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS templates (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    category TEXT NOT NULL,
+    custom_fields TEXT NOT NULL,
+    field_order TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
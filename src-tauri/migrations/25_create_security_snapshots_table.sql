@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS security_snapshots (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    week TEXT NOT NULL UNIQUE,
+    data TEXT NOT NULL,
+    recorded_at TEXT NOT NULL
+);
@@ -0,0 +1,2 @@
+ALTER TABLE buttons ADD COLUMN parent_id INTEGER REFERENCES buttons (id);
+CREATE INDEX IF NOT EXISTS idx_buttons_parent_id ON buttons(parent_id);
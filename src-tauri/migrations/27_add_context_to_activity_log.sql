@@ -0,0 +1 @@
+ALTER TABLE activity_log ADD COLUMN context TEXT;
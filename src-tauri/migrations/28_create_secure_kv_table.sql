@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS secure_kv (
+    namespace_hash TEXT NOT NULL,
+    key_hash TEXT NOT NULL,
+    key_name TEXT NOT NULL,
+    value TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (namespace_hash, key_hash)
+);
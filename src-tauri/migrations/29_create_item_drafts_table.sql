@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS item_drafts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_id INTEGER UNIQUE,
+    data TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (item_id) REFERENCES password_items (id) ON DELETE CASCADE
+);
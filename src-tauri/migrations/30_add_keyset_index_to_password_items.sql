@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS idx_password_items_updated_at_id ON password_items(updated_at DESC, id DESC);
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS item_conflicts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_id INTEGER NOT NULL UNIQUE,
+    source TEXT NOT NULL,
+    incoming TEXT NOT NULL,
+    detected_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL,
+    FOREIGN KEY (item_id) REFERENCES password_items (id) ON DELETE CASCADE
+);
@@ -0,0 +1 @@
+ALTER TABLE activity_log ADD COLUMN chain_hash TEXT;
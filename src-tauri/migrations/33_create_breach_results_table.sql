@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS breach_results (
+    item_id INTEGER PRIMARY KEY,
+    data TEXT NOT NULL,
+    checked_at TEXT NOT NULL,
+    FOREIGN KEY (item_id) REFERENCES password_items (id) ON DELETE CASCADE
+);
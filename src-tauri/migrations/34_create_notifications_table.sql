@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS notifications (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    source TEXT NOT NULL,
+    key TEXT NOT NULL UNIQUE,
+    item_id INTEGER,
+    data TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    dismissed_at TEXT,
+    FOREIGN KEY (item_id) REFERENCES password_items (id) ON DELETE CASCADE
+);
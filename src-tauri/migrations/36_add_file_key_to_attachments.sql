@@ -0,0 +1 @@
+ALTER TABLE attachments ADD COLUMN file_key TEXT;
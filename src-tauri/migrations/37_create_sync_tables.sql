@@ -0,0 +1,47 @@
+CREATE TABLE IF NOT EXISTS item_revisions (
+    item_id INTEGER PRIMARY KEY,
+    sync_id TEXT NOT NULL UNIQUE,
+    revision INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (item_id) REFERENCES password_items (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS sync_tombstones (
+    sync_id TEXT PRIMARY KEY,
+    revision INTEGER NOT NULL,
+    deleted_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS sync_base (
+    peer_id TEXT NOT NULL,
+    sync_id TEXT NOT NULL,
+    local_revision INTEGER NOT NULL,
+    remote_revision INTEGER NOT NULL,
+    PRIMARY KEY (peer_id, sync_id)
+);
+
+-- Items that existed before sync get an id derived from their row id, so two
+-- copies of a vault upgraded separately still agree on which item is which.
+INSERT OR IGNORE INTO item_revisions (item_id, sync_id, revision)
+SELECT id, 'legacy-' || id, 1 FROM password_items;
+
+CREATE TRIGGER IF NOT EXISTS item_revisions_insert
+AFTER INSERT ON password_items
+BEGIN
+    INSERT OR IGNORE INTO item_revisions (item_id, sync_id, revision)
+    VALUES (NEW.id, lower(hex(randomblob(16))), 1);
+END;
+
+CREATE TRIGGER IF NOT EXISTS item_revisions_update
+AFTER UPDATE ON password_items
+BEGIN
+    UPDATE item_revisions SET revision = revision + 1 WHERE item_id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS item_revisions_delete
+BEFORE DELETE ON password_items
+BEGIN
+    INSERT OR REPLACE INTO sync_tombstones (sync_id, revision, deleted_at)
+    SELECT sync_id, revision + 1, strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    FROM item_revisions WHERE item_id = OLD.id;
+    DELETE FROM item_revisions WHERE item_id = OLD.id;
+END;
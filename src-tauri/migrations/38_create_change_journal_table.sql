@@ -0,0 +1,43 @@
+CREATE TABLE IF NOT EXISTS change_journal (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    sync_id TEXT NOT NULL,
+    revision INTEGER NOT NULL,
+    operation TEXT NOT NULL CHECK (operation IN ('create', 'update', 'delete')),
+    recorded_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_change_journal_sync_id ON change_journal (sync_id);
+
+-- Items that already exist count as created, so the changes since revision 0
+-- describe the whole vault.
+INSERT INTO change_journal (sync_id, revision, operation, recorded_at)
+SELECT sync_id, revision, 'create', strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+FROM item_revisions ORDER BY item_id;
+
+CREATE TRIGGER IF NOT EXISTS change_journal_create
+AFTER INSERT ON item_revisions
+BEGIN
+    INSERT INTO change_journal (sync_id, revision, operation, recorded_at)
+    VALUES (NEW.sync_id, NEW.revision, 'create', strftime('%Y-%m-%dT%H:%M:%fZ', 'now'));
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_journal_update
+AFTER UPDATE OF revision ON item_revisions
+BEGIN
+    INSERT INTO change_journal (sync_id, revision, operation, recorded_at)
+    VALUES (NEW.sync_id, NEW.revision, 'update', strftime('%Y-%m-%dT%H:%M:%fZ', 'now'));
+END;
+
+-- Sync gives an item pulled from another copy that copy's sync id.
+CREATE TRIGGER IF NOT EXISTS change_journal_rename
+AFTER UPDATE OF sync_id ON item_revisions
+BEGIN
+    UPDATE change_journal SET sync_id = NEW.sync_id WHERE sync_id = OLD.sync_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_journal_delete
+AFTER INSERT ON sync_tombstones
+BEGIN
+    INSERT INTO change_journal (sync_id, revision, operation, recorded_at)
+    VALUES (NEW.sync_id, NEW.revision, 'delete', strftime('%Y-%m-%dT%H:%M:%fZ', 'now'));
+END;
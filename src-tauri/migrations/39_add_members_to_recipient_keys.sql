@@ -0,0 +1,17 @@
+-- Rows without a private key are members of a shared vault: people whose
+-- public key the vault key is wrapped for. `public_key` is encrypted with a
+-- fresh nonce, so it carries no UNIQUE constraint; duplicate members are
+-- refused when they are added.
+CREATE TABLE recipient_keys_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    public_key TEXT NOT NULL,
+    private_key TEXT,
+    added_at TEXT
+);
+
+INSERT INTO recipient_keys_new (id, name, public_key, private_key)
+SELECT id, name, public_key, private_key FROM recipient_keys;
+
+DROP TABLE recipient_keys;
+ALTER TABLE recipient_keys_new RENAME TO recipient_keys;
@@ -0,0 +1 @@
+ALTER TABLE password_items ADD COLUMN read_only INTEGER NOT NULL DEFAULT 0;
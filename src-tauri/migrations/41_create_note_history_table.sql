@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS note_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_id INTEGER NOT NULL,
+    notes TEXT,
+    recorded_at TEXT NOT NULL,
+    FOREIGN KEY (item_id) REFERENCES password_items (id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_note_history_item_id ON note_history(item_id, id DESC);
@@ -0,0 +1,393 @@
+//! History of failed unlock attempts, so users can see whether someone has
+//! been guessing their password. Attempts are made while the vault is
+//! locked, so they cannot go into its encrypted configuration table. They
+//! are kept in a plaintext file in the app data dir instead, with an HMAC
+//! keyed by a secret in the OS keyring so edits to the file are detected.
+//! The same file persists each vault's unlock backoff across restarts.
+
+use crate::error::{Error, Result};
+use crate::state::AppState;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use subtle::ConstantTimeEq;
+use tauri::{AppHandle, Manager, State};
+use zeroize::Zeroizing;
+
+const FILE_NAME: &str = "unlock-attempts.json";
+const FILE_VERSION: u8 = 1;
+const KEYRING_SERVICE: &str = "pulsar-unlock-attempts";
+const KEYRING_USER: &str = "integrity-key";
+const KEYRING_GENERATION_USER: &str = "generation";
+const MAX_ATTEMPTS: usize = 500;
+
+static FILE: OnceLock<PathBuf> = OnceLock::new();
+static WRITE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnlockAttempt {
+    pub at: String,
+    pub vault_path: String,
+    /// The password was right but the login TOTP code was not.
+    pub totp_stage: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttemptLogIntegrity {
+    Verified,
+    /// The file was edited outside the app at some point.
+    Tampered,
+    /// No keyring is available, so the file carries no HMAC.
+    Unprotected,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnlockAttemptHistory {
+    /// Newest first.
+    pub attempts: Vec<UnlockAttempt>,
+    pub integrity: AttemptLogIntegrity,
+}
+
+/// Persisted unlock backoff of one vault, so relaunching the app does not
+/// reset it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ThrottleRecord {
+    failures: u32,
+    last_failure: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct Body {
+    attempts: Vec<UnlockAttempt>,
+    /// Keyed by vault path. A BTreeMap keeps the serialised form, and so
+    /// the HMAC, stable.
+    throttle: BTreeMap<String, ThrottleRecord>,
+    /// Bumped on every write and mirrored in the keyring, so restoring an
+    /// older copy of the file or deleting it is noticed.
+    generation: u64,
+    /// When an edit was first detected. Sticky, so rewriting the file does
+    /// not hide it.
+    tampered_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AttemptsFile {
+    version: u8,
+    #[serde(flatten)]
+    body: Body,
+    tag: Option<String>,
+}
+
+struct IntegrityKey {
+    key: Zeroizing<Vec<u8>>,
+    generation: u64,
+    created: bool,
+}
+
+/// The log as read from disk, with what is needed to write it back.
+struct AttemptLog {
+    key: Option<IntegrityKey>,
+    body: Body,
+    integrity: AttemptLogIntegrity,
+}
+
+pub fn init(app: &AppHandle) {
+    match app.path().app_data_dir() {
+        Ok(dir) => {
+            let _ = FILE.set(dir.join(FILE_NAME));
+        }
+        Err(e) => eprintln!("[UnlockAttempts] Failed to resolve app data dir: {}", e),
+    }
+}
+
+fn file_path() -> Result<&'static PathBuf> {
+    FILE.get()
+        .ok_or_else(|| Error::Internal("Unlock attempt log is not initialised".to_string()))
+}
+
+/// Loads the HMAC key and write generation from the keyring, creating the
+/// key on first use.
+fn load_integrity_key() -> Result<IntegrityKey> {
+    let entry = crate::keyring_backend::entry(KEYRING_SERVICE, KEYRING_USER)?;
+    let (key, created) = match entry.get_password() {
+        Ok(stored) => (
+            hex::decode(stored)
+                .map(Zeroizing::new)
+                .map_err(|_| Error::Internal("Corrupt unlock attempt log key".to_string()))?,
+            false,
+        ),
+        Err(keyring::Error::NoEntry) => {
+            let mut key = Zeroizing::new(vec![0u8; 32]);
+            OsRng.fill_bytes(&mut key);
+            entry
+                .set_password(&hex::encode(key.as_slice()))
+                .map_err(crate::keyring_backend::map_keyring_error)?;
+            (key, true)
+        }
+        Err(e) => return Err(crate::keyring_backend::map_keyring_error(e)),
+    };
+
+    let entry = crate::keyring_backend::entry(KEYRING_SERVICE, KEYRING_GENERATION_USER)?;
+    let generation = match entry.get_password() {
+        Ok(stored) => stored.parse().unwrap_or(u64::MAX),
+        Err(keyring::Error::NoEntry) => 0,
+        Err(e) => return Err(crate::keyring_backend::map_keyring_error(e)),
+    };
+    Ok(IntegrityKey {
+        key,
+        generation,
+        created,
+    })
+}
+
+fn store_generation(generation: u64) -> Result<()> {
+    crate::keyring_backend::entry(KEYRING_SERVICE, KEYRING_GENERATION_USER)?
+        .set_password(&generation.to_string())
+        .map_err(crate::keyring_backend::map_keyring_error)
+}
+
+async fn run_blocking<T, F>(f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| Error::Internal(format!("Runtime error: {}", e)))?
+}
+
+fn compute_tag(key: &[u8], body: &Body) -> Result<String> {
+    let mut mac =
+        <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC can take key of any size");
+    mac.update(&serde_json::to_vec(body)?);
+    Ok(hex::encode(mac.finalize().into_bytes()))
+}
+
+async fn read_file() -> Result<Option<AttemptsFile>> {
+    match tokio::fs::read(file_path()?).await {
+        Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Error::Io(e)),
+    }
+}
+
+async fn open() -> Result<AttemptLog> {
+    let key = match run_blocking(load_integrity_key).await {
+        Ok(key) => Some(key),
+        Err(e) => {
+            eprintln!("[UnlockAttempts] {}", e);
+            None
+        }
+    };
+    let file = read_file().await?;
+
+    let Some(integrity_key) = key.as_ref() else {
+        return Ok(AttemptLog {
+            key,
+            body: file.map(|f| f.body).unwrap_or_default(),
+            integrity: AttemptLogIntegrity::Unprotected,
+        });
+    };
+    let (body, intact) = match file {
+        Some(file) => {
+            let expected = compute_tag(&integrity_key.key, &file.body)?;
+            let intact = file
+                .tag
+                .is_some_and(|tag| bool::from(expected.as_bytes().ct_eq(tag.as_bytes())))
+                && file.body.generation == integrity_key.generation;
+            (file.body, intact)
+        }
+        // A log that was never written is fine; one the keyring says was
+        // written has been deleted.
+        None => (Body::default(), integrity_key.generation == 0),
+    };
+    let integrity = if intact && body.tampered_at.is_none() {
+        AttemptLogIntegrity::Verified
+    } else {
+        AttemptLogIntegrity::Tampered
+    };
+
+    let mut log = AttemptLog {
+        key,
+        body,
+        integrity,
+    };
+    if integrity == AttemptLogIntegrity::Tampered && log.body.tampered_at.is_none() {
+        log.body.tampered_at = Some(Utc::now().to_rfc3339());
+        log.store().await?;
+    } else if log.key.as_ref().is_some_and(|k| k.created) {
+        log.store().await?;
+    }
+    Ok(log)
+}
+
+impl AttemptLog {
+    async fn store(&mut self) -> Result<()> {
+        let path = file_path()?;
+        if let Some(key) = self.key.as_mut() {
+            key.generation = key.generation.max(self.body.generation).saturating_add(1);
+            self.body.generation = key.generation;
+        }
+        let tag = self
+            .key
+            .as_ref()
+            .map(|key| compute_tag(&key.key, &self.body))
+            .transpose()?;
+        let file = AttemptsFile {
+            version: FILE_VERSION,
+            body: std::mem::take(&mut self.body),
+            tag,
+        };
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let tmp = path.with_extension("tmp");
+        let written = tokio::fs::write(&tmp, serde_json::to_vec(&file)?).await;
+        self.body = file.body;
+        written?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = tokio::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600)).await;
+        }
+        tokio::fs::rename(&tmp, path).await?;
+
+        // Written after the file: a crash in between reads as tampering,
+        // which errs on the side of throttling.
+        if let Some(generation) = self.key.as_ref().map(|key| key.generation) {
+            run_blocking(move || store_generation(generation)).await?;
+        }
+        Ok(())
+    }
+}
+
+async fn try_record_failure(db_path: &Path, totp_stage: bool) -> Result<()> {
+    let _guard = WRITE_LOCK.lock().await;
+    let mut log = open().await?;
+    let vault_path = db_path.to_string_lossy().to_string();
+    let now = Utc::now().to_rfc3339();
+    if !totp_stage {
+        let record = log
+            .body
+            .throttle
+            .entry(vault_path.clone())
+            .or_insert(ThrottleRecord {
+                failures: 0,
+                last_failure: now.clone(),
+            });
+        record.failures = record.failures.saturating_add(1);
+        record.last_failure = now.clone();
+    }
+    log.body.attempts.push(UnlockAttempt {
+        at: now,
+        vault_path,
+        totp_stage,
+    });
+    let excess = log.body.attempts.len().saturating_sub(MAX_ATTEMPTS);
+    log.body.attempts.drain(..excess);
+    log.store().await
+}
+
+/// Records a failed unlock of the vault at `db_path`, and for a wrong
+/// password also its persisted backoff. Failures to write the log are
+/// reported but never fail the unlock itself.
+pub async fn record_failure(db_path: &Path, totp_stage: bool) {
+    if let Err(e) = try_record_failure(db_path, totp_stage).await {
+        eprintln!("[UnlockAttempts] Failed to record unlock attempt: {}", e);
+    }
+}
+
+/// Clears the persisted backoff of a vault after a successful unlock.
+pub async fn reset_throttle(db_path: &Path) {
+    let _guard = WRITE_LOCK.lock().await;
+    let result = match open().await {
+        Ok(mut log) => {
+            if log
+                .body
+                .throttle
+                .remove(db_path.to_string_lossy().as_ref())
+                .is_some()
+            {
+                log.store().await
+            } else {
+                Ok(())
+            }
+        }
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        eprintln!("[UnlockAttempts] Failed to reset unlock throttle: {}", e);
+    }
+}
+
+/// Failure count and time of the last failure persisted for a vault. Once
+/// the log has been tampered with, every vault is treated as having failed
+/// `tampered_failures` times, from the moment the edit was detected.
+pub async fn persisted_throttle(
+    db_path: &Path,
+    tampered_failures: u32,
+) -> Option<(u32, DateTime<Utc>)> {
+    let log = {
+        let _guard = WRITE_LOCK.lock().await;
+        match open().await {
+            Ok(log) => log,
+            Err(e) => {
+                eprintln!("[UnlockAttempts] Failed to read unlock throttle: {}", e);
+                return None;
+            }
+        }
+    };
+    let parse = |at: &str| {
+        DateTime::parse_from_rfc3339(at)
+            .ok()
+            .map(|at| at.with_timezone(&Utc))
+    };
+    let record = log
+        .body
+        .throttle
+        .get(db_path.to_string_lossy().as_ref())
+        .and_then(|r| Some((r.failures, parse(&r.last_failure)?)));
+    if log.integrity != AttemptLogIntegrity::Tampered {
+        return record;
+    }
+    let tampered_at = log.body.tampered_at.as_deref().and_then(parse);
+    let last_failure = record.map(|(_, at)| at).max(tampered_at)?;
+    Some((tampered_failures, last_failure))
+}
+
+/// Failed unlock attempts against the open vault.
+#[tauri::command]
+pub async fn get_unlock_attempt_history(
+    state: State<'_, AppState>,
+) -> Result<UnlockAttemptHistory> {
+    state.key.lock().await.as_ref().ok_or(Error::VaultLocked)?;
+    let db_path = super::get_db_path(&state).await?;
+    let vault_path = db_path.to_string_lossy();
+
+    let log = {
+        let _guard = WRITE_LOCK.lock().await;
+        open().await?
+    };
+    let attempts = log
+        .body
+        .attempts
+        .into_iter()
+        .rev()
+        .filter(|attempt| attempt.vault_path == vault_path)
+        .collect();
+    Ok(UnlockAttemptHistory {
+        attempts,
+        integrity: log.integrity,
+    })
+}
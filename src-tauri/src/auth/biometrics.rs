@@ -1,15 +1,23 @@
 use crate::auth::metadata::get_vault_id;
 use crate::encryption::{decrypt, encrypt};
 use crate::error::{Error, Result};
+use crate::keyring_backend::{entry as keyring_entry, map_keyring_error};
 use crate::state::AppState;
 use base64::{engine::general_purpose, Engine as _};
-use keyring::Entry;
 use rand::rngs::OsRng;
 use rand::RngCore;
+use sha2::{Digest, Sha256};
 use tauri::{AppHandle, State};
 use zeroize::{Zeroize, Zeroizing};
 
 const KEYRING_SERVICE: &str = "pulsar-vault";
+/// Marks a keyring entry whose wrapping key is encrypted to a Secure Enclave
+/// key (macOS) or a Windows Hello credential rather than stored as plain
+/// base64.
+#[cfg(target_os = "macos")]
+const HARDWARE_KEY_PREFIX: &str = "se:";
+#[cfg(target_os = "windows")]
+const HARDWARE_KEY_PREFIX: &str = "hello:";
 
 #[cfg(mobile)]
 use tauri_plugin_biometric::{AuthOptions, BiometricExt, BiometryType};
@@ -92,7 +100,17 @@ pub fn ensure_biometric_available(_app: &AppHandle) -> Result<()> {
         }
     }
 
-    #[cfg(not(any(mobile, target_os = "windows", target_os = "macos")))]
+    #[cfg(target_os = "linux")]
+    {
+        super::fprintd::ensure_available()
+    }
+
+    #[cfg(not(any(
+        mobile,
+        target_os = "windows",
+        target_os = "macos",
+        target_os = "linux"
+    )))]
     {
         Err(Error::Internal(
             "Biometric authentication is not supported on this platform.".to_string(),
@@ -100,7 +118,25 @@ pub fn ensure_biometric_available(_app: &AppHandle) -> Result<()> {
     }
 }
 
-pub fn authenticate_biometric(app: &AppHandle, reason: &str) -> Result<()> {
+/// Runs a biometric or keyring call on the blocking pool. They wait on the
+/// platform service, and the prompts on the user, for as long as it takes.
+async fn run_blocking<T, F>(f: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| Error::Internal(format!("Runtime error: {}", e)))?
+}
+
+pub async fn authenticate_biometric(app: &AppHandle, reason: &str) -> Result<()> {
+    let app = app.clone();
+    let reason = reason.to_string();
+    run_blocking(move || prompt_biometric(&app, &reason)).await
+}
+
+fn prompt_biometric(app: &AppHandle, reason: &str) -> Result<()> {
     ensure_biometric_available(app)?;
 
     #[cfg(mobile)]
@@ -179,7 +215,18 @@ pub fn authenticate_biometric(app: &AppHandle, reason: &str) -> Result<()> {
         }
     }
 
-    #[cfg(not(any(mobile, target_os = "windows", target_os = "macos")))]
+    #[cfg(target_os = "linux")]
+    {
+        let _ = reason;
+        super::fprintd::verify()
+    }
+
+    #[cfg(not(any(
+        mobile,
+        target_os = "windows",
+        target_os = "macos",
+        target_os = "linux"
+    )))]
     {
         let _ = reason;
         Err(Error::Internal(
@@ -188,54 +235,192 @@ pub fn authenticate_biometric(app: &AppHandle, reason: &str) -> Result<()> {
     }
 }
 
+/// Fingerprint of the enrolled biometric set, changing whenever a finger or
+/// face is added or removed. `None` where the platform does not expose one.
+#[cfg(target_os = "macos")]
+fn enrollment_state() -> Option<Vec<u8>> {
+    unsafe {
+        let context: *mut Object = msg_send![class!(LAContext), new];
+        let mut error: *mut Object = std::ptr::null_mut();
+        let policy: i64 = 1; // LAPolicyDeviceOwnerAuthenticationWithBiometrics
+        let can: bool = msg_send![context, canEvaluatePolicy:policy error:&mut error];
+        if !can {
+            return None;
+        }
+        let data: *mut Object = msg_send![context, evaluatedPolicyDomainState];
+        if data.is_null() {
+            return None;
+        }
+        let len: usize = msg_send![data, length];
+        let bytes: *const u8 = msg_send![data, bytes];
+        Some(std::slice::from_raw_parts(bytes, len).to_vec())
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn enrollment_state() -> Option<Vec<u8>> {
+    super::fprintd::enrolled_fingers()
+        .ok()
+        .map(|fingers| fingers.join(",").into_bytes())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn enrollment_state() -> Option<Vec<u8>> {
+    None
+}
+
+fn enrollment_fingerprint() -> Option<String> {
+    enrollment_state().map(|state| hex::encode(Sha256::digest(state)))
+}
+
+#[cfg(target_os = "macos")]
+use super::secure_enclave as hardware_key;
+#[cfg(target_os = "windows")]
+use super::windows_hello as hardware_key;
+
+/// Turns the wrapping key into the form kept in the keyring. Where the
+/// platform offers it, the key is encrypted to a biometry-bound hardware key
+/// (Secure Enclave, Windows Hello); otherwise it is stored as base64.
+fn protect_bio_key(_vault_user: &str, bio_key: &[u8; 32]) -> Result<Zeroizing<String>> {
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    {
+        #[cfg(target_os = "windows")]
+        let supported = hardware_key::is_supported();
+        #[cfg(target_os = "macos")]
+        let supported = true;
+
+        if supported {
+            match hardware_key::wrap(_vault_user, bio_key) {
+                Ok(wrapped) => {
+                    return Ok(Zeroizing::new(format!(
+                        "{}{}",
+                        HARDWARE_KEY_PREFIX,
+                        general_purpose::STANDARD.encode(wrapped)
+                    )))
+                }
+                Err(e) => eprintln!("[Biometrics] Hardware key protection unavailable: {}", e),
+            }
+        }
+    }
+    Ok(Zeroizing::new(general_purpose::STANDARD.encode(bio_key)))
+}
+
+/// Unwraps a hardware-protected entry. `None` for plain entries.
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn unwrap_hardware_key(vault_user: &str, stored: &str) -> Option<Result<Zeroizing<Vec<u8>>>> {
+    let wrapped = stored.strip_prefix(HARDWARE_KEY_PREFIX)?;
+    Some(
+        general_purpose::STANDARD
+            .decode(wrapped)
+            .map_err(|_| Error::Internal("Invalid biometric key format".to_string()))
+            .and_then(|wrapped| hardware_key::unwrap(vault_user, &wrapped))
+            .map(Zeroizing::new),
+    )
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn unwrap_hardware_key(_vault_user: &str, _stored: &str) -> Option<Result<Zeroizing<Vec<u8>>>> {
+    None
+}
+
+/// Recovers the wrapping key from its keyring form, asking for biometric
+/// verification. A hardware key prompts by itself when it is used, so the
+/// separate prompt is only shown for plain entries.
+fn recover_bio_key(
+    app: &AppHandle,
+    vault_user: &str,
+    stored: &str,
+    reason: &str,
+) -> Result<Zeroizing<[u8; 32]>> {
+    let bio_key_vec = match unwrap_hardware_key(vault_user, stored) {
+        Some(unwrapped) => unwrapped?,
+        None => {
+            prompt_biometric(app, reason)?;
+            general_purpose::STANDARD
+                .decode(stored)
+                .map(Zeroizing::new)
+                .map_err(|_| Error::Internal("Invalid biometric key format".to_string()))?
+        }
+    };
+
+    if bio_key_vec.len() != 32 {
+        return Err(Error::Internal("Invalid biometric key length".to_string()));
+    }
+    let mut bio_key = Zeroizing::new([0u8; 32]);
+    bio_key.copy_from_slice(&bio_key_vec);
+    Ok(bio_key)
+}
+
 pub async fn is_biometrics_enabled_impl(
     app: &AppHandle,
     state: &State<'_, AppState>,
 ) -> Result<bool> {
-    if ensure_biometric_available(app).is_err() {
-        return Ok(false);
-    }
     let db_path = crate::auth::get_db_path(state).await?;
     let vault_user = get_vault_id(&db_path);
-    let entry =
-        Entry::new(KEYRING_SERVICE, &vault_user).map_err(|e| Error::Internal(e.to_string()))?;
-    match entry.get_password() {
-        Ok(_) => Ok(true),
-        Err(keyring::Error::NoEntry) => Ok(false),
-        Err(e) => Err(Error::Internal(e.to_string())),
-    }
+    let app = app.clone();
+    run_blocking(move || {
+        if ensure_biometric_available(&app).is_err() {
+            return Ok(false);
+        }
+        let entry = keyring_entry(KEYRING_SERVICE, &vault_user)?;
+        match entry.get_password() {
+            Ok(_) => Ok(true),
+            Err(keyring::Error::NoEntry) => Ok(false),
+            Err(e) => Err(map_keyring_error(e)),
+        }
+    })
+    .await
+}
+
+/// Checks that biometrics can be used and returns the current enrollment
+/// fingerprint.
+async fn available_enrollment(app: &AppHandle) -> Result<Option<String>> {
+    let app = app.clone();
+    run_blocking(move || {
+        ensure_biometric_available(&app)?;
+        Ok(enrollment_fingerprint())
+    })
+    .await
 }
 
 pub async fn get_biometric_master_password(
     app: &AppHandle,
     state: &State<'_, AppState>,
 ) -> Result<String> {
-    authenticate_biometric(app, "Unlock your Pulsar vault")?;
+    let current = available_enrollment(app).await?;
     let db_path = crate::auth::get_db_path(state).await?;
     let vault_user = get_vault_id(&db_path);
 
-    let entry =
-        Entry::new(KEYRING_SERVICE, &vault_user).map_err(|e| Error::Internal(e.to_string()))?;
-    let bio_key_b64 = entry.get_password().map_err(|e| {
-        if matches!(e, keyring::Error::NoEntry) {
-            Error::Internal("Biometrics not configured for this vault".to_string())
-        } else {
-            Error::Internal(e.to_string())
+    let db_pool = state.db.lock().await.clone().ok_or(Error::VaultNotLoaded)?;
+    let enrolled: Option<String> = sqlx::query_scalar(
+        "SELECT value FROM configuration WHERE key = 'biometric_enrollment_state'",
+    )
+    .fetch_optional(&db_pool)
+    .await?;
+    if let (Some(enrolled), Some(current)) = (enrolled, current) {
+        if enrolled != current {
+            disable_biometrics_impl(state).await?;
+            return Err(Error::Internal(
+                "Enrolled biometrics changed, so biometric unlock was turned off. Unlock with your master password and enable it again."
+                    .to_string(),
+            ));
         }
-    })?;
-
-    let mut bio_key_vec = general_purpose::STANDARD
-        .decode(&bio_key_b64)
-        .map_err(|_| Error::Internal("Invalid biometric key format".to_string()))?;
-    if bio_key_vec.len() != 32 {
-        bio_key_vec.zeroize();
-        return Err(Error::Internal("Invalid biometric key length".to_string()));
     }
-    let mut bio_key_bytes = [0u8; 32];
-    bio_key_bytes.copy_from_slice(&bio_key_vec);
-    bio_key_vec.zeroize();
 
-    let db_pool = state.db.lock().await.clone().ok_or(Error::VaultNotLoaded)?;
+    let app = app.clone();
+    let bio_key_bytes = run_blocking(move || {
+        let entry = keyring_entry(KEYRING_SERVICE, &vault_user)?;
+        let stored = Zeroizing::new(entry.get_password().map_err(|e| {
+            if matches!(e, keyring::Error::NoEntry) {
+                Error::Internal("Biometrics not configured for this vault".to_string())
+            } else {
+                map_keyring_error(e)
+            }
+        })?);
+        recover_bio_key(&app, &vault_user, &stored, "Unlock your Pulsar vault")
+    })
+    .await?;
+
     let row: Option<String> = sqlx::query_scalar(
         "SELECT value FROM configuration WHERE key = 'biometric_encrypted_password'",
     )
@@ -246,9 +431,8 @@ pub async fn get_biometric_master_password(
         Error::Internal("Biometric configuration corrupted (DB entry missing)".to_string())
     })?;
 
-    let master_password = decrypt(&encrypted_password_blob, &bio_key_bytes)
+    let master_password = decrypt(&encrypted_password_blob, bio_key_bytes.as_slice())
         .map_err(|_| Error::Internal("Biometric decryption failed".to_string()))?;
-    bio_key_bytes.zeroize();
 
     Ok(master_password)
 }
@@ -258,12 +442,11 @@ pub async fn enable_biometrics_impl(
     state: &State<'_, AppState>,
     password: &str,
 ) -> Result<()> {
-    ensure_biometric_available(app)?;
+    let fingerprint = available_enrollment(app).await?;
     let db_path = crate::auth::get_db_path(state).await?;
 
     let mut bio_key_bytes = [0u8; 32];
     OsRng.fill_bytes(&mut bio_key_bytes);
-    let bio_key_b64 = Zeroizing::new(general_purpose::STANDARD.encode(bio_key_bytes));
 
     let encrypted_password_blob = encrypt(password, &bio_key_bytes)?;
     let db_pool = state.db.lock().await.clone().ok_or(Error::VaultNotLoaded)?;
@@ -273,30 +456,50 @@ pub async fn enable_biometrics_impl(
     .bind(encrypted_password_blob)
     .execute(&db_pool)
     .await?;
+    match fingerprint {
+        Some(fingerprint) => {
+            sqlx::query(
+                "INSERT OR REPLACE INTO configuration (key, value) VALUES ('biometric_enrollment_state', ?)",
+            )
+            .bind(fingerprint)
+            .execute(&db_pool)
+            .await?;
+        }
+        None => {
+            sqlx::query("DELETE FROM configuration WHERE key = 'biometric_enrollment_state'")
+                .execute(&db_pool)
+                .await?;
+        }
+    }
 
     let vault_user = get_vault_id(&db_path);
-    let entry =
-        Entry::new(KEYRING_SERVICE, &vault_user).map_err(|e| Error::Internal(e.to_string()))?;
-    entry
-        .set_password(bio_key_b64.as_str())
-        .map_err(|e| Error::Internal(e.to_string()))?;
+    let bio_key = Zeroizing::new(bio_key_bytes);
     bio_key_bytes.zeroize();
-
-    Ok(())
+    run_blocking(move || {
+        let stored = protect_bio_key(&vault_user, &bio_key)?;
+        let entry = keyring_entry(KEYRING_SERVICE, &vault_user)?;
+        entry
+            .set_password(stored.as_str())
+            .map_err(map_keyring_error)
+    })
+    .await
 }
 
 pub async fn disable_biometrics_impl(state: &State<'_, AppState>) -> Result<()> {
     let db_path = crate::auth::get_db_path(state).await?;
     let vault_user = get_vault_id(&db_path);
 
-    let entry =
-        Entry::new(KEYRING_SERVICE, &vault_user).map_err(|e| Error::Internal(e.to_string()))?;
+    let entry = keyring_entry(KEYRING_SERVICE, &vault_user)?;
     let _ = entry.delete_credential();
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    hardware_key::delete(&vault_user);
 
     if let Some(db_pool) = state.db.lock().await.as_ref() {
-        let _ = sqlx::query("DELETE FROM configuration WHERE key = 'biometric_encrypted_password'")
-            .execute(db_pool)
-            .await;
+        let _ = sqlx::query(
+            "DELETE FROM configuration WHERE key IN ('biometric_encrypted_password', 'biometric_enrollment_state')",
+        )
+        .execute(db_pool)
+        .await;
     }
 
     Ok(())
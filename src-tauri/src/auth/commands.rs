@@ -3,10 +3,15 @@ use crate::auth::crypto_utils::*;
 use crate::auth::metadata::*;
 use crate::auth::types::*;
 use crate::auth::*;
+use crate::db::activity::rewrap_log_key;
+use crate::db::attachments::{rewrap_file_keys, AttachmentRekey};
+use crate::db::utils::UnlockedVault;
 use crate::encryption::{decrypt, encrypt};
 use crate::error::{Error, Result};
+use crate::memlock::LockedKey;
 use crate::security::register_device;
 use crate::state::{AppState, PendingUnlock};
+use crate::types::SecretString;
 use base64::{engine::general_purpose, Engine as _};
 use chacha20poly1305::{
     aead::{Aead, KeyInit},
@@ -17,6 +22,7 @@ use rand::RngCore;
 use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection};
 use sqlx::Connection;
 use std::path::Path;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use subtle::ConstantTimeEq;
 use tauri::{AppHandle, State};
@@ -35,35 +41,60 @@ fn unlock_backoff_duration(failures: u32) -> Duration {
     if failures == 0 {
         return Duration::from_millis(0);
     }
-    let exp = failures.min(6);
+    let exp = failures.min(UNLOCK_BACKOFF_MAX_FAILURES);
     let base = UNLOCK_BACKOFF_BASE_MS.saturating_mul(1u64 << exp);
     Duration::from_millis(base.min(UNLOCK_BACKOFF_MAX_MS))
 }
 
-pub async fn ensure_unlock_not_throttled(state: &State<'_, AppState>) -> Result<()> {
-    let guard = state.unlock_rate_limit.lock().await;
-    let Some(last_failure) = guard.last_failure else {
-        return Ok(());
-    };
-    let backoff = unlock_backoff_duration(guard.failures);
-    if last_failure.elapsed() < backoff {
-        return Err(Error::Validation(
-            "Too many attempts. Please wait and try again.".to_string(),
-        ));
+/// Checks both the in-memory backoff and the one persisted for `db_path`,
+/// which survives restarts of the app.
+pub async fn ensure_unlock_not_throttled(
+    state: &State<'_, AppState>,
+    db_path: &Path,
+) -> Result<()> {
+    let throttled = Err(Error::Validation(
+        "Too many attempts. Please wait and try again.".to_string(),
+    ));
+    {
+        let guard = state.unlock_rate_limit.lock().await;
+        if let Some(last_failure) = guard.last_failure {
+            if last_failure.elapsed() < unlock_backoff_duration(guard.failures) {
+                return throttled;
+            }
+        }
+    }
+
+    if let Some((failures, last_failure)) =
+        attempts::persisted_throttle(db_path, UNLOCK_BACKOFF_MAX_FAILURES).await
+    {
+        // A last failure in the future (the clock was turned back) counts
+        // as now.
+        let elapsed = (chrono::Utc::now() - last_failure)
+            .to_std()
+            .unwrap_or_default();
+        if elapsed < unlock_backoff_duration(failures) {
+            return throttled;
+        }
     }
     Ok(())
 }
 
-async fn register_unlock_failure(state: &State<'_, AppState>) {
-    let mut guard = state.unlock_rate_limit.lock().await;
-    guard.failures = guard.failures.saturating_add(1);
-    guard.last_failure = Some(Instant::now());
+async fn register_unlock_failure(state: &State<'_, AppState>, db_path: &Path) {
+    {
+        let mut guard = state.unlock_rate_limit.lock().await;
+        guard.failures = guard.failures.saturating_add(1);
+        guard.last_failure = Some(Instant::now());
+    }
+    attempts::record_failure(db_path, false).await;
 }
 
-async fn reset_unlock_failures(state: &State<'_, AppState>) {
-    let mut guard = state.unlock_rate_limit.lock().await;
-    guard.failures = 0;
-    guard.last_failure = None;
+async fn reset_unlock_failures(state: &State<'_, AppState>, db_path: &Path) {
+    {
+        let mut guard = state.unlock_rate_limit.lock().await;
+        guard.failures = 0;
+        guard.last_failure = None;
+    }
+    attempts::reset_throttle(db_path).await;
 }
 
 async fn connect_with_key(db_path: &Path, key_bytes: &[u8]) -> Result<SqliteConnection> {
@@ -165,7 +196,7 @@ async fn apply_sqlcipher_pragmas(conn: &mut SqliteConnection, db_name: Option<&s
     Ok(())
 }
 
-async fn attach_encrypted_db(
+pub(crate) async fn attach_encrypted_db(
     conn: &mut SqliteConnection,
     path: &Path,
     hex_key: &str,
@@ -400,8 +431,80 @@ async fn connect_with_timeout(
     }
 }
 
-async fn finalize_unlock(state: &State<'_, AppState>, key_z: Zeroizing<Vec<u8>>) -> Result<()> {
+/// Logs an unlock by the user. Not part of `finalize_unlock`, which also
+/// reopens the vault after rekeying and reloads.
+async fn record_unlock(state: &State<'_, AppState>, key: &[u8]) {
+    if crate::storage::is_read_only() {
+        return;
+    }
+    if let Ok(pool) = get_db_pool(state).await {
+        let _ = crate::db::activity::log_activity_impl(
+            &pool,
+            key,
+            crate::db::activity::UNLOCK_EVENT,
+            None,
+            None,
+            None,
+        )
+        .await;
+    }
+}
+
+/// Signs the open vault's item count into its metadata so the vault list can
+/// show it while the vault is locked. Needs the key, so call before locking.
+pub(crate) async fn store_item_count_hint(state: &AppState) {
+    if crate::storage::is_read_only() {
+        return;
+    }
+    let Some(db_path) = state.db_path.lock().await.clone() else {
+        return;
+    };
+    let Some(key) = state.key.lock().await.clone() else {
+        return;
+    };
+    let Some(pool) = state.db.lock().await.clone() else {
+        return;
+    };
+    let stored = async {
+        let Some(mut meta) = read_password_metadata(&db_path).await? else {
+            return Ok(());
+        };
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM password_items")
+            .fetch_one(&pool)
+            .await?;
+        let vault_id = get_vault_id(&db_path);
+        meta.item_count_hint = Some(sign_item_count(
+            count.max(0) as u64,
+            &vault_id,
+            key.as_slice(),
+        )?);
+        write_password_metadata(&db_path, &meta, Some(key.as_slice())).await
+    }
+    .await;
+    if let Err(e) = stored {
+        eprintln!("Failed to store item count hint: {}", e);
+    }
+}
+
+/// Drops an item count hint that was not signed with this vault's key.
+async fn check_item_count_hint(db_path: &Path, key: &[u8]) -> Result<()> {
+    let Some(mut meta) = read_password_metadata(db_path).await? else {
+        return Ok(());
+    };
+    let Some(hint) = &meta.item_count_hint else {
+        return Ok(());
+    };
+    if verify_item_count(hint, &get_vault_id(db_path), key).is_ok() {
+        return Ok(());
+    }
+    eprintln!("Discarding item count hint that failed verification");
+    meta.item_count_hint = None;
+    write_password_metadata(db_path, &meta, Some(key)).await
+}
+
+pub(crate) async fn finalize_unlock(state: &State<'_, AppState>, key: &[u8]) -> Result<()> {
     let db_path = get_db_path(state).await?;
+    state.clear_overview_cache();
 
     {
         let mut db_guard = state.db.lock().await;
@@ -412,13 +515,23 @@ async fn finalize_unlock(state: &State<'_, AppState>, key_z: Zeroizing<Vec<u8>>)
 
     tokio::time::sleep(Duration::from_millis(50)).await;
 
-    let new_pool = crate::db::init_db_lazy(db_path.as_path(), Some(key_z.as_slice()), false)
+    let pool_path = crate::write_buffer::pool_path(state, db_path.as_path()).await;
+    let new_pool = crate::db::init_db_lazy(pool_path.as_path(), Some(key), false)
         .await
         .map_err(Error::Internal)?;
 
-    if let Err(e) = sqlx::migrate!().run(&new_pool).await {
-        eprintln!("Database migration error during unlock: {}", e);
-        return Err(Error::Database(e.into()));
+    let storage = crate::storage::refresh(db_path.as_path()).await;
+
+    if let Err(e) = crate::db::migrations::ensure_supported(&new_pool).await {
+        new_pool.close().await;
+        return Err(e);
+    }
+    if let Err(e) = crate::db::migrations::MIGRATOR.run(&new_pool).await {
+        if !storage.read_only {
+            eprintln!("Database migration error during unlock: {}", e);
+            return Err(Error::Database(e.into()));
+        }
+        eprintln!("Skipping migrations on read-only vault: {}", e);
     }
 
     {
@@ -428,7 +541,7 @@ async fn finalize_unlock(state: &State<'_, AppState>, key_z: Zeroizing<Vec<u8>>)
 
     {
         let mut key_guard = state.key.lock().await;
-        *key_guard = Some(key_z.clone());
+        *key_guard = Some(Arc::new(LockedKey::new(key)));
     }
 
     {
@@ -438,6 +551,26 @@ async fn finalize_unlock(state: &State<'_, AppState>, key_z: Zeroizing<Vec<u8>>)
         }
     }
 
+    if !storage.read_only {
+        if let Err(e) = check_item_count_hint(db_path.as_path(), key).await {
+            eprintln!("Failed to check item count hint: {}", e);
+        }
+        let pool = get_db_pool(state).await?;
+        if let Err(e) = members::reconcile_member_keys(db_path.as_path(), &pool, key).await {
+            eprintln!("Failed to update member keys: {}", e);
+        }
+        let session = match crate::security::current_device_id() {
+            Ok(device_id) => {
+                let pool = get_db_pool(state).await?;
+                crate::sessions::start(state, &pool, device_id).await
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = session {
+            eprintln!("Failed to start session: {}", e);
+        }
+    }
+
     let state_clone = state.inner().clone();
     tauri::async_runtime::spawn(async move {
         match tokio::time::timeout(Duration::from_secs(5), register_device(&state_clone)).await {
@@ -447,13 +580,26 @@ async fn finalize_unlock(state: &State<'_, AppState>, key_z: Zeroizing<Vec<u8>>)
         }
     });
 
+    crate::vault_watch::watch(db_path.as_path());
     Ok(())
 }
 
+/// Sets the master password of a newly created vault. `template` names one
+/// of the starter templates from `list_vault_templates`, applied once the
+/// vault is unlocked and migrated.
 #[tauri::command]
-pub async fn set_master_password(state: State<'_, AppState>, password: String) -> Result<()> {
+pub async fn set_master_password(
+    state: State<'_, AppState>,
+    password: String,
+    template: Option<String>,
+) -> Result<()> {
     let password = Zeroizing::new(password);
     validate_new_password(password.as_str())?;
+    let template = template
+        .as_deref()
+        .map(crate::db::vault_templates::find_vault_template)
+        .transpose()?;
+    crate::storage::ensure_writable()?;
     let _rekey_lock = tokio::time::timeout(Duration::from_secs(15), state.rekey.lock())
         .await
         .map_err(|_| Error::Internal("Vault is busy. Please try again.".to_string()))?;
@@ -501,6 +647,9 @@ pub async fn set_master_password(state: State<'_, AppState>, password: String) -
         mac_version: None,
         mac_nonce_b64: None,
         mac_tag_b64: None,
+        display: None,
+        item_count_hint: None,
+        member_keys: Vec::new(),
     };
 
     if let Some(pool) = { state.db.lock().await.take() } {
@@ -538,7 +687,7 @@ pub async fn set_master_password(state: State<'_, AppState>, password: String) -
                 let pool = crate::db::init_db_lazy(&temp_db_path, Some(key_z.as_slice()), false)
                     .await
                     .map_err(Error::Internal)?;
-                if let Err(e) = sqlx::migrate!().run(&pool).await {
+                if let Err(e) = crate::db::migrations::MIGRATOR.run(&pool).await {
                     pool.close().await;
                     return Err(Error::Database(e.into()));
                 }
@@ -565,7 +714,11 @@ pub async fn set_master_password(state: State<'_, AppState>, password: String) -
         )));
     }
 
-    finalize_unlock(&state, key_z.clone()).await?;
+    finalize_unlock(&state, key_z.as_slice()).await?;
+    if let Some(template) = template {
+        let vault = UnlockedVault::acquire_writable(&state).await?;
+        crate::db::vault_templates::apply_vault_template(&vault, &template).await?;
+    }
     Ok(())
 }
 
@@ -578,7 +731,7 @@ pub async fn unlock(state: State<'_, AppState>, password: String) -> Result<Unlo
         .await
         .map_err(|_| Error::Internal("Unlock guard closed".to_string()))?;
     let db_path = get_db_path(&state).await?;
-    ensure_unlock_not_throttled(&state).await?;
+    ensure_unlock_not_throttled(&state, db_path.as_path()).await?;
     let metadata = match read_password_metadata(db_path.as_path()).await? {
         Some(meta) => Some(meta),
         None => {
@@ -605,14 +758,13 @@ pub async fn unlock(state: State<'_, AppState>, password: String) -> Result<Unlo
     .await
     .map_err(|e| Error::Internal(format!("Runtime error: {}", e)))??;
 
-    drop(password);
     let key_z = Zeroizing::new(derived_key.to_vec());
     let cipher = XChaCha20Poly1305::new(Key::from_slice(&key_z));
 
     let mut decrypted = match cipher.decrypt(XNonce::from_slice(&nonce), ciphertext.as_ref()) {
         Ok(value) => value,
         Err(_) => {
-            register_unlock_failure(&state).await;
+            register_unlock_failure(&state, db_path.as_path()).await;
             return Err(Error::InvalidPassword);
         }
     };
@@ -620,14 +772,24 @@ pub async fn unlock(state: State<'_, AppState>, password: String) -> Result<Unlo
     let is_valid = decrypted.ct_eq(PASSWORD_CHECK_PLAINTEXT).unwrap_u8() == 1;
     decrypted.zeroize();
     if !is_valid {
-        register_unlock_failure(&state).await;
+        register_unlock_failure(&state, db_path.as_path()).await;
         return Err(Error::InvalidPassword);
     }
-    reset_unlock_failures(&state).await;
+    reset_unlock_failures(&state, db_path.as_path()).await;
+    reverify::note_verified(&state).await;
+    crate::settings::keystore::unlock_with_password(password.as_str()).await;
+    drop(password);
 
     if meta.mac_tag_b64.is_some() {
         let vault_id = get_vault_id(db_path.as_path());
-        verify_metadata_mac(&meta, &vault_id, key_z.as_slice())?;
+        // The MAC binds the metadata to the vault's path, so this is also what
+        // a vault renamed or moved without re-pairing runs into.
+        verify_metadata_mac(&meta, &vault_id, key_z.as_slice()).map_err(|_| {
+            Error::Validation(
+                "Vault metadata does not match this vault file. If it was renamed or moved, repair its metadata pairing."
+                    .to_string(),
+            )
+        })?;
     }
 
     let is_plaintext = is_plaintext_sqlite(db_path.as_path()).await?;
@@ -701,7 +863,7 @@ pub async fn unlock(state: State<'_, AppState>, password: String) -> Result<Unlo
         {
             let mut pending_guard = state.pending_key.lock().await;
             *pending_guard = Some(PendingUnlock {
-                key: key_z.clone(),
+                key: LockedKey::new(key_z.as_slice()),
                 created_at: Instant::now(),
                 attempts: 0,
             });
@@ -710,13 +872,93 @@ pub async fn unlock(state: State<'_, AppState>, password: String) -> Result<Unlo
             totp_required: true,
         })
     } else {
-        finalize_unlock(&state, key_z.clone()).await?;
+        finalize_unlock(&state, key_z.as_slice()).await?;
+        record_unlock(&state, key_z.as_slice()).await;
         Ok(UnlockResponse {
             totp_required: false,
         })
     }
 }
 
+/// Opens a shared vault with a member's X25519 private key instead of the
+/// master password. Login TOTP still applies.
+#[tauri::command]
+pub async fn unlock_with_member_key(
+    state: State<'_, AppState>,
+    private_key: SecretString,
+) -> Result<UnlockResponse> {
+    let _unlock_permit = state
+        .unlock_guard
+        .acquire()
+        .await
+        .map_err(|_| Error::Internal("Unlock guard closed".to_string()))?;
+    let db_path = get_db_path(&state).await?;
+    ensure_unlock_not_throttled(&state, db_path.as_path()).await?;
+    let meta = read_password_metadata(db_path.as_path())
+        .await?
+        .filter(|meta| !meta.member_keys.is_empty())
+        .ok_or_else(|| Error::Validation("This vault is not shared.".to_string()))?;
+
+    let key_z = match members::open_member_key(&meta, private_key.as_str()) {
+        Ok(key) => key,
+        Err(_) => {
+            register_unlock_failure(&state, db_path.as_path()).await;
+            return Err(Error::InvalidPassword);
+        }
+    };
+    let (_, nonce, ciphertext) = decode_metadata(&meta)?;
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&key_z));
+    let mut decrypted = match cipher.decrypt(XNonce::from_slice(&nonce), ciphertext.as_ref()) {
+        Ok(value) => value,
+        Err(_) => {
+            register_unlock_failure(&state, db_path.as_path()).await;
+            return Err(Error::InvalidPassword);
+        }
+    };
+    let is_valid = decrypted.ct_eq(PASSWORD_CHECK_PLAINTEXT).unwrap_u8() == 1;
+    decrypted.zeroize();
+    if !is_valid {
+        register_unlock_failure(&state, db_path.as_path()).await;
+        return Err(Error::InvalidPassword);
+    }
+    reset_unlock_failures(&state, db_path.as_path()).await;
+    reverify::note_verified(&state).await;
+
+    if meta.mac_tag_b64.is_some() {
+        let vault_id = get_vault_id(db_path.as_path());
+        verify_metadata_mac(&meta, &vault_id, key_z.as_slice()).map_err(|_| {
+            Error::Validation(
+                "Vault metadata does not match this vault file. If it was renamed or moved, repair its metadata pairing."
+                    .to_string(),
+            )
+        })?;
+    }
+
+    let mut conn = connect_with_key(db_path.as_path(), key_z.as_slice()).await?;
+    let totp_configured: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM configuration WHERE key = 'login_totp_secret'")
+            .fetch_one(&mut conn)
+            .await?;
+    conn.close().await?;
+
+    if totp_configured > 0 {
+        let mut pending_guard = state.pending_key.lock().await;
+        *pending_guard = Some(PendingUnlock {
+            key: LockedKey::new(key_z.as_slice()),
+            created_at: Instant::now(),
+            attempts: 0,
+        });
+        return Ok(UnlockResponse {
+            totp_required: true,
+        });
+    }
+    finalize_unlock(&state, key_z.as_slice()).await?;
+    record_unlock(&state, key_z.as_slice()).await;
+    Ok(UnlockResponse {
+        totp_required: false,
+    })
+}
+
 #[tauri::command]
 pub async fn verify_login_totp(state: State<'_, AppState>, token: String) -> Result<()> {
     let pending_key = {
@@ -746,16 +988,17 @@ pub async fn verify_login_totp(state: State<'_, AppState>, token: String) -> Res
         pending.key.clone()
     };
 
+    let db_path = get_db_path(&state).await?;
     let trimmed = token.trim();
     if trimmed.len() < 6 {
         let mut guard = state.pending_key.lock().await;
         if let Some(pending) = guard.as_mut() {
             pending.attempts = pending.attempts.saturating_add(1);
         }
+        attempts::record_failure(db_path.as_path(), true).await;
         return Err(Error::Validation("Invalid TOTP token".to_string()));
     }
 
-    let db_path = get_db_path(&state).await?;
     let mut conn = connect_with_key(db_path.as_path(), pending_key.as_slice()).await?;
 
     let secret_enc: Option<String> =
@@ -784,15 +1027,19 @@ pub async fn verify_login_totp(state: State<'_, AppState>, token: String) -> Res
     let is_valid = totp.check_current(trimmed).unwrap_or(false);
     secret_bytes.zeroize();
     if !is_valid {
-        let mut guard = state.pending_key.lock().await;
-        if let Some(pending) = guard.as_mut() {
-            pending.attempts = pending.attempts.saturating_add(1);
+        {
+            let mut guard = state.pending_key.lock().await;
+            if let Some(pending) = guard.as_mut() {
+                pending.attempts = pending.attempts.saturating_add(1);
+            }
         }
+        attempts::record_failure(db_path.as_path(), true).await;
         return Err(Error::Validation("Invalid TOTP token".to_string()));
     }
 
     conn.close().await?;
-    finalize_unlock(&state, pending_key.clone()).await?;
+    finalize_unlock(&state, pending_key.as_slice()).await?;
+    record_unlock(&state, pending_key.as_slice()).await;
     Ok(())
 }
 
@@ -805,8 +1052,11 @@ pub async fn rotate_master_password(
     let current_password = Zeroizing::new(current_password);
     let new_password = Zeroizing::new(new_password);
     validate_password_inputs(current_password.as_str(), new_password.as_str())?;
+    reverify::require_fresh(&state, reverify::SensitiveOperation::RotateMasterPassword).await?;
 
     let _rekey_lock = state.rekey.lock().await;
+    crate::storage::ensure_writable()?;
+    crate::write_buffer::suspend(&state).await?;
     let db_pool = get_db_pool(&state).await?;
     let db_path = get_db_path(&state).await?;
 
@@ -873,6 +1123,16 @@ pub async fn rotate_master_password(
     metadata.salt_b64 = general_purpose::STANDARD.encode(&new_salt);
     metadata.nonce_b64 = general_purpose::STANDARD.encode(&new_nonce);
     metadata.ciphertext_b64 = general_purpose::STANDARD.encode(&new_ciphertext);
+    members::rewrap_member_keys(&mut metadata, new_key_z.as_slice())?;
+
+    crate::db::activity::ensure_log_key(&db_pool, current_key_z.as_slice()).await?;
+    let mut attachments = AttachmentRekey::prepare(
+        &db_pool,
+        &db_path,
+        current_key_z.as_slice(),
+        new_key_z.as_slice(),
+    )
+    .await?;
 
     if let Some(pool) = { state.db.lock().await.take() } {
         close_pool_with_timeout(pool, Duration::from_secs(15)).await?;
@@ -895,38 +1155,72 @@ pub async fn rotate_master_password(
         .pragma("key", format!("\"x'{}'\"", hex_old_key));
 
     let mut _last_err: Option<Error> = None;
-    for _ in 0..10 {
-        match connect_with_timeout(&connect_options, Duration::from_secs(15)).await {
-            Ok(mut conn) => {
-                attach_encrypted_db(&mut conn, &temp_db_path, &hex_new_key).await?;
-                sqlx::query("SELECT sqlcipher_export('encrypted')")
-                    .execute(&mut conn)
+    let rotation: Result<()> = async {
+        for _ in 0..10 {
+            match connect_with_timeout(&connect_options, Duration::from_secs(15)).await {
+                Ok(mut conn) => {
+                    attach_encrypted_db(&mut conn, &temp_db_path, &hex_new_key).await?;
+                    sqlx::query("SELECT sqlcipher_export('encrypted')")
+                        .execute(&mut conn)
+                        .await?;
+                    rewrap_file_keys(
+                        &mut conn,
+                        "encrypted",
+                        current_key_z.as_slice(),
+                        new_key_z.as_slice(),
+                    )
                     .await?;
-                sqlx::query("DETACH DATABASE encrypted")
-                    .execute(&mut conn)
+                    rewrap_log_key(
+                        &mut conn,
+                        "encrypted",
+                        current_key_z.as_slice(),
+                        new_key_z.as_slice(),
+                    )
                     .await?;
+                    sqlx::query("DETACH DATABASE encrypted")
+                        .execute(&mut conn)
+                        .await?;
 
-                let _ = conn.close().await;
+                    let _ = conn.close().await;
 
-                write_password_metadata_to_db(&temp_db_path, new_key_z.as_slice(), &metadata)
-                    .await?;
+                    write_password_metadata_to_db(&temp_db_path, new_key_z.as_slice(), &metadata)
+                        .await?;
 
-                tokio::time::sleep(Duration::from_millis(1000)).await;
-                replace_db_with_backup(&db_path, &temp_db_path, "master password rotation").await?;
+                    tokio::time::sleep(Duration::from_millis(1000)).await;
+                    attachments.apply().await?;
+                    replace_db_with_backup(&db_path, &temp_db_path, "master password rotation")
+                        .await?;
+                    attachments.finish().await;
 
-                write_password_metadata(db_path.as_path(), &metadata, Some(new_key_z.as_slice()))
+                    write_password_metadata(
+                        db_path.as_path(),
+                        &metadata,
+                        Some(new_key_z.as_slice()),
+                    )
                     .await?;
-                _last_err = None;
-                break;
-            }
-            Err(e) => {
-                _last_err = Some(Error::Database(e));
-                tokio::time::sleep(Duration::from_millis(1000)).await;
+                    _last_err = None;
+                    break;
+                }
+                Err(e) => {
+                    _last_err = Some(Error::Database(e));
+                    tokio::time::sleep(Duration::from_millis(1000)).await;
+                }
             }
         }
+        Ok(())
     }
+    .await;
+    // Anything not finished belongs to a database that was never replaced.
+    attachments.rollback().await;
+    rotation?;
+
+    finalize_unlock(&state, new_key_z.as_slice()).await?;
 
-    finalize_unlock(&state, new_key_z.clone()).await?;
+    if let Err(e) =
+        crate::settings::keystore::rewrap(current_password.as_str(), new_password.as_str()).await
+    {
+        eprintln!("[Keystore] Failed to re-wrap settings seed: {}", e);
+    }
 
     let _ = crate::db::activity::log_activity_impl(
         &db_pool,
@@ -941,7 +1235,7 @@ pub async fn rotate_master_password(
     Ok(())
 }
 
-fn validate_password_inputs(current: &str, new_password: &str) -> Result<()> {
+pub(crate) fn validate_password_inputs(current: &str, new_password: &str) -> Result<()> {
     if current.trim().is_empty() {
         return Err(Error::Validation(
             "Current password is required.".to_string(),
@@ -969,6 +1263,11 @@ fn validate_new_password(new_password: &str) -> Result<()> {
             "Password must be at least 12 characters.".to_string(),
         ));
     }
+    if crate::common_passwords::is_common(trimmed) {
+        return Err(Error::Validation(
+            "This password is too common. Choose one that is harder to guess.".to_string(),
+        ));
+    }
     Ok(())
 }
 
@@ -1008,6 +1307,8 @@ pub async fn update_argon2_params(
     validate_argon_params(&new_params)?;
 
     let _rekey_lock = state.rekey.lock().await;
+    crate::storage::ensure_writable()?;
+    crate::write_buffer::suspend(&state).await?;
     let db_pool = get_db_pool(&state).await?;
     let db_path = get_db_path(&state).await?;
 
@@ -1073,10 +1374,20 @@ pub async fn update_argon2_params(
     metadata.salt_b64 = general_purpose::STANDARD.encode(&new_salt);
     metadata.nonce_b64 = general_purpose::STANDARD.encode(&new_nonce);
     metadata.ciphertext_b64 = general_purpose::STANDARD.encode(&new_ciphertext);
+    members::rewrap_member_keys(&mut metadata, new_key_z.as_slice())?;
     metadata.argon2_memory_kib = Some(new_params.memory_kib);
     metadata.argon2_time_cost = Some(new_params.time_cost);
     metadata.argon2_parallelism = Some(new_params.parallelism);
 
+    crate::db::activity::ensure_log_key(&db_pool, current_key_z.as_slice()).await?;
+    let mut attachments = AttachmentRekey::prepare(
+        &db_pool,
+        &db_path,
+        current_key_z.as_slice(),
+        new_key_z.as_slice(),
+    )
+    .await?;
+
     if let Some(pool) = { state.db.lock().await.take() } {
         close_pool_with_timeout(pool, Duration::from_secs(15)).await?;
     }
@@ -1096,36 +1407,63 @@ pub async fn update_argon2_params(
         .pragma("key", format!("\"x'{}'\"", hex_old_key));
 
     let mut last_err: Option<Error> = None;
-    for _ in 0..10 {
-        match connect_with_timeout(&connect_options, Duration::from_secs(15)).await {
-            Ok(mut conn) => {
-                attach_encrypted_db(&mut conn, &temp_db_path, &hex_new_key).await?;
-                sqlx::query("SELECT sqlcipher_export('encrypted')")
-                    .execute(&mut conn)
+    let update: Result<()> = async {
+        for _ in 0..10 {
+            match connect_with_timeout(&connect_options, Duration::from_secs(15)).await {
+                Ok(mut conn) => {
+                    attach_encrypted_db(&mut conn, &temp_db_path, &hex_new_key).await?;
+                    sqlx::query("SELECT sqlcipher_export('encrypted')")
+                        .execute(&mut conn)
+                        .await?;
+                    rewrap_file_keys(
+                        &mut conn,
+                        "encrypted",
+                        current_key_z.as_slice(),
+                        new_key_z.as_slice(),
+                    )
                     .await?;
-                sqlx::query("DETACH DATABASE encrypted")
-                    .execute(&mut conn)
+                    rewrap_log_key(
+                        &mut conn,
+                        "encrypted",
+                        current_key_z.as_slice(),
+                        new_key_z.as_slice(),
+                    )
                     .await?;
+                    sqlx::query("DETACH DATABASE encrypted")
+                        .execute(&mut conn)
+                        .await?;
 
-                conn.close().await?;
+                    conn.close().await?;
 
-                write_password_metadata_to_db(&temp_db_path, new_key_z.as_slice(), &metadata)
-                    .await?;
+                    write_password_metadata_to_db(&temp_db_path, new_key_z.as_slice(), &metadata)
+                        .await?;
 
-                tokio::time::sleep(Duration::from_millis(1000)).await;
-                replace_db_with_backup(&db_path, &temp_db_path, "Argon2 parameter update").await?;
+                    tokio::time::sleep(Duration::from_millis(1000)).await;
+                    attachments.apply().await?;
+                    replace_db_with_backup(&db_path, &temp_db_path, "Argon2 parameter update")
+                        .await?;
+                    attachments.finish().await;
 
-                write_password_metadata(db_path.as_path(), &metadata, Some(new_key_z.as_slice()))
+                    write_password_metadata(
+                        db_path.as_path(),
+                        &metadata,
+                        Some(new_key_z.as_slice()),
+                    )
                     .await?;
-                last_err = None;
-                break;
-            }
-            Err(e) => {
-                last_err = Some(Error::Database(e));
-                tokio::time::sleep(Duration::from_millis(1000)).await;
+                    last_err = None;
+                    break;
+                }
+                Err(e) => {
+                    last_err = Some(Error::Database(e));
+                    tokio::time::sleep(Duration::from_millis(1000)).await;
+                }
             }
         }
+        Ok(())
     }
+    .await;
+    attachments.rollback().await;
+    update?;
 
     if let Some(e) = last_err {
         return Err(Error::Internal(format!(
@@ -1134,7 +1472,7 @@ pub async fn update_argon2_params(
         )));
     }
 
-    finalize_unlock(&state, new_key_z.clone()).await?;
+    finalize_unlock(&state, new_key_z.as_slice()).await?;
 
     let _ = crate::db::activity::log_activity_impl(
         &db_pool,
@@ -1169,6 +1507,7 @@ pub async fn configure_login_totp(state: State<'_, AppState>, secret_b32: String
         .map_err(|e| Error::Validation(format!("Invalid TOTP secret: {}", e)))?;
 
     let encrypted = encrypt(secret_b32.as_str(), key_z.as_slice())?;
+    crate::storage::ensure_writable()?;
     let db_pool = get_db_pool(&state).await?;
 
     sqlx::query(
@@ -1183,6 +1522,7 @@ pub async fn configure_login_totp(state: State<'_, AppState>, secret_b32: String
 
 #[tauri::command]
 pub async fn disable_login_totp(state: State<'_, AppState>) -> Result<()> {
+    crate::storage::ensure_writable()?;
     let db_pool = get_db_pool(&state).await?;
     sqlx::query("DELETE FROM configuration WHERE key = 'login_totp_secret'")
         .execute(&db_pool)
@@ -1202,6 +1542,7 @@ pub async fn is_login_totp_configured(state: State<'_, AppState>) -> Result<bool
 
 #[tauri::command]
 pub async fn get_login_totp_secret(state: State<'_, AppState>) -> Result<Option<String>> {
+    reverify::require_fresh(&state, reverify::SensitiveOperation::ViewLoginTotpSecret).await?;
     let key_opt = {
         let guard = state.key.lock().await;
         guard.clone()
@@ -1224,6 +1565,15 @@ pub async fn get_login_totp_secret(state: State<'_, AppState>) -> Result<Option<
 
 #[tauri::command]
 pub async fn lock(app: tauri::AppHandle, state: State<'_, AppState>) -> Result<()> {
+    crate::write_buffer::flush_before_close(&state).await;
+    store_item_count_hint(&state).await;
+    crate::vault_watch::unwatch();
+    state.clear_overview_cache();
+    reverify::clear(&state).await;
+    crate::sessions::end(&state).await;
+    crate::secure_temp::wipe_all().await;
+    state.parked_keys.lock().await.clear();
+
     {
         let mut key_guard = state.key.lock().await;
         *key_guard = None;
@@ -1244,14 +1594,224 @@ pub async fn lock(app: tauri::AppHandle, state: State<'_, AppState>) -> Result<(
     if let Err(error) = app.clipboard().clear() {
         eprintln!("Failed to clear clipboard on lock: {}", error);
     }
+    crate::events::vault_locked(&app);
     Ok(())
 }
 
+/// Reopens the vault file after another process changed it, so the pool and
+/// caches reflect the new contents and writes are allowed again. Deferred
+/// writes are dropped first when none are pending; otherwise the reload is
+/// refused, as it would discard them.
+#[tauri::command]
+pub async fn reload_vault(state: State<'_, AppState>) -> Result<()> {
+    let _rekey_lock = tokio::time::timeout(Duration::from_secs(15), state.rekey.lock())
+        .await
+        .map_err(|_| Error::Internal("Vault is busy. Please try again.".to_string()))?;
+    let key = state.key.lock().await.clone().ok_or(Error::VaultLocked)?;
+
+    crate::write_buffer::discard(&state).await?;
+    finalize_unlock(&state, key.as_slice()).await
+}
+
 #[tauri::command]
 pub async fn is_locked(state: State<'_, AppState>) -> Result<bool> {
     Ok(state.key.lock().await.is_none())
 }
 
+const MAX_REPAIR_CANDIDATES: usize = 8;
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataRepairResult {
+    /// "repaired" when the paired metadata only needed re-binding,
+    /// "relinked" when an orphaned file was adopted, "regenerated" when it
+    /// was rebuilt from the vault's configuration table.
+    pub source: String,
+    pub meta_path: String,
+}
+
+#[tauri::command]
+pub async fn find_metadata_candidates(db_path: String) -> Result<Vec<MetadataCandidate>> {
+    find_orphaned_metadata(Path::new(&db_path)).await
+}
+
+/// Derives the key for `meta` and returns it when `password` passes the
+/// metadata's password check.
+async fn derive_checked_key(
+    meta: &PasswordMetadata,
+    password: &Zeroizing<String>,
+) -> Result<Option<Zeroizing<Vec<u8>>>> {
+    let (salt, nonce, ciphertext) = decode_metadata(meta)?;
+    let argon_params = meta.argon2_params();
+    validate_argon_params(&argon_params)?;
+
+    let password_clone = password.clone();
+    let derived_key = tauri::async_runtime::spawn_blocking(move || {
+        derive_key(password_clone.as_str(), &salt, &argon_params)
+    })
+    .await
+    .map_err(|e| Error::Internal(format!("Runtime error: {}", e)))??;
+    let key_z = Zeroizing::new(derived_key.to_vec());
+
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&key_z));
+    let Ok(mut decrypted) = cipher.decrypt(XNonce::from_slice(&nonce), ciphertext.as_ref()) else {
+        return Ok(None);
+    };
+    let is_valid = decrypted.ct_eq(PASSWORD_CHECK_PLAINTEXT).unwrap_u8() == 1;
+    decrypted.zeroize();
+    Ok(is_valid.then_some(key_z))
+}
+
+/// Checks that `meta` unlocks the vault file with `password`. Returns the
+/// authoritative metadata from the vault's configuration table and the key.
+async fn match_metadata_to_db(
+    db_path: &Path,
+    meta: &PasswordMetadata,
+    password: &Zeroizing<String>,
+) -> Result<Option<(PasswordMetadata, Zeroizing<Vec<u8>>)>> {
+    let Some(key_z) = derive_checked_key(meta, password).await? else {
+        return Ok(None);
+    };
+
+    let hex_key = Zeroizing::new(hex::encode(key_z.as_slice()));
+    let connect_options = SqliteConnectOptions::new()
+        .filename(db_path)
+        .create_if_missing(false)
+        .busy_timeout(Duration::from_secs(10))
+        .pragma("key", format!("\"x'{}'\"", hex_key.as_str()));
+    let pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(connect_options)
+        .await?;
+    let from_db = load_metadata_from_db(&pool).await;
+    pool.close().await;
+
+    match from_db {
+        Ok(Some(mut db_meta)) if db_meta.salt_b64 == meta.salt_b64 => {
+            if db_meta.display.is_none() {
+                db_meta.display = meta.display.clone();
+            }
+            Ok(Some((db_meta, key_z)))
+        }
+        // The key opens the vault but the configuration table predates
+        // metadata mirroring; the file itself is the only copy.
+        Ok(None) => Ok(Some((meta.clone(), key_z))),
+        Ok(Some(_)) => Ok(None),
+        Err(Error::Database(err)) if is_not_a_database_error(&err) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Re-pairs a vault file with its `.meta.json` after one of them was renamed
+/// or moved. `meta_path` names the metadata to adopt; without it the paired
+/// file and orphaned metadata next to the vault are tried in turn. A plaintext
+/// vault has its metadata regenerated from the configuration table.
+#[tauri::command]
+pub async fn repair_metadata_pairing(
+    state: State<'_, AppState>,
+    db_path: String,
+    meta_path: Option<String>,
+    password: String,
+) -> Result<MetadataRepairResult> {
+    let password = Zeroizing::new(password);
+    let _unlock_permit = state
+        .unlock_guard
+        .acquire()
+        .await
+        .map_err(|_| Error::Internal("Unlock guard closed".to_string()))?;
+    let db_path = Path::new(&db_path);
+    ensure_unlock_not_throttled(&state, db_path).await?;
+    if !fs::try_exists(db_path).await.unwrap_or(false) {
+        return Err(Error::Validation(format!(
+            "Vault file {} does not exist",
+            db_path.display()
+        )));
+    }
+    if let Some(cause) = crate::storage::probe_writable(db_path).await {
+        return Err(Error::ReadOnlyStorage(cause));
+    }
+
+    let own_meta_path = metadata_path(db_path);
+    let mut candidates = Vec::new();
+    match meta_path {
+        Some(path) => candidates.push(std::path::PathBuf::from(path)),
+        None => {
+            if fs::try_exists(&own_meta_path).await.unwrap_or(false) {
+                candidates.push(own_meta_path.clone());
+            }
+            candidates.extend(
+                find_orphaned_metadata(db_path)
+                    .await?
+                    .into_iter()
+                    .map(|c| std::path::PathBuf::from(c.path)),
+            );
+        }
+    }
+
+    for candidate in candidates.iter().take(MAX_REPAIR_CANDIDATES) {
+        let Some(meta) = read_metadata_file(candidate).await? else {
+            continue;
+        };
+        let Some((repaired, key_z)) = match_metadata_to_db(db_path, &meta, &password).await? else {
+            continue;
+        };
+
+        reset_unlock_failures(&state, db_path).await;
+        write_password_metadata(db_path, &repaired, Some(key_z.as_slice())).await?;
+
+        let source = if candidate == &own_meta_path {
+            "repaired"
+        } else {
+            let orphaned = paired_db_path(candidate)
+                .map(|p| !p.exists())
+                .unwrap_or(false);
+            if orphaned {
+                let _ = fs::remove_file(candidate).await;
+            }
+            "relinked"
+        };
+        return Ok(MetadataRepairResult {
+            source: source.to_string(),
+            meta_path: own_meta_path.to_string_lossy().to_string(),
+        });
+    }
+
+    if is_plaintext_sqlite(db_path).await? {
+        let connect_options = SqliteConnectOptions::new()
+            .filename(db_path)
+            .create_if_missing(false)
+            .busy_timeout(Duration::from_secs(10));
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(connect_options)
+            .await?;
+        let from_db = load_metadata_from_db(&pool).await;
+        pool.close().await;
+
+        if let Some(meta) = from_db? {
+            if derive_checked_key(&meta, &password).await?.is_some() {
+                reset_unlock_failures(&state, db_path).await;
+                write_password_metadata(db_path, &meta, None).await?;
+                return Ok(MetadataRepairResult {
+                    source: "regenerated".to_string(),
+                    meta_path: own_meta_path.to_string_lossy().to_string(),
+                });
+            }
+        }
+    }
+
+    if candidates.is_empty() {
+        return Err(Error::Validation(
+            "No metadata file for this vault was found. Select its .meta.json file to re-pair it."
+                .to_string(),
+        ));
+    }
+    register_unlock_failure(&state, db_path).await;
+    Err(Error::Validation(
+        "None of the metadata files match this vault and password.".to_string(),
+    ))
+}
+
 #[tauri::command]
 pub async fn is_master_password_configured(state: State<'_, AppState>) -> Result<bool> {
     if let Ok(db_path) = get_db_path(&state).await {
@@ -16,6 +16,15 @@ pub fn derive_key(password: &str, salt: &[u8], params: &Argon2ParamsConfig) -> R
     Ok(key)
 }
 
+/// Key of the activity log's hash chain.
+pub fn derive_activity_log_key(master_key: &[u8]) -> Result<[u8; 32]> {
+    let hk = Hkdf::<Sha256>::new(None, master_key);
+    let mut out = [0u8; 32];
+    hk.expand(b"pulsar:activity-log", &mut out)
+        .map_err(|_| Error::Internal("Failed to derive activity log key".to_string()))?;
+    Ok(out)
+}
+
 pub fn derive_metadata_mac_key(master_key: &[u8]) -> Result<[u8; 32]> {
     let hk = Hkdf::<Sha256>::new(None, master_key);
     let mut out = [0u8; 32];
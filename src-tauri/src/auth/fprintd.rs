@@ -0,0 +1,123 @@
+//! Fingerprint verification on Linux through fprintd's D-Bus service
+//! (`net.reactivated.Fprint`) on the system bus. fprintd asks Polkit whether
+//! the session may verify, so this works in any desktop session where the
+//! login screen can use the reader. Every call here blocks.
+
+use crate::error::{Error, Result};
+use zbus::blocking::{Connection, Proxy};
+use zbus::zvariant::OwnedObjectPath;
+
+const SERVICE: &str = "net.reactivated.Fprint";
+const MANAGER_PATH: &str = "/net/reactivated/Fprint/Manager";
+const MANAGER_INTERFACE: &str = "net.reactivated.Fprint.Manager";
+const DEVICE_INTERFACE: &str = "net.reactivated.Fprint.Device";
+/// fprintd reads an empty username as the calling user.
+const CURRENT_USER: &str = "";
+
+fn fprintd_error(e: zbus::Error) -> Error {
+    if let zbus::Error::MethodError(name, _, _) = &e {
+        match name.as_str() {
+            "org.freedesktop.DBus.Error.ServiceUnknown" => {
+                return Error::Internal(
+                    "Fingerprint unlock needs fprintd. Install it and enroll a finger.".to_string(),
+                )
+            }
+            "net.reactivated.Fprint.Error.NoSuchDevice" => {
+                return Error::Internal("No fingerprint reader is present.".to_string())
+            }
+            "net.reactivated.Fprint.Error.PermissionDenied" => {
+                return Error::Internal(
+                    "Fingerprint verification was not authorized by Polkit.".to_string(),
+                )
+            }
+            "net.reactivated.Fprint.Error.AlreadyInUse" => {
+                return Error::Internal("The fingerprint reader is busy.".to_string())
+            }
+            _ => {}
+        }
+    }
+    Error::Internal(format!("Failed to query fprintd: {}", e))
+}
+
+fn default_device(conn: &Connection) -> Result<Proxy<'static>> {
+    let manager =
+        Proxy::new(conn, SERVICE, MANAGER_PATH, MANAGER_INTERFACE).map_err(fprintd_error)?;
+    let path: OwnedObjectPath = manager
+        .call("GetDefaultDevice", &())
+        .map_err(fprintd_error)?;
+    Proxy::new(conn, SERVICE, path, DEVICE_INTERFACE).map_err(fprintd_error)
+}
+
+/// Enrolled fingers for the current user, as reported by fprintd.
+pub fn enrolled_fingers() -> Result<Vec<String>> {
+    let conn = Connection::system().map_err(fprintd_error)?;
+    let device = default_device(&conn)?;
+    let mut fingers: Vec<String> = match device.call("ListEnrolledFingers", &(CURRENT_USER,)) {
+        Ok(fingers) => fingers,
+        Err(zbus::Error::MethodError(name, _, _))
+            if name.as_str() == "net.reactivated.Fprint.Error.NoEnrolledPrints" =>
+        {
+            Vec::new()
+        }
+        Err(e) => return Err(fprintd_error(e)),
+    };
+    fingers.sort();
+    Ok(fingers)
+}
+
+pub fn ensure_available() -> Result<()> {
+    if enrolled_fingers()?.is_empty() {
+        return Err(Error::Internal(
+            "No fingerprints are enrolled for this user.".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Asks for a finger and blocks until fprintd reports the result.
+pub fn verify() -> Result<()> {
+    let conn = Connection::system().map_err(fprintd_error)?;
+    let device = default_device(&conn)?;
+    device
+        .call::<_, _, ()>("Claim", &(CURRENT_USER,))
+        .map_err(fprintd_error)?;
+    let result = verify_claimed(&device);
+    let _ = device.call::<_, _, ()>("Release", &());
+    result
+}
+
+fn verify_claimed(device: &Proxy<'_>) -> Result<()> {
+    // Subscribe before starting, so the first status cannot be missed.
+    let statuses = device
+        .receive_signal("VerifyStatus")
+        .map_err(fprintd_error)?;
+    device
+        .call::<_, _, ()>("VerifyStart", &("any",))
+        .map_err(fprintd_error)?;
+
+    // Retry prompts (short swipe, finger not centred) arrive with `done`
+    // unset; the scan goes on until a final status.
+    let mut last = None;
+    for message in statuses {
+        let (result, done): (String, bool) = match message.body().deserialize() {
+            Ok(status) => status,
+            Err(_) => break,
+        };
+        last = Some(result);
+        if done {
+            break;
+        }
+    }
+    let _ = device.call::<_, _, ()>("VerifyStop", &());
+
+    match last.as_deref() {
+        Some("verify-match") => Ok(()),
+        Some("verify-no-match") => Err(Error::Internal("Fingerprint did not match.".to_string())),
+        Some("verify-disconnected") => Err(Error::Internal(
+            "The fingerprint reader was disconnected.".to_string(),
+        )),
+        _ => Err(Error::Internal(
+            "Biometric verification failed.".to_string(),
+        )),
+    }
+}
@@ -0,0 +1,351 @@
+//! Shared vaults. Each member is a row in `recipient_keys` holding only a
+//! name and an X25519 public key; the vault key is wrapped for every member
+//! in the vault's metadata file, where `unlock_with_member_key` finds it
+//! while the vault is locked.
+//!
+//! The wraps are rebuilt from the member list whenever it changes and on
+//! unlock, and re-wrapped when the vault key changes. Removing a member
+//! drops their wrap and changes the master password, so a key they kept
+//! only opens copies of the vault made before they were removed.
+
+use crate::auth::metadata::{read_password_metadata, write_password_metadata};
+use crate::auth::types::{MemberKey, PasswordMetadata};
+use crate::db::utils::UnlockedVault;
+use crate::encryption::{decrypt, encrypt};
+use crate::error::{Error, Result};
+use crate::limits;
+use crate::state::AppState;
+use base64::{engine::general_purpose, Engine as _};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use chrono::Utc;
+use hkdf::Hkdf;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::Serialize;
+use sha2::Sha256;
+use sqlx::{Row, SqlitePool};
+use std::path::Path;
+use tauri::State;
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+use zeroize::Zeroizing;
+
+const HKDF_INFO: &[u8] = b"pulsar:vault-member-key:x25519";
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultMember {
+    pub id: i64,
+    pub name: String,
+    pub public_key: String,
+    pub added_at: Option<String>,
+}
+
+fn decode_key(value: &str, what: &str) -> Result<[u8; 32]> {
+    general_purpose::STANDARD
+        .decode(value.trim())
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes.as_slice()).ok())
+        .ok_or_else(|| Error::Validation(format!("{} must be 32 bytes of base64", what)))
+}
+
+fn member_aad(member: &MemberKey) -> String {
+    format!(
+        "v1:{}:{}:{}:{}",
+        member.public_key_b64, member.eph_pub_b64, member.salt_b64, member.nonce_b64
+    )
+}
+
+fn member_cipher(shared: &[u8], salt: &[u8]) -> Result<XChaCha20Poly1305> {
+    let hk = Hkdf::<Sha256>::new(Some(salt), shared);
+    let mut aead_key = Zeroizing::new([0u8; 32]);
+    hk.expand(HKDF_INFO, &mut aead_key[..])
+        .map_err(|_| Error::Internal("HKDF expand failed".to_string()))?;
+    Ok(XChaCha20Poly1305::new(Key::from_slice(&aead_key[..])))
+}
+
+/// Wraps `vault_key` for the member with public key `public_key_b64`.
+pub(crate) fn wrap_for_member(public_key_b64: &str, vault_key: &[u8]) -> Result<MemberKey> {
+    let public_key = PublicKey::from(decode_key(public_key_b64, "A member public key")?);
+    let eph_secret = EphemeralSecret::random_from_rng(OsRng);
+    let eph_public = PublicKey::from(&eph_secret);
+    let shared = eph_secret.diffie_hellman(&public_key);
+
+    let mut salt = [0u8; 32];
+    OsRng.fill_bytes(&mut salt);
+    let mut nonce = [0u8; 24];
+    OsRng.fill_bytes(&mut nonce);
+
+    let mut member = MemberKey {
+        public_key_b64: general_purpose::STANDARD.encode(public_key.as_bytes()),
+        eph_pub_b64: general_purpose::STANDARD.encode(eph_public.as_bytes()),
+        salt_b64: general_purpose::STANDARD.encode(salt),
+        nonce_b64: general_purpose::STANDARD.encode(nonce),
+        ciphertext_b64: String::new(),
+    };
+    let ciphertext = member_cipher(shared.as_bytes(), &salt)?
+        .encrypt(
+            XNonce::from_slice(&nonce),
+            Payload {
+                msg: vault_key,
+                aad: member_aad(&member).as_bytes(),
+            },
+        )
+        .map_err(|e| Error::Encryption(format!("Wrapping the vault key failed: {}", e)))?;
+    member.ciphertext_b64 = general_purpose::STANDARD.encode(ciphertext);
+    Ok(member)
+}
+
+/// Recovers the vault key from the metadata's wraps with a member's private
+/// key. The caller still checks the key against the vault.
+pub(crate) fn open_member_key(
+    meta: &PasswordMetadata,
+    private_key_b64: &str,
+) -> Result<Zeroizing<Vec<u8>>> {
+    let secret = StaticSecret::from(decode_key(private_key_b64, "The private key")?);
+    let public_b64 = general_purpose::STANDARD.encode(PublicKey::from(&secret).as_bytes());
+    let member = meta
+        .member_keys
+        .iter()
+        .find(|m| m.public_key_b64 == public_b64)
+        .ok_or_else(|| Error::Validation("This key is not a member of the vault.".to_string()))?;
+
+    let decode = |value: &str| {
+        general_purpose::STANDARD
+            .decode(value)
+            .map_err(|_| Error::Validation("The vault's member key is corrupt.".to_string()))
+    };
+    let eph_public = PublicKey::from(decode_key(&member.eph_pub_b64, "The ephemeral key")?);
+    let salt = decode(&member.salt_b64)?;
+    let nonce = decode(&member.nonce_b64)?;
+    let ciphertext = decode(&member.ciphertext_b64)?;
+    if nonce.len() != 24 {
+        return Err(Error::Validation("nonce must be 24 bytes".into()));
+    }
+
+    let shared = secret.diffie_hellman(&eph_public);
+    let key = member_cipher(shared.as_bytes(), &salt)?
+        .decrypt(
+            XNonce::from_slice(&nonce),
+            Payload {
+                msg: &ciphertext,
+                aad: member_aad(member).as_bytes(),
+            },
+        )
+        .map_err(|_| Error::Validation("The vault's member key is corrupt.".to_string()))?;
+    Ok(Zeroizing::new(key))
+}
+
+/// Wraps the new vault key for the same members, after a rekey.
+pub(crate) fn rewrap_member_keys(meta: &mut PasswordMetadata, new_key: &[u8]) -> Result<()> {
+    meta.member_keys = meta
+        .member_keys
+        .iter()
+        .map(|m| wrap_for_member(&m.public_key_b64, new_key))
+        .collect::<Result<_>>()?;
+    Ok(())
+}
+
+async fn load_members(pool: &SqlitePool, key: &[u8]) -> Result<Vec<VaultMember>> {
+    sqlx::query(
+        "SELECT id, name, public_key, added_at FROM recipient_keys WHERE private_key IS NULL ORDER BY id",
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|row| {
+        Ok(VaultMember {
+            id: row.get("id"),
+            name: decrypt(row.get::<String, _>("name").as_str(), key)?,
+            public_key: decrypt(row.get::<String, _>("public_key").as_str(), key)?,
+            added_at: row.get("added_at"),
+        })
+    })
+    .collect()
+}
+
+/// Brings the metadata's wraps in line with the members listed in the
+/// vault. Existing wraps are kept, so the file is only rewritten when
+/// membership changed.
+pub(crate) async fn reconcile_member_keys(
+    db_path: &Path,
+    pool: &SqlitePool,
+    key: &[u8],
+) -> Result<()> {
+    let Some(mut meta) = read_password_metadata(db_path).await? else {
+        return Ok(());
+    };
+    let members = load_members(pool, key).await?;
+    let mut wanted = Vec::with_capacity(members.len());
+    for member in &members {
+        let public_b64 = general_purpose::STANDARD
+            .encode(decode_key(&member.public_key, "A member public key")?);
+        match meta
+            .member_keys
+            .iter()
+            .find(|m| m.public_key_b64 == public_b64)
+        {
+            Some(existing) => wanted.push(existing.clone()),
+            None => wanted.push(wrap_for_member(&public_b64, key)?),
+        }
+    }
+    if wanted == meta.member_keys {
+        return Ok(());
+    }
+    meta.member_keys = wanted;
+    write_password_metadata(db_path, &meta, Some(key)).await
+}
+
+/// Updates the wraps after a change that may have removed members, such as
+/// restoring a backup, which holds no members. Failures are only logged.
+pub(crate) async fn refresh_member_keys(state: &AppState) {
+    let result = async {
+        let vault = UnlockedVault::acquire_writable(state).await?;
+        let db_path = state
+            .db_path
+            .lock()
+            .await
+            .clone()
+            .ok_or(Error::VaultNotLoaded)?;
+        reconcile_member_keys(&db_path, vault.pool(), vault.key()).await
+    }
+    .await;
+    if let Err(e) = result {
+        eprintln!("[Members] Failed to update member keys: {}", e);
+    }
+}
+
+async fn vault_path(state: &State<'_, AppState>) -> Result<std::path::PathBuf> {
+    state
+        .db_path
+        .lock()
+        .await
+        .clone()
+        .ok_or(Error::VaultNotLoaded)
+}
+
+#[tauri::command]
+pub async fn list_vault_members(state: State<'_, AppState>) -> Result<Vec<VaultMember>> {
+    let vault = UnlockedVault::acquire(&state).await?;
+    load_members(vault.pool(), vault.key()).await
+}
+
+/// Shares the vault with the holder of the X25519 key pair whose public
+/// half is `public_key`, as made by `generate_x25519_keypair`.
+#[tauri::command]
+pub async fn add_vault_member(
+    state: State<'_, AppState>,
+    name: String,
+    public_key: String,
+) -> Result<VaultMember> {
+    limits::check_len("Member name", &name, limits::MAX_TITLE_LEN)?;
+    limits::check_len("Public key", &public_key, limits::MAX_KEY_MATERIAL_LEN)?;
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(Error::Validation("A member needs a name.".to_string()));
+    }
+    let public_key =
+        general_purpose::STANDARD.encode(decode_key(&public_key, "A member public key")?);
+
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let db_path = vault_path(&state).await?;
+    if load_members(vault.pool(), vault.key())
+        .await?
+        .iter()
+        .any(|m| m.public_key == public_key)
+    {
+        return Err(Error::Validation(
+            "This key is already a member of the vault.".to_string(),
+        ));
+    }
+
+    let added_at = Utc::now().to_rfc3339();
+    let id = sqlx::query(
+        "INSERT INTO recipient_keys (name, public_key, private_key, added_at) VALUES (?, ?, NULL, ?)",
+    )
+    .bind(encrypt(&name, vault.key())?)
+    .bind(encrypt(&public_key, vault.key())?)
+    .bind(&added_at)
+    .execute(vault.pool())
+    .await?
+    .last_insert_rowid();
+    reconcile_member_keys(&db_path, vault.pool(), vault.key()).await?;
+
+    let _ = crate::db::activity::log_activity_impl(
+        vault.pool(),
+        vault.key(),
+        "vault_member_added",
+        None,
+        None,
+        Some(&format!("Shared the vault with {}", name)),
+    )
+    .await;
+    Ok(VaultMember {
+        id,
+        name,
+        public_key,
+        added_at: Some(added_at),
+    })
+}
+
+/// Removes a member and rotates the vault key by changing the master
+/// password to `new_password`. Dropping the wrap alone would leave the
+/// former member able to open the vault with a key they kept.
+#[tauri::command]
+pub async fn remove_vault_member(
+    state: State<'_, AppState>,
+    id: i64,
+    current_password: String,
+    new_password: String,
+) -> Result<()> {
+    let current_password = Zeroizing::new(current_password);
+    let new_password = Zeroizing::new(new_password);
+    crate::auth::commands::validate_password_inputs(&current_password, &new_password)?;
+    if !crate::auth::verify_master_password_internal(&state, &current_password).await? {
+        return Err(Error::InvalidPassword);
+    }
+    // Checked up front so the rotation cannot fail on it after the removal.
+    crate::auth::reverify::require_fresh(
+        &state,
+        crate::auth::reverify::SensitiveOperation::RotateMasterPassword,
+    )
+    .await?;
+
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let db_path = vault_path(&state).await?;
+    let removed = sqlx::query("DELETE FROM recipient_keys WHERE id = ? AND private_key IS NULL")
+        .bind(id)
+        .execute(vault.pool())
+        .await?
+        .rows_affected();
+    if removed == 0 {
+        return Err(Error::Validation(format!("No vault member with id {}", id)));
+    }
+    reconcile_member_keys(&db_path, vault.pool(), vault.key()).await?;
+
+    let _ = crate::db::activity::log_activity_impl(
+        vault.pool(),
+        vault.key(),
+        "vault_member_removed",
+        None,
+        None,
+        Some("Removed a member from the vault"),
+    )
+    .await;
+    drop(vault);
+
+    crate::auth::commands::rotate_master_password(
+        state,
+        current_password.to_string(),
+        new_password.to_string(),
+    )
+    .await
+    .map_err(|e| {
+        Error::Internal(format!(
+            "The member was removed, but changing the master password failed: {}. Change it to stop them opening the vault.",
+            e
+        ))
+    })
+}
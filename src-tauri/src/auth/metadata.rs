@@ -1,5 +1,5 @@
 use crate::auth::crypto_utils::derive_metadata_mac_key;
-use crate::auth::types::PasswordMetadata;
+use crate::auth::types::{ItemCountHint, PasswordMetadata};
 use crate::error::{Error, Result};
 use base64::{engine::general_purpose, Engine as _};
 use chacha20poly1305::{
@@ -25,9 +25,19 @@ pub fn metadata_path(db_path: &Path) -> PathBuf {
         .join(meta_name)
 }
 
+/// Inverse of `metadata_path`: the vault file a `.meta.json` belongs to.
+pub fn paired_db_path(meta_path: &Path) -> Option<PathBuf> {
+    let file_name = meta_path.file_name()?.to_str()?;
+    let db_name = file_name.strip_suffix(".meta.json")?;
+    Some(meta_path.with_file_name(db_name))
+}
+
 pub async fn read_password_metadata(db_path: &Path) -> Result<Option<PasswordMetadata>> {
-    let path = metadata_path(db_path);
-    match fs::read(&path).await {
+    read_metadata_file(&metadata_path(db_path)).await
+}
+
+pub async fn read_metadata_file(path: &Path) -> Result<Option<PasswordMetadata>> {
+    match fs::read(path).await {
         Ok(bytes) => {
             let meta: PasswordMetadata = serde_json::from_slice(&bytes)?;
             Ok(Some(meta))
@@ -37,6 +47,65 @@ pub async fn read_password_metadata(db_path: &Path) -> Result<Option<PasswordMet
     }
 }
 
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataCandidate {
+    pub path: String,
+    pub paired_db_path: Option<String>,
+    pub salt_b64: String,
+    pub modified_at: Option<i64>,
+}
+
+/// Lists `.meta.json` files next to `db_path` whose own vault file is gone,
+/// newest first. These are left behind when a vault is renamed or moved
+/// without its metadata.
+pub async fn find_orphaned_metadata(db_path: &Path) -> Result<Vec<MetadataCandidate>> {
+    const MAX_DIR_ENTRIES: usize = 10_000;
+
+    let own = metadata_path(db_path);
+    let dir = db_path.parent().unwrap_or_else(|| Path::new("."));
+    let mut entries = fs::read_dir(dir).await?;
+    let mut candidates = Vec::new();
+    let mut seen = 0;
+
+    while let Some(entry) = entries.next_entry().await? {
+        seen += 1;
+        if seen > MAX_DIR_ENTRIES {
+            break;
+        }
+        let path = entry.path();
+        if path == own {
+            continue;
+        }
+        let Some(paired) = paired_db_path(&path) else {
+            continue;
+        };
+        if fs::try_exists(&paired).await.unwrap_or(false) {
+            continue;
+        }
+        let Ok(Some(meta)) = read_metadata_file(&path).await else {
+            continue;
+        };
+
+        let modified_at = entry
+            .metadata()
+            .await
+            .ok()
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as i64);
+        candidates.push(MetadataCandidate {
+            path: path.to_string_lossy().to_string(),
+            paired_db_path: Some(paired.to_string_lossy().to_string()),
+            salt_b64: meta.salt_b64,
+            modified_at,
+        });
+    }
+
+    candidates.sort_by(|a, b| b.modified_at.cmp(&a.modified_at));
+    Ok(candidates)
+}
+
 pub async fn write_password_metadata(
     db_path: &Path,
     meta: &PasswordMetadata,
@@ -206,6 +275,70 @@ pub fn verify_metadata_mac(
     Ok(())
 }
 
+#[derive(Serialize)]
+struct ItemCountMacPayload<'a> {
+    vault_id: &'a str,
+    purpose: &'static str,
+    count: u64,
+    updated_at: i64,
+}
+
+fn item_count_tag(
+    vault_id: &str,
+    count: u64,
+    updated_at: i64,
+    nonce: &[u8],
+    master_key: &[u8],
+) -> Result<Vec<u8>> {
+    let mac_key = derive_metadata_mac_key(master_key)?;
+    let payload = serde_json::to_vec(&ItemCountMacPayload {
+        vault_id,
+        purpose: "item_count",
+        count,
+        updated_at,
+    })?;
+    XChaCha20Poly1305::new(Key::from_slice(&mac_key))
+        .encrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: b"",
+                aad: &payload,
+            },
+        )
+        .map_err(|e| Error::Encryption(format!("Metadata MAC failed: {}", e)))
+}
+
+pub fn sign_item_count(count: u64, vault_id: &str, master_key: &[u8]) -> Result<ItemCountHint> {
+    let updated_at = chrono::Utc::now().timestamp_millis();
+    let mut nonce = [0u8; 24];
+    OsRng.fill_bytes(&mut nonce);
+    let tag = item_count_tag(vault_id, count, updated_at, &nonce, master_key)?;
+    Ok(ItemCountHint {
+        count,
+        updated_at,
+        mac_nonce_b64: general_purpose::STANDARD.encode(nonce),
+        mac_tag_b64: general_purpose::STANDARD.encode(tag),
+    })
+}
+
+pub fn verify_item_count(hint: &ItemCountHint, vault_id: &str, master_key: &[u8]) -> Result<()> {
+    let invalid = || Error::Validation("Item count hint integrity check failed.".to_string());
+    let nonce = general_purpose::STANDARD
+        .decode(&hint.mac_nonce_b64)
+        .map_err(|_| invalid())?;
+    if nonce.len() != 24 {
+        return Err(invalid());
+    }
+    let tag = general_purpose::STANDARD
+        .decode(&hint.mac_tag_b64)
+        .map_err(|_| invalid())?;
+    let expected = item_count_tag(vault_id, hint.count, hint.updated_at, &nonce, master_key)?;
+    if expected.len() != tag.len() || expected.ct_eq(&tag).unwrap_u8() != 1 {
+        return Err(invalid());
+    }
+    Ok(())
+}
+
 pub fn get_vault_id(db_path: &Path) -> String {
     use sha2::{Digest, Sha256};
     let mut hasher = Sha256::new();
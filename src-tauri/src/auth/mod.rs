@@ -1,10 +1,20 @@
+pub mod attempts;
 pub mod biometrics;
 pub mod commands;
 pub mod crypto_utils;
+#[cfg(target_os = "linux")]
+pub mod fprintd;
+pub mod members;
 pub mod metadata;
+pub mod presets;
+pub mod reverify;
+#[cfg(target_os = "macos")]
+pub mod secure_enclave;
 pub mod types;
+#[cfg(target_os = "windows")]
+pub mod windows_hello;
 
-use crate::auth::types::PasswordMetadata;
+use crate::auth::types::{PasswordMetadata, VaultDisplay};
 use crate::error::{Error, Result};
 use crate::state::AppState;
 use sqlx::Row;
@@ -81,6 +91,8 @@ pub async fn load_metadata_from_db(db_pool: &sqlx::SqlitePool) -> Result<Option<
                 value.parse::<u32>().ok()
             });
 
+    let display = load_vault_display_from_db(db_pool).await?;
+
     Ok(Some(PasswordMetadata {
         version: 1,
         salt_b64,
@@ -92,9 +104,26 @@ pub async fn load_metadata_from_db(db_pool: &sqlx::SqlitePool) -> Result<Option<
         mac_version: None,
         mac_nonce_b64: None,
         mac_tag_b64: None,
+        display,
+        item_count_hint: None,
+        member_keys: Vec::new(),
     }))
 }
 
+pub async fn load_vault_display_from_db(
+    db_pool: &sqlx::SqlitePool,
+) -> Result<Option<VaultDisplay>> {
+    let display = sqlx::query("SELECT value FROM configuration WHERE key = ?")
+        .bind("vault_display")
+        .fetch_optional(db_pool)
+        .await?
+        .and_then(|row| {
+            let value: String = row.get("value");
+            serde_json::from_str(&value).ok()
+        });
+    Ok(display)
+}
+
 pub async fn load_existing_metadata(
     _state: &State<'_, AppState>,
     db_pool: &sqlx::SqlitePool,
@@ -147,5 +176,8 @@ pub async fn verify_master_password_internal(
 
     let is_valid = decrypted.ct_eq(PASSWORD_CHECK_PLAINTEXT).unwrap_u8() == 1;
     decrypted.zeroize();
+    if is_valid {
+        reverify::note_verified(state).await;
+    }
     Ok(is_valid)
 }
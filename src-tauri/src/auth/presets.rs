@@ -0,0 +1,122 @@
+//! Named Argon2 strength presets. Each maps to concrete parameters and is
+//! applied through the regular `update_argon2_params` re-derivation. Unlock
+//! time is estimated from a short calibration run on this machine, scaled by
+//! the memory and passes of each preset.
+
+use crate::auth::crypto_utils::derive_key;
+use crate::auth::types::Argon2ParamsConfig;
+use crate::error::{Error, Result};
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use std::time::Instant;
+use tauri::State;
+
+const CALIBRATION_MEMORY_KIB: u32 = 16 * 1024;
+
+/// Milliseconds per KiB-pass with one lane, measured once per run.
+static CALIBRATION: OnceLock<f64> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Argon2Preset {
+    Fast,
+    Balanced,
+    Paranoid,
+}
+
+impl Argon2Preset {
+    pub const ALL: [Argon2Preset; 3] = [
+        Argon2Preset::Fast,
+        Argon2Preset::Balanced,
+        Argon2Preset::Paranoid,
+    ];
+
+    pub fn params(self) -> Argon2ParamsConfig {
+        match self {
+            // OWASP's minimum recommendation for Argon2id.
+            Argon2Preset::Fast => Argon2ParamsConfig {
+                memory_kib: 19 * 1024,
+                time_cost: 2,
+                parallelism: 1,
+            },
+            Argon2Preset::Balanced => Argon2ParamsConfig::default(),
+            Argon2Preset::Paranoid => Argon2ParamsConfig {
+                memory_kib: 256 * 1024,
+                time_cost: 4,
+                parallelism: 4,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Argon2PresetInfo {
+    pub preset: Argon2Preset,
+    pub memory_kib: u32,
+    pub time_cost: u32,
+    pub parallelism: u32,
+    pub estimated_unlock_ms: u64,
+}
+
+fn calibrate() -> Result<f64> {
+    if let Some(ms_per_unit) = CALIBRATION.get() {
+        return Ok(*ms_per_unit);
+    }
+    let params = Argon2ParamsConfig {
+        memory_kib: CALIBRATION_MEMORY_KIB,
+        time_cost: 1,
+        parallelism: 1,
+    };
+    let started = Instant::now();
+    derive_key("pulsar-calibration", b"pulsar-calibration-salt", &params)?;
+    let ms_per_unit = started.elapsed().as_secs_f64() * 1000.0 / f64::from(CALIBRATION_MEMORY_KIB);
+    Ok(*CALIBRATION.get_or_init(|| ms_per_unit))
+}
+
+fn estimate_unlock_ms(params: &Argon2ParamsConfig, ms_per_unit: f64) -> u64 {
+    let cores = std::thread::available_parallelism()
+        .map(|n| n.get() as u32)
+        .unwrap_or(1);
+    let lanes = params.parallelism.clamp(1, cores.max(1));
+    let work = f64::from(params.memory_kib) * f64::from(params.time_cost);
+    (work * ms_per_unit / f64::from(lanes)).round() as u64
+}
+
+#[tauri::command]
+pub async fn get_argon2_presets() -> Result<Vec<Argon2PresetInfo>> {
+    let ms_per_unit = tauri::async_runtime::spawn_blocking(calibrate)
+        .await
+        .map_err(|e| Error::Internal(format!("Runtime error: {}", e)))??;
+    Ok(Argon2Preset::ALL
+        .into_iter()
+        .map(|preset| {
+            let params = preset.params();
+            Argon2PresetInfo {
+                preset,
+                estimated_unlock_ms: estimate_unlock_ms(&params, ms_per_unit),
+                memory_kib: params.memory_kib,
+                time_cost: params.time_cost,
+                parallelism: params.parallelism,
+            }
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn apply_argon2_preset(
+    state: State<'_, AppState>,
+    preset: Argon2Preset,
+    current_password: String,
+) -> Result<()> {
+    let params = preset.params();
+    super::commands::update_argon2_params(
+        state,
+        current_password,
+        params.memory_kib,
+        params.time_cost,
+        params.parallelism,
+    )
+    .await
+}
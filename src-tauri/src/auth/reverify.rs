@@ -0,0 +1,86 @@
+//! Fresh identity checks for sensitive operations. A vault can require that
+//! the master password or a biometric was verified within the last few
+//! seconds before, say, exporting or changing the master password, so an
+//! unattended unlocked session is not enough. The time of the last
+//! verification is kept in `AppState` and forgotten on lock.
+
+use crate::db::utils::UnlockedVault;
+use crate::db::vault_settings::load_vault_settings;
+use crate::error::{Error, Result};
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, State};
+use zeroize::Zeroizing;
+
+pub const MAX_WINDOW_SECONDS: u32 = 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SensitiveOperation {
+    RotateMasterPassword,
+    ExportVault,
+    ViewLoginTotpSecret,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReverificationPolicy {
+    /// How long a verification stays fresh.
+    pub window_seconds: u32,
+    pub operations: Vec<SensitiveOperation>,
+}
+
+pub async fn note_verified(state: &State<'_, AppState>) {
+    *state.last_verification.lock().await = Some(Instant::now());
+}
+
+pub async fn clear(state: &State<'_, AppState>) {
+    *state.last_verification.lock().await = None;
+}
+
+/// Fails with `VerificationRequired` when the vault's policy covers
+/// `operation` and the last verification is older than its window.
+pub async fn require_fresh(
+    state: &State<'_, AppState>,
+    operation: SensitiveOperation,
+) -> Result<()> {
+    let policy = {
+        let vault = UnlockedVault::acquire(state).await?;
+        load_vault_settings(vault.pool(), vault.key())
+            .await?
+            .reverification
+    };
+    let Some(policy) = policy.filter(|p| p.operations.contains(&operation)) else {
+        return Ok(());
+    };
+
+    let window = Duration::from_secs(u64::from(policy.window_seconds));
+    let fresh = state
+        .last_verification
+        .lock()
+        .await
+        .is_some_and(|at| at.elapsed() <= window);
+    if fresh {
+        Ok(())
+    } else {
+        Err(Error::VerificationRequired)
+    }
+}
+
+#[tauri::command]
+pub async fn reverify_with_password(state: State<'_, AppState>, password: String) -> Result<()> {
+    let password = Zeroizing::new(password);
+    if !crate::auth::verify_master_password_internal(&state, password.as_str()).await? {
+        return Err(Error::InvalidPassword);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn reverify_with_biometrics(app: AppHandle, state: State<'_, AppState>) -> Result<()> {
+    state.key.lock().await.as_ref().ok_or(Error::VaultLocked)?;
+    crate::auth::biometrics::authenticate_biometric(&app, "Confirm it's you to continue").await?;
+    note_verified(&state).await;
+    Ok(())
+}
@@ -0,0 +1,85 @@
+//! Secure Enclave protection for the biometric wrapping key on macOS. Each
+//! vault gets a P-256 key generated inside the Secure Enclave with
+//! biometry-bound access control; the wrapping key is stored only encrypted
+//! to it (ECIES). Decrypting needs Touch ID on this device, and the private
+//! key never leaves the enclave, so copying the keychain item or reading it
+//! from another process running as the user does not reveal the secret.
+
+use crate::error::{Error, Result};
+use security_framework::access_control::{ProtectionMode, SecAccessControl};
+use security_framework::item::{ItemClass, ItemSearchOptions, Location, Reference, SearchResult};
+use security_framework::key::{Algorithm, GenerateKeyOptions, KeyType, SecKey, Token};
+use security_framework_sys::access_control::{
+    kSecAccessControlBiometryCurrentSet, kSecAccessControlPrivateKeyUsage,
+};
+
+const ALGORITHM: Algorithm = Algorithm::ECIESEncryptionCofactorVariableIVX963SHA256AESGCM;
+
+fn label(vault_user: &str) -> String {
+    format!("pulsar-biometric-{}", vault_user)
+}
+
+fn find_key(vault_user: &str) -> Option<SecKey> {
+    ItemSearchOptions::new()
+        .class(ItemClass::key())
+        .label(&label(vault_user))
+        .load_refs(true)
+        .search()
+        .ok()?
+        .into_iter()
+        .find_map(|result| match result {
+            SearchResult::Ref(Reference::Key(key)) => Some(key),
+            _ => None,
+        })
+}
+
+fn create_key(vault_user: &str) -> Result<SecKey> {
+    let access = SecAccessControl::create_with_protection(
+        Some(ProtectionMode::AccessibleWhenPasscodeSetThisDeviceOnly),
+        kSecAccessControlPrivateKeyUsage | kSecAccessControlBiometryCurrentSet,
+    )
+    .map_err(|e| Error::Internal(format!("Failed to create access control: {}", e)))?;
+
+    let mut options = GenerateKeyOptions::default();
+    options
+        .set_key_type(KeyType::ec())
+        .set_size_in_bits(256)
+        .set_token(Token::SecureEnclave)
+        .set_label(label(vault_user))
+        .set_location(Location::DataProtectionKeychain)
+        .set_access_control(access);
+    SecKey::new(&options)
+        .map_err(|e| Error::Internal(format!("Secure Enclave key generation failed: {}", e)))
+}
+
+/// Encrypts `secret` to a new Secure Enclave key for `vault_user`,
+/// replacing any previous key.
+pub fn wrap(vault_user: &str, secret: &[u8]) -> Result<Vec<u8>> {
+    delete(vault_user);
+    let private_key = create_key(vault_user)?;
+    let public_key = private_key
+        .public_key()
+        .ok_or_else(|| Error::Internal("Secure Enclave key has no public key".to_string()))?;
+    public_key
+        .encrypt_data(ALGORITHM, secret)
+        .map_err(|e| Error::Encryption(format!("Secure Enclave wrap failed: {}", e)))
+}
+
+/// Decrypts a secret from `wrap`. The system asks for Touch ID.
+pub fn unwrap(vault_user: &str, wrapped: &[u8]) -> Result<Vec<u8>> {
+    let private_key = find_key(vault_user).ok_or_else(|| {
+        Error::Internal(
+            "The Secure Enclave key for this vault is gone. Unlock with your master password and enable biometric unlock again."
+                .to_string(),
+        )
+    })?;
+    private_key
+        .decrypt_data(ALGORITHM, wrapped)
+        .map_err(|_| Error::Internal("Biometric verification failed.".to_string()))
+}
+
+pub fn delete(vault_user: &str) {
+    if let Some(key) = find_key(vault_user) {
+        let _ = key.delete();
+    }
+}
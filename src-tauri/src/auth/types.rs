@@ -7,6 +7,8 @@ pub const PENDING_TOTP_TTL: Duration = Duration::from_secs(120);
 pub const MAX_TOTP_ATTEMPTS: u8 = 5;
 pub const UNLOCK_BACKOFF_BASE_MS: u64 = 250;
 pub const UNLOCK_BACKOFF_MAX_MS: u64 = 5000;
+/// Failures after which the backoff stops growing.
+pub const UNLOCK_BACKOFF_MAX_FAILURES: u32 = 6;
 pub const ARGON2_MIN_MEMORY_KIB: u32 = 8 * 1024;
 pub const ARGON2_MAX_MEMORY_KIB: u32 = 1024 * 1024;
 pub const ARGON2_MAX_TIME_COST: u32 = 10;
@@ -31,6 +33,51 @@ pub struct PasswordMetadata {
     pub mac_nonce_b64: Option<String>,
     #[serde(default)]
     pub mac_tag_b64: Option<String>,
+    /// Cosmetic vault identity. Kept outside the MAC so older clients can
+    /// still verify metadata written by newer ones.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display: Option<VaultDisplay>,
+    /// Item count as of the last lock, shown while the vault is locked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item_count_hint: Option<ItemCountHint>,
+    /// The vault key wrapped for each member of a shared vault. Derived from
+    /// the members listed in the vault, so it is outside the MAC as well.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub member_keys: Vec<MemberKey>,
+}
+
+/// The vault key encrypted to one member's X25519 public key with an
+/// ephemeral key pair, HKDF-SHA256 and XChaCha20-Poly1305.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MemberKey {
+    pub public_key_b64: String,
+    pub eph_pub_b64: String,
+    pub salt_b64: String,
+    pub nonce_b64: String,
+    pub ciphertext_b64: String,
+}
+
+/// A count of the vault's items, signed with the metadata MAC key so that a
+/// tampered value is caught on the next unlock. Until then it is only shown,
+/// never trusted.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ItemCountHint {
+    pub count: u64,
+    pub updated_at: i64,
+    pub mac_nonce_b64: String,
+    pub mac_tag_b64: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct VaultDisplay {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
 }
 
 #[derive(Debug, Clone)]
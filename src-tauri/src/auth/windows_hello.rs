@@ -0,0 +1,103 @@
+//! Windows Hello protection for the biometric wrapping key. A Hello key
+//! credential is created per vault; its private key lives in the TPM (or the
+//! Hello software container) and signs only after the user verifies. The
+//! signature over a fixed challenge is deterministic, so it is hashed into a
+//! key that encrypts the wrapping key. Credential Manager then holds only
+//! ciphertext, and disk plus keyring access is not enough to unlock.
+
+use crate::encryption::{decrypt_bytes, encrypt_bytes};
+use crate::error::{Error, Result};
+use sha2::{Digest, Sha256};
+use windows::core::{Array, HSTRING};
+use windows::Security::Credentials::{
+    KeyCredentialCreationOption, KeyCredentialManager, KeyCredentialStatus,
+};
+use windows::Security::Cryptography::CryptographicBuffer;
+use zeroize::Zeroizing;
+
+const CHALLENGE: &[u8] = b"pulsar-biometric-key-v1";
+
+fn credential_name(vault_user: &str) -> HSTRING {
+    HSTRING::from(format!("pulsar-biometric-{}", vault_user))
+}
+
+fn status_error(status: KeyCredentialStatus) -> Error {
+    match status {
+        KeyCredentialStatus::UserCanceled => {
+            Error::Internal("Windows Hello verification was canceled.".to_string())
+        }
+        KeyCredentialStatus::NotFound => Error::Internal(
+            "The Windows Hello key for this vault is gone. Unlock with your master password and enable biometric unlock again."
+                .to_string(),
+        ),
+        KeyCredentialStatus::SecurityDeviceLocked => {
+            Error::Internal("The security device is locked.".to_string())
+        }
+        _ => Error::Internal("Windows Hello verification failed.".to_string()),
+    }
+}
+
+fn hello_error(e: windows::core::Error) -> Error {
+    Error::Internal(format!("Windows Hello error: {e}"))
+}
+
+pub fn is_supported() -> bool {
+    KeyCredentialManager::IsSupportedAsync()
+        .and_then(|op| op.get())
+        .unwrap_or(false)
+}
+
+/// Derives the wrapping key from a Hello signature, creating the credential
+/// first when `create` is set. Windows shows the Hello prompt.
+fn hello_key(vault_user: &str, create: bool) -> Result<Zeroizing<[u8; 32]>> {
+    let name = credential_name(vault_user);
+    let result = if create {
+        KeyCredentialManager::RequestCreateAsync(
+            &name,
+            KeyCredentialCreationOption::ReplaceExisting,
+        )
+    } else {
+        KeyCredentialManager::OpenAsync(&name)
+    }
+    .and_then(|op| op.get())
+    .map_err(hello_error)?;
+    let status = result.Status().map_err(hello_error)?;
+    if status != KeyCredentialStatus::Success {
+        return Err(status_error(status));
+    }
+    let credential = result.Credential().map_err(hello_error)?;
+
+    let challenge = CryptographicBuffer::CreateFromByteArray(CHALLENGE).map_err(hello_error)?;
+    let signed = credential
+        .RequestSignAsync(&challenge)
+        .and_then(|op| op.get())
+        .map_err(hello_error)?;
+    let status = signed.Status().map_err(hello_error)?;
+    if status != KeyCredentialStatus::Success {
+        return Err(status_error(status));
+    }
+    let mut signature = Array::<u8>::new();
+    CryptographicBuffer::CopyToByteArray(&signed.Result().map_err(hello_error)?, &mut signature)
+        .map_err(hello_error)?;
+
+    let mut hasher = Sha256::new();
+    hasher.update(b"pulsar-hello-wrap:");
+    hasher.update(&signature[..]);
+    Ok(Zeroizing::new(hasher.finalize().into()))
+}
+
+/// Encrypts `secret` under a new Hello credential for `vault_user`.
+pub fn wrap(vault_user: &str, secret: &[u8]) -> Result<Vec<u8>> {
+    let key = hello_key(vault_user, true)?;
+    encrypt_bytes(secret, key.as_slice())
+}
+
+pub fn unwrap(vault_user: &str, wrapped: &[u8]) -> Result<Vec<u8>> {
+    let key = hello_key(vault_user, false)?;
+    decrypt_bytes(wrapped, key.as_slice())
+        .map_err(|_| Error::Internal("Biometric decryption failed".to_string()))
+}
+
+pub fn delete(vault_user: &str) {
+    let _ = KeyCredentialManager::DeleteAsync(&credential_name(vault_user)).and_then(|op| op.get());
+}
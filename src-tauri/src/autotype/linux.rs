@@ -0,0 +1,181 @@
+use crate::autotype::SpecialKey;
+use crate::error::{Error, Result};
+use std::path::PathBuf;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionType {
+    X11,
+    Wayland,
+    Unknown,
+}
+
+impl SessionType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionType::X11 => "x11",
+            SessionType::Wayland => "wayland",
+            SessionType::Unknown => "unknown",
+        }
+    }
+}
+
+/// Input injection backends, in the order they are preferred for each session.
+/// `xdotool` drives the XTest extension, `wtype` uses the wlroots virtual
+/// keyboard protocol and `ydotool` goes through uinput so it works on any
+/// compositor as long as its daemon is running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Xdotool,
+    Wtype,
+    Ydotool,
+}
+
+impl Backend {
+    pub fn binary(&self) -> &'static str {
+        match self {
+            Backend::Xdotool => "xdotool",
+            Backend::Wtype => "wtype",
+            Backend::Ydotool => "ydotool",
+        }
+    }
+}
+
+pub fn session_type() -> SessionType {
+    match std::env::var("XDG_SESSION_TYPE")
+        .unwrap_or_default()
+        .to_lowercase()
+        .as_str()
+    {
+        "wayland" => return SessionType::Wayland,
+        "x11" => return SessionType::X11,
+        _ => {}
+    }
+
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        SessionType::Wayland
+    } else if std::env::var_os("DISPLAY").is_some() {
+        SessionType::X11
+    } else {
+        SessionType::Unknown
+    }
+}
+
+fn find_in_path(binary: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(binary))
+        .find(|candidate| candidate.is_file())
+}
+
+pub fn available_backends() -> Vec<Backend> {
+    let preference: &[Backend] = match session_type() {
+        SessionType::X11 => &[Backend::Xdotool, Backend::Ydotool],
+        SessionType::Wayland => &[Backend::Wtype, Backend::Ydotool, Backend::Xdotool],
+        SessionType::Unknown => &[Backend::Ydotool],
+    };
+
+    preference
+        .iter()
+        .copied()
+        .filter(|backend| find_in_path(backend.binary()).is_some())
+        .collect()
+}
+
+pub fn missing_backend_hint() -> &'static str {
+    match session_type() {
+        SessionType::X11 => "Install xdotool to enable auto-type on X11.",
+        SessionType::Wayland => {
+            "Install wtype (wlroots compositors) or ydotool to enable auto-type on Wayland."
+        }
+        SessionType::Unknown => "Install ydotool to enable auto-type in this session.",
+    }
+}
+
+pub fn select_backend() -> Result<Backend> {
+    available_backends().into_iter().next().ok_or_else(|| {
+        Error::Internal(format!(
+            "No auto-type backend is available. {}",
+            missing_backend_hint()
+        ))
+    })
+}
+
+/// Text is piped through stdin so secrets never show up in the process list.
+pub async fn type_text(text: &str, delay_ms: u64) -> Result<()> {
+    let backend = select_backend()?;
+    let mut command = Command::new(backend.binary());
+    match backend {
+        Backend::Xdotool => {
+            command
+                .args(["type", "--clearmodifiers", "--delay"])
+                .arg(delay_ms.to_string())
+                .args(["--file", "-"]);
+        }
+        Backend::Wtype => {
+            command.arg("-d").arg(delay_ms.to_string()).arg("-");
+        }
+        Backend::Ydotool => {
+            command
+                .args(["type", "--key-delay"])
+                .arg(delay_ms.to_string())
+                .args(["--file", "-"]);
+        }
+    }
+
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| Error::Internal(format!("Failed to start {}: {}", backend.binary(), e)))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes()).await?;
+        stdin.shutdown().await?;
+    }
+
+    let status = child.wait().await?;
+    if !status.success() {
+        return Err(Error::Internal(format!(
+            "Auto-type failed: {} exited with {}",
+            backend.binary(),
+            status
+        )));
+    }
+    Ok(())
+}
+
+pub async fn press_key(key: SpecialKey) -> Result<()> {
+    let backend = select_backend()?;
+    let mut command = Command::new(backend.binary());
+    match (backend, key) {
+        (Backend::Xdotool, SpecialKey::Tab) => command.args(["key", "--clearmodifiers", "Tab"]),
+        (Backend::Xdotool, SpecialKey::Enter) => {
+            command.args(["key", "--clearmodifiers", "Return"])
+        }
+        (Backend::Wtype, SpecialKey::Tab) => command.args(["-k", "Tab"]),
+        (Backend::Wtype, SpecialKey::Enter) => command.args(["-k", "Return"]),
+        // Linux input event codes: KEY_TAB = 15, KEY_ENTER = 28.
+        (Backend::Ydotool, SpecialKey::Tab) => command.args(["key", "15:1", "15:0"]),
+        (Backend::Ydotool, SpecialKey::Enter) => command.args(["key", "28:1", "28:0"]),
+    };
+
+    let status = command
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+        .map_err(|e| Error::Internal(format!("Failed to start {}: {}", backend.binary(), e)))?;
+
+    if !status.success() {
+        return Err(Error::Internal(format!(
+            "Auto-type failed: {} exited with {}",
+            backend.binary(),
+            status
+        )));
+    }
+    Ok(())
+}
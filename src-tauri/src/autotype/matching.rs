@@ -0,0 +1,214 @@
+use crate::db::utils::{CryptoHelper, UnlockedVault};
+use crate::error::Result;
+use crate::state::AppState;
+use serde::Serialize;
+use sqlx::Row;
+use tauri::State;
+
+const SCORE_PATTERN: u32 = 100;
+const SCORE_TITLE: u32 = 60;
+const SCORE_DOMAIN: u32 = 40;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutotypeCandidate {
+    pub item_id: i64,
+    pub title: String,
+    pub username: Option<String>,
+    pub url: Option<String>,
+    pub score: u32,
+    pub matched_by: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutotypeCandidates {
+    pub window_title: Option<String>,
+    pub candidates: Vec<AutotypeCandidate>,
+}
+
+/// Case-insensitive glob match supporting `*` (any run) and `?` (any char),
+/// the same syntax KeePass uses for auto-type window titles.
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<usize> = None;
+    let mut star_t = 0;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some(p);
+            star_t = t;
+            p += 1;
+        } else if let Some(s) = star {
+            p = s + 1;
+            star_t += 1;
+            t = star_t;
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Scores how well an item matches `window_title`. Explicit patterns win over
+/// heuristics based on the item's title and URL.
+pub fn score_item(
+    window_title: &str,
+    title: &str,
+    url: Option<&str>,
+    patterns: &[String],
+) -> Option<(u32, &'static str)> {
+    let explicit = patterns
+        .iter()
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .filter(|p| wildcard_match(p, window_title))
+        .map(|p| p.chars().filter(|c| *c != '*' && *c != '?').count() as u32)
+        .max();
+    if let Some(specificity) = explicit {
+        // Longer literal patterns are more specific and rank higher.
+        return Some((SCORE_PATTERN + specificity.min(SCORE_PATTERN), "pattern"));
+    }
+
+    let window_lower = window_title.to_lowercase();
+    let title_lower = title.trim().to_lowercase();
+    if title_lower.len() > 2 && window_lower.contains(&title_lower) {
+        return Some((SCORE_TITLE, "title"));
+    }
+
+    if let Some(domain) = url.and_then(crate::security::extract_domain) {
+        let stem = domain.split('.').next().unwrap_or(&domain);
+        if window_lower.contains(&domain) || (stem.len() > 2 && window_lower.contains(stem)) {
+            return Some((SCORE_DOMAIN, "url"));
+        }
+    }
+
+    None
+}
+
+pub async fn find_candidates(
+    state: &AppState,
+    window_title: &str,
+) -> Result<Vec<AutotypeCandidate>> {
+    let vault = UnlockedVault::acquire(state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
+
+    let rows =
+        sqlx::query("SELECT id, title, username, url, autotype_patterns FROM password_items")
+            .fetch_all(vault.pool())
+            .await?;
+
+    let mut candidates = Vec::new();
+    for row in rows {
+        let title_enc: String = row.get("title");
+        let title = helper.decrypt(&title_enc)?;
+        let url = helper.decrypt_opt(row.get("url"))?;
+        let patterns: Vec<String> = helper
+            .decrypt_opt(row.get("autotype_patterns"))?
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
+        let Some((score, matched_by)) = score_item(window_title, &title, url.as_deref(), &patterns)
+        else {
+            continue;
+        };
+
+        candidates.push(AutotypeCandidate {
+            item_id: row.get("id"),
+            title,
+            username: helper.decrypt_opt(row.get("username"))?,
+            url,
+            score,
+            matched_by: matched_by.to_string(),
+        });
+    }
+
+    candidates.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.title.to_lowercase().cmp(&b.title.to_lowercase()))
+    });
+    Ok(candidates)
+}
+
+#[tauri::command]
+pub async fn get_autotype_candidates(state: State<'_, AppState>) -> Result<AutotypeCandidates> {
+    let window_title = super::window::active_window_title().await;
+    let candidates = match &window_title {
+        Some(title) => find_candidates(&state, title).await?,
+        None => {
+            UnlockedVault::acquire(&state).await?;
+            Vec::new()
+        }
+    };
+
+    Ok(AutotypeCandidates {
+        window_title,
+        candidates,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(list: &[&str]) -> Vec<String> {
+        list.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*GitHub*", "Sign in to github - Firefox"));
+        assert!(wildcard_match("Log?n", "LOGIN"));
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("a*b*c", "aXXbYYc"));
+        assert!(!wildcard_match("a*b*c", "aXXbYY"));
+        assert!(!wildcard_match("Log?n", "Logn"));
+        assert!(!wildcard_match("GitHub", "GitHub - Firefox"));
+    }
+
+    #[test]
+    fn test_patterns_beat_title_and_url() {
+        let window = "Sign in to GitHub - Mozilla Firefox";
+        let pattern = score_item(
+            window,
+            "GitHub",
+            Some("https://github.com"),
+            &patterns(&["*GitHub*"]),
+        );
+        assert_eq!(pattern, Some((SCORE_PATTERN + 6, "pattern")));
+        assert_eq!(
+            score_item(window, "GitHub", None, &[]),
+            Some((SCORE_TITLE, "title"))
+        );
+        assert_eq!(
+            score_item(window, "Work", Some("https://www.github.com/login"), &[]),
+            Some((SCORE_DOMAIN, "url"))
+        );
+        assert_eq!(score_item(window, "GitLab", Some("gitlab.com"), &[]), None);
+    }
+
+    #[test]
+    fn test_more_specific_patterns_rank_higher() {
+        let window = "Inbox - Mail";
+        let broad = score_item(window, "", None, &patterns(&["*Mail"])).unwrap();
+        let narrow = score_item(window, "", None, &patterns(&["Inbox*Mail"])).unwrap();
+        assert!(narrow.0 > broad.0);
+    }
+
+    #[test]
+    fn test_short_titles_are_ignored() {
+        assert_eq!(score_item("Gmail - Inbox", "Gm", None, &[]), None);
+        assert_eq!(
+            score_item("Anything", "Mail", None, &patterns(&["  "])),
+            None
+        );
+    }
+}
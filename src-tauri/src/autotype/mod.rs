@@ -0,0 +1,464 @@
+#[cfg(target_os = "linux")]
+mod linux;
+pub mod matching;
+pub mod window;
+
+pub use matching::*;
+
+use crate::db::utils::UnlockedVault;
+use crate::error::{Error, Result};
+use crate::state::AppState;
+use crate::types::PasswordItem;
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use zeroize::Zeroizing;
+
+pub const DEFAULT_AUTOTYPE_SEQUENCE: &str = "{USERNAME}{TAB}{PASSWORD}{ENTER}";
+const DEFAULT_START_DELAY_MS: u64 = 1500;
+const MAX_START_DELAY_MS: u64 = 30_000;
+const MAX_INLINE_DELAY_MS: u64 = 10_000;
+const KEYSTROKE_DELAY_MS: u64 = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecialKey {
+    Tab,
+    Enter,
+}
+
+pub enum AutotypeAction {
+    Text(Zeroizing<String>),
+    Key(SpecialKey),
+    Delay(u64),
+}
+
+/// Expands a KeePass-style sequence (`{USERNAME}{TAB}{PASSWORD}{ENTER}`) into
+/// the actions to type for `item`. Unknown placeholders are rejected so a typo
+/// never ends up typed verbatim into another application.
+pub fn expand_sequence(sequence: &str, item: &PasswordItem) -> Result<Vec<AutotypeAction>> {
+    let mut actions = Vec::new();
+    let mut literal = Zeroizing::new(String::new());
+    let mut chars = sequence.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '{' {
+            literal.push(c);
+            continue;
+        }
+
+        let mut placeholder = String::new();
+        let mut closed = false;
+        for inner in chars.by_ref() {
+            if inner == '}' {
+                closed = true;
+                break;
+            }
+            placeholder.push(inner);
+        }
+        if !closed {
+            return Err(Error::Validation(
+                "Auto-type sequence has an unterminated placeholder".to_string(),
+            ));
+        }
+
+        if !literal.is_empty() {
+            actions.push(AutotypeAction::Text(std::mem::take(&mut literal)));
+        }
+
+        let upper = placeholder.trim().to_uppercase();
+        match upper.as_str() {
+            "USERNAME" => actions.push(AutotypeAction::Text(Zeroizing::new(
+                item.username.clone().unwrap_or_default(),
+            ))),
+            "PASSWORD" => actions.push(AutotypeAction::Text(Zeroizing::new(
+                item.password.as_str().to_string(),
+            ))),
+            "URL" => actions.push(AutotypeAction::Text(Zeroizing::new(
+                item.url.clone().unwrap_or_default(),
+            ))),
+            "TITLE" => actions.push(AutotypeAction::Text(Zeroizing::new(item.title.clone()))),
+            "TOTP" => {
+                let secret = item.totp_secret.as_ref().ok_or_else(|| {
+                    Error::Validation("This item has no TOTP secret configured".to_string())
+                })?;
+                let code = crate::totp::build_totp(secret.as_str())?
+                    .generate_current()
+                    .map_err(|e| Error::Totp(e.to_string()))?;
+                actions.push(AutotypeAction::Text(Zeroizing::new(code)));
+            }
+            "TAB" => actions.push(AutotypeAction::Key(SpecialKey::Tab)),
+            "ENTER" => actions.push(AutotypeAction::Key(SpecialKey::Enter)),
+            other => {
+                let delay = other
+                    .strip_prefix("DELAY")
+                    .map(|ms| ms.trim().parse::<u64>())
+                    .ok_or_else(|| {
+                        Error::Validation(format!("Unknown auto-type placeholder {{{}}}", other))
+                    })?
+                    .map_err(|_| {
+                        Error::Validation(format!("Invalid auto-type delay {{{}}}", other))
+                    })?;
+                actions.push(AutotypeAction::Delay(delay.min(MAX_INLINE_DELAY_MS)));
+            }
+        }
+    }
+
+    if !literal.is_empty() {
+        actions.push(AutotypeAction::Text(literal));
+    }
+
+    Ok(actions)
+}
+
+pub async fn type_text(text: &str) -> Result<()> {
+    #[cfg(target_os = "windows")]
+    {
+        use windows::Win32::UI::Input::KeyboardAndMouse::*;
+
+        for unit in text.encode_utf16() {
+            let mut inputs = [INPUT::default(); 2];
+            inputs[0].r#type = INPUT_KEYBOARD;
+            inputs[0].Anonymous.ki = KEYBDINPUT {
+                wVk: VIRTUAL_KEY(0),
+                wScan: unit,
+                dwFlags: KEYEVENTF_UNICODE,
+                time: 0,
+                dwExtraInfo: 0,
+            };
+            inputs[1].r#type = INPUT_KEYBOARD;
+            inputs[1].Anonymous.ki = KEYBDINPUT {
+                wVk: VIRTUAL_KEY(0),
+                wScan: unit,
+                dwFlags: KEYEVENTF_UNICODE | KEYEVENTF_KEYUP,
+                time: 0,
+                dwExtraInfo: 0,
+            };
+
+            let sent = unsafe { SendInput(&inputs, std::mem::size_of::<INPUT>() as i32) };
+            if sent == 0 {
+                return Err(Error::Internal("Failed to send keyboard input".to_string()));
+            }
+            tokio::time::sleep(Duration::from_millis(KEYSTROKE_DELAY_MS)).await;
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        use core_graphics::event::{CGEvent, CGEventTapLocation};
+        use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
+
+        let source = CGEventSource::new(CGEventSourceStateID::CombinedSessionState)
+            .map_err(|_| Error::Internal("Failed to create event source".to_string()))?;
+
+        for c in text.chars() {
+            let mut utf16 = [0u16; 2];
+            let encoded = c.encode_utf16(&mut utf16);
+
+            let event_down = CGEvent::new_keyboard_event(source.clone(), 0, true)
+                .map_err(|_| Error::Internal("Failed to create keyboard event down".to_string()))?;
+            event_down.set_string_from_utf16_unchecked(encoded);
+            event_down.post(CGEventTapLocation::HID);
+
+            let event_up = CGEvent::new_keyboard_event(source.clone(), 0, false)
+                .map_err(|_| Error::Internal("Failed to create keyboard event up".to_string()))?;
+            event_up.set_string_from_utf16_unchecked(encoded);
+            event_up.post(CGEventTapLocation::HID);
+
+            tokio::time::sleep(Duration::from_millis(KEYSTROKE_DELAY_MS)).await;
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        linux::type_text(text, KEYSTROKE_DELAY_MS).await
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    {
+        let _ = text;
+        Err(Error::Internal(
+            "Autotype is not implemented for this OS".to_string(),
+        ))
+    }
+}
+
+pub async fn press_key(key: SpecialKey) -> Result<()> {
+    #[cfg(target_os = "windows")]
+    {
+        use windows::Win32::UI::Input::KeyboardAndMouse::*;
+
+        let vk = match key {
+            SpecialKey::Tab => VK_TAB,
+            SpecialKey::Enter => VK_RETURN,
+        };
+
+        let mut inputs = [INPUT::default(); 2];
+        inputs[0].r#type = INPUT_KEYBOARD;
+        inputs[0].Anonymous.ki = KEYBDINPUT {
+            wVk: vk,
+            wScan: 0,
+            dwFlags: KEYBD_EVENT_FLAGS(0),
+            time: 0,
+            dwExtraInfo: 0,
+        };
+        inputs[1].r#type = INPUT_KEYBOARD;
+        inputs[1].Anonymous.ki = KEYBDINPUT {
+            wVk: vk,
+            wScan: 0,
+            dwFlags: KEYEVENTF_KEYUP,
+            time: 0,
+            dwExtraInfo: 0,
+        };
+
+        let sent = unsafe { SendInput(&inputs, std::mem::size_of::<INPUT>() as i32) };
+        if sent == 0 {
+            return Err(Error::Internal("Failed to send keyboard input".to_string()));
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        use core_graphics::event::{CGEvent, CGEventTapLocation};
+        use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
+
+        let keycode = match key {
+            SpecialKey::Tab => 48,
+            SpecialKey::Enter => 36,
+        };
+
+        let source = CGEventSource::new(CGEventSourceStateID::CombinedSessionState)
+            .map_err(|_| Error::Internal("Failed to create event source".to_string()))?;
+        let event_down = CGEvent::new_keyboard_event(source.clone(), keycode, true)
+            .map_err(|_| Error::Internal("Failed to create key down event".to_string()))?;
+        event_down.post(CGEventTapLocation::HID);
+        let event_up = CGEvent::new_keyboard_event(source, keycode, false)
+            .map_err(|_| Error::Internal("Failed to create key up event".to_string()))?;
+        event_up.post(CGEventTapLocation::HID);
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        linux::press_key(key).await
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    {
+        let _ = key;
+        Err(Error::Internal(
+            "Autotype is not implemented for this OS".to_string(),
+        ))
+    }
+}
+
+pub async fn run_actions(actions: Vec<AutotypeAction>) -> Result<()> {
+    for action in actions {
+        match action {
+            AutotypeAction::Text(text) => type_text(text.as_str()).await?,
+            AutotypeAction::Key(key) => press_key(key).await?,
+            AutotypeAction::Delay(ms) => tokio::time::sleep(Duration::from_millis(ms)).await,
+        }
+    }
+    Ok(())
+}
+
+/// Hides Pulsar so the previously focused window regains focus before typing.
+fn release_focus(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.minimize();
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutotypeCapabilities {
+    pub platform: String,
+    pub session_type: Option<String>,
+    pub backend: Option<String>,
+    pub available_backends: Vec<String>,
+    pub supported: bool,
+    pub message: Option<String>,
+}
+
+#[tauri::command]
+pub fn get_autotype_capabilities() -> AutotypeCapabilities {
+    #[cfg(target_os = "linux")]
+    {
+        let available: Vec<String> = linux::available_backends()
+            .iter()
+            .map(|b| b.binary().to_string())
+            .collect();
+        let message = available
+            .is_empty()
+            .then(|| linux::missing_backend_hint().to_string());
+        AutotypeCapabilities {
+            platform: "linux".to_string(),
+            session_type: Some(linux::session_type().as_str().to_string()),
+            backend: available.first().cloned(),
+            supported: !available.is_empty(),
+            available_backends: available,
+            message,
+        }
+    }
+
+    #[cfg(any(target_os = "windows", target_os = "macos"))]
+    {
+        let backend = if cfg!(target_os = "windows") {
+            "sendinput"
+        } else {
+            "cgevent"
+        };
+        AutotypeCapabilities {
+            platform: std::env::consts::OS.to_string(),
+            session_type: None,
+            backend: Some(backend.to_string()),
+            available_backends: vec![backend.to_string()],
+            supported: true,
+            message: None,
+        }
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    {
+        AutotypeCapabilities {
+            platform: std::env::consts::OS.to_string(),
+            session_type: None,
+            backend: None,
+            available_backends: Vec::new(),
+            supported: false,
+            message: Some("Autotype is not implemented for this OS".to_string()),
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn autotype_item(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    item_id: i64,
+    sequence: Option<String>,
+    start_delay_ms: Option<u64>,
+) -> Result<()> {
+    crate::limits::check_opt_len(
+        "Auto-type sequence",
+        sequence.as_deref(),
+        crate::limits::MAX_AUTOTYPE_SEQUENCE_LEN,
+    )?;
+    let vault = UnlockedVault::acquire(&state).await?;
+    let item = crate::db::get_password_item_by_id(state.clone(), item_id)
+        .await?
+        .ok_or_else(|| Error::Internal("Password item not found.".to_string()))?;
+
+    let sequence = sequence
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_AUTOTYPE_SEQUENCE.to_string());
+    let actions = expand_sequence(&sequence, &item)?;
+    drop(item);
+
+    release_focus(&app);
+    let delay = start_delay_ms
+        .unwrap_or(DEFAULT_START_DELAY_MS)
+        .min(MAX_START_DELAY_MS);
+    tokio::time::sleep(Duration::from_millis(delay)).await;
+
+    run_actions(actions).await?;
+
+    let _ = crate::db::activity::log_secret_access(
+        &app,
+        vault.pool(),
+        vault.key(),
+        "autotype",
+        Some(item_id),
+        None,
+        Some("Credentials were auto-typed"),
+    )
+    .await;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::SecretString;
+
+    fn item() -> PasswordItem {
+        PasswordItem {
+            id: 1,
+            category: "login".to_string(),
+            title: "Example".to_string(),
+            description: None,
+            img: None,
+            tags: None,
+            username: Some("alice".to_string()),
+            url: Some("https://example.com".to_string()),
+            notes: None,
+            password: SecretString::new("hunter2".to_string()),
+            created_at: String::new(),
+            updated_at: String::new(),
+            color: None,
+            totp_secret: None,
+            custom_fields: Vec::new(),
+            field_order: None,
+            attachments: None,
+            autotype_patterns: None,
+            details: None,
+            clear_details: false,
+            read_only: false,
+        }
+    }
+
+    fn describe(sequence: &str) -> Result<Vec<String>> {
+        Ok(expand_sequence(sequence, &item())?
+            .iter()
+            .map(|action| match action {
+                AutotypeAction::Text(text) => format!("text:{}", text.as_str()),
+                AutotypeAction::Key(key) => format!("key:{:?}", key),
+                AutotypeAction::Delay(ms) => format!("delay:{}", ms),
+            })
+            .collect())
+    }
+
+    #[test]
+    fn test_default_sequence() {
+        assert_eq!(
+            describe(DEFAULT_AUTOTYPE_SEQUENCE).unwrap(),
+            ["text:alice", "key:Tab", "text:hunter2", "key:Enter"]
+        );
+    }
+
+    #[test]
+    fn test_literals_and_placeholders() {
+        assert_eq!(
+            describe("user: {username }!{ url}{title}").unwrap(),
+            [
+                "text:user: ",
+                "text:alice",
+                "text:!",
+                "text:https://example.com",
+                "text:Example"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_delays_are_capped() {
+        assert_eq!(
+            describe("{DELAY 250}{DELAY 999999}").unwrap(),
+            [
+                "delay:250",
+                format!("delay:{}", MAX_INLINE_DELAY_MS).as_str()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_sequences_are_rejected() {
+        for sequence in ["{PASSWORD", "{PASWORD}", "{DELAY x}", "{TOTP}"] {
+            assert!(describe(sequence).is_err(), "{sequence}");
+        }
+    }
+}
@@ -0,0 +1,79 @@
+/// Returns the title of the window that currently has keyboard focus, or
+/// `None` when it cannot be determined (e.g. on Wayland, which does not expose
+/// other clients' windows).
+pub async fn active_window_title() -> Option<String> {
+    #[cfg(target_os = "windows")]
+    {
+        use windows::Win32::UI::WindowsAndMessaging::{
+            GetForegroundWindow, GetWindowTextLengthW, GetWindowTextW,
+        };
+
+        unsafe {
+            let hwnd = GetForegroundWindow();
+            if hwnd.0.is_null() {
+                return None;
+            }
+            let len = GetWindowTextLengthW(hwnd);
+            if len <= 0 {
+                return None;
+            }
+            let mut buffer = vec![0u16; len as usize + 1];
+            let copied = GetWindowTextW(hwnd, &mut buffer);
+            if copied <= 0 {
+                return None;
+            }
+            Some(String::from_utf16_lossy(&buffer[..copied as usize]))
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        const SCRIPT: &str = r#"tell application "System Events"
+    set frontApp to first application process whose frontmost is true
+    set appName to name of frontApp
+    try
+        set winName to name of front window of frontApp
+    on error
+        set winName to ""
+    end try
+end tell
+if winName is "" then
+    return appName
+end if
+return winName & " - " & appName"#;
+
+        let output = tokio::process::Command::new("osascript")
+            .args(["-e", SCRIPT])
+            .output()
+            .await
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let title = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (!title.is_empty()).then_some(title)
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        if super::linux::session_type() == super::linux::SessionType::Wayland {
+            return None;
+        }
+
+        let output = tokio::process::Command::new("xdotool")
+            .args(["getactivewindow", "getwindowname"])
+            .output()
+            .await
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let title = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (!title.is_empty()).then_some(title)
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    {
+        None
+    }
+}
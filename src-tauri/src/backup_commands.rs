@@ -1,7 +1,13 @@
+use crate::auth::reverify::{self, SensitiveOperation};
 use crate::auth::verify_master_password_internal;
-use crate::db::{get_buttons_impl, get_password_items_impl, get_recipient_keys_impl};
+use crate::db::utils::UnlockedVault;
+use crate::db::{
+    get_buttons_impl, get_palettes_impl, get_password_items_impl, get_recipient_keys_impl,
+    get_templates_impl,
+};
 use crate::encryption::{decrypt, decrypt_bytes, encrypt, encrypt_bytes};
 use crate::error::{Error, Result};
+use crate::limits;
 use crate::state::AppState;
 use crate::types::{ExportPayload, VaultBackupAttachment, VaultBackupSnapshot};
 use crate::utils::write_sensitive_bytes;
@@ -25,16 +31,6 @@ use tauri_plugin_dialog::DialogExt;
 use tokio::sync::oneshot;
 use zeroize::{Zeroize, Zeroizing};
 
-async fn get_key(state: &State<'_, AppState>) -> Result<Zeroizing<Vec<u8>>> {
-    let guard = state.key.lock().await;
-    guard.clone().ok_or(Error::VaultLocked)
-}
-
-async fn get_db_pool(state: &State<'_, AppState>) -> Result<SqlitePool> {
-    let guard = state.db.lock().await;
-    guard.clone().ok_or(Error::VaultNotLoaded)
-}
-
 async fn get_attachments_snapshot(
     db_pool: &SqlitePool,
     key: &[u8],
@@ -96,13 +92,21 @@ pub async fn export_vault_backend(
         ));
     }
 
-    let key = get_key(&state).await?;
-    let db_pool = get_db_pool(&state).await?;
+    let vault = UnlockedVault::acquire(&state).await?;
+    // Leaves the vault file self-contained, so a copy of it taken along with
+    // this backup does not depend on the -wal file. Fails harmlessly on
+    // read-only storage.
+    if !crate::storage::is_read_only() {
+        let _ = crate::db::wal::checkpoint(vault.pool(), crate::db::wal::CheckpointMode::Truncate)
+            .await;
+    }
 
-    let password_items = get_password_items_impl(&db_pool, key.as_slice()).await?;
-    let buttons = get_buttons_impl(&db_pool, key.as_slice()).await?;
-    let recipient_keys = get_recipient_keys_impl(&db_pool, key.as_slice()).await?;
-    let attachments = get_attachments_snapshot(&db_pool, key.as_slice()).await?;
+    let password_items = get_password_items_impl(vault.pool(), vault.key()).await?;
+    let buttons = get_buttons_impl(vault.pool(), vault.key()).await?;
+    let recipient_keys = get_recipient_keys_impl(vault.pool(), vault.key()).await?;
+    let attachments = get_attachments_snapshot(vault.pool(), vault.key()).await?;
+    let palettes = get_palettes_impl(vault.pool()).await?;
+    let templates = get_templates_impl(vault.pool(), vault.key()).await?;
 
     let snapshot = VaultBackupSnapshot {
         version: 1,
@@ -111,6 +115,8 @@ pub async fn export_vault_backend(
         buttons,
         recipient_keys,
         attachments,
+        palettes,
+        templates,
     };
 
     let vault_data = serde_json::to_string(&snapshot)?;
@@ -175,6 +181,7 @@ pub async fn export_vault_backend(
         salt_b64: general_purpose::STANDARD.encode(salt),
         nonce_b64: general_purpose::STANDARD.encode(nonce),
         ciphertext_b64: general_purpose::STANDARD.encode(&ciphertext),
+        expires_at: None,
     };
 
     let export_bytes = serde_json::to_vec_pretty(&export)?;
@@ -182,8 +189,8 @@ pub async fn export_vault_backend(
     write_sensitive_bytes(&path, &export_bytes).await?;
 
     let _ = crate::db::activity::log_activity_impl(
-        &db_pool,
-        key.as_slice(),
+        vault.pool(),
+        vault.key(),
         "vault_exported",
         None,
         None,
@@ -197,11 +204,13 @@ pub async fn export_vault_backend(
 #[command]
 pub async fn export_vault(
     app_handle: AppHandle,
+    state: State<'_, AppState>,
     vault_data: String,
     passphrase: Option<String>,
     is_plaintext: Option<bool>,
     destination: Option<String>,
 ) -> Result<String> {
+    reverify::require_fresh(&state, SensitiveOperation::ExportVault).await?;
     let is_plaintext = is_plaintext.unwrap_or(false);
     if is_plaintext && !cfg!(debug_assertions) {
         return Err(Error::Validation(
@@ -285,6 +294,7 @@ pub async fn export_vault(
         salt_b64: general_purpose::STANDARD.encode(salt),
         nonce_b64: general_purpose::STANDARD.encode(nonce),
         ciphertext_b64: general_purpose::STANDARD.encode(&ciphertext),
+        expires_at: None,
     };
 
     let export_bytes = serde_json::to_vec_pretty(&export)?;
@@ -332,6 +342,7 @@ pub async fn import_vault(
         ));
     }
 
+    limits::check_file_size("Backup file", &path, limits::MAX_IMPORT_FILE_SIZE).await?;
     let file_content_bytes = tokio::fs::read(&path).await?;
 
     let payload: ExportPayload = serde_json::from_slice(&file_content_bytes).map_err(|_| {
@@ -422,6 +433,7 @@ pub async fn restore_vault_backend(
         }
     };
 
+    limits::check_file_size("Backup file", &path, limits::MAX_IMPORT_FILE_SIZE).await?;
     let file_content_bytes = tokio::fs::read(&path).await?;
 
     let decrypted_json = if path.extension().and_then(|s| s.to_str()) == Some("json") {
@@ -485,11 +497,10 @@ pub async fn restore_vault_backend(
 
     restore_vault_snapshot(state.clone(), snapshot.clone()).await?;
 
-    let key = get_key(&state).await?;
-    let db_pool = get_db_pool(&state).await?;
+    let vault = UnlockedVault::acquire_writable(&state).await?;
     let _ = crate::db::activity::log_activity_impl(
-        &db_pool,
-        key.as_slice(),
+        vault.pool(),
+        vault.key(),
         "vault_restored",
         None,
         None,
@@ -512,9 +523,31 @@ pub async fn restore_vault_snapshot(
         )));
     }
 
-    let key = get_key(&state).await?;
-    let db_pool = get_db_pool(&state).await?;
-    let mut tx = db_pool.begin().await?;
+    for item in &snapshot.password_items {
+        limits::check_password_item(item)?;
+    }
+    for template in &snapshot.templates {
+        limits::check_len("Template name", &template.name, limits::MAX_TITLE_LEN)?;
+        limits::check_len("Category", &template.category, limits::MAX_SHORT_FIELD_LEN)?;
+        limits::check_custom_fields(&template.custom_fields, template.field_order.as_deref())?;
+    }
+    for attachment in &snapshot.attachments {
+        limits::check_len(
+            "Attachment name",
+            &attachment.file_name,
+            limits::MAX_ATTACHMENT_NAME_LEN,
+        )?;
+        // Base64 inflates the payload by a third.
+        limits::check_len(
+            "Attachment",
+            &attachment.data_b64,
+            (limits::MAX_ATTACHMENT_SIZE as usize / 3 + 1) * 4,
+        )?;
+    }
+
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    state.clear_overview_cache();
+    let mut tx = vault.pool().begin().await?;
 
     sqlx::query("DELETE FROM password_items")
         .execute(&mut *tx)
@@ -526,8 +559,20 @@ pub async fn restore_vault_snapshot(
     sqlx::query("DELETE FROM attachments")
         .execute(&mut *tx)
         .await?;
+    sqlx::query("DELETE FROM palettes")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM templates")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM item_conflicts")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM note_history")
+        .execute(&mut *tx)
+        .await?;
     if let Err(e) = sqlx::query(
-        "DELETE FROM sqlite_sequence WHERE name IN ('password_items', 'buttons', 'recipient_keys', 'attachments')",
+        "DELETE FROM sqlite_sequence WHERE name IN ('password_items', 'buttons', 'recipient_keys', 'attachments', 'palettes', 'templates')",
     )
     .execute(&mut *tx)
     .await
@@ -539,57 +584,71 @@ pub async fn restore_vault_snapshot(
     }
 
     for item in &snapshot.password_items {
-        let title_enc = encrypt(&item.title, key.as_slice())?;
+        let title_enc = encrypt(&item.title, vault.key())?;
         let description_enc = item
             .description
             .as_deref()
-            .map(|value| encrypt(value, key.as_slice()))
+            .map(|value| encrypt(value, vault.key()))
             .transpose()?;
         let img_enc = item
             .img
             .as_deref()
-            .map(|value| encrypt(value, key.as_slice()))
+            .map(|value| encrypt(value, vault.key()))
             .transpose()?;
         let tags_enc = item
             .tags
             .as_deref()
-            .map(|value| encrypt(value, key.as_slice()))
+            .map(|value| encrypt(value, vault.key()))
             .transpose()?;
         let username_enc = item
             .username
             .as_deref()
-            .map(|value| encrypt(value, key.as_slice()))
+            .map(|value| encrypt(value, vault.key()))
             .transpose()?;
         let url_enc = item
             .url
             .as_deref()
-            .map(|value| encrypt(value, key.as_slice()))
+            .map(|value| encrypt(value, vault.key()))
             .transpose()?;
         let notes_enc = item
             .notes
             .as_deref()
-            .map(|value| encrypt(value, key.as_slice()))
+            .map(|value| encrypt(value, vault.key()))
             .transpose()?;
-        let password_enc = encrypt(item.password.as_str(), key.as_slice())?;
+        let password_enc = encrypt(item.password.as_str(), vault.key())?;
         let totp_secret_enc = item
             .totp_secret
             .as_ref()
-            .map(|value| encrypt(value.as_str(), key.as_slice()))
+            .map(|value| encrypt(value.as_str(), vault.key()))
             .transpose()?;
         let custom_fields_json = serde_json::to_string(&item.custom_fields)?;
-        let custom_fields_enc = encrypt(&custom_fields_json, key.as_slice())?;
+        let custom_fields_enc = encrypt(&custom_fields_json, vault.key())?;
         let field_order_json = item
             .field_order
             .as_ref()
             .map(serde_json::to_string)
             .transpose()?;
         let field_order_enc = field_order_json
-            .map(|value| encrypt(&value, key.as_slice()))
+            .map(|value| encrypt(&value, vault.key()))
+            .transpose()?;
+        let autotype_patterns_enc = item
+            .autotype_patterns
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()?
+            .map(|value| encrypt(&value, vault.key()))
+            .transpose()?;
+        let details_enc = item
+            .details
+            .as_ref()
+            .map(|d| serde_json::to_string(d).map(Zeroizing::new))
+            .transpose()?
+            .map(|value| encrypt(&value, vault.key()))
             .transpose()?;
 
-        let category_enc = encrypt(&item.category, key.as_slice())?;
+        let category_enc = encrypt(&item.category, vault.key())?;
 
-        sqlx::query("INSERT INTO password_items (id, category, title, description, img, tags, username, url, notes, password, created_at, updated_at, color, totp_secret, custom_fields, field_order) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)")
+        sqlx::query("INSERT INTO password_items (id, category, title, description, img, tags, username, url, notes, password, created_at, updated_at, color, totp_secret, custom_fields, field_order, autotype_patterns, details, read_only) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)")
             .bind(item.id)
             .bind(category_enc)
             .bind(title_enc)
@@ -606,14 +665,17 @@ pub async fn restore_vault_snapshot(
             .bind(totp_secret_enc)
             .bind(custom_fields_enc)
             .bind(field_order_enc)
+            .bind(autotype_patterns_enc)
+            .bind(details_enc)
+            .bind(item.read_only)
             .execute(&mut *tx)
             .await?;
     }
 
     for button in &snapshot.buttons {
-        let text_enc = encrypt(&button.text, key.as_slice())?;
-        let icon_enc = encrypt(&button.icon, key.as_slice())?;
-        let color_enc = encrypt(&button.color, key.as_slice())?;
+        let text_enc = encrypt(&button.text, vault.key())?;
+        let icon_enc = encrypt(&button.icon, vault.key())?;
+        let color_enc = encrypt(&button.color, vault.key())?;
 
         sqlx::query("INSERT INTO buttons (id, text, icon, color) VALUES (?, ?, ?, ?)")
             .bind(button.id)
@@ -624,10 +686,19 @@ pub async fn restore_vault_snapshot(
             .await?;
     }
 
+    // Parents are linked once every tag exists, so snapshot order doesn't matter.
+    for button in snapshot.buttons.iter().filter(|b| b.parent_id.is_some()) {
+        sqlx::query("UPDATE buttons SET parent_id = ? WHERE id = ?")
+            .bind(button.parent_id)
+            .bind(button.id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
     for recipient in &snapshot.recipient_keys {
-        let name_enc = encrypt(&recipient.name, key.as_slice())?;
-        let public_key_enc = encrypt(&recipient.public_key, key.as_slice())?;
-        let private_key_enc = encrypt(&recipient.private_key, key.as_slice())?;
+        let name_enc = encrypt(&recipient.name, vault.key())?;
+        let public_key_enc = encrypt(&recipient.public_key, vault.key())?;
+        let private_key_enc = encrypt(&recipient.private_key, vault.key())?;
 
         sqlx::query(
             "INSERT INTO recipient_keys (id, name, public_key, private_key) VALUES (?, ?, ?, ?)",
@@ -641,12 +712,12 @@ pub async fn restore_vault_snapshot(
     }
 
     for attachment in &snapshot.attachments {
-        let file_name_enc = encrypt(&attachment.file_name, key.as_slice())?;
-        let mime_type_enc = encrypt(&attachment.mime_type, key.as_slice())?;
+        let file_name_enc = encrypt(&attachment.file_name, vault.key())?;
+        let mime_type_enc = encrypt(&attachment.mime_type, vault.key())?;
         let data = general_purpose::STANDARD
             .decode(&attachment.data_b64)
             .map_err(|e| Error::Internal(format!("Invalid attachment data: {}", e)))?;
-        let data_enc = encrypt_bytes(&data, key.as_slice())?;
+        let data_enc = encrypt_bytes(&data, vault.key())?;
 
         sqlx::query("INSERT INTO attachments (id, item_id, file_name, file_size, mime_type, data, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)")
             .bind(attachment.id)
@@ -660,6 +731,41 @@ pub async fn restore_vault_snapshot(
             .await?;
     }
 
+    for palette in &snapshot.palettes {
+        sqlx::query("INSERT INTO palettes (id, name, colors) VALUES (?, ?, ?)")
+            .bind(palette.id)
+            .bind(&palette.name)
+            .bind(serde_json::to_string(&palette.colors)?)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    for template in &snapshot.templates {
+        let field_order_enc = template
+            .field_order
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()?
+            .map(|value| encrypt(&value, vault.key()))
+            .transpose()?;
+        sqlx::query(
+            "INSERT INTO templates (id, name, category, custom_fields, field_order, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(template.id)
+        .bind(encrypt(&template.name, vault.key())?)
+        .bind(encrypt(&template.category, vault.key())?)
+        .bind(encrypt(
+            &serde_json::to_string(&template.custom_fields)?,
+            vault.key(),
+        )?)
+        .bind(field_order_enc)
+        .bind(&template.created_at)
+        .bind(&template.updated_at)
+        .execute(&mut *tx)
+        .await?;
+    }
+
     tx.commit().await?;
+    crate::auth::members::refresh_member_keys(&state).await;
     Ok(())
 }
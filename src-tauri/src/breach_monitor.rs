@@ -0,0 +1,511 @@
+//! Opt-in background breach monitoring. Stored passwords are checked against
+//! Have I Been Pwned's Pwned Passwords range API, which only ever sees the
+//! first five characters of each password's SHA-1 hash (k-anonymity). With an
+//! HIBP API key, item usernames are also checked against the breached-account
+//! API. Results are kept per item, encrypted, and items newly found in a
+//! breach are reported through an event and the activity log.
+
+use crate::db::utils::UnlockedVault;
+use crate::encryption::{decrypt, encrypt};
+use crate::error::{Error, Result};
+use crate::limits;
+use crate::state::AppState;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use sqlx::{Row, SqlitePool};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use zeroize::Zeroizing;
+
+const CONFIG_KEY: &str = "breach_monitor";
+const DEFAULT_INTERVAL_HOURS: u32 = 24;
+const MIN_INTERVAL_HOURS: u32 = 6;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+/// The smallest HIBP subscription allows 10 breached-account requests a
+/// minute.
+const ACCOUNT_REQUEST_INTERVAL: Duration = Duration::from_millis(6500);
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(10);
+const PWNED_PASSWORDS_URL: &str = "https://api.pwnedpasswords.com/range/";
+const BREACHED_ACCOUNT_URL: &str = "https://haveibeenpwned.com/api/v3/breachedaccount/";
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BreachMonitorConfig {
+    pub enabled: bool,
+    pub interval_hours: u32,
+    /// Also check usernames; needs an HIBP API key.
+    pub check_accounts: bool,
+}
+
+impl Default for BreachMonitorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_hours: DEFAULT_INTERVAL_HOURS,
+            check_accounts: false,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BreachMonitorStatus {
+    pub config: BreachMonitorConfig,
+    pub has_api_key: bool,
+    pub last_run: Option<String>,
+}
+
+/// What is persisted, encrypted, in the vault's configuration table.
+#[derive(Serialize, Deserialize, Default)]
+struct StoredBreachMonitor {
+    config: BreachMonitorConfig,
+    #[serde(default)]
+    api_key: Option<String>,
+    #[serde(default)]
+    last_run: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BreachFindings {
+    /// How often the password appears in Pwned Passwords.
+    pub password_seen_count: u64,
+    /// Names of the breaches the item's username appears in.
+    pub account_breaches: Vec<String>,
+}
+
+impl BreachFindings {
+    pub(crate) fn is_breached(&self) -> bool {
+        self.password_seen_count > 0 || !self.account_breaches.is_empty()
+    }
+
+    /// Whether this adds a breach that `previous` did not know about.
+    fn is_new(&self, previous: Option<&BreachFindings>) -> bool {
+        let Some(previous) = previous else {
+            return self.is_breached();
+        };
+        (self.password_seen_count > 0 && previous.password_seen_count == 0)
+            || self
+                .account_breaches
+                .iter()
+                .any(|name| !previous.account_breaches.contains(name))
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemBreachResult {
+    pub item_id: i64,
+    #[serde(flatten)]
+    pub findings: BreachFindings,
+    pub checked_at: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BreachCheckSummary {
+    pub checked_items: usize,
+    pub breached_items: usize,
+    pub newly_breached: Vec<i64>,
+}
+
+#[derive(Deserialize)]
+struct BreachName {
+    #[serde(rename = "Name")]
+    name: String,
+}
+
+/// What is needed of an item for the check, so decrypted passwords are not
+/// held while waiting on the network.
+struct ItemToCheck {
+    id: i64,
+    password_sha1: Option<String>,
+    account: Option<String>,
+}
+
+async fn load_stored(pool: &SqlitePool, key: &[u8]) -> Result<Option<StoredBreachMonitor>> {
+    let value: Option<String> = sqlx::query_scalar("SELECT value FROM configuration WHERE key = ?")
+        .bind(CONFIG_KEY)
+        .fetch_optional(pool)
+        .await?;
+    match value {
+        Some(enc) => {
+            let json = Zeroizing::new(decrypt(&enc, key)?);
+            Ok(Some(serde_json::from_str(&json)?))
+        }
+        None => Ok(None),
+    }
+}
+
+async fn save_stored(pool: &SqlitePool, key: &[u8], stored: &StoredBreachMonitor) -> Result<()> {
+    let json = Zeroizing::new(serde_json::to_string(stored)?);
+    sqlx::query("INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)")
+        .bind(CONFIG_KEY)
+        .bind(encrypt(&json, key)?)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub(crate) async fn load_results(pool: &SqlitePool, key: &[u8]) -> Result<Vec<ItemBreachResult>> {
+    let rows = sqlx::query("SELECT item_id, data, checked_at FROM breach_results ORDER BY item_id")
+        .fetch_all(pool)
+        .await?;
+    rows.into_iter()
+        .map(|row| {
+            let data: String = row.get("data");
+            Ok(ItemBreachResult {
+                item_id: row.get("item_id"),
+                findings: serde_json::from_str(&decrypt(&data, key)?)?,
+                checked_at: row.get("checked_at"),
+            })
+        })
+        .collect()
+}
+
+fn http_client() -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .https_only(true)
+        .timeout(REQUEST_TIMEOUT)
+        .user_agent(concat!("Pulsar/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| Error::Network(e.to_string()))
+}
+
+fn sha1_hex(password: &str) -> String {
+    hex::encode_upper(Sha1::digest(password.as_bytes()))
+}
+
+/// Hash suffixes under `prefix` with how often each was seen. Padding
+/// entries, which have a count of zero, are dropped.
+async fn fetch_range(client: &reqwest::Client, prefix: &str) -> Result<HashMap<String, u64>> {
+    let response = client
+        .get(format!("{PWNED_PASSWORDS_URL}{prefix}"))
+        .header("Add-Padding", "true")
+        .send()
+        .await
+        .map_err(|e| Error::Network(e.to_string()))?;
+    if !response.status().is_success() {
+        return Err(Error::Network(format!(
+            "Pwned Passwords returned {}",
+            response.status()
+        )));
+    }
+    let body = response
+        .text()
+        .await
+        .map_err(|e| Error::Network(e.to_string()))?;
+    Ok(body
+        .lines()
+        .filter_map(|line| {
+            let (suffix, count) = line.trim().split_once(':')?;
+            let count = count.parse::<u64>().ok().filter(|c| *c > 0)?;
+            Some((suffix.to_string(), count))
+        })
+        .collect())
+}
+
+fn retry_after(response: &reqwest::Response) -> Duration {
+    response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_RETRY_AFTER)
+}
+
+async fn fetch_account_breaches(
+    client: &reqwest::Client,
+    api_key: &str,
+    account: &str,
+) -> Result<Vec<String>> {
+    let mut url = url::Url::parse(BREACHED_ACCOUNT_URL)
+        .map_err(|e| Error::Internal(format!("Invalid HIBP URL: {}", e)))?;
+    url.path_segments_mut()
+        .map_err(|_| Error::Internal("Invalid HIBP URL".to_string()))?
+        .pop_if_empty()
+        .push(account);
+    url.query_pairs_mut()
+        .append_pair("truncateResponse", "true");
+
+    for _ in 0..2 {
+        let response = client
+            .get(url.clone())
+            .header("hibp-api-key", api_key)
+            .send()
+            .await
+            .map_err(|e| Error::Network(e.to_string()))?;
+        match response.status().as_u16() {
+            404 => return Ok(Vec::new()),
+            429 => tokio::time::sleep(retry_after(&response)).await,
+            401 => {
+                return Err(Error::Validation(
+                    "The HIBP API key was rejected.".to_string(),
+                ))
+            }
+            _ if response.status().is_success() => {
+                let body = response
+                    .bytes()
+                    .await
+                    .map_err(|e| Error::Network(e.to_string()))?;
+                let breaches: Vec<BreachName> = serde_json::from_slice(&body)?;
+                return Ok(breaches.into_iter().map(|b| b.name).collect());
+            }
+            status => {
+                return Err(Error::Network(format!(
+                    "HIBP breached-account API returned {}",
+                    status
+                )))
+            }
+        }
+    }
+    Err(Error::Network(
+        "HIBP rate limit exceeded; try again later.".to_string(),
+    ))
+}
+
+async fn items_to_check(vault: &UnlockedVault, accounts: bool) -> Result<Vec<ItemToCheck>> {
+    let items = crate::db::get_password_audit_data_impl(vault.pool(), vault.key()).await?;
+    Ok(items
+        .iter()
+        .map(|item| {
+            let password = item.password.as_str();
+            ItemToCheck {
+                id: item.id,
+                password_sha1: (!password.is_empty() && password != "N/A")
+                    .then(|| sha1_hex(password)),
+                account: item
+                    .username
+                    .as_deref()
+                    .map(|u| u.trim().to_lowercase())
+                    .filter(|u| accounts && !u.is_empty()),
+            }
+        })
+        .collect())
+}
+
+pub(crate) async fn run_check(app: &AppHandle, state: &AppState) -> Result<BreachCheckSummary> {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return Err(Error::Validation(
+            "A breach check is already running.".to_string(),
+        ));
+    }
+    let result = run_check_inner(app, state).await;
+    RUNNING.store(false, Ordering::SeqCst);
+    result
+}
+
+async fn run_check_inner(app: &AppHandle, state: &AppState) -> Result<BreachCheckSummary> {
+    let (stored, items) = {
+        let vault = UnlockedVault::acquire(state).await?;
+        let stored = load_stored(vault.pool(), vault.key())
+            .await?
+            .unwrap_or_default();
+        let api_key = stored.api_key.as_deref().filter(|k| !k.is_empty());
+        let accounts = stored.config.check_accounts && api_key.is_some();
+        let items = items_to_check(&vault, accounts).await?;
+        (stored, items)
+    };
+
+    let client = http_client()?;
+    let mut ranges: HashMap<String, HashMap<String, u64>> = HashMap::new();
+    for hash in items.iter().filter_map(|i| i.password_sha1.as_deref()) {
+        let prefix = &hash[..5];
+        if !ranges.contains_key(prefix) {
+            ranges.insert(prefix.to_string(), fetch_range(&client, prefix).await?);
+        }
+    }
+
+    let mut account_breaches: HashMap<String, Vec<String>> = HashMap::new();
+    if let Some(api_key) = stored.api_key.as_deref() {
+        let accounts: HashSet<&str> = items.iter().filter_map(|i| i.account.as_deref()).collect();
+        for (n, account) in accounts.into_iter().enumerate() {
+            if n > 0 {
+                tokio::time::sleep(ACCOUNT_REQUEST_INTERVAL).await;
+            }
+            let breaches = fetch_account_breaches(&client, api_key, account).await?;
+            account_breaches.insert(account.to_string(), breaches);
+        }
+    }
+
+    let vault = UnlockedVault::acquire_writable(state).await?;
+    let previous: HashMap<i64, BreachFindings> = load_results(vault.pool(), vault.key())
+        .await?
+        .into_iter()
+        .map(|r| (r.item_id, r.findings))
+        .collect();
+    let now = Utc::now().to_rfc3339();
+    let mut breached_items = 0;
+    let mut newly_breached = Vec::new();
+
+    let mut tx = vault.pool().begin().await?;
+    for item in &items {
+        let findings = BreachFindings {
+            password_seen_count: item
+                .password_sha1
+                .as_deref()
+                .and_then(|hash| ranges.get(&hash[..5])?.get(&hash[5..]).copied())
+                .unwrap_or(0),
+            account_breaches: item
+                .account
+                .as_deref()
+                .and_then(|a| account_breaches.get(a).cloned())
+                .unwrap_or_default(),
+        };
+        if findings.is_breached() {
+            breached_items += 1;
+        }
+        if findings.is_new(previous.get(&item.id)) {
+            newly_breached.push(item.id);
+        }
+        sqlx::query(
+            "INSERT OR REPLACE INTO breach_results (item_id, data, checked_at) VALUES (?, ?, ?)",
+        )
+        .bind(item.id)
+        .bind(encrypt(&serde_json::to_string(&findings)?, vault.key())?)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await?;
+    }
+    sqlx::query("DELETE FROM breach_results WHERE item_id NOT IN (SELECT id FROM password_items)")
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    let mut stored = load_stored(vault.pool(), vault.key())
+        .await?
+        .unwrap_or(stored);
+    stored.last_run = Some(now);
+    save_stored(vault.pool(), vault.key(), &stored).await?;
+
+    if !newly_breached.is_empty() {
+        let details = format!("{} item(s) found in new breaches", newly_breached.len());
+        let _ = crate::db::activity::log_activity_impl(
+            vault.pool(),
+            vault.key(),
+            "breach_detected",
+            None,
+            None,
+            Some(&details),
+        )
+        .await;
+        crate::events::breaches_detected(app, newly_breached.clone());
+    }
+    drop(vault);
+    if let Err(e) = crate::notifications::refresh(app, state).await {
+        eprintln!("[BreachMonitor] Failed to update notifications: {}", e);
+    }
+
+    Ok(BreachCheckSummary {
+        checked_items: items.len(),
+        breached_items,
+        newly_breached,
+    })
+}
+
+/// Runs the check if monitoring is enabled and the interval has passed.
+pub(crate) async fn run_if_due(app: &AppHandle) -> Result<()> {
+    if crate::storage::is_read_only() {
+        return Ok(());
+    }
+    let state = app.state::<AppState>();
+    let stored = {
+        let Ok(vault) = UnlockedVault::acquire(&state).await else {
+            return Ok(());
+        };
+        load_stored(vault.pool(), vault.key()).await?
+    };
+    let Some(stored) = stored.filter(|s| s.config.enabled) else {
+        return Ok(());
+    };
+    let interval = chrono::Duration::hours(i64::from(stored.config.interval_hours));
+    let due = stored
+        .last_run
+        .as_deref()
+        .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+        .is_none_or(|at| Utc::now() - at.with_timezone(&Utc) >= interval);
+    if due {
+        run_check(app, &state).await?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_breach_monitor_config(state: State<'_, AppState>) -> Result<BreachMonitorStatus> {
+    let vault = UnlockedVault::acquire(&state).await?;
+    let stored = load_stored(vault.pool(), vault.key())
+        .await?
+        .unwrap_or_default();
+    Ok(BreachMonitorStatus {
+        has_api_key: stored.api_key.is_some(),
+        config: stored.config,
+        last_run: stored.last_run,
+    })
+}
+
+/// Saves the monitoring settings. `api_key` is write-only: `None` keeps the
+/// stored key, an empty string removes it.
+#[tauri::command]
+pub async fn set_breach_monitor_config(
+    state: State<'_, AppState>,
+    config: BreachMonitorConfig,
+    api_key: Option<String>,
+) -> Result<()> {
+    limits::check_opt_len(
+        "HIBP API key",
+        api_key.as_deref(),
+        limits::MAX_SHORT_FIELD_LEN,
+    )?;
+    if config.interval_hours < MIN_INTERVAL_HOURS {
+        return Err(Error::Validation(format!(
+            "Breach checks can run at most every {} hours.",
+            MIN_INTERVAL_HOURS
+        )));
+    }
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let previous = load_stored(vault.pool(), vault.key()).await?;
+    let last_run = previous.as_ref().and_then(|p| p.last_run.clone());
+    let api_key = match api_key {
+        Some(k) if k.trim().is_empty() => None,
+        Some(k) => Some(k.trim().to_string()),
+        None => previous.and_then(|p| p.api_key),
+    };
+    if config.check_accounts && api_key.is_none() {
+        return Err(Error::Validation(
+            "Checking usernames needs an HIBP API key.".to_string(),
+        ));
+    }
+
+    save_stored(
+        vault.pool(),
+        vault.key(),
+        &StoredBreachMonitor {
+            config,
+            api_key,
+            last_run,
+        },
+    )
+    .await
+}
+
+/// Runs a check right away, whether or not monitoring is enabled.
+#[tauri::command]
+pub async fn run_breach_check_now(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<BreachCheckSummary> {
+    run_check(&app, &state).await
+}
+
+/// The latest result of every checked item.
+#[tauri::command]
+pub async fn get_breach_results(state: State<'_, AppState>) -> Result<Vec<ItemBreachResult>> {
+    let vault = UnlockedVault::acquire(&state).await?;
+    load_results(vault.pool(), vault.key()).await
+}
@@ -59,12 +59,21 @@ pub async fn copy_to_clipboard(
         }
     }
 
+    let mut duration = policy.clear_after_duration;
     {
         let db_guard = state.db.lock().await;
         let key_guard = state.key.lock().await;
         if let (Some(pool), Some(key)) = (db_guard.as_ref(), key_guard.as_ref()) {
+            if let Ok(settings) =
+                crate::db::vault_settings::load_vault_settings(pool, key.as_slice()).await
+            {
+                if let Some(seconds) = settings.clipboard_clear_seconds {
+                    duration = u64::from(seconds);
+                }
+            }
             let details = label.as_ref().map(|l| format!("Copied {}", l));
-            let _ = crate::db::activity::log_activity_impl(
+            let _ = crate::db::activity::log_secret_access(
+                &app,
                 pool,
                 key.as_slice(),
                 "clipboard_copy",
@@ -76,15 +85,12 @@ pub async fn copy_to_clipboard(
         }
     }
 
-    app.clipboard()
-        .write_text(text)
-        .map_err(|e| Error::Internal(e.to_string()))?;
+    write_secret_text(&app, &text)?;
 
     if let Some(handle) = policy.clear_task_handle.take() {
         handle.abort();
     }
 
-    let duration = policy.clear_after_duration;
     if duration > 0 {
         let app_clone = app.clone();
         policy.clear_task_handle = Some(tokio::spawn(async move {
@@ -96,6 +102,91 @@ pub async fn copy_to_clipboard(
     Ok(())
 }
 
+/// Writes a secret with the hints clipboard managers use to skip it:
+/// `ExcludeClipboardContentFromMonitorProcessing`, `CanIncludeInClipboardHistory`
+/// and `CanUploadToCloudClipboard` on Windows (Clipboard History, cloud
+/// clipboard), `org.nspasteboard.ConcealedType` on macOS.
+#[cfg(target_os = "windows")]
+fn write_secret_text(_app: &tauri::AppHandle, text: &str) -> Result<()> {
+    use windows::core::w;
+    use windows::Win32::Foundation::{HANDLE, HWND};
+    use windows::Win32::System::DataExchange::{
+        CloseClipboard, EmptyClipboard, OpenClipboard, RegisterClipboardFormatW, SetClipboardData,
+    };
+    use windows::Win32::System::Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE};
+    use windows::Win32::System::Ole::CF_UNICODETEXT;
+
+    unsafe fn global_copy(bytes: &[u8]) -> windows::core::Result<HANDLE> {
+        let handle = GlobalAlloc(GMEM_MOVEABLE, bytes.len().max(1))?;
+        let ptr = GlobalLock(handle) as *mut u8;
+        if ptr.is_null() {
+            return Err(windows::core::Error::from_win32());
+        }
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr, bytes.len());
+        let _ = GlobalUnlock(handle);
+        Ok(HANDLE(handle.0))
+    }
+
+    let wide: zeroize::Zeroizing<Vec<u16>> =
+        zeroize::Zeroizing::new(text.encode_utf16().chain(std::iter::once(0)).collect());
+    let text_bytes =
+        unsafe { std::slice::from_raw_parts(wide.as_ptr() as *const u8, wide.len() * 2) };
+    let zero = 0u32.to_le_bytes();
+
+    unsafe {
+        OpenClipboard(HWND::default())
+            .map_err(|e| Error::Internal(format!("Failed to open clipboard: {e}")))?;
+        let result = (|| {
+            EmptyClipboard()?;
+            SetClipboardData(CF_UNICODETEXT.0 as u32, global_copy(text_bytes)?)?;
+            for name in [
+                w!("ExcludeClipboardContentFromMonitorProcessing"),
+                w!("CanIncludeInClipboardHistory"),
+                w!("CanUploadToCloudClipboard"),
+            ] {
+                let format = RegisterClipboardFormatW(name);
+                if format != 0 {
+                    SetClipboardData(format, global_copy(&zero)?)?;
+                }
+            }
+            Ok(())
+        })();
+        let _ = CloseClipboard();
+        result.map_err(|e: windows::core::Error| {
+            Error::Internal(format!("Failed to write clipboard: {e}"))
+        })
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn write_secret_text(_app: &tauri::AppHandle, text: &str) -> Result<()> {
+    use objc::runtime::Object;
+    use objc::{class, msg_send, sel, sel_impl};
+    use objc_foundation::{INSString, NSString};
+
+    unsafe {
+        let pasteboard: *mut Object = msg_send![class!(NSPasteboard), generalPasteboard];
+        let _: i64 = msg_send![pasteboard, clearContents];
+        let value = NSString::from_str(text);
+        let string_type = NSString::from_str("public.utf8-plain-text");
+        let written: bool = msg_send![pasteboard, setString:&*value forType:&*string_type];
+        if !written {
+            return Err(Error::Internal("Failed to write clipboard.".to_string()));
+        }
+        let marker = NSString::from_str("");
+        let concealed_type = NSString::from_str("org.nspasteboard.ConcealedType");
+        let _: bool = msg_send![pasteboard, setString:&*marker forType:&*concealed_type];
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn write_secret_text(app: &tauri::AppHandle, text: &str) -> Result<()> {
+    app.clipboard()
+        .write_text(text)
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
 fn history_blocking_supported() -> bool {
     cfg!(target_os = "windows")
 }
@@ -1,5 +1,6 @@
 use crate::error::{Error, Result};
 use crate::file_dialog::pick_save_file;
+use crate::limits;
 use crate::types::{ExportPayload, PasswordItem, PubKeyExportPayload};
 use argon2::{Algorithm, Argon2, Params, Version};
 use base64::{engine::general_purpose, Engine as _};
@@ -7,6 +8,7 @@ use chacha20poly1305::{
     aead::{Aead, KeyInit},
     Key, XChaCha20Poly1305, XNonce,
 };
+use chrono::{DateTime, Utc};
 use hkdf::Hkdf;
 use rand::rngs::OsRng;
 use rand::RngCore;
@@ -16,18 +18,87 @@ use tauri::Window;
 use x25519_dalek::{EphemeralSecret as X25519Secret, PublicKey as X25519Public, StaticSecret};
 use zeroize::{Zeroize, Zeroizing};
 
+/// The longest lifetime an expiring export may be given.
+const MAX_EXPIRY_SECONDS: u64 = 365 * 24 * 60 * 60;
+
+/// Turns an optional lifetime into the `expires_at` of an export payload.
+fn expiry_after(expires_in_seconds: Option<u64>) -> Result<Option<String>> {
+    let Some(seconds) = expires_in_seconds else {
+        return Ok(None);
+    };
+    if seconds == 0 || seconds > MAX_EXPIRY_SECONDS {
+        return Err(Error::Validation(format!(
+            "An export must expire within {} days.",
+            MAX_EXPIRY_SECONDS / (24 * 60 * 60)
+        )));
+    }
+    Ok(Some(
+        (Utc::now() + chrono::Duration::seconds(seconds as i64)).to_rfc3339(),
+    ))
+}
+
+/// Fails once `expires_at` has passed. Call before decrypting; the AAD
+/// makes sure the value was not changed.
+fn check_expiry(expires_at: Option<&str>) -> Result<()> {
+    let Some(expires_at) = expires_at else {
+        return Ok(());
+    };
+    let expires_at = DateTime::parse_from_rfc3339(expires_at)
+        .map_err(|_| Error::Validation("The export has an invalid expiry.".to_string()))?;
+    if expires_at.with_timezone(&Utc) <= Utc::now() {
+        return Err(Error::Validation(format!(
+            "This export expired on {}.",
+            expires_at.to_rfc3339()
+        )));
+    }
+    Ok(())
+}
+
+/// Appends the expiry to an AAD string, leaving payloads without one as
+/// they were.
+fn with_expiry(aad: String, expires_at: Option<&str>) -> String {
+    match expires_at {
+        Some(at) => format!("{}:expires={}", aad, at),
+        None => aad,
+    }
+}
+
+/// Writes an entry to a file chosen by the user, encrypted with
+/// `passphrase`. With `expires_in_seconds`, imports refuse the file after
+/// that long.
 #[tauri::command]
 pub async fn export_password_entry(
     window: Window,
     password_item: PasswordItem,
     passphrase: String,
+    expires_in_seconds: Option<u64>,
 ) -> Result<String> {
+    let expires_at = expiry_after(expires_in_seconds)?;
     let path_str = pick_save_file(window).await?;
     let path = std::path::PathBuf::from(&path_str);
     let plaintext = serde_json::to_vec(&password_item)?;
 
     let passphrase = Zeroizing::new(passphrase);
+    let export = seal_with_passphrase_until(&plaintext, passphrase.as_str(), expires_at)?;
 
+    let export_bytes = serde_json::to_vec_pretty(&export)?;
+    write_sensitive_bytes(&path, &export_bytes).await?;
+
+    Ok(format!("Exported (passphrase) to {}", path.display()))
+}
+
+/// Encrypts `plaintext` under an Argon2id key derived from `passphrase`, in
+/// the format `export_password_entry` writes.
+pub(crate) fn seal_with_passphrase(plaintext: &[u8], passphrase: &str) -> Result<ExportPayload> {
+    seal_with_passphrase_until(plaintext, passphrase, None)
+}
+
+/// Like `seal_with_passphrase`, for a payload refused after `expires_at`.
+pub(crate) fn seal_with_passphrase_until(
+    plaintext: &[u8],
+    passphrase: &str,
+    expires_at: Option<String>,
+) -> Result<ExportPayload> {
     let mut salt = [0u8; 16];
     OsRng.fill_bytes(&mut salt);
 
@@ -46,7 +117,10 @@ pub async fn export_password_entry(
     let version: u8 = 2;
     let salt_b64 = general_purpose::STANDARD.encode(salt);
     let nonce_b64 = general_purpose::STANDARD.encode(nonce);
-    let aad = format!("v{version}:{salt_b64}:{nonce_b64}");
+    let aad = with_expiry(
+        format!("v{version}:{salt_b64}:{nonce_b64}"),
+        expires_at.as_deref(),
+    );
 
     let ciphertext = cipher
         .encrypt(
@@ -60,17 +134,69 @@ pub async fn export_password_entry(
 
     key.zeroize();
 
-    let export = ExportPayload {
+    Ok(ExportPayload {
         version,
         salt_b64,
         nonce_b64,
         ciphertext_b64: general_purpose::STANDARD.encode(&ciphertext),
+        expires_at,
+    })
+}
+
+/// Decrypts a payload written by `seal_with_passphrase`.
+pub(crate) fn open_with_passphrase(
+    payload: &ExportPayload,
+    passphrase: &str,
+) -> Result<Zeroizing<Vec<u8>>> {
+    if payload.version != 2 {
+        return Err(Error::Validation(format!(
+            "Unsupported export version {}",
+            payload.version
+        )));
+    }
+    check_expiry(payload.expires_at.as_deref())?;
+    let decode = |value: &str| {
+        general_purpose::STANDARD
+            .decode(value)
+            .map_err(|_| Error::Validation("The file is not a valid export.".to_string()))
     };
+    let salt = decode(&payload.salt_b64)?;
+    let nonce = decode(&payload.nonce_b64)?;
+    let ciphertext = decode(&payload.ciphertext_b64)?;
+    if nonce.len() != 24 {
+        return Err(Error::Validation("nonce must be 24 bytes".into()));
+    }
 
-    let export_bytes = serde_json::to_vec_pretty(&export)?;
-    write_sensitive_bytes(&path, &export_bytes).await?;
+    let params = Params::new(64 * 1024, 3, 1, None).map_err(|e| Error::Internal(e.to_string()))?;
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+    let mut key = Zeroizing::new([0u8; 32]);
+    argon2
+        .hash_password_into(passphrase.as_bytes(), &salt, &mut key[..])
+        .map_err(|e| Error::Internal(format!("KDF failed: {}", e)))?;
 
-    Ok(format!("Exported (passphrase) to {}", path.display()))
+    let aad = with_expiry(
+        format!(
+            "v{}:{}:{}",
+            payload.version, payload.salt_b64, payload.nonce_b64
+        ),
+        payload.expires_at.as_deref(),
+    );
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&key[..]));
+    let plaintext = cipher
+        .decrypt(
+            XNonce::from_slice(&nonce),
+            chacha20poly1305::aead::Payload {
+                msg: &ciphertext,
+                aad: aad.as_bytes(),
+            },
+        )
+        .map_err(|_| {
+            Error::Validation(
+                "Decryption failed. The passphrase may be incorrect or the file is corrupt."
+                    .to_string(),
+            )
+        })?;
+    Ok(Zeroizing::new(plaintext))
 }
 
 #[tauri::command]
@@ -87,7 +213,9 @@ pub async fn export_password_entry_to_public_key(
     window: Window,
     password_item: PasswordItem,
     recipient_pubkey_b64: String,
+    expires_in_seconds: Option<u64>,
 ) -> Result<String> {
+    let expires_at = expiry_after(expires_in_seconds)?;
     let path_str = pick_save_file(window).await?;
     let path = std::path::PathBuf::from(&path_str);
 
@@ -125,9 +253,12 @@ pub async fn export_password_entry_to_public_key(
     let eph_pub_b64 = general_purpose::STANDARD.encode(eph_pk.as_bytes());
     let salt_b64 = general_purpose::STANDARD.encode(&salt);
     let nonce_b64 = general_purpose::STANDARD.encode(&nonce);
-    let aad = format!(
-        "v1:x25519-ephemeral-static:hkdf-sha256:xchacha20poly1305:{}:{}:{}:{}",
-        recipient_pub_b64, eph_pub_b64, salt_b64, nonce_b64
+    let aad = with_expiry(
+        format!(
+            "v1:x25519-ephemeral-static:hkdf-sha256:xchacha20poly1305:{}:{}:{}:{}",
+            recipient_pub_b64, eph_pub_b64, salt_b64, nonce_b64
+        ),
+        expires_at.as_deref(),
     );
 
     let ciphertext = cipher
@@ -152,6 +283,7 @@ pub async fn export_password_entry_to_public_key(
         salt_b64,
         nonce_b64,
         ciphertext_b64: general_purpose::STANDARD.encode(&ciphertext),
+        expires_at,
     };
 
     let bytes = serde_json::to_vec_pretty(&payload)?;
@@ -165,6 +297,16 @@ pub async fn import_password_entry_with_private_key(
     payload_json: String,
     recipient_secret_b64: String,
 ) -> Result<PasswordItem> {
+    limits::check_len(
+        "Share payload",
+        &payload_json,
+        limits::MAX_SHARE_PAYLOAD_LEN,
+    )?;
+    limits::check_len(
+        "Secret key",
+        &recipient_secret_b64,
+        limits::MAX_KEY_MATERIAL_LEN,
+    )?;
     let payload: PubKeyExportPayload = serde_json::from_str(&payload_json)?;
 
     if payload.scheme != "x25519-ephemeral-static"
@@ -173,6 +315,7 @@ pub async fn import_password_entry_with_private_key(
     {
         return Err(Error::Validation("unsupported payload parameters".into()));
     }
+    check_expiry(payload.expires_at.as_deref())?;
 
     let sk_bytes = general_purpose::STANDARD
         .decode(recipient_secret_b64)
@@ -215,9 +358,12 @@ pub async fn import_password_entry_with_private_key(
     hk.expand(b"pulsar:password-export:x25519", &mut aead_key)
         .map_err(|_| Error::Internal("HKDF expand failed".to_string()))?;
 
-    let aad = format!(
-        "v1:x25519-ephemeral-static:hkdf-sha256:xchacha20poly1305:{}:{}:{}:{}",
-        payload.recipient_pub_b64, payload.eph_pub_b64, payload.salt_b64, payload.nonce_b64
+    let aad = with_expiry(
+        format!(
+            "v1:x25519-ephemeral-static:hkdf-sha256:xchacha20poly1305:{}:{}:{}:{}",
+            payload.recipient_pub_b64, payload.eph_pub_b64, payload.salt_b64, payload.nonce_b64
+        ),
+        payload.expires_at.as_deref(),
     );
 
     let cipher = XChaCha20Poly1305::new(Key::from_slice(&aead_key));
@@ -236,6 +382,30 @@ pub async fn import_password_entry_with_private_key(
     Ok(item)
 }
 
+/// Reads an entry written by `export_password_entry`, refusing it once its
+/// expiry has passed.
+#[tauri::command]
+pub async fn import_password_entry(
+    payload_json: String,
+    passphrase: String,
+) -> Result<PasswordItem> {
+    limits::check_len(
+        "Share payload",
+        &payload_json,
+        limits::MAX_SHARE_PAYLOAD_LEN,
+    )?;
+    let passphrase = Zeroizing::new(passphrase);
+    let payload: ExportPayload = serde_json::from_str(&payload_json)?;
+    let plaintext = tauri::async_runtime::spawn_blocking(move || {
+        open_with_passphrase(&payload, passphrase.as_str())
+    })
+    .await
+    .map_err(|e| Error::Internal(format!("Runtime error: {}", e)))??;
+    let item: PasswordItem = serde_json::from_slice(&plaintext)?;
+    limits::check_password_item(&item)?;
+    Ok(item)
+}
+
 async fn write_sensitive_bytes(path: &Path, bytes: &[u8]) -> Result<()> {
     let tmp_path = path.with_extension("tmp");
     if tokio::fs::try_exists(&tmp_path).await.unwrap_or(false) {
@@ -1,10 +1,17 @@
-use crate::db::utils::{get_db_pool, get_key};
-use crate::encryption::{decrypt, encrypt};
-use crate::error::Result;
+use crate::auth::crypto_utils::derive_activity_log_key;
+use crate::db::utils::UnlockedVault;
+use crate::encryption::{decrypt, decrypt_zeroized, encrypt};
+use crate::error::{Error, Result};
 use crate::state::AppState;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
 use serde::{Deserialize, Serialize};
+use sha2::Sha256;
 use sqlx::Row;
+use std::collections::BTreeMap;
+use subtle::ConstantTimeEq;
 use tauri::State;
+use zeroize::Zeroizing;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -15,75 +22,1018 @@ pub struct ActivityEntry {
     pub item_title: Option<String>,
     pub details: Option<String>,
     pub created_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<AccessContext>,
 }
 
-pub async fn log_activity_impl<'a, E>(
-    executor: E,
+/// Coarse description of the machine a secret was revealed on. Only recorded
+/// when `security.recordAccessContext` is enabled; never includes location.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessContext {
+    pub hostname: Option<String>,
+    pub os_user: Option<String>,
+    pub app_version: String,
+}
+
+#[derive(Deserialize, Default)]
+struct ContextSettings {
+    #[serde(default)]
+    security: ContextSecuritySettings,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct ContextSecuritySettings {
+    #[serde(default)]
+    record_access_context: bool,
+}
+
+fn non_empty_env(var: &str) -> Option<String> {
+    std::env::var(var)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+pub(crate) fn hostname() -> Option<String> {
+    non_empty_env("COMPUTERNAME")
+        .or_else(|| non_empty_env("HOSTNAME"))
+        .or_else(|| {
+            std::fs::read_to_string("/etc/hostname")
+                .ok()
+                .map(|h| h.trim().to_string())
+                .filter(|h| !h.is_empty())
+        })
+}
+
+/// Returns the context to attach to a secret reveal, or `None` unless the
+/// user opted in.
+pub async fn access_context(app: &tauri::AppHandle) -> Option<AccessContext> {
+    let enabled = crate::settings::get_all_settings_internal(app)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str::<ContextSettings>(&json).ok())
+        .unwrap_or_default()
+        .security
+        .record_access_context;
+    if !enabled {
+        return None;
+    }
+
+    Some(AccessContext {
+        hostname: hostname(),
+        os_user: non_empty_env("USER").or_else(|| non_empty_env("USERNAME")),
+        app_version: app.package_info().version.to_string(),
+    })
+}
+
+const ANCHOR_KEY: &str = "activity_log_anchor";
+const HEAD_KEY: &str = "activity_log_head";
+const LOG_KEY_KEY: &str = "activity_log_key";
+/// Logged by every clear. It records the anchor the clear left, so an
+/// anchor set without one shows up as removed entries.
+const CLEARED_EVENT: &str = "activity_log_cleared";
+const CLEARED_ANCHOR_LABEL: &str = "Chain anchor: ";
+
+fn cleared_details(removed: u64, anchor: &str) -> String {
+    format!(
+        "Removed {} entries. {}{}",
+        removed, CLEARED_ANCHOR_LABEL, anchor
+    )
+}
+
+fn cleared_anchor(details: &str) -> Option<&str> {
+    details
+        .rsplit_once(CLEARED_ANCHOR_LABEL)
+        .map(|(_, anchor)| anchor)
+}
+
+/// Key an entry's chain hash was computed with.
+#[derive(Clone, Copy)]
+enum ChainScheme<'k> {
+    /// Derived from the master key, without the row id. Used by vaults whose
+    /// log predates the per-vault log key; it stops verifying once the
+    /// master password changes.
+    Legacy(&'k [u8]),
+    /// The random per-vault log key, which is re-wrapped on rekey. The row
+    /// id is covered too.
+    Current(&'k [u8]),
+}
+
+/// Stored (encrypted) columns of an entry that its chain hash covers.
+struct ChainedFields<'r> {
+    id: i64,
+    event_type: &'r str,
+    item_id: Option<i64>,
+    item_title: Option<&'r str>,
+    details: Option<&'r str>,
+    context: Option<&'r str>,
+    created_at: &'r str,
+}
+
+/// HMAC over the previous entry's hash and this entry's fields. Each field
+/// is length-prefixed so values cannot be shifted between fields.
+fn chain_hash(scheme: ChainScheme<'_>, previous: &str, fields: &ChainedFields<'_>) -> String {
+    let (log_key, id) = match scheme {
+        ChainScheme::Legacy(key) => (key, None),
+        ChainScheme::Current(key) => (key, Some(fields.id.to_string())),
+    };
+    let mut mac =
+        <Hmac<Sha256> as Mac>::new_from_slice(log_key).expect("HMAC can take key of any size");
+    let item_id = fields.item_id.map(|id| id.to_string());
+    let parts = [
+        Some(previous),
+        Some(fields.event_type),
+        item_id.as_deref(),
+        fields.item_title,
+        fields.details,
+        fields.context,
+        Some(fields.created_at),
+    ];
+    for part in id.as_deref().map(Some).into_iter().chain(parts) {
+        match part {
+            Some(value) => {
+                mac.update(&(value.len() as u32).to_be_bytes());
+                mac.update(value.as_bytes());
+            }
+            None => mac.update(&u32::MAX.to_be_bytes()),
+        }
+    }
+    hex::encode(mac.finalize().into_bytes())
+}
+
+struct ChainRow {
+    id: i64,
+    event_type: String,
+    item_id: Option<i64>,
+    item_title: Option<String>,
+    details: Option<String>,
+    context: Option<String>,
+    created_at: String,
+    chain_hash: Option<String>,
+}
+
+impl ChainRow {
+    fn fields(&self) -> ChainedFields<'_> {
+        ChainedFields {
+            id: self.id,
+            event_type: &self.event_type,
+            item_id: self.item_id,
+            item_title: self.item_title.as_deref(),
+            details: self.details.as_deref(),
+            context: self.context.as_deref(),
+            created_at: &self.created_at,
+        }
+    }
+}
+
+async fn chain_rows(conn: &mut sqlx::SqliteConnection, before_id: i64) -> Result<Vec<ChainRow>> {
+    let rows = sqlx::query(
+        "SELECT id, event_type, item_id, item_title, details, context, created_at, chain_hash FROM activity_log WHERE id < ? ORDER BY id",
+    )
+    .bind(before_id)
+    .fetch_all(conn)
+    .await?;
+    Ok(rows
+        .iter()
+        .map(|row| ChainRow {
+            id: row.get("id"),
+            event_type: row.get("event_type"),
+            item_id: row.get("item_id"),
+            item_title: row.get("item_title"),
+            details: row.get("details"),
+            context: row.get("context"),
+            created_at: row.get("created_at"),
+            chain_hash: row.get("chain_hash"),
+        })
+        .collect())
+}
+
+/// Walks `rows` (in id order) from `anchor` and reports the first place the
+/// chain breaks. `head` is the hash of the newest entry ever written.
+fn walk_chain(
+    scheme: ChainScheme<'_>,
+    anchor: &str,
+    head: Option<&str>,
+    rows: &[ChainRow],
+) -> ActivityLogVerification {
+    let mut previous = anchor;
+    let mut verification = ActivityLogVerification {
+        intact: true,
+        verified_entries: 0,
+        unchained_entries: 0,
+        first_broken_id: None,
+        truncated: false,
+        unattested_anchor: false,
+    };
+    for row in rows {
+        let Some(stored) = row.chain_hash.as_deref() else {
+            if verification.verified_entries == 0 {
+                verification.unchained_entries += 1;
+                continue;
+            }
+            verification.first_broken_id = Some(row.id);
+            break;
+        };
+        let expected = chain_hash(scheme, previous, &row.fields());
+        if !bool::from(expected.as_bytes().ct_eq(stored.as_bytes())) {
+            verification.first_broken_id = Some(row.id);
+            break;
+        }
+        verification.verified_entries += 1;
+        previous = stored;
+    }
+
+    if verification.first_broken_id.is_none() {
+        verification.truncated = head.is_some_and(|head| head != previous);
+    }
+    verification.intact = verification.first_broken_id.is_none() && !verification.truncated;
+    verification
+}
+
+async fn config_value(conn: &mut sqlx::SqliteConnection, key: &str) -> Result<Option<String>> {
+    Ok(
+        sqlx::query_scalar("SELECT value FROM configuration WHERE key = ?")
+            .bind(key)
+            .fetch_optional(conn)
+            .await?,
+    )
+}
+
+/// Hash the entry with id `before_id` chains from: the newest chained entry
+/// before it, else the anchor left by the last clear.
+async fn chain_tip(conn: &mut sqlx::SqliteConnection, before_id: i64) -> Result<String> {
+    let tip: Option<String> = sqlx::query_scalar(
+        "SELECT COALESCE(\
+         (SELECT chain_hash FROM activity_log WHERE id < ? AND chain_hash IS NOT NULL ORDER BY id DESC LIMIT 1), \
+         (SELECT value FROM configuration WHERE key = ?))",
+    )
+    .bind(before_id)
+    .bind(ANCHOR_KEY)
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(tip.unwrap_or_default())
+}
+
+async fn set_config(conn: &mut sqlx::SqliteConnection, key: &str, value: &str) -> Result<()> {
+    sqlx::query("INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)")
+        .bind(key)
+        .bind(value)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+fn unwrap_log_key(wrapped: &str, key: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+    let hex_key = decrypt_zeroized(wrapped, key)?;
+    hex::decode(hex_key.as_str())
+        .map(Zeroizing::new)
+        .map_err(|_| Error::Decryption("Invalid activity log key".to_string()))
+}
+
+/// The per-vault log key, created on first use. Entries written before it
+/// existed are re-chained under it, up to the first one that does not
+/// verify under the legacy key, so an existing break stays visible.
+/// `before_id` excludes entries still being written.
+async fn log_key(
+    conn: &mut sqlx::SqliteConnection,
+    key: &[u8],
+    before_id: i64,
+) -> Result<Zeroizing<Vec<u8>>> {
+    if let Some(wrapped) = config_value(conn, LOG_KEY_KEY).await? {
+        return unwrap_log_key(&wrapped, key);
+    }
+
+    let log_key = Zeroizing::new(rand::random::<[u8; 32]>().to_vec());
+    let legacy_key = Zeroizing::new(derive_activity_log_key(key)?);
+    let anchor = config_value(conn, ANCHOR_KEY).await?.unwrap_or_default();
+    let head = config_value(conn, HEAD_KEY).await?;
+    let rows = chain_rows(conn, before_id).await?;
+    let legacy = walk_chain(
+        ChainScheme::Legacy(legacy_key.as_slice()),
+        &anchor,
+        head.as_deref(),
+        &rows,
+    );
+
+    let mut previous = anchor;
+    for row in &rows {
+        if Some(row.id) == legacy.first_broken_id {
+            break;
+        }
+        if row.chain_hash.is_none() {
+            continue;
+        }
+        previous = chain_hash(
+            ChainScheme::Current(log_key.as_slice()),
+            &previous,
+            &row.fields(),
+        );
+        sqlx::query("UPDATE activity_log SET chain_hash = ? WHERE id = ?")
+            .bind(&previous)
+            .bind(row.id)
+            .execute(&mut *conn)
+            .await?;
+    }
+    if legacy.intact && legacy.verified_entries > 0 {
+        set_config(conn, HEAD_KEY, &previous).await?;
+    }
+
+    let hex_key = Zeroizing::new(hex::encode(log_key.as_slice()));
+    set_config(conn, LOG_KEY_KEY, &encrypt(&hex_key, key)?).await?;
+    Ok(log_key)
+}
+
+/// Makes sure the vault has a per-vault log key, so a rekey can re-wrap it
+/// instead of orphaning a chain keyed from the old master key.
+pub(crate) async fn ensure_log_key(pool: &sqlx::SqlitePool, key: &[u8]) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    log_key(&mut tx, key, i64::MAX).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Re-wraps the log key in `schema`'s configuration table from `old_key`
+/// to `new_key`.
+pub(crate) async fn rewrap_log_key(
+    conn: &mut sqlx::SqliteConnection,
+    schema: &str,
+    old_key: &[u8],
+    new_key: &[u8],
+) -> Result<()> {
+    let wrapped: Option<String> = sqlx::query_scalar(&format!(
+        "SELECT value FROM {}.configuration WHERE key = ?",
+        schema
+    ))
+    .bind(LOG_KEY_KEY)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some(wrapped) = wrapped else {
+        return Ok(());
+    };
+    let hex_key = decrypt_zeroized(&wrapped, old_key)?;
+    sqlx::query(&format!(
+        "UPDATE {}.configuration SET value = ? WHERE key = ?",
+        schema
+    ))
+    .bind(encrypt(&hex_key, new_key)?)
+    .bind(LOG_KEY_KEY)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+async fn insert_activity<'a, A>(
+    executor: A,
     key: &[u8],
     event_type: &str,
     item_id: Option<i64>,
     item_title: Option<&str>,
     details: Option<&str>,
+    context: Option<&AccessContext>,
 ) -> Result<()>
 where
-    E: sqlx::SqliteExecutor<'a>,
+    A: sqlx::Acquire<'a, Database = sqlx::Sqlite>,
 {
     let item_title_enc = item_title.map(|t| encrypt(t, key)).transpose()?;
     let details_enc = details.map(|d| encrypt(d, key)).transpose()?;
+    let context_enc = context
+        .map(serde_json::to_string)
+        .transpose()?
+        .map(|c| encrypt(&c, key))
+        .transpose()?;
+    // Set here rather than by the column default, which drops the day of
+    // the month.
+    let created_at = timestamp(Utc::now());
 
-    sqlx::query(
-        "INSERT INTO activity_log (event_type, item_id, item_title, details) VALUES (?, ?, ?, ?)",
+    let mut tx = executor.begin().await?;
+    // Inserting first takes the write lock, so the tip read below cannot
+    // race another writer and fork the chain.
+    let id = sqlx::query(
+        "INSERT INTO activity_log (event_type, item_id, item_title, details, context, created_at) VALUES (?, ?, ?, ?, ?, ?)",
     )
     .bind(event_type)
     .bind(item_id)
-    .bind(item_title_enc)
-    .bind(details_enc)
-    .execute(executor)
-    .await?;
+    .bind(&item_title_enc)
+    .bind(&details_enc)
+    .bind(&context_enc)
+    .bind(&created_at)
+    .execute(&mut *tx)
+    .await?
+    .last_insert_rowid();
+
+    let log_key = log_key(&mut tx, key, id).await?;
+    let previous = chain_tip(&mut tx, id).await?;
+    let hash = chain_hash(
+        ChainScheme::Current(log_key.as_slice()),
+        &previous,
+        &ChainedFields {
+            id,
+            event_type,
+            item_id,
+            item_title: item_title_enc.as_deref(),
+            details: details_enc.as_deref(),
+            context: context_enc.as_deref(),
+            created_at: &created_at,
+        },
+    );
+    sqlx::query("UPDATE activity_log SET chain_hash = ? WHERE id = ?")
+        .bind(&hash)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    set_config(&mut tx, HEAD_KEY, &hash).await?;
+    tx.commit().await?;
 
     Ok(())
 }
 
+pub async fn log_activity_impl<'a, A>(
+    executor: A,
+    key: &[u8],
+    event_type: &str,
+    item_id: Option<i64>,
+    item_title: Option<&str>,
+    details: Option<&str>,
+) -> Result<()>
+where
+    A: sqlx::Acquire<'a, Database = sqlx::Sqlite>,
+{
+    insert_activity(
+        executor, key, event_type, item_id, item_title, details, None,
+    )
+    .await
+}
+
+/// Logs an event that exposed a secret, with the opt-in access context.
+pub async fn log_secret_access<'a, A>(
+    app: &tauri::AppHandle,
+    executor: A,
+    key: &[u8],
+    event_type: &str,
+    item_id: Option<i64>,
+    item_title: Option<&str>,
+    details: Option<&str>,
+) -> Result<()>
+where
+    A: sqlx::Acquire<'a, Database = sqlx::Sqlite>,
+{
+    let context = access_context(app).await;
+    insert_activity(
+        executor,
+        key,
+        event_type,
+        item_id,
+        item_title,
+        details,
+        context.as_ref(),
+    )
+    .await
+}
+
+/// Records that the UI revealed `field` of an item, e.g. unmasking its
+/// password.
+#[tauri::command]
+pub async fn record_secret_reveal(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    item_id: i64,
+    field: String,
+) -> Result<()> {
+    crate::limits::check_len("Field", &field, crate::limits::MAX_SHORT_FIELD_LEN)?;
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+
+    let title_enc: Option<String> =
+        sqlx::query_scalar("SELECT title FROM password_items WHERE id = ?")
+            .bind(item_id)
+            .fetch_optional(vault.pool())
+            .await?;
+    let title = title_enc.map(|t| decrypt(&t, vault.key())).transpose()?;
+
+    log_secret_access(
+        &app,
+        vault.pool(),
+        vault.key(),
+        "secret_revealed",
+        Some(item_id),
+        title.as_deref(),
+        Some(&format!("Revealed {}", field)),
+    )
+    .await
+}
+
+/// Logged on every successful unlock.
+pub const UNLOCK_EVENT: &str = "vault_unlocked";
+
+const DEFAULT_PAGE_SIZE: u32 = 50;
+const MAX_PAGE_SIZE: u32 = 500;
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ActivityLogFilter {
+    /// Only these event types; empty matches all.
+    pub event_types: Vec<String>,
+    pub item_id: Option<i64>,
+    /// Inclusive RFC 3339 bounds.
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityLogPage {
+    pub entries: Vec<ActivityEntry>,
+    /// Pass back as `cursor` for the next, older page; `None` at the end.
+    pub next_cursor: Option<i64>,
+}
+
+/// Timestamps are stored as `%Y-%m-%dT%H:%M:%SZ` in UTC so they compare as
+/// strings.
+fn timestamp(at: chrono::DateTime<Utc>) -> String {
+    at.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+fn parse_bound(name: &str, value: Option<&str>) -> Result<Option<String>> {
+    value
+        .map(|v| {
+            chrono::DateTime::parse_from_rfc3339(v)
+                .map(|at| timestamp(at.with_timezone(&Utc)))
+                .map_err(|_| Error::Validation(format!("Invalid {} date: {}", name, v)))
+        })
+        .transpose()
+}
+
+fn decrypt_entry(row: &sqlx::sqlite::SqliteRow, key: &[u8]) -> Result<ActivityEntry> {
+    let item_title_enc: Option<String> = row.get("item_title");
+    let details_enc: Option<String> = row.get("details");
+    let context_enc: Option<String> = row.get("context");
+
+    Ok(ActivityEntry {
+        id: row.get("id"),
+        event_type: row.get("event_type"),
+        item_id: row.get("item_id"),
+        item_title: item_title_enc.map(|t| decrypt(&t, key)).transpose()?,
+        details: details_enc.map(|d| decrypt(&d, key)).transpose()?,
+        created_at: row.get("created_at"),
+        context: context_enc
+            .and_then(|c| decrypt(&c, key).ok())
+            .and_then(|c| serde_json::from_str(&c).ok()),
+    })
+}
+
+/// Pages through the log from newest to oldest. `cursor` is the
+/// `next_cursor` of the previous page.
 #[tauri::command]
 pub async fn get_activity_log(
     state: State<'_, AppState>,
-    limit: i64,
-) -> Result<Vec<ActivityEntry>> {
-    let key = get_key(&state).await?;
-    let pool = get_db_pool(&state).await?;
-
-    let rows = sqlx::query("SELECT id, event_type, item_id, item_title, details, created_at FROM activity_log ORDER BY created_at DESC LIMIT ?")
-        .bind(limit)
-        .fetch_all(&pool)
+    filter: Option<ActivityLogFilter>,
+    cursor: Option<i64>,
+) -> Result<ActivityLogPage> {
+    let filter = filter.unwrap_or_default();
+    let limit = filter
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let from = parse_bound("start", filter.from.as_deref())?;
+    let to = parse_bound("end", filter.to.as_deref())?;
+    let event_types = (!filter.event_types.is_empty())
+        .then(|| serde_json::to_string(&filter.event_types))
+        .transpose()?;
+
+    let vault = UnlockedVault::acquire(&state).await?;
+
+    // One row more than requested tells whether another page exists.
+    let rows = sqlx::query(
+        "SELECT id, event_type, item_id, item_title, details, created_at, context FROM activity_log \
+         WHERE (?1 IS NULL OR id < ?1) \
+         AND (?2 IS NULL OR item_id = ?2) \
+         AND (?3 IS NULL OR created_at >= ?3) \
+         AND (?4 IS NULL OR created_at <= ?4) \
+         AND (?5 IS NULL OR event_type IN (SELECT value FROM json_each(?5))) \
+         ORDER BY id DESC LIMIT ?6",
+    )
+    .bind(cursor)
+    .bind(filter.item_id)
+    .bind(from)
+    .bind(to)
+    .bind(event_types)
+    .bind(i64::from(limit) + 1)
+    .fetch_all(vault.pool())
+    .await?;
+
+    let has_more = rows.len() > limit as usize;
+    let entries = rows
+        .iter()
+        .take(limit as usize)
+        .map(|row| decrypt_entry(row, vault.key()))
+        .collect::<Result<Vec<_>>>()?;
+    let next_cursor = if has_more {
+        entries.last().map(|e| e.id)
+    } else {
+        None
+    };
+
+    Ok(ActivityLogPage {
+        entries,
+        next_cursor,
+    })
+}
+
+/// Deletes entries older than `before_date`, or the whole log without one.
+/// `confirm` must be set, so a stray call cannot wipe the log. Returns the
+/// number of entries removed.
+#[tauri::command]
+pub async fn clear_activity_log(
+    state: State<'_, AppState>,
+    before_date: Option<String>,
+    confirm: bool,
+) -> Result<u64> {
+    if !confirm {
+        return Err(Error::Validation(
+            "Clearing the activity log must be confirmed.".to_string(),
+        ));
+    }
+    let before = parse_bound("cutoff", before_date.as_deref())?;
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+
+    // Only a prefix of the log is removed, and the hash of its last entry
+    // becomes the anchor the rest of the chain is verified from. A chained
+    // entry recording that anchor is appended in the same transaction.
+    let mut tx = vault.pool().begin().await?;
+    let last_removed: Option<i64> =
+        sqlx::query_scalar("SELECT MAX(id) FROM activity_log WHERE ?1 IS NULL OR created_at < ?1")
+            .bind(before)
+            .fetch_one(&mut *tx)
+            .await?;
+    let Some(last_removed) = last_removed else {
+        return Ok(0);
+    };
+
+    let anchor = chain_tip(&mut tx, last_removed + 1).await?;
+    let result = sqlx::query("DELETE FROM activity_log WHERE id <= ?")
+        .bind(last_removed)
+        .execute(&mut *tx)
         .await?;
+    set_config(&mut tx, ANCHOR_KEY, &anchor).await?;
+    insert_activity(
+        tx.as_mut(),
+        vault.key(),
+        CLEARED_EVENT,
+        None,
+        None,
+        Some(&cleared_details(result.rows_affected(), &anchor)),
+        None,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(result.rows_affected())
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityLogVerification {
+    pub intact: bool,
+    /// Entries whose chain hash was checked.
+    pub verified_entries: u64,
+    /// Entries written before the log was chained; they cannot be checked.
+    pub unchained_entries: u64,
+    /// First entry that does not verify: it was altered, or an entry before
+    /// it was deleted.
+    pub first_broken_id: Option<i64>,
+    /// Entries were removed from the end of the log.
+    pub truncated: bool,
+    /// The log starts from an anchor that no `activity_log_cleared` entry
+    /// records: entries were removed from its start outside of a clear.
+    pub unattested_anchor: bool,
+}
+
+/// Walks the hash chain from the anchor to the newest entry and reports the
+/// first place it breaks.
+#[tauri::command]
+pub async fn verify_activity_log(state: State<'_, AppState>) -> Result<ActivityLogVerification> {
+    let vault = UnlockedVault::acquire(&state).await?;
+    let mut conn = vault.pool().acquire().await?;
+
+    // Vaults that have not written an entry since the per-vault key was
+    // introduced are still chained under the legacy key.
+    let wrapped = config_value(&mut conn, LOG_KEY_KEY).await?;
+    let log_key = match &wrapped {
+        Some(wrapped) => unwrap_log_key(wrapped, vault.key())?,
+        None => Zeroizing::new(derive_activity_log_key(vault.key())?.to_vec()),
+    };
+    let scheme = if wrapped.is_some() {
+        ChainScheme::Current(log_key.as_slice())
+    } else {
+        ChainScheme::Legacy(log_key.as_slice())
+    };
+    let anchor = config_value(&mut conn, ANCHOR_KEY)
+        .await?
+        .unwrap_or_default();
+    let head = config_value(&mut conn, HEAD_KEY).await?;
+    let rows = chain_rows(&mut conn, i64::MAX).await?;
+
+    let mut verification = walk_chain(scheme, &anchor, head.as_deref(), &rows);
+    if !anchor.is_empty() {
+        let recorded = rows
+            .iter()
+            .rev()
+            .filter(|row| row.event_type == CLEARED_EVENT && row.chain_hash.is_some())
+            .find_map(|row| row.details.as_deref())
+            .and_then(|details| decrypt(details, vault.key()).ok());
+        verification.unattested_anchor =
+            recorded.as_deref().and_then(cleared_anchor) != Some(anchor.as_str());
+        verification.intact &= !verification.unattested_anchor;
+    }
+    Ok(verification)
+}
+
+const MOST_EDITED_LIMIT: i64 = 10;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityPeriod {
+    Week,
+    Month,
+    Quarter,
+    Year,
+}
+
+impl ActivityPeriod {
+    fn days(self) -> i64 {
+        match self {
+            ActivityPeriod::Week => 7,
+            ActivityPeriod::Month => 30,
+            ActivityPeriod::Quarter => 90,
+            ActivityPeriod::Year => 365,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyActivity {
+    /// `YYYY-MM-DD` in UTC.
+    pub date: String,
+    /// Number of entries per event type.
+    pub counts: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditedItem {
+    pub item_id: i64,
+    /// The current title, or the last logged one for deleted items.
+    pub title: Option<String>,
+    pub edits: u64,
+    pub deleted: bool,
+}
 
-    let mut entries = Vec::new();
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnlockFrequency {
+    pub total: u64,
+    pub average_per_day: f64,
+    pub busiest_day: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivitySummary {
+    pub period: ActivityPeriod,
+    pub from: String,
+    pub to: String,
+    /// Only days with activity, oldest first.
+    pub days: Vec<DailyActivity>,
+    pub totals: BTreeMap<String, u64>,
+    pub most_edited: Vec<EditedItem>,
+    pub unlocks: UnlockFrequency,
+}
+
+async fn edited_item_title(vault: &UnlockedVault, item_id: i64) -> Result<(Option<String>, bool)> {
+    let current: Option<String> =
+        sqlx::query_scalar("SELECT title FROM password_items WHERE id = ?")
+            .bind(item_id)
+            .fetch_optional(vault.pool())
+            .await?;
+    if let Some(title) = current {
+        return Ok((Some(decrypt(&title, vault.key())?), false));
+    }
+    let logged: Option<String> = sqlx::query_scalar(
+        "SELECT item_title FROM activity_log WHERE item_id = ? AND item_title IS NOT NULL ORDER BY id DESC LIMIT 1",
+    )
+    .bind(item_id)
+    .fetch_optional(vault.pool())
+    .await?;
+    Ok((logged.map(|t| decrypt(&t, vault.key())).transpose()?, true))
+}
+
+/// Aggregates the log over `period` so a dashboard does not need to page
+/// through and decrypt every entry. Only titles of the most edited items are
+/// decrypted.
+#[tauri::command]
+pub async fn get_activity_summary(
+    state: State<'_, AppState>,
+    period: ActivityPeriod,
+) -> Result<ActivitySummary> {
+    let vault = UnlockedVault::acquire(&state).await?;
+    let to = Utc::now();
+    let from = to - chrono::Duration::days(period.days());
+    let from_bound = timestamp(from);
+
+    let rows = sqlx::query(
+        "SELECT substr(created_at, 1, 10) AS day, event_type, COUNT(*) AS count FROM activity_log \
+         WHERE created_at >= ? GROUP BY day, event_type ORDER BY day",
+    )
+    .bind(&from_bound)
+    .fetch_all(vault.pool())
+    .await?;
+
+    let mut days: Vec<DailyActivity> = Vec::new();
+    let mut totals = BTreeMap::new();
+    let mut unlocks_per_day: Vec<(String, u64)> = Vec::new();
     for row in rows {
-        let item_title_enc: Option<String> = row.get("item_title");
-        let details_enc: Option<String> = row.get("details");
+        let day: String = row.get("day");
+        let event_type: String = row.get("event_type");
+        let count = row.get::<i64, _>("count") as u64;
+        *totals.entry(event_type.clone()).or_insert(0) += count;
+        if event_type == UNLOCK_EVENT {
+            unlocks_per_day.push((day.clone(), count));
+        }
+        match days.last_mut() {
+            Some(last) if last.date == day => {
+                last.counts.insert(event_type, count);
+            }
+            _ => days.push(DailyActivity {
+                date: day,
+                counts: BTreeMap::from([(event_type, count)]),
+            }),
+        }
+    }
 
-        entries.push(ActivityEntry {
-            id: row.get("id"),
-            event_type: row.get("event_type"),
-            item_id: row.get("item_id"),
-            item_title: item_title_enc
-                .map(|t| decrypt(&t, key.as_slice()))
-                .transpose()?,
-            details: details_enc
-                .map(|d| decrypt(&d, key.as_slice()))
-                .transpose()?,
-            created_at: row.get("created_at"),
+    let edited = sqlx::query(
+        "SELECT item_id, COUNT(*) AS edits FROM activity_log \
+         WHERE event_type = 'item_updated' AND item_id IS NOT NULL AND created_at >= ? \
+         GROUP BY item_id ORDER BY edits DESC, item_id LIMIT ?",
+    )
+    .bind(&from_bound)
+    .bind(MOST_EDITED_LIMIT)
+    .fetch_all(vault.pool())
+    .await?;
+    let mut most_edited = Vec::with_capacity(edited.len());
+    for row in edited {
+        let item_id: i64 = row.get("item_id");
+        let (title, deleted) = edited_item_title(&vault, item_id).await?;
+        most_edited.push(EditedItem {
+            item_id,
+            title,
+            edits: row.get::<i64, _>("edits") as u64,
+            deleted,
         });
     }
 
-    Ok(entries)
+    let total_unlocks = totals.get(UNLOCK_EVENT).copied().unwrap_or(0);
+    let busiest_day = unlocks_per_day
+        .iter()
+        .max_by_key(|(_, count)| *count)
+        .map(|(day, _)| day.clone());
+
+    Ok(ActivitySummary {
+        period,
+        from: from_bound,
+        to: timestamp(to),
+        days,
+        totals,
+        most_edited,
+        unlocks: UnlockFrequency {
+            total: total_unlocks,
+            average_per_day: total_unlocks as f64 / period.days() as f64,
+            busiest_day,
+        },
+    })
 }
 
-#[tauri::command]
-pub async fn clear_activity_log(state: State<'_, AppState>) -> Result<()> {
-    let pool = get_db_pool(&state).await?;
-    sqlx::query("DELETE FROM activity_log")
-        .execute(&pool)
-        .await?;
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; 32] = [7u8; 32];
+
+    fn entry(id: i64, event_type: &str) -> ChainRow {
+        ChainRow {
+            id,
+            event_type: event_type.to_string(),
+            item_id: Some(id * 10),
+            item_title: Some(format!("title-{id}")),
+            details: None,
+            context: None,
+            created_at: format!("2026-01-0{id} 12:00:00"),
+            chain_hash: None,
+        }
+    }
+
+    /// Chains `rows` from `anchor` and returns the head hash.
+    fn chain(scheme: ChainScheme<'_>, anchor: &str, rows: &mut [ChainRow]) -> String {
+        let mut previous = anchor.to_string();
+        for row in rows.iter_mut() {
+            previous = chain_hash(scheme, &previous, &row.fields());
+            row.chain_hash = Some(previous.clone());
+        }
+        previous
+    }
+
+    fn log() -> (Vec<ChainRow>, String) {
+        let mut rows = vec![
+            entry(1, "unlock"),
+            entry(2, "item_updated"),
+            entry(3, "lock"),
+        ];
+        let head = chain(ChainScheme::Current(&KEY), "", &mut rows);
+        (rows, head)
+    }
+
+    #[test]
+    fn test_intact_chain_verifies() {
+        let (rows, head) = log();
+        let result = walk_chain(ChainScheme::Current(&KEY), "", Some(&head), &rows);
+        assert!(result.intact);
+        assert_eq!(result.verified_entries, 3);
+        assert_eq!(result.first_broken_id, None);
+        assert!(!result.truncated);
+    }
+
+    #[test]
+    fn test_altered_entry_breaks_chain() {
+        let (mut rows, head) = log();
+        rows[1].details = Some("forged".to_string());
+        let result = walk_chain(ChainScheme::Current(&KEY), "", Some(&head), &rows);
+        assert!(!result.intact);
+        assert_eq!(result.first_broken_id, Some(2));
+        assert_eq!(result.verified_entries, 1);
+    }
+
+    #[test]
+    fn test_deleted_entry_breaks_next() {
+        let (mut rows, head) = log();
+        rows.remove(1);
+        let result = walk_chain(ChainScheme::Current(&KEY), "", Some(&head), &rows);
+        assert_eq!(result.first_broken_id, Some(3));
+    }
+
+    #[test]
+    fn test_changed_row_id_breaks_chain() {
+        let (mut rows, head) = log();
+        rows[2].id = 4;
+        let result = walk_chain(ChainScheme::Current(&KEY), "", Some(&head), &rows);
+        assert_eq!(result.first_broken_id, Some(4));
+    }
+
+    #[test]
+    fn test_removed_tail_is_truncation() {
+        let (mut rows, head) = log();
+        rows.pop();
+        let result = walk_chain(ChainScheme::Current(&KEY), "", Some(&head), &rows);
+        assert!(!result.intact);
+        assert!(result.truncated);
+        assert_eq!(result.first_broken_id, None);
+    }
+
+    #[test]
+    fn test_chain_verifies_from_anchor() {
+        let (rows, head) = log();
+        let anchor = rows[0].chain_hash.clone().unwrap();
+        let result = walk_chain(ChainScheme::Current(&KEY), &anchor, Some(&head), &rows[1..]);
+        assert!(result.intact);
+        assert_eq!(result.verified_entries, 2);
+    }
+
+    #[test]
+    fn test_leading_unchained_entries_are_counted() {
+        let (chained, head) = log();
+        let mut rows = vec![entry(0, "legacy")];
+        rows.extend(chained);
+        let result = walk_chain(ChainScheme::Current(&KEY), "", Some(&head), &rows);
+        assert!(result.intact);
+        assert_eq!(result.unchained_entries, 1);
+        assert_eq!(result.verified_entries, 3);
+    }
+
+    #[test]
+    fn test_wrong_key_or_scheme_does_not_verify() {
+        let (rows, head) = log();
+        let other = [8u8; 32];
+        let result = walk_chain(ChainScheme::Current(&other), "", Some(&head), &rows);
+        assert_eq!(result.first_broken_id, Some(1));
+        let result = walk_chain(ChainScheme::Legacy(&KEY), "", Some(&head), &rows);
+        assert_eq!(result.first_broken_id, Some(1));
+    }
+
+    #[test]
+    fn test_cleared_entry_records_anchor() {
+        let details = cleared_details(3, "abc123");
+        assert_eq!(cleared_anchor(&details), Some("abc123"));
+        assert_eq!(cleared_anchor("Removed 3 entries."), None);
+    }
 }
@@ -0,0 +1,154 @@
+//! Previews of attachments for the item view: downsampled PNG thumbnails of
+//! images and short text snippets of text and PDF files. Attachments are
+//! decrypted in memory only; no plaintext is written to disk.
+
+use crate::db::attachments::read_attachment;
+use crate::db::utils::UnlockedVault;
+use crate::encryption::decrypt;
+use crate::error::{Error, Result};
+use crate::state::AppState;
+use image::{ImageFormat, ImageReader, Limits};
+use serde::Serialize;
+use sqlx::Row;
+use std::io::Cursor;
+use tauri::State;
+
+const MIN_DIMENSION: u32 = 16;
+const MAX_DIMENSION: u32 = 1024;
+const MAX_IMAGE_SOURCE_SIZE: i64 = 50 * 1024 * 1024;
+/// Caps what a decoder may allocate, so a small file that claims huge
+/// dimensions cannot exhaust memory.
+const MAX_DECODE_ALLOC: u64 = 256 * 1024 * 1024;
+const MAX_TEXT_SOURCE_SIZE: i64 = 1024 * 1024;
+const MAX_PDF_SOURCE_SIZE: i64 = 10 * 1024 * 1024;
+const SNIPPET_CHARS: usize = 2000;
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AttachmentPreview {
+    Image {
+        /// PNG bytes.
+        png: Vec<u8>,
+        width: u32,
+        height: u32,
+    },
+    Text {
+        snippet: String,
+        truncated: bool,
+    },
+    /// Not an image, text or PDF file, too large to preview, or not
+    /// decodable.
+    Unsupported,
+}
+
+#[derive(Clone, Copy)]
+enum PreviewKind {
+    Image,
+    Text,
+    Pdf,
+}
+
+fn preview_kind(mime_type: &str) -> Option<PreviewKind> {
+    let mime = mime_type.to_ascii_lowercase();
+    if mime.starts_with("image/") && mime != "image/svg+xml" {
+        Some(PreviewKind::Image)
+    } else if mime.starts_with("text/")
+        || matches!(
+            mime.as_str(),
+            "application/json" | "application/xml" | "application/x-yaml" | "application/toml"
+        )
+    {
+        Some(PreviewKind::Text)
+    } else if mime == "application/pdf" {
+        Some(PreviewKind::Pdf)
+    } else {
+        None
+    }
+}
+
+fn snippet(text: &str) -> AttachmentPreview {
+    let text = text.trim();
+    let mut chars = text.char_indices();
+    match chars.nth(SNIPPET_CHARS) {
+        Some((end, _)) => AttachmentPreview::Text {
+            snippet: text[..end].to_string(),
+            truncated: true,
+        },
+        None => AttachmentPreview::Text {
+            snippet: text.to_string(),
+            truncated: false,
+        },
+    }
+}
+
+fn image_preview(data: &[u8], max_dimension: u32) -> Result<AttachmentPreview> {
+    let mut reader = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|e| Error::Internal(format!("Failed to read image: {}", e)))?;
+    let mut limits = Limits::default();
+    limits.max_alloc = Some(MAX_DECODE_ALLOC);
+    reader.limits(limits);
+    let Ok(image) = reader.decode() else {
+        return Ok(AttachmentPreview::Unsupported);
+    };
+
+    let thumbnail = image.thumbnail(max_dimension, max_dimension);
+    let mut png = Vec::new();
+    thumbnail
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| Error::Internal(format!("Failed to encode preview: {}", e)))?;
+    Ok(AttachmentPreview::Image {
+        png,
+        width: thumbnail.width(),
+        height: thumbnail.height(),
+    })
+}
+
+fn render(kind: PreviewKind, data: &[u8], max_dimension: u32) -> Result<AttachmentPreview> {
+    match kind {
+        PreviewKind::Image => image_preview(data, max_dimension),
+        PreviewKind::Text => Ok(snippet(&String::from_utf8_lossy(data))),
+        PreviewKind::Pdf => Ok(pdf_extract::extract_text_from_mem(data)
+            .map(|text| snippet(&text))
+            .unwrap_or(AttachmentPreview::Unsupported)),
+    }
+}
+
+/// `max_dimension` bounds the width and height of image previews and is
+/// clamped to 16–1024 pixels.
+#[tauri::command]
+pub async fn get_attachment_preview(
+    state: State<'_, AppState>,
+    attachment_id: i64,
+    max_dimension: u32,
+) -> Result<AttachmentPreview> {
+    let max_dimension = max_dimension.clamp(MIN_DIMENSION, MAX_DIMENSION);
+    let (kind, file_size) = {
+        let vault = UnlockedVault::acquire(&state).await?;
+        let row = sqlx::query("SELECT mime_type, file_size FROM attachments WHERE id = ?")
+            .bind(attachment_id)
+            .fetch_optional(vault.pool())
+            .await?
+            .ok_or_else(|| Error::Internal("Attachment not found".to_string()))?;
+        let mime_type = decrypt(&row.get::<String, _>("mime_type"), vault.key())?;
+        (preview_kind(&mime_type), row.get::<i64, _>("file_size"))
+    };
+
+    let Some(kind) = kind else {
+        return Ok(AttachmentPreview::Unsupported);
+    };
+    let max_size = match kind {
+        PreviewKind::Image => MAX_IMAGE_SOURCE_SIZE,
+        PreviewKind::Text => MAX_TEXT_SOURCE_SIZE,
+        PreviewKind::Pdf => MAX_PDF_SOURCE_SIZE,
+    };
+    if file_size > max_size {
+        return Ok(AttachmentPreview::Unsupported);
+    }
+
+    let attachment = read_attachment(&state, attachment_id).await?;
+    // Decoders can panic on malformed input; that only fails the preview.
+    tauri::async_runtime::spawn_blocking(move || render(kind, &attachment.data, max_dimension))
+        .await
+        .unwrap_or(Ok(AttachmentPreview::Unsupported))
+}
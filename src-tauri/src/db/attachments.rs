@@ -1,14 +1,51 @@
-use crate::db::utils::{get_db_pool, get_key};
-use crate::encryption::{decrypt_bytes, encrypt, encrypt_bytes};
+use crate::db::passwords::ensure_item_writable;
+use crate::db::scanner::{self, ScanResult, ScanVerdict};
+use crate::db::utils::UnlockedVault;
+use crate::encryption::{
+    decrypt, decrypt_bytes, decrypt_zeroized, encrypt, is_stream_ciphertext, StreamDecryptor,
+    StreamEncryptor, STREAM_CHUNK_LEN, STREAM_FRAME_LEN, STREAM_HEADER_LEN,
+};
 use crate::error::{Error, Result};
+use crate::limits;
 use crate::state::AppState;
 use crate::types::Attachment;
 use chrono::Utc;
+use serde::Serialize;
+use sqlx::{Row, SqliteConnection, SqlitePool};
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
-use tauri::State;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, State};
+use tauri_plugin_opener::OpenerExt;
 use tokio::fs;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use zeroize::Zeroizing;
 
-async fn get_attachments_dir(state: &AppState) -> Result<PathBuf> {
+pub const ATTACHMENT_PROGRESS_EVENT: &str = "attachment:progress";
+
+/// Progress is reported about every this many bytes.
+const PROGRESS_INTERVAL: u64 = 1024 * 1024;
+const MIB: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentProgress {
+    pub item_id: i64,
+    pub file_name: String,
+    pub processed_bytes: u64,
+    pub total_bytes: u64,
+}
+
+/// The directory next to the vault file that holds its attachments.
+pub(crate) fn attachments_dir_for(db_path: &Path) -> Result<PathBuf> {
+    let file_name = db_path
+        .file_name()
+        .ok_or_else(|| Error::Internal("Invalid DB path".to_string()))?
+        .to_string_lossy();
+    Ok(db_path.with_file_name(format!("{}.attachments", file_name)))
+}
+
+pub(crate) async fn get_attachments_dir(state: &AppState) -> Result<PathBuf> {
     let db_path = state
         .db_path
         .lock()
@@ -16,14 +53,7 @@ async fn get_attachments_dir(state: &AppState) -> Result<PathBuf> {
         .clone()
         .ok_or_else(|| Error::Internal("Database path not set".to_string()))?;
 
-    let mut dir = db_path.clone();
-    let file_name = dir
-        .file_name()
-        .ok_or_else(|| Error::Internal("Invalid DB path".to_string()))?
-        .to_string_lossy();
-
-    dir.set_file_name(format!("{}.attachments", file_name));
-
+    let dir = attachments_dir_for(&db_path)?;
     if !fs::try_exists(&dir).await.unwrap_or(false) {
         fs::create_dir_all(&dir).await?;
     }
@@ -31,16 +61,417 @@ async fn get_attachments_dir(state: &AppState) -> Result<PathBuf> {
     Ok(dir)
 }
 
+/// Size limits from the vault's settings.
+pub(crate) struct AttachmentLimits {
+    pub per_attachment: u64,
+    /// Combined size of all attachments in the vault, if limited.
+    pub vault_total: Option<u64>,
+}
+
+pub(crate) async fn attachment_limits(pool: &SqlitePool, key: &[u8]) -> Result<AttachmentLimits> {
+    let settings = crate::db::vault_settings::load_vault_settings(pool, key).await?;
+    Ok(AttachmentLimits {
+        per_attachment: settings
+            .max_attachment_size_mb
+            .map(|mb| u64::from(mb) * MIB)
+            .unwrap_or(limits::MAX_ATTACHMENT_SIZE),
+        vault_total: settings
+            .max_attachments_total_mb
+            .map(|mb| u64::from(mb) * MIB),
+    })
+}
+
+async fn total_attachment_size(pool: &SqlitePool) -> Result<u64> {
+    let total: i64 = sqlx::query_scalar("SELECT COALESCE(SUM(file_size), 0) FROM attachments")
+        .fetch_one(pool)
+        .await?;
+    Ok(total.max(0) as u64)
+}
+
+/// The largest attachment that can still be added, or an error explaining
+/// which limit a file of `size` bytes exceeds.
+async fn check_attachment_limits(pool: &SqlitePool, key: &[u8], size: u64) -> Result<u64> {
+    let quota = attachment_limits(pool, key).await?;
+    if size > quota.per_attachment {
+        return Err(Error::InputTooLarge(format!(
+            "The attachment is {} bytes, which exceeds the limit of {} bytes",
+            size, quota.per_attachment
+        )));
+    }
+    let Some(vault_total) = quota.vault_total else {
+        return Ok(quota.per_attachment);
+    };
+    let used = total_attachment_size(pool).await?;
+    let remaining = vault_total.saturating_sub(used);
+    if size > remaining {
+        return Err(Error::InputTooLarge(format!(
+            "Adding this attachment would exceed the vault's attachment quota of {} MiB ({} MiB used)",
+            vault_total / MIB,
+            used / MIB
+        )));
+    }
+    Ok(quota.per_attachment.min(remaining))
+}
+
+/// Creates a random key for a new attachment file. Returns the key and the
+/// key wrapped by the vault key, for the attachments row. Since every file
+/// has its own key, changing the vault key only re-wraps these small blobs.
+fn new_file_key(vault_key: &[u8]) -> Result<(Zeroizing<Vec<u8>>, String)> {
+    let file_key = Zeroizing::new(rand::random::<[u8; 32]>().to_vec());
+    let hex_key = Zeroizing::new(hex::encode(file_key.as_slice()));
+    let wrapped = encrypt(&hex_key, vault_key)?;
+    Ok((file_key, wrapped))
+}
+
+/// The key an attachment file is encrypted with. Attachments added before
+/// per-file keys have no wrapped key and use the vault key directly.
+fn attachment_file_key(wrapped: Option<&str>, vault_key: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+    let Some(wrapped) = wrapped else {
+        return Ok(Zeroizing::new(vault_key.to_vec()));
+    };
+    let hex_key = decrypt_zeroized(wrapped, vault_key)?;
+    hex::decode(hex_key.as_str())
+        .map(Zeroizing::new)
+        .map_err(|_| Error::Decryption("Invalid attachment key".to_string()))
+}
+
+/// Re-wraps every per-file key in `schema`'s attachments table from
+/// `old_key` to `new_key`.
+pub(crate) async fn rewrap_file_keys(
+    conn: &mut SqliteConnection,
+    schema: &str,
+    old_key: &[u8],
+    new_key: &[u8],
+) -> Result<()> {
+    let rows = sqlx::query(&format!(
+        "SELECT id, file_key FROM {}.attachments WHERE file_key IS NOT NULL",
+        schema
+    ))
+    .fetch_all(&mut *conn)
+    .await?;
+    let update = format!(
+        "UPDATE {}.attachments SET file_key = ? WHERE id = ?",
+        schema
+    );
+    for row in rows {
+        let id: i64 = row.get("id");
+        let wrapped: String = row.get("file_key");
+        let hex_key = decrypt_zeroized(&wrapped, old_key)?;
+        sqlx::query(&update)
+            .bind(encrypt(&hex_key, new_key)?)
+            .bind(id)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Reads until `buf` is full or the reader is exhausted.
+async fn read_full<R: AsyncRead + Unpin>(reader: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let n = reader.read(&mut buf[filled..]).await?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    Ok(filled)
+}
+
+async fn create_private_file(path: &Path) -> Result<fs::File> {
+    let mut options = fs::OpenOptions::new();
+    options.create_new(true).write(true);
+    #[cfg(unix)]
+    {
+        options.mode(0o600);
+    }
+    Ok(options.open(path).await?)
+}
+
+/// Encrypts `input` into a new file at `dest` one chunk at a time, so memory
+/// use does not depend on the file size. Returns the plaintext size and its
+/// BLAKE3 hash. Fails once more than `max_size` bytes were read, in case the
+/// file grew after its size was checked.
+async fn encrypt_file(
+    mut input: impl AsyncRead + Unpin,
+    dest: &Path,
+    key: &[u8],
+    max_size: u64,
+    mut progress: impl FnMut(u64),
+) -> Result<(u64, blake3::Hash)> {
+    let mut output = create_private_file(dest).await?;
+    let mut encryptor = StreamEncryptor::new(key)?;
+    output.write_all(encryptor.header()).await?;
+
+    let mut buf = Zeroizing::new(vec![0u8; STREAM_CHUNK_LEN]);
+    let mut hasher = blake3::Hasher::new();
+    let mut total: u64 = 0;
+    let mut reported: u64 = 0;
+    loop {
+        let n = read_full(&mut input, &mut buf).await?;
+        hasher.update(&buf[..n]);
+        total += n as u64;
+        if total > max_size {
+            return Err(Error::InputTooLarge(format!(
+                "Attachment exceeds the limit of {} bytes",
+                max_size
+            )));
+        }
+        let last = n < STREAM_CHUNK_LEN;
+        output
+            .write_all(&encryptor.encrypt_chunk(&buf[..n], last)?)
+            .await?;
+        if last || total - reported >= PROGRESS_INTERVAL {
+            progress(total);
+            reported = total;
+        }
+        if last {
+            break;
+        }
+    }
+    output.sync_all().await?;
+    Ok((total, hasher.finalize()))
+}
+
+/// Decrypts an attachment file one frame at a time into `output`, so memory
+/// use does not depend on the file size, and returns the BLAKE3 hash of the
+/// plaintext. Files in the older single-blob format are decrypted in one
+/// piece.
+async fn decrypt_file_to<W: AsyncWrite + Unpin>(
+    path: &Path,
+    key: &[u8],
+    output: &mut W,
+) -> Result<blake3::Hash> {
+    let mut input = fs::File::open(path).await?;
+    let mut hasher = blake3::Hasher::new();
+    let mut header = vec![0u8; STREAM_HEADER_LEN];
+    let n = read_full(&mut input, &mut header).await?;
+    header.truncate(n);
+    if !is_stream_ciphertext(&header) {
+        let mut data = header;
+        input.read_to_end(&mut data).await?;
+        let plaintext = Zeroizing::new(decrypt_bytes(&data, key)?);
+        hasher.update(&plaintext);
+        output.write_all(&plaintext).await?;
+        output.flush().await?;
+        return Ok(hasher.finalize());
+    }
+
+    let mut decryptor = StreamDecryptor::new(key, &header)?;
+    let mut frame = vec![0u8; STREAM_FRAME_LEN];
+    while !decryptor.is_finished() {
+        let n = read_full(&mut input, &mut frame).await?;
+        if n == 0 {
+            return Err(Error::Decryption("Attachment is truncated".to_string()));
+        }
+        let chunk = Zeroizing::new(decryptor.decrypt_frame(&frame[..n], n < STREAM_FRAME_LEN)?);
+        hasher.update(&chunk);
+        output.write_all(&chunk).await?;
+    }
+    if input.read(&mut frame[..1]).await? != 0 {
+        return Err(Error::Decryption(
+            "Attachment has data after its final frame".to_string(),
+        ));
+    }
+    output.flush().await?;
+    Ok(hasher.finalize())
+}
+
+/// Writes a copy of the attachment file at `source` to `dest`, encrypted
+/// under `new_key`. Files in the older single-blob format are converted to
+/// the streaming format on the way.
+async fn reencrypt_file(source: &Path, dest: &Path, old_key: &[u8], new_key: &[u8]) -> Result<()> {
+    let mut input = fs::File::open(source).await?;
+    let mut output = create_private_file(dest).await?;
+    let mut encryptor = StreamEncryptor::new(new_key)?;
+    output.write_all(encryptor.header()).await?;
+
+    let mut header = vec![0u8; STREAM_HEADER_LEN];
+    let n = read_full(&mut input, &mut header).await?;
+    header.truncate(n);
+    if is_stream_ciphertext(&header) {
+        let mut decryptor = StreamDecryptor::new(old_key, &header)?;
+        let mut frame = vec![0u8; STREAM_FRAME_LEN];
+        while !decryptor.is_finished() {
+            let n = read_full(&mut input, &mut frame).await?;
+            if n == 0 {
+                return Err(Error::Decryption("Attachment is truncated".to_string()));
+            }
+            let chunk = Zeroizing::new(decryptor.decrypt_frame(&frame[..n], n < STREAM_FRAME_LEN)?);
+            output
+                .write_all(&encryptor.encrypt_chunk(&chunk, decryptor.is_finished())?)
+                .await?;
+        }
+    } else {
+        let mut data = header;
+        input.read_to_end(&mut data).await?;
+        let plaintext = Zeroizing::new(decrypt_bytes(&data, old_key)?);
+        let mut offset = 0;
+        loop {
+            let end = (offset + STREAM_CHUNK_LEN).min(plaintext.len());
+            let last = end - offset < STREAM_CHUNK_LEN;
+            output
+                .write_all(&encryptor.encrypt_chunk(&plaintext[offset..end], last)?)
+                .await?;
+            if last {
+                break;
+            }
+            offset = end;
+        }
+    }
+    output.sync_all().await?;
+    Ok(())
+}
+
+/// Re-encrypts the vault's attachment files that have no key of their own
+/// for a new vault key. Every such file is first copied to `{id}.rekey` under the new key while the originals stay
+/// untouched. `apply` then swaps the copies in, keeping each original as
+/// `{id}.old` until `finish` removes them; `rollback` puts the originals
+/// back at any point before that.
+pub(crate) struct AttachmentRekey {
+    dir: PathBuf,
+    files: Vec<String>,
+    /// How many of `files` have been swapped in by `apply`.
+    applied: usize,
+}
+
+impl AttachmentRekey {
+    const STAGED_SUFFIX: &'static str = "rekey";
+    const BACKUP_SUFFIX: &'static str = "old";
+
+    pub(crate) async fn prepare(
+        pool: &SqlitePool,
+        db_path: &Path,
+        old_key: &[u8],
+        new_key: &[u8],
+    ) -> Result<Self> {
+        let mut rekey = Self {
+            dir: attachments_dir_for(db_path)?,
+            files: Vec::new(),
+            applied: 0,
+        };
+        if !fs::try_exists(&rekey.dir).await.unwrap_or(false) {
+            return Ok(rekey);
+        }
+
+        let with_own_key: HashSet<String> =
+            sqlx::query_scalar::<_, i64>("SELECT id FROM attachments WHERE file_key IS NOT NULL")
+                .fetch_all(pool)
+                .await?
+                .into_iter()
+                .map(|id| id.to_string())
+                .collect();
+        let mut names = Vec::new();
+        let mut entries = fs::read_dir(&rekey.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.parse::<i64>().is_ok()
+                && !with_own_key.contains(&name)
+                && entry.file_type().await?.is_file()
+            {
+                names.push(name);
+            }
+        }
+        names.sort();
+
+        for name in names {
+            let staged = rekey.staged_path(&name);
+            let _ = fs::remove_file(&staged).await;
+            let result = reencrypt_file(&rekey.dir.join(&name), &staged, old_key, new_key).await;
+            rekey.files.push(name);
+            if let Err(e) = result {
+                rekey.rollback().await;
+                return Err(e);
+            }
+        }
+        Ok(rekey)
+    }
+
+    fn staged_path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", name, Self::STAGED_SUFFIX))
+    }
+
+    fn backup_path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", name, Self::BACKUP_SUFFIX))
+    }
+
+    /// Replaces every original with its re-encrypted copy. On failure the
+    /// files already swapped are restored before the error is returned.
+    pub(crate) async fn apply(&mut self) -> Result<()> {
+        while self.applied < self.files.len() {
+            let name = self.files[self.applied].clone();
+            let path = self.dir.join(&name);
+            let backup = self.backup_path(&name);
+            let swapped = match fs::rename(&path, &backup).await {
+                Ok(()) => match fs::rename(self.staged_path(&name), &path).await {
+                    Ok(()) => Ok(()),
+                    Err(e) => {
+                        let _ = fs::rename(&backup, &path).await;
+                        Err(e)
+                    }
+                },
+                Err(e) => Err(e),
+            };
+            if let Err(e) = swapped {
+                self.rollback().await;
+                return Err(Error::Internal(format!(
+                    "Failed to replace attachment file {}: {}",
+                    name, e
+                )));
+            }
+            self.applied += 1;
+        }
+        Ok(())
+    }
+
+    /// Deletes the originals once the new key is in use.
+    pub(crate) async fn finish(&mut self) {
+        for name in self.files.drain(..) {
+            let _ =
+                fs::remove_file(self.dir.join(format!("{}.{}", name, Self::BACKUP_SUFFIX))).await;
+        }
+        self.applied = 0;
+    }
+
+    /// Restores the originals and deletes the re-encrypted copies. Does
+    /// nothing after `finish`.
+    pub(crate) async fn rollback(&mut self) {
+        for (index, name) in self.files.iter().enumerate() {
+            if index < self.applied {
+                let path = self.dir.join(name);
+                if let Err(e) = fs::rename(self.backup_path(name), &path).await {
+                    eprintln!(
+                        "[Attachments] Failed to restore {} after a failed re-encryption: {}",
+                        path.display(),
+                        e
+                    );
+                }
+            } else {
+                let _ = fs::remove_file(self.staged_path(name)).await;
+            }
+        }
+        self.files.clear();
+        self.applied = 0;
+    }
+}
+
+fn checksum_mismatch(file_name: &str) -> Error {
+    Error::Validation(format!(
+        "'{}' failed its integrity check: the stored file does not match the attachment that was added",
+        file_name
+    ))
+}
+
+/// Encrypts the file at `file_path` into the vault's attachment directory,
+/// reporting progress through `ATTACHMENT_PROGRESS_EVENT`.
 #[tauri::command]
 pub async fn add_attachment(
+    app: AppHandle,
     state: State<'_, AppState>,
     item_id: i64,
     file_path: String,
 ) -> Result<Attachment> {
-    let key = get_key(&state).await?;
-    let db_pool = get_db_pool(&state).await?;
-    let attachments_dir = get_attachments_dir(&state).await?;
-
     let path = Path::new(&file_path);
     if !fs::try_exists(path).await.unwrap_or(false) {
         return Err(Error::Internal("File not found".to_string()));
@@ -51,32 +482,164 @@ pub async fn add_attachment(
         .and_then(|n| n.to_str())
         .ok_or_else(|| Error::Internal("Invalid file name".to_string()))?
         .to_string();
+    let total_bytes = fs::metadata(path).await?.len();
+    let input = fs::File::open(path).await?;
+    store_attachment(&app, &state, item_id, file_name, input, total_bytes).await
+}
+
+/// Adds an attachment from bytes held by the frontend, e.g. a dropped or
+/// pasted file, so no plaintext copy has to be written to disk first.
+///
+/// The file is the raw request body; the item goes in an `x-item-id` header
+/// and the percent-encoded name in `x-file-name`. Bodies larger than
+/// `limits::MAX_ATTACHMENT_SIZE` are refused by their `Content-Length`
+/// before they are read; larger files are added by path instead.
+#[tauri::command]
+pub async fn add_attachment_from_bytes(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    request: tauri::ipc::Request<'_>,
+) -> Result<Attachment> {
+    let headers = request.headers();
+    if let Some(length) = headers.get(tauri::http::header::CONTENT_LENGTH) {
+        let length: u64 = length
+            .to_str()
+            .ok()
+            .and_then(|l| l.parse().ok())
+            .ok_or_else(|| Error::Validation("Invalid Content-Length".to_string()))?;
+        check_upload_size(length)?;
+    }
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| Error::Validation(format!("Missing {} header", name)))
+    };
+    let item_id: i64 = header("x-item-id")?
+        .parse()
+        .map_err(|_| Error::Validation("Invalid item id".to_string()))?;
+    let file_name = percent_encoding::percent_decode_str(header("x-file-name")?)
+        .decode_utf8()
+        .map_err(|_| Error::Validation("Invalid file name".to_string()))?;
 
-    let file_data = fs::read(path).await?;
-    let file_size = file_data.len() as i64;
+    let tauri::ipc::InvokeBody::Raw(bytes) = request.body() else {
+        return Err(Error::Validation(
+            "Expected the attachment as a raw request body".to_string(),
+        ));
+    };
+    check_upload_size(bytes.len() as u64)?;
 
-    let mime_type = mime_guess::from_path(path)
+    // Only the final component is kept in case the name came with a path.
+    let file_name = Path::new(file_name.as_ref())
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .ok_or_else(|| Error::Validation("Invalid file name".to_string()))?
+        .to_string();
+    let total_bytes = bytes.len() as u64;
+    store_attachment(
+        &app,
+        &state,
+        item_id,
+        file_name,
+        bytes.as_slice(),
+        total_bytes,
+    )
+    .await
+}
+
+fn check_upload_size(size: u64) -> Result<()> {
+    if size > limits::MAX_ATTACHMENT_SIZE {
+        return Err(Error::InputTooLarge(format!(
+            "The attachment is {} bytes; files over {} bytes must be added from disk",
+            size,
+            limits::MAX_ATTACHMENT_SIZE
+        )));
+    }
+    Ok(())
+}
+
+/// Encrypts `input` into the vault's attachment directory and records it
+/// under `item_id`, reporting progress through `ATTACHMENT_PROGRESS_EVENT`.
+async fn store_attachment(
+    app: &AppHandle,
+    state: &State<'_, AppState>,
+    item_id: i64,
+    file_name: String,
+    input: impl AsyncRead + Unpin,
+    total_bytes: u64,
+) -> Result<Attachment> {
+    let vault = UnlockedVault::acquire_writable(state).await?;
+    let attachments_dir = get_attachments_dir(state).await?;
+    ensure_item_writable(vault.pool(), item_id).await?;
+
+    limits::check_len(
+        "Attachment name",
+        &file_name,
+        limits::MAX_ATTACHMENT_NAME_LEN,
+    )?;
+    let max_size = check_attachment_limits(vault.pool(), vault.key(), total_bytes).await?;
+
+    let mime_type = mime_guess::from_path(&file_name)
         .first_or_octet_stream()
         .to_string();
 
-    let encrypted_data = encrypt_bytes(&file_data, key.as_slice())?;
+    let (file_key, file_key_enc) = new_file_key(vault.key())?;
+    let part_path =
+        attachments_dir.join(format!("{}.part", hex::encode(rand::random::<[u8; 16]>())));
+    let report = |processed_bytes| {
+        let _ = app.emit(
+            ATTACHMENT_PROGRESS_EVENT,
+            AttachmentProgress {
+                item_id,
+                file_name: file_name.clone(),
+                processed_bytes,
+                total_bytes,
+            },
+        );
+    };
+    let (file_size, checksum) =
+        match encrypt_file(input, &part_path, file_key.as_slice(), max_size, report).await {
+            Ok((size, checksum)) => (size as i64, checksum),
+            Err(e) => {
+                let _ = fs::remove_file(&part_path).await;
+                return Err(e);
+            }
+        };
 
-    let name_enc = encrypt(&file_name, key.as_slice())?;
-    let mime_enc = encrypt(&mime_type, key.as_slice())?;
+    let name_enc = encrypt(&file_name, vault.key())?;
+    let mime_enc = encrypt(&mime_type, vault.key())?;
+    let checksum_enc = encrypt(checksum.to_hex().as_str(), vault.key())?;
     let now = Utc::now().to_rfc3339();
 
-    let id = sqlx::query("INSERT INTO attachments (item_id, file_name, file_size, mime_type, created_at) VALUES (?, ?, ?, ?, ?)")
+    let inserted = sqlx::query("INSERT INTO attachments (item_id, file_name, file_size, mime_type, created_at, checksum, file_key) VALUES (?, ?, ?, ?, ?, ?, ?)")
         .bind(item_id)
         .bind(name_enc)
         .bind(file_size)
         .bind(mime_enc)
         .bind(&now)
-        .execute(&db_pool)
-        .await?
-        .last_insert_rowid();
+        .bind(checksum_enc)
+        .bind(file_key_enc)
+        .execute(vault.pool())
+        .await;
+    let id = match inserted {
+        Ok(result) => result.last_insert_rowid(),
+        Err(e) => {
+            let _ = fs::remove_file(&part_path).await;
+            return Err(e.into());
+        }
+    };
 
     let storage_path = attachments_dir.join(id.to_string());
-    fs::write(storage_path, encrypted_data).await?;
+    if let Err(e) = fs::rename(&part_path, &storage_path).await {
+        let _ = fs::remove_file(&part_path).await;
+        let _ = sqlx::query("DELETE FROM attachments WHERE id = ?")
+            .bind(id)
+            .execute(vault.pool())
+            .await;
+        return Err(e.into());
+    }
 
     Ok(Attachment {
         id,
@@ -91,11 +654,19 @@ pub async fn add_attachment(
 #[tauri::command]
 pub async fn delete_attachment(state: State<'_, AppState>, id: i64) -> Result<()> {
     let attachments_dir = get_attachments_dir(&state).await?;
-    let db_pool = get_db_pool(&state).await?;
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+
+    let item_id: Option<i64> = sqlx::query_scalar("SELECT item_id FROM attachments WHERE id = ?")
+        .bind(id)
+        .fetch_optional(vault.pool())
+        .await?;
+    if let Some(item_id) = item_id {
+        ensure_item_writable(vault.pool(), item_id).await?;
+    }
 
     sqlx::query("DELETE FROM attachments WHERE id = ?")
         .bind(id)
-        .execute(&db_pool)
+        .execute(vault.pool())
         .await?;
 
     let storage_path = attachments_dir.join(id.to_string());
@@ -106,45 +677,380 @@ pub async fn delete_attachment(state: State<'_, AppState>, id: i64) -> Result<()
     Ok(())
 }
 
+/// The decrypted title of `item_id`, failing if there is no such item.
+async fn item_title(pool: &SqlitePool, key: &[u8], item_id: i64) -> Result<String> {
+    let title: String = sqlx::query_scalar("SELECT title FROM password_items WHERE id = ?")
+        .bind(item_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| Error::Validation(format!("Item {} not found", item_id)))?;
+    decrypt(&title, key)
+}
+
+fn attachment_not_found(id: i64) -> Error {
+    Error::Validation(format!("Attachment {} not found", id))
+}
+
+/// Moves an attachment to another item. Only the row changes; the file
+/// stays where it is and is not decrypted.
+#[tauri::command]
+pub async fn move_attachment(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    attachment_id: i64,
+    target_item_id: i64,
+) -> Result<()> {
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let row = sqlx::query("SELECT item_id, file_name FROM attachments WHERE id = ?")
+        .bind(attachment_id)
+        .fetch_optional(vault.pool())
+        .await?
+        .ok_or_else(|| attachment_not_found(attachment_id))?;
+    let source_item_id: i64 = row.get("item_id");
+    if source_item_id == target_item_id {
+        return Ok(());
+    }
+    ensure_item_writable(vault.pool(), source_item_id).await?;
+    ensure_item_writable(vault.pool(), target_item_id).await?;
+    let target_title = item_title(vault.pool(), vault.key(), target_item_id).await?;
+    let file_name = decrypt(&row.get::<String, _>("file_name"), vault.key())?;
+
+    let mut tx = vault.pool().begin().await?;
+    sqlx::query("UPDATE attachments SET item_id = ? WHERE id = ?")
+        .bind(target_item_id)
+        .bind(attachment_id)
+        .execute(&mut *tx)
+        .await?;
+    let _ = crate::db::activity::log_activity_impl(
+        tx.as_mut(),
+        vault.key(),
+        "attachment_moved",
+        Some(target_item_id),
+        Some(&target_title),
+        Some(&format!(
+            "Attachment '{}' moved from item {}",
+            file_name, source_item_id
+        )),
+    )
+    .await;
+    tx.commit().await?;
+
+    crate::events::items_updated(&app, vec![source_item_id, target_item_id]);
+    Ok(())
+}
+
+/// Copies an attachment to another item. The encrypted file is copied as
+/// is, so the copy shares the original's file key and checksum.
+#[tauri::command]
+pub async fn copy_attachment(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    attachment_id: i64,
+    target_item_id: i64,
+) -> Result<Attachment> {
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let attachments_dir = get_attachments_dir(&state).await?;
+    let row = sqlx::query(
+        "SELECT file_name, file_size, mime_type, checksum, file_key FROM attachments WHERE id = ?",
+    )
+    .bind(attachment_id)
+    .fetch_optional(vault.pool())
+    .await?
+    .ok_or_else(|| attachment_not_found(attachment_id))?;
+    let target_title = item_title(vault.pool(), vault.key(), target_item_id).await?;
+    ensure_item_writable(vault.pool(), target_item_id).await?;
+
+    let name_enc: String = row.get("file_name");
+    let mime_enc: String = row.get("mime_type");
+    let file_size: i64 = row.get("file_size");
+    let file_name = decrypt(&name_enc, vault.key())?;
+    let mime_type = decrypt(&mime_enc, vault.key())?;
+    check_attachment_limits(vault.pool(), vault.key(), file_size.max(0) as u64).await?;
+
+    let source_path = attachments_dir.join(attachment_id.to_string());
+    let part_path =
+        attachments_dir.join(format!("{}.part", hex::encode(rand::random::<[u8; 16]>())));
+    if let Err(e) = fs::copy(&source_path, &part_path).await {
+        let _ = fs::remove_file(&part_path).await;
+        return Err(e.into());
+    }
+
+    let now = Utc::now().to_rfc3339();
+    let inserted = sqlx::query("INSERT INTO attachments (item_id, file_name, file_size, mime_type, created_at, checksum, file_key) VALUES (?, ?, ?, ?, ?, ?, ?)")
+        .bind(target_item_id)
+        .bind(&name_enc)
+        .bind(file_size)
+        .bind(&mime_enc)
+        .bind(&now)
+        .bind(row.get::<Option<String>, _>("checksum"))
+        .bind(row.get::<Option<String>, _>("file_key"))
+        .execute(vault.pool())
+        .await;
+    let id = match inserted {
+        Ok(result) => result.last_insert_rowid(),
+        Err(e) => {
+            let _ = fs::remove_file(&part_path).await;
+            return Err(e.into());
+        }
+    };
+
+    let storage_path = attachments_dir.join(id.to_string());
+    if let Err(e) = fs::rename(&part_path, &storage_path).await {
+        let _ = fs::remove_file(&part_path).await;
+        let _ = sqlx::query("DELETE FROM attachments WHERE id = ?")
+            .bind(id)
+            .execute(vault.pool())
+            .await;
+        return Err(e.into());
+    }
+
+    let _ = crate::db::activity::log_activity_impl(
+        vault.pool(),
+        vault.key(),
+        "attachment_copied",
+        Some(target_item_id),
+        Some(&target_title),
+        Some(&format!(
+            "Attachment '{}' copied from attachment {}",
+            file_name, attachment_id
+        )),
+    )
+    .await;
+
+    crate::events::items_updated(&app, vec![target_item_id]);
+    Ok(Attachment {
+        id,
+        item_id: target_item_id,
+        file_name,
+        file_size,
+        mime_type,
+        created_at: now,
+    })
+}
+
+pub(crate) struct DecryptedAttachment {
+    pub file_name: String,
+    pub data: Vec<u8>,
+}
+
+/// An attachment file on disk together with what is needed to decrypt and
+/// check it.
+struct StoredAttachment {
+    file_name: String,
+    path: PathBuf,
+    file_key: Zeroizing<Vec<u8>>,
+    checksum: Option<String>,
+}
+
+impl StoredAttachment {
+    async fn load(state: &AppState, attachment_id: i64) -> Result<Self> {
+        let vault = UnlockedVault::acquire(state).await?;
+        let attachments_dir = get_attachments_dir(state).await?;
+
+        let path = attachments_dir.join(attachment_id.to_string());
+        if !fs::try_exists(&path).await.unwrap_or(false) {
+            return Err(Error::Internal(
+                "Attachment file not found on disk".to_string(),
+            ));
+        }
+
+        let row = sqlx::query("SELECT file_name, checksum, file_key FROM attachments WHERE id = ?")
+            .bind(attachment_id)
+            .fetch_optional(vault.pool())
+            .await?;
+        let name_enc: Option<String> = row.as_ref().map(|r| r.get("file_name"));
+        let checksum_enc: Option<String> = row.as_ref().and_then(|r| r.get("checksum"));
+        let file_key_enc: Option<String> = row.as_ref().and_then(|r| r.get("file_key"));
+
+        let file_key = attachment_file_key(file_key_enc.as_deref(), vault.key())?;
+        let file_name = name_enc
+            .and_then(|n| decrypt(&n, vault.key()).ok())
+            .unwrap_or_else(|| format!("attachment-{}", attachment_id));
+        // Attachments added before checksums were recorded have none.
+        let checksum = checksum_enc.map(|c| decrypt(&c, vault.key())).transpose()?;
+
+        Ok(Self {
+            file_name,
+            path,
+            file_key,
+            checksum,
+        })
+    }
+
+    /// Decrypts the file into `output` and checks it against the recorded
+    /// checksum.
+    async fn decrypt_to<W: AsyncWrite + Unpin>(&self, output: &mut W) -> Result<()> {
+        let hash = decrypt_file_to(&self.path, &self.file_key, output).await?;
+        match &self.checksum {
+            Some(expected) if hash.to_hex().as_str() != expected => {
+                Err(checksum_mismatch(&self.file_name))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+pub(crate) async fn read_attachment(
+    state: &AppState,
+    attachment_id: i64,
+) -> Result<DecryptedAttachment> {
+    let stored = StoredAttachment::load(state, attachment_id).await?;
+    // The ciphertext is a little longer than the plaintext, so this is enough.
+    let capacity = fs::metadata(&stored.path).await?.len() as usize;
+    let mut data = Vec::with_capacity(capacity);
+    stored.decrypt_to(&mut data).await?;
+    Ok(DecryptedAttachment {
+        file_name: stored.file_name,
+        data,
+    })
+}
+
+async fn scan(
+    app: &tauri::AppHandle,
+    state: &AppState,
+    attachment_id: i64,
+    attachment: &DecryptedAttachment,
+) -> Result<Option<ScanResult>> {
+    let vault = UnlockedVault::acquire(state).await?;
+    scanner::scan_attachment_bytes(
+        app,
+        vault.pool(),
+        vault.key(),
+        attachment_id,
+        &attachment.file_name,
+        &attachment.data,
+    )
+    .await
+}
+
+/// Decrypts an attachment straight into a private file next to `save_path`,
+/// scans it there and only then moves it into place, so the plaintext is
+/// never held in memory as a whole.
 #[tauri::command]
 pub async fn save_attachment_to_disk(
+    app: tauri::AppHandle,
     state: State<'_, AppState>,
     attachment_id: i64,
     save_path: String,
 ) -> Result<()> {
-    let key = get_key(&state).await?;
-    let attachments_dir = get_attachments_dir(&state).await?;
+    let dest = PathBuf::from(&save_path);
+    let dest_name = dest
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| Error::Validation("Invalid save path".to_string()))?;
+    let stored = StoredAttachment::load(&state, attachment_id).await?;
 
-    let storage_path = attachments_dir.join(attachment_id.to_string());
-    if !fs::try_exists(&storage_path).await.unwrap_or(false) {
-        return Err(Error::Internal(
-            "Attachment file not found on disk".to_string(),
-        ));
+    // The original name is kept at the end so scanners still see its
+    // extension.
+    let part_path = dest.with_file_name(format!(
+        ".{}-{}",
+        hex::encode(rand::random::<[u8; 8]>()),
+        dest_name
+    ));
+    let result: Result<()> = async {
+        let mut output = create_private_file(&part_path).await?;
+        stored.decrypt_to(&mut output).await?;
+        output.sync_all().await?;
+        drop(output);
+
+        let scanned = {
+            let vault = UnlockedVault::acquire(&state).await?;
+            scanner::scan_attachment_file(
+                &app,
+                vault.pool(),
+                vault.key(),
+                &stored.file_name,
+                &part_path,
+            )
+            .await?
+        };
+        require_clean_scan(&stored.file_name, scanned, "saved")?;
+        fs::rename(&part_path, &dest).await?;
+        Ok(())
     }
+    .await;
+    if result.is_err() {
+        let _ = fs::remove_file(&part_path).await;
+    }
+    result
+}
 
-    let data_blob = fs::read(storage_path).await?;
-    let file_data = decrypt_bytes(&data_blob, key.as_slice())?;
+/// Files are only written out once the configured scanner passes them.
+/// `action` completes "was not ..." in the error for a flagged file.
+fn require_clean_scan(file_name: &str, scan: Option<ScanResult>, action: &str) -> Result<()> {
+    match scan.map(|s| s.verdict) {
+        None | Some(ScanVerdict::Clean) => Ok(()),
+        Some(ScanVerdict::Infected) => Err(Error::Validation(format!(
+            "'{}' was flagged by the attachment scanner and was not {}",
+            file_name, action
+        ))),
+        Some(ScanVerdict::Failed) => Err(Error::Validation(format!(
+            "'{}' could not be scanned. Check the scanner command in the security settings.",
+            file_name
+        ))),
+    }
+}
 
-    write_sensitive_bytes(Path::new(&save_path), &file_data).await?;
-    Ok(())
+/// How long an opened attachment stays on disk unless the vault locks first.
+const OPEN_ATTACHMENT_LIFETIME: Duration = Duration::from_secs(10 * 60);
+const MAX_OPEN_ATTACHMENT_LIFETIME: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Decrypts an attachment to a private temporary file and opens it with the
+/// system's default application. The file is overwritten and removed after
+/// `timeout_seconds` (ten minutes by default) or when the vault locks.
+#[tauri::command]
+pub async fn open_attachment(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    attachment_id: i64,
+    timeout_seconds: Option<u64>,
+) -> Result<()> {
+    let lifetime = timeout_seconds
+        .map(Duration::from_secs)
+        .unwrap_or(OPEN_ATTACHMENT_LIFETIME)
+        .min(MAX_OPEN_ATTACHMENT_LIFETIME);
+    let attachment = read_attachment(&state, attachment_id).await?;
+    let scanned = scan(&app, &state, attachment_id, &attachment).await?;
+    require_clean_scan(&attachment.file_name, scanned, "opened")?;
+
+    let data = Zeroizing::new(attachment.data);
+    let path = crate::secure_temp::create(&attachment.file_name, &data, lifetime).await?;
+    app.opener()
+        .open_path(path.to_string_lossy(), None::<&str>)
+        .map_err(|e| Error::Internal(format!("Failed to open attachment: {}", e)))
+}
+
+/// Runs the configured scanner over an attachment without saving it, e.g.
+/// before it is opened. Returns `None` when no scanner is configured.
+#[tauri::command]
+pub async fn scan_attachment(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    attachment_id: i64,
+) -> Result<Option<ScanResult>> {
+    let attachment = read_attachment(&state, attachment_id).await?;
+    scan(&app, &state, attachment_id, &attachment).await
 }
 
 #[tauri::command]
 pub async fn import_file_as_attachment(
+    app: AppHandle,
     state: State<'_, AppState>,
     item_id: i64,
     file_path: PathBuf,
 ) -> Result<Attachment> {
-    add_attachment(state, item_id, file_path.to_string_lossy().to_string()).await
+    add_attachment(app, state, item_id, file_path.to_string_lossy().to_string()).await
 }
 
 #[tauri::command]
 pub async fn export_attachment_to_file(
+    app: tauri::AppHandle,
     state: State<'_, AppState>,
     attachment_id: i64,
     save_path: PathBuf,
 ) -> Result<()> {
     save_attachment_to_disk(
+        app,
         state,
         attachment_id,
         save_path.to_string_lossy().to_string(),
@@ -152,50 +1058,360 @@ pub async fn export_attachment_to_file(
     .await
 }
 
-async fn write_sensitive_bytes(path: &Path, bytes: &[u8]) -> Result<()> {
-    let tmp_path = path.with_extension("tmp");
-    if fs::try_exists(&tmp_path).await.unwrap_or(false) {
-        let _ = fs::remove_file(&tmp_path).await;
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AttachmentProblemKind {
+    /// The file is not in the attachments directory.
+    Missing,
+    /// The file is truncated, corrupted or encrypted with another key.
+    Unreadable,
+    /// The file decrypts but is not the attachment that was added.
+    ChecksumMismatch,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentProblem {
+    pub attachment_id: i64,
+    pub item_id: i64,
+    pub kind: AttachmentProblemKind,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentVerification {
+    pub checked: usize,
+    /// Attachments added before checksums were recorded; they are only
+    /// checked for whether they decrypt.
+    pub without_checksum: usize,
+    pub problems: Vec<AttachmentProblem>,
+    /// Files in the attachments directory that belong to no attachment,
+    /// e.g. left over from an interrupted upload.
+    pub unreferenced_files: Vec<String>,
+}
+
+async fn verify_attachment(
+    path: &Path,
+    key: &[u8],
+    file_key_enc: Option<&str>,
+    checksum_enc: Option<&str>,
+) -> Result<Option<AttachmentProblemKind>> {
+    if !fs::try_exists(path).await.unwrap_or(false) {
+        return Ok(Some(AttachmentProblemKind::Missing));
     }
+    let Ok(file_key) = attachment_file_key(file_key_enc, key) else {
+        return Ok(Some(AttachmentProblemKind::Unreadable));
+    };
+    let Ok(hash) = decrypt_file_to(path, &file_key, &mut tokio::io::sink()).await else {
+        return Ok(Some(AttachmentProblemKind::Unreadable));
+    };
+    let Some(checksum_enc) = checksum_enc else {
+        return Ok(None);
+    };
+    let expected = decrypt(checksum_enc, key)?;
+    Ok((hash.to_hex().as_str() != expected).then_some(AttachmentProblemKind::ChecksumMismatch))
+}
 
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::OpenOptionsExt;
-        let mut file = fs::OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .mode(0o600)
-            .open(&tmp_path)
-            .await?;
-        tokio::io::AsyncWriteExt::write_all(&mut file, bytes).await?;
-        file.sync_all().await?;
+/// Decrypts every attachment file and compares it with its recorded
+/// checksum to find bit rot, truncated or swapped files. Files are streamed,
+/// so large attachments are never held in memory.
+#[tauri::command]
+pub async fn verify_all_attachments(state: State<'_, AppState>) -> Result<AttachmentVerification> {
+    let vault = UnlockedVault::acquire(&state).await?;
+    let attachments_dir = get_attachments_dir(&state).await?;
+    let rows = sqlx::query("SELECT id, item_id, checksum, file_key FROM attachments ORDER BY id")
+        .fetch_all(vault.pool())
+        .await?;
+
+    let mut verification = AttachmentVerification {
+        checked: rows.len(),
+        without_checksum: 0,
+        problems: Vec::new(),
+        unreferenced_files: Vec::new(),
+    };
+    let mut referenced = HashSet::new();
+    for row in &rows {
+        let id: i64 = row.get("id");
+        let checksum: Option<String> = row.get("checksum");
+        let file_key_enc: Option<String> = row.get("file_key");
+        if checksum.is_none() {
+            verification.without_checksum += 1;
+        }
+        referenced.insert(id.to_string());
+        let path = attachments_dir.join(id.to_string());
+        if let Some(kind) = verify_attachment(
+            &path,
+            vault.key(),
+            file_key_enc.as_deref(),
+            checksum.as_deref(),
+        )
+        .await?
+        {
+            verification.problems.push(AttachmentProblem {
+                attachment_id: id,
+                item_id: row.get("item_id"),
+                kind,
+            });
+        }
     }
 
-    #[cfg(not(unix))]
-    {
-        let mut file = fs::OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(&tmp_path)
-            .await?;
-        tokio::io::AsyncWriteExt::write_all(&mut file, bytes).await?;
-        file.sync_all().await?;
+    let mut entries = fs::read_dir(&attachments_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !referenced.contains(&name) {
+            verification.unreferenced_files.push(name);
+        }
+    }
+    verification.unreferenced_files.sort();
+    Ok(verification)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemAttachmentUsage {
+    pub item_id: i64,
+    pub title: Option<String>,
+    pub attachment_count: u64,
+    pub total_bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentUsage {
+    pub attachment_count: u64,
+    /// Combined plaintext size of all attachments.
+    pub total_bytes: u64,
+    /// Largest first.
+    pub items: Vec<ItemAttachmentUsage>,
+    /// Everything in the attachments directory, including encryption
+    /// overhead and leftover files.
+    pub directory_bytes: u64,
+    /// The vault database and its write-ahead log.
+    pub database_bytes: u64,
+    pub max_attachment_bytes: u64,
+    pub max_total_bytes: Option<u64>,
+}
+
+async fn file_size_or_zero(path: &Path) -> u64 {
+    fs::metadata(path).await.map(|m| m.len()).unwrap_or(0)
+}
+
+/// Where the space of the vault goes: attachment sizes per item, the
+/// attachments directory and the database file, with the configured limits.
+#[tauri::command]
+pub async fn get_attachment_usage(state: State<'_, AppState>) -> Result<AttachmentUsage> {
+    let vault = UnlockedVault::acquire(&state).await?;
+    let quota = attachment_limits(vault.pool(), vault.key()).await?;
+    let rows = sqlx::query(
+        "SELECT a.item_id, COUNT(*) AS count, COALESCE(SUM(a.file_size), 0) AS bytes, p.title \
+         FROM attachments a LEFT JOIN password_items p ON p.id = a.item_id \
+         GROUP BY a.item_id ORDER BY bytes DESC",
+    )
+    .fetch_all(vault.pool())
+    .await?;
+
+    let mut items = Vec::with_capacity(rows.len());
+    for row in rows {
+        let title: Option<String> = row.get("title");
+        items.push(ItemAttachmentUsage {
+            item_id: row.get("item_id"),
+            title: title.and_then(|t| decrypt(&t, vault.key()).ok()),
+            attachment_count: row.get::<i64, _>("count").max(0) as u64,
+            total_bytes: row.get::<i64, _>("bytes").max(0) as u64,
+        });
     }
 
-    if let Err(err) = fs::rename(&tmp_path, path).await {
-        if err.kind() == std::io::ErrorKind::Other
-            || err.raw_os_error() == Some(17)
-            || err.raw_os_error() == Some(18)
-        {
-            fs::copy(&tmp_path, path).await?;
+    let attachments_dir = get_attachments_dir(&state).await?;
+    let mut directory_bytes = 0;
+    let mut entries = fs::read_dir(&attachments_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        directory_bytes += entry.metadata().await.map(|m| m.len()).unwrap_or(0);
+    }
 
-            let _ = fs::remove_file(&tmp_path).await;
-        } else {
-            return Err(Error::Io(err));
+    let db_path = state
+        .db_path
+        .lock()
+        .await
+        .clone()
+        .ok_or_else(|| Error::Internal("Database path not set".to_string()))?;
+    let mut wal_path = db_path.clone().into_os_string();
+    wal_path.push("-wal");
+    let database_bytes =
+        file_size_or_zero(&db_path).await + file_size_or_zero(Path::new(&wal_path)).await;
+
+    Ok(AttachmentUsage {
+        attachment_count: items.iter().map(|i| i.attachment_count).sum(),
+        total_bytes: items.iter().map(|i| i.total_bytes).sum(),
+        items,
+        directory_bytes,
+        database_bytes,
+        max_attachment_bytes: quota.per_attachment,
+        max_total_bytes: quota.vault_total,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encryption::encrypt_bytes;
+
+    const KEY: &[u8] = b"an-example-key-that-is-32-bytes!";
+    const OTHER_KEY: &[u8] = b"another-example-key-of-32-bytes!";
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "pulsar-attachments-test-{}",
+                hex::encode(rand::random::<[u8; 8]>())
+            ));
+            std::fs::create_dir_all(&dir).unwrap();
+            Self(dir)
         }
     }
 
-    Ok(())
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn sample(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    async fn decrypt_to_vec(path: &Path, key: &[u8]) -> Result<Vec<u8>> {
+        let mut plaintext = Vec::new();
+        decrypt_file_to(path, key, &mut plaintext).await?;
+        Ok(plaintext)
+    }
+
+    #[tokio::test]
+    async fn test_encrypt_file_roundtrip() {
+        let dir = TempDir::new();
+        for len in [0, 1, STREAM_CHUNK_LEN, STREAM_CHUNK_LEN * 2 + 7] {
+            let data = sample(len);
+            let path = dir.0.join(len.to_string());
+            let (size, hash) = encrypt_file(data.as_slice(), &path, KEY, u64::MAX, |_| {})
+                .await
+                .unwrap();
+            assert_eq!(size, len as u64);
+            assert_eq!(hash, blake3::hash(&data));
+
+            let mut plaintext = Vec::new();
+            let decrypted_hash = decrypt_file_to(&path, KEY, &mut plaintext).await.unwrap();
+            assert_eq!(plaintext, data);
+            assert_eq!(decrypted_hash, hash);
+            assert!(decrypt_to_vec(&path, OTHER_KEY).await.is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_encrypt_file_enforces_the_size_limit() {
+        let dir = TempDir::new();
+        let data = sample(STREAM_CHUNK_LEN + 1);
+        let result = encrypt_file(
+            data.as_slice(),
+            &dir.0.join("big"),
+            KEY,
+            STREAM_CHUNK_LEN as u64,
+            |_| {},
+        )
+        .await;
+        assert!(matches!(result, Err(Error::InputTooLarge(_))));
+    }
+
+    #[tokio::test]
+    async fn test_decrypt_file_detects_truncation() {
+        let dir = TempDir::new();
+        let path = dir.0.join("file");
+        let data = sample(STREAM_CHUNK_LEN * 2 + 7);
+        encrypt_file(data.as_slice(), &path, KEY, u64::MAX, |_| {})
+            .await
+            .unwrap();
+        let encrypted = std::fs::read(&path).unwrap();
+
+        // Whole frames dropped from the end, the final frame cut short, and
+        // extra bytes after the final frame.
+        let damaged = [
+            encrypted[..STREAM_HEADER_LEN + STREAM_FRAME_LEN].to_vec(),
+            encrypted[..STREAM_HEADER_LEN + STREAM_FRAME_LEN * 2].to_vec(),
+            encrypted[..encrypted.len() - 1].to_vec(),
+            [encrypted.as_slice(), &[0u8]].concat(),
+        ];
+        for (i, bytes) in damaged.iter().enumerate() {
+            let damaged_path = dir.0.join(format!("damaged-{i}"));
+            std::fs::write(&damaged_path, bytes).unwrap();
+            assert!(
+                decrypt_to_vec(&damaged_path, KEY).await.is_err(),
+                "case {i}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_decrypt_file_detects_reordered_frames() {
+        let dir = TempDir::new();
+        let path = dir.0.join("file");
+        let data = sample(STREAM_CHUNK_LEN * 2 + 7);
+        encrypt_file(data.as_slice(), &path, KEY, u64::MAX, |_| {})
+            .await
+            .unwrap();
+        let mut encrypted = std::fs::read(&path).unwrap();
+
+        let (first, second) = encrypted[STREAM_HEADER_LEN..].split_at_mut(STREAM_FRAME_LEN);
+        first.swap_with_slice(&mut second[..STREAM_FRAME_LEN]);
+        std::fs::write(&path, &encrypted).unwrap();
+        assert!(decrypt_to_vec(&path, KEY).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reencrypt_file_changes_the_key() {
+        let dir = TempDir::new();
+        let source = dir.0.join("source");
+        let dest = dir.0.join("dest");
+        let data = sample(STREAM_CHUNK_LEN * 2 + 7);
+        encrypt_file(data.as_slice(), &source, KEY, u64::MAX, |_| {})
+            .await
+            .unwrap();
+
+        reencrypt_file(&source, &dest, KEY, OTHER_KEY)
+            .await
+            .unwrap();
+        assert_eq!(decrypt_to_vec(&dest, OTHER_KEY).await.unwrap(), data);
+        assert!(decrypt_to_vec(&dest, KEY).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_legacy_files_are_read_and_converted() {
+        let dir = TempDir::new();
+        let source = dir.0.join("legacy");
+        let dest = dir.0.join("converted");
+        let data = sample(STREAM_CHUNK_LEN + 7);
+        std::fs::write(&source, encrypt_bytes(&data, KEY).unwrap()).unwrap();
+        assert_eq!(decrypt_to_vec(&source, KEY).await.unwrap(), data);
+
+        reencrypt_file(&source, &dest, KEY, OTHER_KEY)
+            .await
+            .unwrap();
+        assert!(is_stream_ciphertext(&std::fs::read(&dest).unwrap()));
+        assert_eq!(decrypt_to_vec(&dest, OTHER_KEY).await.unwrap(), data);
+    }
+
+    #[tokio::test]
+    async fn test_reencrypt_file_rejects_a_truncated_source() {
+        let dir = TempDir::new();
+        let source = dir.0.join("source");
+        let data = sample(STREAM_CHUNK_LEN * 2 + 7);
+        encrypt_file(data.as_slice(), &source, KEY, u64::MAX, |_| {})
+            .await
+            .unwrap();
+        let encrypted = std::fs::read(&source).unwrap();
+        std::fs::write(&source, &encrypted[..STREAM_HEADER_LEN + STREAM_FRAME_LEN]).unwrap();
+
+        assert!(reencrypt_file(&source, &dir.0.join("dest"), KEY, OTHER_KEY)
+            .await
+            .is_err());
+    }
 }
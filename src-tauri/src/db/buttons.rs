@@ -1,32 +1,93 @@
-use crate::db::utils::{get_db_pool, get_key, CryptoHelper};
-use crate::error::Result;
+use crate::db::palettes::{same_color, validate_palette_color};
+use crate::db::utils::{CryptoHelper, UnlockedVault};
+use crate::error::{Error, Result};
+use crate::limits;
 use crate::state::AppState;
-use crate::types::Button;
+use crate::types::{Button, TagTreeNode};
 use chrono::Utc;
 use sqlx::Row;
+use std::collections::{HashMap, HashSet};
 use tauri::State;
 
+/// Recursive CTE binding one tag id and yielding it plus all nested tags as
+/// `tag_subtree(id)`. `UNION` stops at already visited ids.
+pub(crate) const TAG_SUBTREE_CTE: &str = "WITH RECURSIVE tag_subtree(id) AS (SELECT ? UNION SELECT b.id FROM buttons b JOIN tag_subtree t ON b.parent_id = t.id)";
+
+/// Ensures `parent_id` exists and that nesting `id` under it doesn't create a
+/// cycle.
+async fn check_parent(
+    conn: &mut sqlx::SqliteConnection,
+    id: Option<i64>,
+    parent_id: Option<i64>,
+) -> Result<()> {
+    let Some(parent_id) = parent_id else {
+        return Ok(());
+    };
+
+    let mut current = Some(parent_id);
+    let mut seen = HashSet::new();
+    while let Some(tag_id) = current {
+        if Some(tag_id) == id {
+            return Err(Error::Validation(
+                "A tag cannot be nested under itself or one of its children".to_string(),
+            ));
+        }
+        if !seen.insert(tag_id) {
+            break;
+        }
+        let row: Option<(Option<i64>,)> =
+            sqlx::query_as("SELECT parent_id FROM buttons WHERE id = ?")
+                .bind(tag_id)
+                .fetch_optional(&mut *conn)
+                .await?;
+        match row {
+            Some((parent,)) => current = parent,
+            None if tag_id == parent_id => {
+                return Err(Error::Validation(format!(
+                    "Parent tag {} not found",
+                    parent_id
+                )))
+            }
+            None => break,
+        }
+    }
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn save_button(
+    app: tauri::AppHandle,
     state: State<'_, AppState>,
     text: String,
     icon: String,
     color: String,
+    parent_id: Option<i64>,
 ) -> Result<()> {
-    let key = get_key(&state).await?;
-    let db_pool = get_db_pool(&state).await?;
-    let helper = CryptoHelper::new(key.as_slice())?;
+    limits::check_len("Tag", &text, limits::MAX_TITLE_LEN)?;
+    limits::check_len("Tag icon", &icon, limits::MAX_IMG_LEN)?;
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
+
+    let color = validate_palette_color(vault.pool(), Some(&color))
+        .await?
+        .unwrap_or_default();
 
     let text_enc = helper.encrypt(&text)?;
     let icon_enc = helper.encrypt(&icon)?;
     let color_enc = helper.encrypt(&color)?;
 
-    sqlx::query("INSERT INTO buttons (text, icon, color) VALUES (?, ?, ?)")
+    let mut conn = vault.pool().acquire().await?;
+    check_parent(&mut conn, None, parent_id).await?;
+
+    let id = sqlx::query("INSERT INTO buttons (text, icon, color, parent_id) VALUES (?, ?, ?, ?)")
         .bind(text_enc)
         .bind(icon_enc)
         .bind(color_enc)
-        .execute(&db_pool)
-        .await?;
+        .bind(parent_id)
+        .execute(&mut *conn)
+        .await?
+        .last_insert_rowid();
+    crate::events::tag_changed(&app, Some(id));
     Ok(())
 }
 
@@ -34,7 +95,7 @@ pub async fn get_buttons_impl<'a, E>(executor: E, key: &[u8]) -> Result<Vec<Butt
 where
     E: sqlx::SqliteExecutor<'a>,
 {
-    let rows = sqlx::query("SELECT id, text, icon, color FROM buttons")
+    let rows = sqlx::query("SELECT id, text, icon, color, parent_id FROM buttons")
         .fetch_all(executor)
         .await?;
 
@@ -50,6 +111,7 @@ where
             text: helper.decrypt(&text_enc)?,
             icon: helper.decrypt(&icon_enc)?,
             color: helper.decrypt(&color_enc)?,
+            parent_id: row.get("parent_id"),
         });
     }
 
@@ -58,51 +120,75 @@ where
 
 #[tauri::command]
 pub async fn get_buttons(state: State<'_, AppState>) -> Result<Vec<Button>> {
-    let key = get_key(&state).await?;
-    let db_pool = get_db_pool(&state).await?;
-    get_buttons_impl(&db_pool, key.as_slice()).await
+    let vault = UnlockedVault::acquire(&state).await?;
+    get_buttons_impl(vault.pool(), vault.key()).await
 }
 
 #[tauri::command]
 pub async fn update_button(
+    app: tauri::AppHandle,
     state: State<'_, AppState>,
     id: i64,
     text: String,
     icon: String,
     color: String,
+    parent_id: Option<i64>,
 ) -> Result<()> {
-    let key = get_key(&state).await?;
-    let db_pool = get_db_pool(&state).await?;
-    let helper = CryptoHelper::new(key.as_slice())?;
+    limits::check_len("Tag", &text, limits::MAX_TITLE_LEN)?;
+    limits::check_len("Tag icon", &icon, limits::MAX_IMG_LEN)?;
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
+
+    let stored_color = sqlx::query_scalar::<_, String>("SELECT color FROM buttons WHERE id = ?")
+        .bind(id)
+        .fetch_optional(vault.pool())
+        .await?
+        .map(|c| helper.decrypt(&c))
+        .transpose()?;
+    let color = match stored_color {
+        Some(stored) if same_color(Some(&color), Some(&stored)) => stored,
+        _ => validate_palette_color(vault.pool(), Some(&color))
+            .await?
+            .unwrap_or_default(),
+    };
 
     let text_enc = helper.encrypt(&text)?;
     let icon_enc = helper.encrypt(&icon)?;
     let color_enc = helper.encrypt(&color)?;
 
-    sqlx::query("UPDATE buttons SET text = ?, icon = ?, color = ? WHERE id = ?")
+    let mut tx = vault.pool().begin().await?;
+    check_parent(&mut tx, Some(id), parent_id).await?;
+
+    sqlx::query("UPDATE buttons SET text = ?, icon = ?, color = ?, parent_id = ? WHERE id = ?")
         .bind(text_enc)
         .bind(icon_enc)
         .bind(color_enc)
+        .bind(parent_id)
         .bind(id)
-        .execute(&db_pool)
+        .execute(&mut *tx)
         .await?;
+    tx.commit().await?;
+    crate::events::tag_changed(&app, Some(id));
     Ok(())
 }
 
 #[tauri::command]
-pub async fn delete_button(state: State<'_, AppState>, id: i64) -> Result<()> {
-    let key = get_key(&state).await?;
-    let db_pool = get_db_pool(&state).await?;
-    let helper = CryptoHelper::new(key.as_slice())?;
-    
-    let mut tx = db_pool.begin().await?;
+pub async fn delete_button(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    id: i64,
+) -> Result<()> {
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
+
+    let mut tx = vault.pool().begin().await?;
 
     // 1. Get the tag text
     let row: Option<(String,)> = sqlx::query_as("SELECT text FROM buttons WHERE id = ?")
         .bind(id)
         .fetch_optional(&mut *tx)
         .await?;
-        
+
     let tag_text = if let Some((text_enc,)) = row {
         Some(helper.decrypt(&text_enc)?)
     } else {
@@ -121,17 +207,26 @@ pub async fn delete_button(state: State<'_, AppState>, id: i64) -> Result<()> {
         .execute(&mut *tx)
         .await?;
 
-    // 4. Delete the button itself
+    // 4. Move nested tags up to the deleted tag's parent
+    sqlx::query(
+        "UPDATE buttons SET parent_id = (SELECT parent_id FROM buttons WHERE id = ?) WHERE parent_id = ?",
+    )
+    .bind(id)
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+
+    // 5. Delete the button itself
     sqlx::query("DELETE FROM buttons WHERE id = ?")
         .bind(id)
         .execute(&mut *tx)
         .await?;
 
-    // 5. Update password_items tags text
+    // 6. Update password_items tags text
     if let Some(tag_trimmed) = tag_text {
         let tag_trimmed = tag_trimmed.trim();
         let now = Utc::now().to_rfc3339();
-        
+
         for item_id in item_ids {
             let tags_enc: Option<String> =
                 sqlx::query_scalar("SELECT tags FROM password_items WHERE id = ?")
@@ -147,7 +242,7 @@ pub async fn delete_button(state: State<'_, AppState>, id: i64) -> Result<()> {
                     .filter(|s| !s.is_empty())
                     .map(|s| s.to_string())
                     .collect();
-                
+
                 // Remove the specific tag
                 parts.retain(|t| t != tag_trimmed);
 
@@ -169,16 +264,18 @@ pub async fn delete_button(state: State<'_, AppState>, id: i64) -> Result<()> {
     }
 
     tx.commit().await?;
+    crate::events::tag_changed(&app, Some(id));
     Ok(())
 }
 
 #[tauri::command]
-pub async fn get_tag_counts(state: State<'_, AppState>) -> Result<std::collections::HashMap<i64, i64>> {
-    let _key = get_key(&state).await?;
-    let db_pool = get_db_pool(&state).await?;
+pub async fn get_tag_counts(
+    state: State<'_, AppState>,
+) -> Result<std::collections::HashMap<i64, i64>> {
+    let vault = UnlockedVault::acquire(&state).await?;
 
     let rows = sqlx::query("SELECT tag_id, COUNT(*) as count FROM item_tags GROUP BY tag_id")
-        .fetch_all(&db_pool)
+        .fetch_all(vault.pool())
         .await?;
 
     let mut counts = std::collections::HashMap::new();
@@ -190,3 +287,76 @@ pub async fn get_tag_counts(state: State<'_, AppState>) -> Result<std::collectio
 
     Ok(counts)
 }
+
+fn build_tag_node(
+    tag: &Button,
+    children: &HashMap<i64, Vec<&Button>>,
+    items_by_tag: &HashMap<i64, Vec<i64>>,
+    visited: &mut HashSet<i64>,
+) -> (TagTreeNode, HashSet<i64>) {
+    visited.insert(tag.id);
+    let direct = items_by_tag.get(&tag.id).cloned().unwrap_or_default();
+    let mut subtree_items: HashSet<i64> = direct.iter().copied().collect();
+
+    let mut child_nodes = Vec::new();
+    for child in children.get(&tag.id).into_iter().flatten() {
+        if visited.contains(&child.id) {
+            continue;
+        }
+        let (node, items) = build_tag_node(child, children, items_by_tag, visited);
+        subtree_items.extend(items);
+        child_nodes.push(node);
+    }
+
+    let node = TagTreeNode {
+        tag: tag.clone(),
+        item_count: direct.len() as i64,
+        subtree_item_count: subtree_items.len() as i64,
+        children: child_nodes,
+    };
+    (node, subtree_items)
+}
+
+/// Returns the tags as a forest, with direct and per-subtree item counts.
+#[tauri::command]
+pub async fn get_tag_tree(state: State<'_, AppState>) -> Result<Vec<TagTreeNode>> {
+    let vault = UnlockedVault::acquire(&state).await?;
+
+    let buttons = get_buttons_impl(vault.pool(), vault.key()).await?;
+    let pairs: Vec<(i64, i64)> = sqlx::query_as("SELECT tag_id, item_id FROM item_tags")
+        .fetch_all(vault.pool())
+        .await?;
+
+    let mut items_by_tag: HashMap<i64, Vec<i64>> = HashMap::new();
+    for (tag_id, item_id) in pairs {
+        items_by_tag.entry(tag_id).or_default().push(item_id);
+    }
+
+    let ids: HashSet<i64> = buttons.iter().map(|b| b.id).collect();
+    let mut children: HashMap<i64, Vec<&Button>> = HashMap::new();
+    let mut roots = Vec::new();
+    for button in &buttons {
+        match button
+            .parent_id
+            .filter(|p| ids.contains(p) && *p != button.id)
+        {
+            Some(parent) => children.entry(parent).or_default().push(button),
+            None => roots.push(button),
+        }
+    }
+
+    let mut visited = HashSet::new();
+    let mut tree = Vec::with_capacity(roots.len());
+    for root in roots {
+        tree.push(build_tag_node(root, &children, &items_by_tag, &mut visited).0);
+    }
+    // Tags caught in a parent cycle (only possible through a hand-edited
+    // database) are surfaced at the top level rather than dropped.
+    for button in &buttons {
+        if !visited.contains(&button.id) {
+            tree.push(build_tag_node(button, &children, &items_by_tag, &mut visited).0);
+        }
+    }
+
+    Ok(tree)
+}
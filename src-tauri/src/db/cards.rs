@@ -0,0 +1,82 @@
+//! Masked card details for list views, so the frontend can show which card
+//! an item holds without ever receiving the full number.
+
+use crate::db::utils::UnlockedVault;
+use crate::db::validation::{
+    card_expires_on, card_last4, detect_card_brand, mask_card_number, CardBrand,
+};
+use crate::error::Result;
+use crate::limits;
+use crate::state::AppState;
+use crate::types::ItemDetails;
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::Row;
+use tauri::State;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CardSummary {
+    pub item_id: i64,
+    /// Detected from the number; `None` for unknown networks.
+    pub brand: Option<CardBrand>,
+    pub last4: Option<String>,
+    pub masked_number: Option<String>,
+    pub cardholder_name: Option<String>,
+    pub expiry_month: Option<u8>,
+    pub expiry_year: Option<u16>,
+    pub expired: bool,
+}
+
+/// Summaries of the card items among `ids`. Other items are left out.
+#[tauri::command]
+pub async fn get_card_summaries(
+    state: State<'_, AppState>,
+    ids: Vec<i64>,
+) -> Result<Vec<CardSummary>> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    limits::check_count("Item ids", ids.len(), limits::MAX_BATCH_IDS)?;
+
+    let vault = UnlockedVault::acquire(&state).await?;
+    let helper = vault.helper()?;
+    let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+    let sql = format!(
+        "SELECT id, details FROM password_items WHERE details IS NOT NULL AND id IN ({})",
+        placeholders
+    );
+    let mut query = sqlx::query(&sql);
+    for id in ids {
+        query = query.bind(id);
+    }
+    let rows = query.fetch_all(vault.pool()).await?;
+
+    let today = Utc::now().date_naive();
+    let mut summaries = Vec::new();
+    for row in rows {
+        let enc: String = row.get("details");
+        let Ok(json) = helper.decrypt_secret(&enc) else {
+            continue;
+        };
+        let Ok(ItemDetails::Card(card)) = serde_json::from_str::<ItemDetails>(json.as_str()) else {
+            continue;
+        };
+        let number = card.number.as_ref().map(|n| n.as_str());
+        let expired = match (card.expiry_month, card.expiry_year) {
+            (Some(month), Some(year)) => card_expires_on(month, year).is_some_and(|d| d <= today),
+            _ => false,
+        };
+        summaries.push(CardSummary {
+            item_id: row.get("id"),
+            brand: number.and_then(detect_card_brand),
+            last4: number.and_then(card_last4),
+            masked_number: number.and_then(mask_card_number),
+            cardholder_name: card.cardholder_name,
+            expiry_month: card.expiry_month,
+            expiry_year: card.expiry_year,
+            expired,
+        });
+    }
+    Ok(summaries)
+}
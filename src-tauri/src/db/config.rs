@@ -1,15 +1,15 @@
-use crate::db::utils::{get_db_pool, get_key};
+use crate::db::utils::UnlockedVault;
 use crate::encryption::{decrypt, encrypt};
 use crate::error::Result;
+use crate::limits;
 use crate::state::AppState;
 use sqlx::Row;
 use tauri::State;
 
 #[tauri::command]
 pub async fn wipe_vault_database(state: State<'_, AppState>) -> Result<()> {
-    get_key(&state).await?;
-    let db_pool = get_db_pool(&state).await?;
-    let mut tx = db_pool.begin().await?;
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let mut tx = vault.pool().begin().await?;
 
     sqlx::query("DELETE FROM password_items")
         .execute(&mut *tx)
@@ -21,12 +21,16 @@ pub async fn wipe_vault_database(state: State<'_, AppState>) -> Result<()> {
     sqlx::query("DELETE FROM attachments")
         .execute(&mut *tx)
         .await?;
+    sqlx::query("DELETE FROM note_history")
+        .execute(&mut *tx)
+        .await?;
 
     if let Err(e) = sqlx::query("DELETE FROM sqlite_sequence WHERE name IN ('password_items', 'buttons', 'recipient_keys', 'attachments')").execute(&mut *tx).await {
          let _ = e;
     }
 
     tx.commit().await?;
+    crate::auth::members::refresh_member_keys(&state).await;
     Ok(())
 }
 
@@ -35,30 +39,29 @@ pub async fn save_profile_settings(
     state: State<'_, AppState>,
     settings_json: String,
 ) -> Result<()> {
-    let key = get_key(&state).await?;
-    let db_pool = get_db_pool(&state).await?;
+    limits::check_len("Profile settings", &settings_json, limits::MAX_SETTINGS_LEN)?;
+    let vault = UnlockedVault::acquire_writable(&state).await?;
 
-    let encrypted = encrypt(&settings_json, key.as_slice())?;
+    let encrypted = encrypt(&settings_json, vault.key())?;
 
     sqlx::query("INSERT OR REPLACE INTO configuration (key, value) VALUES ('profile_settings', ?)")
         .bind(encrypted)
-        .execute(&db_pool)
+        .execute(vault.pool())
         .await?;
     Ok(())
 }
 
 #[tauri::command]
 pub async fn get_profile_settings(state: State<'_, AppState>) -> Result<Option<String>> {
-    let key = get_key(&state).await?;
-    let db_pool = get_db_pool(&state).await?;
+    let vault = UnlockedVault::acquire(&state).await?;
 
     let row = sqlx::query("SELECT value FROM configuration WHERE key = 'profile_settings'")
-        .fetch_optional(&db_pool)
+        .fetch_optional(vault.pool())
         .await?;
 
     if let Some(row) = row {
         let encrypted: String = row.get("value");
-        let decrypted = decrypt(&encrypted, key.as_slice())?;
+        let decrypted = decrypt(&encrypted, vault.key())?;
         Ok(Some(decrypted))
     } else {
         Ok(None)
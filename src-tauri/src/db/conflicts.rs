@@ -0,0 +1,335 @@
+use crate::db::passwords::{
+    apply_password_item_update, fetch_password_item, insert_password_item, stored_item_details,
+};
+use crate::db::utils::{CryptoHelper, UnlockedVault};
+use crate::db::validation::{validate_card_changes, validate_item};
+use crate::db::vault_settings::load_password_policy;
+use crate::error::{Error, Result};
+use crate::limits;
+use crate::state::AppState;
+use crate::types::{ItemConflict, PasswordItem, VaultBackupSnapshot};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use tauri::State;
+
+/// Conflicts left unresolved this long are settled by the default policy:
+/// the more recently updated version wins, the local one on a tie.
+const CONFLICT_TIMEOUT_DAYS: i64 = 7;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "choice", rename_all = "snake_case")]
+pub enum ConflictResolution {
+    KeepLocal,
+    KeepIncoming,
+    Merged { item: PasswordItem },
+}
+
+#[derive(Debug, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeSummary {
+    pub added: usize,
+    pub unchanged: usize,
+    pub conflicts: usize,
+}
+
+/// Whether two versions differ in anything the user can edit.
+pub(crate) fn same_content(a: &PasswordItem, b: &PasswordItem) -> Result<bool> {
+    let comparable = |item: &PasswordItem| {
+        let mut item = item.clone();
+        item.id = 0;
+        item.created_at.clear();
+        item.updated_at.clear();
+        item.attachments = None;
+        serde_json::to_value(item)
+    };
+    Ok(comparable(a)? == comparable(b)?)
+}
+
+/// Records `incoming` as a pending alternative to the stored item, replacing
+/// any earlier pending version of the same item.
+pub(crate) async fn stage_conflict(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    helper: &CryptoHelper,
+    item_id: i64,
+    source: &str,
+    incoming: &PasswordItem,
+) -> Result<()> {
+    let now = Utc::now();
+    let expires_at = now + Duration::days(CONFLICT_TIMEOUT_DAYS);
+    let incoming_enc = helper.encrypt(&serde_json::to_string(incoming)?)?;
+
+    sqlx::query(
+        "INSERT INTO item_conflicts (item_id, source, incoming, detected_at, expires_at) VALUES (?, ?, ?, ?, ?)
+         ON CONFLICT(item_id) DO UPDATE SET source = excluded.source, incoming = excluded.incoming, detected_at = excluded.detected_at, expires_at = excluded.expires_at",
+    )
+    .bind(item_id)
+    .bind(source)
+    .bind(incoming_enc)
+    .bind(now.to_rfc3339())
+    .bind(expires_at.to_rfc3339())
+    .execute(tx.as_mut())
+    .await?;
+    Ok(())
+}
+
+struct StoredConflict {
+    id: i64,
+    item_id: i64,
+    source: String,
+    incoming: PasswordItem,
+    detected_at: String,
+    expires_at: String,
+}
+
+async fn load_conflicts<'a, E>(
+    executor: E,
+    helper: &CryptoHelper,
+    expired_only: bool,
+) -> Result<Vec<StoredConflict>>
+where
+    E: sqlx::SqliteExecutor<'a>,
+{
+    let sql = if expired_only {
+        "SELECT id, item_id, source, incoming, detected_at, expires_at FROM item_conflicts WHERE expires_at <= ? ORDER BY detected_at"
+    } else {
+        "SELECT id, item_id, source, incoming, detected_at, expires_at FROM item_conflicts WHERE expires_at > ? ORDER BY detected_at"
+    };
+    let rows = sqlx::query(sql)
+        .bind(Utc::now().to_rfc3339())
+        .fetch_all(executor)
+        .await?;
+
+    rows.into_iter()
+        .map(|row| {
+            let incoming_enc: String = row.get("incoming");
+            let incoming: PasswordItem = serde_json::from_str(&helper.decrypt(&incoming_enc)?)?;
+            Ok(StoredConflict {
+                id: row.get("id"),
+                item_id: row.get("item_id"),
+                source: row.get("source"),
+                incoming,
+                detected_at: row.get("detected_at"),
+                expires_at: row.get("expires_at"),
+            })
+        })
+        .collect()
+}
+
+/// Applies `resolved` (if any) over the stored item and drops the conflict.
+async fn settle(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    helper: &CryptoHelper,
+    key: &[u8],
+    conflict_id: i64,
+    item_id: i64,
+    resolved: Option<PasswordItem>,
+    details: &str,
+) -> Result<()> {
+    let mut title = None;
+    if let Some(mut item) = resolved {
+        item.id = item_id;
+        let now = Utc::now().to_rfc3339();
+        apply_password_item_update(tx, helper, key, &item, &now).await?;
+        title = Some(item.title);
+    }
+
+    sqlx::query("DELETE FROM item_conflicts WHERE id = ?")
+        .bind(conflict_id)
+        .execute(tx.as_mut())
+        .await?;
+
+    let _ = crate::db::activity::log_activity_impl(
+        tx.as_mut(),
+        key,
+        "conflict_resolved",
+        Some(item_id),
+        title.as_deref(),
+        Some(details),
+    )
+    .await;
+    Ok(())
+}
+
+/// Settles every conflict past its deadline with the default policy.
+async fn apply_expired_conflicts(db_pool: &SqlitePool, key: &[u8]) -> Result<()> {
+    let helper = CryptoHelper::new(key)?;
+    let mut tx = db_pool.begin().await?;
+
+    for conflict in load_conflicts(tx.as_mut(), &helper, true).await? {
+        let local = fetch_password_item(tx.as_mut(), &helper, conflict.item_id).await?;
+        let resolved = match local {
+            Some(local) if conflict.incoming.updated_at > local.updated_at => {
+                Some(conflict.incoming)
+            }
+            _ => None,
+        };
+        let details = if resolved.is_some() {
+            "Conflict timed out; kept the newer incoming version"
+        } else {
+            "Conflict timed out; kept the local version"
+        };
+        settle(
+            &mut tx,
+            &helper,
+            key,
+            conflict.id,
+            conflict.item_id,
+            resolved,
+            details,
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Conflicts awaiting a decision. Conflicts past their deadline are settled
+/// by the default policy first.
+#[tauri::command]
+pub async fn list_pending_conflicts(state: State<'_, AppState>) -> Result<Vec<ItemConflict>> {
+    let vault = UnlockedVault::acquire(&state).await?;
+
+    let expired: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM item_conflicts WHERE expires_at <= ?")
+            .bind(Utc::now().to_rfc3339())
+            .fetch_one(vault.pool())
+            .await?;
+    if expired > 0 && !crate::storage::is_read_only() {
+        let writable = UnlockedVault::acquire_writable(&state).await?;
+        apply_expired_conflicts(writable.pool(), writable.key()).await?;
+    }
+
+    let helper = CryptoHelper::new(vault.key())?;
+    let mut conflicts = Vec::new();
+    for stored in load_conflicts(vault.pool(), &helper, false).await? {
+        let Some(local) = fetch_password_item(vault.pool(), &helper, stored.item_id).await? else {
+            continue;
+        };
+        conflicts.push(ItemConflict {
+            id: stored.id,
+            item_id: stored.item_id,
+            source: stored.source,
+            local,
+            incoming: stored.incoming,
+            detected_at: stored.detected_at,
+            expires_at: stored.expires_at,
+        });
+    }
+    Ok(conflicts)
+}
+
+/// Settles a conflict by keeping the local version, taking the incoming one,
+/// or saving a `merged` item built from both.
+#[tauri::command]
+pub async fn resolve_conflict(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    id: i64,
+    resolution: ConflictResolution,
+) -> Result<()> {
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
+    let mut tx = vault.pool().begin().await?;
+
+    let row = sqlx::query("SELECT item_id, incoming FROM item_conflicts WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| Error::Validation(format!("Conflict {} not found", id)))?;
+    let item_id: i64 = row.get("item_id");
+
+    let (resolved, details) = match resolution {
+        ConflictResolution::KeepLocal => (None, "Kept the local version"),
+        ConflictResolution::KeepIncoming => {
+            let incoming_enc: String = row.get("incoming");
+            let incoming: PasswordItem = serde_json::from_str(&helper.decrypt(&incoming_enc)?)?;
+            (Some(incoming), "Kept the incoming version")
+        }
+        ConflictResolution::Merged { item } => {
+            limits::check_password_item(&item)?;
+            let policy = load_password_policy(tx.as_mut(), vault.key()).await?;
+            validate_item(&item, &policy)?;
+            let stored = stored_item_details(tx.as_mut(), &helper, item_id).await?;
+            validate_card_changes(stored.as_ref(), item.details.as_ref())?;
+            (Some(item), "Saved a merged version")
+        }
+    };
+
+    let resolved_changes_item = resolved.is_some();
+    settle(
+        &mut tx,
+        &helper,
+        vault.key(),
+        id,
+        item_id,
+        resolved,
+        details,
+    )
+    .await?;
+    tx.commit().await?;
+    if resolved_changes_item {
+        crate::events::items_updated(&app, vec![item_id]);
+    }
+    Ok(())
+}
+
+/// Merges the items of a backup into the vault instead of replacing it. Items
+/// missing locally are added; items that exist with different content are
+/// staged as conflicts for `resolve_conflict`.
+#[tauri::command]
+pub async fn merge_vault_snapshot(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    snapshot: VaultBackupSnapshot,
+) -> Result<MergeSummary> {
+    if snapshot.version != 1 {
+        return Err(Error::Validation(format!(
+            "Unsupported backup version {}. Please upgrade Pulsar to restore this backup.",
+            snapshot.version
+        )));
+    }
+    for item in &snapshot.password_items {
+        limits::check_password_item(item)?;
+    }
+
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
+    let now = Utc::now().to_rfc3339();
+    let mut summary = MergeSummary::default();
+    let mut added_ids = Vec::new();
+
+    let mut tx = vault.pool().begin().await?;
+    for item in &snapshot.password_items {
+        match fetch_password_item(tx.as_mut(), &helper, item.id).await? {
+            None => {
+                added_ids
+                    .push(insert_password_item(&mut tx, &helper, vault.key(), item, &now).await?);
+                summary.added += 1;
+            }
+            Some(local) if same_content(&local, item)? => summary.unchanged += 1,
+            Some(local) => {
+                stage_conflict(&mut tx, &helper, local.id, "backup_merge", item).await?;
+                summary.conflicts += 1;
+            }
+        }
+    }
+
+    let _ = crate::db::activity::log_activity_impl(
+        tx.as_mut(),
+        vault.key(),
+        "vault_merged",
+        None,
+        None,
+        Some(&format!(
+            "Merged a backup: {} added, {} unchanged, {} conflicts",
+            summary.added, summary.unchanged, summary.conflicts
+        )),
+    )
+    .await;
+
+    tx.commit().await?;
+    crate::events::items_created(&app, added_ids);
+    Ok(summary)
+}
@@ -0,0 +1,189 @@
+use crate::db::attachments::get_attachments_dir;
+use crate::db::utils::UnlockedVault;
+use crate::error::Result;
+use crate::state::AppState;
+use serde::Serialize;
+use sqlx::Row;
+use tauri::State;
+use tokio::fs;
+
+const ORPHANED_ATTACHMENTS: &str =
+    "FROM attachments WHERE item_id NOT IN (SELECT id FROM password_items)";
+
+/// Tables whose rows belong to an item or tag, with the SQL that finds rows
+/// pointing at one that no longer exists.
+const ORPHAN_CHECKS: &[(&str, &str)] = &[
+    (
+        "item_tags",
+        "FROM item_tags WHERE item_id NOT IN (SELECT id FROM password_items) OR tag_id NOT IN (SELECT id FROM buttons)",
+    ),
+    (
+        "search_indices",
+        "FROM search_indices WHERE item_id NOT IN (SELECT id FROM password_items)",
+    ),
+    (
+        "search_trigrams",
+        "FROM search_trigrams WHERE item_id NOT IN (SELECT id FROM password_items)",
+    ),
+    ("attachments", ORPHANED_ATTACHMENTS),
+];
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForeignKeyViolation {
+    pub table: String,
+    pub row_id: Option<i64>,
+    pub parent: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrphanedRows {
+    pub table: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndecryptableItem {
+    pub item_id: i64,
+    pub columns: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsistencyReport {
+    pub foreign_key_violations: Vec<ForeignKeyViolation>,
+    pub orphaned_rows: Vec<OrphanedRows>,
+    /// Never fixed automatically, since that would mean deleting items.
+    pub undecryptable_items: Vec<UndecryptableItem>,
+    /// Rows deleted by the fix; always 0 when `fix` was not set.
+    pub fixed_rows: u64,
+}
+
+async fn foreign_key_violations(vault: &UnlockedVault) -> Result<Vec<ForeignKeyViolation>> {
+    let rows = sqlx::query("PRAGMA foreign_key_check")
+        .fetch_all(vault.pool())
+        .await?;
+    Ok(rows
+        .iter()
+        .map(|row| ForeignKeyViolation {
+            table: row.get(0),
+            row_id: row.get(1),
+            parent: row.get(2),
+        })
+        .collect())
+}
+
+async fn undecryptable_items(vault: &UnlockedVault) -> Result<Vec<UndecryptableItem>> {
+    let helper = vault.helper()?;
+    let rows = sqlx::query("SELECT id, title, password FROM password_items ORDER BY id")
+        .fetch_all(vault.pool())
+        .await?;
+    let mut items = Vec::new();
+    for row in rows {
+        let mut columns = Vec::new();
+        if helper.decrypt(&row.get::<String, _>("title")).is_err() {
+            columns.push("title".to_string());
+        }
+        if helper
+            .decrypt_secret(&row.get::<String, _>("password"))
+            .is_err()
+        {
+            columns.push("password".to_string());
+        }
+        if !columns.is_empty() {
+            items.push(UndecryptableItem {
+                item_id: row.get("id"),
+                columns,
+            });
+        }
+    }
+    Ok(items)
+}
+
+/// Deletes orphaned rows and the rows `PRAGMA foreign_key_check` reports,
+/// then the files of the deleted attachments. Returns the number of rows.
+async fn repair(
+    state: &AppState,
+    vault: &UnlockedVault,
+    violations: &[ForeignKeyViolation],
+) -> Result<u64> {
+    let orphaned_attachments: Vec<i64> =
+        sqlx::query_scalar(&format!("SELECT id {}", ORPHANED_ATTACHMENTS))
+            .fetch_all(vault.pool())
+            .await?;
+
+    let mut fixed = 0;
+    let mut tx = vault.pool().begin().await?;
+    for (_, from) in ORPHAN_CHECKS {
+        fixed += sqlx::query(&format!("DELETE {}", from))
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+    }
+    for violation in violations {
+        let Some(row_id) = violation.row_id else {
+            continue;
+        };
+        fixed += sqlx::query(&format!(
+            "DELETE FROM \"{}\" WHERE rowid = ?",
+            violation.table.replace('"', "\"\"")
+        ))
+        .bind(row_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    }
+    tx.commit().await?;
+
+    if !orphaned_attachments.is_empty() {
+        let dir = get_attachments_dir(state).await?;
+        for id in orphaned_attachments {
+            let _ = fs::remove_file(dir.join(id.to_string())).await;
+        }
+    }
+    Ok(fixed)
+}
+
+/// Looks for rows that reference missing items or tags and for items whose
+/// title or password no longer decrypt with the vault key. With `fix` set,
+/// the dangling rows are deleted and the vault is checked again.
+#[tauri::command]
+pub async fn check_db_consistency(
+    state: State<'_, AppState>,
+    fix: Option<bool>,
+) -> Result<ConsistencyReport> {
+    let fix = fix.unwrap_or(false);
+    let vault = if fix {
+        UnlockedVault::acquire_writable(&state).await?
+    } else {
+        UnlockedVault::acquire(&state).await?
+    };
+
+    let mut fixed_rows = 0;
+    if fix {
+        let violations = foreign_key_violations(&vault).await?;
+        fixed_rows = repair(&state, &vault, &violations).await?;
+    }
+
+    let mut orphaned_rows = Vec::new();
+    for (table, from) in ORPHAN_CHECKS {
+        let count: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) {}", from))
+            .fetch_one(vault.pool())
+            .await?;
+        if count > 0 {
+            orphaned_rows.push(OrphanedRows {
+                table: table.to_string(),
+                count,
+            });
+        }
+    }
+
+    Ok(ConsistencyReport {
+        foreign_key_violations: foreign_key_violations(&vault).await?,
+        orphaned_rows,
+        undecryptable_items: undecryptable_items(&vault).await?,
+        fixed_rows,
+    })
+}
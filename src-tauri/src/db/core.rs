@@ -23,11 +23,13 @@ fn build_connect_options(
     db_path_abs: &Path,
     password: Option<&[u8]>,
     create_if_missing: bool,
+    read_only: bool,
 ) -> SqliteConnectOptions {
     let path_str = db_path_abs.to_string_lossy();
     let mut opts = SqliteConnectOptions::new()
         .filename(path_str.as_ref())
         .create_if_missing(create_if_missing)
+        .read_only(read_only)
         .busy_timeout(std::time::Duration::from_secs(30));
 
     if let Some(key_bytes) = password {
@@ -39,15 +41,18 @@ fn build_connect_options(
     opts
 }
 
-fn build_pool_options() -> SqlitePoolOptions {
+fn build_pool_options(read_only: bool) -> SqlitePoolOptions {
     SqlitePoolOptions::new()
         .max_connections(4)
         .acquire_timeout(std::time::Duration::from_secs(60))
         .after_connect(move |conn, _meta| {
             Box::pin(async move {
-                sqlx::query("PRAGMA journal_mode = WAL")
-                    .execute(&mut *conn)
-                    .await?;
+                // Switching the journal mode writes to the file.
+                if !read_only {
+                    sqlx::query("PRAGMA journal_mode = WAL")
+                        .execute(&mut *conn)
+                        .await?;
+                }
 
                 sqlx::query("PRAGMA synchronous = NORMAL")
                     .execute(&mut *conn)
@@ -105,7 +110,17 @@ pub async fn init_db_lazy(
         })?;
     }
 
-    let opts = build_connect_options(db_path_abs.as_path(), password, create_if_missing);
+    let read_only = match tokio::fs::try_exists(&db_path_abs).await {
+        Ok(true) => crate::storage::probe_writable(&db_path_abs).await.is_some(),
+        _ => false,
+    };
+
+    let opts = build_connect_options(
+        db_path_abs.as_path(),
+        password,
+        create_if_missing,
+        read_only,
+    );
 
-    Ok(build_pool_options().connect_lazy_with(opts))
+    Ok(build_pool_options(read_only).connect_lazy_with(opts))
 }
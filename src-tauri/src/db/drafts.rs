@@ -0,0 +1,119 @@
+use crate::db::utils::{CryptoHelper, UnlockedVault};
+use crate::error::{Error, Result};
+use crate::limits;
+use crate::state::AppState;
+use crate::types::ItemDraft;
+use chrono::Utc;
+use sqlx::Row;
+use tauri::State;
+
+/// Stores an in-progress item edit so it survives a crash or an accidental
+/// close. Drafts of existing items are keyed by `item_id`; drafts of new
+/// items by the returned draft id. Drafts are removed when the item is saved.
+#[tauri::command]
+pub async fn save_item_draft(state: State<'_, AppState>, draft: ItemDraft) -> Result<i64> {
+    limits::check_len("Draft", &draft.data, limits::MAX_DRAFT_LEN)?;
+    serde_json::from_str::<serde_json::Value>(&draft.data)
+        .map_err(|e| Error::Validation(format!("Draft data is not valid JSON: {}", e)))?;
+
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
+    let data_enc = helper.encrypt(&draft.data)?;
+    let now = Utc::now().to_rfc3339();
+
+    let mut tx = vault.pool().begin().await?;
+
+    if let Some(item_id) = draft.item_id {
+        let exists: Option<i64> = sqlx::query_scalar("SELECT id FROM password_items WHERE id = ?")
+            .bind(item_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        if exists.is_none() {
+            return Err(Error::Validation(format!("Item {} not found", item_id)));
+        }
+    }
+
+    let existing: Option<i64> = match draft.item_id {
+        Some(item_id) => {
+            sqlx::query_scalar("SELECT id FROM item_drafts WHERE item_id = ?")
+                .bind(item_id)
+                .fetch_optional(&mut *tx)
+                .await?
+        }
+        None if draft.id != 0 => {
+            sqlx::query_scalar("SELECT id FROM item_drafts WHERE id = ? AND item_id IS NULL")
+                .bind(draft.id)
+                .fetch_optional(&mut *tx)
+                .await?
+        }
+        None => None,
+    };
+
+    let id = match existing {
+        Some(id) => {
+            sqlx::query("UPDATE item_drafts SET data = ?, updated_at = ? WHERE id = ?")
+                .bind(&data_enc)
+                .bind(&now)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            id
+        }
+        None => {
+            let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM item_drafts")
+                .fetch_one(&mut *tx)
+                .await?;
+            limits::check_count("Drafts", count as usize + 1, limits::MAX_DRAFTS)?;
+
+            sqlx::query(
+                "INSERT INTO item_drafts (item_id, data, created_at, updated_at) VALUES (?, ?, ?, ?)",
+            )
+            .bind(draft.item_id)
+            .bind(&data_enc)
+            .bind(&now)
+            .bind(&now)
+            .execute(&mut *tx)
+            .await?
+            .last_insert_rowid()
+        }
+    };
+
+    tx.commit().await?;
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn get_item_drafts(state: State<'_, AppState>) -> Result<Vec<ItemDraft>> {
+    let vault = UnlockedVault::acquire(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
+
+    let rows = sqlx::query(
+        "SELECT id, item_id, data, created_at, updated_at FROM item_drafts ORDER BY updated_at DESC",
+    )
+    .fetch_all(vault.pool())
+    .await?;
+
+    let mut drafts = Vec::with_capacity(rows.len());
+    for row in rows {
+        let data_enc: String = row.get("data");
+        drafts.push(ItemDraft {
+            id: row.get("id"),
+            item_id: row.get("item_id"),
+            data: helper.decrypt(&data_enc)?,
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        });
+    }
+    Ok(drafts)
+}
+
+/// Discards a draft, e.g. when the user cancels the edit.
+#[tauri::command]
+pub async fn delete_item_draft(state: State<'_, AppState>, id: i64) -> Result<()> {
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    sqlx::query("DELETE FROM item_drafts WHERE id = ?")
+        .bind(id)
+        .execute(vault.pool())
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,317 @@
+use crate::db::utils::{CryptoHelper, UnlockedVault};
+use crate::error::{Error, Result};
+use crate::state::AppState;
+use base64::{engine::general_purpose, Engine as _};
+use chrono::Utc;
+use serde::Deserialize;
+use sqlx::Row;
+use std::time::Duration;
+use tauri::State;
+use zeroize::Zeroize;
+
+const MAX_ICON_SIZE: usize = 256 * 1024;
+const MAX_PAGE_SIZE: usize = 512 * 1024;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Deserialize, Default)]
+struct IconSettings {
+    #[serde(default)]
+    security: IconSecuritySettings,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct IconSecuritySettings {
+    #[serde(default)]
+    favicon_offline_only: bool,
+}
+
+fn derive_icon_hash_key(master_key: &[u8]) -> Result<[u8; 32]> {
+    use hkdf::Hkdf;
+    use sha2::Sha256;
+
+    let hk = Hkdf::<Sha256>::new(None, master_key);
+    let mut out = [0u8; 32];
+    hk.expand(b"pulsar:icons", &mut out)
+        .map_err(|_| Error::Internal("Failed to derive icon hash key".to_string()))?;
+    Ok(out)
+}
+
+/// Keyed hash of the host so the icons table does not reveal which sites the
+/// vault has entries for.
+fn host_hash(master_key: &[u8], host: &str) -> Result<String> {
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    let mut hash_key = derive_icon_hash_key(master_key)?;
+    let mut mac =
+        <Hmac<Sha256> as Mac>::new_from_slice(&hash_key).expect("HMAC can take key of any size");
+    hash_key.zeroize();
+    mac.update(host.as_bytes());
+    Ok(hex::encode(mac.finalize().into_bytes()))
+}
+
+fn parse_host(raw: &str) -> Result<url::Url> {
+    let trimmed = raw.trim();
+    let with_scheme = if trimmed.contains("://") {
+        trimmed.to_string()
+    } else {
+        format!("https://{}", trimmed)
+    };
+
+    let mut parsed = url::Url::parse(&with_scheme)
+        .map_err(|_| Error::Validation(format!("Invalid URL '{}'", raw)))?;
+    if parsed.scheme() != "https" {
+        // Never fetch over plain HTTP, upgrade instead.
+        parsed
+            .set_scheme("https")
+            .map_err(|_| Error::Validation(format!("Invalid URL '{}'", raw)))?;
+    }
+    if parsed.host_str().map_or(true, |h| !h.contains('.')) {
+        return Err(Error::Validation(format!("Invalid URL '{}'", raw)));
+    }
+
+    parsed.set_path("/");
+    parsed.set_query(None);
+    parsed.set_fragment(None);
+    Ok(parsed)
+}
+
+fn sniff_image_mime(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(&[0, 0, 1, 0]) {
+        Some("image/x-icon")
+    } else if bytes.starts_with(b"GIF8") {
+        Some("image/gif")
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if bytes.len() > 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        let head = String::from_utf8_lossy(&bytes[..bytes.len().min(512)]).to_ascii_lowercase();
+        head.contains("<svg").then_some("image/svg+xml")
+    }
+}
+
+/// Extracts the first `<link rel="icon">` (or `shortcut icon` /
+/// `apple-touch-icon`) href from a page.
+fn find_icon_link(html: &str) -> Option<String> {
+    // ASCII-only so every byte offset into `lower` is valid in `html`.
+    let lower = html.to_ascii_lowercase();
+    let mut offset = 0;
+
+    while let Some(start) = lower[offset..].find("<link") {
+        let tag_start = offset + start;
+        let tag_end = tag_start + lower[tag_start..].find('>')?;
+        let tag = &html[tag_start..tag_end];
+        let tag_lower = &lower[tag_start..tag_end];
+        offset = tag_end;
+
+        let rel = attribute(tag_lower, tag_lower, "rel").unwrap_or_default();
+        if rel.split_whitespace().any(|r| r.contains("icon")) {
+            if let Some(href) = attribute(tag, tag_lower, "href") {
+                if !href.is_empty() {
+                    return Some(href);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+fn attribute(tag: &str, tag_lower: &str, name: &str) -> Option<String> {
+    let needle = format!("{}=", name);
+    let mut search = 0;
+    let pos = loop {
+        let found = search + tag_lower[search..].find(&needle)?;
+        let boundary = tag_lower[..found]
+            .chars()
+            .last()
+            .map_or(true, char::is_whitespace);
+        if boundary {
+            break found + needle.len();
+        }
+        search = found + needle.len();
+    };
+
+    let rest = &tag[pos..];
+    let value = match rest.chars().next()? {
+        quote @ ('"' | '\'') => rest[1..].split(quote).next()?,
+        _ => rest.split(|c: char| c.is_whitespace() || c == '/').next()?,
+    };
+    Some(value.trim().replace("&amp;", "&"))
+}
+
+async fn read_limited(mut response: reqwest::Response, limit: usize) -> Result<Vec<u8>> {
+    if response
+        .content_length()
+        .is_some_and(|len| len as usize > limit)
+    {
+        return Err(Error::InputTooLarge(format!(
+            "Response exceeds the limit of {} bytes",
+            limit
+        )));
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| Error::Network(e.to_string()))?
+    {
+        if body.len() + chunk.len() > limit {
+            return Err(Error::InputTooLarge(format!(
+                "Response exceeds the limit of {} bytes",
+                limit
+            )));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+async fn download_icon(client: &reqwest::Client, url: url::Url) -> Result<Option<String>> {
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| Error::Network(e.to_string()))?;
+    if !response.status().is_success() {
+        return Ok(None);
+    }
+
+    let bytes = read_limited(response, MAX_ICON_SIZE).await?;
+    // The Content-Type header is unreliable for icons, so trust the bytes.
+    Ok(sniff_image_mime(&bytes).map(|mime| {
+        format!(
+            "data:{};base64,{}",
+            mime,
+            general_purpose::STANDARD.encode(&bytes)
+        )
+    }))
+}
+
+async fn fetch_remote_icon(site: &url::Url) -> Result<Option<String>> {
+    let client = reqwest::Client::builder()
+        .https_only(true)
+        .timeout(FETCH_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::limited(3))
+        .user_agent(concat!("Pulsar/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| Error::Network(e.to_string()))?;
+
+    let page_icon = match client.get(site.clone()).send().await {
+        Ok(response) if response.status().is_success() => {
+            let page_url = response.url().clone();
+            match read_limited(response, MAX_PAGE_SIZE).await {
+                Ok(body) => find_icon_link(&String::from_utf8_lossy(&body))
+                    .and_then(|href| page_url.join(&href).ok())
+                    .filter(|u| u.scheme() == "https"),
+                Err(_) => None,
+            }
+        }
+        _ => None,
+    };
+
+    if let Some(icon_url) = page_icon {
+        if let Ok(Some(icon)) = download_icon(&client, icon_url).await {
+            return Ok(Some(icon));
+        }
+    }
+
+    let fallback = site
+        .join("/favicon.ico")
+        .map_err(|e| Error::Internal(e.to_string()))?;
+    download_icon(&client, fallback).await
+}
+
+async fn is_offline_only(app_handle: &tauri::AppHandle) -> bool {
+    crate::settings::get_all_settings_internal(app_handle)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str::<IconSettings>(&json).ok())
+        .unwrap_or_default()
+        .security
+        .favicon_offline_only
+}
+
+/// Returns the site's icon as a data URL. Icons are served from the encrypted
+/// cache when present; otherwise they are downloaded over HTTPS unless the
+/// offline-only setting is enabled, in which case `None` is returned.
+#[tauri::command]
+pub async fn fetch_favicon(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    url: String,
+) -> Result<Option<String>> {
+    crate::limits::check_len("URL", &url, crate::limits::MAX_SHORT_FIELD_LEN)?;
+
+    let vault = UnlockedVault::acquire(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
+
+    let site = parse_host(&url)?;
+    let host = site.host_str().unwrap_or_default().to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host).to_string();
+    let hash = host_hash(vault.key(), &host)?;
+
+    let cached = sqlx::query("SELECT data FROM icons WHERE host_hash = ?")
+        .bind(&hash)
+        .fetch_optional(vault.pool())
+        .await?;
+    if let Some(row) = cached {
+        let data: String = row.get("data");
+        // Entries written under a previous key are refetched and overwritten.
+        if let Ok(icon) = helper.decrypt(&data) {
+            return Ok(Some(icon));
+        }
+    }
+
+    if is_offline_only(&app_handle).await {
+        return Ok(None);
+    }
+
+    let Some(icon) = fetch_remote_icon(&site).await? else {
+        return Ok(None);
+    };
+
+    if crate::storage::is_read_only() {
+        return Ok(Some(icon));
+    }
+
+    sqlx::query(
+        "INSERT INTO icons (host_hash, data, fetched_at) VALUES (?, ?, ?)
+         ON CONFLICT(host_hash) DO UPDATE SET data = excluded.data, fetched_at = excluded.fetched_at",
+    )
+    .bind(&hash)
+    .bind(helper.encrypt(&icon)?)
+    .bind(Utc::now().to_rfc3339())
+    .execute(vault.pool())
+    .await?;
+
+    Ok(Some(icon))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_icon_link() {
+        let html = r#"<head><LINK REL="Shortcut Icon" HREF="/Fav.ico?a=1&amp;b=2"></head>"#;
+        assert_eq!(find_icon_link(html).as_deref(), Some("/Fav.ico?a=1&b=2"));
+        assert_eq!(
+            find_icon_link(r#"<link rel="stylesheet" href="a.css">"#),
+            None
+        );
+    }
+
+    #[test]
+    fn test_find_icon_link_after_non_ascii_text() {
+        // 'İ' lowercases to a longer string, which used to shift the offsets.
+        let html = r#"<title>İİİİ</title><link rel="icon" href="/icon.png">"#;
+        assert_eq!(find_icon_link(html).as_deref(), Some("/icon.png"));
+    }
+}
@@ -0,0 +1,114 @@
+use crate::db::utils::UnlockedVault;
+use crate::error::{Error, Result};
+use crate::state::AppState;
+use serde::Serialize;
+use sqlx::migrate::Migrator;
+use sqlx::{Row, SqlitePool};
+use std::collections::HashSet;
+use tauri::State;
+
+pub(crate) static MIGRATOR: Migrator = sqlx::migrate!();
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub description: String,
+    pub installed_on: String,
+    pub success: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingMigration {
+    pub version: i64,
+    pub description: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationStatus {
+    /// Highest migration applied to the open vault, `None` for a vault that
+    /// has never been migrated.
+    pub schema_version: Option<i64>,
+    /// Highest migration this version of Pulsar knows.
+    pub latest_version: i64,
+    pub applied: Vec<AppliedMigration>,
+    pub pending: Vec<PendingMigration>,
+}
+
+fn latest_version() -> i64 {
+    MIGRATOR.iter().map(|m| m.version).max().unwrap_or(0)
+}
+
+async fn has_migrations_table(pool: &SqlitePool) -> Result<bool> {
+    let found: Option<String> = sqlx::query_scalar(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations'",
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(found.is_some())
+}
+
+async fn schema_version(pool: &SqlitePool) -> Result<Option<i64>> {
+    if !has_migrations_table(pool).await? {
+        return Ok(None);
+    }
+    Ok(
+        sqlx::query_scalar("SELECT MAX(version) FROM _sqlx_migrations")
+            .fetch_one(pool)
+            .await?,
+    )
+}
+
+/// Refuses vaults migrated by a newer version of Pulsar. Running the older
+/// migrations over them would fail on the versions this build does not know.
+pub(crate) async fn ensure_supported(pool: &SqlitePool) -> Result<()> {
+    let supported_version = latest_version();
+    match schema_version(pool).await? {
+        Some(vault_version) if vault_version > supported_version => Err(Error::VaultTooNew {
+            vault_version,
+            supported_version,
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub async fn get_migration_status(state: State<'_, AppState>) -> Result<MigrationStatus> {
+    let vault = UnlockedVault::acquire(&state).await?;
+    let applied: Vec<AppliedMigration> = if has_migrations_table(vault.pool()).await? {
+        sqlx::query(
+            "SELECT version, description, installed_on, success FROM _sqlx_migrations ORDER BY version",
+        )
+        .fetch_all(vault.pool())
+        .await?
+        .iter()
+        .map(|row| AppliedMigration {
+            version: row.get("version"),
+            description: row.get("description"),
+            installed_on: row.get("installed_on"),
+            success: row.get("success"),
+        })
+        .collect()
+    } else {
+        Vec::new()
+    };
+
+    let applied_versions: HashSet<i64> = applied.iter().map(|m| m.version).collect();
+    let pending = MIGRATOR
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration() && !applied_versions.contains(&m.version))
+        .map(|m| PendingMigration {
+            version: m.version,
+            description: m.description.to_string(),
+        })
+        .collect();
+
+    Ok(MigrationStatus {
+        schema_version: applied.iter().map(|m| m.version).max(),
+        latest_version: latest_version(),
+        applied,
+        pending,
+    })
+}
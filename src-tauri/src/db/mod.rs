@@ -1,17 +1,53 @@
 pub mod activity;
+pub mod attachment_preview;
 pub mod attachments;
 pub mod buttons;
+pub mod cards;
 pub mod config;
+pub mod conflicts;
+pub mod consistency;
 pub mod core;
+pub mod drafts;
+pub mod icons;
+pub mod migrations;
+pub mod note_history;
+pub mod palettes;
 pub mod passwords;
+pub mod query_console;
+pub mod ranking;
 pub mod recipient_keys;
+pub mod scanner;
+pub mod search_index;
+pub mod secure_kv;
+pub mod templates;
 pub mod utils;
 pub mod validation;
+pub mod vault_settings;
+pub mod vault_templates;
+pub mod wal;
+pub mod wifi;
 
 pub use activity::*;
+pub use attachment_preview::*;
 pub use attachments::*;
 pub use buttons::*;
+pub use cards::*;
 pub use config::*;
+pub use conflicts::*;
+pub use consistency::*;
 pub use core::*;
+pub use drafts::*;
+pub use icons::*;
+pub use migrations::*;
+pub use note_history::*;
+pub use palettes::*;
 pub use passwords::*;
+pub use query_console::*;
 pub use recipient_keys::*;
+pub use search_index::*;
+pub use secure_kv::*;
+pub use templates::*;
+pub use vault_settings::*;
+pub use vault_templates::*;
+pub use wal::*;
+pub use wifi::*;
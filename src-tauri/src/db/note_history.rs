@@ -0,0 +1,171 @@
+//! Earlier versions of an item's notes. Whenever an update changes the
+//! notes, the previous text is kept, encrypted like the notes themselves,
+//! up to the vault's `note_history_depth` versions per item.
+
+use crate::db::passwords::ensure_item_writable;
+use crate::db::utils::{CryptoHelper, UnlockedVault};
+use crate::db::vault_settings::load_vault_settings;
+use crate::error::{Error, Result};
+use crate::state::AppState;
+use crate::types::SecretString;
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::{Row, Sqlite, Transaction};
+use tauri::State;
+
+pub(crate) const DEFAULT_NOTE_HISTORY_DEPTH: u32 = 20;
+pub(crate) const MAX_NOTE_HISTORY_DEPTH: u32 = 500;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteVersion {
+    pub id: i64,
+    pub item_id: i64,
+    /// The notes as they were before being replaced; `None` when empty.
+    pub notes: Option<SecretString>,
+    pub recorded_at: String,
+}
+
+/// Keeps the stored notes of `item_id` as a version when `new_notes`
+/// differs from them, then trims the item's history to the configured
+/// depth. Call before writing `new_notes`.
+pub(crate) async fn record_note_version(
+    tx: &mut Transaction<'_, Sqlite>,
+    helper: &CryptoHelper,
+    key: &[u8],
+    item_id: i64,
+    new_notes: Option<&str>,
+    now: &str,
+) -> Result<()> {
+    let depth = load_vault_settings(tx.as_mut(), key)
+        .await?
+        .note_history_depth
+        .unwrap_or(DEFAULT_NOTE_HISTORY_DEPTH);
+    if depth == 0 {
+        return Ok(());
+    }
+
+    let Some(stored) = sqlx::query("SELECT notes FROM password_items WHERE id = ?")
+        .bind(item_id)
+        .fetch_optional(tx.as_mut())
+        .await?
+    else {
+        return Ok(());
+    };
+    let stored_enc: Option<String> = stored.get("notes");
+    let current = helper.decrypt_secret_opt(stored_enc.clone())?;
+    let current = current
+        .as_ref()
+        .map(|n| n.as_str())
+        .filter(|n| !n.is_empty());
+    let new_notes = new_notes.filter(|n| !n.is_empty());
+    if current.is_none() || current == new_notes {
+        return Ok(());
+    }
+
+    sqlx::query("INSERT INTO note_history (item_id, notes, recorded_at) VALUES (?, ?, ?)")
+        .bind(item_id)
+        .bind(stored_enc)
+        .bind(now)
+        .execute(tx.as_mut())
+        .await?;
+    sqlx::query(
+        "DELETE FROM note_history WHERE item_id = ? AND id NOT IN \
+         (SELECT id FROM note_history WHERE item_id = ? ORDER BY id DESC LIMIT ?)",
+    )
+    .bind(item_id)
+    .bind(item_id)
+    .bind(i64::from(depth))
+    .execute(tx.as_mut())
+    .await?;
+    Ok(())
+}
+
+/// Lists the earlier versions of an item's notes, newest first.
+#[tauri::command]
+pub async fn get_note_history(
+    state: State<'_, AppState>,
+    item_id: i64,
+) -> Result<Vec<NoteVersion>> {
+    let vault = UnlockedVault::acquire(&state).await?;
+    let helper = vault.helper()?;
+    sqlx::query(
+        "SELECT id, item_id, notes, recorded_at FROM note_history WHERE item_id = ? ORDER BY id DESC",
+    )
+    .bind(item_id)
+    .fetch_all(vault.pool())
+    .await?
+    .into_iter()
+    .map(|row| {
+        Ok(NoteVersion {
+            id: row.get("id"),
+            item_id: row.get("item_id"),
+            notes: helper.decrypt_secret_opt(row.get("notes"))?,
+            recorded_at: row.get("recorded_at"),
+        })
+    })
+    .collect()
+}
+
+/// Puts back the notes saved as version `version_id`. The notes it replaces
+/// become a version of their own, so a restore can itself be undone.
+#[tauri::command]
+pub async fn restore_note_version(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    version_id: i64,
+) -> Result<()> {
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let helper = vault.helper()?;
+    let now = Utc::now().to_rfc3339();
+    let mut tx = vault.pool().begin().await?;
+
+    let row = sqlx::query("SELECT item_id, notes, recorded_at FROM note_history WHERE id = ?")
+        .bind(version_id)
+        .fetch_optional(tx.as_mut())
+        .await?
+        .ok_or_else(|| Error::Validation(format!("Note version {} not found", version_id)))?;
+    let item_id: i64 = row.get("item_id");
+    let notes_enc: Option<String> = row.get("notes");
+    ensure_item_writable(tx.as_mut(), item_id).await?;
+
+    let notes = helper.decrypt_secret_opt(notes_enc.clone())?;
+    record_note_version(
+        &mut tx,
+        &helper,
+        vault.key(),
+        item_id,
+        notes.as_ref().map(|n| n.as_str()),
+        &now,
+    )
+    .await?;
+    sqlx::query("UPDATE password_items SET notes = ?, updated_at = ? WHERE id = ?")
+        .bind(notes_enc)
+        .bind(&now)
+        .bind(item_id)
+        .execute(tx.as_mut())
+        .await?;
+
+    let title_enc: Option<String> =
+        sqlx::query_scalar("SELECT title FROM password_items WHERE id = ?")
+            .bind(item_id)
+            .fetch_optional(tx.as_mut())
+            .await?;
+    let title = title_enc.and_then(|t| helper.decrypt(&t).ok());
+    let _ = crate::db::activity::log_activity_impl(
+        tx.as_mut(),
+        vault.key(),
+        "note_version_restored",
+        Some(item_id),
+        title.as_deref(),
+        Some(&format!(
+            "Restored notes from {}",
+            row.get::<String, _>("recorded_at")
+        )),
+    )
+    .await;
+
+    tx.commit().await?;
+    crate::events::items_updated(&app, vec![item_id]);
+    Ok(())
+}
@@ -0,0 +1,240 @@
+use crate::db::utils::UnlockedVault;
+use crate::error::{Error, Result};
+use crate::limits;
+use crate::state::AppState;
+use crate::types::{Button, Palette, PasswordItemOverview};
+use sqlx::Row;
+use std::collections::HashMap;
+use tauri::State;
+
+/// Brings `#rgb`, `#rrggbb` and `#rrggbbaa` colors to one canonical form:
+/// lowercase `#rrggbb`, or `#rrggbbaa` when not fully opaque. `#fff`,
+/// `#FFFFFF` and `#ffffffff` all become `#ffffff`.
+pub fn normalize_color(color: &str) -> Result<String> {
+    let invalid = || {
+        Error::Validation(format!(
+            "Invalid color '{}': expected #rgb, #rrggbb or #rrggbbaa",
+            color
+        ))
+    };
+    let trimmed = color.trim().to_lowercase();
+    let hex = trimmed.strip_prefix('#').ok_or_else(invalid)?;
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+
+    match hex.len() {
+        3 => Ok(hex.chars().fold(String::from("#"), |mut out, c| {
+            out.push(c);
+            out.push(c);
+            out
+        })),
+        6 => Ok(trimmed),
+        8 if hex.ends_with("ff") => Ok(trimmed[..7].to_string()),
+        8 => Ok(trimmed),
+        _ => Err(invalid()),
+    }
+}
+
+/// Whether two stored or submitted colors are the same once normalized.
+pub(crate) fn same_color(a: Option<&str>, b: Option<&str>) -> bool {
+    let canonical = |c: Option<&str>| {
+        c.map(str::trim)
+            .filter(|c| !c.is_empty())
+            .map(|c| normalize_color(c).unwrap_or_else(|_| c.to_string()))
+    };
+    canonical(a) == canonical(b)
+}
+
+pub async fn get_palettes_impl<'a, E>(executor: E) -> Result<Vec<Palette>>
+where
+    E: sqlx::SqliteExecutor<'a>,
+{
+    let rows = sqlx::query("SELECT id, name, colors FROM palettes ORDER BY id")
+        .fetch_all(executor)
+        .await?;
+
+    let mut palettes = Vec::with_capacity(rows.len());
+    for row in rows {
+        let colors_json: String = row.get("colors");
+        palettes.push(Palette {
+            id: row.get("id"),
+            name: row.get("name"),
+            colors: serde_json::from_str(&colors_json).unwrap_or_default(),
+        });
+    }
+
+    Ok(palettes)
+}
+
+/// Normalizes `color` and checks it against the defined palettes. When no
+/// palettes exist every well-formed color is accepted. Callers updating a
+/// record skip this when the color is unchanged (see `same_color`), so
+/// editing a palette does not lock records out of other edits.
+pub async fn validate_palette_color<'a, E>(
+    executor: E,
+    color: Option<&str>,
+) -> Result<Option<String>>
+where
+    E: sqlx::SqliteExecutor<'a>,
+{
+    let Some(color) = color.filter(|c| !c.trim().is_empty()) else {
+        return Ok(None);
+    };
+
+    let normalized = normalize_color(color)?;
+    let palettes = get_palettes_impl(executor).await?;
+    if palettes.is_empty() {
+        return Ok(Some(normalized));
+    }
+
+    let allowed = palettes
+        .iter()
+        .flat_map(|p| p.colors.iter())
+        .any(|c| same_color(Some(c), Some(&normalized)));
+
+    if !allowed {
+        return Err(Error::Validation(format!(
+            "Color '{}' is not part of any defined palette",
+            normalized
+        )));
+    }
+
+    Ok(Some(normalized))
+}
+
+pub fn apply_tag_colors(items: &mut [PasswordItemOverview], buttons: &[Button]) {
+    let colors: HashMap<&str, &str> = buttons
+        .iter()
+        .map(|b| (b.text.as_str(), b.color.as_str()))
+        .collect();
+
+    for item in items.iter_mut() {
+        let Some(tags) = &item.tags else {
+            continue;
+        };
+        item.tag_colors = tags
+            .split(',')
+            .map(|t| t.trim())
+            .filter_map(|t| colors.get(t).map(|c| (t.to_string(), c.to_string())))
+            .collect();
+    }
+}
+
+fn normalize_palette_colors(colors: &[String]) -> Result<Vec<String>> {
+    limits::check_count("Palette colors", colors.len(), limits::MAX_PALETTE_COLORS)?;
+    if colors.is_empty() {
+        return Err(Error::Validation(
+            "A palette must contain at least one color".to_string(),
+        ));
+    }
+
+    let mut normalized = Vec::with_capacity(colors.len());
+    for color in colors {
+        let c = normalize_color(color)?;
+        if !normalized.contains(&c) {
+            normalized.push(c);
+        }
+    }
+    Ok(normalized)
+}
+
+#[tauri::command]
+pub async fn get_palettes(state: State<'_, AppState>) -> Result<Vec<Palette>> {
+    let vault = UnlockedVault::acquire(&state).await?;
+    get_palettes_impl(vault.pool()).await
+}
+
+#[tauri::command]
+pub async fn save_palette(
+    state: State<'_, AppState>,
+    name: String,
+    colors: Vec<String>,
+) -> Result<i64> {
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+
+    limits::check_len("Palette name", &name, limits::MAX_TITLE_LEN)?;
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(Error::Validation("Palette name is required".to_string()));
+    }
+    let colors = normalize_palette_colors(&colors)?;
+
+    let id = sqlx::query("INSERT INTO palettes (name, colors) VALUES (?, ?)")
+        .bind(name)
+        .bind(serde_json::to_string(&colors)?)
+        .execute(vault.pool())
+        .await?
+        .last_insert_rowid();
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn update_palette(
+    state: State<'_, AppState>,
+    id: i64,
+    name: String,
+    colors: Vec<String>,
+) -> Result<()> {
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+
+    limits::check_len("Palette name", &name, limits::MAX_TITLE_LEN)?;
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(Error::Validation("Palette name is required".to_string()));
+    }
+    let colors = normalize_palette_colors(&colors)?;
+
+    sqlx::query("UPDATE palettes SET name = ?, colors = ? WHERE id = ?")
+        .bind(name)
+        .bind(serde_json::to_string(&colors)?)
+        .bind(id)
+        .execute(vault.pool())
+        .await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delete_palette(state: State<'_, AppState>, id: i64) -> Result<()> {
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    sqlx::query("DELETE FROM palettes WHERE id = ?")
+        .bind(id)
+        .execute(vault.pool())
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_color() {
+        let cases = [
+            ("#fff", "#ffffff"),
+            ("#FFFFFF", "#ffffff"),
+            ("#ffffffff", "#ffffff"),
+            (" #A1b2C3 ", "#a1b2c3"),
+            ("#a1b2c380", "#a1b2c380"),
+            ("#0f0", "#00ff00"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalize_color(input).unwrap(), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_normalize_color_rejects_invalid() {
+        for input in ["fff", "#ff", "#ffff", "#fffff", "#ggg", "#ffffff0", "", "#"] {
+            assert!(normalize_color(input).is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_same_color() {
+        assert!(same_color(Some("#FFF"), Some("#ffffffff")));
+        assert!(same_color(None, Some("  ")));
+        assert!(!same_color(Some("#fff"), Some("#000")));
+        assert!(!same_color(Some("#fff"), None));
+    }
+}
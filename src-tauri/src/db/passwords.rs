@@ -1,12 +1,22 @@
-use crate::db::utils::{get_db_pool, get_key, CryptoHelper};
+use crate::db::palettes::{apply_tag_colors, same_color, validate_palette_color};
+use crate::db::utils::{CryptoHelper, UnlockedVault};
+use crate::db::validation::{validate_card_changes, validate_item};
+use crate::db::vault_settings::load_password_policy;
 use crate::error::{Error, Result};
+use crate::limits;
+use crate::overview_cache::OverviewCache;
 use crate::state::AppState;
-use crate::types::{Attachment, CustomField, PasswordItem, PasswordItemOverview};
+use crate::types::{
+    Attachment, CustomField, ItemDetails, OverviewCursor, OverviewPage, PasswordItem,
+    PasswordItemFields, PasswordItemOverview, SearchFilters,
+};
 use chrono::Utc;
 use sqlx::{Row, SqlitePool};
 use std::collections::HashMap;
+use std::sync::PoisonError;
+use tauri::ipc::Channel;
 use tauri::State;
-use validator::Validate;
+use zeroize::Zeroizing;
 
 async fn fetch_attachments_bulk(
     pool: &SqlitePool,
@@ -55,6 +65,7 @@ pub struct AuditPasswordItem {
     pub id: i64,
     pub title: String,
     pub username: Option<String>,
+    pub url: Option<String>,
     pub password: String,
     pub tags: Option<String>,
 }
@@ -72,6 +83,8 @@ struct PreparedPasswordItem {
     totp_secret: Option<String>,
     custom_fields: String,
     field_order: Option<String>,
+    autotype_patterns: Option<String>,
+    details: Option<String>,
 }
 
 impl PreparedPasswordItem {
@@ -99,6 +112,22 @@ impl PreparedPasswordItem {
                 .transpose()?
                 .map(|fo| helper.encrypt(&fo))
                 .transpose()?,
+            // An empty list is stored as no patterns.
+            autotype_patterns: item
+                .autotype_patterns
+                .as_ref()
+                .filter(|p| !p.is_empty())
+                .map(|p| serde_json::to_string(p))
+                .transpose()?
+                .map(|p| helper.encrypt(&p))
+                .transpose()?,
+            details: item
+                .details
+                .as_ref()
+                .map(|d| serde_json::to_string(d).map(Zeroizing::new))
+                .transpose()?
+                .map(|d| helper.encrypt(&d))
+                .transpose()?,
         })
     }
 }
@@ -145,6 +174,19 @@ fn decrypt_password_item_row(
         .and_then(|fo_enc| helper.decrypt(&fo_enc).ok())
         .and_then(|fo_json| serde_json::from_str(&fo_json).ok());
 
+    let autotype_patterns = helper
+        .decrypt_opt(row.get("autotype_patterns"))?
+        .map(|p_json| serde_json::from_str(&p_json))
+        .transpose()?;
+
+    let details_enc: Option<String> = row.get("details");
+    let details = details_enc
+        .and_then(|d_enc| helper.decrypt_secret(&d_enc).ok())
+        .and_then(|d_json| serde_json::from_str(d_json.as_str()).ok());
+
+    // Listings that select fewer columns report items as writable.
+    let read_only = row.try_get::<bool, _>("read_only").unwrap_or(false);
+
     Ok(PasswordItem {
         id,
         category,
@@ -163,6 +205,10 @@ fn decrypt_password_item_row(
         custom_fields,
         field_order,
         attachments,
+        autotype_patterns,
+        details,
+        clear_details: false,
+        read_only,
     })
 }
 
@@ -198,9 +244,47 @@ fn decrypt_password_item_overview_row(
         created_at: row.get("created_at"),
         updated_at: row.get("updated_at"),
         color: row.get("color"),
+        tag_colors: HashMap::new(),
+        score: None,
     })
 }
 
+/// Decrypts overview rows in parallel, taking items whose `updated_at` is
+/// unchanged from the overview cache.
+fn decrypt_overview_rows(
+    rows: Vec<sqlx::sqlite::SqliteRow>,
+    helper: &CryptoHelper,
+    cache: &std::sync::Mutex<OverviewCache>,
+) -> Result<Vec<PasswordItemOverview>> {
+    use rayon::prelude::*;
+
+    let cached: Vec<Option<PasswordItemOverview>> = {
+        let mut cache = cache.lock().unwrap_or_else(PoisonError::into_inner);
+        rows.iter()
+            .map(|row| cache.get(row.get("id"), &row.get::<String, _>("updated_at")))
+            .collect()
+    };
+    let decrypted = rows
+        .into_par_iter()
+        .zip(cached)
+        .map(|(row, hit)| match hit {
+            Some(overview) => Ok((overview, false)),
+            None => decrypt_password_item_overview_row(&row, helper).map(|o| (o, true)),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut cache = cache.lock().unwrap_or_else(PoisonError::into_inner);
+    Ok(decrypted
+        .into_iter()
+        .map(|(overview, fresh)| {
+            if fresh {
+                cache.insert(&overview);
+            }
+            overview
+        })
+        .collect())
+}
+
 async fn sync_item_tags(
     tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
     item_id: i64,
@@ -241,13 +325,16 @@ async fn sync_item_tags(
     Ok(())
 }
 
-async fn sync_search_indices(
+/// Rebuilds the keyed search index of one item. `extra_text` carries notes
+/// and custom field values when the vault indexes them.
+pub(crate) async fn sync_search_indices(
     tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
     item_id: i64,
     helper: &CryptoHelper,
     title: &str,
     username: Option<&String>,
     tags: Option<&String>,
+    extra_text: Option<&str>,
 ) -> Result<()> {
     sqlx::query("DELETE FROM search_indices WHERE item_id = ?")
         .bind(item_id)
@@ -268,6 +355,11 @@ async fn sync_search_indices(
         all_searchable_text.push(' ');
         all_searchable_text.push_str(t);
     }
+    if let Some(extra) = extra_text.filter(|e| !e.trim().is_empty()) {
+        all_searchable_text.push(' ');
+        all_searchable_text.push_str(extra);
+    }
+    let all_searchable_text = Zeroizing::new(all_searchable_text);
 
     let title_token = helper.generate_search_token(title);
     if !title_token.is_empty() {
@@ -320,7 +412,7 @@ pub async fn get_password_items_impl(
     db_pool: &SqlitePool,
     key: &[u8],
 ) -> Result<Vec<PasswordItem>> {
-    let rows = sqlx::query("SELECT id, category, title, description, img, tags, username, url, notes, password, created_at, updated_at, color, totp_secret, custom_fields, field_order FROM password_items")
+    let rows = sqlx::query("SELECT id, category, title, description, img, tags, username, url, notes, password, created_at, updated_at, color, totp_secret, custom_fields, field_order, autotype_patterns, details, read_only FROM password_items")
         .fetch_all(db_pool)
         .await?;
 
@@ -344,7 +436,7 @@ pub async fn get_password_audit_data_impl(
     db_pool: &SqlitePool,
     key: &[u8],
 ) -> Result<Vec<AuditPasswordItem>> {
-    let rows = sqlx::query("SELECT id, title, username, password, tags FROM password_items")
+    let rows = sqlx::query("SELECT id, title, username, url, password, tags FROM password_items")
         .fetch_all(db_pool)
         .await?;
 
@@ -359,6 +451,7 @@ pub async fn get_password_audit_data_impl(
             id: row.get("id"),
             title: helper.decrypt(&title_enc)?,
             username: helper.decrypt_opt(row.get("username"))?,
+            url: helper.decrypt_opt(row.get("url"))?,
             password: helper.decrypt(&password_enc)?,
             tags: helper.decrypt_opt(row.get("tags"))?,
         });
@@ -385,6 +478,93 @@ pub async fn get_password_overviews_impl(
     Ok(items)
 }
 
+enum FilterBind {
+    Text(String),
+    Int(i64),
+}
+
+/// Normalises a filter date to an RFC 3339 timestamp SQLite's `datetime()`
+/// understands. Bare dates mean midnight UTC.
+fn parse_filter_date(field: &str, value: &str) -> Result<String> {
+    let value = value.trim();
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Ok(dt.with_timezone(&Utc).to_rfc3339());
+    }
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|dt| dt.and_utc().to_rfc3339())
+        .ok_or_else(|| Error::Validation(format!("Invalid date for {}: '{}'", field, value)))
+}
+
+/// Translates the filters backed by plaintext columns into SQL conditions on
+/// `p` with their bind values, in placeholder order.
+fn search_filter_conditions(filters: &SearchFilters) -> Result<(Vec<String>, Vec<FilterBind>)> {
+    let mut conditions = Vec::new();
+    let mut binds = Vec::new();
+
+    let date_bounds = [
+        ("createdAfter", "created_at", ">=", &filters.created_after),
+        ("createdBefore", "created_at", "<", &filters.created_before),
+        ("updatedAfter", "updated_at", ">=", &filters.updated_after),
+        ("updatedBefore", "updated_at", "<", &filters.updated_before),
+    ];
+    for (field, column, op, value) in date_bounds {
+        if let Some(value) = value.as_deref().filter(|v| !v.trim().is_empty()) {
+            conditions.push(format!("datetime(p.{}) {} datetime(?)", column, op));
+            binds.push(FilterBind::Text(parse_filter_date(field, value)?));
+        }
+    }
+
+    if let Some(has_attachment) = filters.has_attachment {
+        conditions.push(format!(
+            "{}EXISTS (SELECT 1 FROM attachments a WHERE a.item_id = p.id)",
+            if has_attachment { "" } else { "NOT " }
+        ));
+    }
+
+    if let Some(has_totp) = filters.has_totp {
+        conditions.push(
+            if has_totp {
+                "(p.totp_secret IS NOT NULL AND p.totp_secret != '')"
+            } else {
+                "(p.totp_secret IS NULL OR p.totp_secret = '')"
+            }
+            .to_string(),
+        );
+    }
+
+    if let Some(days) = filters.password_older_than_days {
+        conditions.push("datetime(p.updated_at) <= datetime('now', ?)".to_string());
+        binds.push(FilterBind::Text(format!("-{} days", days)));
+    }
+
+    if let Some(color) = filters.color.as_deref().filter(|c| !c.trim().is_empty()) {
+        limits::check_len("Color", color, limits::MAX_SHORT_FIELD_LEN)?;
+        conditions.push("p.color = ?".to_string());
+        binds.push(FilterBind::Text(color.trim().to_string()));
+    }
+
+    if let Some(folder_id) = filters.folder_id {
+        conditions.push(format!(
+            "p.id IN ({} SELECT item_id FROM item_tags WHERE tag_id IN (SELECT id FROM tag_subtree))",
+            crate::db::buttons::TAG_SUBTREE_CTE
+        ));
+        binds.push(FilterBind::Int(folder_id));
+    }
+
+    Ok((conditions, binds))
+}
+
+/// Share of the query's trigrams an item must contain to match.
+const EXACT_TRIGRAM_SHARE: f64 = 0.6;
+/// Lowest share accepted when typos are tolerated. Such candidates are then
+/// verified by edit distance after decryption.
+const FUZZY_TRIGRAM_SHARE: f64 = 0.25;
+const MAX_SEARCH_TYPOS: u8 = 2;
+
+/// Searches items by text, tag and `filters`. With `max_typos` (at most 2)
+/// the text query also matches words misspelled by that many edits.
 #[tauri::command]
 pub async fn search_password_items(
     state: State<'_, AppState>,
@@ -393,32 +573,64 @@ pub async fn search_password_items(
     category: Option<String>,
     limit: Option<u32>,
     offset: Option<u32>,
+    include_subtags: Option<bool>,
+    filters: Option<SearchFilters>,
+    max_typos: Option<u8>,
 ) -> Result<Vec<PasswordItemOverview>> {
-    let key = get_key(&state).await?;
-    let db_pool = get_db_pool(&state).await?;
-    let helper = CryptoHelper::new(key.as_slice())?;
+    limits::check_len("Search query", &query, limits::MAX_SEARCH_QUERY_LEN)?;
+    limits::check_opt_len("Category", category.as_deref(), limits::MAX_SHORT_FIELD_LEN)?;
+    let filters = filters.unwrap_or_default();
+    let filter_categories: Vec<String> = filters
+        .categories
+        .iter()
+        .flatten()
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect();
+    limits::check_count("Categories", filter_categories.len(), limits::MAX_BATCH_IDS)?;
+    let (filter_conditions, filter_binds) = search_filter_conditions(&filters)?;
+    let vault = UnlockedVault::acquire(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
 
     let query_trimmed = query.trim();
+    let max_typos = max_typos.unwrap_or(0).min(MAX_SEARCH_TYPOS) as usize;
 
     // Optimization: tag_id join doesn't need DISTINCT if (item_id, tag_id) is PK
     let mut sql = "SELECT p.id, p.category, p.title, p.description, p.img, p.tags, p.username, p.url, p.created_at, p.updated_at, p.color 
                    FROM password_items p".to_string();
 
-    if tag_id.is_some() {
+    // A subtree filter can match an item through several tags, so it is a
+    // condition rather than a join.
+    let subtree_tag_id = tag_id.filter(|_| include_subtags.unwrap_or(false));
+    let joined_tag_id = tag_id.filter(|_| subtree_tag_id.is_none());
+
+    if joined_tag_id.is_some() {
         sql.push_str(" JOIN item_tags it ON p.id = it.item_id AND it.tag_id = ?");
     }
 
     let mut conditions = Vec::new();
 
+    if subtree_tag_id.is_some() {
+        conditions.push(format!(
+            "p.id IN ({} SELECT item_id FROM item_tags WHERE tag_id IN (SELECT id FROM tag_subtree))",
+            crate::db::buttons::TAG_SUBTREE_CTE
+        ));
+    }
+
     if !query_trimmed.is_empty() {
         let trigrams = helper.generate_trigram_hashes(query_trimmed);
 
         if trigrams.len() >= 2 {
             let placeholders = trigrams.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
 
+            sql = sql.replacen(
+                " FROM password_items p",
+                ", s.hits FROM password_items p",
+                1,
+            );
             sql.push_str(
                 " JOIN (
-                SELECT item_id FROM search_trigrams 
+                SELECT item_id, COUNT(trigram_hash) AS hits FROM search_trigrams 
                 WHERE trigram_hash IN (",
             );
             sql.push_str(&placeholders);
@@ -440,7 +652,7 @@ pub async fn search_password_items(
         match cat.as_str() {
             "recent" | "favorites" => {
                 let buttons =
-                    crate::db::buttons::get_buttons_impl(&db_pool, key.as_slice()).await?;
+                    crate::db::buttons::get_buttons_impl(vault.pool(), vault.key()).await?;
                 let target_names = if cat == "recent" {
                     vec!["pinned", "pin"]
                 } else {
@@ -481,14 +693,23 @@ pub async fn search_password_items(
         }
     }
 
+    conditions.extend(filter_conditions);
+
     if !conditions.is_empty() {
-        sql.push_str(if tag_id.is_some() { " AND " } else { " WHERE " });
+        sql.push_str(if joined_tag_id.is_some() {
+            " AND "
+        } else {
+            " WHERE "
+        });
         sql.push_str(&conditions.join(" AND "));
     }
 
     sql.push_str(" ORDER BY p.updated_at DESC");
 
-    if let Some(l) = limit {
+    // Category filters and relevance ranking run after decryption, so paging
+    // has to follow them.
+    let page_in_sql = filter_categories.is_empty() && query_trimmed.is_empty();
+    if let (Some(l), true) = (limit, page_in_sql) {
         sql.push_str(&format!(" LIMIT {}", l));
         if let Some(o) = offset {
             sql.push_str(&format!(" OFFSET {}", o));
@@ -496,38 +717,96 @@ pub async fn search_password_items(
     }
 
     let mut q = sqlx::query(&sql);
-    if let Some(tid) = tag_id {
+    if let Some(tid) = joined_tag_id {
         q = q.bind(tid);
     }
 
-    if !query_trimmed.is_empty() {
-        let trigrams = helper.generate_trigram_hashes(query_trimmed);
-        if trigrams.len() >= 2 {
-            for hash in &trigrams {
-                q = q.bind(hash.clone());
-            }
-            let threshold = (trigrams.len() as f64 * 0.6).ceil() as i64;
-            q = q.bind(threshold);
-        } else {
-            let token = helper.generate_search_token(query_trimmed);
-            q = q.bind(token);
+    // Binds follow placeholder order: joins first, then WHERE conditions.
+    let trigrams = if query_trimmed.is_empty() {
+        Vec::new()
+    } else {
+        helper.generate_trigram_hashes(query_trimmed)
+    };
+    let exact_threshold = (trigrams.len() as f64 * EXACT_TRIGRAM_SHARE).ceil() as i64;
+    let fuzzy = max_typos > 0 && trigrams.len() >= 2;
+    if trigrams.len() >= 2 {
+        for hash in &trigrams {
+            q = q.bind(hash.clone());
         }
+        // Each typo breaks up to three trigrams of the query.
+        let threshold = if fuzzy {
+            let floor = (trigrams.len() as f64 * FUZZY_TRIGRAM_SHARE).ceil() as i64;
+            (trigrams.len() as i64 - 3 * max_typos as i64)
+                .max(floor)
+                .min(exact_threshold)
+        } else {
+            exact_threshold
+        };
+        q = q.bind(threshold);
+    }
+
+    if let Some(tid) = subtree_tag_id {
+        q = q.bind(tid);
+    }
+
+    if !query_trimmed.is_empty() && trigrams.len() < 2 {
+        let token = helper.generate_search_token(query_trimmed);
+        q = q.bind(token);
     }
 
     for id in category_tag_ids {
         q = q.bind(id);
     }
 
-    let rows = q.fetch_all(&db_pool).await?;
+    for bind in filter_binds {
+        q = match bind {
+            FilterBind::Text(value) => q.bind(value),
+            FilterBind::Int(value) => q.bind(value),
+        };
+    }
 
-    // Parallel decryption using Rayon
-    use rayon::prelude::*;
-    let items: Result<Vec<PasswordItemOverview>> = rows
-        .into_par_iter()
-        .map(|row| decrypt_password_item_overview_row(&row, &helper))
-        .collect();
+    let rows = q.fetch_all(vault.pool()).await?;
+    let hits: Vec<i64> = if fuzzy {
+        rows.iter().map(|row| row.get("hits")).collect()
+    } else {
+        Vec::new()
+    };
+
+    let mut items = decrypt_overview_rows(rows, &helper, &state.overview_cache)?;
+
+    // Loosely matched candidates must hold up against the plaintext.
+    if fuzzy {
+        items = items
+            .into_iter()
+            .zip(hits)
+            .filter(|(item, hits)| {
+                *hits >= exact_threshold
+                    || crate::db::ranking::matches_with_typos(item, query_trimmed, max_typos)
+            })
+            .map(|(item, _)| item)
+            .collect();
+    }
 
-    items
+    if !filter_categories.is_empty() {
+        items.retain(|item| {
+            filter_categories
+                .iter()
+                .any(|c| c.eq_ignore_ascii_case(&item.category))
+        });
+    }
+    if !query_trimmed.is_empty() {
+        crate::db::ranking::rank_by_relevance(&mut items, query_trimmed);
+    }
+    if !page_in_sql {
+        let skip = offset.unwrap_or(0) as usize;
+        let take = limit.map_or(usize::MAX, |l| l as usize);
+        items = items.into_iter().skip(skip).take(take).collect();
+    }
+
+    let buttons = crate::db::buttons::get_buttons_impl(vault.pool(), vault.key()).await?;
+    apply_tag_colors(&mut items, &buttons);
+
+    Ok(items)
 }
 
 #[tauri::command]
@@ -536,10 +815,9 @@ pub async fn get_password_overviews(
     limit: Option<u32>,
     offset: Option<u32>,
 ) -> Result<Vec<PasswordItemOverview>> {
-    let key = get_key(&state).await?;
-    let db_pool = get_db_pool(&state).await?;
+    let vault = UnlockedVault::acquire(&state).await?;
 
-    let mut sql = "SELECT id, category, title, description, img, tags, username, url, created_at, updated_at, color FROM password_items ORDER BY updated_at DESC".to_string();
+    let mut sql = "SELECT id, category, title, description, img, tags, username, url, created_at, updated_at, color FROM password_items ORDER BY updated_at DESC, id DESC".to_string();
 
     if let Some(l) = limit {
         sql.push_str(&format!(" LIMIT {}", l));
@@ -548,18 +826,123 @@ pub async fn get_password_overviews(
         }
     }
 
-    let rows = sqlx::query(&sql).fetch_all(&db_pool).await?;
+    let rows = sqlx::query(&sql).fetch_all(vault.pool()).await?;
 
-    let helper = CryptoHelper::new(key.as_slice())?;
+    let helper = CryptoHelper::new(vault.key())?;
+    let mut items = decrypt_overview_rows(rows, &helper, &state.overview_cache)?;
 
-    // Parallel decryption using Rayon
-    use rayon::prelude::*;
-    let items: Result<Vec<PasswordItemOverview>> = rows
-        .into_par_iter()
-        .map(|row| decrypt_password_item_overview_row(&row, &helper))
-        .collect();
+    let buttons = crate::db::buttons::get_buttons_impl(vault.pool(), vault.key()).await?;
+    apply_tag_colors(&mut items, &buttons);
+
+    Ok(items)
+}
+
+const MAX_PAGE_SIZE: u32 = 1000;
+
+/// Keyset-paginated listing, newest first. Unlike `LIMIT`/`OFFSET` paging,
+/// items edited between calls are neither skipped nor repeated, and late
+/// pages cost the same as early ones. Use `get_total_items_count` for the
+/// total.
+#[tauri::command]
+pub async fn get_password_overviews_page(
+    state: State<'_, AppState>,
+    limit: u32,
+    cursor: Option<OverviewCursor>,
+) -> Result<OverviewPage> {
+    let vault = UnlockedVault::acquire(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
+
+    let (mut items, next_cursor) = fetch_overview_page(
+        vault.pool(),
+        &helper,
+        &state.overview_cache,
+        cursor.as_ref(),
+        limit,
+    )
+    .await?;
+
+    let buttons = crate::db::buttons::get_buttons_impl(vault.pool(), vault.key()).await?;
+    apply_tag_colors(&mut items, &buttons);
+
+    Ok(OverviewPage { items, next_cursor })
+}
 
-    items
+/// One keyset page of decrypted overviews, without tag colors, and the cursor
+/// of the next page if there is one.
+async fn fetch_overview_page(
+    db_pool: &SqlitePool,
+    helper: &CryptoHelper,
+    cache: &std::sync::Mutex<OverviewCache>,
+    cursor: Option<&OverviewCursor>,
+    limit: u32,
+) -> Result<(Vec<PasswordItemOverview>, Option<OverviewCursor>)> {
+    let limit = limit.clamp(1, MAX_PAGE_SIZE);
+
+    let mut sql = "SELECT id, category, title, description, img, tags, username, url, created_at, updated_at, color FROM password_items".to_string();
+    if cursor.is_some() {
+        sql.push_str(" WHERE updated_at < ? OR (updated_at = ? AND id < ?)");
+    }
+    sql.push_str(" ORDER BY updated_at DESC, id DESC LIMIT ?");
+
+    let mut q = sqlx::query(&sql);
+    if let Some(c) = cursor {
+        q = q.bind(&c.updated_at).bind(&c.updated_at).bind(c.id);
+    }
+    // One extra row tells whether another page follows.
+    let mut rows = q.bind(limit as i64 + 1).fetch_all(db_pool).await?;
+    let has_more = rows.len() > limit as usize;
+    rows.truncate(limit as usize);
+
+    let items = decrypt_overview_rows(rows, helper, cache)?;
+
+    let next_cursor = items
+        .last()
+        .filter(|_| has_more)
+        .map(|last| OverviewCursor {
+            updated_at: last.updated_at.clone(),
+            id: last.id,
+        });
+    Ok((items, next_cursor))
+}
+
+const DEFAULT_STREAM_BATCH_SIZE: u32 = 200;
+
+/// Sends every overview to `on_batch` in batches of `batch_size`, newest
+/// first, so the list can render before the whole vault is decrypted.
+/// Returns the number of items sent.
+#[tauri::command]
+pub async fn stream_password_overviews(
+    state: State<'_, AppState>,
+    on_batch: Channel<Vec<PasswordItemOverview>>,
+    batch_size: Option<u32>,
+) -> Result<usize> {
+    let vault = UnlockedVault::acquire(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
+    let buttons = crate::db::buttons::get_buttons_impl(vault.pool(), vault.key()).await?;
+    let batch_size = batch_size.unwrap_or(DEFAULT_STREAM_BATCH_SIZE);
+
+    let mut sent = 0;
+    let mut cursor = None;
+    loop {
+        let (mut items, next_cursor) = fetch_overview_page(
+            vault.pool(),
+            &helper,
+            &state.overview_cache,
+            cursor.as_ref(),
+            batch_size,
+        )
+        .await?;
+        apply_tag_colors(&mut items, &buttons);
+        sent += items.len();
+        on_batch
+            .send(items)
+            .map_err(|e| Error::Internal(format!("Failed to send batch: {}", e)))?;
+
+        match next_cursor {
+            Some(next) => cursor = Some(next),
+            None => return Ok(sent),
+        }
+    }
 }
 
 #[tauri::command]
@@ -570,10 +953,10 @@ pub async fn get_password_overviews_by_ids(
     if ids.is_empty() {
         return Ok(Vec::new());
     }
+    limits::check_count("Item ids", ids.len(), limits::MAX_BATCH_IDS)?;
 
-    let key = get_key(&state).await?;
-    let db_pool = get_db_pool(&state).await?;
-    let helper = CryptoHelper::new(key.as_slice())?;
+    let vault = UnlockedVault::acquire(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
 
     let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
     let sql = format!(
@@ -587,40 +970,34 @@ pub async fn get_password_overviews_by_ids(
         q = q.bind(id);
     }
 
-    let rows = q.fetch_all(&db_pool).await?;
+    let rows = q.fetch_all(vault.pool()).await?;
+    let mut items = decrypt_overview_rows(rows, &helper, &state.overview_cache)?;
 
-    // Parallel decryption using Rayon
-    use rayon::prelude::*;
-    let items: Result<Vec<PasswordItemOverview>> = rows
-        .into_par_iter()
-        .map(|row| decrypt_password_item_overview_row(&row, &helper))
-        .collect();
+    let buttons = crate::db::buttons::get_buttons_impl(vault.pool(), vault.key()).await?;
+    apply_tag_colors(&mut items, &buttons);
 
-    items
+    Ok(items)
 }
 
 #[tauri::command]
 pub async fn get_password_items(state: State<'_, AppState>) -> Result<Vec<PasswordItem>> {
-    let key = get_key(&state).await?;
-    let db_pool = get_db_pool(&state).await?;
-    get_password_items_impl(&db_pool, key.as_slice()).await
+    let vault = UnlockedVault::acquire(&state).await?;
+    get_password_items_impl(vault.pool(), vault.key()).await
 }
 
-#[tauri::command]
-pub async fn save_password_item(state: State<'_, AppState>, item: PasswordItem) -> Result<i64> {
-    item.validate()
-        .map_err(|e| Error::Validation(e.to_string()))?;
-
-    let key = get_key(&state).await?;
-    let helper = CryptoHelper::new(key.as_slice())?;
-    let now = Utc::now().to_rfc3339();
-
-    let prepared = PreparedPasswordItem::new(&item, &helper)?;
-
-    let db_pool = get_db_pool(&state).await?;
-    let mut tx = db_pool.begin().await?;
+/// Encrypts and inserts `item` along with its tag links and search indices.
+/// Callers are responsible for validation and for committing `tx`.
+pub(crate) async fn insert_password_item(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    helper: &CryptoHelper,
+    key: &[u8],
+    item: &PasswordItem,
+    now: &str,
+) -> Result<i64> {
+    let prepared = PreparedPasswordItem::new(item, helper)?;
+    let color = validate_palette_color(tx.as_mut(), item.color.as_deref()).await?;
 
-    let item_id = sqlx::query("INSERT INTO password_items (category, title, description, img, tags, username, url, notes, password, created_at, updated_at, color, totp_secret, custom_fields, field_order) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)")
+    let item_id = sqlx::query("INSERT INTO password_items (category, title, description, img, tags, username, url, notes, password, created_at, updated_at, color, totp_secret, custom_fields, field_order, autotype_patterns, details) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)")
         .bind(prepared.category)
         .bind(prepared.title)
         .bind(prepared.description)
@@ -630,30 +1007,62 @@ pub async fn save_password_item(state: State<'_, AppState>, item: PasswordItem)
         .bind(prepared.url)
         .bind(prepared.notes)
         .bind(prepared.password)
-        .bind(now.clone())
         .bind(now)
-        .bind(item.color)
+        .bind(now)
+        .bind(color)
         .bind(prepared.totp_secret)
         .bind(prepared.custom_fields)
         .bind(prepared.field_order)
+        .bind(prepared.autotype_patterns)
+        .bind(prepared.details)
         .execute(tx.as_mut())
         .await?
         .last_insert_rowid();
 
-    sync_item_tags(&mut tx, item_id, item.tags.as_ref(), key.as_slice()).await?;
+    sync_item_tags(tx, item_id, item.tags.as_ref(), key).await?;
+    let extra = crate::db::search_index::item_extended_text(tx.as_mut(), item).await?;
     sync_search_indices(
-        &mut tx,
+        tx,
         item_id,
-        &helper,
+        helper,
         &item.title,
         item.username.as_ref(),
         item.tags.as_ref(),
+        extra.as_deref().map(String::as_str),
     )
     .await?;
 
+    Ok(item_id)
+}
+
+#[tauri::command]
+pub async fn save_password_item(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    item: PasswordItem,
+    draft_id: Option<i64>,
+) -> Result<i64> {
+    limits::check_password_item(&item)?;
+
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let policy = load_password_policy(vault.pool(), vault.key()).await?;
+    validate_item(&item, &policy)?;
+    validate_card_changes(None, item.details.as_ref())?;
+    let helper = CryptoHelper::new(vault.key())?;
+    let now = Utc::now().to_rfc3339();
+    let mut tx = vault.pool().begin().await?;
+    let item_id = insert_password_item(&mut tx, &helper, vault.key(), &item, &now).await?;
+
+    if let Some(draft_id) = draft_id {
+        sqlx::query("DELETE FROM item_drafts WHERE id = ? AND item_id IS NULL")
+            .bind(draft_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
     let _ = crate::db::activity::log_activity_impl(
         tx.as_mut(),
-        key.as_slice(),
+        vault.key(),
         "item_created",
         Some(item_id),
         Some(&item.title),
@@ -662,24 +1071,82 @@ pub async fn save_password_item(state: State<'_, AppState>, item: PasswordItem)
     .await;
 
     tx.commit().await?;
+    crate::events::items_created(&app, vec![item_id]);
     Ok(item_id)
 }
 
 #[tauri::command]
-pub async fn update_password_item(state: State<'_, AppState>, item: PasswordItem) -> Result<()> {
-    item.validate()
-        .map_err(|e| Error::Validation(e.to_string()))?;
+pub async fn update_password_item(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    item: PasswordItem,
+) -> Result<()> {
+    limits::check_password_item(&item)?;
 
-    let key = get_key(&state).await?;
-    let helper = CryptoHelper::new(key.as_slice())?;
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let policy = load_password_policy(vault.pool(), vault.key()).await?;
+    validate_item(&item, &policy)?;
+    let helper = CryptoHelper::new(vault.key())?;
     let now = Utc::now().to_rfc3339();
+    let mut tx = vault.pool().begin().await?;
+    ensure_item_writable(tx.as_mut(), item.id).await?;
+    let stored = stored_item_details(tx.as_mut(), &helper, item.id).await?;
+    validate_card_changes(stored.as_ref(), item.details.as_ref())?;
+    apply_password_item_update(&mut tx, &helper, vault.key(), &item, &now).await?;
+
+    sqlx::query("DELETE FROM item_drafts WHERE item_id = ?")
+        .bind(item.id)
+        .execute(&mut *tx)
+        .await?;
+
+    let _ = crate::db::activity::log_activity_impl(
+        tx.as_mut(),
+        vault.key(),
+        "item_updated",
+        Some(item.id),
+        Some(&item.title),
+        Some("Password item updated"),
+    )
+    .await;
 
-    let prepared = PreparedPasswordItem::new(&item, &helper)?;
+    tx.commit().await?;
+    crate::events::items_updated(&app, vec![item.id]);
+    Ok(())
+}
 
-    let db_pool = get_db_pool(&state).await?;
-    let mut tx = db_pool.begin().await?;
+/// Encrypts `item` over the stored item with the same id and refreshes its tag
+/// links and search indices. Callers are responsible for validation and for
+/// committing `tx`.
+pub(crate) async fn apply_password_item_update(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    helper: &CryptoHelper,
+    key: &[u8],
+    item: &PasswordItem,
+    now: &str,
+) -> Result<()> {
+    let prepared = PreparedPasswordItem::new(item, helper)?;
+    let stored_color: Option<String> =
+        sqlx::query_scalar("SELECT color FROM password_items WHERE id = ?")
+            .bind(item.id)
+            .fetch_optional(tx.as_mut())
+            .await?
+            .flatten();
+    let color = if same_color(item.color.as_deref(), stored_color.as_deref()) {
+        stored_color
+    } else {
+        validate_palette_color(tx.as_mut(), item.color.as_deref()).await?
+    };
+    crate::db::note_history::record_note_version(
+        tx,
+        helper,
+        key,
+        item.id,
+        item.notes.as_ref().map(|n| n.as_str()),
+        now,
+    )
+    .await?;
 
-    sqlx::query("UPDATE password_items SET category = ?, title = ?, description = ?, img = ?, tags = ?, username = ?, url = ?, notes = ?, password = ?, updated_at = ?, color = ?, totp_secret = ?, custom_fields = ?, field_order = ? WHERE id = ?")
+    sqlx::query("UPDATE password_items SET category = ?, title = ?, description = ?, img = ?, tags = ?, username = ?, url = ?, notes = ?, password = ?, updated_at = ?, color = ?, totp_secret = ?, custom_fields = ?, field_order = ?, autotype_patterns = CASE WHEN ? THEN NULL ELSE COALESCE(?, autotype_patterns) END, details = CASE WHEN ? THEN NULL ELSE COALESCE(?, details) END WHERE id = ?")
         .bind(prepared.category)
         .bind(prepared.title)
         .bind(prepared.description)
@@ -690,54 +1157,142 @@ pub async fn update_password_item(state: State<'_, AppState>, item: PasswordItem
         .bind(prepared.notes)
         .bind(prepared.password)
         .bind(now)
-        .bind(item.color)
+        .bind(color)
         .bind(prepared.totp_secret)
         .bind(prepared.custom_fields)
         .bind(prepared.field_order)
+        // `None` keeps the stored patterns; an empty list clears them.
+        .bind(item.autotype_patterns.as_ref().is_some_and(Vec::is_empty))
+        .bind(prepared.autotype_patterns)
+        .bind(item.clear_details)
+        .bind(prepared.details)
         .bind(item.id)
         .execute(tx.as_mut())
         .await?;
 
-    sync_item_tags(&mut tx, item.id, item.tags.as_ref(), key.as_slice()).await?;
+    sync_item_tags(tx, item.id, item.tags.as_ref(), key).await?;
+    let extra = crate::db::search_index::item_extended_text(tx.as_mut(), item).await?;
     sync_search_indices(
-        &mut tx,
+        tx,
         item.id,
-        &helper,
+        helper,
         &item.title,
         item.username.as_ref(),
         item.tags.as_ref(),
+        extra.as_deref().map(String::as_str),
     )
     .await?;
 
-    let _ = crate::db::activity::log_activity_impl(
-        tx.as_mut(),
-        key.as_slice(),
-        "item_updated",
-        Some(item.id),
-        Some(&item.title),
-        Some("Password item updated"),
+    Ok(())
+}
+
+/// Stores a copy of `item` taken from another copy of the vault, over
+/// `target` or as a new row. Unlike `apply_password_item_update`, the row is
+/// written as is, read-only flag and timestamps included, without the
+/// editor's palette check, so one item cannot fail a whole merge.
+pub(crate) async fn store_synced_item(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    helper: &CryptoHelper,
+    key: &[u8],
+    target: Option<i64>,
+    item: &PasswordItem,
+    now: &str,
+) -> Result<i64> {
+    let prepared = PreparedPasswordItem::new(item, helper)?;
+    let item_id = match target {
+        Some(id) => {
+            crate::db::note_history::record_note_version(
+                tx,
+                helper,
+                key,
+                id,
+                item.notes.as_ref().map(|n| n.as_str()),
+                now,
+            )
+            .await?;
+            sqlx::query("UPDATE password_items SET category = ?, title = ?, description = ?, img = ?, tags = ?, username = ?, url = ?, notes = ?, password = ?, created_at = ?, updated_at = ?, color = ?, totp_secret = ?, custom_fields = ?, field_order = ?, autotype_patterns = ?, details = ?, read_only = ? WHERE id = ?")
+                .bind(prepared.category)
+                .bind(prepared.title)
+                .bind(prepared.description)
+                .bind(prepared.img)
+                .bind(prepared.tags)
+                .bind(prepared.username)
+                .bind(prepared.url)
+                .bind(prepared.notes)
+                .bind(prepared.password)
+                .bind(&item.created_at)
+                .bind(&item.updated_at)
+                .bind(&item.color)
+                .bind(prepared.totp_secret)
+                .bind(prepared.custom_fields)
+                .bind(prepared.field_order)
+                .bind(prepared.autotype_patterns)
+                .bind(prepared.details)
+                .bind(item.read_only)
+                .bind(id)
+                .execute(tx.as_mut())
+                .await?;
+            id
+        }
+        None => sqlx::query("INSERT INTO password_items (category, title, description, img, tags, username, url, notes, password, created_at, updated_at, color, totp_secret, custom_fields, field_order, autotype_patterns, details, read_only) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)")
+            .bind(prepared.category)
+            .bind(prepared.title)
+            .bind(prepared.description)
+            .bind(prepared.img)
+            .bind(prepared.tags)
+            .bind(prepared.username)
+            .bind(prepared.url)
+            .bind(prepared.notes)
+            .bind(prepared.password)
+            .bind(&item.created_at)
+            .bind(&item.updated_at)
+            .bind(&item.color)
+            .bind(prepared.totp_secret)
+            .bind(prepared.custom_fields)
+            .bind(prepared.field_order)
+            .bind(prepared.autotype_patterns)
+            .bind(prepared.details)
+            .bind(item.read_only)
+            .execute(tx.as_mut())
+            .await?
+            .last_insert_rowid(),
+    };
+
+    sync_item_tags(tx, item_id, item.tags.as_ref(), key).await?;
+    let extra = crate::db::search_index::item_extended_text(tx.as_mut(), item).await?;
+    sync_search_indices(
+        tx,
+        item_id,
+        helper,
+        &item.title,
+        item.username.as_ref(),
+        item.tags.as_ref(),
+        extra.as_deref().map(String::as_str),
     )
-    .await;
+    .await?;
 
-    tx.commit().await?;
-    Ok(())
+    Ok(item_id)
 }
 
 #[tauri::command]
-pub async fn delete_password_item(state: State<'_, AppState>, id: i64) -> Result<()> {
-    let key = get_key(&state).await?;
-    let db_pool = get_db_pool(&state).await?;
+pub async fn delete_password_item(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    id: i64,
+) -> Result<()> {
+    let vault = UnlockedVault::acquire_writable(&state).await?;
 
     let title_enc: Option<String> =
         sqlx::query_scalar("SELECT title FROM password_items WHERE id = ?")
             .bind(id)
-            .fetch_optional(&db_pool)
+            .fetch_optional(vault.pool())
             .await?;
 
-    let helper = CryptoHelper::new(key.as_slice())?;
+    let helper = CryptoHelper::new(vault.key())?;
     let title = title_enc.and_then(|t| helper.decrypt(&t).ok());
 
-    let mut tx = db_pool.begin().await?;
+    let mut tx = vault.pool().begin().await?;
+    ensure_item_writable(tx.as_mut(), id).await?;
 
     sqlx::query("DELETE FROM attachments WHERE item_id = ?")
         .bind(id)
@@ -754,6 +1309,21 @@ pub async fn delete_password_item(state: State<'_, AppState>, id: i64) -> Result
         .execute(&mut *tx)
         .await?;
 
+    sqlx::query("DELETE FROM item_drafts WHERE item_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM item_conflicts WHERE item_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM note_history WHERE item_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
     sqlx::query("DELETE FROM password_items WHERE id = ?")
         .bind(id)
         .execute(tx.as_mut())
@@ -761,7 +1331,7 @@ pub async fn delete_password_item(state: State<'_, AppState>, id: i64) -> Result
 
     let _ = crate::db::activity::log_activity_impl(
         tx.as_mut(),
-        key.as_slice(),
+        vault.key(),
         "item_deleted",
         Some(id),
         title.as_deref(),
@@ -770,30 +1340,152 @@ pub async fn delete_password_item(state: State<'_, AppState>, id: i64) -> Result
     .await;
 
     tx.commit().await?;
+    crate::events::items_deleted(&app, vec![id]);
+    Ok(())
+}
+
+/// Fails with `ItemReadOnly` when item `id` is flagged read-only.
+pub(crate) async fn ensure_item_writable<'a, E>(executor: E, id: i64) -> Result<()>
+where
+    E: sqlx::SqliteExecutor<'a>,
+{
+    let read_only: Option<bool> =
+        sqlx::query_scalar("SELECT read_only FROM password_items WHERE id = ?")
+            .bind(id)
+            .fetch_optional(executor)
+            .await?;
+    if read_only == Some(true) {
+        return Err(Error::ItemReadOnly { item_id: id });
+    }
     Ok(())
 }
 
+/// The type-specific details item `id` is stored with, for checks that only
+/// apply to changed values.
+pub(crate) async fn stored_item_details<'a, E>(
+    executor: E,
+    helper: &CryptoHelper,
+    id: i64,
+) -> Result<Option<ItemDetails>>
+where
+    E: sqlx::SqliteExecutor<'a>,
+{
+    let details: Option<String> =
+        sqlx::query_scalar("SELECT details FROM password_items WHERE id = ?")
+            .bind(id)
+            .fetch_optional(executor)
+            .await?
+            .flatten();
+    Ok(helper
+        .decrypt_secret_opt(details)?
+        .and_then(|d| serde_json::from_str(d.as_str()).ok()))
+}
+
+async fn set_read_only_flag(
+    app: &tauri::AppHandle,
+    state: &State<'_, AppState>,
+    id: i64,
+    read_only: bool,
+) -> Result<()> {
+    let vault = UnlockedVault::acquire_writable(state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
+    let mut tx = vault.pool().begin().await?;
+
+    let title_enc: Option<String> =
+        sqlx::query_scalar("SELECT title FROM password_items WHERE id = ?")
+            .bind(id)
+            .fetch_optional(tx.as_mut())
+            .await?;
+    let Some(title_enc) = title_enc else {
+        return Err(Error::Validation(format!("No item with id {}", id)));
+    };
+    let title = helper.decrypt(&title_enc).ok();
+
+    sqlx::query("UPDATE password_items SET read_only = ? WHERE id = ?")
+        .bind(read_only)
+        .bind(id)
+        .execute(tx.as_mut())
+        .await?;
+
+    let (event, details) = if read_only {
+        ("item_read_only_set", "Item marked read-only")
+    } else {
+        ("item_read_only_cleared", "Read-only flag cleared")
+    };
+    let _ = crate::db::activity::log_activity_impl(
+        tx.as_mut(),
+        vault.key(),
+        event,
+        Some(id),
+        title.as_deref(),
+        Some(details),
+    )
+    .await;
+
+    tx.commit().await?;
+    crate::events::items_updated(app, vec![id]);
+    Ok(())
+}
+
+/// Protects an item from edits and deletion, e.g. one provisioned by an
+/// administrator.
+#[tauri::command]
+pub async fn mark_password_item_read_only(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    id: i64,
+) -> Result<()> {
+    set_read_only_flag(&app, &state, id, true).await
+}
+
+/// Makes a read-only item editable again. The change is recorded in the
+/// activity log.
+#[tauri::command]
+pub async fn clear_password_item_read_only(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    id: i64,
+) -> Result<()> {
+    set_read_only_flag(&app, &state, id, false).await
+}
+
+/// Reads and decrypts one item without its attachments.
+pub(crate) async fn fetch_password_item<'a, E>(
+    executor: E,
+    helper: &CryptoHelper,
+    id: i64,
+) -> Result<Option<PasswordItem>>
+where
+    E: sqlx::SqliteExecutor<'a>,
+{
+    let row = sqlx::query("SELECT id, category, title, description, img, tags, username, url, notes, password, created_at, updated_at, color, totp_secret, custom_fields, field_order, autotype_patterns, details, read_only FROM password_items WHERE id = ?")
+        .bind(id)
+        .fetch_optional(executor)
+        .await?;
+    row.map(|row| decrypt_password_item_row(&row, helper, None))
+        .transpose()
+}
+
 #[tauri::command]
 pub async fn get_password_item_by_id(
     state: State<'_, AppState>,
     id: i64,
 ) -> Result<Option<PasswordItem>> {
-    let key = get_key(&state).await?;
-    let db_pool = get_db_pool(&state).await?;
-    let row = sqlx::query("SELECT id, category, title, description, img, tags, username, url, notes, password, created_at, updated_at, color, totp_secret, custom_fields, field_order FROM password_items WHERE id = ?")
+    let vault = UnlockedVault::acquire(&state).await?;
+    let row = sqlx::query("SELECT id, category, title, description, img, tags, username, url, notes, password, created_at, updated_at, color, totp_secret, custom_fields, field_order, autotype_patterns, details, read_only FROM password_items WHERE id = ?")
         .bind(id)
-        .fetch_optional(&db_pool)
+        .fetch_optional(vault.pool())
         .await?;
 
     if let Some(row) = row {
-        let helper = CryptoHelper::new(key.as_slice())?;
+        let helper = CryptoHelper::new(vault.key())?;
         let id: i64 = row.get("id");
 
         let attachments = sqlx::query(
             "SELECT id, file_name, file_size, mime_type, created_at FROM attachments WHERE item_id = ?",
         )
         .bind(id)
-        .fetch_all(&db_pool)
+        .fetch_all(vault.pool())
         .await?;
 
         let mut decrypted_attachments = Vec::new();
@@ -820,18 +1512,145 @@ pub async fn get_password_item_by_id(
     }
 }
 
+/// Columns `get_item_fields` may read. Also the whitelist that keeps caller
+/// supplied names out of the SQL.
+const ITEM_FIELD_COLUMNS: &[&str] = &[
+    "category",
+    "title",
+    "description",
+    "img",
+    "tags",
+    "username",
+    "url",
+    "notes",
+    "password",
+    "created_at",
+    "updated_at",
+    "color",
+    "totp_secret",
+    "custom_fields",
+    "field_order",
+    "autotype_patterns",
+    "details",
+];
+
+/// Reads and decrypts only `fields` of one item, e.g. just `password` for a
+/// copy action, instead of the whole item with its attachments.
+#[tauri::command]
+pub async fn get_item_fields(
+    state: State<'_, AppState>,
+    item_id: i64,
+    fields: Vec<String>,
+) -> Result<Option<PasswordItemFields>> {
+    limits::check_count("Fields", fields.len(), ITEM_FIELD_COLUMNS.len())?;
+    let mut columns: Vec<&str> = Vec::with_capacity(fields.len());
+    for field in &fields {
+        let column = ITEM_FIELD_COLUMNS
+            .iter()
+            .find(|c| **c == field.as_str())
+            .ok_or_else(|| Error::Validation(format!("Unknown item field '{}'", field)))?;
+        if !columns.contains(column) {
+            columns.push(column);
+        }
+    }
+
+    let vault = UnlockedVault::acquire(&state).await?;
+    let mut select = String::from("id");
+    for column in &columns {
+        select.push_str(", ");
+        select.push_str(column);
+    }
+    let row = sqlx::query(&format!(
+        "SELECT {} FROM password_items WHERE id = ?",
+        select
+    ))
+    .bind(item_id)
+    .fetch_optional(vault.pool())
+    .await?;
+
+    let Some(row) = row else {
+        return Ok(None);
+    };
+    let helper = CryptoHelper::new(vault.key())?;
+    let mut result = PasswordItemFields {
+        id: row.get("id"),
+        ..Default::default()
+    };
+
+    for column in columns {
+        match column {
+            "category" => {
+                let enc: String = row.get("category");
+                result.category =
+                    Some(helper.decrypt(&enc).unwrap_or_else(|_| "login".to_string()));
+            }
+            "title" => {
+                let enc: String = row.get("title");
+                result.title = Some(helper.decrypt(&enc)?);
+            }
+            "description" => result.description = helper.decrypt_opt(row.get("description"))?,
+            "img" => result.img = helper.decrypt_opt(row.get("img"))?,
+            "tags" => result.tags = helper.decrypt_opt(row.get("tags"))?,
+            "username" => result.username = helper.decrypt_opt(row.get("username"))?,
+            "url" => result.url = helper.decrypt_opt(row.get("url"))?,
+            "notes" => result.notes = helper.decrypt_secret_opt(row.get("notes"))?,
+            "password" => {
+                let enc: String = row.get("password");
+                result.password = Some(helper.decrypt_secret(&enc)?);
+            }
+            "created_at" => result.created_at = Some(row.get("created_at")),
+            "updated_at" => result.updated_at = Some(row.get("updated_at")),
+            "color" => result.color = row.get("color"),
+            "totp_secret" => {
+                result.totp_secret = helper.decrypt_secret_opt(row.get("totp_secret"))?
+            }
+            "custom_fields" => {
+                let enc: Option<String> = row.get("custom_fields");
+                result.custom_fields = Some(
+                    enc.map(|cf| helper.decrypt(&cf))
+                        .transpose()?
+                        .map(|cf| serde_json::from_str(&cf).unwrap_or_default())
+                        .unwrap_or_default(),
+                );
+            }
+            "field_order" => {
+                let enc: Option<String> = row.get("field_order");
+                result.field_order = enc
+                    .and_then(|fo| helper.decrypt(&fo).ok())
+                    .and_then(|fo| serde_json::from_str(&fo).ok());
+            }
+            "autotype_patterns" => {
+                result.autotype_patterns = helper
+                    .decrypt_opt(row.get("autotype_patterns"))?
+                    .map(|p| serde_json::from_str(&p))
+                    .transpose()?;
+            }
+            "details" => {
+                let enc: Option<String> = row.get("details");
+                result.details = enc
+                    .and_then(|d| helper.decrypt_secret(&d).ok())
+                    .and_then(|d| serde_json::from_str(d.as_str()).ok());
+            }
+            _ => unreachable!("column is taken from ITEM_FIELD_COLUMNS"),
+        }
+    }
+
+    Ok(Some(result))
+}
+
 #[tauri::command]
 pub async fn update_password_item_tags(
+    app: tauri::AppHandle,
     state: State<'_, AppState>,
     id: i64,
     tags: String,
 ) -> Result<()> {
-    let key = get_key(&state).await?;
-    let helper = CryptoHelper::new(key.as_slice())?;
+    limits::check_len("Tags", &tags, limits::MAX_TAGS_LEN)?;
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
     let now = Utc::now().to_rfc3339();
-
-    let db_pool = get_db_pool(&state).await?;
-    let mut tx = db_pool.begin().await?;
+    let mut tx = vault.pool().begin().await?;
+    ensure_item_writable(tx.as_mut(), id).await?;
 
     let row = sqlx::query("SELECT title, username FROM password_items WHERE id = ?")
         .bind(id)
@@ -857,20 +1676,37 @@ pub async fn update_password_item_tags(
     let title = helper.decrypt(&title_enc)?;
     let username = username_enc.map(|u| helper.decrypt(&u)).transpose()?;
 
-    sync_search_indices(&mut tx, id, &helper, &title, username.as_ref(), Some(&tags)).await?;
+    let extra = crate::db::search_index::stored_extended_text(tx.as_mut(), &helper, id).await?;
+    sync_search_indices(
+        &mut tx,
+        id,
+        &helper,
+        &title,
+        username.as_ref(),
+        Some(&tags),
+        extra.as_deref().map(String::as_str),
+    )
+    .await?;
 
     tx.commit().await?;
+    crate::events::items_updated(&app, vec![id]);
     Ok(())
 }
 
 #[tauri::command]
 pub async fn update_password_item_totp_secret(
+    app: tauri::AppHandle,
     state: State<'_, AppState>,
     id: i64,
     totp_secret: Option<String>,
 ) -> Result<()> {
-    let key = get_key(&state).await?;
-    let helper = CryptoHelper::new(key.as_slice())?;
+    limits::check_opt_len(
+        "TOTP secret",
+        totp_secret.as_deref(),
+        limits::MAX_TOTP_SECRET_LEN,
+    )?;
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
     let now = Utc::now().to_rfc3339();
     let totp_secret_clean = totp_secret.and_then(|secret| {
         let trimmed = secret.trim().to_string();
@@ -884,8 +1720,8 @@ pub async fn update_password_item_totp_secret(
         Some(secret) => Some(helper.encrypt(&secret)?),
         None => None,
     };
-    let db_pool = get_db_pool(&state).await?;
-    let mut tx = db_pool.begin().await?;
+    let mut tx = vault.pool().begin().await?;
+    ensure_item_writable(tx.as_mut(), id).await?;
 
     sqlx::query("UPDATE password_items SET totp_secret = ?, updated_at = ? WHERE id = ?")
         .bind(totp_secret_enc)
@@ -906,6 +1742,7 @@ pub async fn update_password_item_totp_secret(
     let title = helper.decrypt(&title_enc)?;
     let username = username_enc.map(|u| helper.decrypt(&u)).transpose()?;
     let tags = tags_enc.map(|t| helper.decrypt(&t)).transpose()?;
+    let extra = crate::db::search_index::stored_extended_text(tx.as_mut(), &helper, id).await?;
 
     sync_search_indices(
         &mut tx,
@@ -914,27 +1751,40 @@ pub async fn update_password_item_totp_secret(
         &title,
         username.as_ref(),
         tags.as_ref(),
+        extra.as_deref().map(String::as_str),
     )
     .await?;
 
     tx.commit().await?;
+    crate::events::items_updated(&app, vec![id]);
     Ok(())
 }
 
 #[tauri::command]
 pub async fn add_custom_field(
+    app: tauri::AppHandle,
     state: State<'_, AppState>,
     item_id: i64,
     field_name: String,
     field_type: String,
 ) -> Result<()> {
-    let key = get_key(&state).await?;
-    let helper = CryptoHelper::new(key.as_slice())?;
-    let db_pool = get_db_pool(&state).await?;
+    limits::check_len(
+        "Custom field name",
+        &field_name,
+        limits::MAX_CUSTOM_FIELD_NAME_LEN,
+    )?;
+    limits::check_len(
+        "Custom field type",
+        &field_type,
+        limits::MAX_CUSTOM_FIELD_NAME_LEN,
+    )?;
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
+    ensure_item_writable(vault.pool(), item_id).await?;
 
     let row = sqlx::query("SELECT custom_fields FROM password_items WHERE id = ?")
         .bind(item_id)
-        .fetch_one(&db_pool)
+        .fetch_one(vault.pool())
         .await?;
 
     let custom_fields_enc: Option<String> = row.get("custom_fields");
@@ -944,6 +1794,11 @@ pub async fn add_custom_field(
         .unwrap_or_else(|| "[]".to_string());
 
     let mut custom_fields: Vec<CustomField> = serde_json::from_str(&custom_fields_json)?;
+    limits::check_count(
+        "Custom fields",
+        custom_fields.len() + 1,
+        limits::MAX_CUSTOM_FIELDS,
+    )?;
 
     custom_fields.push(CustomField {
         name: field_name,
@@ -959,28 +1814,27 @@ pub async fn add_custom_field(
         .bind(updated_custom_fields_enc)
         .bind(now)
         .bind(item_id)
-        .execute(&db_pool)
+        .execute(vault.pool())
         .await?;
 
+    crate::events::items_updated(&app, vec![item_id]);
     Ok(())
 }
 
 #[tauri::command]
 pub async fn get_total_items_count(state: State<'_, AppState>) -> Result<i64> {
-    let _key = get_key(&state).await?;
-    let db_pool = get_db_pool(&state).await?;
+    let vault = UnlockedVault::acquire(&state).await?;
     let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM password_items")
-        .fetch_one(&db_pool)
+        .fetch_one(vault.pool())
         .await?;
     Ok(count)
 }
 
 #[tauri::command]
 pub async fn get_favorites_count(state: State<'_, AppState>) -> Result<i64> {
-    let key = get_key(&state).await?;
-    let db_pool = get_db_pool(&state).await?;
+    let vault = UnlockedVault::acquire(&state).await?;
 
-    let buttons = crate::db::buttons::get_buttons_impl(&db_pool, key.as_slice()).await?;
+    let buttons = crate::db::buttons::get_buttons_impl(vault.pool(), vault.key()).await?;
     let target_names = vec!["favorite", "fav", "star"];
     let tag_ids: Vec<i64> = buttons
         .iter()
@@ -1003,24 +1857,25 @@ pub async fn get_favorites_count(state: State<'_, AppState>) -> Result<i64> {
         q = q.bind(id);
     }
 
-    let count: i64 = q.fetch_one(&db_pool).await?;
+    let count: i64 = q.fetch_one(vault.pool()).await?;
     Ok(count)
 }
 
 #[tauri::command]
 pub async fn remove_tag_from_password_items(
+    app: tauri::AppHandle,
     state: State<'_, AppState>,
     tag: String,
 ) -> Result<i64> {
-    let key = get_key(&state).await?;
-    let db_pool = get_db_pool(&state).await?;
-    let helper = CryptoHelper::new(key.as_slice())?;
+    limits::check_len("Tag", &tag, limits::MAX_TITLE_LEN)?;
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
     let tag_trimmed = tag.trim().to_string();
     if tag_trimmed.is_empty() {
         return Ok(0);
     }
 
-    let mut tx = db_pool.begin().await?;
+    let mut tx = vault.pool().begin().await?;
 
     let tag_id: Option<i64> = sqlx::query_scalar("SELECT id FROM buttons WHERE text = ?")
         .bind(helper.encrypt(&tag_trimmed)?)
@@ -1036,6 +1891,9 @@ pub async fn remove_tag_from_password_items(
         .fetch_all(&mut *tx)
         .await?;
 
+    for &id in &item_ids {
+        ensure_item_writable(&mut *tx, id).await?;
+    }
     let updated_count = item_ids.len() as i64;
 
     sqlx::query("DELETE FROM item_tags WHERE tag_id = ?")
@@ -1044,7 +1902,7 @@ pub async fn remove_tag_from_password_items(
         .await?;
 
     let now = Utc::now().to_rfc3339();
-    for id in item_ids {
+    for &id in &item_ids {
         let tags_enc: Option<String> =
             sqlx::query_scalar("SELECT tags FROM password_items WHERE id = ?")
                 .bind(id)
@@ -1078,25 +1936,29 @@ pub async fn remove_tag_from_password_items(
     }
 
     tx.commit().await?;
+    crate::events::items_updated(&app, item_ids);
+    crate::events::tag_changed(&app, Some(tag_id));
     Ok(updated_count)
 }
 
 #[tauri::command]
 pub async fn rename_tag_in_password_items(
+    app: tauri::AppHandle,
     state: State<'_, AppState>,
     old_tag: String,
     new_tag: String,
 ) -> Result<i64> {
-    let key = get_key(&state).await?;
-    let db_pool = get_db_pool(&state).await?;
-    let helper = CryptoHelper::new(key.as_slice())?;
+    limits::check_len("Tag", &old_tag, limits::MAX_TITLE_LEN)?;
+    limits::check_len("Tag", &new_tag, limits::MAX_TITLE_LEN)?;
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
     let old_trimmed = old_tag.trim().to_string();
     let new_trimmed = new_tag.trim().to_string();
     if old_trimmed.is_empty() || new_trimmed.is_empty() {
         return Ok(0);
     }
 
-    let mut tx = db_pool.begin().await?;
+    let mut tx = vault.pool().begin().await?;
 
     let tag_id: Option<i64> = sqlx::query_scalar("SELECT id FROM buttons WHERE text = ?")
         .bind(helper.encrypt(&old_trimmed)?)
@@ -1112,10 +1974,13 @@ pub async fn rename_tag_in_password_items(
         .fetch_all(&mut *tx)
         .await?;
 
+    for &id in &item_ids {
+        ensure_item_writable(&mut *tx, id).await?;
+    }
     let updated_count = item_ids.len() as i64;
 
     let now = Utc::now().to_rfc3339();
-    for id in item_ids {
+    for &id in &item_ids {
         let tags_enc: Option<String> =
             sqlx::query_scalar("SELECT tags FROM password_items WHERE id = ?")
                 .bind(id)
@@ -1153,5 +2018,7 @@ pub async fn rename_tag_in_password_items(
     }
 
     tx.commit().await?;
+    crate::events::items_updated(&app, item_ids);
+    crate::events::tag_changed(&app, Some(tag_id));
     Ok(updated_count)
 }
@@ -0,0 +1,167 @@
+use crate::auth::verify_master_password_internal;
+use crate::db::utils::{CryptoHelper, UnlockedVault};
+use crate::error::{Error, Result};
+use crate::state::AppState;
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{Column, Connection, Row, TypeInfo, ValueRef};
+use tauri::State;
+use zeroize::Zeroizing;
+
+const MAX_QUERY_LEN: usize = 10_000;
+const MAX_QUERY_ROWS: usize = 1000;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+    /// More rows matched than were returned.
+    pub truncated: bool,
+}
+
+#[derive(Deserialize, Default)]
+struct ConsoleSettings {
+    #[serde(default)]
+    advanced: ConsoleAdvancedSettings,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct ConsoleAdvancedSettings {
+    #[serde(default)]
+    sql_console: bool,
+}
+
+async fn console_enabled(app: &tauri::AppHandle) -> bool {
+    crate::settings::get_all_settings_internal(app)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str::<ConsoleSettings>(&json).ok())
+        .unwrap_or_default()
+        .advanced
+        .sql_console
+}
+
+/// Accepts a single `SELECT` (optionally `WITH ... SELECT`) statement and
+/// returns it without the trailing semicolon.
+fn validate_select(sql: &str) -> Result<&str> {
+    let sql = sql.trim().trim_end_matches(';').trim_end();
+    if sql.is_empty() {
+        return Err(Error::Validation("Query is empty.".to_string()));
+    }
+    if sql.len() > MAX_QUERY_LEN {
+        return Err(Error::Validation("Query is too long.".to_string()));
+    }
+    if sql.contains(';') {
+        return Err(Error::Validation(
+            "Only a single statement can be run.".to_string(),
+        ));
+    }
+    let first_word = sql
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    if first_word != "select" && first_word != "with" {
+        return Err(Error::Validation(
+            "Only SELECT statements are allowed.".to_string(),
+        ));
+    }
+    Ok(sql)
+}
+
+/// Converts one cell to JSON, replacing ciphertext with its plaintext when the
+/// vault key opens it.
+fn cell_value(row: &sqlx::sqlite::SqliteRow, index: usize, helper: &CryptoHelper) -> Value {
+    let type_name = match row.try_get_raw(index) {
+        Ok(raw) if raw.is_null() => return Value::Null,
+        Ok(raw) => raw.type_info().name().to_string(),
+        Err(_) => return Value::Null,
+    };
+    match type_name.as_str() {
+        "INTEGER" => row.try_get::<i64, _>(index).map(Value::from),
+        "REAL" => row.try_get::<f64, _>(index).map(Value::from),
+        "BLOB" => row
+            .try_get::<Vec<u8>, _>(index)
+            .map(|bytes| Value::from(general_purpose::STANDARD.encode(bytes))),
+        _ => row
+            .try_get::<String, _>(index)
+            .map(|text| Value::from(helper.decrypt(&text).unwrap_or(text))),
+    }
+    .unwrap_or(Value::Null)
+}
+
+/// Runs an ad-hoc `SELECT` against the unlocked vault and returns at most
+/// `MAX_QUERY_ROWS` rows with encrypted columns decrypted. Requires the
+/// `advanced.sqlConsole` setting and the master password. The query runs on a
+/// connection taken out of the pool and switched to `query_only`; it is closed
+/// afterwards rather than returned, so the pragma can never leak to other
+/// queries, even if this command is cancelled.
+#[tauri::command]
+pub async fn execute_readonly_query(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    sql: String,
+    password: String,
+) -> Result<QueryResult> {
+    if !console_enabled(&app).await {
+        return Err(Error::Validation(
+            "The SQL console is disabled. Enable it in advanced settings.".to_string(),
+        ));
+    }
+    let password = Zeroizing::new(password);
+    if !verify_master_password_internal(&state, password.as_str()).await? {
+        return Err(Error::InvalidPassword);
+    }
+    let sql = validate_select(&sql)?;
+
+    let vault = UnlockedVault::acquire(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
+    let mut conn = vault.pool().acquire().await?.detach();
+
+    sqlx::query("PRAGMA query_only = ON")
+        .execute(&mut conn)
+        .await?;
+    let wrapped = format!("SELECT * FROM ({}) LIMIT ?", sql);
+    let rows = sqlx::query(&wrapped)
+        .bind(MAX_QUERY_ROWS as i64 + 1)
+        .fetch_all(&mut conn)
+        .await;
+    let _ = conn.close().await;
+    let mut rows = rows?;
+
+    let truncated = rows.len() > MAX_QUERY_ROWS;
+    rows.truncate(MAX_QUERY_ROWS);
+
+    let columns = rows
+        .first()
+        .map(|row| row.columns().iter().map(|c| c.name().to_string()).collect())
+        .unwrap_or_default();
+    let rows = rows
+        .iter()
+        .map(|row| {
+            (0..row.len())
+                .map(|i| cell_value(row, i, &helper))
+                .collect()
+        })
+        .collect();
+
+    let _ = crate::db::activity::log_activity_impl(
+        vault.pool(),
+        vault.key(),
+        "sql_console_query",
+        None,
+        None,
+        Some(&format!("Ran a read-only query: {}", sql)),
+    )
+    .await;
+
+    Ok(QueryResult {
+        columns,
+        rows,
+        truncated,
+    })
+}
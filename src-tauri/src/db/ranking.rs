@@ -0,0 +1,310 @@
+//! Relevance scoring for search results. The search index only holds keyed
+//! hashes, so scoring runs on the decrypted overviews of the candidate set.
+
+use crate::types::PasswordItemOverview;
+use std::collections::HashSet;
+
+const TRIGRAM_WEIGHT: f64 = 1.0;
+const TITLE_EXACT_WEIGHT: f64 = 3.0;
+const TITLE_PREFIX_WEIGHT: f64 = 1.5;
+const TITLE_TOKEN_WEIGHT: f64 = 1.0;
+const USERNAME_EXACT_WEIGHT: f64 = 2.0;
+const USERNAME_TOKEN_WEIGHT: f64 = 0.75;
+const HOST_MATCH_WEIGHT: f64 = 1.5;
+const HOST_PARTIAL_WEIGHT: f64 = 0.75;
+
+fn trigrams(text: &str) -> HashSet<String> {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() < 3 {
+        return std::iter::once(text.to_string())
+            .filter(|t| !t.is_empty())
+            .collect();
+    }
+    chars.windows(3).map(|w| w.iter().collect()).collect()
+}
+
+fn tokens(text: &str) -> Vec<&str> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+/// Share of `query_tokens` that appear as whole tokens in `text`.
+fn token_overlap(query_tokens: &[&str], text: &str) -> f64 {
+    if query_tokens.is_empty() {
+        return 0.0;
+    }
+    let field_tokens: HashSet<&str> = tokens(text).into_iter().collect();
+    let matched = query_tokens
+        .iter()
+        .filter(|t| field_tokens.contains(*t))
+        .count();
+    matched as f64 / query_tokens.len() as f64
+}
+
+fn url_host(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    let with_scheme = if raw.contains("://") {
+        raw.to_string()
+    } else {
+        format!("https://{}", raw)
+    };
+    url::Url::parse(&with_scheme)
+        .ok()?
+        .host_str()
+        .map(|h| h.trim_start_matches("www.").to_lowercase())
+}
+
+fn host_score(host: &str, query: &str) -> f64 {
+    if host == query || host.split('.').any(|label| label == query) {
+        HOST_MATCH_WEIGHT
+    } else if host.contains(query) {
+        HOST_PARTIAL_WEIGHT
+    } else {
+        0.0
+    }
+}
+
+/// Scores how well `item` matches `query`: trigram overlap across the
+/// searchable fields, exact and whole-token matches on title and username,
+/// and matches against the URL host. Higher is better; 0 means no signal.
+pub fn relevance_score(item: &PasswordItemOverview, query: &str) -> f64 {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return 0.0;
+    }
+    let query_tokens = tokens(&query);
+    let title = item.title.to_lowercase();
+    let username = item.username.as_deref().unwrap_or_default().to_lowercase();
+
+    let mut haystack = title.clone();
+    for field in [
+        item.username.as_deref(),
+        item.url.as_deref(),
+        item.tags.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    {
+        haystack.push(' ');
+        haystack.push_str(&field.to_lowercase());
+    }
+    let query_trigrams = trigrams(&query);
+    let field_trigrams = trigrams(&haystack);
+    let overlap = query_trigrams
+        .iter()
+        .filter(|t| field_trigrams.contains(*t))
+        .count() as f64
+        / query_trigrams.len().max(1) as f64;
+
+    let mut score = overlap * TRIGRAM_WEIGHT;
+
+    if title == query {
+        score += TITLE_EXACT_WEIGHT;
+    } else if title.starts_with(&query) {
+        score += TITLE_PREFIX_WEIGHT;
+    }
+    score += token_overlap(&query_tokens, &title) * TITLE_TOKEN_WEIGHT;
+
+    if !username.is_empty() {
+        if username == query {
+            score += USERNAME_EXACT_WEIGHT;
+        } else {
+            score += token_overlap(&query_tokens, &username) * USERNAME_TOKEN_WEIGHT;
+        }
+    }
+
+    if let Some(host) = item.url.as_deref().and_then(url_host) {
+        score += host_score(&host, &query);
+    }
+
+    (score * 1000.0).round() / 1000.0
+}
+
+/// Edit distance between `a` and `b` counted in characters.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// Whether every word of `query` is within `max_typos` edits of a word, or of
+/// the start of a word, in the title, username, URL or tags of `item`. Used to
+/// confirm candidates that only matched the index loosely.
+pub fn matches_with_typos(item: &PasswordItemOverview, query: &str, max_typos: usize) -> bool {
+    let query = query.trim().to_lowercase();
+    let query_tokens = tokens(&query);
+    if query_tokens.is_empty() {
+        return false;
+    }
+
+    let mut haystack = item.title.to_lowercase();
+    for field in [
+        item.username.as_deref(),
+        item.url.as_deref(),
+        item.tags.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    {
+        haystack.push(' ');
+        haystack.push_str(&field.to_lowercase());
+    }
+    let field_tokens = tokens(&haystack);
+
+    query_tokens.iter().all(|q| {
+        let q_len = q.chars().count();
+        // A typo budget larger than the word itself would match anything.
+        let allowed = max_typos.min(q_len.saturating_sub(1) / 2);
+        field_tokens.iter().any(|t| {
+            if levenshtein(q, t) <= allowed {
+                return true;
+            }
+            let prefix: String = t.chars().take(q_len).collect();
+            prefix.chars().count() == q_len && levenshtein(q, &prefix) <= allowed
+        })
+    })
+}
+
+/// Scores `items` against `query` and sorts them by descending score. The
+/// sort is stable, so equally scored items keep their previous order.
+pub fn rank_by_relevance(items: &mut [PasswordItemOverview], query: &str) {
+    for item in items.iter_mut() {
+        item.score = Some(relevance_score(item, query));
+    }
+    items.sort_by(|a, b| {
+        b.score
+            .unwrap_or_default()
+            .total_cmp(&a.score.unwrap_or_default())
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(
+        id: i64,
+        title: &str,
+        username: Option<&str>,
+        url: Option<&str>,
+    ) -> PasswordItemOverview {
+        PasswordItemOverview {
+            id,
+            category: "login".to_string(),
+            title: title.to_string(),
+            description: None,
+            img: None,
+            tags: None,
+            username: username.map(str::to_string),
+            url: url.map(str::to_string),
+            created_at: String::new(),
+            updated_at: String::new(),
+            color: None,
+            tag_colors: Default::default(),
+            score: None,
+        }
+    }
+
+    #[test]
+    fn test_exact_title_beats_prefix_and_substring() {
+        let exact = relevance_score(&item(1, "GitHub", None, None), "github");
+        let prefix = relevance_score(&item(2, "GitHub Enterprise", None, None), "github");
+        let substring = relevance_score(&item(3, "My GitHub account", None, None), "github");
+        assert!(exact > prefix, "{exact} <= {prefix}");
+        assert!(prefix > substring, "{prefix} <= {substring}");
+        assert!(substring > 0.0);
+    }
+
+    #[test]
+    fn test_username_and_host_matches_score() {
+        let by_username = relevance_score(&item(1, "Mail", Some("alice"), None), "alice");
+        let by_host = relevance_score(
+            &item(2, "Work", None, Some("https://www.example.com/login")),
+            "example",
+        );
+        let unrelated = relevance_score(&item(3, "Bank", Some("bob"), None), "alice");
+        assert!(by_username >= USERNAME_EXACT_WEIGHT);
+        assert!(by_host >= HOST_MATCH_WEIGHT);
+        assert_eq!(unrelated, 0.0);
+        assert_eq!(relevance_score(&item(4, "Bank", None, None), "   "), 0.0);
+    }
+
+    #[test]
+    fn test_host_score() {
+        assert_eq!(host_score("example.com", "example.com"), HOST_MATCH_WEIGHT);
+        assert_eq!(host_score("mail.example.com", "example"), HOST_MATCH_WEIGHT);
+        assert_eq!(host_score("examples.com", "example"), HOST_PARTIAL_WEIGHT);
+        assert_eq!(host_score("example.com", "other"), 0.0);
+        assert_eq!(
+            url_host("WWW.Example.com:8443/x").as_deref(),
+            Some("example.com")
+        );
+    }
+
+    #[test]
+    fn test_rank_by_relevance_is_stable() {
+        let mut items = vec![
+            item(1, "Other", None, None),
+            item(2, "Bank of Examples", None, None),
+            item(3, "Other too", None, None),
+            item(4, "Example", None, None),
+        ];
+        rank_by_relevance(&mut items, "example");
+        let order: Vec<i64> = items.iter().map(|i| i.id).collect();
+        assert_eq!(order, [4, 2, 1, 3]);
+        assert!(items.iter().all(|i| i.score.is_some()));
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("github", "gihtub"), 2);
+        assert_eq!(levenshtein("naïve", "naive"), 1);
+        assert_eq!(levenshtein("abc", ""), 3);
+    }
+
+    #[test]
+    fn test_matches_with_typos() {
+        let github = item(1, "GitHub", Some("alice@example.com"), Some("github.com"));
+        assert!(matches_with_typos(&github, "githib", 1));
+        assert!(matches_with_typos(&github, "gthub alcie", 2));
+        assert!(!matches_with_typos(&github, "githib", 0));
+        assert!(!matches_with_typos(&github, "gitlab", 1));
+        // Every word has to match.
+        assert!(!matches_with_typos(&github, "github bank", 2));
+        assert!(!matches_with_typos(&github, "  ", 2));
+    }
+
+    #[test]
+    fn test_typos_match_word_prefixes() {
+        let item = item(1, "Microsoft Outlook", None, None);
+        assert!(matches_with_typos(&item, "micrs", 1));
+        assert!(matches_with_typos(&item, "outlok", 1));
+    }
+
+    #[test]
+    fn test_short_words_get_a_smaller_typo_budget() {
+        let item = item(1, "AWS console", None, None);
+        // One edit on a two-letter word would match almost anything.
+        assert!(!matches_with_typos(&item, "ab", 2));
+        assert!(matches_with_typos(&item, "aws", 2));
+        assert!(matches_with_typos(&item, "awz", 2));
+        assert!(!matches_with_typos(&item, "azz", 2));
+        assert!(matches_with_typos(&item, "consloe", 2));
+    }
+}
@@ -1,6 +1,7 @@
-use crate::db::utils::{get_db_pool, get_key};
+use crate::db::utils::UnlockedVault;
 use crate::encryption::{decrypt, decrypt_zeroized, encrypt};
 use crate::error::Result;
+use crate::limits;
 use crate::state::AppState;
 use crate::types::{RecipientKey, SecretString};
 use sqlx::{Row, SqlitePool};
@@ -13,17 +14,23 @@ pub async fn save_recipient_key(
     public_key: String,
     private_key: SecretString,
 ) -> Result<()> {
-    let key = get_key(&state).await?;
-    let name_enc = encrypt(&name, key.as_slice())?;
-    let public_key_enc = encrypt(&public_key, key.as_slice())?;
-    let private_key_enc = encrypt(private_key.as_str(), key.as_slice())?;
+    limits::check_len("Key name", &name, limits::MAX_TITLE_LEN)?;
+    limits::check_len("Public key", &public_key, limits::MAX_KEY_MATERIAL_LEN)?;
+    limits::check_len(
+        "Private key",
+        private_key.as_str(),
+        limits::MAX_KEY_MATERIAL_LEN,
+    )?;
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let name_enc = encrypt(&name, vault.key())?;
+    let public_key_enc = encrypt(&public_key, vault.key())?;
+    let private_key_enc = encrypt(private_key.as_str(), vault.key())?;
 
-    let db_pool = get_db_pool(&state).await?;
     sqlx::query("INSERT INTO recipient_keys (name, public_key, private_key) VALUES (?, ?, ?)")
         .bind(name_enc)
         .bind(public_key_enc)
         .bind(private_key_enc)
-        .execute(&db_pool)
+        .execute(vault.pool())
         .await?;
     Ok(())
 }
@@ -32,7 +39,7 @@ pub async fn get_recipient_keys_impl(
     db_pool: &SqlitePool,
     key: &[u8],
 ) -> Result<Vec<RecipientKey>> {
-    let rows = sqlx::query("SELECT id, name, public_key, private_key FROM recipient_keys")
+    let rows = sqlx::query("SELECT id, name, public_key, private_key FROM recipient_keys WHERE private_key IS NOT NULL")
         .fetch_all(db_pool)
         .await?;
 
@@ -57,18 +64,16 @@ pub async fn get_recipient_keys_impl(
 
 #[tauri::command]
 pub async fn get_recipient_keys(state: State<'_, AppState>) -> Result<Vec<RecipientKey>> {
-    let key = get_key(&state).await?;
-    let db_pool = get_db_pool(&state).await?;
-    get_recipient_keys_impl(&db_pool, key.as_slice()).await
+    let vault = UnlockedVault::acquire(&state).await?;
+    get_recipient_keys_impl(vault.pool(), vault.key()).await
 }
 
 #[tauri::command]
 pub async fn delete_recipient_key(state: State<'_, AppState>, id: i64) -> Result<()> {
-    get_key(&state).await?;
-    let db_pool = get_db_pool(&state).await?;
-    sqlx::query("DELETE FROM recipient_keys WHERE id = ?")
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    sqlx::query("DELETE FROM recipient_keys WHERE id = ? AND private_key IS NOT NULL")
         .bind(id)
-        .execute(&db_pool)
+        .execute(vault.pool())
         .await?;
     Ok(())
 }
@@ -0,0 +1,249 @@
+//! Optional malware scan of decrypted attachments. The user configures a
+//! scanner command (e.g. `clamscan --no-summary {file}`); attachments are
+//! written to a private temp directory, scanned there and removed again.
+//! Attachments being saved are scanned where they are written instead, before
+//! they are moved into place.
+
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tauri::Manager;
+use tokio::fs;
+
+const SCAN_TIMEOUT: Duration = Duration::from_secs(120);
+const FILE_PLACEHOLDER: &str = "{file}";
+
+#[derive(Deserialize, Default)]
+struct ScannerSettings {
+    #[serde(default)]
+    security: ScannerSecuritySettings,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct ScannerSecuritySettings {
+    #[serde(default)]
+    attachment_scanner: String,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanVerdict {
+    Clean,
+    Infected,
+    Failed,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanResult {
+    pub verdict: ScanVerdict,
+    pub output: String,
+}
+
+async fn configured_scanner(app: &tauri::AppHandle) -> Option<String> {
+    crate::settings::get_all_settings_internal(app)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str::<ScannerSettings>(&json).ok())
+        .map(|s| s.security.attachment_scanner.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// Splits a command line on whitespace, honouring double quotes.
+fn split_command(command: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_token = false;
+
+    for c in command.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_token = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_token {
+                    args.push(std::mem::take(&mut current));
+                    has_token = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_token = true;
+            }
+        }
+    }
+    if has_token {
+        args.push(current);
+    }
+    args
+}
+
+async fn private_scan_dir(app: &tauri::AppHandle) -> Result<std::path::PathBuf> {
+    let dir = app
+        .path()
+        .app_local_data_dir()
+        .map_err(|e| Error::Internal(format!("Failed to resolve app data dir: {e}")))?
+        .join("scan")
+        .join(hex::encode(rand::random::<[u8; 16]>()));
+    fs::create_dir_all(&dir).await?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700)).await?;
+    }
+    Ok(dir)
+}
+
+async fn run_scanner(command: &str, file: &Path) -> Result<ScanResult> {
+    let mut args = split_command(command);
+    if args.is_empty() {
+        return Err(Error::Validation(
+            "The scanner command is empty".to_string(),
+        ));
+    }
+    let file_arg = file.to_string_lossy().to_string();
+    if args.iter().any(|a| a.contains(FILE_PLACEHOLDER)) {
+        for arg in args.iter_mut() {
+            *arg = arg.replace(FILE_PLACEHOLDER, &file_arg);
+        }
+    } else {
+        args.push(file_arg);
+    }
+
+    let program = args.remove(0);
+    let mut cmd = tokio::process::Command::new(&program);
+    cmd.args(&args)
+        .current_dir(file.parent().unwrap_or_else(|| Path::new(".")))
+        .env_clear()
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    // The scanner only gets what it needs to locate its own binaries and
+    // signature databases.
+    for var in ["PATH", "SYSTEMROOT", "HOME", "TMPDIR", "TEMP", "TMP"] {
+        if let Some(value) = std::env::var_os(var) {
+            cmd.env(var, value);
+        }
+    }
+
+    let child = cmd
+        .spawn()
+        .map_err(|e| Error::Internal(format!("Failed to start scanner '{}': {}", program, e)))?;
+    let output = tokio::time::timeout(SCAN_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| Error::Internal("The attachment scan timed out".to_string()))??;
+
+    let mut text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !stderr.trim().is_empty() {
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text.push_str(stderr.trim());
+    }
+    // Keep the activity log entry small; the path is a throwaway temp file.
+    let text = text.replace(file.to_string_lossy().as_ref(), "<attachment>");
+    let text: String = text.chars().take(1024).collect();
+
+    // clamscan and most command line scanners: 0 clean, 1 infected.
+    let verdict = match output.status.code() {
+        Some(0) => ScanVerdict::Clean,
+        Some(1) => ScanVerdict::Infected,
+        _ => ScanVerdict::Failed,
+    };
+    Ok(ScanResult {
+        verdict,
+        output: text,
+    })
+}
+
+/// Scans `data` with the configured scanner. Returns `None` when scanning is
+/// disabled. The outcome is recorded in the activity log.
+pub async fn scan_attachment_bytes(
+    app: &tauri::AppHandle,
+    pool: &SqlitePool,
+    key: &[u8],
+    attachment_id: i64,
+    file_name: &str,
+    data: &[u8],
+) -> Result<Option<ScanResult>> {
+    let Some(command) = configured_scanner(app).await else {
+        return Ok(None);
+    };
+
+    let dir = private_scan_dir(app).await?;
+    let extension = Path::new(file_name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| format!(".{}", e))
+        .unwrap_or_default();
+    let file = dir.join(format!("attachment-{}{}", attachment_id, extension));
+
+    let result = async {
+        crate::utils::write_sensitive_bytes(&file, data).await?;
+        run_scanner(&command, &file).await
+    }
+    .await;
+    let _ = fs::remove_dir_all(&dir).await;
+
+    Ok(Some(record_scan(pool, key, file_name, result).await))
+}
+
+/// Scans `file`, which holds the decrypted attachment, in place. Returns
+/// `None` when scanning is disabled. The outcome is recorded in the activity
+/// log.
+pub async fn scan_attachment_file(
+    app: &tauri::AppHandle,
+    pool: &SqlitePool,
+    key: &[u8],
+    file_name: &str,
+    file: &Path,
+) -> Result<Option<ScanResult>> {
+    let Some(command) = configured_scanner(app).await else {
+        return Ok(None);
+    };
+    let result = run_scanner(&command, file).await;
+    Ok(Some(record_scan(pool, key, file_name, result).await))
+}
+
+async fn record_scan(
+    pool: &SqlitePool,
+    key: &[u8],
+    file_name: &str,
+    result: Result<ScanResult>,
+) -> ScanResult {
+    let result = result.unwrap_or_else(|e| ScanResult {
+        verdict: ScanVerdict::Failed,
+        output: e.to_string(),
+    });
+
+    let details = match result.verdict {
+        ScanVerdict::Clean => format!("Scan of '{}' found no threats", file_name),
+        ScanVerdict::Infected => format!(
+            "Scan of '{}' flagged a threat: {}",
+            file_name, result.output
+        ),
+        ScanVerdict::Failed => format!("Scan of '{}' failed: {}", file_name, result.output),
+    };
+    if !crate::storage::is_read_only() {
+        let _ = crate::db::activity::log_activity_impl(
+            pool,
+            key,
+            "attachment_scanned",
+            None,
+            None,
+            Some(&details),
+        )
+        .await;
+    }
+
+    result
+}
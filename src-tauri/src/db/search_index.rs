@@ -0,0 +1,171 @@
+//! Opt-in indexing of notes and non-secret custom field values. The extra
+//! text goes through the same keyed trigram scheme as titles, but it is off
+//! by default because it indexes far more of each item.
+
+use crate::db::utils::{CryptoHelper, UnlockedVault};
+use crate::error::Result;
+use crate::state::AppState;
+use crate::types::{CustomField, PasswordItem};
+use sqlx::Row;
+use tauri::State;
+use zeroize::Zeroizing;
+
+const SETTING_KEY: &str = "search_index_extended";
+/// Custom field types whose values are never indexed.
+const SECRET_FIELD_TYPES: &[&str] = &["password", "hidden", "totp", "pin", "secret", "file"];
+/// Long notes are only indexed up to this many characters.
+const MAX_EXTENDED_INDEX_CHARS: usize = 8 * 1024;
+const BACKFILL_BATCH_SIZE: i64 = 200;
+
+pub async fn is_extended_search_enabled<'a, E>(executor: E) -> Result<bool>
+where
+    E: sqlx::SqliteExecutor<'a>,
+{
+    let value: Option<String> = sqlx::query_scalar("SELECT value FROM configuration WHERE key = ?")
+        .bind(SETTING_KEY)
+        .fetch_optional(executor)
+        .await?;
+    Ok(value.as_deref() == Some("true"))
+}
+
+fn extended_search_text(notes: Option<&str>, custom_fields: &[CustomField]) -> Zeroizing<String> {
+    let mut text = Zeroizing::new(String::new());
+    if let Some(notes) = notes {
+        text.push_str(notes);
+    }
+    for field in custom_fields {
+        if SECRET_FIELD_TYPES.contains(&field.field_type.as_str()) || field.value.is_empty() {
+            continue;
+        }
+        text.push(' ');
+        text.push_str(&field.value);
+    }
+    if let Some((cut, _)) = text.char_indices().nth(MAX_EXTENDED_INDEX_CHARS) {
+        text.truncate(cut);
+    }
+    text
+}
+
+/// Extra searchable text for `item`, or `None` when the vault doesn't index
+/// notes and custom fields.
+pub async fn item_extended_text(
+    conn: &mut sqlx::SqliteConnection,
+    item: &PasswordItem,
+) -> Result<Option<Zeroizing<String>>> {
+    if !is_extended_search_enabled(&mut *conn).await? {
+        return Ok(None);
+    }
+    Ok(Some(extended_search_text(
+        item.notes.as_ref().map(|n| n.as_str()),
+        &item.custom_fields,
+    )))
+}
+
+/// Like `item_extended_text`, reading notes and custom fields from the
+/// stored item. Used when only some columns of an item change.
+pub async fn stored_extended_text(
+    conn: &mut sqlx::SqliteConnection,
+    helper: &CryptoHelper,
+    item_id: i64,
+) -> Result<Option<Zeroizing<String>>> {
+    if !is_extended_search_enabled(&mut *conn).await? {
+        return Ok(None);
+    }
+    let row = sqlx::query("SELECT notes, custom_fields FROM password_items WHERE id = ?")
+        .bind(item_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    let Some(row) = row else {
+        return Ok(None);
+    };
+    Ok(Some(decrypt_extended_text(&row, helper)?))
+}
+
+fn decrypt_extended_text(
+    row: &sqlx::sqlite::SqliteRow,
+    helper: &CryptoHelper,
+) -> Result<Zeroizing<String>> {
+    let notes = helper.decrypt_secret_opt(row.get("notes"))?;
+    let custom_fields: Vec<CustomField> = helper
+        .decrypt_secret_opt(row.get("custom_fields"))?
+        .and_then(|cf| serde_json::from_str(cf.as_str()).ok())
+        .unwrap_or_default();
+    Ok(extended_search_text(
+        notes.as_ref().map(|n| n.as_str()),
+        &custom_fields,
+    ))
+}
+
+#[tauri::command]
+pub async fn get_extended_search_index(state: State<'_, AppState>) -> Result<bool> {
+    let vault = UnlockedVault::acquire(&state).await?;
+    is_extended_search_enabled(vault.pool()).await
+}
+
+/// Turns indexing of notes and custom fields on or off for this vault. Only
+/// items saved afterwards are affected; run `backfill_search_index` to
+/// re-index the rest.
+#[tauri::command]
+pub async fn set_extended_search_index(state: State<'_, AppState>, enabled: bool) -> Result<()> {
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    sqlx::query("INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)")
+        .bind(SETTING_KEY)
+        .bind(if enabled { "true" } else { "false" })
+        .execute(vault.pool())
+        .await?;
+    Ok(())
+}
+
+/// Rebuilds the search index of every item under the current setting, so
+/// existing items become searchable by notes and custom fields (or stop
+/// being, after the setting is turned off). Returns the number of items.
+#[tauri::command]
+pub async fn backfill_search_index(state: State<'_, AppState>) -> Result<usize> {
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
+    let extended = is_extended_search_enabled(vault.pool()).await?;
+
+    let mut last_id = 0i64;
+    let mut indexed = 0usize;
+    loop {
+        let rows = sqlx::query(
+            "SELECT id, title, username, tags, notes, custom_fields FROM password_items WHERE id > ? ORDER BY id LIMIT ?",
+        )
+        .bind(last_id)
+        .bind(BACKFILL_BATCH_SIZE)
+        .fetch_all(vault.pool())
+        .await?;
+        let Some(last) = rows.last() else {
+            break;
+        };
+        last_id = last.get("id");
+
+        let mut tx = vault.pool().begin().await?;
+        for row in &rows {
+            let title_enc: String = row.get("title");
+            let title = helper.decrypt(&title_enc)?;
+            let username = helper.decrypt_opt(row.get("username"))?;
+            let tags = helper.decrypt_opt(row.get("tags"))?;
+            let extra = if extended {
+                Some(decrypt_extended_text(row, &helper)?)
+            } else {
+                None
+            };
+
+            crate::db::passwords::sync_search_indices(
+                &mut tx,
+                row.get("id"),
+                &helper,
+                &title,
+                username.as_ref(),
+                tags.as_ref(),
+                extra.as_deref().map(String::as_str),
+            )
+            .await?;
+            indexed += 1;
+        }
+        tx.commit().await?;
+    }
+
+    Ok(indexed)
+}
@@ -0,0 +1,129 @@
+//! Namespaced key-value storage for frontend state that references vault
+//! contents (window layouts, draft edits) and so must not live in the plain
+//! settings store. Namespaces and keys are stored as keyed hashes, values and
+//! key names encrypted.
+
+use crate::db::utils::{CryptoHelper, UnlockedVault};
+use crate::error::{Error, Result};
+use crate::limits;
+use crate::state::AppState;
+use chrono::Utc;
+use tauri::State;
+use zeroize::Zeroize;
+
+fn derive_kv_hash_key(master_key: &[u8]) -> Result<[u8; 32]> {
+    use hkdf::Hkdf;
+    use sha2::Sha256;
+
+    let hk = Hkdf::<Sha256>::new(None, master_key);
+    let mut out = [0u8; 32];
+    hk.expand(b"pulsar:secure-kv", &mut out)
+        .map_err(|_| Error::Internal("Failed to derive key-value hash key".to_string()))?;
+    Ok(out)
+}
+
+fn kv_hash(master_key: &[u8], domain: &str, value: &str) -> Result<String> {
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    let mut hash_key = derive_kv_hash_key(master_key)?;
+    let mut mac =
+        <Hmac<Sha256> as Mac>::new_from_slice(&hash_key).expect("HMAC can take key of any size");
+    hash_key.zeroize();
+    mac.update(domain.as_bytes());
+    mac.update(&[0]);
+    mac.update(value.as_bytes());
+    Ok(hex::encode(mac.finalize().into_bytes()))
+}
+
+fn check_names(ns: &str, key: Option<&str>) -> Result<()> {
+    limits::check_len("Namespace", ns, limits::MAX_TITLE_LEN)?;
+    if ns.trim().is_empty() {
+        return Err(Error::Validation("Namespace is required".to_string()));
+    }
+    if let Some(key) = key {
+        limits::check_len("Key", key, limits::MAX_TITLE_LEN)?;
+        if key.is_empty() {
+            return Err(Error::Validation("Key is required".to_string()));
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn secure_kv_set(
+    state: State<'_, AppState>,
+    ns: String,
+    key: String,
+    value: String,
+) -> Result<()> {
+    check_names(&ns, Some(&key))?;
+    limits::check_len("Value", &value, limits::MAX_SETTINGS_LEN)?;
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
+
+    sqlx::query(
+        "INSERT INTO secure_kv (namespace_hash, key_hash, key_name, value, updated_at) VALUES (?, ?, ?, ?, ?)
+         ON CONFLICT(namespace_hash, key_hash) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+    )
+    .bind(kv_hash(vault.key(), "ns", &ns)?)
+    .bind(kv_hash(vault.key(), &ns, &key)?)
+    .bind(helper.encrypt(&key)?)
+    .bind(helper.encrypt(&value)?)
+    .bind(Utc::now().to_rfc3339())
+    .execute(vault.pool())
+    .await?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn secure_kv_get(
+    state: State<'_, AppState>,
+    ns: String,
+    key: String,
+) -> Result<Option<String>> {
+    check_names(&ns, Some(&key))?;
+    let vault = UnlockedVault::acquire(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
+
+    let value: Option<String> =
+        sqlx::query_scalar("SELECT value FROM secure_kv WHERE namespace_hash = ? AND key_hash = ?")
+            .bind(kv_hash(vault.key(), "ns", &ns)?)
+            .bind(kv_hash(vault.key(), &ns, &key)?)
+            .fetch_optional(vault.pool())
+            .await?;
+
+    value.map(|v| helper.decrypt(&v)).transpose()
+}
+
+#[tauri::command]
+pub async fn secure_kv_delete(state: State<'_, AppState>, ns: String, key: String) -> Result<()> {
+    check_names(&ns, Some(&key))?;
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+
+    sqlx::query("DELETE FROM secure_kv WHERE namespace_hash = ? AND key_hash = ?")
+        .bind(kv_hash(vault.key(), "ns", &ns)?)
+        .bind(kv_hash(vault.key(), &ns, &key)?)
+        .execute(vault.pool())
+        .await?;
+
+    Ok(())
+}
+
+/// Lists the keys stored in `ns`.
+#[tauri::command]
+pub async fn secure_kv_keys(state: State<'_, AppState>, ns: String) -> Result<Vec<String>> {
+    check_names(&ns, None)?;
+    let vault = UnlockedVault::acquire(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
+
+    let names: Vec<String> = sqlx::query_scalar(
+        "SELECT key_name FROM secure_kv WHERE namespace_hash = ? ORDER BY updated_at",
+    )
+    .bind(kv_hash(vault.key(), "ns", &ns)?)
+    .fetch_all(vault.pool())
+    .await?;
+
+    names.iter().map(|n| helper.decrypt(n)).collect()
+}
@@ -0,0 +1,230 @@
+use crate::db::utils::{CryptoHelper, UnlockedVault};
+use crate::db::validation::validate_item;
+use crate::db::vault_settings::load_password_policy;
+use crate::error::{Error, Result};
+use crate::limits;
+use crate::state::AppState;
+use crate::types::{CustomField, ItemTemplate, PasswordItem, SecretString};
+use chrono::Utc;
+use sqlx::Row;
+use tauri::State;
+
+fn check_template(
+    name: &str,
+    category: &str,
+    custom_fields: &[CustomField],
+    field_order: Option<&[String]>,
+) -> Result<()> {
+    limits::check_len("Template name", name, limits::MAX_TITLE_LEN)?;
+    limits::check_len("Category", category, limits::MAX_SHORT_FIELD_LEN)?;
+    limits::check_custom_fields(custom_fields, field_order)?;
+    if name.trim().is_empty() {
+        return Err(Error::Validation("Template name is required".to_string()));
+    }
+    if category.trim().is_empty() {
+        return Err(Error::Validation(
+            "Template category is required".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+pub async fn get_templates_impl<'a, E>(executor: E, key: &[u8]) -> Result<Vec<ItemTemplate>>
+where
+    E: sqlx::SqliteExecutor<'a>,
+{
+    let rows = sqlx::query(
+        "SELECT id, name, category, custom_fields, field_order, created_at, updated_at FROM templates ORDER BY id",
+    )
+    .fetch_all(executor)
+    .await?;
+
+    let helper = CryptoHelper::new(key)?;
+    let mut templates = Vec::with_capacity(rows.len());
+    for row in rows {
+        templates.push(decrypt_template_row(&row, &helper)?);
+    }
+    Ok(templates)
+}
+
+fn decrypt_template_row(
+    row: &sqlx::sqlite::SqliteRow,
+    helper: &CryptoHelper,
+) -> Result<ItemTemplate> {
+    let name_enc: String = row.get("name");
+    let category_enc: String = row.get("category");
+    let custom_fields_enc: String = row.get("custom_fields");
+    let field_order_enc: Option<String> = row.get("field_order");
+
+    Ok(ItemTemplate {
+        id: row.get("id"),
+        name: helper.decrypt(&name_enc)?,
+        category: helper.decrypt(&category_enc)?,
+        custom_fields: serde_json::from_str(&helper.decrypt(&custom_fields_enc)?)
+            .unwrap_or_default(),
+        field_order: field_order_enc
+            .and_then(|fo| helper.decrypt(&fo).ok())
+            .and_then(|fo| serde_json::from_str(&fo).ok()),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    })
+}
+
+#[tauri::command]
+pub async fn get_item_templates(state: State<'_, AppState>) -> Result<Vec<ItemTemplate>> {
+    let vault = UnlockedVault::acquire(&state).await?;
+    get_templates_impl(vault.pool(), vault.key()).await
+}
+
+#[tauri::command]
+pub async fn save_item_template(
+    state: State<'_, AppState>,
+    name: String,
+    category: String,
+    custom_fields: Vec<CustomField>,
+    field_order: Option<Vec<String>>,
+) -> Result<i64> {
+    check_template(&name, &category, &custom_fields, field_order.as_deref())?;
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
+    let now = Utc::now().to_rfc3339();
+
+    let field_order_enc = field_order
+        .map(|fo| serde_json::to_string(&fo))
+        .transpose()?
+        .map(|fo| helper.encrypt(&fo))
+        .transpose()?;
+
+    let id = sqlx::query(
+        "INSERT INTO templates (name, category, custom_fields, field_order, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(helper.encrypt(name.trim())?)
+    .bind(helper.encrypt(category.trim())?)
+    .bind(helper.encrypt(&serde_json::to_string(&custom_fields)?)?)
+    .bind(field_order_enc)
+    .bind(&now)
+    .bind(&now)
+    .execute(vault.pool())
+    .await?
+    .last_insert_rowid();
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn update_item_template(
+    state: State<'_, AppState>,
+    id: i64,
+    name: String,
+    category: String,
+    custom_fields: Vec<CustomField>,
+    field_order: Option<Vec<String>>,
+) -> Result<()> {
+    check_template(&name, &category, &custom_fields, field_order.as_deref())?;
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
+
+    let field_order_enc = field_order
+        .map(|fo| serde_json::to_string(&fo))
+        .transpose()?
+        .map(|fo| helper.encrypt(&fo))
+        .transpose()?;
+
+    let updated = sqlx::query(
+        "UPDATE templates SET name = ?, category = ?, custom_fields = ?, field_order = ?, updated_at = ? WHERE id = ?",
+    )
+    .bind(helper.encrypt(name.trim())?)
+    .bind(helper.encrypt(category.trim())?)
+    .bind(helper.encrypt(&serde_json::to_string(&custom_fields)?)?)
+    .bind(field_order_enc)
+    .bind(Utc::now().to_rfc3339())
+    .bind(id)
+    .execute(vault.pool())
+    .await?
+    .rows_affected();
+
+    if updated == 0 {
+        return Err(Error::Validation(format!("Template {} not found", id)));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delete_item_template(state: State<'_, AppState>, id: i64) -> Result<()> {
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    sqlx::query("DELETE FROM templates WHERE id = ?")
+        .bind(id)
+        .execute(vault.pool())
+        .await?;
+    Ok(())
+}
+
+/// Creates an item with the template's category, custom fields and field
+/// order. The title defaults to the template name.
+#[tauri::command]
+pub async fn create_item_from_template(
+    state: State<'_, AppState>,
+    template_id: i64,
+    title: Option<String>,
+) -> Result<i64> {
+    limits::check_opt_len("Title", title.as_deref(), limits::MAX_TITLE_LEN)?;
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
+
+    let row = sqlx::query(
+        "SELECT id, name, category, custom_fields, field_order, created_at, updated_at FROM templates WHERE id = ?",
+    )
+    .bind(template_id)
+    .fetch_optional(vault.pool())
+    .await?
+    .ok_or_else(|| Error::Validation(format!("Template {} not found", template_id)))?;
+    let template = decrypt_template_row(&row, &helper)?;
+
+    let title = title
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .unwrap_or_else(|| template.name.clone());
+    let item = PasswordItem {
+        id: 0,
+        category: template.category,
+        title,
+        description: None,
+        img: None,
+        tags: None,
+        username: None,
+        url: None,
+        notes: None,
+        password: SecretString::default(),
+        created_at: String::new(),
+        updated_at: String::new(),
+        color: None,
+        totp_secret: None,
+        custom_fields: template.custom_fields,
+        field_order: template.field_order,
+        attachments: None,
+        autotype_patterns: None,
+        details: None,
+        clear_details: false,
+        read_only: false,
+    };
+    limits::check_password_item(&item)?;
+    let policy = load_password_policy(vault.pool(), vault.key()).await?;
+    validate_item(&item, &policy)?;
+
+    let now = Utc::now().to_rfc3339();
+    let mut tx = vault.pool().begin().await?;
+    let item_id =
+        crate::db::insert_password_item(&mut tx, &helper, vault.key(), &item, &now).await?;
+
+    let _ = crate::db::activity::log_activity_impl(
+        tx.as_mut(),
+        vault.key(),
+        "item_created",
+        Some(item_id),
+        Some(&item.title),
+        Some(&format!("Created from template '{}'", template.name)),
+    )
+    .await;
+
+    tx.commit().await?;
+    Ok(item_id)
+}
@@ -1,21 +1,53 @@
 use crate::encryption::CipherSession;
 use crate::error::{Error, Result};
+use crate::memlock::LockedKey;
 use crate::state::AppState;
 use crate::types::SecretString;
 use sqlx::SqlitePool;
-use tauri::State;
-use zeroize::Zeroizing;
-
-pub async fn get_key(state: &State<'_, AppState>) -> Result<Zeroizing<Vec<u8>>> {
-    let guard = state.key.lock().await;
-    let opt = guard.clone();
-    drop(guard);
-    opt.ok_or(Error::VaultLocked)
+use std::sync::Arc;
+
+/// Pool and key of an unlocked vault, read together so both belong to the
+/// same unlock session. Only `acquire`/`acquire_writable` create one, so code
+/// taking an `UnlockedVault` cannot run against a locked vault, and cannot
+/// pair a pool with a key from before a lock or rekey.
+pub struct UnlockedVault {
+    pool: SqlitePool,
+    key: Arc<LockedKey>,
 }
 
-pub async fn get_db_pool(state: &State<'_, AppState>) -> Result<SqlitePool> {
-    let guard = state.db.lock().await;
-    guard.clone().ok_or(Error::VaultNotLoaded)
+impl UnlockedVault {
+    pub async fn acquire(state: &AppState) -> Result<Self> {
+        let vault = {
+            let db_guard = state.db.lock().await;
+            let key_guard = state.key.lock().await;
+            let key = key_guard.clone().ok_or(Error::VaultLocked)?;
+            let pool = db_guard.clone().ok_or(Error::VaultNotLoaded)?;
+            Self { pool, key }
+        };
+        crate::sessions::ensure_active(state, &vault.pool).await?;
+        Ok(vault)
+    }
+
+    /// Like `acquire`, for commands that modify the vault. Fails with
+    /// `ReadOnlyStorage` up front instead of partway through a write.
+    pub async fn acquire_writable(state: &AppState) -> Result<Self> {
+        crate::storage::ensure_writable()?;
+        crate::vault_watch::ensure_current()?;
+        crate::write_buffer::mark_dirty(state).await;
+        Self::acquire(state).await
+    }
+
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+
+    pub fn key(&self) -> &[u8] {
+        self.key.as_slice()
+    }
+
+    pub fn helper(&self) -> Result<CryptoHelper> {
+        CryptoHelper::new(self.key())
+    }
 }
 
 pub struct CryptoHelper {
@@ -1,7 +1,90 @@
-use crate::types::PasswordItem;
+use crate::db::vault_settings::PasswordPolicy;
+use crate::error::{Error, Result};
+use crate::types::{
+    BankAccountDetails, CardDetails, IdentityDetails, ItemDetails, PasswordItem, WifiDetails,
+    WifiSecurity,
+};
+use serde::{Deserialize, Serialize};
+use validator::{ValidateArgs, ValidationError, ValidationErrorsKind};
+
+const POLICY_ERROR_CODE: &str = "password_policy";
+
+/// One way a password falls short of the vault's password policy.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum PolicyViolation {
+    #[serde(rename_all = "camelCase")]
+    TooShort {
+        min_length: u32,
+    },
+    MissingUppercase,
+    MissingLowercase,
+    MissingDigit,
+    MissingSymbol,
+    BannedWord {
+        word: String,
+    },
+    CommonPassword,
+}
+
+/// Every rule of `policy` that `password` breaks, in the order the policy
+/// lists them. Length is counted in characters.
+pub fn policy_violations(policy: &PasswordPolicy, password: &str) -> Vec<PolicyViolation> {
+    let mut violations = Vec::new();
+    if (password.chars().count() as u64) < u64::from(policy.min_length) {
+        violations.push(PolicyViolation::TooShort {
+            min_length: policy.min_length,
+        });
+    }
+    if policy.require_uppercase && !password.chars().any(char::is_uppercase) {
+        violations.push(PolicyViolation::MissingUppercase);
+    }
+    if policy.require_lowercase && !password.chars().any(char::is_lowercase) {
+        violations.push(PolicyViolation::MissingLowercase);
+    }
+    if policy.require_digits && !password.chars().any(|c| c.is_ascii_digit()) {
+        violations.push(PolicyViolation::MissingDigit);
+    }
+    if policy.require_symbols && !password.chars().any(|c| !c.is_alphanumeric()) {
+        violations.push(PolicyViolation::MissingSymbol);
+    }
+    let lowered = password.to_lowercase();
+    for word in &policy.banned_words {
+        let banned = word.trim().to_lowercase();
+        if !banned.is_empty() && lowered.contains(&banned) {
+            violations.push(PolicyViolation::BannedWord { word: word.clone() });
+        }
+    }
+    if policy.block_common_passwords && crate::common_passwords::is_common(password) {
+        violations.push(PolicyViolation::CommonPassword);
+    }
+    violations
+}
+
+/// Validates `item` against the field rules and the vault's password
+/// policy. Policy violations come back as `Error::PasswordPolicy` so the
+/// editor can point at each one.
+pub fn validate_item(item: &PasswordItem, policy: &PasswordPolicy) -> Result<()> {
+    let Err(errors) = item.validate_with_args(policy) else {
+        return Ok(());
+    };
+    let violations = errors.errors().get("__all__").and_then(|kind| match kind {
+        ValidationErrorsKind::Field(errors) => errors
+            .iter()
+            .find(|e| e.code == POLICY_ERROR_CODE)
+            .and_then(|e| e.params.get("violations"))
+            .and_then(|v| serde_json::from_value(v.clone()).ok()),
+        _ => None,
+    });
+    match violations {
+        Some(violations) => Err(Error::PasswordPolicy { violations }),
+        None => Err(Error::Validation(errors.to_string())),
+    }
+}
 
 pub fn validate_password_item_fields(
     item: &PasswordItem,
+    policy: &PasswordPolicy,
 ) -> std::result::Result<(), validator::ValidationError> {
     if item.title.is_empty() {
         return Err(validator::ValidationError::new("title_empty"));
@@ -21,6 +104,14 @@ pub fn validate_password_item_fields(
     if !is_placeholder_password && item.password.len() < 8 {
         return Err(validator::ValidationError::new("password_too_short"));
     }
+    if !is_placeholder_password {
+        let violations = policy_violations(policy, item.password.as_str());
+        if !violations.is_empty() {
+            let mut error = ValidationError::new(POLICY_ERROR_CODE);
+            error.add_param("violations".into(), &violations);
+            return Err(error);
+        }
+    }
 
     if let Some(url) = &item.url {
         if !url.is_empty() && !url.starts_with("http://") && !url.starts_with("https://") {
@@ -34,5 +125,585 @@ pub fn validate_password_item_fields(
         }
     }
 
+    if let Some(details) = &item.details {
+        validate_item_details(&item.category, details)?;
+
+        if let ItemDetails::Wifi(wifi) = details {
+            validate_wifi_password(wifi.security, item.password.as_str())?;
+        }
+    }
+
     Ok(())
 }
+
+pub fn validate_item_details(
+    category: &str,
+    details: &ItemDetails,
+) -> std::result::Result<(), ValidationError> {
+    if details.category() != category {
+        return Err(ValidationError::new("details_category_mismatch"));
+    }
+
+    match details {
+        ItemDetails::Card(card) => validate_card_details(card),
+        ItemDetails::Identity(identity) => validate_identity_details(identity),
+        ItemDetails::Wifi(wifi) => validate_wifi_details(wifi),
+        ItemDetails::BankAccount(account) => validate_bank_account_details(account),
+    }
+}
+
+fn is_digits(value: &str) -> bool {
+    !value.is_empty() && value.chars().all(|c| c.is_ascii_digit())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CardBrand {
+    Visa,
+    Mastercard,
+    Amex,
+    Discover,
+    DinersClub,
+    Jcb,
+    UnionPay,
+    Maestro,
+}
+
+impl CardBrand {
+    /// The lengths card numbers of this brand are issued in.
+    fn lengths(self) -> std::ops::RangeInclusive<usize> {
+        match self {
+            CardBrand::Visa => 13..=19,
+            CardBrand::Mastercard => 16..=16,
+            CardBrand::Amex => 15..=15,
+            CardBrand::Discover | CardBrand::Jcb | CardBrand::UnionPay => 16..=19,
+            CardBrand::DinersClub => 14..=19,
+            CardBrand::Maestro => 12..=19,
+        }
+    }
+}
+
+/// Whether `number` passes the Luhn checksum every payment card carries.
+pub fn luhn_valid(number: &str) -> bool {
+    if !is_digits(number) {
+        return false;
+    }
+    let sum: u32 = number
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(i, b)| {
+            let digit = u32::from(b - b'0');
+            if i % 2 == 1 {
+                let doubled = digit * 2;
+                if doubled > 9 {
+                    doubled - 9
+                } else {
+                    doubled
+                }
+            } else {
+                digit
+            }
+        })
+        .sum();
+    sum % 10 == 0
+}
+
+/// The card network, from the number's issuer prefix.
+pub fn detect_card_brand(number: &str) -> Option<CardBrand> {
+    if !is_digits(number) {
+        return None;
+    }
+    let prefix = |len: usize| number.get(..len).and_then(|p| p.parse::<u32>().ok());
+    let (Some(p2), Some(p3), Some(p4), Some(p6)) = (prefix(2), prefix(3), prefix(4), prefix(6))
+    else {
+        return None;
+    };
+    if p2 == 34 || p2 == 37 {
+        Some(CardBrand::Amex)
+    } else if (51..=55).contains(&p2) || (222100..=272099).contains(&p6) {
+        Some(CardBrand::Mastercard)
+    } else if p4 == 6011 || p2 == 65 || (644..=649).contains(&p3) {
+        Some(CardBrand::Discover)
+    } else if (3528..=3589).contains(&p4) {
+        Some(CardBrand::Jcb)
+    } else if matches!(p2, 36 | 38 | 39) || (300..=305).contains(&p3) || p3 == 309 {
+        Some(CardBrand::DinersClub)
+    } else if p2 == 62 {
+        Some(CardBrand::UnionPay)
+    } else if number.starts_with('4') {
+        Some(CardBrand::Visa)
+    } else if p2 == 50 || (56..=69).contains(&p2) {
+        Some(CardBrand::Maestro)
+    } else {
+        None
+    }
+}
+
+/// The last four digits of a card number, for list views.
+pub fn card_last4(number: &str) -> Option<String> {
+    let digits: Vec<char> = number.chars().filter(char::is_ascii_digit).collect();
+    (digits.len() >= 4).then(|| digits[digits.len() - 4..].iter().collect())
+}
+
+/// A card number with all but the last four digits hidden, e.g.
+/// `•••• 4242`.
+pub fn mask_card_number(number: &str) -> Option<String> {
+    card_last4(number).map(|last4| format!("•••• {}", last4))
+}
+
+/// The first day a card with this expiry can no longer be used.
+pub(crate) fn card_expires_on(month: u8, year: u16) -> Option<chrono::NaiveDate> {
+    let year = if year < 100 { 2000 + year } else { year };
+    let (year, month) = if month == 12 {
+        (i32::from(year) + 1, 1)
+    } else {
+        (i32::from(year), u32::from(month) + 1)
+    };
+    chrono::NaiveDate::from_ymd_opt(year, month, 1)
+}
+
+fn validate_card_details(card: &CardDetails) -> std::result::Result<(), ValidationError> {
+    if let Some(month) = card.expiry_month {
+        if !(1..=12).contains(&month) {
+            return Err(ValidationError::new("card_expiry_month_invalid"));
+        }
+    }
+
+    if let Some(year) = card.expiry_year {
+        if !(1970..=2200).contains(&year) {
+            return Err(ValidationError::new("card_expiry_year_invalid"));
+        }
+    }
+
+    if let Some(code) = card.security_code.as_deref().filter(|c| !c.is_empty()) {
+        if !is_digits(code) || !(3..=4).contains(&code.len()) {
+            return Err(ValidationError::new("card_security_code_invalid"));
+        }
+    }
+
+    if let Some(pin) = card.pin.as_deref().filter(|p| !p.is_empty()) {
+        if !is_digits(pin) || !(4..=12).contains(&pin.len()) {
+            return Err(ValidationError::new("card_pin_invalid"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks a card's number, its checksum and its length for the brand, and
+/// that its expiry is complete. Cards saved before these checks, or imported, may
+/// fail them, so they apply only to a number or expiry that differs from
+/// `previous`, the details the item is stored with.
+pub fn validate_card_changes(
+    previous: Option<&ItemDetails>,
+    details: Option<&ItemDetails>,
+) -> Result<()> {
+    let Some(ItemDetails::Card(card)) = details else {
+        return Ok(());
+    };
+    let previous = match previous {
+        Some(ItemDetails::Card(previous)) => Some(previous),
+        _ => None,
+    };
+    let check = || -> std::result::Result<(), ValidationError> {
+        let number_changed = previous.is_none_or(|p| p.number != card.number);
+        if let Some(number) = card
+            .number
+            .as_deref()
+            .filter(|n| number_changed && !n.is_empty())
+        {
+            if !is_digits(number) || !(12..=19).contains(&number.len()) {
+                return Err(ValidationError::new("card_number_invalid"));
+            }
+            if !luhn_valid(number) {
+                return Err(ValidationError::new("card_number_checksum_invalid"));
+            }
+            if detect_card_brand(number)
+                .is_some_and(|brand| !brand.lengths().contains(&number.len()))
+            {
+                return Err(ValidationError::new("card_number_length_invalid"));
+            }
+        }
+
+        // An expiry is a month and a year; one without the other is a typo.
+        let expiry_changed = previous.is_none_or(|p| {
+            (p.expiry_month, p.expiry_year) != (card.expiry_month, card.expiry_year)
+        });
+        if expiry_changed && card.expiry_month.is_some() != card.expiry_year.is_some() {
+            return Err(ValidationError::new("card_expiry_incomplete"));
+        }
+        Ok(())
+    };
+    check().map_err(|e| Error::Validation(e.to_string()))
+}
+
+fn validate_identity_details(
+    identity: &IdentityDetails,
+) -> std::result::Result<(), ValidationError> {
+    if let Some(email) = identity.email.as_deref().filter(|e| !e.is_empty()) {
+        let valid = email
+            .split_once('@')
+            .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.'));
+        if !valid {
+            return Err(ValidationError::new("identity_email_invalid"));
+        }
+    }
+
+    if let Some(phone) = identity.phone.as_deref().filter(|p| !p.is_empty()) {
+        let valid = phone
+            .chars()
+            .all(|c| c.is_ascii_digit() || " +-().".contains(c))
+            && phone.chars().filter(char::is_ascii_digit).count() >= 3;
+        if !valid {
+            return Err(ValidationError::new("identity_phone_invalid"));
+        }
+    }
+
+    if let Some(date) = identity.birth_date.as_deref().filter(|d| !d.is_empty()) {
+        if chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err() {
+            return Err(ValidationError::new("identity_birth_date_invalid"));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_wifi_details(wifi: &WifiDetails) -> std::result::Result<(), ValidationError> {
+    // 802.11 caps SSIDs at 32 octets.
+    if wifi.ssid.is_empty() || wifi.ssid.len() > 32 {
+        return Err(ValidationError::new("wifi_ssid_invalid"));
+    }
+
+    Ok(())
+}
+
+fn validate_wifi_password(
+    security: WifiSecurity,
+    password: &str,
+) -> std::result::Result<(), ValidationError> {
+    if password.is_empty() {
+        return Ok(());
+    }
+
+    let valid = match security {
+        // A WPA passphrase, or the raw 256-bit PSK as hex.
+        WifiSecurity::Wpa => {
+            (8..=63).contains(&password.len())
+                || (password.len() == 64 && password.chars().all(|c| c.is_ascii_hexdigit()))
+        }
+        WifiSecurity::Wep => matches!(password.len(), 5 | 10 | 13 | 26),
+        WifiSecurity::None => false,
+    };
+    if !valid {
+        return Err(ValidationError::new("wifi_password_invalid"));
+    }
+
+    Ok(())
+}
+
+/// IBAN lengths by country, from the SWIFT IBAN registry. Countries not
+/// listed are held to the general 15 to 34 characters.
+const IBAN_LENGTHS: &[(&str, usize)] = &[
+    ("AD", 24),
+    ("AE", 23),
+    ("AL", 28),
+    ("AT", 20),
+    ("AZ", 28),
+    ("BA", 20),
+    ("BE", 16),
+    ("BG", 22),
+    ("BH", 22),
+    ("BR", 29),
+    ("CH", 21),
+    ("CR", 22),
+    ("CY", 28),
+    ("CZ", 24),
+    ("DE", 22),
+    ("DK", 18),
+    ("DO", 28),
+    ("EE", 20),
+    ("EG", 29),
+    ("ES", 24),
+    ("FI", 18),
+    ("FO", 18),
+    ("FR", 27),
+    ("GB", 22),
+    ("GE", 22),
+    ("GI", 23),
+    ("GL", 18),
+    ("GR", 27),
+    ("GT", 28),
+    ("HR", 21),
+    ("HU", 28),
+    ("IE", 22),
+    ("IL", 23),
+    ("IS", 26),
+    ("IT", 27),
+    ("JO", 30),
+    ("KW", 30),
+    ("KZ", 20),
+    ("LB", 28),
+    ("LI", 21),
+    ("LT", 20),
+    ("LU", 20),
+    ("LV", 21),
+    ("MC", 27),
+    ("MD", 24),
+    ("ME", 22),
+    ("MK", 19),
+    ("MR", 27),
+    ("MT", 31),
+    ("MU", 30),
+    ("NL", 18),
+    ("NO", 15),
+    ("PK", 24),
+    ("PL", 28),
+    ("PS", 29),
+    ("PT", 25),
+    ("QA", 29),
+    ("RO", 24),
+    ("RS", 22),
+    ("SA", 24),
+    ("SE", 24),
+    ("SI", 19),
+    ("SK", 24),
+    ("SM", 27),
+    ("TN", 24),
+    ("TR", 26),
+    ("UA", 29),
+    ("VA", 22),
+    ("VG", 24),
+    ("XK", 20),
+];
+
+/// A validation error naming the offending field and why it was rejected,
+/// so the editor can highlight it.
+fn field_error(code: &'static str, field: &'static str, reason: &'static str) -> ValidationError {
+    let mut error = ValidationError::new(code);
+    error.add_param("field".into(), &field);
+    error.add_param("reason".into(), &reason);
+    error
+}
+
+/// Checks an IBAN, ignoring spaces and case. Returns why it is invalid.
+pub fn check_iban(iban: &str) -> std::result::Result<(), &'static str> {
+    let iban: String = iban
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    if !iban.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err("characters");
+    }
+    let bytes = iban.as_bytes();
+    if bytes.len() < 4
+        || !bytes[..2].iter().all(u8::is_ascii_uppercase)
+        || !bytes[2..4].iter().all(u8::is_ascii_digit)
+    {
+        return Err("format");
+    }
+    let expected = IBAN_LENGTHS
+        .iter()
+        .find(|(country, _)| *country == &iban[..2])
+        .map(|(_, len)| *len);
+    let length_ok = match expected {
+        Some(len) => iban.len() == len,
+        None => (15..=34).contains(&iban.len()),
+    };
+    if !length_ok {
+        return Err("length");
+    }
+
+    // Move the country code and check digits to the end, turn letters into
+    // 10..35 and take the whole number mod 97, a digit at a time.
+    let remainder = iban[4..]
+        .chars()
+        .chain(iban[..4].chars())
+        .fold(0u32, |acc, c| {
+            let value = c.to_digit(36).unwrap_or(0);
+            if value >= 10 {
+                (acc * 100 + value) % 97
+            } else {
+                (acc * 10 + value) % 97
+            }
+        });
+    if remainder != 1 {
+        return Err("checksum");
+    }
+    Ok(())
+}
+
+/// Checks a BIC (SWIFT code): a 4-letter bank code, a 2-letter country, a
+/// 2-character location and an optional 3-character branch.
+pub fn check_bic(bic: &str) -> std::result::Result<(), &'static str> {
+    let bic = bic.trim().to_ascii_uppercase();
+    let bytes = bic.as_bytes();
+    if bytes.len() != 8 && bytes.len() != 11 {
+        return Err("length");
+    }
+    let valid = bytes[..6].iter().all(u8::is_ascii_uppercase)
+        && bytes[6..].iter().all(u8::is_ascii_alphanumeric);
+    if !valid {
+        return Err("format");
+    }
+    Ok(())
+}
+
+/// Checks a 9-digit US ABA routing number and its weighted checksum.
+pub fn check_aba_routing_number(number: &str) -> std::result::Result<(), &'static str> {
+    let number = number.trim();
+    if number.len() != 9 || !is_digits(number) {
+        return Err("format");
+    }
+    let digits: Vec<u32> = number.bytes().map(|b| u32::from(b - b'0')).collect();
+    // The first two digits name a Federal Reserve district or a thrift,
+    // electronic or traveler's cheque range.
+    let prefix = digits[0] * 10 + digits[1];
+    if !matches!(prefix, 0..=12 | 21..=32 | 61..=72 | 80) {
+        return Err("prefix");
+    }
+    let sum: u32 = digits.chunks(3).map(|d| 3 * d[0] + 7 * d[1] + d[2]).sum();
+    if sum % 10 != 0 {
+        return Err("checksum");
+    }
+    Ok(())
+}
+
+fn validate_bank_account_details(
+    account: &BankAccountDetails,
+) -> std::result::Result<(), ValidationError> {
+    if let Some(iban) = account.iban.as_deref().filter(|i| !i.trim().is_empty()) {
+        check_iban(iban).map_err(|reason| field_error("bank_iban_invalid", "iban", reason))?;
+    }
+
+    if let Some(bic) = account.bic.as_deref().filter(|b| !b.trim().is_empty()) {
+        check_bic(bic).map_err(|reason| field_error("bank_bic_invalid", "bic", reason))?;
+    }
+
+    if let Some(routing) = account
+        .routing_number
+        .as_deref()
+        .filter(|r| !r.trim().is_empty())
+    {
+        check_aba_routing_number(routing).map_err(|reason| {
+            field_error("bank_routing_number_invalid", "routingNumber", reason)
+        })?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::SecretString;
+
+    fn card(number: Option<&str>, month: Option<u8>, year: Option<u16>) -> ItemDetails {
+        ItemDetails::Card(CardDetails {
+            number: number.map(|n| SecretString::new(n.to_string())),
+            expiry_month: month,
+            expiry_year: year,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_luhn_valid() {
+        assert!(luhn_valid("4242424242424242"));
+        assert!(luhn_valid("378282246310005"));
+        assert!(!luhn_valid("4242424242424241"));
+        assert!(!luhn_valid("4242 4242 4242 4242"));
+        assert!(!luhn_valid(""));
+    }
+
+    #[test]
+    fn test_detect_card_brand() {
+        let cases = [
+            ("4242424242424242", CardBrand::Visa),
+            ("5555555555554444", CardBrand::Mastercard),
+            ("2223003122003222", CardBrand::Mastercard),
+            ("378282246310005", CardBrand::Amex),
+            ("6011111111111117", CardBrand::Discover),
+            ("3530111333300000", CardBrand::Jcb),
+            ("30569309025904", CardBrand::DinersClub),
+            ("6200000000000005", CardBrand::UnionPay),
+            ("6759649826438453", CardBrand::Maestro),
+        ];
+        for (number, brand) in cases {
+            assert_eq!(detect_card_brand(number), Some(brand), "{number}");
+        }
+        assert_eq!(detect_card_brand("1234567890123"), None);
+        assert_eq!(detect_card_brand("42a2424242424242"), None);
+        assert_eq!(detect_card_brand("424"), None);
+    }
+
+    #[test]
+    fn test_card_expires_on() {
+        let date = |y, m, d| chrono::NaiveDate::from_ymd_opt(y, m, d);
+        assert_eq!(card_expires_on(2, 2026), date(2026, 3, 1));
+        assert_eq!(card_expires_on(12, 2025), date(2026, 1, 1));
+        assert_eq!(card_expires_on(7, 27), date(2027, 8, 1));
+        assert_eq!(card_expires_on(13, 2026), None);
+    }
+
+    #[test]
+    fn test_card_last4() {
+        assert_eq!(card_last4("4242424242424242").as_deref(), Some("4242"));
+        assert_eq!(card_last4("4242 4242 4242 1881").as_deref(), Some("1881"));
+        assert_eq!(card_last4("123"), None);
+    }
+
+    #[test]
+    fn test_new_card_is_checked() {
+        let bad_checksum = card(Some("4242424242424241"), None, None);
+        assert!(validate_card_changes(None, Some(&bad_checksum)).is_err());
+        let bad_length = card(Some("3782822463100052"), None, None);
+        assert!(validate_card_changes(None, Some(&bad_length)).is_err());
+        let incomplete = card(None, Some(4), None);
+        assert!(validate_card_changes(None, Some(&incomplete)).is_err());
+        let valid = card(Some("4242424242424242"), Some(4), Some(2030));
+        assert!(validate_card_changes(None, Some(&valid)).is_ok());
+    }
+
+    #[test]
+    fn test_unchanged_card_values_are_not_rechecked() {
+        let stored = card(Some("4242424242424241"), Some(4), None);
+        assert!(validate_card_changes(Some(&stored), Some(&stored)).is_ok());
+
+        let new_number = card(Some("1111111111111111"), Some(4), None);
+        assert!(validate_card_changes(Some(&stored), Some(&new_number)).is_err());
+
+        let new_expiry = card(Some("4242424242424241"), Some(5), None);
+        assert!(validate_card_changes(Some(&stored), Some(&new_expiry)).is_err());
+    }
+
+    #[test]
+    fn test_check_iban() {
+        assert_eq!(check_iban("DE89370400440532013000"), Ok(()));
+        assert_eq!(check_iban("GB29NWBK60161331926819"), Ok(()));
+        assert_eq!(check_iban("gb29 nwbk 6016 1331 9268 19"), Ok(()));
+        assert_eq!(check_iban("DE89370400440532013001"), Err("checksum"));
+        assert_eq!(check_iban("DE8937040044053201300"), Err("length"));
+        assert_eq!(check_iban("DE89-3704-0044-0532-0130-00"), Err("characters"));
+        assert_eq!(check_iban("8937040044053201300DE"), Err("format"));
+    }
+
+    #[test]
+    fn test_check_bic() {
+        assert_eq!(check_bic("DEUTDEFF"), Ok(()));
+        assert_eq!(check_bic("DEUTDEFF500"), Ok(()));
+        assert_eq!(check_bic("deutdeff"), Ok(()));
+        assert_eq!(check_bic("DEUTDEF"), Err("length"));
+        assert_eq!(check_bic("DEU1DEFF"), Err("format"));
+    }
+
+    #[test]
+    fn test_check_aba_routing_number() {
+        assert_eq!(check_aba_routing_number("011000015"), Ok(()));
+        assert_eq!(check_aba_routing_number("021000021"), Ok(()));
+        assert_eq!(check_aba_routing_number("011000016"), Err("checksum"));
+        assert_eq!(check_aba_routing_number("01100001"), Err("format"));
+        assert_eq!(check_aba_routing_number("991000015"), Err("prefix"));
+    }
+}
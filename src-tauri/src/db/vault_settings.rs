@@ -0,0 +1,223 @@
+//! Preferences that belong to a vault rather than to the installation. They
+//! are stored encrypted in the vault's `configuration` table, so they travel
+//! with the file and apply on every machine that opens it. Unset values fall
+//! back to the app settings.
+
+use crate::auth::reverify::{self, ReverificationPolicy};
+use crate::db::note_history;
+use crate::db::utils::UnlockedVault;
+use crate::db::validation::{policy_violations, PolicyViolation};
+use crate::encryption::{decrypt, encrypt};
+use crate::error::{Error, Result};
+use crate::limits;
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use zeroize::Zeroizing;
+
+const CONFIG_KEY: &str = "vault_settings";
+
+const MAX_CLIPBOARD_CLEAR_SECONDS: u32 = 24 * 60 * 60;
+const MAX_AUTO_LOCK_MINUTES: u32 = 7 * 24 * 60;
+const MAX_POLICY_LENGTH: u32 = 1024;
+const MAX_PASSWORD_AGE_DAYS: u32 = 10 * 365;
+const MAX_BANNED_WORDS: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PasswordPolicy {
+    pub min_length: u32,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digits: bool,
+    pub require_symbols: bool,
+    /// Days after which a password should be changed; 0 never expires.
+    /// Reported by notifications rather than enforced on save.
+    pub max_age_days: u32,
+    /// Words a password may not contain, ignoring case.
+    pub banned_words: Vec<String>,
+    /// Rejects passwords on the common-password blocklist.
+    pub block_common_passwords: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VaultSettings {
+    /// Seconds before copied secrets are cleared; 0 never clears.
+    pub clipboard_clear_seconds: Option<u32>,
+    /// Minutes of inactivity before the vault locks; 0 never locks.
+    pub auto_lock_minutes: Option<u32>,
+    /// Category preselected for new items.
+    pub default_category: Option<String>,
+    pub password_policy: Option<PasswordPolicy>,
+    /// Operations that need a recent password or biometric check.
+    pub reverification: Option<ReverificationPolicy>,
+    /// Largest attachment that can be added, in MiB.
+    pub max_attachment_size_mb: Option<u32>,
+    /// Combined size of all attachments in the vault, in MiB.
+    pub max_attachments_total_mb: Option<u32>,
+    /// Earlier versions of notes kept per item; 0 keeps none.
+    pub note_history_depth: Option<u32>,
+}
+
+pub(crate) fn validate(settings: &VaultSettings) -> Result<()> {
+    if settings
+        .clipboard_clear_seconds
+        .is_some_and(|s| s > MAX_CLIPBOARD_CLEAR_SECONDS)
+    {
+        return Err(Error::Validation(format!(
+            "Clipboard clear time cannot exceed {} seconds.",
+            MAX_CLIPBOARD_CLEAR_SECONDS
+        )));
+    }
+    if settings
+        .auto_lock_minutes
+        .is_some_and(|m| m > MAX_AUTO_LOCK_MINUTES)
+    {
+        return Err(Error::Validation(format!(
+            "Auto-lock timeout cannot exceed {} minutes.",
+            MAX_AUTO_LOCK_MINUTES
+        )));
+    }
+    if let Some(category) = &settings.default_category {
+        limits::check_len("Default category", category, limits::MAX_TITLE_LEN)?;
+        if category.trim().is_empty() {
+            return Err(Error::Validation(
+                "Default category cannot be empty.".to_string(),
+            ));
+        }
+    }
+    if let Some(policy) = &settings.password_policy {
+        if policy.min_length > MAX_POLICY_LENGTH {
+            return Err(Error::Validation(format!(
+                "Minimum password length cannot exceed {}.",
+                MAX_POLICY_LENGTH
+            )));
+        }
+        if policy.max_age_days > MAX_PASSWORD_AGE_DAYS {
+            return Err(Error::Validation(format!(
+                "Maximum password age cannot exceed {} days.",
+                MAX_PASSWORD_AGE_DAYS
+            )));
+        }
+        if policy.banned_words.len() > MAX_BANNED_WORDS {
+            return Err(Error::Validation(format!(
+                "A password policy can ban at most {} words.",
+                MAX_BANNED_WORDS
+            )));
+        }
+        for word in &policy.banned_words {
+            limits::check_len("Banned word", word, limits::MAX_TITLE_LEN)?;
+        }
+    }
+    let max_attachment_mb = limits::MAX_ATTACHMENT_SIZE_CEILING / (1024 * 1024);
+    if settings
+        .max_attachment_size_mb
+        .is_some_and(|mb| mb == 0 || u64::from(mb) > max_attachment_mb)
+    {
+        return Err(Error::Validation(format!(
+            "Attachment size limit must be between 1 and {} MiB.",
+            max_attachment_mb
+        )));
+    }
+    if settings.max_attachments_total_mb == Some(0) {
+        return Err(Error::Validation(
+            "The attachment quota must be at least 1 MiB.".to_string(),
+        ));
+    }
+    if settings
+        .note_history_depth
+        .is_some_and(|d| d > note_history::MAX_NOTE_HISTORY_DEPTH)
+    {
+        return Err(Error::Validation(format!(
+            "Notes history cannot keep more than {} versions.",
+            note_history::MAX_NOTE_HISTORY_DEPTH
+        )));
+    }
+    if settings
+        .reverification
+        .as_ref()
+        .is_some_and(|r| r.window_seconds > reverify::MAX_WINDOW_SECONDS)
+    {
+        return Err(Error::Validation(format!(
+            "Verification window cannot exceed {} seconds.",
+            reverify::MAX_WINDOW_SECONDS
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) async fn load_vault_settings<'a, E>(executor: E, key: &[u8]) -> Result<VaultSettings>
+where
+    E: sqlx::SqliteExecutor<'a>,
+{
+    let value: Option<String> = sqlx::query_scalar("SELECT value FROM configuration WHERE key = ?")
+        .bind(CONFIG_KEY)
+        .fetch_optional(executor)
+        .await?;
+    match value {
+        Some(enc) => {
+            let json = Zeroizing::new(decrypt(&enc, key)?);
+            Ok(serde_json::from_str(&json)?)
+        }
+        None => Ok(VaultSettings::default()),
+    }
+}
+
+/// The vault's password policy, or an empty one when none is set.
+pub(crate) async fn load_password_policy<'a, E>(executor: E, key: &[u8]) -> Result<PasswordPolicy>
+where
+    E: sqlx::SqliteExecutor<'a>,
+{
+    Ok(load_vault_settings(executor, key)
+        .await?
+        .password_policy
+        .unwrap_or_default())
+}
+
+pub(crate) async fn store_vault_settings<'a, E>(
+    executor: E,
+    key: &[u8],
+    settings: &VaultSettings,
+) -> Result<()>
+where
+    E: sqlx::SqliteExecutor<'a>,
+{
+    let json = Zeroizing::new(serde_json::to_string(settings)?);
+    sqlx::query("INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)")
+        .bind(CONFIG_KEY)
+        .bind(encrypt(&json, key)?)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_vault_settings(state: State<'_, AppState>) -> Result<VaultSettings> {
+    let vault = UnlockedVault::acquire(&state).await?;
+    load_vault_settings(vault.pool(), vault.key()).await
+}
+
+#[tauri::command]
+pub async fn save_vault_settings(
+    state: State<'_, AppState>,
+    settings: VaultSettings,
+) -> Result<()> {
+    validate(&settings)?;
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    store_vault_settings(vault.pool(), vault.key(), &settings).await
+}
+
+/// Lists the ways `password` breaks the vault's password policy, for live
+/// feedback while editing. An empty list means it complies.
+#[tauri::command]
+pub async fn check_policy(
+    state: State<'_, AppState>,
+    password: String,
+) -> Result<Vec<PolicyViolation>> {
+    let password = Zeroizing::new(password);
+    limits::check_len("Password", &password, limits::MAX_PASSWORD_LEN)?;
+    let vault = UnlockedVault::acquire(&state).await?;
+    let policy = load_password_policy(vault.pool(), vault.key()).await?;
+    Ok(policy_violations(&policy, &password))
+}
@@ -0,0 +1,118 @@
+//! Starter content for new vaults. A template adds a set of tags, item
+//! templates for the kinds of entries the vault is meant for, and default
+//! vault settings. The templates ship with the app as embedded JSON.
+
+use crate::db::buttons::get_buttons_impl;
+use crate::db::palettes::normalize_color;
+use crate::db::utils::{CryptoHelper, UnlockedVault};
+use crate::db::vault_settings::{self, VaultSettings};
+use crate::error::{Error, Result};
+use crate::limits;
+use crate::types::CustomField;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+const TEMPLATES_JSON: &str = include_str!("vault_templates.json");
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateTag {
+    pub text: String,
+    pub icon: String,
+    pub color: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateItemTemplate {
+    pub name: String,
+    pub category: String,
+    pub custom_fields: Vec<CustomField>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultTemplate {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub tags: Vec<TemplateTag>,
+    pub item_templates: Vec<TemplateItemTemplate>,
+    pub settings: VaultSettings,
+}
+
+fn vault_templates() -> Result<Vec<VaultTemplate>> {
+    Ok(serde_json::from_str(TEMPLATES_JSON)?)
+}
+
+/// Looks up a template by id and checks its content before anything is
+/// written, so a bad template cannot leave a half-created vault behind.
+pub(crate) fn find_vault_template(id: &str) -> Result<VaultTemplate> {
+    let template = vault_templates()?
+        .into_iter()
+        .find(|t| t.id == id)
+        .ok_or_else(|| Error::Validation(format!("Unknown vault template '{}'", id)))?;
+
+    for tag in &template.tags {
+        limits::check_len("Tag text", &tag.text, limits::MAX_TITLE_LEN)?;
+        limits::check_len("Tag icon", &tag.icon, limits::MAX_IMG_LEN)?;
+        normalize_color(&tag.color)?;
+    }
+    for item in &template.item_templates {
+        limits::check_len("Template name", &item.name, limits::MAX_TITLE_LEN)?;
+        limits::check_len("Category", &item.category, limits::MAX_SHORT_FIELD_LEN)?;
+        limits::check_custom_fields(&item.custom_fields, None)?;
+    }
+    vault_settings::validate(&template.settings)?;
+    Ok(template)
+}
+
+/// Writes a template's tags, item templates and settings in one transaction.
+/// Tags whose name the vault already uses are skipped.
+pub(crate) async fn apply_vault_template(
+    vault: &UnlockedVault,
+    template: &VaultTemplate,
+) -> Result<()> {
+    let helper = CryptoHelper::new(vault.key())?;
+    let now = Utc::now().to_rfc3339();
+    let mut tx = vault.pool().begin().await?;
+
+    let existing: Vec<String> = get_buttons_impl(&mut *tx, vault.key())
+        .await?
+        .into_iter()
+        .map(|b| b.text.to_lowercase())
+        .collect();
+    for tag in &template.tags {
+        if existing.contains(&tag.text.to_lowercase()) {
+            continue;
+        }
+        sqlx::query("INSERT INTO buttons (text, icon, color, parent_id) VALUES (?, ?, ?, NULL)")
+            .bind(helper.encrypt(&tag.text)?)
+            .bind(helper.encrypt(&tag.icon)?)
+            .bind(helper.encrypt(&normalize_color(&tag.color)?)?)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    for item in &template.item_templates {
+        sqlx::query(
+            "INSERT INTO templates (name, category, custom_fields, field_order, created_at, updated_at) VALUES (?, ?, ?, NULL, ?, ?)",
+        )
+        .bind(helper.encrypt(&item.name)?)
+        .bind(helper.encrypt(&item.category)?)
+        .bind(helper.encrypt(&serde_json::to_string(&item.custom_fields)?)?)
+        .bind(&now)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    vault_settings::store_vault_settings(&mut *tx, vault.key(), &template.settings).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_vault_templates() -> Result<Vec<VaultTemplate>> {
+    vault_templates()
+}
@@ -0,0 +1,63 @@
+use crate::db::utils::UnlockedVault;
+use crate::error::Result;
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use tauri::State;
+
+/// How `PRAGMA wal_checkpoint` treats readers and writers; see the SQLite
+/// documentation. `Truncate` also shrinks the `-wal` file to zero bytes.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckpointMode {
+    #[default]
+    Passive,
+    Full,
+    Restart,
+    Truncate,
+}
+
+impl CheckpointMode {
+    fn as_sql(self) -> &'static str {
+        match self {
+            CheckpointMode::Passive => "PASSIVE",
+            CheckpointMode::Full => "FULL",
+            CheckpointMode::Restart => "RESTART",
+            CheckpointMode::Truncate => "TRUNCATE",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckpointResult {
+    /// Set when a reader or writer kept the checkpoint from completing.
+    pub busy: bool,
+    /// Frames in the WAL, or -1 when the vault is not in WAL mode.
+    pub log_frames: i64,
+    pub checkpointed_frames: i64,
+}
+
+/// Copies the WAL's pages back into the vault file.
+pub(crate) async fn checkpoint(
+    pool: &SqlitePool,
+    mode: CheckpointMode,
+) -> Result<CheckpointResult> {
+    let row = sqlx::query(&format!("PRAGMA wal_checkpoint({})", mode.as_sql()))
+        .fetch_one(pool)
+        .await?;
+    Ok(CheckpointResult {
+        busy: row.get::<i64, _>(0) != 0,
+        log_frames: row.get(1),
+        checkpointed_frames: row.get(2),
+    })
+}
+
+#[tauri::command]
+pub async fn checkpoint_wal(
+    state: State<'_, AppState>,
+    mode: Option<CheckpointMode>,
+) -> Result<CheckpointResult> {
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    checkpoint(vault.pool(), mode.unwrap_or_default()).await
+}
@@ -0,0 +1,142 @@
+use crate::db::utils::{CryptoHelper, UnlockedVault};
+use crate::error::{Error, Result};
+use crate::state::AppState;
+use crate::types::{ItemDetails, WifiDetails, WifiSecurity};
+use qrcodegen::{QrCode, QrCodeEcc};
+use sqlx::Row;
+use std::fmt::Write as _;
+use tauri::State;
+use zeroize::Zeroizing;
+
+/// Escapes the characters the `WIFI:` scheme reserves.
+fn escape_wifi_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | ';' | ',' | ':' | '"') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Builds the payload Android and iOS camera apps understand, e.g.
+/// `WIFI:T:WPA;S:home;P:secret;;`.
+pub fn wifi_qr_payload(wifi: &WifiDetails, password: &str) -> Zeroizing<String> {
+    let security = match wifi.security {
+        WifiSecurity::Wpa => "WPA",
+        WifiSecurity::Wep => "WEP",
+        WifiSecurity::None => "nopass",
+    };
+
+    let mut payload = Zeroizing::new(format!(
+        "WIFI:T:{};S:{};",
+        security,
+        escape_wifi_value(&wifi.ssid)
+    ));
+    if wifi.security != WifiSecurity::None {
+        let escaped = Zeroizing::new(escape_wifi_value(password));
+        payload.push_str("P:");
+        payload.push_str(&escaped);
+        payload.push(';');
+    }
+    if wifi.hidden {
+        payload.push_str("H:true;");
+    }
+    payload.push(';');
+    payload
+}
+
+/// Renders `payload` as a QR code image in `format`, `png` or `svg`.
+pub(crate) fn render_qr(payload: &str, format: &str) -> Result<Vec<u8>> {
+    match format {
+        "svg" => {
+            let qr = QrCode::encode_text(payload, QrCodeEcc::Medium)
+                .map_err(|e| Error::Internal(format!("Failed to encode QR code: {:?}", e)))?;
+            Ok(render_svg(&qr))
+        }
+        "png" => qrcodegen_image::draw_png(payload).map_err(Error::Internal),
+        other => Err(Error::Validation(format!(
+            "Unsupported QR format '{}'",
+            other
+        ))),
+    }
+}
+
+fn render_svg(qr: &QrCode) -> Vec<u8> {
+    const BORDER: i32 = 4;
+    let dimension = qr.size() + BORDER * 2;
+
+    let mut path = String::new();
+    for y in 0..qr.size() {
+        for x in 0..qr.size() {
+            if qr.get_module(x, y) {
+                let _ = write!(path, "M{},{}h1v1h-1z", x + BORDER, y + BORDER);
+            }
+        }
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" viewBox=\"0 0 {0} {0}\" stroke=\"none\">\n<rect width=\"100%\" height=\"100%\" fill=\"#FFFFFF\"/>\n<path d=\"{1}\" fill=\"#000000\"/>\n</svg>\n",
+        dimension, path
+    )
+    .into_bytes()
+}
+
+/// Renders a QR code that joins the Wi-Fi network stored in `item_id`.
+/// `format` is `png` (default) or `svg`.
+#[tauri::command]
+pub async fn generate_wifi_qr(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    item_id: i64,
+    format: Option<String>,
+) -> Result<Vec<u8>> {
+    let vault = UnlockedVault::acquire(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
+
+    let row = sqlx::query("SELECT title, password, details FROM password_items WHERE id = ?")
+        .bind(item_id)
+        .fetch_optional(vault.pool())
+        .await?
+        .ok_or_else(|| Error::Validation(format!("Item {} not found", item_id)))?;
+
+    let details_enc: Option<String> = row.get("details");
+    let details: Option<ItemDetails> = match details_enc {
+        Some(enc) => {
+            let json = helper.decrypt_secret(&enc)?;
+            serde_json::from_str(json.as_str()).ok()
+        }
+        None => None,
+    };
+    let Some(ItemDetails::Wifi(wifi)) = details else {
+        return Err(Error::Validation(
+            "This item is not a Wi-Fi network".to_string(),
+        ));
+    };
+
+    let password_enc: String = row.get("password");
+    let password = helper.decrypt_secret(&password_enc)?;
+    if wifi.security != WifiSecurity::None && password.as_str().is_empty() {
+        return Err(Error::Validation(
+            "The Wi-Fi network has no password".to_string(),
+        ));
+    }
+
+    let payload = wifi_qr_payload(&wifi, password.as_str());
+    let bytes = render_qr(&payload, format.as_deref().unwrap_or("png"))?;
+
+    let title_enc: String = row.get("title");
+    let _ = crate::db::activity::log_secret_access(
+        &app,
+        vault.pool(),
+        vault.key(),
+        "wifi_qr_generated",
+        Some(item_id),
+        helper.decrypt(&title_enc).ok().as_deref(),
+        None,
+    )
+    .await;
+
+    Ok(bytes)
+}
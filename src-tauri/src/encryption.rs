@@ -1,7 +1,8 @@
 use crate::error::{Error, Result};
+use crate::memlock::LockedKey;
 use base64::{engine::general_purpose, Engine as _};
 use chacha20poly1305::{
-    aead::{Aead, KeyInit},
+    aead::{Aead, KeyInit, Payload},
     Key, XChaCha20Poly1305, XNonce,
 };
 use rand::{rngs::OsRng, RngCore};
@@ -123,19 +124,154 @@ pub fn decrypt_bytes(encrypted_data: &[u8], key: &[u8]) -> Result<Vec<u8>> {
     Ok(decrypted_bytes)
 }
 
+/// Plaintext bytes per frame of the streaming format.
+pub const STREAM_CHUNK_LEN: usize = 64 * 1024;
+/// A full frame: one chunk plus its Poly1305 tag.
+pub const STREAM_FRAME_LEN: usize = STREAM_CHUNK_LEN + 16;
+const STREAM_MAGIC: &[u8; 8] = b"PLSRSTM1";
+const STREAM_NONCE_PREFIX_LEN: usize = 19;
+pub const STREAM_HEADER_LEN: usize = STREAM_MAGIC.len() + STREAM_NONCE_PREFIX_LEN;
+
+/// Whether `data` starts with the header of the streaming format.
+pub fn is_stream_ciphertext(data: &[u8]) -> bool {
+    data.starts_with(STREAM_MAGIC)
+}
+
+/// Nonce of frame `counter`: the random prefix, the big-endian counter and a
+/// flag marking the last frame, as in the STREAM construction. Reordered,
+/// dropped or truncated frames fail to authenticate.
+fn stream_nonce(prefix: &[u8], counter: u32, last: bool) -> XNonce {
+    let mut nonce = [0u8; 24];
+    nonce[..STREAM_NONCE_PREFIX_LEN].copy_from_slice(prefix);
+    nonce[STREAM_NONCE_PREFIX_LEN..23].copy_from_slice(&counter.to_be_bytes());
+    nonce[23] = u8::from(last);
+    *XNonce::from_slice(&nonce)
+}
+
+/// Encrypts data of any size as a header followed by frames of at most
+/// `STREAM_FRAME_LEN` bytes. Every frame but the last carries exactly
+/// `STREAM_CHUNK_LEN` plaintext bytes; the last one carries fewer and may be
+/// empty.
+pub struct StreamEncryptor {
+    cipher: XChaCha20Poly1305,
+    header: [u8; STREAM_HEADER_LEN],
+    counter: u32,
+    finished: bool,
+}
+
+impl StreamEncryptor {
+    pub fn new(key: &[u8]) -> Result<Self> {
+        ensure_key_len(key, Error::Encryption)?;
+        let mut header = [0u8; STREAM_HEADER_LEN];
+        header[..STREAM_MAGIC.len()].copy_from_slice(STREAM_MAGIC);
+        OsRng.fill_bytes(&mut header[STREAM_MAGIC.len()..]);
+        Ok(Self {
+            cipher: XChaCha20Poly1305::new(Key::from_slice(key)),
+            header,
+            counter: 0,
+            finished: false,
+        })
+    }
+
+    pub fn header(&self) -> &[u8] {
+        &self.header
+    }
+
+    pub fn encrypt_chunk(&mut self, chunk: &[u8], last: bool) -> Result<Vec<u8>> {
+        if self.finished {
+            return Err(Error::Encryption("Stream already finished".to_string()));
+        }
+        if chunk.len() > STREAM_CHUNK_LEN || (!last && chunk.len() != STREAM_CHUNK_LEN) {
+            return Err(Error::Encryption("Invalid stream chunk length".to_string()));
+        }
+        let nonce = stream_nonce(&self.header[STREAM_MAGIC.len()..], self.counter, last);
+        let frame = self
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: chunk,
+                    aad: &self.header,
+                },
+            )
+            .map_err(|e| Error::Encryption(format!("Encryption failed: {e}")))?;
+        self.counter = self
+            .counter
+            .checked_add(1)
+            .ok_or_else(|| Error::Encryption("Stream is too long".to_string()))?;
+        self.finished = last;
+        Ok(frame)
+    }
+}
+
+pub struct StreamDecryptor {
+    cipher: XChaCha20Poly1305,
+    header: [u8; STREAM_HEADER_LEN],
+    counter: u32,
+    finished: bool,
+}
+
+impl StreamDecryptor {
+    pub fn new(key: &[u8], header: &[u8]) -> Result<Self> {
+        ensure_key_len(key, Error::Decryption)?;
+        if header.len() != STREAM_HEADER_LEN || !is_stream_ciphertext(header) {
+            return Err(Error::Decryption("Invalid stream header".to_string()));
+        }
+        let mut stored = [0u8; STREAM_HEADER_LEN];
+        stored.copy_from_slice(header);
+        Ok(Self {
+            cipher: XChaCha20Poly1305::new(Key::from_slice(key)),
+            header: stored,
+            counter: 0,
+            finished: false,
+        })
+    }
+
+    /// `last` must be set for the final frame, which is the first one
+    /// shorter than `STREAM_FRAME_LEN`.
+    pub fn decrypt_frame(&mut self, frame: &[u8], last: bool) -> Result<Vec<u8>> {
+        if self.finished {
+            return Err(Error::Decryption(
+                "Data after the end of the stream".to_string(),
+            ));
+        }
+        let nonce = stream_nonce(&self.header[STREAM_MAGIC.len()..], self.counter, last);
+        let chunk = self
+            .cipher
+            .decrypt(
+                &nonce,
+                Payload {
+                    msg: frame,
+                    aad: &self.header,
+                },
+            )
+            .map_err(|e| Error::Decryption(format!("Decryption failed: {e}")))?;
+        self.counter = self
+            .counter
+            .checked_add(1)
+            .ok_or_else(|| Error::Decryption("Stream is too long".to_string()))?;
+        self.finished = last;
+        Ok(chunk)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
 pub struct CipherSession {
     cipher: XChaCha20Poly1305,
-    key: Vec<u8>,
+    /// Kept for the search-token HMAC; zeroized by `LockedKey` on drop.
+    key: LockedKey,
 }
 
 impl CipherSession {
     pub fn new(key: &[u8]) -> Result<Self> {
         ensure_key_len(key, Error::Encryption)?;
-        let key_vec = key.to_vec();
         let key_slice: &Key = Key::from_slice(key);
         Ok(Self {
             cipher: XChaCha20Poly1305::new(key_slice),
-            key: key_vec,
+            key: LockedKey::new(key),
         })
     }
 
@@ -273,5 +409,67 @@ mod tests {
         assert!(encrypt_bytes(b"hello", short_key).is_err());
         assert!(decrypt("a:b", short_key).is_err());
         assert!(decrypt_bytes(&[0u8; 24], short_key).is_err());
+        assert!(StreamEncryptor::new(short_key).is_err());
+    }
+
+    fn encrypt_stream(data: &[u8], key: &[u8]) -> Vec<u8> {
+        let mut encryptor = StreamEncryptor::new(key).unwrap();
+        let mut out = encryptor.header().to_vec();
+        let mut chunks = data.chunks(STREAM_CHUNK_LEN).peekable();
+        while let Some(chunk) = chunks.next() {
+            let last = chunks.peek().is_none() && chunk.len() < STREAM_CHUNK_LEN;
+            out.extend(encryptor.encrypt_chunk(chunk, last).unwrap());
+        }
+        if data.len() % STREAM_CHUNK_LEN == 0 {
+            out.extend(encryptor.encrypt_chunk(&[], true).unwrap());
+        }
+        out
+    }
+
+    fn decrypt_stream(data: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+        if data.len() < STREAM_HEADER_LEN {
+            return Err(Error::Decryption("Invalid stream: too short".to_string()));
+        }
+        let (header, body) = data.split_at(STREAM_HEADER_LEN);
+        // A body that is empty or a multiple of the frame length lost its final
+        // frame.
+        if body.len() % STREAM_FRAME_LEN == 0 {
+            return Err(Error::Decryption("Stream is truncated".to_string()));
+        }
+        let mut decryptor = StreamDecryptor::new(key, header)?;
+        let mut plaintext = Vec::with_capacity(body.len());
+        let mut frames = body.chunks(STREAM_FRAME_LEN).peekable();
+        while let Some(frame) = frames.next() {
+            let last = frames.peek().is_none();
+            plaintext.extend_from_slice(&decryptor.decrypt_frame(frame, last)?);
+        }
+        Ok(plaintext)
+    }
+
+    #[test]
+    fn test_stream_roundtrip() {
+        let key = b"an-example-key-that-is-32-bytes!";
+        for len in [0, 1, STREAM_CHUNK_LEN, STREAM_CHUNK_LEN * 2 + 7] {
+            let data: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let encrypted = encrypt_stream(&data, key);
+            assert!(is_stream_ciphertext(&encrypted));
+            assert_eq!(decrypt_stream(&encrypted, key).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_stream_detects_truncation_and_tampering() {
+        let key = b"an-example-key-that-is-32-bytes!";
+        let data = vec![7u8; STREAM_CHUNK_LEN * 2 + 7];
+        let encrypted = encrypt_stream(&data, key);
+
+        let truncated = &encrypted[..STREAM_HEADER_LEN + STREAM_FRAME_LEN];
+        assert!(decrypt_stream(truncated, key).is_err());
+        let dropped_tail = &encrypted[..STREAM_HEADER_LEN + STREAM_FRAME_LEN + 10];
+        assert!(decrypt_stream(dropped_tail, key).is_err());
+
+        let mut tampered = encrypted.clone();
+        tampered[STREAM_HEADER_LEN + 5] ^= 1;
+        assert!(decrypt_stream(&tampered, key).is_err());
     }
 }
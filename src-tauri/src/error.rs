@@ -4,10 +4,13 @@ use thiserror::Error;
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Database error: {0}")]
-    Database(#[from] sqlx::Error),
+    Database(#[source] sqlx::Error),
 
     #[error("IO error: {0}")]
-    Io(#[from] std::io::Error),
+    Io(#[source] std::io::Error),
+
+    #[error("Storage is read-only: {0}")]
+    ReadOnlyStorage(String),
 
     #[error("Encryption error: {0}")]
     Encryption(String),
@@ -18,18 +21,47 @@ pub enum Error {
     #[error("Validation error: {0}")]
     Validation(String),
 
+    #[error("Input too large: {0}")]
+    InputTooLarge(String),
+
+    #[error("Network error: {0}")]
+    Network(String),
+
     #[error("Internal error: {0}")]
     Internal(String),
 
+    #[error("This item is read-only. Clear its read-only flag before changing it.")]
+    ItemReadOnly { item_id: i64 },
+
+    #[error("The password does not meet the vault's password policy.")]
+    PasswordPolicy {
+        violations: Vec<crate::db::validation::PolicyViolation>,
+    },
+
     #[error("Vault is locked")]
     VaultLocked,
 
     #[error("Vault not loaded")]
     VaultNotLoaded,
 
+    #[error("The vault file was changed outside Pulsar. Reload the vault before making changes.")]
+    VaultModifiedExternally,
+
     #[error("Invalid password")]
     InvalidPassword,
 
+    #[error("Confirm your identity to continue.")]
+    VerificationRequired,
+
+    #[error("This device's session was revoked from another device.")]
+    SessionRevoked,
+
+    #[error("This vault was created by a newer version of Pulsar (schema {vault_version}, this version supports up to {supported_version}). Update Pulsar to open it.")]
+    VaultTooNew {
+        vault_version: i64,
+        supported_version: i64,
+    },
+
     #[error("TOTP error: {0}")]
     Totp(String),
 
@@ -45,13 +77,22 @@ impl Error {
         match self {
             Error::Database(_) => "Database",
             Error::Io(_) => "Io",
+            Error::ReadOnlyStorage(_) => "ReadOnlyStorage",
             Error::Encryption(_) => "Encryption",
             Error::Decryption(_) => "Decryption",
             Error::Validation(_) => "Validation",
+            Error::InputTooLarge(_) => "InputTooLarge",
+            Error::Network(_) => "Network",
             Error::Internal(_) => "Internal",
+            Error::ItemReadOnly { .. } => "ItemReadOnly",
+            Error::PasswordPolicy { .. } => "PasswordPolicy",
             Error::VaultLocked => "VaultLocked",
             Error::VaultNotLoaded => "VaultNotLoaded",
+            Error::VaultModifiedExternally => "VaultModifiedExternally",
             Error::InvalidPassword => "InvalidPassword",
+            Error::VerificationRequired => "VerificationRequired",
+            Error::SessionRevoked => "SessionRevoked",
+            Error::VaultTooNew { .. } => "VaultTooNew",
             Error::Totp(_) => "Totp",
             Error::Serialization(_) => "Serialization",
             Error::Tauri(_) => "Tauri",
@@ -59,14 +100,46 @@ impl Error {
     }
 }
 
+// Write failures caused by the storage itself are surfaced as
+// `ReadOnlyStorage` so the UI can switch to read-only mode instead of showing
+// a raw database or IO error mid-edit.
+impl From<sqlx::Error> for Error {
+    fn from(err: sqlx::Error) -> Self {
+        match crate::storage::read_only_cause_sqlx(&err) {
+            Some(cause) => {
+                crate::storage::mark_read_only(&cause);
+                Error::ReadOnlyStorage(cause)
+            }
+            None => Error::Database(err),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        match crate::storage::read_only_cause_io(&err) {
+            Some(cause) => Error::ReadOnlyStorage(cause),
+            None => Error::Io(err),
+        }
+    }
+}
+
 impl Serialize for Error {
     fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("Error", 2)?;
+        let violations = match self {
+            Error::PasswordPolicy { violations } => Some(violations),
+            _ => None,
+        };
+        let mut state =
+            serializer.serialize_struct("Error", if violations.is_some() { 3 } else { 2 })?;
         state.serialize_field("code", self.code())?;
         state.serialize_field("message", &self.to_string())?;
+        if let Some(violations) = violations {
+            state.serialize_field("violations", violations)?;
+        }
         state.end()
     }
 }
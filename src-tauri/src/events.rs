@@ -0,0 +1,68 @@
+//! Change events for the frontend, emitted after a change is committed so
+//! views can update in place instead of re-fetching everything.
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+pub const ITEM_CREATED_EVENT: &str = "item:created";
+pub const ITEM_UPDATED_EVENT: &str = "item:updated";
+pub const ITEM_DELETED_EVENT: &str = "item:deleted";
+pub const TAG_CHANGED_EVENT: &str = "tag:changed";
+pub const VAULT_LOCKED_EVENT: &str = "vault:locked";
+pub const BREACHES_DETECTED_EVENT: &str = "breach:detected";
+pub const AUTOTYPE_UNLOCK_REQUIRED_EVENT: &str = "autotype:unlock-required";
+pub const AUTOTYPE_CHOOSER_EVENT: &str = "autotype:chooser";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemsChanged {
+    pub ids: Vec<i64>,
+}
+
+/// `id` is `None` when several tags may have changed.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagChanged {
+    pub id: Option<i64>,
+}
+
+fn emit_items(app: &AppHandle, event: &str, ids: Vec<i64>) {
+    if !ids.is_empty() {
+        let _ = app.emit(event, ItemsChanged { ids });
+    }
+}
+
+pub fn items_created(app: &AppHandle, ids: Vec<i64>) {
+    emit_items(app, ITEM_CREATED_EVENT, ids);
+}
+
+pub fn items_updated(app: &AppHandle, ids: Vec<i64>) {
+    emit_items(app, ITEM_UPDATED_EVENT, ids);
+}
+
+pub fn items_deleted(app: &AppHandle, ids: Vec<i64>) {
+    emit_items(app, ITEM_DELETED_EVENT, ids);
+}
+
+pub fn tag_changed(app: &AppHandle, id: Option<i64>) {
+    let _ = app.emit(TAG_CHANGED_EVENT, TagChanged { id });
+}
+
+pub fn vault_locked(app: &AppHandle) {
+    let _ = app.emit(VAULT_LOCKED_EVENT, ());
+}
+
+/// Items found in a breach they were not known to be in before.
+pub fn breaches_detected(app: &AppHandle, ids: Vec<i64>) {
+    emit_items(app, BREACHES_DETECTED_EVENT, ids);
+}
+
+/// The auto-type hotkey was pressed while the vault is locked.
+pub fn autotype_unlock_required(app: &AppHandle) {
+    let _ = app.emit(AUTOTYPE_UNLOCK_REQUIRED_EVENT, ());
+}
+
+/// Several items match the focused window; the user picks one.
+pub fn autotype_chooser(app: &AppHandle, candidates: crate::autotype::AutotypeCandidates) {
+    let _ = app.emit(AUTOTYPE_CHOOSER_EVENT, candidates);
+}
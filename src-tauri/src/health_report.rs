@@ -0,0 +1,379 @@
+//! Optional monthly vault health summary, written to a file or mailed through
+//! a user-configured SMTP server. The summary only carries aggregate numbers
+//! (score, counts, backup status) and never item names or secrets.
+
+use crate::db::utils::UnlockedVault;
+use crate::encryption::{decrypt, encrypt};
+use crate::error::{Error, Result};
+use crate::limits;
+use crate::state::AppState;
+use chrono::Utc;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use zeroize::Zeroizing;
+
+const CONFIG_KEY: &str = "health_report";
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_RECIPIENTS: usize = 20;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ReportDelivery {
+    /// Writes `pulsar-health-YYYY-MM.txt` into `directory`.
+    File { directory: String },
+    /// Sends the report by mail. `starttls` upgrades a plain connection
+    /// (usually port 587); otherwise implicit TLS is used (usually port 465).
+    Smtp {
+        host: String,
+        port: u16,
+        starttls: bool,
+        username: Option<String>,
+        from: String,
+        to: Vec<String>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthReportConfig {
+    pub enabled: bool,
+    pub delivery: ReportDelivery,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthReportStatus {
+    pub config: HealthReportConfig,
+    pub has_smtp_password: bool,
+    /// Month of the last delivered report, e.g. `2026-10`.
+    pub last_sent_month: Option<String>,
+}
+
+/// What is persisted, encrypted, in the vault's configuration table.
+#[derive(Serialize, Deserialize)]
+struct StoredHealthReport {
+    config: HealthReportConfig,
+    #[serde(default)]
+    smtp_password: Option<String>,
+    #[serde(default)]
+    last_sent_month: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct ReportSettings {
+    #[serde(default)]
+    backup: ReportBackupSettings,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct ReportBackupSettings {
+    #[serde(default)]
+    automatic_backups: bool,
+}
+
+fn current_month() -> String {
+    Utc::now().format("%Y-%m").to_string()
+}
+
+fn validate_config(config: &HealthReportConfig) -> Result<()> {
+    match &config.delivery {
+        ReportDelivery::File { directory } => {
+            limits::check_len("Report directory", directory, limits::MAX_SHORT_FIELD_LEN)?;
+            if directory.trim().is_empty() {
+                return Err(Error::Validation(
+                    "A report directory is required".to_string(),
+                ));
+            }
+        }
+        ReportDelivery::Smtp {
+            host,
+            port,
+            username,
+            from,
+            to,
+            ..
+        } => {
+            limits::check_len("SMTP host", host, limits::MAX_SHORT_FIELD_LEN)?;
+            limits::check_opt_len(
+                "SMTP username",
+                username.as_deref(),
+                limits::MAX_SHORT_FIELD_LEN,
+            )?;
+            limits::check_count("Recipients", to.len(), MAX_RECIPIENTS)?;
+            if host.trim().is_empty() || *port == 0 {
+                return Err(Error::Validation(
+                    "An SMTP host and port are required".to_string(),
+                ));
+            }
+            if to.is_empty() {
+                return Err(Error::Validation(
+                    "At least one recipient is required".to_string(),
+                ));
+            }
+            for address in std::iter::once(from).chain(to.iter()) {
+                limits::check_len("Email address", address, limits::MAX_TITLE_LEN)?;
+                address.parse::<Mailbox>().map_err(|e| {
+                    Error::Validation(format!("Invalid email address '{}': {}", address, e))
+                })?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn load_stored(pool: &SqlitePool, key: &[u8]) -> Result<Option<StoredHealthReport>> {
+    let value: Option<String> = sqlx::query_scalar("SELECT value FROM configuration WHERE key = ?")
+        .bind(CONFIG_KEY)
+        .fetch_optional(pool)
+        .await?;
+    match value {
+        Some(enc) => {
+            let json = Zeroizing::new(decrypt(&enc, key)?);
+            Ok(Some(serde_json::from_str(&json)?))
+        }
+        None => Ok(None),
+    }
+}
+
+async fn save_stored(pool: &SqlitePool, key: &[u8], stored: &StoredHealthReport) -> Result<()> {
+    let json = Zeroizing::new(serde_json::to_string(stored)?);
+    sqlx::query("INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)")
+        .bind(CONFIG_KEY)
+        .bind(encrypt(&json, key)?)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Time of the last vault export and whether automatic backups are on.
+pub(crate) async fn backup_status(
+    app: &AppHandle,
+    vault: &UnlockedVault,
+) -> Result<(Option<String>, bool)> {
+    let last_backup: Option<String> = sqlx::query_scalar(
+        "SELECT MAX(created_at) FROM activity_log WHERE event_type = 'vault_exported'",
+    )
+    .fetch_one(vault.pool())
+    .await?;
+    let automatic_backups = crate::settings::get_all_settings_internal(app)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str::<ReportSettings>(&json).ok())
+        .unwrap_or_default()
+        .backup
+        .automatic_backups;
+    Ok((last_backup, automatic_backups))
+}
+
+async fn render_report(app: &AppHandle, state: &AppState, vault: &UnlockedVault) -> Result<String> {
+    let snapshot = crate::security::current_security_snapshot(state, vault).await?;
+    let vault_name = crate::auth::load_vault_display_from_db(vault.pool())
+        .await?
+        .map(|d| d.name)
+        .filter(|n| !n.trim().is_empty());
+    let (last_backup, automatic_backups) = backup_status(app, vault).await?;
+
+    let mut report = format!("Pulsar vault health report for {}\n", current_month());
+    if let Some(name) = vault_name {
+        report.push_str(&format!("Vault: {}\n", name));
+    }
+    report.push_str(&format!("Generated: {}\n\n", Utc::now().to_rfc3339()));
+    report.push_str(&format!("Health score: {:.0}/100\n", snapshot.score));
+    report.push_str(&format!("Items: {}\n", snapshot.item_count));
+    report.push_str(&format!("Weak passwords: {}\n", snapshot.weak_count));
+    report.push_str(&format!("Reused passwords: {}\n", snapshot.reused_count));
+    report.push_str(&format!(
+        "Breached passwords: {}\n\n",
+        snapshot.breached_count
+    ));
+    report.push_str(&format!(
+        "Last backup: {}\n",
+        last_backup.as_deref().unwrap_or("never")
+    ));
+    report.push_str(&format!(
+        "Automatic backups: {}\n",
+        if automatic_backups { "on" } else { "off" }
+    ));
+    Ok(report)
+}
+
+async fn send_mail(
+    delivery: &ReportDelivery,
+    password: Option<&str>,
+    subject: &str,
+    body: String,
+) -> Result<String> {
+    let ReportDelivery::Smtp {
+        host,
+        port,
+        starttls,
+        username,
+        from,
+        to,
+    } = delivery
+    else {
+        return Err(Error::Internal("Not an SMTP delivery".to_string()));
+    };
+
+    let invalid = |e: lettre::address::AddressError| Error::Validation(e.to_string());
+    let mut builder = Message::builder()
+        .from(from.parse::<Mailbox>().map_err(invalid)?)
+        .subject(subject);
+    for recipient in to {
+        builder = builder.to(recipient.parse::<Mailbox>().map_err(invalid)?);
+    }
+    let message = builder
+        .body(body)
+        .map_err(|e| Error::Internal(format!("Failed to build report email: {}", e)))?;
+
+    let transport = if *starttls {
+        AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+    } else {
+        AsyncSmtpTransport::<Tokio1Executor>::relay(host)
+    }
+    .map_err(|e| Error::Internal(format!("Invalid SMTP server: {}", e)))?
+    .port(*port)
+    .timeout(Some(SMTP_TIMEOUT));
+    let transport = match (username, password) {
+        (Some(user), Some(pass)) => transport
+            .credentials(Credentials::new(user.clone(), pass.to_string()))
+            .build(),
+        _ => transport.build(),
+    };
+
+    transport
+        .send(message)
+        .await
+        .map_err(|e| Error::Internal(format!("Failed to send health report: {}", e)))?;
+    Ok(format!("Health report sent to {}", to.join(", ")))
+}
+
+async fn deliver(
+    app: &AppHandle,
+    state: &AppState,
+    vault: &UnlockedVault,
+    stored: &StoredHealthReport,
+) -> Result<String> {
+    let body = render_report(app, state, vault).await?;
+    let month = current_month();
+
+    let outcome = match &stored.config.delivery {
+        ReportDelivery::File { directory } => {
+            let path = PathBuf::from(directory).join(format!("pulsar-health-{}.txt", month));
+            crate::utils::write_sensitive_bytes(&path, body.as_bytes()).await?;
+            format!("Health report written to {}", path.display())
+        }
+        delivery @ ReportDelivery::Smtp { .. } => {
+            let subject = format!("Pulsar vault health report {}", month);
+            send_mail(delivery, stored.smtp_password.as_deref(), &subject, body).await?
+        }
+    };
+
+    let _ = crate::db::activity::log_activity_impl(
+        vault.pool(),
+        vault.key(),
+        "health_report_sent",
+        None,
+        None,
+        Some(&outcome),
+    )
+    .await;
+    Ok(outcome)
+}
+
+/// Sends the report if it is enabled and has not gone out this month.
+pub(crate) async fn run_if_due(app: &AppHandle) -> Result<()> {
+    if crate::storage::is_read_only() {
+        return Ok(());
+    }
+    let state = app.state::<AppState>();
+    let Ok(vault) = UnlockedVault::acquire(&state).await else {
+        return Ok(());
+    };
+
+    let Some(mut stored) = load_stored(vault.pool(), vault.key()).await? else {
+        return Ok(());
+    };
+    let month = current_month();
+    if !stored.config.enabled || stored.last_sent_month.as_deref() == Some(month.as_str()) {
+        return Ok(());
+    }
+
+    deliver(app, &state, &vault, &stored).await?;
+    stored.last_sent_month = Some(month);
+    crate::write_buffer::mark_dirty(&state).await;
+    save_stored(vault.pool(), vault.key(), &stored).await
+}
+
+#[tauri::command]
+pub async fn get_health_report_config(
+    state: State<'_, AppState>,
+) -> Result<Option<HealthReportStatus>> {
+    let vault = UnlockedVault::acquire(&state).await?;
+    Ok(load_stored(vault.pool(), vault.key())
+        .await?
+        .map(|stored| HealthReportStatus {
+            has_smtp_password: stored.smtp_password.is_some(),
+            config: stored.config,
+            last_sent_month: stored.last_sent_month,
+        }))
+}
+
+/// Saves the report settings. `smtp_password` is write-only: `None` keeps the
+/// stored password, an empty string removes it.
+#[tauri::command]
+pub async fn set_health_report_config(
+    state: State<'_, AppState>,
+    config: HealthReportConfig,
+    smtp_password: Option<String>,
+) -> Result<()> {
+    validate_config(&config)?;
+    limits::check_opt_len(
+        "SMTP password",
+        smtp_password.as_deref(),
+        limits::MAX_PASSWORD_LEN,
+    )?;
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+
+    let previous = load_stored(vault.pool(), vault.key()).await?;
+    let last_sent_month = previous.as_ref().and_then(|p| p.last_sent_month.clone());
+    let smtp_password = match smtp_password {
+        Some(p) if p.is_empty() => None,
+        Some(p) => Some(p),
+        None => previous.and_then(|p| p.smtp_password),
+    };
+    let smtp_password = match config.delivery {
+        ReportDelivery::Smtp { .. } => smtp_password,
+        ReportDelivery::File { .. } => None,
+    };
+
+    save_stored(
+        vault.pool(),
+        vault.key(),
+        &StoredHealthReport {
+            config,
+            smtp_password,
+            last_sent_month,
+        },
+    )
+    .await
+}
+
+/// Delivers a report right away, e.g. to test the SMTP settings. Does not
+/// count as this month's scheduled report.
+#[tauri::command]
+pub async fn send_health_report_now(app: AppHandle, state: State<'_, AppState>) -> Result<String> {
+    let vault = UnlockedVault::acquire(&state).await?;
+    let stored = load_stored(vault.pool(), vault.key())
+        .await?
+        .ok_or_else(|| Error::Validation("The health report is not configured".to_string()))?;
+    deliver(&app, &state, &vault, &stored).await
+}
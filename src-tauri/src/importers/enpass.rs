@@ -0,0 +1,328 @@
+//! Enpass JSON exports. Folders become tags, favorites are tagged as such and
+//! typed fields map onto Pulsar's custom field types.
+
+use super::{
+    digits_only, new_item, normalize_totp, parse_expiry, push_field, set_details, set_tags,
+    str_field, ParsedImport, FAVORITE_TAG,
+};
+use crate::error::{Error, Result};
+use crate::types::{CardDetails, ItemDetails, PasswordItem, SecretString};
+use serde_json::Value;
+use std::collections::HashMap;
+
+fn category_for(enpass_category: &str) -> &'static str {
+    match enpass_category {
+        "creditcard" => "card",
+        "identity" => "identity",
+        "note" => "note",
+        _ => "login",
+    }
+}
+
+fn field_type_for(enpass_type: &str, sensitive: bool) -> &'static str {
+    match enpass_type {
+        "email" => "email",
+        "phone" => "phone",
+        "date" | "ccExpiry" | "ccValidfrom" => "date",
+        "url" => "url",
+        "password" | "pin" | "ccCvc" | "ccPin" | "ccNumber" | "totp" => "password",
+        _ if sensitive => "password",
+        _ => "text",
+    }
+}
+
+fn is_set(value: &Value, key: &str) -> bool {
+    match value.get(key) {
+        Some(Value::Bool(b)) => *b,
+        Some(Value::Number(n)) => n.as_i64().unwrap_or(0) != 0,
+        _ => false,
+    }
+}
+
+fn map_item(raw: &Value, folders: &HashMap<&str, &str>) -> PasswordItem {
+    let category = str_field(raw, "category").unwrap_or("login");
+    let title = str_field(raw, "title").unwrap_or_default();
+    let mut item = new_item(category_for(category), title);
+
+    item.notes = str_field(raw, "note").map(|n| SecretString::new(n.to_string()));
+
+    let fields = raw
+        .get("fields")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    let is_login = item.category == "login";
+    let is_card = item.category == "card";
+    let mut fallback_email: Option<String> = None;
+    let mut card = CardDetails::default();
+    // Card fields held back until the typed details validate.
+    let mut card_fields = Vec::new();
+
+    for field in fields {
+        if is_set(field, "deleted") {
+            continue;
+        }
+        let kind = field.get("type").and_then(Value::as_str).unwrap_or("text");
+        if kind == "section" {
+            continue;
+        }
+        let Some(value) = str_field(field, "value") else {
+            continue;
+        };
+        let label = str_field(field, "label").unwrap_or(kind);
+
+        if is_card {
+            let consumed = match kind {
+                "ccName" if card.cardholder_name.is_none() => {
+                    card.cardholder_name = Some(value.to_string());
+                    true
+                }
+                "ccNumber" if card.number.is_none() => {
+                    card.number = Some(SecretString::new(digits_only(value)));
+                    true
+                }
+                "ccCvc" if card.security_code.is_none() => {
+                    card.security_code = Some(SecretString::new(digits_only(value)));
+                    true
+                }
+                "ccPin" if card.pin.is_none() => {
+                    card.pin = Some(SecretString::new(digits_only(value)));
+                    true
+                }
+                "ccType" if card.brand.is_none() => {
+                    card.brand = Some(value.to_string());
+                    true
+                }
+                "ccExpiry" if card.expiry_month.is_none() => match parse_expiry(value) {
+                    Some((month, year)) => {
+                        card.expiry_month = Some(month);
+                        card.expiry_year = Some(year);
+                        true
+                    }
+                    None => false,
+                },
+                _ => false,
+            };
+            if consumed {
+                card_fields.push((label, value, kind, is_set(field, "sensitive")));
+                continue;
+            }
+        }
+
+        match kind {
+            "username" if is_login && item.username.is_none() => {
+                item.username = Some(value.to_string());
+                continue;
+            }
+            "email" if is_login && fallback_email.is_none() => {
+                fallback_email = Some(value.to_string());
+            }
+            "password" if is_login && item.password.is_empty() => {
+                item.password = SecretString::new(value.to_string());
+                continue;
+            }
+            "url" if item.url.is_none() => {
+                item.url = Some(value.to_string());
+                continue;
+            }
+            "totp" if item.totp_secret.is_none() => {
+                if let Some(secret) = normalize_totp(value) {
+                    item.totp_secret = Some(SecretString::new(secret));
+                    continue;
+                }
+            }
+            _ => {}
+        }
+
+        push_field(
+            &mut item,
+            label,
+            value,
+            field_type_for(kind, is_set(field, "sensitive")),
+        );
+    }
+
+    if is_card && set_details(&mut item, ItemDetails::Card(card)).is_some() {
+        for (label, value, kind, sensitive) in card_fields {
+            push_field(&mut item, label, value, field_type_for(kind, sensitive));
+        }
+    }
+
+    // Logins without a username field use their email address instead.
+    if item.username.is_none() {
+        if let Some(email) = fallback_email {
+            item.custom_fields
+                .retain(|f| !(f.field_type == "email" && f.value == email));
+            item.username = Some(email);
+        }
+    }
+
+    let mut tags: Vec<String> = raw
+        .get("folders")
+        .and_then(Value::as_array)
+        .map(|ids| {
+            ids.iter()
+                .filter_map(Value::as_str)
+                .filter_map(|id| folders.get(id).map(|t| t.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    if is_set(raw, "favorite") {
+        tags.push(FAVORITE_TAG.to_string());
+    }
+    set_tags(&mut item, &tags);
+
+    item
+}
+
+pub fn parse(bytes: &[u8]) -> Result<ParsedImport> {
+    let root: Value = serde_json::from_slice(bytes)
+        .map_err(|_| Error::Validation("The file is not a valid Enpass JSON export".to_string()))?;
+
+    let items = root.get("items").and_then(Value::as_array).ok_or_else(|| {
+        Error::Validation("The file is not a valid Enpass JSON export".to_string())
+    })?;
+
+    let folders: HashMap<&str, &str> = root
+        .get("folders")
+        .and_then(Value::as_array)
+        .map(|folders| {
+            folders
+                .iter()
+                .filter_map(|f| Some((str_field(f, "uuid")?, str_field(f, "title")?)))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut parsed = ParsedImport::default();
+    let mut attachments_skipped = 0;
+    for raw in items {
+        if is_set(raw, "trashed") {
+            parsed.skipped += 1;
+            continue;
+        }
+        if let Some(attachments) = raw.get("attachments").and_then(Value::as_array) {
+            attachments_skipped += attachments.len();
+        }
+        parsed.items.push(map_item(raw, &folders));
+    }
+
+    if attachments_skipped > 0 {
+        parsed.warnings.push(format!(
+            "{} attachment(s) were not imported; add them to the items manually",
+            attachments_skipped
+        ));
+    }
+
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"{
+        "folders": [{ "uuid": "f1", "title": "Work" }],
+        "items": [
+            {
+                "title": "Mail",
+                "category": "login",
+                "favorite": 1,
+                "folders": ["f1", "missing"],
+                "note": "shared inbox",
+                "attachments": [{ "name": "a.pdf" }, { "name": "b.pdf" }],
+                "fields": [
+                    { "type": "section", "label": "Login", "value": "" },
+                    { "type": "email", "label": "E-mail", "value": "alice@example.com" },
+                    { "type": "password", "label": "Password", "value": "hunter2" },
+                    { "type": "url", "label": "Website", "value": "https://mail.example.com" },
+                    { "type": "totp", "label": "TOTP", "value": "JBSW Y3DP EHPK 3PXP" },
+                    { "type": "text", "label": "Security answer", "value": "blue", "sensitive": 1 },
+                    { "type": "text", "label": "Old", "value": "gone", "deleted": 1 }
+                ]
+            },
+            {
+                "title": "Visa",
+                "category": "creditcard",
+                "fields": [
+                    { "type": "ccName", "label": "Cardholder", "value": "Alice Example" },
+                    { "type": "ccNumber", "label": "Number", "value": "4242-4242-4242-4242" },
+                    { "type": "ccExpiry", "label": "Expiry", "value": "04/30" },
+                    { "type": "ccCvc", "label": "CVC", "value": "123" }
+                ]
+            },
+            { "title": "Deleted", "category": "login", "trashed": true }
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_export() {
+        let parsed = parse(EXPORT.as_bytes()).unwrap();
+        assert_eq!(parsed.items.len(), 2);
+        assert_eq!(parsed.skipped, 1);
+        assert_eq!(parsed.warnings.len(), 1);
+        assert!(parsed.warnings[0].starts_with("2 attachment(s)"));
+
+        let login = &parsed.items[0];
+        assert_eq!(login.category, "login");
+        // The email doubles as the username and is not kept twice.
+        assert_eq!(login.username.as_deref(), Some("alice@example.com"));
+        assert_eq!(login.password.as_str(), "hunter2");
+        assert_eq!(login.url.as_deref(), Some("https://mail.example.com"));
+        assert_eq!(
+            login.totp_secret.as_ref().map(|s| s.as_str()),
+            Some("JBSWY3DPEHPK3PXP")
+        );
+        assert_eq!(
+            login.notes.as_ref().map(|n| n.as_str()),
+            Some("shared inbox")
+        );
+        assert_eq!(login.tags.as_deref(), Some("Work, favorite"));
+        assert_eq!(login.custom_fields.len(), 1);
+        assert_eq!(login.custom_fields[0].name, "Security answer");
+        assert_eq!(login.custom_fields[0].field_type, "password");
+
+        let card = &parsed.items[1];
+        assert_eq!(card.category, "card");
+        let Some(ItemDetails::Card(details)) = &card.details else {
+            panic!("expected card details");
+        };
+        assert_eq!(details.cardholder_name.as_deref(), Some("Alice Example"));
+        assert_eq!(
+            details.number.as_ref().map(|n| n.as_str()),
+            Some("4242424242424242")
+        );
+        assert_eq!(
+            (details.expiry_month, details.expiry_year),
+            (Some(4), Some(2030))
+        );
+        assert!(card.custom_fields.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_card_details_fall_back_to_fields() {
+        let export = EXPORT.replace(r#""value": "123""#, r#""value": "12345""#);
+        let parsed = parse(export.as_bytes()).unwrap();
+        let card = &parsed.items[1];
+        assert!(card.details.is_none());
+        let names: Vec<&str> = card.custom_fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["Cardholder", "Number", "Expiry", "CVC"]);
+    }
+
+    #[test]
+    fn test_field_types() {
+        assert_eq!(field_type_for("ccNumber", false), "password");
+        assert_eq!(field_type_for("ccExpiry", false), "date");
+        assert_eq!(field_type_for("text", true), "password");
+        assert_eq!(field_type_for("text", false), "text");
+        assert_eq!(category_for("creditcard"), "card");
+        assert_eq!(category_for("travel"), "login");
+    }
+
+    #[test]
+    fn test_rejects_invalid_exports() {
+        assert!(parse(b"{}").is_err());
+        assert!(parse(b"not json").is_err());
+    }
+}
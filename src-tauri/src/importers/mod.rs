@@ -0,0 +1,370 @@
+mod enpass;
+mod proton_pass;
+
+use crate::db::utils::{CryptoHelper, UnlockedVault};
+use crate::error::{Error, Result};
+use crate::limits;
+use crate::state::AppState;
+use crate::types::{CustomField, ItemDetails, PasswordItem, SecretString};
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::Path;
+use tauri::State;
+
+pub const FAVORITE_TAG: &str = "favorite";
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    pub source: String,
+    pub imported: usize,
+    pub skipped: usize,
+    pub tags_created: usize,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct ParsedImport {
+    pub items: Vec<PasswordItem>,
+    pub skipped: usize,
+    pub warnings: Vec<String>,
+}
+
+pub fn new_item(category: &str, title: &str) -> PasswordItem {
+    let title = title.trim();
+    PasswordItem {
+        id: 0,
+        category: category.to_string(),
+        title: if title.is_empty() {
+            "Untitled".to_string()
+        } else {
+            title.to_string()
+        },
+        description: None,
+        img: None,
+        tags: None,
+        username: None,
+        url: None,
+        notes: None,
+        password: SecretString::default(),
+        created_at: String::new(),
+        updated_at: String::new(),
+        color: None,
+        totp_secret: None,
+        custom_fields: Vec::new(),
+        field_order: None,
+        attachments: None,
+        autotype_patterns: None,
+        details: None,
+        clear_details: false,
+        read_only: false,
+    }
+}
+
+pub fn push_field(item: &mut PasswordItem, name: &str, value: &str, field_type: &str) {
+    let value = value.trim();
+    if value.is_empty() {
+        return;
+    }
+
+    // Custom field names are used as identifiers by the UI, keep them unique.
+    let mut unique = name.trim().to_string();
+    let mut n = 2;
+    while item.custom_fields.iter().any(|f| f.name == unique) {
+        unique = format!("{} {}", name.trim(), n);
+        n += 1;
+    }
+
+    item.custom_fields.push(CustomField {
+        name: unique,
+        value: value.to_string(),
+        field_type: field_type.to_string(),
+    });
+}
+
+pub fn set_tags(item: &mut PasswordItem, tags: &[String]) {
+    let tags: Vec<String> = tags
+        .iter()
+        .map(|t| t.replace(',', " ").trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect();
+    item.tags = (!tags.is_empty()).then(|| tags.join(", "));
+}
+
+/// Accepts either a bare base32 secret or an `otpauth://` URI and returns the
+/// normalized base32 secret Pulsar stores.
+pub fn normalize_totp(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    let secret = if raw.to_lowercase().starts_with("otpauth://") {
+        let query = raw.split_once('?')?.1;
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(k, _)| k.eq_ignore_ascii_case("secret"))
+            .map(|(_, v)| v.replace("%3D", "="))?
+    } else {
+        raw.to_string()
+    };
+
+    let secret: String = secret
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect::<String>()
+        .trim_end_matches('=')
+        .to_uppercase();
+
+    let valid = !secret.is_empty()
+        && secret
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || ('2'..='7').contains(&c));
+    valid.then_some(secret)
+}
+
+/// Attaches typed details when they validate, otherwise returns them so the
+/// caller can fall back to custom fields.
+pub fn set_details(item: &mut PasswordItem, details: ItemDetails) -> Option<ItemDetails> {
+    match crate::db::validation::validate_item_details(&item.category, &details) {
+        Ok(()) => {
+            item.details = Some(details);
+            None
+        }
+        Err(_) => Some(details),
+    }
+}
+
+/// Strips separators from card numbers and similar digit strings.
+pub fn digits_only(value: &str) -> String {
+    value.chars().filter(char::is_ascii_digit).collect()
+}
+
+/// Parses `MM/YY`, `MM/YYYY`, `YYYY-MM` and `MMYYYY` expiry dates.
+pub fn parse_expiry(value: &str) -> Option<(u8, u16)> {
+    let value = value.trim();
+    let (month, year) = if let Some((a, b)) = value.split_once(['/', '-']) {
+        if a.len() == 4 {
+            (b, a)
+        } else {
+            (a, b)
+        }
+    } else if value.len() == 6 {
+        value.split_at(2)
+    } else {
+        return None;
+    };
+
+    let month: u8 = month.trim().parse().ok()?;
+    let year: u16 = year.trim().parse().ok()?;
+    let year = if year < 100 { 2000 + year } else { year };
+    (1..=12).contains(&month).then_some((month, year))
+}
+
+/// Returns the trimmed string at `key` when it is present and non-empty.
+pub fn str_field<'a>(value: &'a Value, key: &str) -> Option<&'a str> {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+}
+
+/// Turns `cardholderName` or `phone_number` into "Cardholder name" / "Phone number".
+pub fn humanize_key(key: &str) -> String {
+    let mut words = String::new();
+    for (i, c) in key.chars().enumerate() {
+        if c == '_' || c == '-' {
+            words.push(' ');
+        } else if c.is_uppercase() && i > 0 {
+            words.push(' ');
+            words.extend(c.to_lowercase());
+        } else if i == 0 {
+            words.extend(c.to_uppercase());
+        } else {
+            words.push(c);
+        }
+    }
+    words
+}
+
+async fn ensure_tags(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    helper: &CryptoHelper,
+    key: &[u8],
+    names: &BTreeSet<String>,
+) -> Result<usize> {
+    let existing = crate::db::buttons::get_buttons_impl(tx.as_mut(), key).await?;
+    let mut created = 0;
+
+    for name in names {
+        if existing.iter().any(|b| &b.text == name) {
+            continue;
+        }
+        sqlx::query("INSERT INTO buttons (text, icon, color) VALUES (?, ?, ?)")
+            .bind(helper.encrypt(name)?)
+            .bind(helper.encrypt("")?)
+            .bind(helper.encrypt("")?)
+            .execute(tx.as_mut())
+            .await?;
+        created += 1;
+    }
+
+    Ok(created)
+}
+
+fn parse_export(manager: &str, bytes: &[u8]) -> Result<ParsedImport> {
+    match manager.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+        "protonpass" => proton_pass::parse(bytes),
+        "enpass" => enpass::parse(bytes),
+        other => Err(Error::Validation(format!(
+            "Unsupported password manager '{}'",
+            other
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn import_from_password_manager(
+    state: State<'_, AppState>,
+    manager: String,
+    path: String,
+) -> Result<ImportSummary> {
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
+
+    let path = Path::new(&path);
+    limits::check_file_size("Import file", path, limits::MAX_IMPORT_FILE_SIZE).await?;
+    let bytes = zeroize::Zeroizing::new(tokio::fs::read(path).await?);
+
+    let mut parsed = parse_export(&manager, &bytes)?;
+    drop(bytes);
+
+    let mut items = Vec::with_capacity(parsed.items.len());
+    for item in parsed.items.drain(..) {
+        match limits::check_password_item(&item) {
+            Ok(()) => items.push(item),
+            Err(e) => {
+                parsed.skipped += 1;
+                parsed
+                    .warnings
+                    .push(format!("Skipped '{}': {}", item.title, e));
+            }
+        }
+    }
+
+    let tag_names: BTreeSet<String> = items
+        .iter()
+        .filter_map(|i| i.tags.as_deref())
+        .flat_map(|t| t.split(','))
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect();
+
+    let now = Utc::now().to_rfc3339();
+    let mut tx = vault.pool().begin().await?;
+    let tags_created = ensure_tags(&mut tx, &helper, vault.key(), &tag_names).await?;
+    for item in &items {
+        crate::db::insert_password_item(&mut tx, &helper, vault.key(), item, &now).await?;
+    }
+
+    let _ = crate::db::activity::log_activity_impl(
+        tx.as_mut(),
+        vault.key(),
+        "vault_imported",
+        None,
+        None,
+        Some(&format!("Imported {} items from {}", items.len(), manager)),
+    )
+    .await;
+    tx.commit().await?;
+
+    Ok(ImportSummary {
+        source: manager,
+        imported: items.len(),
+        skipped: parsed.skipped,
+        tags_created,
+        warnings: parsed.warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_totp() {
+        assert_eq!(
+            normalize_totp(" jbsw y3dp-ehpk 3pxp ").as_deref(),
+            Some("JBSWY3DPEHPK3PXP")
+        );
+        assert_eq!(
+            normalize_totp(
+                "otpauth://totp/Example:alice?Secret=JBSWY3DPEHPK3PXP%3D%3D&issuer=Example"
+            )
+            .as_deref(),
+            Some("JBSWY3DPEHPK3PXP")
+        );
+        assert_eq!(
+            normalize_totp("otpauth://totp/Example:alice?issuer=Example"),
+            None
+        );
+        assert_eq!(normalize_totp("not a secret!"), None);
+        assert_eq!(normalize_totp("JBSWY3DPEHPK3PX1"), None);
+        assert_eq!(normalize_totp(""), None);
+    }
+
+    #[test]
+    fn test_parse_expiry() {
+        assert_eq!(parse_expiry("04/27"), Some((4, 2027)));
+        assert_eq!(parse_expiry("4/2027"), Some((4, 2027)));
+        assert_eq!(parse_expiry("2027-04"), Some((4, 2027)));
+        assert_eq!(parse_expiry("042027"), Some((4, 2027)));
+        assert_eq!(parse_expiry("13/27"), None);
+        assert_eq!(parse_expiry("April 2027"), None);
+    }
+
+    #[test]
+    fn test_humanize_key() {
+        assert_eq!(humanize_key("cardholderName"), "Cardholder name");
+        assert_eq!(humanize_key("phone_number"), "Phone number");
+        assert_eq!(humanize_key("pin"), "Pin");
+    }
+
+    #[test]
+    fn test_push_field_keeps_names_unique_and_skips_empty_values() {
+        let mut item = new_item("login", "  ");
+        assert_eq!(item.title, "Untitled");
+        push_field(&mut item, "URL", "https://a.example", "url");
+        push_field(&mut item, "URL", "https://b.example", "url");
+        push_field(&mut item, "URL", "   ", "url");
+        push_field(&mut item, "URL", "https://c.example", "url");
+        let names: Vec<&str> = item.custom_fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["URL", "URL 2", "URL 3"]);
+    }
+
+    #[test]
+    fn test_set_tags() {
+        let mut item = new_item("login", "Example");
+        set_tags(
+            &mut item,
+            &[
+                "Work, Personal".to_string(),
+                " ".to_string(),
+                FAVORITE_TAG.to_string(),
+            ],
+        );
+        assert_eq!(item.tags.as_deref(), Some("Work  Personal, favorite"));
+        set_tags(&mut item, &[]);
+        assert_eq!(item.tags, None);
+    }
+
+    #[test]
+    fn test_parse_export_picks_the_importer() {
+        assert!(parse_export("Proton-Pass", br#"{"vaults": {}}"#).is_ok());
+        assert!(parse_export("enpass", br#"{"items": []}"#).is_ok());
+        assert!(matches!(
+            parse_export("lastpass", b"{}"),
+            Err(Error::Validation(_))
+        ));
+    }
+}
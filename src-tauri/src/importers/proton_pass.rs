@@ -0,0 +1,487 @@
+//! Proton Pass exports: either a zip containing `Proton Pass/data.json` or the
+//! bare JSON. Vaults become tags, pinned items are tagged as favorites.
+
+use super::{
+    digits_only, humanize_key, new_item, normalize_totp, parse_expiry, push_field, set_details,
+    set_tags, str_field, ParsedImport, FAVORITE_TAG,
+};
+use crate::error::{Error, Result};
+use crate::types::{
+    CardDetails, IdentityDetails, ItemDetails, PasswordItem, PostalAddress, SecretString,
+};
+use serde_json::Value;
+use std::io::{Cursor, Read};
+
+const ITEM_STATE_TRASHED: i64 = 2;
+
+fn read_data_json(bytes: &[u8]) -> Result<Vec<u8>> {
+    if bytes.starts_with(b"-----BEGIN PGP") {
+        return Err(Error::Validation(
+            "Encrypted Proton Pass exports are not supported. Export without encryption and try again."
+                .to_string(),
+        ));
+    }
+
+    if !bytes.starts_with(b"PK\x03\x04") {
+        return Ok(bytes.to_vec());
+    }
+
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| Error::Validation(format!("Invalid Proton Pass archive: {e}")))?;
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| Error::Validation(format!("Invalid Proton Pass archive: {e}")))?;
+        if entry.name().ends_with("data.json") {
+            let limit = crate::limits::MAX_IMPORT_FILE_SIZE;
+            if entry.size() > limit {
+                return Err(Error::InputTooLarge(format!(
+                    "Proton Pass data exceeds the limit of {} bytes",
+                    limit
+                )));
+            }
+            let mut data = Vec::with_capacity(entry.size() as usize);
+            entry.by_ref().take(limit).read_to_end(&mut data)?;
+            return Ok(data);
+        }
+    }
+
+    Err(Error::Validation(
+        "The archive does not contain a Proton Pass data.json file".to_string(),
+    ))
+}
+
+fn map_extra_fields(item: &mut PasswordItem, fields: Option<&Value>) {
+    let Some(fields) = fields.and_then(Value::as_array) else {
+        return;
+    };
+
+    for field in fields {
+        let name = str_field(field, "fieldName").unwrap_or("Field");
+        let content = field
+            .get("data")
+            .and_then(|d| str_field(d, "content"))
+            .unwrap_or_default();
+
+        match field.get("type").and_then(Value::as_str).unwrap_or("text") {
+            "totp" if item.totp_secret.is_none() => match normalize_totp(content) {
+                Some(secret) => item.totp_secret = Some(SecretString::new(secret)),
+                None => push_field(item, name, content, "text"),
+            },
+            "hidden" => push_field(item, name, content, "password"),
+            _ => push_field(item, name, content, "text"),
+        }
+    }
+}
+
+fn map_login(item: &mut PasswordItem, content: &Value) {
+    let username = str_field(content, "itemUsername").or_else(|| str_field(content, "username"));
+    let email = str_field(content, "itemEmail");
+
+    match (username, email) {
+        (Some(u), Some(e)) => {
+            item.username = Some(u.to_string());
+            if u != e {
+                push_field(item, "Email", e, "email");
+            }
+        }
+        (Some(u), None) => item.username = Some(u.to_string()),
+        (None, Some(e)) => item.username = Some(e.to_string()),
+        (None, None) => {}
+    }
+
+    if let Some(password) = str_field(content, "password") {
+        item.password = SecretString::new(password.to_string());
+    }
+
+    let urls: Vec<&str> = content
+        .get("urls")
+        .and_then(Value::as_array)
+        .map(|urls| urls.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    if let Some((first, rest)) = urls.split_first() {
+        item.url = Some(first.to_string());
+        for url in rest {
+            push_field(item, "URL", url, "url");
+        }
+    }
+
+    if let Some(totp) = str_field(content, "totpUri") {
+        match normalize_totp(totp) {
+            Some(secret) => item.totp_secret = Some(SecretString::new(secret)),
+            None => push_field(item, "TOTP", totp, "text"),
+        }
+    }
+}
+
+fn map_credit_card(item: &mut PasswordItem, content: &Value) {
+    let secret = |key: &str| str_field(content, key).map(|v| SecretString::new(digits_only(v)));
+    let expiry = str_field(content, "expirationDate").and_then(parse_expiry);
+    let card = CardDetails {
+        cardholder_name: str_field(content, "cardholderName").map(str::to_string),
+        number: secret("number"),
+        expiry_month: expiry.map(|(m, _)| m),
+        expiry_year: expiry.map(|(_, y)| y),
+        security_code: secret("verificationNumber"),
+        pin: secret("pin"),
+        ..Default::default()
+    };
+    if set_details(item, ItemDetails::Card(card)).is_none() {
+        return;
+    }
+
+    const FIELDS: &[(&str, &str, &str)] = &[
+        ("cardholderName", "Cardholder name", "text"),
+        ("number", "Card number", "password"),
+        ("expirationDate", "Expiration date", "text"),
+        ("verificationNumber", "Security code", "password"),
+        ("pin", "PIN", "password"),
+    ];
+    for (key, label, field_type) in FIELDS {
+        if let Some(value) = str_field(content, key) {
+            push_field(item, label, value, field_type);
+        }
+    }
+}
+
+/// Identity keys that have a typed counterpart in `IdentityDetails`.
+const IDENTITY_KEYS: &[&str] = &[
+    "firstName",
+    "middleName",
+    "lastName",
+    "email",
+    "phoneNumber",
+    "birthdate",
+    "company",
+    "jobTitle",
+    "streetAddress",
+    "city",
+    "stateOrProvince",
+    "zipOrPostalCode",
+    "countryOrRegion",
+    "socialSecurityNumber",
+    "passportNumber",
+    "licenseNumber",
+];
+
+fn map_identity(item: &mut PasswordItem, content: &Value) {
+    let text = |key: &str| str_field(content, key).map(str::to_string);
+    let secret = |key: &str| str_field(content, key).map(|v| SecretString::new(v.to_string()));
+
+    let address = PostalAddress {
+        street: text("streetAddress"),
+        city: text("city"),
+        region: text("stateOrProvince"),
+        postal_code: text("zipOrPostalCode"),
+        country: text("countryOrRegion"),
+    };
+    let has_address = address.street.is_some()
+        || address.city.is_some()
+        || address.region.is_some()
+        || address.postal_code.is_some()
+        || address.country.is_some();
+
+    let identity = IdentityDetails {
+        first_name: text("firstName"),
+        middle_name: text("middleName"),
+        last_name: text("lastName"),
+        email: text("email"),
+        phone: text("phoneNumber"),
+        birth_date: text("birthdate"),
+        company: text("company"),
+        job_title: text("jobTitle"),
+        address: has_address.then_some(address),
+        national_id: secret("socialSecurityNumber"),
+        passport_number: secret("passportNumber"),
+        license_number: secret("licenseNumber"),
+        ..Default::default()
+    };
+
+    let skip = if set_details(item, ItemDetails::Identity(identity)).is_none() {
+        IDENTITY_KEYS
+    } else {
+        &[]
+    };
+    map_generic(item, content, skip);
+}
+
+/// Maps every non-empty string in `content` (except `skip`) to a custom
+/// field, plus the nested `extra*` sections identity items carry.
+fn map_generic(item: &mut PasswordItem, content: &Value, skip: &[&str]) {
+    let Some(object) = content.as_object() else {
+        return;
+    };
+
+    for (key, value) in object {
+        if skip.contains(&key.as_str()) {
+            continue;
+        }
+        match value {
+            Value::String(s) => {
+                let lower = key.to_lowercase();
+                let field_type = if lower.contains("email") {
+                    "email"
+                } else if lower.contains("phone") {
+                    "phone"
+                } else if lower.contains("address") || lower == "city" || lower == "zipcode" {
+                    "address"
+                } else if lower.contains("birth") || lower.contains("date") {
+                    "date"
+                } else if lower.contains("website") || lower.contains("url") {
+                    "url"
+                } else {
+                    "text"
+                };
+                push_field(item, &humanize_key(key), s, field_type);
+            }
+            Value::Array(entries) if key.starts_with("extra") => {
+                for entry in entries {
+                    if entry.get("fieldName").is_some() {
+                        map_extra_fields(item, Some(&Value::Array(vec![entry.clone()])));
+                    } else {
+                        map_extra_fields(item, entry.get("sectionFields"));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn map_item(raw: &Value, vault_name: Option<&str>) -> Option<PasswordItem> {
+    let data = raw.get("data")?;
+    let metadata = data.get("metadata");
+    let title = metadata
+        .and_then(|m| str_field(m, "name"))
+        .unwrap_or_default();
+    let content = data.get("content").cloned().unwrap_or(Value::Null);
+    let kind = data.get("type").and_then(Value::as_str).unwrap_or("login");
+
+    let mut item = match kind {
+        "login" => {
+            let mut item = new_item("login", title);
+            map_login(&mut item, &content);
+            item
+        }
+        "alias" => {
+            let mut item = new_item("login", title);
+            item.username = str_field(raw, "aliasEmail").map(str::to_string);
+            item
+        }
+        "note" => new_item("note", title),
+        "creditCard" => {
+            let mut item = new_item("card", title);
+            map_credit_card(&mut item, &content);
+            item
+        }
+        "identity" => {
+            let mut item = new_item("identity", title);
+            map_identity(&mut item, &content);
+            item
+        }
+        _ => {
+            let mut item = new_item("note", title);
+            map_generic(&mut item, &content, &[]);
+            item
+        }
+    };
+
+    item.notes = metadata
+        .and_then(|m| str_field(m, "note"))
+        .map(|n| SecretString::new(n.to_string()));
+    map_extra_fields(&mut item, data.get("extraFields"));
+
+    let mut tags = Vec::new();
+    if let Some(vault) = vault_name {
+        tags.push(vault.to_string());
+    }
+    if raw.get("pinned").and_then(Value::as_bool).unwrap_or(false) {
+        tags.push(FAVORITE_TAG.to_string());
+    }
+    set_tags(&mut item, &tags);
+
+    Some(item)
+}
+
+pub fn parse(bytes: &[u8]) -> Result<ParsedImport> {
+    let data = read_data_json(bytes)?;
+    let root: Value = serde_json::from_slice(&data)
+        .map_err(|_| Error::Validation("The file is not a valid Proton Pass export".to_string()))?;
+
+    if root.get("encrypted").and_then(Value::as_bool) == Some(true) {
+        return Err(Error::Validation(
+            "Encrypted Proton Pass exports are not supported. Export without encryption and try again."
+                .to_string(),
+        ));
+    }
+
+    let vaults = root
+        .get("vaults")
+        .and_then(Value::as_object)
+        .ok_or_else(|| {
+            Error::Validation("The file is not a valid Proton Pass export".to_string())
+        })?;
+
+    let mut parsed = ParsedImport::default();
+    for vault in vaults.values() {
+        let vault_name = str_field(vault, "name");
+        let items = vault
+            .get("items")
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        for raw in items {
+            if raw.get("state").and_then(Value::as_i64) == Some(ITEM_STATE_TRASHED) {
+                parsed.skipped += 1;
+                continue;
+            }
+            match map_item(raw, vault_name) {
+                Some(item) => parsed.items.push(item),
+                None => {
+                    parsed.skipped += 1;
+                    parsed
+                        .warnings
+                        .push("Skipped an item without data".to_string());
+                }
+            }
+        }
+    }
+
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const EXPORT: &str = r#"{
+        "encrypted": false,
+        "vaults": {
+            "v1": {
+                "name": "Personal",
+                "items": [
+                    {
+                        "pinned": true,
+                        "state": 1,
+                        "data": {
+                            "type": "login",
+                            "metadata": { "name": "GitHub", "note": "work account" },
+                            "content": {
+                                "itemUsername": "alice",
+                                "itemEmail": "alice@example.com",
+                                "password": "hunter2",
+                                "urls": ["https://github.com", "https://gist.github.com"],
+                                "totpUri": "otpauth://totp/GitHub:alice?secret=JBSWY3DPEHPK3PXP"
+                            },
+                            "extraFields": [
+                                { "fieldName": "Recovery", "type": "hidden", "data": { "content": "abc-123" } }
+                            ]
+                        }
+                    },
+                    {
+                        "state": 2,
+                        "data": { "type": "note", "metadata": { "name": "Old" }, "content": {} }
+                    },
+                    {
+                        "state": 1,
+                        "data": {
+                            "type": "creditCard",
+                            "metadata": { "name": "Visa" },
+                            "content": {
+                                "cardholderName": "Alice Example",
+                                "number": "4242 4242 4242 4242",
+                                "expirationDate": "2030-04",
+                                "verificationNumber": "123"
+                            }
+                        }
+                    },
+                    { "state": 1 }
+                ]
+            }
+        }
+    }"#;
+
+    fn field<'a>(item: &'a PasswordItem, name: &str) -> Option<&'a str> {
+        item.custom_fields
+            .iter()
+            .find(|f| f.name == name)
+            .map(|f| f.value.as_str())
+    }
+
+    #[test]
+    fn test_parse_export() {
+        let parsed = parse(EXPORT.as_bytes()).unwrap();
+        assert_eq!(parsed.items.len(), 2);
+        assert_eq!(parsed.skipped, 2);
+
+        let login = &parsed.items[0];
+        assert_eq!(login.category, "login");
+        assert_eq!(login.username.as_deref(), Some("alice"));
+        assert_eq!(login.password.as_str(), "hunter2");
+        assert_eq!(login.url.as_deref(), Some("https://github.com"));
+        assert_eq!(
+            login.totp_secret.as_ref().map(|s| s.as_str()),
+            Some("JBSWY3DPEHPK3PXP")
+        );
+        assert_eq!(
+            login.notes.as_ref().map(|n| n.as_str()),
+            Some("work account")
+        );
+        assert_eq!(login.tags.as_deref(), Some("Personal, favorite"));
+        assert_eq!(field(login, "Email"), Some("alice@example.com"));
+        assert_eq!(field(login, "URL"), Some("https://gist.github.com"));
+        assert_eq!(field(login, "Recovery"), Some("abc-123"));
+
+        let card = &parsed.items[1];
+        let Some(ItemDetails::Card(details)) = &card.details else {
+            panic!("expected card details");
+        };
+        assert_eq!(
+            details.number.as_ref().map(|n| n.as_str()),
+            Some("4242424242424242")
+        );
+        assert_eq!(
+            (details.expiry_month, details.expiry_year),
+            (Some(4), Some(2030))
+        );
+        assert!(card.custom_fields.is_empty());
+        assert_eq!(card.tags.as_deref(), Some("Personal"));
+    }
+
+    #[test]
+    fn test_invalid_card_details_fall_back_to_fields() {
+        let export = EXPORT.replace(
+            r#""verificationNumber": "123""#,
+            r#""verificationNumber": "12""#,
+        );
+        let parsed = parse(export.as_bytes()).unwrap();
+        let card = &parsed.items[1];
+        assert!(card.details.is_none());
+        assert_eq!(field(card, "Card number"), Some("4242 4242 4242 4242"));
+        assert_eq!(field(card, "Security code"), Some("12"));
+    }
+
+    #[test]
+    fn test_reads_data_json_from_the_archive() {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file(
+            "Proton Pass/data.json",
+            zip::write::SimpleFileOptions::default(),
+        )
+        .unwrap();
+        zip.write_all(EXPORT.as_bytes()).unwrap();
+        let archive = zip.finish().unwrap().into_inner();
+
+        assert_eq!(parse(&archive).unwrap().items.len(), 2);
+    }
+
+    #[test]
+    fn test_rejects_encrypted_and_invalid_exports() {
+        assert!(parse(b"-----BEGIN PGP MESSAGE-----").is_err());
+        assert!(parse(br#"{"encrypted": true, "vaults": {}}"#).is_err());
+        assert!(parse(b"[]").is_err());
+        assert!(parse(b"not json").is_err());
+    }
+}
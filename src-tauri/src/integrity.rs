@@ -0,0 +1,381 @@
+//! Startup self-check of the running executable.
+//!
+//! On macOS and Windows releases are code-signed by the bundler, which
+//! rewrites the executable, so the operating system's signature check is the
+//! integrity check there.
+//!
+//! Elsewhere release builds carry an embedded manifest: a 16-byte marker
+//! followed by a 32-byte SHA-256 digest slot. After linking,
+//! `scripts/stamp-integrity.mjs` (the `beforeBundleCommand` of `tauri build`)
+//! hashes the executable with the digest slot zeroed and writes the result
+//! into the slot. At runtime the same computation is repeated on the file on
+//! disk; a mismatch means the binary was modified after it was stamped.
+//! Unsigned or unstamped (development) builds report `unverified` instead of
+//! failing.
+
+use crate::error::Result;
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IntegrityStatus {
+    Verified,
+    Tampered,
+    Unverified,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BinaryIntegrityReport {
+    pub status: IntegrityStatus,
+    pub binary_path: Option<String>,
+    pub sha256: Option<String>,
+    pub expected_sha256: Option<String>,
+    pub standard_location: bool,
+    pub warnings: Vec<String>,
+    pub checked_at: String,
+}
+
+#[derive(Deserialize, Default)]
+struct IntegritySettings {
+    #[serde(default)]
+    security: IntegritySecuritySettings,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IntegritySecuritySettings {
+    #[serde(default = "default_true")]
+    binary_integrity_check: bool,
+}
+
+impl Default for IntegritySecuritySettings {
+    fn default() -> Self {
+        Self {
+            binary_integrity_check: true,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// The embedded digest of platforms whose releases are not code-signed.
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod manifest {
+    use crate::error::{Error, Result};
+    use sha2::{Digest, Sha256};
+
+    const MANIFEST_MAGIC_LEN: usize = 16;
+    const MANIFEST_DIGEST_LEN: usize = 32;
+
+    #[repr(C)]
+    #[allow(dead_code)]
+    struct EmbeddedManifest {
+        magic: [u8; MANIFEST_MAGIC_LEN],
+        digest: [u8; MANIFEST_DIGEST_LEN],
+    }
+
+    #[used]
+    static EMBEDDED_MANIFEST: EmbeddedManifest = EmbeddedManifest {
+        magic: *b"PULSAR-INTEG-V1\0",
+        digest: [0u8; MANIFEST_DIGEST_LEN],
+    };
+
+    fn find_manifest(bytes: &[u8]) -> Option<usize> {
+        // Read through a volatile load so the marker is never folded into code,
+        // which would leave a second copy of it in the executable.
+        let magic = unsafe { std::ptr::read_volatile(&EMBEDDED_MANIFEST.magic) };
+        bytes
+            .windows(MANIFEST_MAGIC_LEN + MANIFEST_DIGEST_LEN)
+            .position(|window| window[..MANIFEST_MAGIC_LEN] == magic)
+    }
+
+    /// Returns the digest the binary should have and the digest it actually has.
+    /// The expected digest is `None` when the binary was never stamped.
+    pub(super) fn hash_binary(bytes: &mut [u8]) -> Result<(Option<[u8; 32]>, [u8; 32])> {
+        let offset = find_manifest(bytes).ok_or_else(|| {
+            Error::Internal("Integrity manifest not found in executable".to_string())
+        })?;
+        let slot = offset + MANIFEST_MAGIC_LEN..offset + MANIFEST_MAGIC_LEN + MANIFEST_DIGEST_LEN;
+
+        let mut expected = [0u8; MANIFEST_DIGEST_LEN];
+        expected.copy_from_slice(&bytes[slot.clone()]);
+        bytes[slot].fill(0);
+
+        let actual: [u8; 32] = Sha256::digest(&*bytes).into();
+        let expected = (expected != [0u8; MANIFEST_DIGEST_LEN]).then_some(expected);
+        Ok((expected, actual))
+    }
+}
+
+fn is_standard_location(exe: &Path) -> bool {
+    #[cfg(target_os = "windows")]
+    {
+        let mut roots: Vec<PathBuf> = ["ProgramFiles", "ProgramFiles(x86)", "ProgramW6432"]
+            .iter()
+            .filter_map(|var| std::env::var_os(var).map(PathBuf::from))
+            .collect();
+        if let Some(local) = dirs::data_local_dir() {
+            roots.push(local.join("Programs"));
+        }
+        roots.iter().any(|root| exe.starts_with(root))
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let mut roots = vec![PathBuf::from("/Applications")];
+        if let Some(home) = dirs::home_dir() {
+            roots.push(home.join("Applications"));
+        }
+        roots.iter().any(|root| exe.starts_with(root))
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        if std::env::var_os("APPIMAGE").is_some() || std::env::var_os("FLATPAK_ID").is_some() {
+            return true;
+        }
+        let mut roots = vec![
+            PathBuf::from("/usr"),
+            PathBuf::from("/opt"),
+            PathBuf::from("/snap"),
+        ];
+        if let Some(home) = dirs::home_dir() {
+            roots.push(home.join(".local"));
+        }
+        roots.iter().any(|root| exe.starts_with(root))
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    {
+        let _ = exe;
+        false
+    }
+}
+
+/// Checks the code signature of the enclosing .app bundle, or of the
+/// executable when there is none.
+#[cfg(target_os = "macos")]
+async fn verify_code_signature(exe: &Path) -> (IntegrityStatus, Option<String>) {
+    let target = exe
+        .ancestors()
+        .find(|p| p.extension().map(|e| e == "app").unwrap_or(false))
+        .unwrap_or(exe);
+
+    match tokio::process::Command::new("/usr/bin/codesign")
+        .args(["--verify", "--deep", "--strict"])
+        .arg(target)
+        .output()
+        .await
+    {
+        Ok(output) if output.status.success() => (IntegrityStatus::Verified, None),
+        Ok(output) if String::from_utf8_lossy(&output.stderr).contains("not signed at all") => (
+            IntegrityStatus::Unverified,
+            Some("This build is not code-signed.".to_string()),
+        ),
+        Ok(_) => (
+            IntegrityStatus::Tampered,
+            Some(
+                "The application's code signature is invalid and it may have been tampered with."
+                    .to_string(),
+            ),
+        ),
+        Err(e) => (
+            IntegrityStatus::Error,
+            Some(format!("Failed to check the code signature: {e}")),
+        ),
+    }
+}
+
+/// Checks the executable's Authenticode signature.
+#[cfg(target_os = "windows")]
+async fn verify_code_signature(exe: &Path) -> (IntegrityStatus, Option<String>) {
+    let powershell = std::env::var_os("SystemRoot")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(r"C:\Windows"))
+        .join(r"System32\WindowsPowerShell\v1.0\powershell.exe");
+
+    // The path goes through the environment so it needs no quoting.
+    let output = tokio::process::Command::new(powershell)
+        .args([
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            "(Get-AuthenticodeSignature -LiteralPath $env:PULSAR_EXE).Status",
+        ])
+        .env("PULSAR_EXE", exe)
+        .output()
+        .await;
+    match output {
+        Ok(output) => match String::from_utf8_lossy(&output.stdout).trim() {
+            "Valid" => (IntegrityStatus::Verified, None),
+            "NotSigned" => (
+                IntegrityStatus::Unverified,
+                Some("This build is not code-signed.".to_string()),
+            ),
+            "HashMismatch" => (
+                IntegrityStatus::Tampered,
+                Some("The application binary does not match its signature and may have been tampered with.".to_string()),
+            ),
+            other => (
+                IntegrityStatus::Error,
+                Some(format!("The application's code signature could not be verified: {other}")),
+            ),
+        },
+        Err(e) => (
+            IntegrityStatus::Error,
+            Some(format!("Failed to check the code signature: {e}")),
+        ),
+    }
+}
+
+pub async fn check_binary_integrity() -> BinaryIntegrityReport {
+    let checked_at = chrono::Utc::now().to_rfc3339();
+    let mut warnings = Vec::new();
+
+    let exe = match std::env::current_exe().and_then(|p| p.canonicalize()) {
+        Ok(p) => p,
+        Err(e) => {
+            return BinaryIntegrityReport {
+                status: IntegrityStatus::Error,
+                binary_path: None,
+                sha256: None,
+                expected_sha256: None,
+                standard_location: false,
+                warnings: vec![format!("Could not resolve the running executable: {e}")],
+                checked_at,
+            };
+        }
+    };
+
+    let standard_location = is_standard_location(&exe);
+    if !standard_location {
+        warnings.push(format!(
+            "Pulsar is running from a non-standard location: {}",
+            exe.display()
+        ));
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    {
+        let (status, warning) = verify_code_signature(&exe).await;
+        warnings.extend(warning);
+        let sha256 = tokio::fs::read(&exe)
+            .await
+            .ok()
+            .map(|bytes| hex::encode(Sha256::digest(&bytes)));
+        BinaryIntegrityReport {
+            status,
+            binary_path: Some(exe.display().to_string()),
+            sha256,
+            expected_sha256: None,
+            standard_location,
+            warnings,
+            checked_at,
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        self_hash_report(exe, standard_location, warnings, checked_at).await
+    }
+}
+
+/// Compares the executable against the digest stamped into its manifest.
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+async fn self_hash_report(
+    exe: PathBuf,
+    standard_location: bool,
+    mut warnings: Vec<String>,
+    checked_at: String,
+) -> BinaryIntegrityReport {
+    use crate::error::Error;
+
+    let hashed = match tokio::fs::read(&exe).await {
+        Ok(mut bytes) => tokio::task::spawn_blocking(move || manifest::hash_binary(&mut bytes))
+            .await
+            .map_err(|e| Error::Internal(e.to_string()))
+            .and_then(|r| r),
+        Err(e) => Err(e.into()),
+    };
+
+    let (status, sha256, expected_sha256) = match hashed {
+        Ok((Some(expected), actual)) if expected == actual => (
+            IntegrityStatus::Verified,
+            Some(hex::encode(actual)),
+            Some(hex::encode(expected)),
+        ),
+        Ok((Some(expected), actual)) => {
+            warnings.push(
+                "The application binary does not match its embedded manifest and may have been tampered with."
+                    .to_string(),
+            );
+            (
+                IntegrityStatus::Tampered,
+                Some(hex::encode(actual)),
+                Some(hex::encode(expected)),
+            )
+        }
+        Ok((None, actual)) => {
+            warnings.push("This build carries no integrity manifest.".to_string());
+            (IntegrityStatus::Unverified, Some(hex::encode(actual)), None)
+        }
+        Err(e) => {
+            warnings.push(format!("Failed to hash the application binary: {e}"));
+            (IntegrityStatus::Error, None, None)
+        }
+    };
+
+    BinaryIntegrityReport {
+        status,
+        binary_path: Some(exe.display().to_string()),
+        sha256,
+        expected_sha256,
+        standard_location,
+        warnings,
+        checked_at,
+    }
+}
+
+async fn is_check_enabled(app_handle: &AppHandle) -> bool {
+    match crate::settings::get_all_settings_internal(app_handle).await {
+        Ok(Some(json)) => serde_json::from_str::<IntegritySettings>(&json)
+            .map(|s| s.security.binary_integrity_check)
+            .unwrap_or(true),
+        _ => true,
+    }
+}
+
+/// Runs the self-check in the background at startup when enabled in settings.
+pub fn spawn_startup_check(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        if !is_check_enabled(&app_handle).await {
+            return;
+        }
+
+        let report = check_binary_integrity().await;
+        for warning in &report.warnings {
+            eprintln!("[Integrity] {}", warning);
+        }
+
+        let state = app_handle.state::<AppState>();
+        *state.binary_integrity.lock().await = Some(report);
+    });
+}
+
+#[tauri::command]
+pub async fn run_binary_integrity_check(
+    state: State<'_, AppState>,
+) -> Result<BinaryIntegrityReport> {
+    let report = check_binary_integrity().await;
+    *state.binary_integrity.lock().await = Some(report.clone());
+    Ok(report)
+}
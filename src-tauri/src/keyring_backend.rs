@@ -0,0 +1,170 @@
+//! Access to the OS secret store used for biometric and settings keys. On
+//! Linux the store is whichever daemon owns the Secret Service D-Bus name
+//! (gnome-keyring, KWallet, KeePassXC); it is detected up front so a missing
+//! or locked store produces an actionable error instead of an opaque
+//! keyring failure.
+
+use crate::error::{Error, Result};
+use keyring::Entry;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretBackend {
+    MacosKeychain,
+    WindowsCredentialManager,
+    GnomeKeyring,
+    Kwallet,
+    Keepassxc,
+    /// A Secret Service provider that is not recognised, or one D-Bus will
+    /// start on first use.
+    SecretService,
+    None,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretBackendDiagnostics {
+    pub backend: SecretBackend,
+    pub available: bool,
+    /// Whether a test read reached the store. `false` with `available` set
+    /// usually means the keyring is locked.
+    pub accessible: bool,
+    pub detail: Option<String>,
+    pub settings_keystore: crate::settings::keystore::KeystoreBackend,
+}
+
+#[cfg(target_os = "linux")]
+fn dbus_call(method: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new("dbus-send")
+        .args([
+            "--session",
+            "--print-reply",
+            "--dest=org.freedesktop.DBus",
+            "/org/freedesktop/DBus",
+            &format!("org.freedesktop.DBus.{}", method),
+        ])
+        .args(args)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(target_os = "linux")]
+pub fn detect() -> SecretBackend {
+    const SECRETS_NAME: &str = "org.freedesktop.secrets";
+
+    let owner_pid = dbus_call(
+        "GetConnectionUnixProcessID",
+        &[&format!("string:{}", SECRETS_NAME)],
+    )
+    .and_then(|reply| {
+        reply
+            .split_whitespace()
+            .skip_while(|token| *token != "uint32")
+            .nth(1)
+            .and_then(|pid| pid.parse::<u32>().ok())
+    });
+
+    let Some(pid) = owner_pid else {
+        let activatable = dbus_call("ListActivatableNames", &[])
+            .is_some_and(|reply| reply.contains(&format!("\"{}\"", SECRETS_NAME)));
+        return if activatable {
+            SecretBackend::SecretService
+        } else {
+            SecretBackend::None
+        };
+    };
+
+    let process = std::fs::read_to_string(format!("/proc/{}/comm", pid))
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    if process.contains("gnome-keyring") {
+        SecretBackend::GnomeKeyring
+    } else if process.contains("kwalletd") || process.contains("ksecretd") {
+        SecretBackend::Kwallet
+    } else if process.contains("keepassxc") {
+        SecretBackend::Keepassxc
+    } else {
+        SecretBackend::SecretService
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn detect() -> SecretBackend {
+    SecretBackend::MacosKeychain
+}
+
+#[cfg(target_os = "windows")]
+pub fn detect() -> SecretBackend {
+    SecretBackend::WindowsCredentialManager
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+pub fn detect() -> SecretBackend {
+    SecretBackend::None
+}
+
+fn describe(backend: SecretBackend) -> &'static str {
+    match backend {
+        SecretBackend::MacosKeychain => "the macOS Keychain",
+        SecretBackend::WindowsCredentialManager => "Windows Credential Manager",
+        SecretBackend::GnomeKeyring => "GNOME Keyring",
+        SecretBackend::Kwallet => "KWallet",
+        SecretBackend::Keepassxc => "KeePassXC",
+        SecretBackend::SecretService => "the system keyring",
+        SecretBackend::None => "no keyring",
+    }
+}
+
+/// Turns a keyring failure into an error that says what to do about it.
+/// `NoEntry` should be handled by the caller before this.
+pub fn map_keyring_error(error: keyring::Error) -> Error {
+    let store = describe(detect());
+    match error {
+        keyring::Error::NoStorageAccess(e) => Error::Internal(format!(
+            "Could not access {}; it may be locked. Unlock it and try again. ({})",
+            store, e
+        )),
+        keyring::Error::PlatformFailure(e) => {
+            Error::Internal(format!("{} reported an error: {}", store, e))
+        }
+        e => Error::Internal(format!("Keyring error ({}): {}", store, e)),
+    }
+}
+
+/// Opens an entry in the detected secret store, failing with a clear message
+/// when no store is running.
+pub fn entry(service: &str, user: &str) -> Result<Entry> {
+    if detect() == SecretBackend::None {
+        return Err(Error::Internal(
+            "No system keyring is running. Install and start GNOME Keyring, or enable KWallet's Secret Service integration."
+                .to_string(),
+        ));
+    }
+    Entry::new(service, user).map_err(map_keyring_error)
+}
+
+#[tauri::command]
+pub async fn get_secret_backend_diagnostics() -> Result<SecretBackendDiagnostics> {
+    tauri::async_runtime::spawn_blocking(|| {
+        let backend = detect();
+        let probe =
+            entry("pulsar-diagnostics", "probe").and_then(|entry| match entry.get_password() {
+                Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+                Err(e) => Err(map_keyring_error(e)),
+            });
+        SecretBackendDiagnostics {
+            backend,
+            available: backend != SecretBackend::None,
+            accessible: probe.is_ok(),
+            detail: probe.err().map(|e| e.to_string()),
+            settings_keystore: crate::settings::keystore::backend(),
+        }
+    })
+    .await
+    .map_err(|e| Error::Internal(format!("Runtime error: {}", e)))
+}
@@ -0,0 +1,199 @@
+//! Upper bounds for everything the renderer can send to the backend.
+//!
+//! These checks run before any decryption, hashing or file read so a
+//! compromised webview cannot make the backend allocate unbounded memory.
+
+use crate::error::{Error, Result};
+use crate::types::{CustomField, PasswordItem};
+use std::path::Path;
+
+pub const MAX_TITLE_LEN: usize = 255;
+pub const MAX_SHORT_FIELD_LEN: usize = 2048;
+pub const MAX_PASSWORD_LEN: usize = 4096;
+pub const MAX_TOTP_SECRET_LEN: usize = 1024;
+pub const MAX_NOTES_LEN: usize = 1024 * 1024;
+pub const MAX_IMG_LEN: usize = 512 * 1024;
+pub const MAX_TAGS_LEN: usize = 8 * 1024;
+pub const MAX_CUSTOM_FIELDS: usize = 200;
+pub const MAX_CUSTOM_FIELD_NAME_LEN: usize = 255;
+pub const MAX_CUSTOM_FIELD_VALUE_LEN: usize = 64 * 1024;
+pub const MAX_ATTACHMENT_NAME_LEN: usize = 255;
+/// Default per-attachment limit; vaults can raise it up to
+/// `MAX_ATTACHMENT_SIZE_CEILING`.
+pub const MAX_ATTACHMENT_SIZE: u64 = 100 * 1024 * 1024;
+pub const MAX_ATTACHMENT_SIZE_CEILING: u64 = 16 * 1024 * 1024 * 1024;
+pub const MAX_IMPORT_FILE_SIZE: u64 = 256 * 1024 * 1024;
+pub const MAX_SEARCH_QUERY_LEN: usize = 1024;
+pub const MAX_BATCH_IDS: usize = 10_000;
+pub const MAX_SETTINGS_LEN: usize = 1024 * 1024;
+pub const MAX_PALETTE_COLORS: usize = 256;
+pub const MAX_KEY_MATERIAL_LEN: usize = 16 * 1024;
+pub const MAX_SHARE_PAYLOAD_LEN: usize = 4 * 1024 * 1024;
+pub const MAX_AUTOTYPE_SEQUENCE_LEN: usize = 1024;
+pub const MAX_AUTOTYPE_PATTERNS: usize = 64;
+pub const MAX_SCAN_DIRECTORIES: usize = 64;
+pub const MAX_DRAFT_LEN: usize = 4 * 1024 * 1024;
+pub const MAX_DRAFTS: usize = 100;
+
+pub fn check_len(field: &str, value: &str, max: usize) -> Result<()> {
+    if value.len() > max {
+        return Err(Error::InputTooLarge(format!(
+            "{} exceeds the maximum length of {} bytes",
+            field, max
+        )));
+    }
+    Ok(())
+}
+
+pub fn check_opt_len(field: &str, value: Option<&str>, max: usize) -> Result<()> {
+    match value {
+        Some(v) => check_len(field, v, max),
+        None => Ok(()),
+    }
+}
+
+pub fn check_count(field: &str, count: usize, max: usize) -> Result<()> {
+    if count > max {
+        return Err(Error::InputTooLarge(format!(
+            "{} exceeds the maximum of {} entries",
+            field, max
+        )));
+    }
+    Ok(())
+}
+
+/// Checks the size reported by the filesystem so oversized files are
+/// rejected before they are read into memory. Returns the file size.
+pub async fn check_file_size(field: &str, path: &Path, max: u64) -> Result<u64> {
+    let size = tokio::fs::metadata(path).await?.len();
+    if size > max {
+        return Err(Error::InputTooLarge(format!(
+            "{} is {} bytes, which exceeds the limit of {} bytes",
+            field, size, max
+        )));
+    }
+    Ok(size)
+}
+
+pub fn check_custom_fields(fields: &[CustomField], field_order: Option<&[String]>) -> Result<()> {
+    check_count("Custom fields", fields.len(), MAX_CUSTOM_FIELDS)?;
+    for field in fields {
+        check_len("Custom field name", &field.name, MAX_CUSTOM_FIELD_NAME_LEN)?;
+        check_len(
+            "Custom field value",
+            &field.value,
+            MAX_CUSTOM_FIELD_VALUE_LEN,
+        )?;
+        check_len(
+            "Custom field type",
+            &field.field_type,
+            MAX_CUSTOM_FIELD_NAME_LEN,
+        )?;
+    }
+
+    if let Some(order) = field_order {
+        check_count("Field order", order.len(), MAX_CUSTOM_FIELDS + 16)?;
+        for name in order {
+            check_len("Field order entry", name, MAX_CUSTOM_FIELD_NAME_LEN)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn check_password_item(item: &PasswordItem) -> Result<()> {
+    check_len("Title", &item.title, MAX_TITLE_LEN)?;
+    check_len("Category", &item.category, MAX_SHORT_FIELD_LEN)?;
+    check_opt_len(
+        "Description",
+        item.description.as_deref(),
+        MAX_SHORT_FIELD_LEN,
+    )?;
+    check_opt_len("Icon", item.img.as_deref(), MAX_IMG_LEN)?;
+    check_opt_len("Tags", item.tags.as_deref(), MAX_TAGS_LEN)?;
+    check_opt_len("Username", item.username.as_deref(), MAX_SHORT_FIELD_LEN)?;
+    check_opt_len("URL", item.url.as_deref(), MAX_SHORT_FIELD_LEN)?;
+    check_opt_len(
+        "Notes",
+        item.notes.as_ref().map(|n| n.as_str()),
+        MAX_NOTES_LEN,
+    )?;
+    check_len("Password", item.password.as_str(), MAX_PASSWORD_LEN)?;
+    check_opt_len("Color", item.color.as_deref(), MAX_TITLE_LEN)?;
+    check_opt_len(
+        "TOTP secret",
+        item.totp_secret.as_ref().map(|s| s.as_str()),
+        MAX_TOTP_SECRET_LEN,
+    )?;
+
+    check_custom_fields(&item.custom_fields, item.field_order.as_deref())?;
+
+    if let Some(patterns) = &item.autotype_patterns {
+        check_count("Auto-type patterns", patterns.len(), MAX_AUTOTYPE_PATTERNS)?;
+        for pattern in patterns {
+            check_len("Auto-type pattern", pattern, MAX_SHORT_FIELD_LEN)?;
+        }
+    }
+
+    if let Some(details) = &item.details {
+        let json = zeroize::Zeroizing::new(serde_json::to_string(details)?);
+        check_len("Item details", &json, MAX_CUSTOM_FIELD_VALUE_LEN)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, value: &str) -> CustomField {
+        CustomField {
+            name: name.to_string(),
+            value: value.to_string(),
+            field_type: "text".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_check_len_counts_bytes() {
+        assert!(check_len("Title", "abcd", 4).is_ok());
+        assert!(matches!(
+            check_len("Title", "abcde", 4),
+            Err(Error::InputTooLarge(_))
+        ));
+        // Two characters, four bytes.
+        assert!(check_len("Title", "éé", 3).is_err());
+        assert!(check_len("Title", "", 0).is_ok());
+    }
+
+    #[test]
+    fn test_check_opt_len() {
+        assert!(check_opt_len("URL", None, 0).is_ok());
+        assert!(check_opt_len("URL", Some("ab"), 2).is_ok());
+        assert!(check_opt_len("URL", Some("abc"), 2).is_err());
+    }
+
+    #[test]
+    fn test_check_count() {
+        assert!(check_count("Ids", MAX_BATCH_IDS, MAX_BATCH_IDS).is_ok());
+        assert!(check_count("Ids", MAX_BATCH_IDS + 1, MAX_BATCH_IDS).is_err());
+    }
+
+    #[test]
+    fn test_check_custom_fields() {
+        assert!(check_custom_fields(&[field("PIN", "1234")], None).is_ok());
+
+        let long_name = "n".repeat(MAX_CUSTOM_FIELD_NAME_LEN + 1);
+        assert!(check_custom_fields(&[field(&long_name, "")], None).is_err());
+        let long_value = "v".repeat(MAX_CUSTOM_FIELD_VALUE_LEN + 1);
+        assert!(check_custom_fields(&[field("PIN", &long_value)], None).is_err());
+
+        let too_many = vec![field("a", "b"); MAX_CUSTOM_FIELDS + 1];
+        assert!(check_custom_fields(&too_many, None).is_err());
+
+        let order = vec!["a".to_string(); MAX_CUSTOM_FIELDS + 17];
+        assert!(check_custom_fields(&[], Some(order.as_slice())).is_err());
+        assert!(check_custom_fields(&[], Some(&order[..MAX_CUSTOM_FIELDS + 16])).is_ok());
+    }
+}
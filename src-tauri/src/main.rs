@@ -1,21 +1,46 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod auth;
+mod autotype;
 mod backup_commands;
+mod breach_monitor;
 mod clipboard;
+mod common_passwords;
 mod crypto;
 mod db;
 mod encryption;
 mod error;
+mod events;
 mod file_dialog;
+mod health_report;
+mod importers;
+mod integrity;
+mod keyring_backend;
+mod limits;
+mod memlock;
+mod notifications;
+mod overview_cache;
+mod scheduler;
+mod secure_temp;
 mod security;
+mod security_report;
+mod sessions;
 mod settings;
+mod share;
+#[cfg(desktop)]
+mod shortcuts;
 mod state;
+mod storage;
+mod sync;
 mod totp;
+mod totp_export;
 mod tray;
 mod types;
+mod username;
 mod utils;
 mod vault_commands;
+mod vault_watch;
+mod write_buffer;
 
 use std::sync::Arc;
 use tokio::sync::{Mutex, Semaphore};
@@ -25,6 +50,7 @@ use crate::state::AppState;
 use tauri::{Manager, RunEvent};
 
 fn main() {
+    memlock::disable_core_dumps();
     let context = tauri::generate_context!();
     let mut builder = tauri::Builder::default()
         .manage(AppState {
@@ -36,6 +62,12 @@ fn main() {
             clipboard_policy: Arc::new(Mutex::new(Default::default())),
             unlock_rate_limit: Arc::new(Mutex::new(Default::default())),
             unlock_guard: Arc::new(Semaphore::new(UNLOCK_CONCURRENCY_LIMIT)),
+            binary_integrity: Arc::new(Mutex::new(None)),
+            write_buffer: Arc::new(Mutex::new(None)),
+            overview_cache: Arc::new(std::sync::Mutex::new(Default::default())),
+            last_verification: Arc::new(Mutex::new(None)),
+            session: Arc::new(Mutex::new(None)),
+            parked_keys: Arc::new(Mutex::new(Default::default())),
         })
         .plugin(tauri_plugin_clipboard_manager::init());
 
@@ -44,17 +76,48 @@ fn main() {
         builder = builder.plugin(tauri_plugin_biometric::init());
     }
 
+    #[cfg(desktop)]
+    {
+        builder = builder.plugin(tauri_plugin_global_shortcut::Builder::new().build());
+    }
+
     builder = builder
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_store::Builder::new().build())
+        .plugin(tauri_plugin_notification::init())
+        .setup(|app| {
+            security::install_panic_hook(app.handle().clone());
+            storage::init(app.handle().clone());
+            vault_watch::init(app.handle().clone());
+            sessions::init(app.handle().clone());
+            secure_temp::init();
+            settings::keystore::init(app.handle());
+            auth::attempts::init(app.handle());
+            security::init_device_id(app.handle());
+            integrity::spawn_startup_check(app.handle().clone());
+            scheduler::spawn(app.handle().clone());
+
+            #[cfg(desktop)]
+            {
+                let handle = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = shortcuts::apply_global_shortcuts(&handle).await {
+                        eprintln!("[Shortcuts] {}", e);
+                    }
+                });
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             vault_commands::is_database_loaded,
             vault_commands::get_active_db_path,
             vault_commands::switch_database,
             auth::set_master_password,
             auth::unlock,
+            auth::find_metadata_candidates,
+            auth::repair_metadata_pairing,
             auth::verify_login_totp,
             auth::configure_login_totp,
             auth::disable_login_totp,
@@ -63,19 +126,35 @@ fn main() {
             auth::get_argon2_params,
             auth::rotate_master_password,
             auth::update_argon2_params,
+            auth::presets::get_argon2_presets,
+            auth::presets::apply_argon2_preset,
             auth::verify_master_password,
             auth::lock,
+            auth::reverify::reverify_with_password,
+            auth::reverify::reverify_with_biometrics,
             auth::is_locked,
+            auth::attempts::get_unlock_attempt_history,
+            auth::reload_vault,
             auth::is_master_password_configured,
             auth::enable_biometrics,
             auth::disable_biometrics,
             auth::is_biometrics_enabled,
             auth::unlock_with_biometrics,
+            auth::unlock_with_member_key,
+            auth::members::list_vault_members,
+            auth::members::add_vault_member,
+            auth::members::remove_vault_member,
+            db::fetch_favicon,
             db::save_button,
             db::get_buttons,
             db::get_tag_counts,
+            db::get_tag_tree,
             db::update_button,
             db::delete_button,
+            db::get_palettes,
+            db::save_palette,
+            db::update_palette,
+            db::delete_palette,
             db::remove_tag_from_password_items,
             db::rename_tag_in_password_items,
             db::save_password_item,
@@ -84,34 +163,102 @@ fn main() {
             db::get_total_items_count,
             db::get_favorites_count,
             db::get_password_overviews,
+            db::get_password_overviews_page,
+            db::stream_password_overviews,
+            db::list_pending_conflicts,
+            db::resolve_conflict,
+            db::merge_vault_snapshot,
+            db::execute_readonly_query,
             db::get_password_overviews_by_ids,
             db::get_password_item_by_id,
+            db::get_item_templates,
+            db::save_item_template,
+            db::update_item_template,
+            db::delete_item_template,
+            db::create_item_from_template,
+            db::get_item_fields,
+            db::generate_wifi_qr,
+            db::get_card_summaries,
             db::update_password_item,
+            db::get_note_history,
+            db::restore_note_version,
             db::update_password_item_tags,
             db::update_password_item_totp_secret,
             db::delete_password_item,
+            db::mark_password_item_read_only,
+            db::clear_password_item_read_only,
             db::wipe_vault_database,
             db::add_custom_field,
             db::add_attachment,
+            db::add_attachment_from_bytes,
             db::import_file_as_attachment,
+            db::verify_all_attachments,
+            db::check_db_consistency,
+            db::checkpoint_wal,
+            db::get_migration_status,
+            db::get_attachment_preview,
+            db::get_attachment_usage,
             db::export_attachment_to_file,
             db::delete_attachment,
+            db::move_attachment,
+            db::copy_attachment,
             db::save_attachment_to_disk,
+            db::open_attachment,
+            db::scan_attachment,
             db::save_recipient_key,
             db::get_recipient_keys,
             db::delete_recipient_key,
             db::get_activity_log,
+            db::record_secret_reveal,
+            db::secure_kv_set,
+            db::secure_kv_get,
+            db::secure_kv_delete,
+            db::secure_kv_keys,
+            db::save_item_draft,
+            db::get_item_drafts,
+            db::delete_item_draft,
+            db::get_extended_search_index,
+            db::set_extended_search_index,
+            db::backfill_search_index,
             db::clear_activity_log,
+            db::verify_activity_log,
+            db::get_activity_summary,
             db::save_profile_settings,
             db::get_profile_settings,
+            db::get_vault_settings,
+            db::save_vault_settings,
+            db::check_policy,
+            db::list_vault_templates,
+            common_passwords::is_common_password,
             crypto::export_password_entry,
             crypto::generate_x25519_keypair,
             crypto::export_password_entry_to_public_key,
             crypto::import_password_entry_with_private_key,
+            crypto::import_password_entry,
+            share::create_one_time_share,
+            share::import_one_time_share,
+            share::export_entry_as_qr,
+            share::import_entry_from_qr,
             totp::generate_totp_secret,
             totp::generate_totp,
             totp::verify_totp_secret,
             totp::verify_totp,
+            totp_export::export_totp_secrets,
+            username::generate_username,
+            health_report::get_health_report_config,
+            health_report::set_health_report_config,
+            health_report::send_health_report_now,
+            breach_monitor::get_breach_monitor_config,
+            breach_monitor::set_breach_monitor_config,
+            breach_monitor::run_breach_check_now,
+            breach_monitor::get_breach_results,
+            notifications::list_notifications,
+            notifications::dismiss_notification,
+            notifications::get_notification_settings,
+            notifications::set_notification_settings,
+            scheduler::list_jobs,
+            scheduler::trigger_job,
+            scheduler::set_job_paused,
             file_dialog::pick_open_file,
             file_dialog::pick_save_file,
             file_dialog::elevated_copy,
@@ -123,17 +270,57 @@ fn main() {
             backup_commands::import_vault,
             backup_commands::restore_vault_backend,
             backup_commands::restore_vault_snapshot,
+            sync::sync_with_vault_copy,
+            sync::journal::get_change_journal,
+            sync::patch::export_changes_since,
+            sync::patch::apply_change_patch,
+            sync::webdav::get_webdav_sync_config,
+            sync::webdav::set_webdav_sync_config,
+            sync::webdav::get_webdav_sync_status,
+            sync::webdav::run_webdav_sync_now,
+            sync::s3::get_s3_sync_config,
+            sync::s3::set_s3_sync_config,
+            sync::s3::get_s3_sync_status,
+            sync::s3::run_s3_sync_now,
+            sync::s3::list_s3_sync_versions,
+            importers::import_from_password_manager,
             vault_commands::list_vaults,
+            vault_commands::update_vault_display,
+            vault_commands::update_vault_metadata,
+            vault_commands::scan_for_vaults,
+            vault_commands::search_all_vaults,
+            vault_commands::get_recent_vaults,
+            vault_commands::add_recent_vault,
+            vault_commands::remove_recent_vault,
+            vault_commands::pin_vault,
+            vault_commands::clear_recent_vaults,
             security::list_devices,
             security::remove_device,
             security::revoke_all_devices,
             security::wipe_memory,
+            security::set_screen_capture_protection,
             security::get_security_report,
+            security::get_security_trends,
+            security::get_password_reuse_graph,
             security::run_integrity_check,
+            security_report::generate_security_report,
+            integrity::run_binary_integrity_check,
+            storage::get_storage_status,
+            storage::recheck_storage,
+            write_buffer::set_deferred_writes,
+            write_buffer::flush_pending_writes,
+            write_buffer::get_write_buffer_status,
+            vault_watch::get_vault_modified_externally,
             settings::get_all_settings,
             settings::set_all_settings,
             settings::apply_system_settings,
+            settings::keystore::get_keystore_status,
+            settings::keystore::set_keystore_backend,
+            keyring_backend::get_secret_backend_diagnostics,
             settings::simulate_autotype,
+            autotype::autotype_item,
+            autotype::get_autotype_capabilities,
+            autotype::get_autotype_candidates,
             clipboard::get_clipboard_capabilities,
             clipboard::apply_clipboard_policy,
             clipboard::copy_to_clipboard,
@@ -150,6 +337,9 @@ fn main() {
                 let policy = state.clipboard_policy.lock().await;
                 clipboard::restore_clipboard_history(&policy);
             });
+            if matches!(event, RunEvent::Exit) {
+                security::wipe_secrets_now(app_handle);
+            }
         }
         _ => {}
     });
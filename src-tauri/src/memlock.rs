@@ -0,0 +1,164 @@
+//! Key material that stays out of swap and crash dumps. `LockedKey` keeps
+//! its bytes in a page-aligned allocation of its own that is locked into RAM
+//! (mlock on Unix, VirtualLock on Windows) and zeroized before it is freed.
+//! Because the pages belong to one key only, unlocking them on drop cannot
+//! unlock anything else. `disable_core_dumps` keeps the process out of core
+//! files and error-report dumps.
+
+use std::alloc::{alloc_zeroed, dealloc, Layout};
+use std::fmt;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, Ordering};
+use zeroize::Zeroize;
+
+static LOCK_FAILURE_REPORTED: AtomicBool = AtomicBool::new(false);
+
+fn page_size() -> usize {
+    #[cfg(unix)]
+    {
+        let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        if size > 0 {
+            return size as usize;
+        }
+    }
+    4096
+}
+
+#[cfg(unix)]
+unsafe fn lock_pages(ptr: *mut u8, len: usize) -> bool {
+    libc::mlock(ptr as *const libc::c_void, len) == 0
+}
+
+#[cfg(unix)]
+unsafe fn unlock_pages(ptr: *mut u8, len: usize) {
+    libc::munlock(ptr as *const libc::c_void, len);
+}
+
+#[cfg(windows)]
+unsafe fn lock_pages(ptr: *mut u8, len: usize) -> bool {
+    windows::Win32::System::Memory::VirtualLock(ptr as *const std::ffi::c_void, len).is_ok()
+}
+
+#[cfg(windows)]
+unsafe fn unlock_pages(ptr: *mut u8, len: usize) {
+    let _ = windows::Win32::System::Memory::VirtualUnlock(ptr as *const std::ffi::c_void, len);
+}
+
+#[cfg(not(any(unix, windows)))]
+unsafe fn lock_pages(_ptr: *mut u8, _len: usize) -> bool {
+    false
+}
+
+#[cfg(not(any(unix, windows)))]
+unsafe fn unlock_pages(_ptr: *mut u8, _len: usize) {}
+
+pub struct LockedKey {
+    ptr: *mut u8,
+    len: usize,
+    layout: Layout,
+    locked: bool,
+}
+
+// The buffer is owned exclusively and only exposed through `&[u8]`.
+unsafe impl Send for LockedKey {}
+unsafe impl Sync for LockedKey {}
+
+impl LockedKey {
+    /// Copies `bytes` into a fresh locked allocation. If the OS refuses the
+    /// lock (e.g. RLIMIT_MEMLOCK is exhausted) the key is still usable, just
+    /// not pinned; the failure is logged once.
+    pub fn new(bytes: &[u8]) -> Self {
+        let page = page_size();
+        let size = bytes.len().max(1).div_ceil(page) * page;
+        let layout = Layout::from_size_align(size, page).expect("valid key layout");
+        let ptr = unsafe { alloc_zeroed(layout) };
+        if ptr.is_null() {
+            std::alloc::handle_alloc_error(layout);
+        }
+        let locked = unsafe { lock_pages(ptr, size) };
+        if !locked && !LOCK_FAILURE_REPORTED.swap(true, Ordering::Relaxed) {
+            eprintln!("[Memlock] Could not lock key memory; it may be swapped to disk.");
+        }
+        unsafe { std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr, bytes.len()) };
+        Self {
+            ptr,
+            len: bytes.len(),
+            layout,
+            locked,
+        }
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        self
+    }
+}
+
+impl Deref for LockedKey {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl Clone for LockedKey {
+    fn clone(&self) -> Self {
+        Self::new(self)
+    }
+}
+
+impl fmt::Debug for LockedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("LockedKey([REDACTED])")
+    }
+}
+
+impl Zeroize for LockedKey {
+    fn zeroize(&mut self) {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr, self.layout.size()) }.zeroize();
+    }
+}
+
+impl Drop for LockedKey {
+    fn drop(&mut self) {
+        self.zeroize();
+        unsafe {
+            if self.locked {
+                unlock_pages(self.ptr, self.layout.size());
+            }
+            dealloc(self.ptr, self.layout);
+        }
+    }
+}
+
+/// Stops the OS from writing this process's memory to a core file or an
+/// error-report dump after a crash.
+pub fn disable_core_dumps() {
+    #[cfg(unix)]
+    unsafe {
+        let limit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        if libc::setrlimit(libc::RLIMIT_CORE, &limit) != 0 {
+            eprintln!("[Memlock] Failed to disable core dumps");
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    unsafe {
+        // Also blocks ptrace attach and /proc/<pid>/mem reads by other
+        // processes of the same user.
+        libc::prctl(libc::PR_SET_DUMPABLE, 0, 0, 0, 0);
+    }
+
+    #[cfg(windows)]
+    unsafe {
+        use windows::Win32::System::Diagnostics::Debug::{
+            SetErrorMode, SEM_FAILCRITICALERRORS, SEM_NOGPFAULTERRORBOX,
+        };
+        // Without the fault dialog, Windows Error Reporting does not collect
+        // a dump of the process.
+        SetErrorMode(SEM_FAILCRITICALERRORS | SEM_NOGPFAULTERRORBOX);
+    }
+}
@@ -0,0 +1,563 @@
+//! Reminders about things in the vault that need attention: cards about to
+//! expire, passwords older than the vault's password policy allows, overdue
+//! backups and breached items. The scheduler re-evaluates the sources hourly
+//! while a vault is unlocked. Each reminder is stored once, encrypted, so
+//! dismissing it sticks until its cause changes. New reminders can also be
+//! shown as system notifications, which only carry a generic title and never
+//! item names.
+
+use crate::breach_monitor::{self, BreachFindings};
+use crate::db::utils::UnlockedVault;
+use crate::db::validation::card_expires_on;
+use crate::encryption::{decrypt, encrypt};
+use crate::error::{Error, Result};
+use crate::state::AppState;
+use crate::types::items::ItemDetails;
+use chrono::{DateTime, Datelike, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{Row, SqlitePool};
+use std::collections::{HashMap, HashSet};
+use tauri::{AppHandle, Emitter, State};
+use tauri_plugin_notification::NotificationExt;
+use zeroize::Zeroizing;
+
+const CONFIG_KEY: &str = "notification_settings";
+const MAX_WARNING_DAYS: u32 = 365;
+/// Above this many new reminders a single summary is shown instead.
+const MAX_SYSTEM_NOTIFICATIONS: usize = 3;
+
+pub const NOTIFICATIONS_CHANGED_EVENT: &str = "notifications:changed";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationSource {
+    ExpiringItem,
+    PasswordAge,
+    OverdueBackup,
+    Breach,
+}
+
+impl NotificationSource {
+    const ALL: [NotificationSource; 4] = [
+        NotificationSource::ExpiringItem,
+        NotificationSource::PasswordAge,
+        NotificationSource::OverdueBackup,
+        NotificationSource::Breach,
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            NotificationSource::ExpiringItem => "expiring_item",
+            NotificationSource::PasswordAge => "password_age",
+            NotificationSource::OverdueBackup => "overdue_backup",
+            NotificationSource::Breach => "breach",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.as_str() == value)
+    }
+
+    /// Shown in system notifications, so it must not name an item.
+    fn system_title(self) -> &'static str {
+        match self {
+            NotificationSource::ExpiringItem => "A card is about to expire",
+            NotificationSource::PasswordAge => "A password is due for a change",
+            NotificationSource::OverdueBackup => "Your vault backup is overdue",
+            NotificationSource::Breach => "An item was found in a data breach",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SourceSettings {
+    pub enabled: bool,
+    /// Also show new reminders from this source as system notifications.
+    pub system_notifications: bool,
+}
+
+impl Default for SourceSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            system_notifications: true,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotificationSettings {
+    pub expiring_items: SourceSettings,
+    /// Uses the maximum password age of the vault's password policy.
+    pub password_age: SourceSettings,
+    pub overdue_backups: SourceSettings,
+    pub breach_alerts: SourceSettings,
+    /// How many days before a card expires to start reminding.
+    pub expiry_warning_days: u32,
+    /// How many days after the last backup it counts as overdue.
+    pub backup_overdue_days: u32,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            expiring_items: SourceSettings::default(),
+            password_age: SourceSettings::default(),
+            overdue_backups: SourceSettings::default(),
+            breach_alerts: SourceSettings::default(),
+            expiry_warning_days: 30,
+            backup_overdue_days: 30,
+        }
+    }
+}
+
+impl NotificationSettings {
+    fn source(&self, source: NotificationSource) -> SourceSettings {
+        match source {
+            NotificationSource::ExpiringItem => self.expiring_items,
+            NotificationSource::PasswordAge => self.password_age,
+            NotificationSource::OverdueBackup => self.overdue_backups,
+            NotificationSource::Breach => self.breach_alerts,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Notification {
+    pub id: i64,
+    pub source: NotificationSource,
+    pub item_id: Option<i64>,
+    pub title: String,
+    pub body: String,
+    pub created_at: String,
+    pub dismissed_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct NotificationText {
+    title: String,
+    body: String,
+}
+
+/// A reminder a source currently produces. `key` identifies its cause, so a
+/// dismissed reminder comes back only once the cause changes.
+struct Candidate {
+    source: NotificationSource,
+    key: String,
+    item_id: Option<i64>,
+    text: NotificationText,
+}
+
+async fn load_settings(pool: &SqlitePool, key: &[u8]) -> Result<NotificationSettings> {
+    let value: Option<String> = sqlx::query_scalar("SELECT value FROM configuration WHERE key = ?")
+        .bind(CONFIG_KEY)
+        .fetch_optional(pool)
+        .await?;
+    match value {
+        Some(enc) => {
+            let json = Zeroizing::new(decrypt(&enc, key)?);
+            Ok(serde_json::from_str(&json)?)
+        }
+        None => Ok(NotificationSettings::default()),
+    }
+}
+
+async fn item_titles(vault: &UnlockedVault) -> Result<HashMap<i64, String>> {
+    let rows = sqlx::query("SELECT id, title FROM password_items")
+        .fetch_all(vault.pool())
+        .await?;
+    rows.into_iter()
+        .map(|row| {
+            let title: String = row.get("title");
+            Ok((row.get("id"), decrypt(&title, vault.key())?))
+        })
+        .collect()
+}
+
+fn item_title(titles: &HashMap<i64, String>, id: i64) -> &str {
+    titles.get(&id).map(String::as_str).unwrap_or("An item")
+}
+
+async fn expiring_items(
+    vault: &UnlockedVault,
+    settings: &NotificationSettings,
+    titles: &HashMap<i64, String>,
+) -> Result<Vec<Candidate>> {
+    let rows = sqlx::query("SELECT id, details FROM password_items WHERE details IS NOT NULL")
+        .fetch_all(vault.pool())
+        .await?;
+    let today = Utc::now().date_naive();
+    let warn_until = today + chrono::Duration::days(i64::from(settings.expiry_warning_days));
+
+    let helper = vault.helper()?;
+    let mut candidates = Vec::new();
+    for row in rows {
+        let id: i64 = row.get("id");
+        let enc: String = row.get("details");
+        let Ok(json) = helper.decrypt_zeroized(&enc) else {
+            continue;
+        };
+        let Ok(ItemDetails::Card(card)) = serde_json::from_str::<ItemDetails>(&json) else {
+            continue;
+        };
+        let (Some(month), Some(year)) = (card.expiry_month, card.expiry_year) else {
+            continue;
+        };
+        let Some(expires_on) = card_expires_on(month, year) else {
+            continue;
+        };
+        if expires_on > warn_until {
+            continue;
+        }
+        let body = if expires_on <= today {
+            format!(
+                "{} expired at the end of {:02}/{}.",
+                item_title(titles, id),
+                month,
+                year
+            )
+        } else {
+            format!(
+                "{} expires at the end of {:02}/{}.",
+                item_title(titles, id),
+                month,
+                year
+            )
+        };
+        candidates.push(Candidate {
+            source: NotificationSource::ExpiringItem,
+            key: format!(
+                "expiring:{}:{}-{:02}",
+                id,
+                expires_on.year(),
+                expires_on.month()
+            ),
+            item_id: Some(id),
+            text: NotificationText {
+                title: "Card expiring".to_string(),
+                body,
+            },
+        });
+    }
+    Ok(candidates)
+}
+
+async fn password_age(
+    vault: &UnlockedVault,
+    titles: &HashMap<i64, String>,
+) -> Result<Vec<Candidate>> {
+    let max_age_days = crate::db::vault_settings::load_vault_settings(vault.pool(), vault.key())
+        .await?
+        .password_policy
+        .map(|p| p.max_age_days)
+        .unwrap_or(0);
+    if max_age_days == 0 {
+        return Ok(Vec::new());
+    }
+    let rows = sqlx::query(
+        "SELECT id, password, updated_at FROM password_items WHERE datetime(updated_at) <= datetime('now', ?)",
+    )
+    .bind(format!("-{} days", max_age_days))
+    .fetch_all(vault.pool())
+    .await?;
+
+    let helper = vault.helper()?;
+    Ok(rows
+        .into_iter()
+        .filter(|row| {
+            // Cards, identities and notes carry no password to change.
+            row.get::<Option<String>, _>("password")
+                .and_then(|enc| helper.decrypt_zeroized(&enc).ok())
+                .is_some_and(|p| !p.is_empty() && p.as_str() != "N/A")
+        })
+        .map(|row| {
+            let id: i64 = row.get("id");
+            let updated_at: String = row.get("updated_at");
+            Candidate {
+                source: NotificationSource::PasswordAge,
+                key: format!("password_age:{}:{}", id, updated_at),
+                item_id: Some(id),
+                text: NotificationText {
+                    title: "Password due for a change".to_string(),
+                    body: format!(
+                        "The password of {} has not changed in over {} days.",
+                        item_title(titles, id),
+                        max_age_days
+                    ),
+                },
+            }
+        })
+        .collect())
+}
+
+async fn overdue_backup(
+    app: &AppHandle,
+    vault: &UnlockedVault,
+    settings: &NotificationSettings,
+) -> Result<Option<Candidate>> {
+    let (last_backup, _) = crate::health_report::backup_status(app, vault).await?;
+    let last = last_backup
+        .as_deref()
+        .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+        .map(|at| at.with_timezone(&Utc));
+    let overdue_after = chrono::Duration::days(i64::from(settings.backup_overdue_days));
+    if last.is_some_and(|at| Utc::now() - at < overdue_after) {
+        return Ok(None);
+    }
+    let body = match last {
+        Some(at) => format!("The vault was last backed up on {}.", at.format("%Y-%m-%d")),
+        None => "The vault has never been backed up.".to_string(),
+    };
+    Ok(Some(Candidate {
+        source: NotificationSource::OverdueBackup,
+        key: format!(
+            "overdue_backup:{}",
+            last_backup.as_deref().unwrap_or("never")
+        ),
+        item_id: None,
+        text: NotificationText {
+            title: "Backup overdue".to_string(),
+            body,
+        },
+    }))
+}
+
+fn findings_digest(findings: &BreachFindings) -> Result<String> {
+    let json = serde_json::to_vec(findings)?;
+    Ok(hex::encode(&Sha256::digest(&json)[..8]))
+}
+
+async fn breaches(vault: &UnlockedVault, titles: &HashMap<i64, String>) -> Result<Vec<Candidate>> {
+    let results = breach_monitor::load_results(vault.pool(), vault.key()).await?;
+    results
+        .into_iter()
+        .filter(|r| r.findings.is_breached())
+        .map(|r| {
+            let title = item_title(titles, r.item_id);
+            let body = if r.findings.password_seen_count > 0 {
+                format!("The password of {} appears in known data breaches.", title)
+            } else {
+                format!(
+                    "The account of {} appears in {} data breach(es).",
+                    title,
+                    r.findings.account_breaches.len()
+                )
+            };
+            Ok(Candidate {
+                source: NotificationSource::Breach,
+                key: format!("breach:{}:{}", r.item_id, findings_digest(&r.findings)?),
+                item_id: Some(r.item_id),
+                text: NotificationText {
+                    title: "Breached item".to_string(),
+                    body,
+                },
+            })
+        })
+        .collect()
+}
+
+async fn candidates(
+    app: &AppHandle,
+    vault: &UnlockedVault,
+    settings: &NotificationSettings,
+) -> Result<Vec<Candidate>> {
+    let titles = item_titles(vault).await?;
+    let mut candidates = Vec::new();
+    if settings.expiring_items.enabled {
+        candidates.extend(expiring_items(vault, settings, &titles).await?);
+    }
+    if settings.password_age.enabled {
+        candidates.extend(password_age(vault, &titles).await?);
+    }
+    if settings.overdue_backups.enabled {
+        candidates.extend(overdue_backup(app, vault, settings).await?);
+    }
+    if settings.breach_alerts.enabled {
+        candidates.extend(breaches(vault, &titles).await?);
+    }
+    Ok(candidates)
+}
+
+/// Re-evaluates every enabled source. Reminders whose cause is gone are
+/// removed; dismissed reminders of disabled sources are kept so they do not
+/// return when the source is enabled again.
+pub(crate) async fn refresh(app: &AppHandle, state: &AppState) -> Result<()> {
+    let vault = UnlockedVault::acquire_writable(state).await?;
+    let settings = load_settings(vault.pool(), vault.key()).await?;
+    let current = candidates(app, &vault, &settings).await?;
+
+    let existing = sqlx::query("SELECT id, source, key, dismissed_at FROM notifications")
+        .fetch_all(vault.pool())
+        .await?;
+    let current_keys: HashSet<&str> = current.iter().map(|c| c.key.as_str()).collect();
+    let mut known_keys = HashSet::new();
+    let mut stale = Vec::new();
+    for row in &existing {
+        let id: i64 = row.get("id");
+        let key: String = row.get("key");
+        let dismissed = row.get::<Option<String>, _>("dismissed_at").is_some();
+        let enabled = NotificationSource::parse(&row.get::<String, _>("source"))
+            .is_some_and(|s| settings.source(s).enabled);
+        if current_keys.contains(key.as_str()) || (!enabled && dismissed) {
+            known_keys.insert(key);
+        } else {
+            stale.push(id);
+        }
+    }
+
+    let now = Utc::now().to_rfc3339();
+    let mut added = Vec::new();
+    let mut tx = vault.pool().begin().await?;
+    for id in &stale {
+        sqlx::query("DELETE FROM notifications WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    for candidate in current.iter().filter(|c| !known_keys.contains(&c.key)) {
+        let data = Zeroizing::new(serde_json::to_string(&candidate.text)?);
+        sqlx::query(
+            "INSERT INTO notifications (source, key, item_id, data, created_at) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(candidate.source.as_str())
+        .bind(&candidate.key)
+        .bind(candidate.item_id)
+        .bind(encrypt(&data, vault.key())?)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await?;
+        added.push(candidate.source);
+    }
+    tx.commit().await?;
+    drop(vault);
+
+    if !added.is_empty() || !stale.is_empty() {
+        let _ = app.emit(NOTIFICATIONS_CHANGED_EVENT, ());
+    }
+    show_system_notifications(app, &settings, &added);
+    Ok(())
+}
+
+fn show_system_notifications(
+    app: &AppHandle,
+    settings: &NotificationSettings,
+    added: &[NotificationSource],
+) {
+    let shown: Vec<NotificationSource> = added
+        .iter()
+        .copied()
+        .filter(|s| settings.source(*s).system_notifications)
+        .collect();
+    if shown.len() > MAX_SYSTEM_NOTIFICATIONS {
+        let title = format!("{} new reminders in Pulsar", shown.len());
+        show_system_notification(app, &title, Some("Open Pulsar to review them."));
+        return;
+    }
+    for source in shown {
+        show_system_notification(app, source.system_title(), None);
+    }
+}
+
+fn show_system_notification(app: &AppHandle, title: &str, body: Option<&str>) {
+    let mut builder = app.notification().builder().title(title);
+    if let Some(body) = body {
+        builder = builder.body(body);
+    }
+    if let Err(e) = builder.show() {
+        eprintln!("[Notifications] Failed to show notification: {}", e);
+    }
+}
+
+/// Newest first. Dismissed reminders are only included when asked for.
+#[tauri::command]
+pub async fn list_notifications(
+    state: State<'_, AppState>,
+    include_dismissed: Option<bool>,
+) -> Result<Vec<Notification>> {
+    let vault = UnlockedVault::acquire(&state).await?;
+    let sql = if include_dismissed.unwrap_or(false) {
+        "SELECT id, source, item_id, data, created_at, dismissed_at FROM notifications ORDER BY created_at DESC, id DESC"
+    } else {
+        "SELECT id, source, item_id, data, created_at, dismissed_at FROM notifications WHERE dismissed_at IS NULL ORDER BY created_at DESC, id DESC"
+    };
+    let rows = sqlx::query(sql).fetch_all(vault.pool()).await?;
+    rows.into_iter()
+        .filter_map(|row| {
+            let source = NotificationSource::parse(&row.get::<String, _>("source"))?;
+            Some((row, source))
+        })
+        .map(|(row, source)| {
+            let data: String = row.get("data");
+            let text: NotificationText =
+                serde_json::from_str(&Zeroizing::new(decrypt(&data, vault.key())?))?;
+            Ok(Notification {
+                id: row.get("id"),
+                source,
+                item_id: row.get("item_id"),
+                title: text.title,
+                body: text.body,
+                created_at: row.get("created_at"),
+                dismissed_at: row.get("dismissed_at"),
+            })
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub async fn dismiss_notification(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: i64,
+) -> Result<()> {
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let result = sqlx::query(
+        "UPDATE notifications SET dismissed_at = ? WHERE id = ? AND dismissed_at IS NULL",
+    )
+    .bind(Utc::now().to_rfc3339())
+    .bind(id)
+    .execute(vault.pool())
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(Error::Internal("Notification not found".to_string()));
+    }
+    let _ = app.emit(NOTIFICATIONS_CHANGED_EVENT, ());
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_notification_settings(state: State<'_, AppState>) -> Result<NotificationSettings> {
+    let vault = UnlockedVault::acquire(&state).await?;
+    load_settings(vault.pool(), vault.key()).await
+}
+
+/// Saves the settings and re-evaluates the sources right away.
+#[tauri::command]
+pub async fn set_notification_settings(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    settings: NotificationSettings,
+) -> Result<()> {
+    if settings.expiry_warning_days > MAX_WARNING_DAYS
+        || settings.backup_overdue_days == 0
+        || settings.backup_overdue_days > MAX_WARNING_DAYS
+    {
+        return Err(Error::Validation(format!(
+            "Reminder periods must be between 1 and {} days.",
+            MAX_WARNING_DAYS
+        )));
+    }
+    {
+        let vault = UnlockedVault::acquire_writable(&state).await?;
+        let json = Zeroizing::new(serde_json::to_string(&settings)?);
+        sqlx::query("INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)")
+            .bind(CONFIG_KEY)
+            .bind(encrypt(&json, vault.key())?)
+            .execute(vault.pool())
+            .await?;
+    }
+    refresh(&app, &state).await
+}
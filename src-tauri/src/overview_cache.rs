@@ -0,0 +1,82 @@
+//! Least-recently-used cache of decrypted item overviews, so listing and
+//! searching don't decrypt every row again. An entry is only served while the
+//! row's `updated_at` matches, so edits invalidate it without bookkeeping.
+//! Holds plaintext and must be cleared whenever the vault is locked.
+
+use crate::types::PasswordItemOverview;
+use std::collections::{BTreeMap, HashMap};
+
+const CAPACITY: usize = 10_000;
+
+struct Entry {
+    updated_at: String,
+    overview: PasswordItemOverview,
+    last_used: u64,
+}
+
+#[derive(Default)]
+pub struct OverviewCache {
+    entries: HashMap<i64, Entry>,
+    /// `last_used` tick to item id, oldest first.
+    recency: BTreeMap<u64, i64>,
+    tick: u64,
+}
+
+impl OverviewCache {
+    fn touch(&mut self, id: i64) {
+        self.tick += 1;
+        if let Some(entry) = self.entries.get_mut(&id) {
+            self.recency.remove(&entry.last_used);
+            entry.last_used = self.tick;
+            self.recency.insert(self.tick, id);
+        }
+    }
+
+    pub fn get(&mut self, id: i64, updated_at: &str) -> Option<PasswordItemOverview> {
+        match self.entries.get(&id) {
+            Some(entry) if entry.updated_at == updated_at => {}
+            Some(_) => {
+                self.remove(id);
+                return None;
+            }
+            None => return None,
+        }
+        self.touch(id);
+        self.entries.get(&id).map(|entry| entry.overview.clone())
+    }
+
+    pub fn insert(&mut self, overview: &PasswordItemOverview) {
+        self.remove(overview.id);
+        while self.entries.len() >= CAPACITY {
+            let Some((_, oldest)) = self.recency.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+
+        self.tick += 1;
+        let mut overview = overview.clone();
+        overview.tag_colors.clear();
+        overview.score = None;
+        self.recency.insert(self.tick, overview.id);
+        self.entries.insert(
+            overview.id,
+            Entry {
+                updated_at: overview.updated_at.clone(),
+                overview,
+                last_used: self.tick,
+            },
+        );
+    }
+
+    pub fn remove(&mut self, id: i64) {
+        if let Some(entry) = self.entries.remove(&id) {
+            self.recency.remove(&entry.last_used);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries = HashMap::new();
+        self.recency.clear();
+    }
+}
@@ -0,0 +1,23 @@
+// Shared by `build.rs`, which builds the common-password filter, and
+// `common_passwords.rs`, which queries it, so both hash words the same way.
+// Keep it free of crate imports.
+
+/// Probes per word; optimal for the 16 bits per entry `build.rs` allots.
+pub(crate) const BLOOM_HASHES: u64 = 11;
+
+fn fnv1a(seed: u64, bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325 ^ seed;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+/// The bit positions `word` sets in a filter of `bits` bits, by double
+/// hashing.
+pub(crate) fn bloom_positions(word: &str, bits: u64) -> impl Iterator<Item = u64> {
+    let h1 = fnv1a(0, word.as_bytes());
+    let h2 = fnv1a(0x9e37_79b9_7f4a_7c15, word.as_bytes()) | 1;
+    (0..BLOOM_HASHES).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % bits)
+}
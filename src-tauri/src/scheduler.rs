@@ -0,0 +1,373 @@
+//! Recurring background work. Every job runs on a fixed interval while a
+//! writable vault is unlocked. The vault's configuration table stores
+//! whether each job is paused and how its last run went, so that state
+//! travels with the vault. Jobs keep their own settings, such as whether
+//! breach monitoring is enabled, and decide on each run whether there is
+//! anything to do.
+
+use crate::db::utils::UnlockedVault;
+use crate::encryption::{decrypt, encrypt};
+use crate::error::{Error, Result};
+use crate::state::AppState;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::{BTreeMap, BTreeSet};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use zeroize::Zeroizing;
+
+const CONFIG_KEY: &str = "scheduler";
+const STARTUP_DELAY: Duration = Duration::from_secs(60);
+const TICK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const HOUR: Duration = Duration::from_secs(60 * 60);
+
+type JobFuture = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+
+struct Job {
+    id: &'static str,
+    description: &'static str,
+    interval: Duration,
+    /// The flag is set when the run was triggered by the user rather than
+    /// the schedule.
+    run: fn(AppHandle, bool) -> JobFuture,
+}
+
+const JOBS: &[Job] = &[
+    Job {
+        id: "notifications",
+        description: "Update reminders about expiring cards, old passwords, backups and breaches",
+        interval: HOUR,
+        run: run_notifications,
+    },
+    Job {
+        id: "breach_check",
+        description: "Check passwords and usernames against known data breaches",
+        interval: HOUR,
+        run: run_breach_check,
+    },
+    Job {
+        id: "health_report",
+        description: "Send the monthly vault health report",
+        interval: HOUR,
+        run: run_health_report,
+    },
+    Job {
+        id: "index_maintenance",
+        description: "Remove stale search index entries, compact the change journal and optimize the database",
+        interval: Duration::from_secs(7 * 24 * 60 * 60),
+        run: run_index_maintenance,
+    },
+    Job {
+        id: "webdav_sync",
+        description: "Sync the vault with its copy on a WebDAV server",
+        interval: Duration::from_secs(5 * 60),
+        run: run_webdav_sync,
+    },
+    Job {
+        id: "s3_sync",
+        description: "Sync the vault with its copy in an S3-compatible bucket",
+        interval: Duration::from_secs(5 * 60),
+        run: run_s3_sync,
+    },
+    Job {
+        id: "wal_checkpoint",
+        description: "Write pending changes into the vault file and shrink its -wal file",
+        interval: Duration::from_secs(15 * 60),
+        run: run_wal_checkpoint,
+    },
+];
+
+static RUNNING: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+/// Serializes updates of the stored job state.
+static STATE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+fn run_notifications(app: AppHandle, _manual: bool) -> JobFuture {
+    Box::pin(async move {
+        let state = app.state::<AppState>();
+        crate::notifications::refresh(&app, &state).await
+    })
+}
+
+/// Scheduled runs follow the breach monitor's own settings; a manual run
+/// checks right away.
+fn run_breach_check(app: AppHandle, manual: bool) -> JobFuture {
+    Box::pin(async move {
+        if manual {
+            let state = app.state::<AppState>();
+            crate::breach_monitor::run_check(&app, &state).await?;
+            Ok(())
+        } else {
+            crate::breach_monitor::run_if_due(&app).await
+        }
+    })
+}
+
+fn run_health_report(app: AppHandle, manual: bool) -> JobFuture {
+    Box::pin(async move {
+        if manual {
+            let state = app.state::<AppState>();
+            crate::health_report::send_health_report_now(app.clone(), state).await?;
+            Ok(())
+        } else {
+            crate::health_report::run_if_due(&app).await
+        }
+    })
+}
+
+fn run_index_maintenance(app: AppHandle, _manual: bool) -> JobFuture {
+    Box::pin(async move {
+        let state = app.state::<AppState>();
+        let vault = UnlockedVault::acquire_writable(&state).await?;
+        let mut tx = vault.pool().begin().await?;
+        for sql in [
+            "DELETE FROM search_trigrams WHERE item_id NOT IN (SELECT id FROM password_items)",
+            "DELETE FROM search_indices WHERE item_id NOT IN (SELECT id FROM password_items)",
+            "DELETE FROM item_tags WHERE item_id NOT IN (SELECT id FROM password_items)",
+            "DELETE FROM item_tags WHERE tag_id NOT IN (SELECT id FROM buttons)",
+        ] {
+            sqlx::query(sql).execute(&mut *tx).await?;
+        }
+        crate::sync::journal::compact(&mut *tx).await?;
+        tx.commit().await?;
+        sqlx::query("PRAGMA optimize").execute(vault.pool()).await?;
+        Ok(())
+    })
+}
+
+/// Scheduled runs follow the WebDAV sync settings; a manual run syncs right
+/// away.
+fn run_webdav_sync(app: AppHandle, manual: bool) -> JobFuture {
+    Box::pin(async move {
+        if manual {
+            let state = app.state::<AppState>();
+            crate::sync::webdav::run_sync(&app, &state).await?;
+            Ok(())
+        } else {
+            crate::sync::webdav::run_if_due(&app).await
+        }
+    })
+}
+
+/// Scheduled runs follow the S3 sync settings; a manual run syncs right away.
+fn run_s3_sync(app: AppHandle, manual: bool) -> JobFuture {
+    Box::pin(async move {
+        if manual {
+            let state = app.state::<AppState>();
+            crate::sync::s3::run_sync(&app, &state).await?;
+            Ok(())
+        } else {
+            crate::sync::s3::run_if_due(&app).await
+        }
+    })
+}
+
+/// Keeps the `-wal` file from growing without bound during long sessions.
+fn run_wal_checkpoint(app: AppHandle, _manual: bool) -> JobFuture {
+    Box::pin(async move {
+        let state = app.state::<AppState>();
+        let vault = UnlockedVault::acquire_writable(&state).await?;
+        crate::db::wal::checkpoint(vault.pool(), crate::db::wal::CheckpointMode::Truncate).await?;
+        Ok(())
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct JobState {
+    paused: bool,
+    last_run: Option<String>,
+    last_error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobInfo {
+    pub id: String,
+    pub description: String,
+    pub interval_seconds: u64,
+    pub paused: bool,
+    pub running: bool,
+    pub last_run: Option<String>,
+    pub last_error: Option<String>,
+    /// `None` while paused.
+    pub next_run: Option<String>,
+}
+
+async fn load_states(pool: &SqlitePool, key: &[u8]) -> Result<BTreeMap<String, JobState>> {
+    let value: Option<String> = sqlx::query_scalar("SELECT value FROM configuration WHERE key = ?")
+        .bind(CONFIG_KEY)
+        .fetch_optional(pool)
+        .await?;
+    match value {
+        Some(enc) => {
+            let json = Zeroizing::new(decrypt(&enc, key)?);
+            Ok(serde_json::from_str(&json)?)
+        }
+        None => Ok(BTreeMap::new()),
+    }
+}
+
+async fn save_states(
+    pool: &SqlitePool,
+    key: &[u8],
+    states: &BTreeMap<String, JobState>,
+) -> Result<()> {
+    let json = Zeroizing::new(serde_json::to_string(states)?);
+    sqlx::query("INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)")
+        .bind(CONFIG_KEY)
+        .bind(encrypt(&json, key)?)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+async fn update_state(
+    state: &AppState,
+    id: &str,
+    update: impl FnOnce(&mut JobState),
+) -> Result<()> {
+    let _guard = STATE_LOCK.lock().await;
+    let vault = UnlockedVault::acquire_writable(state).await?;
+    let mut states = load_states(vault.pool(), vault.key()).await?;
+    update(states.entry(id.to_string()).or_default());
+    save_states(vault.pool(), vault.key(), &states).await
+}
+
+fn find_job(id: &str) -> Result<&'static Job> {
+    JOBS.iter()
+        .find(|job| job.id == id)
+        .ok_or_else(|| Error::Validation(format!("Unknown job: {}", id)))
+}
+
+fn is_running(id: &str) -> bool {
+    RUNNING.lock().map(|r| r.contains(id)).unwrap_or(false)
+}
+
+fn parse_time(at: Option<&str>) -> Option<DateTime<Utc>> {
+    at.and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+        .map(|at| at.with_timezone(&Utc))
+}
+
+fn next_run(job: &Job, state: &JobState) -> Option<DateTime<Utc>> {
+    if state.paused {
+        return None;
+    }
+    let interval = chrono::Duration::from_std(job.interval).ok()?;
+    Some(parse_time(state.last_run.as_deref()).map_or_else(Utc::now, |at| at + interval))
+}
+
+/// Runs `job` and records the outcome. Fails without running it when it is
+/// already running.
+async fn run_job(app: &AppHandle, job: &'static Job, manual: bool) -> Result<()> {
+    {
+        let mut running = RUNNING
+            .lock()
+            .map_err(|_| Error::Internal("Scheduler lock poisoned".to_string()))?;
+        if !running.insert(job.id) {
+            return Err(Error::Validation(format!(
+                "The job \"{}\" is already running.",
+                job.id
+            )));
+        }
+    }
+    let result = (job.run)(app.clone(), manual).await;
+    if let Ok(mut running) = RUNNING.lock() {
+        running.remove(job.id);
+    }
+
+    let state = app.state::<AppState>();
+    let now = Utc::now().to_rfc3339();
+    let last_error = result.as_ref().err().map(|e| e.to_string());
+    if let Err(e) = update_state(&state, job.id, |s| {
+        s.last_run = Some(now);
+        s.last_error = last_error;
+    })
+    .await
+    {
+        eprintln!("[Scheduler] Failed to record run of {}: {}", job.id, e);
+    }
+    result
+}
+
+/// Starts every job that is due and not paused or already running.
+async fn tick(app: &AppHandle) -> Result<()> {
+    if crate::storage::is_read_only() {
+        return Ok(());
+    }
+    let state = app.state::<AppState>();
+    let states = {
+        let Ok(vault) = UnlockedVault::acquire(&state).await else {
+            return Ok(());
+        };
+        load_states(vault.pool(), vault.key()).await?
+    };
+
+    let now = Utc::now();
+    for job in JOBS {
+        let job_state = states.get(job.id).cloned().unwrap_or_default();
+        let due = next_run(job, &job_state).is_some_and(|at| at <= now);
+        if !due || is_running(job.id) {
+            continue;
+        }
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = run_job(&app, job, false).await {
+                eprintln!("[Scheduler] {}: {}", job.id, e);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Checks every few minutes which jobs are due.
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(STARTUP_DELAY).await;
+        loop {
+            if let Err(e) = tick(&app).await {
+                eprintln!("[Scheduler] {}", e);
+            }
+            tokio::time::sleep(TICK_INTERVAL).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn list_jobs(state: State<'_, AppState>) -> Result<Vec<JobInfo>> {
+    let vault = UnlockedVault::acquire(&state).await?;
+    let states = load_states(vault.pool(), vault.key()).await?;
+    Ok(JOBS
+        .iter()
+        .map(|job| {
+            let job_state = states.get(job.id).cloned().unwrap_or_default();
+            JobInfo {
+                id: job.id.to_string(),
+                description: job.description.to_string(),
+                interval_seconds: job.interval.as_secs(),
+                paused: job_state.paused,
+                running: is_running(job.id),
+                next_run: next_run(job, &job_state).map(|at| at.to_rfc3339()),
+                last_run: job_state.last_run,
+                last_error: job_state.last_error,
+            }
+        })
+        .collect())
+}
+
+/// Runs a job now, whether or not it is paused or due, and waits for it.
+#[tauri::command]
+pub async fn trigger_job(app: AppHandle, state: State<'_, AppState>, id: String) -> Result<()> {
+    let job = find_job(&id)?;
+    UnlockedVault::acquire_writable(&state).await?;
+    run_job(&app, job, true).await
+}
+
+#[tauri::command]
+pub async fn set_job_paused(state: State<'_, AppState>, id: String, paused: bool) -> Result<()> {
+    let job = find_job(&id)?;
+    update_state(&state, job.id, |s| s.paused = paused).await
+}
@@ -0,0 +1,176 @@
+//! Decrypted copies of attachments opened in another application. Each copy
+//! sits in its own private directory under a per-user root and is
+//! overwritten and removed after a timeout, when the vault locks, or on the
+//! next start if the app exited before either happened.
+
+use crate::error::{Error, Result};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+const DIR_NAME: &str = "pulsar-open";
+const WIPE_CHUNK_LEN: usize = 64 * 1024;
+
+/// The runtime directory where there is one, else the user's cache
+/// directory. Never the shared temp directory, where another local user
+/// could create the root first and plant links in it.
+fn root() -> Result<PathBuf> {
+    dirs::runtime_dir()
+        .or_else(dirs::cache_dir)
+        .map(|dir| dir.join(DIR_NAME))
+        .ok_or_else(|| Error::Internal("No per-user directory for temporary files".to_string()))
+}
+
+#[cfg(unix)]
+fn owned_privately(meta: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    meta.uid() == unsafe { libc::geteuid() } && meta.mode() & 0o777 == 0o700
+}
+
+/// Per-user directories on other platforms are already private.
+#[cfg(not(unix))]
+fn owned_privately(_meta: &std::fs::Metadata) -> bool {
+    true
+}
+
+/// Creates the root if needed and checks it is a real directory that only
+/// the current user can enter.
+async fn private_root() -> Result<PathBuf> {
+    let root = root()?;
+    create_private_dir(&root).await?;
+    let meta = fs::symlink_metadata(&root).await?;
+    if !meta.is_dir() || !owned_privately(&meta) {
+        return Err(Error::Internal(format!(
+            "Refusing to use {} for temporary files: it is not a private directory owned by this user",
+            root.display()
+        )));
+    }
+    Ok(root)
+}
+
+async fn create_private_dir(path: &Path) -> Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        builder.mode(0o700);
+    }
+    Ok(builder.create(path).await?)
+}
+
+/// Writes `data` to a new file named `file_name` that only the current user
+/// can read, and removes it after `lifetime`. Returns the file's path.
+pub(crate) async fn create(file_name: &str, data: &[u8], lifetime: Duration) -> Result<PathBuf> {
+    let file_name = Path::new(file_name)
+        .file_name()
+        .ok_or_else(|| Error::Validation("Invalid file name".to_string()))?;
+    let dir = private_root()
+        .await?
+        .join(hex::encode(rand::random::<[u8; 16]>()));
+    create_private_dir(&dir).await?;
+
+    let path = dir.join(file_name);
+    let mut options = fs::OpenOptions::new();
+    options.create_new(true).write(true);
+    #[cfg(unix)]
+    {
+        options.mode(0o600);
+    }
+    let written = async {
+        let mut file = options.open(&path).await?;
+        file.write_all(data).await?;
+        file.sync_all().await
+    }
+    .await;
+    if let Err(e) = written {
+        let _ = wipe_dir(&dir).await;
+        return Err(e.into());
+    }
+
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(lifetime).await;
+        if let Err(e) = wipe_dir(&dir).await {
+            eprintln!("[SecureTemp] Failed to remove {}: {}", dir.display(), e);
+        }
+    });
+    Ok(path)
+}
+
+/// Overwrites `path` with zeros before removing it. A symlink put in its
+/// place is not followed.
+async fn wipe_file(path: &Path) -> std::io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true);
+    #[cfg(unix)]
+    {
+        options.custom_flags(libc::O_NOFOLLOW);
+    }
+    let mut file = options.open(path).await?;
+    let len = file.metadata().await?.len();
+    let zeros = vec![0u8; WIPE_CHUNK_LEN];
+    let mut remaining = len;
+    while remaining > 0 {
+        let n = remaining.min(WIPE_CHUNK_LEN as u64) as usize;
+        file.write_all(&zeros[..n]).await?;
+        remaining -= n as u64;
+    }
+    file.sync_all().await?;
+    drop(file);
+    fs::remove_file(path).await
+}
+
+/// Wipes the files in `dir` and removes it. Links are unlinked, never
+/// followed, so nothing outside the directory is touched.
+async fn wipe_dir(dir: &Path) -> std::io::Result<()> {
+    let meta = match fs::symlink_metadata(dir).await {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    if !meta.is_dir() {
+        return fs::remove_file(dir).await;
+    }
+    let mut entries = fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let file_type = entry.file_type().await?;
+        if file_type.is_file() {
+            wipe_file(&entry.path()).await?;
+        } else if file_type.is_dir() {
+            // Copies are never nested; `remove_dir_all` does not follow links.
+            fs::remove_dir_all(entry.path()).await?;
+        } else {
+            fs::remove_file(entry.path()).await?;
+        }
+    }
+    fs::remove_dir(dir).await
+}
+
+/// Removes every copy still on disk. A copy the other application holds
+/// open on Windows cannot be removed and is retried on the next call.
+pub async fn wipe_all() {
+    let root = match private_root().await {
+        Ok(root) => root,
+        Err(e) => {
+            eprintln!("[SecureTemp] {}", e);
+            return;
+        }
+    };
+    let Ok(mut entries) = fs::read_dir(root).await else {
+        return;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        if let Err(e) = wipe_dir(&entry.path()).await {
+            eprintln!(
+                "[SecureTemp] Failed to remove {}: {}",
+                entry.path().display(),
+                e
+            );
+        }
+    }
+}
+
+/// Cleans up copies left behind when the app last exited.
+pub fn init() {
+    tauri::async_runtime::spawn(wipe_all());
+}
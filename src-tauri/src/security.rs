@@ -1,11 +1,22 @@
-use crate::db::utils::{get_db_pool, get_key};
+use crate::db::utils::UnlockedVault;
 use crate::encryption::{decrypt, encrypt};
 use crate::error::{Error, Result};
+use crate::integrity::BinaryIntegrityReport;
 use crate::state::AppState;
+use rand::rngs::OsRng;
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
-use tauri::State;
-use zeroize::{Zeroize, Zeroizing};
+use std::sync::OnceLock;
+use tauri::{Manager, State};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use zeroize::Zeroize;
+
+const DEVICE_ID_FILE: &str = "device-id";
+/// Devices not seen for this long are marked stale.
+const STALE_DEVICE_DAYS: i64 = 90;
+
+static DEVICE_ID: OnceLock<String> = OnceLock::new();
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -15,21 +26,56 @@ pub struct DeviceRecord {
     #[serde(default)]
     pub kind: String,
     #[serde(default)]
-    pub last_seen: Option<String>,
+    pub os: Option<String>,
+    #[serde(default)]
+    pub app_version: Option<String>,
     #[serde(default)]
+    pub first_seen: Option<String>,
+    #[serde(default)]
+    pub last_seen: Option<String>,
+    /// Set when listing, from this install's device ID.
+    #[serde(default, skip_deserializing)]
     pub is_current: bool,
+    /// Set when listing: not seen for `STALE_DEVICE_DAYS`.
+    #[serde(default, skip_deserializing)]
+    pub is_stale: bool,
 }
 
-async fn get_key_local(state: &AppState) -> Result<Zeroizing<Vec<u8>>> {
-    let guard = state.key.lock().await;
-    let opt = guard.clone();
-    drop(guard);
-    opt.ok_or(Error::VaultLocked)
+/// Loads this install's device ID from the app data dir, creating it on
+/// first launch.
+pub fn init_device_id(app: &tauri::AppHandle) {
+    let path = match app.path().app_data_dir() {
+        Ok(dir) => dir.join(DEVICE_ID_FILE),
+        Err(e) => {
+            eprintln!("[Devices] Failed to resolve app data dir: {}", e);
+            return;
+        }
+    };
+    let existing = std::fs::read_to_string(&path)
+        .ok()
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty());
+    let id = existing.unwrap_or_else(|| {
+        let mut bytes = [0u8; 16];
+        OsRng.fill_bytes(&mut bytes);
+        let id = hex::encode(bytes);
+        let written = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(&path, &id));
+        if let Err(e) = written {
+            eprintln!("[Devices] Failed to store device ID: {}", e);
+        }
+        id
+    });
+    let _ = DEVICE_ID.set(id);
 }
 
-async fn get_db_pool_local(state: &AppState) -> Result<SqlitePool> {
-    let guard = state.db.lock().await;
-    guard.clone().ok_or(Error::VaultNotLoaded)
+pub fn current_device_id() -> Result<&'static str> {
+    DEVICE_ID
+        .get()
+        .map(String::as_str)
+        .ok_or_else(|| Error::Internal("Device ID is not initialised".to_string()))
 }
 
 async fn load_devices(pool: &SqlitePool, key: &[u8]) -> Result<Vec<DeviceRecord>> {
@@ -61,15 +107,13 @@ async fn save_devices(pool: &SqlitePool, key: &[u8], devices: &[DeviceRecord]) -
     Ok(())
 }
 
+/// Records this install in the vault's device registry on unlock.
 pub async fn register_device(state: &AppState) -> Result<()> {
-    let key = get_key_local(state).await?;
-    let pool = get_db_pool_local(state).await?;
-    let mut devices = load_devices(&pool, key.as_slice()).await?;
-
-    let hostname = std::env::var("COMPUTERNAME")
-        .or_else(|_| std::env::var("HOSTNAME"))
-        .unwrap_or_else(|_| "Unknown Device".to_string());
+    let device_id = current_device_id()?;
+    let vault = UnlockedVault::acquire(state).await?;
+    let mut devices = load_devices(vault.pool(), vault.key()).await?;
 
+    let hostname = crate::db::activity::hostname().unwrap_or_else(|| "Unknown Device".to_string());
     let platform = if cfg!(target_os = "windows") {
         "Windows"
     } else if cfg!(target_os = "macos") {
@@ -79,52 +123,84 @@ pub async fn register_device(state: &AppState) -> Result<()> {
     } else {
         "Unknown"
     };
-
-    let device_id = format!("{hostname}-{platform}");
     let now = chrono::Utc::now().to_rfc3339();
 
-    let mut found = false;
-    for device in &mut devices {
-        if device.id == device_id {
-            device.last_seen = Some(now.clone());
-            device.is_current = true;
-            found = true;
-        } else {
-            device.is_current = false;
+    // Before devices had IDs of their own they were keyed by hostname and
+    // platform; adopt such an entry's history.
+    let legacy_id = format!("{hostname}-{platform}");
+    let legacy_seen = devices
+        .iter()
+        .find(|d| d.id == legacy_id)
+        .and_then(|d| d.last_seen.clone());
+    devices.retain(|d| d.id != legacy_id);
+
+    let index = match devices.iter().position(|d| d.id == device_id) {
+        Some(index) => index,
+        None => {
+            devices.push(DeviceRecord {
+                id: device_id.to_string(),
+                name: String::new(),
+                kind: String::new(),
+                os: None,
+                app_version: None,
+                first_seen: Some(legacy_seen.unwrap_or_else(|| now.clone())),
+                last_seen: None,
+                is_current: false,
+                is_stale: false,
+            });
+            devices.len() - 1
         }
-    }
-
-    if !found {
-        devices.push(DeviceRecord {
-            id: device_id,
-            name: hostname,
-            kind: platform.to_lowercase(),
-            last_seen: Some(now),
-            is_current: true,
-        });
-    }
-
-    save_devices(&pool, key.as_slice(), &devices).await
+    };
+    let device = &mut devices[index];
+    device.name = hostname;
+    device.kind = platform.to_lowercase();
+    device.os = Some(format!(
+        "{} {}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    ));
+    device.app_version = Some(env!("CARGO_PKG_VERSION").to_string());
+    device.last_seen = Some(now);
+
+    save_devices(vault.pool(), vault.key(), &devices).await
 }
 
 #[tauri::command]
 pub async fn list_devices(state: State<'_, AppState>) -> Result<Vec<DeviceRecord>> {
-    let key = get_key(&state).await?;
-    let pool = get_db_pool(&state).await?;
-    let mut devices = load_devices(&pool, key.as_slice()).await?;
-    devices
-        .iter_mut()
-        .filter(|d| d.kind.trim().is_empty())
-        .for_each(|d| d.kind = "unknown".to_string());
+    let vault = UnlockedVault::acquire(&state).await?;
+    let mut devices = load_devices(vault.pool(), vault.key()).await?;
+    let current = DEVICE_ID.get();
+    let stale_before = chrono::Utc::now() - chrono::Duration::days(STALE_DEVICE_DAYS);
+    for device in &mut devices {
+        if device.kind.trim().is_empty() {
+            device.kind = "unknown".to_string();
+        }
+        device.is_current = current == Some(&device.id);
+        device.is_stale = !device.is_current
+            && device
+                .last_seen
+                .as_deref()
+                .and_then(|seen| chrono::DateTime::parse_from_rfc3339(seen).ok())
+                .is_none_or(|seen| seen < stale_before);
+    }
+    devices.sort_by(|a, b| {
+        b.is_current
+            .cmp(&a.is_current)
+            .then_with(|| b.last_seen.cmp(&a.last_seen))
+    });
 
     Ok(devices)
 }
 
 #[tauri::command]
 pub async fn remove_device(state: State<'_, AppState>, device_id: String) -> Result<()> {
-    let key = get_key(&state).await?;
-    let pool = get_db_pool(&state).await?;
-    let mut devices = load_devices(&pool, key.as_slice()).await?;
+    if DEVICE_ID.get() == Some(&device_id) {
+        return Err(Error::Validation(
+            "This device cannot be removed while it is in use.".to_string(),
+        ));
+    }
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let mut devices = load_devices(vault.pool(), vault.key()).await?;
     let original_len = devices.len();
     devices.retain(|device| device.id != device_id);
 
@@ -132,25 +208,42 @@ pub async fn remove_device(state: State<'_, AppState>, device_id: String) -> Res
         return Err(Error::Internal("Device not found".to_string()));
     }
 
-    save_devices(&pool, key.as_slice(), &devices).await
+    save_devices(vault.pool(), vault.key(), &devices).await?;
+    sqlx::query("DELETE FROM configuration WHERE key = ?")
+        .bind(crate::sessions::config_key(&device_id))
+        .execute(vault.pool())
+        .await?;
+    Ok(())
 }
 
+/// Removes every device but this one and ends their sessions.
 #[tauri::command]
 pub async fn revoke_all_devices(state: State<'_, AppState>) -> Result<()> {
-    let key = get_key(&state).await?;
-    let pool = get_db_pool(&state).await?;
-    let mut devices = load_devices(&pool, key.as_slice()).await?;
-    devices.retain(|device| device.is_current);
-    save_devices(&pool, key.as_slice(), &devices).await
+    let device_id = current_device_id()?;
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let mut devices = load_devices(vault.pool(), vault.key()).await?;
+    devices.retain(|device| device.id == device_id);
+    save_devices(vault.pool(), vault.key(), &devices).await?;
+    sqlx::query("DELETE FROM configuration WHERE key LIKE 'session:%' AND key != ?")
+        .bind(crate::sessions::config_key(device_id))
+        .execute(vault.pool())
+        .await?;
+    Ok(())
 }
 
 #[tauri::command]
-pub async fn wipe_memory(state: State<'_, AppState>) -> Result<()> {
+pub async fn wipe_memory(app: tauri::AppHandle, state: State<'_, AppState>) -> Result<()> {
+    crate::vault_watch::unwatch();
+    state.clear_overview_cache();
+    crate::auth::reverify::clear(&state).await;
+    crate::sessions::end(&state).await;
+    crate::secure_temp::wipe_all().await;
+    state.parked_keys.lock().await.clear();
+
     {
+        // The last holder of the shared key zeroizes it on drop.
         let mut key_guard = state.key.lock().await;
-        if let Some(mut key) = key_guard.take() {
-            key.zeroize();
-        }
+        *key_guard = None;
     }
 
     {
@@ -167,9 +260,60 @@ pub async fn wipe_memory(state: State<'_, AppState>) -> Result<()> {
         }
     }
 
+    crate::events::vault_locked(&app);
     Ok(())
 }
 
+/// Zeroizes the vault keys and the settings key and clears the clipboard.
+/// Locks are only tried, never waited on, so this is safe from a panic hook
+/// on a thread that may hold one of them; a key whose lock is held is left
+/// for its owner to drop.
+pub fn wipe_secrets_now(app: &tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    if let Ok(mut key_guard) = state.key.try_lock() {
+        // Dropping the last reference zeroizes the key.
+        key_guard.take();
+    }
+    if let Ok(mut pending_guard) = state.pending_key.try_lock() {
+        if let Some(mut pending) = pending_guard.take() {
+            pending.key.zeroize();
+        }
+    }
+    if let Ok(mut parked) = state.parked_keys.try_lock() {
+        parked.clear();
+    }
+    if let Ok(mut cache) = state.overview_cache.try_lock() {
+        cache.clear();
+    }
+    crate::settings::clear_cached_settings_key();
+    let _ = app.clipboard().clear();
+}
+
+/// Excludes the app's windows from screenshots, screen recording and remote
+/// desktop capture (SetWindowDisplayAffinity with WDA_EXCLUDEFROMCAPTURE on
+/// Windows, `NSWindowSharingNone` on macOS). Other platforms ignore it.
+#[tauri::command]
+pub async fn set_screen_capture_protection(app: tauri::AppHandle, enabled: bool) -> Result<()> {
+    for window in app.webview_windows().values() {
+        window
+            .set_content_protected(enabled)
+            .map_err(|e| Error::Internal(format!("Failed to set capture protection: {}", e)))?;
+    }
+    Ok(())
+}
+
+/// Wipes secrets before the default panic output, so a panic that takes the
+/// process down does not leave keys behind in memory. The vault is locked
+/// even when the panic was contained to a background task.
+pub fn install_panic_hook(app: tauri::AppHandle) {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        wipe_secrets_now(&app);
+        crate::events::vault_locked(&app);
+        previous(info);
+    }));
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SecurityReport {
@@ -179,6 +323,7 @@ pub struct SecurityReport {
     pub unique_passwords_count: usize,
     pub total_passwords_count: usize,
     pub overall_health_score: f64,
+    pub binary_integrity: Option<BinaryIntegrityReport>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -189,12 +334,7 @@ pub struct ReusedPasswordGroup {
     pub count: usize,
 }
 
-#[tauri::command]
-pub async fn get_security_report(state: State<'_, AppState>) -> Result<SecurityReport> {
-    let key = get_key(&state).await?;
-    let pool = get_db_pool(&state).await?;
-    let items = crate::db::get_password_audit_data_impl(&pool, key.as_slice()).await?;
-
+fn compute_security_report(items: &[crate::db::AuditPasswordItem]) -> SecurityReport {
     use sha2::{Digest, Sha256};
     use std::collections::HashMap;
 
@@ -202,7 +342,7 @@ pub async fn get_security_report(state: State<'_, AppState>) -> Result<SecurityR
     let mut breached_passwords = Vec::new();
     let total_passwords_count = items.len();
 
-    for item in &items {
+    for item in items {
         if !item.password.as_str().is_empty() && item.password.as_str() != "N/A" {
             let mut hasher = Sha256::new();
             hasher.update(item.password.as_str().as_bytes());
@@ -239,7 +379,7 @@ pub async fn get_security_report(state: State<'_, AppState>) -> Result<SecurityR
         }
     }
 
-    for item in &items {
+    for item in items {
         let mut is_weak = false;
         let p = item.password.as_str();
 
@@ -303,21 +443,258 @@ pub async fn get_security_report(state: State<'_, AppState>) -> Result<SecurityR
         score = (score - reused_penalty - weak_penalty - breached_penalty).max(0.0);
     }
 
-    Ok(SecurityReport {
+    SecurityReport {
         reused_passwords,
         weak_passwords,
         breached_passwords,
         unique_passwords_count,
         total_passwords_count,
         overall_health_score: score,
+        binary_integrity: None,
+    }
+}
+
+/// Metrics kept per week for `get_security_trends`. Never contains secrets or
+/// item identifiers.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SecuritySnapshot {
+    pub item_count: usize,
+    pub weak_count: usize,
+    pub reused_count: usize,
+    pub breached_count: usize,
+    pub score: f64,
+}
+
+impl From<&SecurityReport> for SecuritySnapshot {
+    fn from(report: &SecurityReport) -> Self {
+        Self {
+            item_count: report.total_passwords_count,
+            weak_count: report.weak_passwords.len(),
+            reused_count: report.reused_passwords.iter().map(|g| g.count).sum(),
+            breached_count: report.breached_passwords.len(),
+            score: report.overall_health_score,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SecurityTrendPoint {
+    /// ISO week, e.g. `2026-W42`.
+    pub week: String,
+    pub recorded_at: String,
+    #[serde(flatten)]
+    pub snapshot: SecuritySnapshot,
+}
+
+const DEFAULT_TREND_WEEKS: u32 = 52;
+
+/// Stores the first report of each ISO week. Later reports in the same week
+/// leave it untouched so reading the report stays cheap.
+async fn record_security_snapshot(
+    state: &AppState,
+    vault: &UnlockedVault,
+    report: &SecurityReport,
+) -> Result<()> {
+    if crate::storage::is_read_only() {
+        return Ok(());
+    }
+
+    let now = chrono::Utc::now();
+    let week = now.format("%G-W%V").to_string();
+    let snapshot = SecuritySnapshot::from(report);
+
+    crate::write_buffer::mark_dirty(state).await;
+    sqlx::query(
+        "INSERT OR IGNORE INTO security_snapshots (week, data, recorded_at) VALUES (?, ?, ?)",
+    )
+    .bind(week)
+    .bind(encrypt(&serde_json::to_string(&snapshot)?, vault.key())?)
+    .bind(now.to_rfc3339())
+    .execute(vault.pool())
+    .await?;
+    Ok(())
+}
+
+pub(crate) async fn build_security_report(
+    state: &AppState,
+    vault: &UnlockedVault,
+) -> Result<SecurityReport> {
+    let items = crate::db::get_password_audit_data_impl(vault.pool(), vault.key()).await?;
+    let mut report = compute_security_report(&items);
+    drop(items);
+
+    if let Err(e) = record_security_snapshot(state, vault, &report).await {
+        eprintln!("[Security] Failed to record security snapshot: {}", e);
+    }
+
+    report.binary_integrity = state.binary_integrity.lock().await.clone();
+    Ok(report)
+}
+
+/// Current aggregate metrics without any per-item detail.
+pub(crate) async fn current_security_snapshot(
+    state: &AppState,
+    vault: &UnlockedVault,
+) -> Result<SecuritySnapshot> {
+    let report = build_security_report(state, vault).await?;
+    Ok(SecuritySnapshot::from(&report))
+}
+
+#[tauri::command]
+pub async fn get_security_report(state: State<'_, AppState>) -> Result<SecurityReport> {
+    let vault = UnlockedVault::acquire(&state).await?;
+    build_security_report(&state, &vault).await
+}
+
+/// Weekly security metrics, oldest first, covering at most `weeks` weeks.
+#[tauri::command]
+pub async fn get_security_trends(
+    state: State<'_, AppState>,
+    weeks: Option<u32>,
+) -> Result<Vec<SecurityTrendPoint>> {
+    let vault = UnlockedVault::acquire(&state).await?;
+    // Makes sure the current week is present.
+    build_security_report(&state, &vault).await?;
+
+    let limit = weeks.unwrap_or(DEFAULT_TREND_WEEKS).clamp(1, 520);
+    let rows = sqlx::query(
+        "SELECT week, data, recorded_at FROM security_snapshots ORDER BY week DESC LIMIT ?",
+    )
+    .bind(limit as i64)
+    .fetch_all(vault.pool())
+    .await?;
+
+    let mut points = Vec::with_capacity(rows.len());
+    for row in rows {
+        use sqlx::Row;
+        let data_enc: String = row.get("data");
+        let Some(snapshot) = decrypt(&data_enc, vault.key())
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+        else {
+            continue;
+        };
+        points.push(SecurityTrendPoint {
+            week: row.get("week"),
+            recorded_at: row.get("recorded_at"),
+            snapshot,
+        });
+    }
+    points.reverse();
+    Ok(points)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReuseCluster {
+    pub cluster_id: String,
+    pub item_ids: Vec<i64>,
+    pub domains: Vec<String>,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordReuseGraph {
+    pub clusters: Vec<ReuseCluster>,
+    pub total_items: usize,
+    pub reused_items: usize,
+}
+
+fn derive_reuse_hash_key(master_key: &[u8]) -> Result<[u8; 32]> {
+    use hkdf::Hkdf;
+    use sha2::Sha256;
+
+    let hk = Hkdf::<Sha256>::new(None, master_key);
+    let mut out = [0u8; 32];
+    hk.expand(b"pulsar:reuse-graph", &mut out)
+        .map_err(|_| Error::Internal("Failed to derive reuse graph key".to_string()))?;
+    Ok(out)
+}
+
+pub fn extract_domain(url: &str) -> Option<String> {
+    let trimmed = url.trim();
+    let without_scheme = trimmed
+        .split_once("://")
+        .map(|(_, rest)| rest)
+        .unwrap_or(trimmed);
+    let authority = without_scheme.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?;
+    let host = host.split(':').next()?.trim().to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host).to_string();
+
+    if host.is_empty() {
+        None
+    } else {
+        Some(host)
+    }
+}
+
+#[tauri::command]
+pub async fn get_password_reuse_graph(state: State<'_, AppState>) -> Result<PasswordReuseGraph> {
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+    use std::collections::{BTreeSet, HashMap};
+
+    let vault = UnlockedVault::acquire(&state).await?;
+    let items = crate::db::get_password_audit_data_impl(vault.pool(), vault.key()).await?;
+
+    let mut hash_key = derive_reuse_hash_key(vault.key())?;
+    let mut groups: HashMap<Vec<u8>, Vec<&crate::db::AuditPasswordItem>> = HashMap::new();
+
+    for item in &items {
+        let p = item.password.as_str();
+        if p.is_empty() || p == "N/A" {
+            continue;
+        }
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&hash_key)
+            .expect("HMAC can take key of any size");
+        mac.update(p.as_bytes());
+        let digest = mac.finalize().into_bytes().to_vec();
+        groups.entry(digest).or_default().push(item);
+    }
+    hash_key.zeroize();
+
+    let mut clusters = Vec::new();
+    let mut reused_items = 0;
+    for (digest, members) in groups {
+        if members.len() < 2 {
+            continue;
+        }
+        reused_items += members.len();
+
+        let domains: BTreeSet<String> = members
+            .iter()
+            .filter_map(|m| m.url.as_deref().and_then(extract_domain))
+            .collect();
+
+        let mut item_ids: Vec<i64> = members.iter().map(|m| m.id).collect();
+        item_ids.sort_unstable();
+
+        clusters.push(ReuseCluster {
+            cluster_id: hex::encode(&digest[..8]),
+            count: item_ids.len(),
+            item_ids,
+            domains: domains.into_iter().collect(),
+        });
+    }
+
+    clusters.sort_by(|a, b| b.count.cmp(&a.count).then(a.item_ids.cmp(&b.item_ids)));
+
+    Ok(PasswordReuseGraph {
+        clusters,
+        total_items: items.len(),
+        reused_items,
     })
 }
 
 #[tauri::command]
 pub async fn run_integrity_check(state: State<'_, AppState>) -> Result<String> {
-    let pool = get_db_pool(&state).await?;
+    let vault = UnlockedVault::acquire(&state).await?;
     let result: (String,) = sqlx::query_as("PRAGMA integrity_check;")
-        .fetch_one(&pool)
+        .fetch_one(vault.pool())
         .await?;
     Ok(result.0)
 }
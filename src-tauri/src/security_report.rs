@@ -0,0 +1,125 @@
+//! A single structured report of the vault's security posture for periodic
+//! review by the user or an auditor: the password audit, how the vault is
+//! protected, backup recency, known devices and the state of the logs. It
+//! lists item IDs but never item names or secrets. It can be written to a
+//! file, optionally encrypted with a passphrase.
+
+use crate::auth::attempts::UnlockAttemptHistory;
+use crate::auth::reverify::ReverificationPolicy;
+use crate::auth::types::Argon2ParamsResponse;
+use crate::db::activity::ActivityLogVerification;
+use crate::db::utils::UnlockedVault;
+use crate::error::{Error, Result};
+use crate::security::{DeviceRecord, SecurityReport};
+use crate::state::AppState;
+use crate::utils::write_sensitive_bytes;
+use chrono::Utc;
+use serde::Serialize;
+use std::path::PathBuf;
+use tauri::{AppHandle, State};
+use zeroize::Zeroizing;
+
+const REPORT_VERSION: u8 = 1;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultProtection {
+    pub argon2: Argon2ParamsResponse,
+    pub biometrics_enabled: bool,
+    pub login_totp_enabled: bool,
+    pub reverification: Option<ReverificationPolicy>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupStatus {
+    pub last_backup: Option<String>,
+    pub automatic_backups: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FullSecurityReport {
+    pub version: u8,
+    pub generated_at: String,
+    pub app_version: String,
+    pub vault_name: Option<String>,
+    pub audit: SecurityReport,
+    pub protection: VaultProtection,
+    pub backups: BackupStatus,
+    pub devices: Vec<DeviceRecord>,
+    pub activity_log: ActivityLogVerification,
+    pub unlock_attempts: UnlockAttemptHistory,
+}
+
+async fn build_report(app: &AppHandle, state: &State<'_, AppState>) -> Result<FullSecurityReport> {
+    let vault = UnlockedVault::acquire(state).await?;
+    let audit = crate::security::build_security_report(state, &vault).await?;
+    let vault_name = crate::auth::load_vault_display_from_db(vault.pool())
+        .await?
+        .map(|d| d.name)
+        .filter(|n| !n.trim().is_empty());
+    let reverification = crate::db::vault_settings::load_vault_settings(vault.pool(), vault.key())
+        .await?
+        .reverification;
+    let (last_backup, automatic_backups) = crate::health_report::backup_status(app, &vault).await?;
+    drop(vault);
+
+    Ok(FullSecurityReport {
+        version: REPORT_VERSION,
+        generated_at: Utc::now().to_rfc3339(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        vault_name,
+        audit,
+        protection: VaultProtection {
+            argon2: crate::auth::get_argon2_params(state.clone()).await?,
+            biometrics_enabled: crate::auth::is_biometrics_enabled(app.clone(), state.clone())
+                .await?,
+            login_totp_enabled: crate::auth::is_login_totp_configured(state.clone()).await?,
+            reverification,
+        },
+        backups: BackupStatus {
+            last_backup,
+            automatic_backups,
+        },
+        devices: crate::security::list_devices(state.clone()).await?,
+        activity_log: crate::db::verify_activity_log(state.clone()).await?,
+        unlock_attempts: crate::auth::attempts::get_unlock_attempt_history(state.clone()).await?,
+    })
+}
+
+/// Builds the report and, given a `destination`, also writes it there as
+/// JSON. With a `passphrase` the file is encrypted in the format of
+/// passphrase-protected entry exports.
+#[tauri::command]
+pub async fn generate_security_report(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    destination: Option<String>,
+    passphrase: Option<String>,
+) -> Result<FullSecurityReport> {
+    let passphrase = passphrase.map(Zeroizing::new);
+    if passphrase.as_ref().is_some_and(|p| p.is_empty()) {
+        return Err(Error::Validation(
+            "The report passphrase cannot be empty.".to_string(),
+        ));
+    }
+    let report = build_report(&app, &state).await?;
+
+    if let Some(destination) = destination {
+        let json = serde_json::to_vec_pretty(&report)?;
+        let bytes = match passphrase {
+            Some(passphrase) => {
+                let sealed = tauri::async_runtime::spawn_blocking(move || {
+                    crate::crypto::seal_with_passphrase(&json, passphrase.as_str())
+                })
+                .await
+                .map_err(|e| Error::Internal(format!("Runtime error: {}", e)))??;
+                serde_json::to_vec_pretty(&sealed)?
+            }
+            None => json,
+        };
+        write_sensitive_bytes(&PathBuf::from(destination), &bytes).await?;
+    }
+    Ok(report)
+}
@@ -0,0 +1,103 @@
+//! Per-device unlock sessions. Each unlock stores a fresh token for this
+//! device in the vault's configuration table. Removing or revoking a device
+//! deletes its token, and once the vault file reaches that device (usually
+//! through a sync client) its next vault access finds the token gone and
+//! locks the vault.
+
+use crate::error::{Error, Result};
+use crate::state::AppState;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sqlx::SqlitePool;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use subtle::ConstantTimeEq;
+use tauri::{AppHandle, Emitter, Manager};
+
+pub const SESSION_REVOKED_EVENT: &str = "vault:session-revoked";
+
+/// How long a successful check is trusted before the table is read again.
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+
+#[derive(Debug, Clone)]
+pub struct ActiveSession {
+    pub device_id: String,
+    token: String,
+    checked_at: Instant,
+}
+
+pub fn init(app: AppHandle) {
+    let _ = APP_HANDLE.set(app);
+}
+
+pub fn config_key(device_id: &str) -> String {
+    format!("session:{device_id}")
+}
+
+/// Stores a new session token for this device. Until it is stored, vault
+/// access is not checked.
+pub async fn start(state: &AppState, pool: &SqlitePool, device_id: &str) -> Result<()> {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let token = hex::encode(bytes);
+    sqlx::query("INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)")
+        .bind(config_key(device_id))
+        .bind(&token)
+        .execute(pool)
+        .await?;
+    *state.session.lock().await = Some(ActiveSession {
+        device_id: device_id.to_string(),
+        token,
+        checked_at: Instant::now(),
+    });
+    Ok(())
+}
+
+pub async fn end(state: &AppState) {
+    *state.session.lock().await = None;
+}
+
+/// Fails with `SessionRevoked`, and locks the vault, when this device's
+/// session was revoked from another device.
+pub async fn ensure_active(state: &AppState, pool: &SqlitePool) -> Result<()> {
+    let (device_id, token) = {
+        let guard = state.session.lock().await;
+        match guard.as_ref() {
+            Some(session) if session.checked_at.elapsed() >= CHECK_INTERVAL => {
+                (session.device_id.clone(), session.token.clone())
+            }
+            _ => return Ok(()),
+        }
+    };
+
+    let stored: Option<String> =
+        sqlx::query_scalar("SELECT value FROM configuration WHERE key = ?")
+            .bind(config_key(&device_id))
+            .fetch_optional(pool)
+            .await?;
+    let active = stored.is_some_and(|stored| bool::from(stored.as_bytes().ct_eq(token.as_bytes())));
+    if active {
+        if let Some(session) = state.session.lock().await.as_mut() {
+            session.checked_at = Instant::now();
+        }
+        return Ok(());
+    }
+
+    end(state).await;
+    force_lock();
+    Err(Error::SessionRevoked)
+}
+
+fn force_lock() {
+    let Some(app) = APP_HANDLE.get().cloned() else {
+        return;
+    };
+    let _ = app.emit(SESSION_REVOKED_EVENT, ());
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = crate::auth::lock(app.clone(), app.state::<AppState>()).await {
+            eprintln!("[Sessions] Failed to lock revoked session: {}", e);
+        }
+    });
+}
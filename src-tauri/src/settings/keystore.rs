@@ -0,0 +1,290 @@
+//! Where the seed of the settings key is kept. The OS keyring is the default.
+//! Where no keyring is reachable (headless Linux, sandboxed Flatpak) the seed
+//! can instead live in a file wrapped with an Argon2 key derived from a
+//! master password. Settings are then unreadable until a vault is unlocked
+//! with the password the file was wrapped with.
+
+use crate::auth::types::Argon2ParamsConfig;
+use crate::auth::{derive_key, verify_master_password_internal};
+use crate::error::{Error, Result};
+use crate::state::AppState;
+use base64::{engine::general_purpose, Engine as _};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{OnceLock, RwLock};
+use tauri::{AppHandle, Manager, State};
+use zeroize::Zeroizing;
+
+const SEED_FILE_NAME: &str = ".settings-seed.json";
+const SEED_FILE_VERSION: u8 = 1;
+
+static SEED_FILE: OnceLock<PathBuf> = OnceLock::new();
+static KEYRING_ERROR: RwLock<Option<String>> = RwLock::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeystoreBackend {
+    Keyring,
+    EncryptedFile,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeystoreStatus {
+    pub backend: KeystoreBackend,
+    /// Whether the settings key is available. An encrypted-file seed stays
+    /// locked until a vault is unlocked with its password.
+    pub unlocked: bool,
+    /// The keyring could not be used, so the key was derived from the
+    /// hardware ID alone and is only as secret as that ID.
+    pub degraded: bool,
+    pub keyring_error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SeedFile {
+    version: u8,
+    salt_b64: String,
+    nonce_b64: String,
+    ciphertext_b64: String,
+    memory_kib: u32,
+    time_cost: u32,
+    parallelism: u32,
+}
+
+pub fn init(app: &AppHandle) {
+    match app.path().app_data_dir() {
+        Ok(dir) => {
+            let _ = SEED_FILE.set(dir.join(SEED_FILE_NAME));
+        }
+        Err(e) => eprintln!("[Keystore] Failed to resolve app data dir: {}", e),
+    }
+}
+
+fn seed_file_path() -> Result<&'static PathBuf> {
+    SEED_FILE
+        .get()
+        .ok_or_else(|| Error::Internal("Keystore is not initialised".to_string()))
+}
+
+pub fn backend() -> KeystoreBackend {
+    match SEED_FILE.get() {
+        Some(path) if path.exists() => KeystoreBackend::EncryptedFile,
+        _ => KeystoreBackend::Keyring,
+    }
+}
+
+pub(super) fn note_keyring_error(error: Option<String>) {
+    if let Ok(mut guard) = KEYRING_ERROR.write() {
+        *guard = error;
+    }
+}
+
+fn keyring_error() -> Option<String> {
+    KEYRING_ERROR.read().ok().and_then(|e| e.clone())
+}
+
+fn wrap_seed(password: &str, seed: &str) -> Result<SeedFile> {
+    let params = Argon2ParamsConfig::default();
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let mut nonce = [0u8; 24];
+    OsRng.fill_bytes(&mut nonce);
+
+    let key = Zeroizing::new(derive_key(password, &salt, &params)?);
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key.as_slice()));
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce), seed.as_bytes())
+        .map_err(|_| Error::Encryption("Failed to wrap settings seed".to_string()))?;
+
+    Ok(SeedFile {
+        version: SEED_FILE_VERSION,
+        salt_b64: general_purpose::STANDARD.encode(salt),
+        nonce_b64: general_purpose::STANDARD.encode(nonce),
+        ciphertext_b64: general_purpose::STANDARD.encode(ciphertext),
+        memory_kib: params.memory_kib,
+        time_cost: params.time_cost,
+        parallelism: params.parallelism,
+    })
+}
+
+fn unwrap_seed(password: &str, file: &SeedFile) -> Result<Zeroizing<String>> {
+    if file.version != SEED_FILE_VERSION {
+        return Err(Error::Internal(format!(
+            "Unsupported settings seed file version {}",
+            file.version
+        )));
+    }
+    let decode = |value: &str| {
+        general_purpose::STANDARD
+            .decode(value)
+            .map_err(|e| Error::Internal(format!("Corrupt settings seed file: {}", e)))
+    };
+    let salt = decode(&file.salt_b64)?;
+    let nonce = decode(&file.nonce_b64)?;
+    let ciphertext = decode(&file.ciphertext_b64)?;
+    if nonce.len() != 24 {
+        return Err(Error::Internal(
+            "Corrupt settings seed file: bad nonce".to_string(),
+        ));
+    }
+
+    let params = Argon2ParamsConfig {
+        memory_kib: file.memory_kib,
+        time_cost: file.time_cost,
+        parallelism: file.parallelism,
+    };
+    crate::auth::validate_argon_params(&params)?;
+    let key = Zeroizing::new(derive_key(password, &salt, &params)?);
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key.as_slice()));
+    let seed = cipher
+        .decrypt(XNonce::from_slice(&nonce), ciphertext.as_ref())
+        .map_err(|_| Error::InvalidPassword)?;
+    String::from_utf8(seed)
+        .map(Zeroizing::new)
+        .map_err(|_| Error::Internal("Corrupt settings seed file".to_string()))
+}
+
+async fn read_seed_file() -> Result<SeedFile> {
+    let json = tokio::fs::read_to_string(seed_file_path()?).await?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+async fn write_seed_file(file: &SeedFile) -> Result<()> {
+    let path = seed_file_path()?;
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let tmp = path.with_extension("tmp");
+    tokio::fs::write(&tmp, serde_json::to_vec(file)?).await?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = tokio::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600)).await;
+    }
+    tokio::fs::rename(&tmp, path).await?;
+    Ok(())
+}
+
+async fn run_blocking<T, F>(f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| Error::Internal(format!("Runtime error: {}", e)))?
+}
+
+/// Loads the settings key from the seed file after a successful unlock. A
+/// password that does not open the file (another vault's) is not an error;
+/// settings just stay locked.
+pub async fn unlock_with_password(password: &str) {
+    if backend() != KeystoreBackend::EncryptedFile || super::cached_settings_key().is_some() {
+        return;
+    }
+    let file = match read_seed_file().await {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!("[Keystore] Failed to read settings seed file: {}", e);
+            return;
+        }
+    };
+    let password = Zeroizing::new(password.to_string());
+    match run_blocking(move || unwrap_seed(password.as_str(), &file)).await {
+        Ok(seed) => match super::derive_settings_key(seed.as_str()) {
+            Ok(key) => super::set_cached_settings_key(Some(key)),
+            Err(e) => eprintln!("[Keystore] Failed to derive settings key: {}", e),
+        },
+        Err(Error::InvalidPassword) => {}
+        Err(e) => eprintln!("[Keystore] Failed to unwrap settings seed: {}", e),
+    }
+}
+
+/// Re-wraps the seed file after a master password change so it keeps
+/// opening with the new password.
+pub async fn rewrap(current_password: &str, new_password: &str) -> Result<()> {
+    if backend() != KeystoreBackend::EncryptedFile {
+        return Ok(());
+    }
+    let file = read_seed_file().await?;
+    let current = Zeroizing::new(current_password.to_string());
+    let new = Zeroizing::new(new_password.to_string());
+    let rewrapped = run_blocking(move || {
+        let seed = unwrap_seed(current.as_str(), &file)?;
+        wrap_seed(new.as_str(), seed.as_str())
+    })
+    .await;
+    match rewrapped {
+        Ok(file) => write_seed_file(&file).await,
+        // Wrapped with another vault's password; nothing to update here.
+        Err(Error::InvalidPassword) => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+#[tauri::command]
+pub async fn get_keystore_status() -> Result<KeystoreStatus> {
+    let backend = backend();
+    let keyring_error = keyring_error();
+    Ok(KeystoreStatus {
+        backend,
+        unlocked: super::cached_settings_key().is_some(),
+        degraded: backend == KeystoreBackend::Keyring && keyring_error.is_some(),
+        keyring_error,
+    })
+}
+
+/// Moves the settings key to `backend`, re-encrypting the stored settings.
+/// The encrypted-file backend is wrapped with the open vault's master
+/// password, which must be supplied.
+#[tauri::command]
+pub async fn set_keystore_backend(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    backend: KeystoreBackend,
+    password: String,
+) -> Result<KeystoreStatus> {
+    let password = Zeroizing::new(password);
+    if !verify_master_password_internal(&state, password.as_str()).await? {
+        return Err(Error::InvalidPassword);
+    }
+    if backend == self::backend() {
+        return get_keystore_status().await;
+    }
+
+    // Read with the old key before it is replaced.
+    let settings = super::get_all_settings_internal(&app).await?;
+    if self::backend() == KeystoreBackend::EncryptedFile && super::cached_settings_key().is_none() {
+        return Err(Error::Validation(
+            "The settings seed file does not open with this vault's master password.".to_string(),
+        ));
+    }
+
+    match backend {
+        KeystoreBackend::EncryptedFile => {
+            let mut seed_bytes = Zeroizing::new([0u8; 32]);
+            OsRng.fill_bytes(&mut seed_bytes[..]);
+            let seed = Zeroizing::new(hex::encode(&seed_bytes[..]));
+            let key = super::derive_settings_key(seed.as_str())?;
+            let file = run_blocking(move || wrap_seed(password.as_str(), seed.as_str())).await?;
+            write_seed_file(&file).await?;
+            super::set_cached_settings_key(Some(key));
+        }
+        KeystoreBackend::Keyring => {
+            tokio::fs::remove_file(seed_file_path()?).await?;
+            super::set_cached_settings_key(None);
+        }
+    }
+
+    if let Some(settings) = settings {
+        super::store_settings(&app, &settings).await?;
+    }
+    get_keystore_status().await
+}
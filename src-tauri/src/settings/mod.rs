@@ -2,21 +2,40 @@ use crate::encryption::{decrypt, encrypt};
 use crate::error::{Error, Result};
 use keyring::Entry;
 use sha2::Sha256;
+use tauri::Emitter;
 use tauri_plugin_store::StoreBuilder;
+use zeroize::Zeroize;
 
+pub mod keystore;
 pub mod system;
+#[cfg(target_os = "windows")]
+mod windows_protect;
 pub use system::*;
 
 const SETTINGS_KEYRING_SERVICE: &str = "Pulsar-App-Settings-v4";
 const SETTINGS_KEYRING_USER: &str = "StableRoot";
 const SETTINGS_SALT: &[u8] = b"pulsar-v4-hardware-bound-salt";
+/// Seed used before macOS and Linux had a hardware ID. Settings encrypted
+/// under it (or under a bare hardware ID) are re-encrypted with the current
+/// key the next time they are read.
+const LEGACY_FALLBACK_SEED: &str = "fallback-stable-id-pulsar";
 
-static SETTINGS_KEY_CACHE: std::sync::OnceLock<Vec<u8>> = std::sync::OnceLock::new();
+pub const SETTINGS_CHANGED_EVENT: &str = "settings_changed";
+
+static SETTINGS_KEY_CACHE: std::sync::RwLock<Option<Vec<u8>>> = std::sync::RwLock::new(None);
 static STORE_MUTEX: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
 
 #[derive(serde::Deserialize)]
 struct AllSettings {
     general: GeneralSettings,
+    #[serde(default)]
+    security: SecuritySettings,
+}
+
+#[derive(serde::Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct SecuritySettings {
+    screen_capture_protection: bool,
 }
 
 #[derive(serde::Deserialize)]
@@ -26,85 +45,306 @@ struct GeneralSettings {
     show_in_system_tray: bool,
 }
 
-fn get_hardware_id() -> String {
-    #[cfg(target_os = "windows")]
-    {
-        use windows::core::HSTRING;
-        use windows::Win32::System::Registry::{
-            RegCloseKey, RegOpenKeyExW, RegQueryValueExW, HKEY, HKEY_LOCAL_MACHINE, KEY_READ,
-        };
+/// Payload of `settings_changed`: the top-level sections (`general`,
+/// `security`, ...) whose contents differ from the previous save. The UI uses
+/// it to re-arm what the backend does not own, such as the auto-lock timer.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsChanged {
+    pub sections: Vec<String>,
+}
+
+fn changed_sections(previous: Option<&str>, next: &str) -> Vec<String> {
+    let parse = |raw: &str| match serde_json::from_str::<serde_json::Value>(raw) {
+        Ok(serde_json::Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    };
+    let previous = previous.map(parse).unwrap_or_default();
+    let next = parse(next);
+
+    let mut sections: Vec<String> = next
+        .iter()
+        .filter(|(name, value)| previous.get(*name) != Some(*value))
+        .map(|(name, _)| name.clone())
+        .chain(
+            previous
+                .keys()
+                .filter(|name| !next.contains_key(*name))
+                .cloned(),
+        )
+        .collect();
+    sections.sort();
+    sections
+}
+
+fn apply_general_settings(app_handle: &tauri::AppHandle, general: &GeneralSettings) -> Result<()> {
+    system::set_autostart(general.start_on_system_boot)?;
+
+    if general.show_in_system_tray {
+        if app_handle.tray_by_id("main").is_none() {
+            let _ = crate::tray::setup_tray(app_handle);
+        } else if let Some(tray) = app_handle.tray_by_id("main") {
+            let _ = tray.set_visible(true);
+        }
+    } else if let Some(tray) = app_handle.tray_by_id("main") {
+        let _ = tray.set_visible(false);
+    }
 
-        let sub_key = HSTRING::from("SOFTWARE\\Microsoft\\Cryptography");
-        let value_name = HSTRING::from("MachineGuid");
-        let mut h_key = HKEY::default();
+    Ok(())
+}
+
+/// Brings the OS-level state owned by the backend in line with the sections
+/// that just changed. Failures are logged so one broken integration does not
+/// undo a successful save.
+async fn apply_changed_sections(
+    app_handle: &tauri::AppHandle,
+    settings: &str,
+    sections: &[String],
+) {
+    let changed = |name: &str| sections.iter().any(|s| s == name);
+
+    if changed("general") {
+        if let Ok(all) = serde_json::from_str::<AllSettings>(settings) {
+            if let Err(e) = apply_general_settings(app_handle, &all.general) {
+                eprintln!("[Settings] Failed to apply general settings: {}", e);
+            }
+        }
+    }
+
+    if changed("security") {
+        if let Ok(all) = serde_json::from_str::<AllSettings>(settings) {
+            if let Err(e) = crate::security::set_screen_capture_protection(
+                app_handle.clone(),
+                all.security.screen_capture_protection,
+            )
+            .await
+            {
+                eprintln!("[Settings] Failed to apply capture protection: {}", e);
+            }
+        }
+    }
 
-        unsafe {
-            if RegOpenKeyExW(HKEY_LOCAL_MACHINE, &sub_key, 0, KEY_READ, &mut h_key).is_ok() {
-                let mut data_type = windows::Win32::System::Registry::REG_VALUE_TYPE::default();
-                let mut data_len = 0u32;
+    #[cfg(desktop)]
+    if changed("autofill") {
+        if let Err(e) = crate::shortcuts::apply_global_shortcuts(app_handle).await {
+            eprintln!("[Settings] Failed to apply global shortcuts: {}", e);
+        }
+    }
+}
 
+#[cfg(target_os = "windows")]
+fn get_hardware_id() -> Option<String> {
+    use windows::core::HSTRING;
+    use windows::Win32::System::Registry::{
+        RegCloseKey, RegOpenKeyExW, RegQueryValueExW, HKEY, HKEY_LOCAL_MACHINE, KEY_READ,
+    };
+
+    let sub_key = HSTRING::from("SOFTWARE\\Microsoft\\Cryptography");
+    let value_name = HSTRING::from("MachineGuid");
+    let mut h_key = HKEY::default();
+
+    unsafe {
+        if RegOpenKeyExW(HKEY_LOCAL_MACHINE, &sub_key, 0, KEY_READ, &mut h_key).is_ok() {
+            let mut data_type = windows::Win32::System::Registry::REG_VALUE_TYPE::default();
+            let mut data_len = 0u32;
+
+            if RegQueryValueExW(
+                h_key,
+                &value_name,
+                None,
+                Some(&mut data_type),
+                None,
+                Some(&mut data_len),
+            )
+            .is_ok()
+            {
+                let mut buffer = vec![0u16; (data_len / 2) as usize];
                 if RegQueryValueExW(
                     h_key,
                     &value_name,
                     None,
-                    Some(&mut data_type),
                     None,
+                    Some(buffer.as_mut_ptr() as *mut u8),
                     Some(&mut data_len),
                 )
                 .is_ok()
                 {
-                    let mut buffer = vec![0u16; (data_len / 2) as usize];
-                    if RegQueryValueExW(
-                        h_key,
-                        &value_name,
-                        None,
-                        None,
-                        Some(buffer.as_mut_ptr() as *mut u8),
-                        Some(&mut data_len),
-                    )
-                    .is_ok()
-                    {
-                        let _ = RegCloseKey(h_key);
-                        let guid = String::from_utf16_lossy(&buffer);
-                        return guid.trim_matches('\0').to_string();
-                    }
+                    let _ = RegCloseKey(h_key);
+                    let guid = String::from_utf16_lossy(&buffer);
+                    return Some(guid.trim_matches('\0').to_string());
                 }
-                let _ = RegCloseKey(h_key);
             }
+            let _ = RegCloseKey(h_key);
         }
     }
-    "fallback-stable-id-pulsar".to_string()
+    None
 }
 
-fn get_or_create_settings_key() -> Result<Vec<u8>> {
-    if let Some(key) = SETTINGS_KEY_CACHE.get() {
-        return Ok(key.clone());
+/// The `IOPlatformUUID` of the machine, as reported by the IOKit registry.
+#[cfg(target_os = "macos")]
+fn get_hardware_id() -> Option<String> {
+    let output = std::process::Command::new("/usr/sbin/ioreg")
+        .args(["-rd1", "-c", "IOPlatformExpertDevice"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
     }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find(|line| line.contains("\"IOPlatformUUID\""))
+        .and_then(|line| line.split('"').nth(3))
+        .map(str::to_string)
+        .filter(|uuid| !uuid.is_empty())
+}
 
-    let entry = Entry::new(SETTINGS_KEYRING_SERVICE, SETTINGS_KEYRING_USER)
-        .map_err(|e| Error::Internal(format!("Keyring init error: {e}")))?;
+/// The systemd/D-Bus machine ID, hashed with an app-specific prefix as
+/// machine-id(5) asks so the raw ID never leaves the machine's own files.
+#[cfg(target_os = "linux")]
+fn get_hardware_id() -> Option<String> {
+    use sha2::Digest;
+
+    let machine_id = ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+        .iter()
+        .find_map(|path| std::fs::read_to_string(path).ok())
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())?;
+
+    let mut hasher = Sha256::new();
+    hasher.update(b"pulsar-settings-machine-id:");
+    hasher.update(machine_id.as_bytes());
+    Some(hex::encode(hasher.finalize()))
+}
 
-    // Attempt to get the existing secret or use the hardware ID as a stable seed
-    let stable_seed = match entry.get_password() {
-        Ok(s) => s,
-        Err(_) => {
-            let hw_id = get_hardware_id();
-            // Fallback to hardware-bound ID if keyring is unavailable
-            let _ = entry.set_password(&hw_id);
-            hw_id
-        }
-    };
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn get_hardware_id() -> Option<String> {
+    None
+}
 
+fn derive_settings_key(seed: &str) -> Result<Vec<u8>> {
     use hkdf::Hkdf;
 
-    let hk = Hkdf::<Sha256>::new(Some(SETTINGS_SALT), stable_seed.as_bytes());
+    let hk = Hkdf::<Sha256>::new(Some(SETTINGS_SALT), seed.as_bytes());
     let mut okm = [0u8; 32];
     hk.expand(b"pulsar-settings-v4-hkdf-expansion", &mut okm)
         .map_err(|_| Error::Internal("Key derivation failed".to_string()))?;
+    Ok(okm.to_vec())
+}
 
-    let key = okm.to_vec();
+/// Decrypts stored settings, also accepting keys derived from earlier seeds.
+/// The flag is set when an earlier key was needed, meaning the value should
+/// be re-encrypted.
+fn decrypt_settings(encrypted: &str, key: &[u8]) -> Option<(String, bool)> {
+    if let Ok(decrypted) = decrypt(encrypted, key) {
+        return Some((decrypted, false));
+    }
+    // Earlier seeds: the fixed fallback, and the bare hardware ID that was
+    // stored as the seed before Windows seeds were protected.
+    std::iter::once(LEGACY_FALLBACK_SEED.to_string())
+        .chain(get_hardware_id())
+        .filter_map(|seed| derive_settings_key(&seed).ok())
+        .filter(|legacy_key| legacy_key.as_slice() != key)
+        .find_map(|legacy_key| decrypt(encrypted, &legacy_key).ok())
+        .map(|decrypted| (decrypted, true))
+}
 
-    let _ = SETTINGS_KEY_CACHE.set(key.clone());
+/// Replaces a plain stored seed (formerly the MachineGuid, readable by any
+/// local user) with a random seed kept TPM- or DPAPI-protected. Settings
+/// encrypted under the old seed are migrated by `decrypt_settings`.
+#[cfg(target_os = "windows")]
+fn protected_windows_seed(entry: &Entry, stored: String) -> Result<String> {
+    match windows_protect::unprotect_seed(&stored) {
+        Some(Ok(seed)) => return Ok(hex::encode(seed)),
+        Some(Err(e)) => eprintln!("[Settings] Stored settings seed is unreadable: {}", e),
+        None => {}
+    }
+
+    let mut seed = zeroize::Zeroizing::new([0u8; 32]);
+    rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut seed[..]);
+    let protected = windows_protect::protect_seed(&seed[..])?;
+    entry
+        .set_password(&protected)
+        .map_err(crate::keyring_backend::map_keyring_error)?;
+    Ok(hex::encode(&seed[..]))
+}
+
+fn cached_settings_key() -> Option<Vec<u8>> {
+    SETTINGS_KEY_CACHE.read().ok().and_then(|key| key.clone())
+}
+
+fn set_cached_settings_key(key: Option<Vec<u8>>) {
+    if let Ok(mut guard) = SETTINGS_KEY_CACHE.write() {
+        if let Some(mut old) = std::mem::replace(&mut *guard, key) {
+            old.zeroize();
+        }
+    }
+}
+
+/// Drops the cached settings key without blocking, for shutdown paths.
+pub(crate) fn clear_cached_settings_key() {
+    if let Ok(mut guard) = SETTINGS_KEY_CACHE.try_write() {
+        if let Some(mut key) = guard.take() {
+            key.zeroize();
+        }
+    }
+}
+
+fn get_or_create_settings_key() -> Result<Vec<u8>> {
+    if let Some(key) = cached_settings_key() {
+        return Ok(key);
+    }
+    if keystore::backend() == keystore::KeystoreBackend::EncryptedFile {
+        return Err(Error::VaultLocked);
+    }
+
+    let hardware_id = get_hardware_id();
+    let entry = match crate::keyring_backend::entry(SETTINGS_KEYRING_SERVICE, SETTINGS_KEYRING_USER)
+    {
+        Ok(entry) => entry,
+        Err(e) => {
+            // No usable keyring: the hardware ID alone seeds the key, which
+            // `get_keystore_status` reports as degraded.
+            keystore::note_keyring_error(Some(e.to_string()));
+            let seed = hardware_id.unwrap_or_else(|| LEGACY_FALLBACK_SEED.to_string());
+            let key = derive_settings_key(&seed)?;
+            set_cached_settings_key(Some(key.clone()));
+            return Ok(key);
+        }
+    };
+
+    // Attempt to get the existing secret or use the hardware ID as a stable seed.
+    // A stored legacy fallback seed is upgraded once a hardware ID is available.
+    let stable_seed = match (entry.get_password(), hardware_id) {
+        (Ok(s), Some(hw_id)) if s == LEGACY_FALLBACK_SEED => {
+            let _ = entry.set_password(&hw_id);
+            keystore::note_keyring_error(None);
+            hw_id
+        }
+        (Ok(s), _) => {
+            keystore::note_keyring_error(None);
+            s
+        }
+        (Err(get_err), hw_id) => {
+            let hw_id = hw_id.unwrap_or_else(|| LEGACY_FALLBACK_SEED.to_string());
+            // Fallback to hardware-bound ID if keyring is unavailable
+            let error = match entry.set_password(&hw_id) {
+                Ok(()) if matches!(get_err, keyring::Error::NoEntry) => None,
+                Ok(()) => Some(crate::keyring_backend::map_keyring_error(get_err).to_string()),
+                Err(set_err) => {
+                    Some(crate::keyring_backend::map_keyring_error(set_err).to_string())
+                }
+            };
+            keystore::note_keyring_error(error);
+            hw_id
+        }
+    };
+
+    #[cfg(target_os = "windows")]
+    let stable_seed = protected_windows_seed(&entry, stable_seed)?;
+
+    let key = derive_settings_key(&stable_seed)?;
+
+    set_cached_settings_key(Some(key.clone()));
 
     // Best-effort cleanup of old legacy entries
     let _ =
@@ -123,21 +363,20 @@ fn get_or_create_settings_key() -> Result<Vec<u8>> {
 pub async fn apply_system_settings(app_handle: tauri::AppHandle) -> Result<()> {
     if let Some(settings_json) = get_all_settings(app_handle.clone()).await? {
         if let Ok(settings) = serde_json::from_str::<AllSettings>(&settings_json) {
-            system::set_autostart(settings.general.start_on_system_boot)?;
-
-            if settings.general.show_in_system_tray {
-                if app_handle.tray_by_id("main").is_none() {
-                    let _ = crate::tray::setup_tray(&app_handle);
-                } else if let Some(tray) = app_handle.tray_by_id("main") {
-                    let _ = tray.set_visible(true);
-                }
-            } else {
-                if let Some(tray) = app_handle.tray_by_id("main") {
-                    let _ = tray.set_visible(false);
-                }
-            }
+            apply_general_settings(&app_handle, &settings.general)?;
+            crate::security::set_screen_capture_protection(
+                app_handle.clone(),
+                settings.security.screen_capture_protection,
+            )
+            .await?;
         }
     }
+
+    #[cfg(desktop)]
+    {
+        crate::shortcuts::apply_global_shortcuts(&app_handle).await?;
+    }
+
     Ok(())
 }
 
@@ -161,17 +400,35 @@ pub async fn get_all_settings_internal(app_handle: &tauri::AppHandle) -> Result<
 
     store.reload().ok();
 
+    // Encrypted-file keystore not opened yet: settings exist but are sealed.
+    if keystore::backend() == keystore::KeystoreBackend::EncryptedFile
+        && cached_settings_key().is_none()
+    {
+        return Ok(None);
+    }
+
     if let Some(encrypted_val) = store.get("settings_encrypted") {
         let encrypted_str = encrypted_val
             .as_str()
             .ok_or_else(|| Error::Internal("Invalid encrypted settings format".to_string()))?;
 
         let key = get_or_create_settings_key()?;
-        match decrypt(encrypted_str, &key) {
-            Ok(decrypted) => {
+        match decrypt_settings(encrypted_str, &key) {
+            Some((decrypted, false)) => {
+                return Ok(Some(decrypted));
+            }
+            Some((decrypted, true)) => {
+                let encrypted = encrypt(&decrypted, &key)?;
+                store.set(
+                    "settings_encrypted".to_string(),
+                    serde_json::Value::String(encrypted),
+                );
+                if let Err(e) = store.save() {
+                    eprintln!("[Settings] Failed to re-encrypt legacy settings: {}", e);
+                }
                 return Ok(Some(decrypted));
             }
-            Err(_) => {
+            None => {
                 store.delete("settings_encrypted");
                 let _ = store.save();
                 return Ok(None);
@@ -202,8 +459,33 @@ pub async fn get_all_settings_internal(app_handle: &tauri::AppHandle) -> Result<
     Ok(None)
 }
 
+/// Writes `settings` encrypted with the current settings key, without
+/// applying or announcing changes.
+async fn store_settings(app_handle: &tauri::AppHandle, settings: &str) -> Result<()> {
+    let _guard = STORE_MUTEX.lock().await;
+    use tauri::Manager;
+    let settings_path = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| Error::Internal(e.to_string()))?
+        .join(".settings.dat");
+
+    let store = StoreBuilder::new(app_handle, settings_path)
+        .build()
+        .map_err(|e| Error::Internal(e.to_string()))?;
+
+    let key = get_or_create_settings_key()?;
+    store.set(
+        "settings_encrypted".to_string(),
+        serde_json::Value::String(encrypt(settings, &key)?),
+    );
+    store.delete("settings");
+    store.save().map_err(|e| Error::Internal(e.to_string()))
+}
+
 #[tauri::command]
 pub async fn set_all_settings(app_handle: tauri::AppHandle, settings: String) -> Result<()> {
+    crate::limits::check_len("Settings", &settings, crate::limits::MAX_SETTINGS_LEN)?;
     let _guard = STORE_MUTEX.lock().await;
     use tauri::Manager;
     let settings_path = app_handle
@@ -219,6 +501,15 @@ pub async fn set_all_settings(app_handle: tauri::AppHandle, settings: String) ->
     store.reload().ok();
 
     let key = get_or_create_settings_key()?;
+    let previous = store
+        .get("settings_encrypted")
+        .and_then(|v| v.as_str().and_then(|s| decrypt_settings(s, &key)))
+        .map(|(decrypted, _)| decrypted)
+        .or_else(|| {
+            store
+                .get("settings")
+                .and_then(|v| v.as_str().map(str::to_string))
+        });
     let encrypted = encrypt(&settings, &key)?;
 
     (*store).set(
@@ -231,5 +522,14 @@ pub async fn set_all_settings(app_handle: tauri::AppHandle, settings: String) ->
         Ok(()) => {}
         Err(e) => return Err(Error::Internal(e.to_string())),
     }
+    drop(_guard);
+
+    let sections = changed_sections(previous.as_deref(), &settings);
+    if sections.is_empty() {
+        return Ok(());
+    }
+
+    apply_changed_sections(&app_handle, &settings, &sections).await;
+    let _ = app_handle.emit(SETTINGS_CHANGED_EVENT, SettingsChanged { sections });
     Ok(())
 }
@@ -0,0 +1,190 @@
+//! Protection of the settings seed on Windows. The seed is random and stored
+//! in Credential Manager only in protected form: sealed with a TPM-backed key
+//! from the Platform Crypto Provider where a TPM is present, otherwise
+//! encrypted with DPAPI for the current user. Either way, reading the
+//! credential or the registry is not enough to recover the settings key.
+
+use crate::error::{Error, Result};
+use base64::{engine::general_purpose, Engine as _};
+use windows::core::{w, PCWSTR};
+use windows::Win32::Foundation::{LocalFree, HLOCAL};
+use windows::Win32::Security::Cryptography::{
+    CryptProtectData, CryptUnprotectData, NCryptCreatePersistedKey, NCryptDecrypt, NCryptEncrypt,
+    NCryptFinalizeKey, NCryptFreeObject, NCryptOpenKey, NCryptOpenStorageProvider,
+    BCRYPT_OAEP_PADDING_INFO, BCRYPT_RSA_ALGORITHM, BCRYPT_SHA256_ALGORITHM, CERT_KEY_SPEC,
+    CRYPTPROTECT_UI_FORBIDDEN, CRYPT_INTEGER_BLOB, MS_PLATFORM_CRYPTO_PROVIDER, NCRYPT_FLAGS,
+    NCRYPT_HANDLE, NCRYPT_KEY_HANDLE, NCRYPT_PAD_OAEP_FLAG, NCRYPT_PROV_HANDLE,
+};
+
+const TPM_PREFIX: &str = "tpm:";
+const DPAPI_PREFIX: &str = "dpapi:";
+const TPM_KEY_NAME: PCWSTR = w!("Pulsar-Settings-Seed");
+
+fn dpapi_protect(data: &[u8]) -> Result<Vec<u8>> {
+    unsafe {
+        let input = CRYPT_INTEGER_BLOB {
+            cbData: data.len() as u32,
+            pbData: data.as_ptr() as *mut u8,
+        };
+        let mut output = CRYPT_INTEGER_BLOB::default();
+        CryptProtectData(
+            &input,
+            w!("Pulsar settings"),
+            None,
+            None,
+            None,
+            CRYPTPROTECT_UI_FORBIDDEN,
+            &mut output,
+        )
+        .map_err(|e| Error::Encryption(format!("DPAPI protect failed: {e}")))?;
+        let protected = std::slice::from_raw_parts(output.pbData, output.cbData as usize).to_vec();
+        let _ = LocalFree(HLOCAL(output.pbData as _));
+        Ok(protected)
+    }
+}
+
+fn dpapi_unprotect(data: &[u8]) -> Result<Vec<u8>> {
+    unsafe {
+        let input = CRYPT_INTEGER_BLOB {
+            cbData: data.len() as u32,
+            pbData: data.as_ptr() as *mut u8,
+        };
+        let mut output = CRYPT_INTEGER_BLOB::default();
+        CryptUnprotectData(
+            &input,
+            None,
+            None,
+            None,
+            None,
+            CRYPTPROTECT_UI_FORBIDDEN,
+            &mut output,
+        )
+        .map_err(|e| Error::Decryption(format!("DPAPI unprotect failed: {e}")))?;
+        let plain = std::slice::from_raw_parts(output.pbData, output.cbData as usize).to_vec();
+        let _ = LocalFree(HLOCAL(output.pbData as _));
+        Ok(plain)
+    }
+}
+
+/// Opens (or with `create`, creates) the persisted TPM key. Fails when the
+/// machine has no usable TPM.
+fn open_tpm_key(create: bool) -> Result<(NCRYPT_PROV_HANDLE, NCRYPT_KEY_HANDLE)> {
+    unsafe {
+        let mut provider = NCRYPT_PROV_HANDLE::default();
+        NCryptOpenStorageProvider(&mut provider, MS_PLATFORM_CRYPTO_PROVIDER, 0)
+            .map_err(|e| Error::Internal(format!("TPM unavailable: {e}")))?;
+
+        let mut key = NCRYPT_KEY_HANDLE::default();
+        let opened = NCryptOpenKey(
+            provider,
+            &mut key,
+            TPM_KEY_NAME,
+            CERT_KEY_SPEC(0),
+            NCRYPT_FLAGS(0),
+        );
+        if opened.is_err() {
+            let created = if create {
+                NCryptCreatePersistedKey(
+                    provider,
+                    &mut key,
+                    BCRYPT_RSA_ALGORITHM,
+                    TPM_KEY_NAME,
+                    CERT_KEY_SPEC(0),
+                    NCRYPT_FLAGS(0),
+                )
+                .and_then(|_| NCryptFinalizeKey(key, NCRYPT_FLAGS(0)))
+            } else {
+                opened
+            };
+            if let Err(e) = created {
+                let _ = NCryptFreeObject(NCRYPT_HANDLE(provider.0));
+                return Err(Error::Internal(format!("TPM key unavailable: {e}")));
+            }
+        }
+        Ok((provider, key))
+    }
+}
+
+fn tpm_crypt(data: &[u8], encrypt: bool) -> Result<Vec<u8>> {
+    let (provider, key) = open_tpm_key(encrypt)?;
+    let padding = BCRYPT_OAEP_PADDING_INFO {
+        pszAlgId: BCRYPT_SHA256_ALGORITHM,
+        pbLabel: std::ptr::null_mut(),
+        cbLabel: 0,
+    };
+    let padding_ptr = Some(&padding as *const _ as *const std::ffi::c_void);
+
+    let result = unsafe {
+        let run = |output: Option<&mut [u8]>, len: &mut u32| {
+            if encrypt {
+                NCryptEncrypt(
+                    key,
+                    Some(data),
+                    padding_ptr,
+                    output,
+                    len,
+                    NCRYPT_PAD_OAEP_FLAG,
+                )
+            } else {
+                NCryptDecrypt(
+                    key,
+                    Some(data),
+                    padding_ptr,
+                    output,
+                    len,
+                    NCRYPT_PAD_OAEP_FLAG,
+                )
+            }
+        };
+        let mut len = 0u32;
+        run(None, &mut len).and_then(|_| {
+            let mut buffer = vec![0u8; len as usize];
+            run(Some(&mut buffer), &mut len).map(|_| {
+                buffer.truncate(len as usize);
+                buffer
+            })
+        })
+    };
+
+    unsafe {
+        let _ = NCryptFreeObject(NCRYPT_HANDLE(key.0));
+        let _ = NCryptFreeObject(NCRYPT_HANDLE(provider.0));
+    }
+    result.map_err(|e| Error::Encryption(format!("TPM operation failed: {e}")))
+}
+
+/// Protects `seed` for storage, sealing it to the TPM when possible.
+pub fn protect_seed(seed: &[u8]) -> Result<String> {
+    match tpm_crypt(seed, true) {
+        Ok(sealed) => Ok(format!(
+            "{}{}",
+            TPM_PREFIX,
+            general_purpose::STANDARD.encode(sealed)
+        )),
+        Err(e) => {
+            eprintln!("[Settings] Falling back to DPAPI: {}", e);
+            Ok(format!(
+                "{}{}",
+                DPAPI_PREFIX,
+                general_purpose::STANDARD.encode(dpapi_protect(seed)?)
+            ))
+        }
+    }
+}
+
+/// Recovers a seed stored by `protect_seed`. `None` when `stored` is a
+/// plain seed from before the seed was protected.
+pub fn unprotect_seed(stored: &str) -> Option<Result<Vec<u8>>> {
+    let decode = |value: &str| {
+        general_purpose::STANDARD
+            .decode(value)
+            .map_err(|_| Error::Decryption("Corrupt protected settings seed".to_string()))
+    };
+    if let Some(sealed) = stored.strip_prefix(TPM_PREFIX) {
+        return Some(decode(sealed).and_then(|sealed| tpm_crypt(&sealed, false)));
+    }
+    if let Some(protected) = stored.strip_prefix(DPAPI_PREFIX) {
+        return Some(decode(protected).and_then(|protected| dpapi_unprotect(&protected)));
+    }
+    None
+}
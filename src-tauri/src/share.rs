@@ -0,0 +1,549 @@
+//! One-time shares of a single entry. The entry is encrypted with a random
+//! key into a payload file, and the key is given to the recipient as a
+//! short string over another channel. The payload carries its expiry in the
+//! authenticated data and marks itself single-use: importing it records its
+//! id in the open vault, refuses it a second time and deletes the file.
+//!
+//! An entry can also be shared as QR codes, for a phone without a network
+//! connection: the passphrase-encrypted entry is split into chunks that
+//! each carry a `PULSAR-QR:<id>:<n>/<total>:` header, so they can be
+//! scanned in any order and put back together.
+
+use crate::crypto::{open_with_passphrase, seal_with_passphrase};
+use crate::db::passwords::fetch_password_item;
+use crate::db::utils::{CryptoHelper, UnlockedVault};
+use crate::db::wifi::render_qr;
+use crate::encryption::{decrypt, encrypt};
+use crate::error::{Error, Result};
+use crate::limits;
+use crate::state::AppState;
+use crate::types::{ExportPayload, PasswordItem};
+use crate::utils::write_sensitive_bytes;
+use base32::Alphabet;
+use base64::{engine::general_purpose, Engine as _};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use chrono::{DateTime, Duration, Utc};
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sqlx::SqlitePool;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use tauri::{AppHandle, State};
+use zeroize::Zeroizing;
+
+const SHARE_FORMAT: &str = "pulsar-one-time-share";
+const SHARE_VERSION: u8 = 1;
+const SHARE_KEY_LEN: usize = 16;
+const HKDF_INFO: &[u8] = b"pulsar:one-time-share";
+const CONSUMED_KEY: &str = "consumed_one_time_shares";
+const MIN_TTL_SECONDS: u64 = 60;
+const MAX_TTL_SECONDS: u64 = 30 * 24 * 60 * 60;
+const QR_PREFIX: &str = "PULSAR-QR";
+/// Characters of payload per QR code, small enough for phone cameras to
+/// read reliably.
+const QR_CHUNK_LEN: usize = 900;
+const MAX_QR_CHUNKS: usize = 64;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OneTimeSharePayload {
+    format: String,
+    version: u8,
+    /// Always set; tells importers to accept the payload only once.
+    single_use: bool,
+    share_id: String,
+    created_at: String,
+    expires_at: String,
+    salt_b64: String,
+    nonce_b64: String,
+    ciphertext_b64: String,
+}
+
+impl OneTimeSharePayload {
+    fn aad(&self) -> String {
+        format!(
+            "{}:v{}:{}:{}:{}:{}:{}",
+            self.format,
+            self.version,
+            self.single_use,
+            self.share_id,
+            self.expires_at,
+            self.salt_b64,
+            self.nonce_b64
+        )
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OneTimeShare {
+    pub path: String,
+    /// Send this separately from the file.
+    pub key: String,
+    pub share_id: String,
+    pub expires_at: String,
+}
+
+fn share_cipher(share_key: &[u8], salt: &[u8]) -> Result<XChaCha20Poly1305> {
+    let hk = Hkdf::<Sha256>::new(Some(salt), share_key);
+    let mut key = Zeroizing::new([0u8; 32]);
+    hk.expand(HKDF_INFO, &mut key[..])
+        .map_err(|_| Error::Internal("HKDF expand failed".to_string()))?;
+    Ok(XChaCha20Poly1305::new(Key::from_slice(&key[..])))
+}
+
+/// Parses a share key as typed by a person: case, spaces and dashes are
+/// ignored.
+fn parse_share_key(key: &str) -> Result<Zeroizing<Vec<u8>>> {
+    let cleaned: String = key
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect::<String>()
+        .to_uppercase();
+    base32::decode(Alphabet::Rfc4648 { padding: false }, &cleaned)
+        .filter(|bytes| bytes.len() == SHARE_KEY_LEN)
+        .map(Zeroizing::new)
+        .ok_or_else(|| Error::Validation("The share key is not valid.".to_string()))
+}
+
+/// Ids of imported shares, with their expiry so they can be forgotten once
+/// the payload would be refused anyway.
+async fn load_consumed(pool: &SqlitePool, key: &[u8]) -> Result<HashMap<String, String>> {
+    let value: Option<String> = sqlx::query_scalar("SELECT value FROM configuration WHERE key = ?")
+        .bind(CONSUMED_KEY)
+        .fetch_optional(pool)
+        .await?;
+    match value {
+        Some(enc) => Ok(serde_json::from_str(&decrypt(&enc, key)?)?),
+        None => Ok(HashMap::new()),
+    }
+}
+
+async fn save_consumed(
+    pool: &SqlitePool,
+    key: &[u8],
+    consumed: &HashMap<String, String>,
+) -> Result<()> {
+    sqlx::query("INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)")
+        .bind(CONSUMED_KEY)
+        .bind(encrypt(&serde_json::to_string(consumed)?, key)?)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Encrypts an entry into a one-time share file at `path` that can be
+/// imported until `ttl_seconds` from now. Returns the key to send along.
+#[tauri::command]
+pub async fn create_one_time_share(
+    state: State<'_, AppState>,
+    item_id: i64,
+    ttl_seconds: u64,
+    path: String,
+) -> Result<OneTimeShare> {
+    if !(MIN_TTL_SECONDS..=MAX_TTL_SECONDS).contains(&ttl_seconds) {
+        return Err(Error::Validation(format!(
+            "A share must expire between {} seconds and {} days from now.",
+            MIN_TTL_SECONDS,
+            MAX_TTL_SECONDS / (24 * 60 * 60)
+        )));
+    }
+    let vault = UnlockedVault::acquire(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
+    let item = fetch_password_item(vault.pool(), &helper, item_id)
+        .await?
+        .ok_or_else(|| Error::Validation(format!("Item {} not found", item_id)))?;
+    let plaintext = Zeroizing::new(serde_json::to_vec(&item)?);
+
+    let share_key = Zeroizing::new(rand::random::<[u8; SHARE_KEY_LEN]>());
+    let salt = rand::random::<[u8; 16]>();
+    let nonce = rand::random::<[u8; 24]>();
+    let now = Utc::now();
+    let mut payload = OneTimeSharePayload {
+        format: SHARE_FORMAT.to_string(),
+        version: SHARE_VERSION,
+        single_use: true,
+        share_id: hex::encode(rand::random::<[u8; 16]>()),
+        created_at: now.to_rfc3339(),
+        expires_at: (now + Duration::seconds(ttl_seconds as i64)).to_rfc3339(),
+        salt_b64: general_purpose::STANDARD.encode(salt),
+        nonce_b64: general_purpose::STANDARD.encode(nonce),
+        ciphertext_b64: String::new(),
+    };
+    let ciphertext = share_cipher(&share_key[..], &salt)?
+        .encrypt(
+            XNonce::from_slice(&nonce),
+            Payload {
+                msg: &plaintext,
+                aad: payload.aad().as_bytes(),
+            },
+        )
+        .map_err(|e| Error::Encryption(format!("encryption failed: {}", e)))?;
+    payload.ciphertext_b64 = general_purpose::STANDARD.encode(ciphertext);
+
+    let path = PathBuf::from(path);
+    write_sensitive_bytes(&path, &serde_json::to_vec_pretty(&payload)?).await?;
+
+    let _ = crate::db::activity::log_activity_impl(
+        vault.pool(),
+        vault.key(),
+        "one_time_share_created",
+        Some(item_id),
+        Some(&item.title),
+        Some(&format!("Shared once until {}", payload.expires_at)),
+    )
+    .await;
+
+    Ok(OneTimeShare {
+        path: path.display().to_string(),
+        key: base32::encode(Alphabet::Rfc4648 { padding: false }, &share_key[..]),
+        share_id: payload.share_id,
+        expires_at: payload.expires_at,
+    })
+}
+
+/// Decrypts a one-time share file with the key sent along with it. The
+/// entry is returned for the caller to save; the share is then used up.
+#[tauri::command]
+pub async fn import_one_time_share(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    key: String,
+) -> Result<PasswordItem> {
+    limits::check_len("Share key", &key, limits::MAX_KEY_MATERIAL_LEN)?;
+    let path = PathBuf::from(path);
+    limits::check_file_size("Share file", &path, limits::MAX_SHARE_PAYLOAD_LEN as u64).await?;
+    let payload: OneTimeSharePayload = serde_json::from_slice(&tokio::fs::read(&path).await?)
+        .map_err(|_| Error::Validation("The file is not a one-time share.".to_string()))?;
+    if payload.format != SHARE_FORMAT || payload.version != SHARE_VERSION {
+        return Err(Error::Validation(format!(
+            "Unsupported one-time share version {}",
+            payload.version
+        )));
+    }
+    let expires_at = DateTime::parse_from_rfc3339(&payload.expires_at)
+        .map_err(|_| Error::Validation("The share has an invalid expiry.".to_string()))?
+        .with_timezone(&Utc);
+    if expires_at <= Utc::now() {
+        return Err(Error::Validation("This share has expired.".to_string()));
+    }
+
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let mut consumed = load_consumed(vault.pool(), vault.key()).await?;
+    if payload.single_use && consumed.contains_key(&payload.share_id) {
+        return Err(Error::Validation(
+            "This share has already been imported.".to_string(),
+        ));
+    }
+
+    let share_key = parse_share_key(&key)?;
+    let decode = |value: &str| {
+        general_purpose::STANDARD
+            .decode(value)
+            .map_err(|_| Error::Validation("The file is not a one-time share.".to_string()))
+    };
+    let salt = decode(&payload.salt_b64)?;
+    let nonce = decode(&payload.nonce_b64)?;
+    let ciphertext = decode(&payload.ciphertext_b64)?;
+    if nonce.len() != 24 {
+        return Err(Error::Validation("nonce must be 24 bytes".into()));
+    }
+    let plaintext = Zeroizing::new(
+        share_cipher(&share_key, &salt)?
+            .decrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: payload.aad().as_bytes(),
+                },
+            )
+            .map_err(|_| {
+                Error::Validation("The share key is wrong or the file is corrupt.".to_string())
+            })?,
+    );
+    let item: PasswordItem = serde_json::from_slice(&plaintext)?;
+    limits::check_password_item(&item)?;
+
+    if payload.single_use {
+        let now = Utc::now();
+        consumed.retain(|_, expiry| {
+            DateTime::parse_from_rfc3339(expiry).is_ok_and(|at| at.with_timezone(&Utc) > now)
+        });
+        consumed.insert(payload.share_id.clone(), payload.expires_at.clone());
+        save_consumed(vault.pool(), vault.key(), &consumed).await?;
+        if let Err(e) = tokio::fs::remove_file(&path).await {
+            eprintln!("[Share] Failed to delete used share file: {}", e);
+        }
+    }
+
+    let _ = crate::db::activity::log_secret_access(
+        &app,
+        vault.pool(),
+        vault.key(),
+        "one_time_share_imported",
+        None,
+        Some(&item.title),
+        Some(&format!("Imported share {}", payload.share_id)),
+    )
+    .await;
+    Ok(item)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QrChunk {
+    pub sequence: usize,
+    pub total: usize,
+    /// The text encoded in the QR code.
+    pub text: String,
+    pub image: Vec<u8>,
+}
+
+/// Splits `text` into the texts of the QR codes, each with its
+/// `PULSAR-QR:<id>:<n>/<total>:` header.
+fn split_qr_chunks(text: &str) -> Result<Vec<String>> {
+    let pieces: Vec<&str> = text
+        .as_bytes()
+        .chunks(QR_CHUNK_LEN)
+        .map(|chunk| std::str::from_utf8(chunk).map_err(|e| Error::Internal(e.to_string())))
+        .collect::<Result<_>>()?;
+    if pieces.len() > MAX_QR_CHUNKS {
+        return Err(Error::Validation(
+            "This entry is too large to share as QR codes.".to_string(),
+        ));
+    }
+    let id = hex::encode(rand::random::<[u8; 4]>());
+    let total = pieces.len();
+    Ok(pieces
+        .into_iter()
+        .enumerate()
+        .map(|(index, piece)| format!("{}:{}:{}/{}:{}", QR_PREFIX, id, index + 1, total, piece))
+        .collect())
+}
+
+/// Puts scanned QR chunks back together, in any order. Scanning a code twice
+/// is harmless; two different codes claiming the same position are not.
+fn join_qr_chunks(chunks: &[String]) -> Result<String> {
+    let mut id: Option<String> = None;
+    let mut expected_total: Option<usize> = None;
+    let mut parts: BTreeMap<usize, String> = BTreeMap::new();
+    for chunk in chunks {
+        limits::check_len("QR chunk", chunk, QR_CHUNK_LEN * 2)?;
+        let invalid = || Error::Validation("This is not a Pulsar QR code.".to_string());
+        let mut fields = chunk.trim().splitn(4, ':');
+        if fields.next() != Some(QR_PREFIX) {
+            return Err(invalid());
+        }
+        let chunk_id = fields.next().ok_or_else(invalid)?;
+        let (sequence, total) = fields
+            .next()
+            .and_then(|s| s.split_once('/'))
+            .and_then(|(n, t)| Some((n.parse::<usize>().ok()?, t.parse::<usize>().ok()?)))
+            .ok_or_else(invalid)?;
+        let data = fields.next().ok_or_else(invalid)?;
+        if total == 0 || total > MAX_QR_CHUNKS || sequence == 0 || sequence > total {
+            return Err(invalid());
+        }
+        if id.get_or_insert_with(|| chunk_id.to_string()) != chunk_id
+            || *expected_total.get_or_insert(total) != total
+        {
+            return Err(Error::Validation(
+                "These QR codes belong to different shares.".to_string(),
+            ));
+        }
+        if let Some(previous) = parts.insert(sequence, data.to_string()) {
+            if previous != data {
+                return Err(Error::Validation(format!(
+                    "QR code {} of {} was scanned twice with different contents.",
+                    sequence, total
+                )));
+            }
+        }
+    }
+
+    let total = expected_total
+        .ok_or_else(|| Error::Validation("Scan at least one QR code.".to_string()))?;
+    let missing: Vec<String> = (1..=total)
+        .filter(|n| !parts.contains_key(n))
+        .map(|n| n.to_string())
+        .collect();
+    if !missing.is_empty() {
+        return Err(Error::Validation(format!(
+            "Missing QR codes {} of {}.",
+            missing.join(", "),
+            total
+        )));
+    }
+    Ok(parts.into_values().collect())
+}
+
+/// Renders an entry, encrypted with `passphrase`, as one or more QR codes.
+/// `format` is `png` (default) or `svg`.
+#[tauri::command]
+pub async fn export_entry_as_qr(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    item_id: i64,
+    passphrase: String,
+    format: Option<String>,
+) -> Result<Vec<QrChunk>> {
+    let passphrase = Zeroizing::new(passphrase);
+    if passphrase.is_empty() {
+        return Err(Error::Validation(
+            "A passphrase is required to share an entry.".to_string(),
+        ));
+    }
+    let format = format.unwrap_or_else(|| "png".to_string());
+    let vault = UnlockedVault::acquire(&state).await?;
+    let helper = CryptoHelper::new(vault.key())?;
+    let item = fetch_password_item(vault.pool(), &helper, item_id)
+        .await?
+        .ok_or_else(|| Error::Validation(format!("Item {} not found", item_id)))?;
+
+    let plaintext = Zeroizing::new(serde_json::to_vec(&item)?);
+    let payload = tauri::async_runtime::spawn_blocking(move || {
+        seal_with_passphrase(&plaintext, passphrase.as_str())
+    })
+    .await
+    .map_err(|e| Error::Internal(format!("Runtime error: {}", e)))??;
+    let text = serde_json::to_string(&payload)?;
+
+    let texts = split_qr_chunks(&text)?;
+    let total = texts.len();
+    let mut chunks = Vec::with_capacity(total);
+    for (index, text) in texts.into_iter().enumerate() {
+        chunks.push(QrChunk {
+            sequence: index + 1,
+            total,
+            image: render_qr(&text, &format)?,
+            text,
+        });
+    }
+
+    let _ = crate::db::activity::log_secret_access(
+        &app,
+        vault.pool(),
+        vault.key(),
+        "entry_qr_exported",
+        Some(item_id),
+        Some(&item.title),
+        Some(&format!("Shared as {} QR codes", total)),
+    )
+    .await;
+    Ok(chunks)
+}
+
+/// Reassembles scanned QR chunks from `export_entry_as_qr`, in any order,
+/// and decrypts the entry. Duplicate scans are ignored.
+#[tauri::command]
+pub async fn import_entry_from_qr(chunks: Vec<String>, passphrase: String) -> Result<PasswordItem> {
+    let passphrase = Zeroizing::new(passphrase);
+    limits::check_count("QR chunks", chunks.len(), MAX_QR_CHUNKS * 4)?;
+
+    let text = join_qr_chunks(&chunks)?;
+    let payload: ExportPayload = serde_json::from_str(&text)
+        .map_err(|_| Error::Validation("The QR codes could not be read.".to_string()))?;
+    let plaintext = tauri::async_runtime::spawn_blocking(move || {
+        open_with_passphrase(&payload, passphrase.as_str())
+    })
+    .await
+    .map_err(|e| Error::Internal(format!("Runtime error: {}", e)))??;
+    let item: PasswordItem = serde_json::from_slice(&plaintext)?;
+    limits::check_password_item(&item)?;
+    Ok(item)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_chunks() -> (String, Vec<String>) {
+        let text: String = (0..QR_CHUNK_LEN * 2 + 10)
+            .map(|i| char::from(b'a' + (i % 26) as u8))
+            .collect();
+        let chunks = split_qr_chunks(&text).unwrap();
+        (text, chunks)
+    }
+
+    fn error_message(result: Result<String>) -> String {
+        match result {
+            Err(Error::Validation(message)) => message,
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_chunks_roundtrip_in_any_order() {
+        let (text, chunks) = sample_chunks();
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks[0].starts_with("PULSAR-QR:"));
+        assert!(chunks[2].contains(":3/3:"));
+        assert_eq!(join_qr_chunks(&chunks).unwrap(), text);
+
+        let reordered = vec![chunks[2].clone(), chunks[0].clone(), chunks[1].clone()];
+        assert_eq!(join_qr_chunks(&reordered).unwrap(), text);
+    }
+
+    #[test]
+    fn test_repeated_scans_are_ignored() {
+        let (text, chunks) = sample_chunks();
+        let mut repeated = chunks.clone();
+        repeated.extend(chunks.iter().cloned());
+        repeated.push(format!("  {}\n", chunks[1]));
+        assert_eq!(join_qr_chunks(&repeated).unwrap(), text);
+    }
+
+    #[test]
+    fn test_conflicting_duplicates_are_rejected() {
+        let (_, mut chunks) = sample_chunks();
+        let (header, _) = chunks[1].rsplit_once(':').unwrap();
+        chunks.push(format!("{}:forged", header));
+        assert!(error_message(join_qr_chunks(&chunks)).contains("scanned twice"));
+    }
+
+    #[test]
+    fn test_missing_chunks_are_listed() {
+        let (_, chunks) = sample_chunks();
+        let message = error_message(join_qr_chunks(&chunks[1..2]));
+        assert_eq!(message, "Missing QR codes 1, 3 of 3.");
+        assert!(join_qr_chunks(&[]).is_err());
+    }
+
+    #[test]
+    fn test_chunks_of_different_shares_are_rejected() {
+        let (_, first) = sample_chunks();
+        let (_, second) = sample_chunks();
+        let mixed = vec![first[0].clone(), second[1].clone(), first[2].clone()];
+        assert!(error_message(join_qr_chunks(&mixed)).contains("different shares"));
+    }
+
+    #[test]
+    fn test_malformed_chunks_are_rejected() {
+        for chunk in [
+            "hello",
+            "PULSAR-QR:abcd",
+            "PULSAR-QR:abcd:1:data",
+            "PULSAR-QR:abcd:0/2:data",
+            "PULSAR-QR:abcd:3/2:data",
+            "PULSAR-QR:abcd:1/65:data",
+            "OTHER-QR:abcd:1/1:data",
+        ] {
+            assert!(join_qr_chunks(&[chunk.to_string()]).is_err(), "{chunk}");
+        }
+    }
+
+    #[test]
+    fn test_parse_share_key() {
+        let key = [7u8; SHARE_KEY_LEN];
+        let encoded = base32::encode(Alphabet::Rfc4648 { padding: false }, &key);
+        let typed = format!(" {}-{} ", encoded[..13].to_lowercase(), &encoded[13..]);
+        assert_eq!(parse_share_key(&typed).unwrap().as_slice(), &key);
+        assert!(parse_share_key("AAAA").is_err());
+        assert!(parse_share_key("not base32!").is_err());
+    }
+}
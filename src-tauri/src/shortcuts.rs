@@ -0,0 +1,126 @@
+use crate::autotype;
+use crate::error::{Error, Result};
+use crate::state::AppState;
+use serde::Deserialize;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+pub const DEFAULT_AUTOTYPE_SHORTCUT: &str = "CommandOrControl+Alt+A";
+/// Gives the user time to release the hotkey's modifiers before typing starts.
+const HOTKEY_START_DELAY_MS: u64 = 400;
+
+#[derive(Deserialize, Default)]
+struct ShortcutSettings {
+    #[serde(default)]
+    autofill: AutofillShortcutSettings,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct AutofillShortcutSettings {
+    #[serde(default)]
+    global_autotype: bool,
+    #[serde(default)]
+    global_autotype_shortcut: Option<String>,
+}
+
+fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Resolves the focused window, picks the matching item and types it. When
+/// the match is ambiguous the main window is raised with a chooser instead.
+async fn handle_autotype_hotkey(app: AppHandle) {
+    let state = app.state::<AppState>();
+
+    if state.key.lock().await.is_none() {
+        show_main_window(&app);
+        crate::events::autotype_unlock_required(&app);
+        return;
+    }
+
+    let window_title = autotype::window::active_window_title().await;
+    let candidates = match &window_title {
+        Some(title) => match autotype::find_candidates(&state, title).await {
+            Ok(candidates) => candidates,
+            Err(e) => {
+                eprintln!("[Shortcuts] Failed to resolve auto-type candidates: {}", e);
+                return;
+            }
+        },
+        None => Vec::new(),
+    };
+
+    if let [candidate] = candidates.as_slice() {
+        let item_id = candidate.item_id;
+        if let Err(e) = autotype::autotype_item(
+            app.clone(),
+            state,
+            item_id,
+            None,
+            Some(HOTKEY_START_DELAY_MS),
+        )
+        .await
+        {
+            eprintln!("[Shortcuts] Global auto-type failed: {}", e);
+        }
+        return;
+    }
+
+    show_main_window(&app);
+    crate::events::autotype_chooser(
+        &app,
+        autotype::AutotypeCandidates {
+            window_title,
+            candidates,
+        },
+    );
+}
+
+/// Re-registers the global shortcuts from the current settings.
+pub async fn apply_global_shortcuts(app: &AppHandle) -> Result<()> {
+    let settings = crate::settings::get_all_settings_internal(app)
+        .await?
+        .and_then(|json| serde_json::from_str::<ShortcutSettings>(&json).ok())
+        .unwrap_or_default();
+
+    let manager = app.global_shortcut();
+    manager
+        .unregister_all()
+        .map_err(|e| Error::Internal(format!("Failed to unregister shortcuts: {e}")))?;
+
+    if !settings.autofill.global_autotype {
+        return Ok(());
+    }
+
+    let accelerator = settings
+        .autofill
+        .global_autotype_shortcut
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_AUTOTYPE_SHORTCUT.to_string());
+    let shortcut: Shortcut = accelerator.parse().map_err(|e| {
+        Error::Validation(format!(
+            "Invalid auto-type shortcut '{}': {}",
+            accelerator, e
+        ))
+    })?;
+
+    manager
+        .on_shortcut(shortcut, |app, _shortcut, event| {
+            if event.state == ShortcutState::Pressed {
+                tauri::async_runtime::spawn(handle_autotype_hotkey(app.clone()));
+            }
+        })
+        .map_err(|e| {
+            Error::Internal(format!(
+                "Failed to register shortcut '{}': {}",
+                accelerator, e
+            ))
+        })?;
+
+    Ok(())
+}
@@ -1,9 +1,14 @@
+use crate::integrity::BinaryIntegrityReport;
+use crate::memlock::LockedKey;
+use crate::overview_cache::OverviewCache;
+use crate::sessions::ActiveSession;
+use crate::write_buffer::WriteBuffer;
 use sqlx::SqlitePool;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::sync::{Mutex, Semaphore};
-use zeroize::Zeroizing;
 
 #[derive(Debug)]
 pub struct ClipboardPolicyState {
@@ -31,18 +36,39 @@ impl Default for ClipboardPolicyState {
 #[derive(Clone)]
 pub struct AppState {
     pub db: Arc<Mutex<Option<SqlitePool>>>,
-    pub key: Arc<Mutex<Option<Zeroizing<Vec<u8>>>>>,
+    /// Shared so readers borrow the one locked copy instead of making their own.
+    pub key: Arc<Mutex<Option<Arc<LockedKey>>>>,
     pub pending_key: Arc<Mutex<Option<PendingUnlock>>>,
     pub db_path: Arc<Mutex<Option<PathBuf>>>,
     pub rekey: Arc<Mutex<()>>,
     pub clipboard_policy: Arc<Mutex<ClipboardPolicyState>>,
     pub unlock_rate_limit: Arc<Mutex<UnlockRateLimit>>,
     pub unlock_guard: Arc<Semaphore>,
+    pub binary_integrity: Arc<Mutex<Option<BinaryIntegrityReport>>>,
+    pub write_buffer: Arc<Mutex<Option<WriteBuffer>>>,
+    /// Decrypted overviews; a std mutex so rayon workers can use it.
+    pub overview_cache: Arc<std::sync::Mutex<OverviewCache>>,
+    /// When the master password or a biometric was last verified.
+    pub last_verification: Arc<Mutex<Option<Instant>>>,
+    pub session: Arc<Mutex<Option<ActiveSession>>>,
+    /// Keys of vaults switched away from while their "relock on switch"
+    /// setting was off, so switching back reopens them without a prompt.
+    pub parked_keys: Arc<Mutex<HashMap<PathBuf, Arc<LockedKey>>>>,
+}
+
+impl AppState {
+    /// Drops every cached plaintext overview.
+    pub fn clear_overview_cache(&self) {
+        self.overview_cache
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clear();
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct PendingUnlock {
-    pub key: Zeroizing<Vec<u8>>,
+    pub key: LockedKey,
     pub created_at: Instant,
     pub attempts: u8,
 }
@@ -0,0 +1,156 @@
+//! Tracks whether the open vault can be written to. A vault on a read-only
+//! file or volume, or on a full disk, stays usable for reading while mutating
+//! commands fail fast with `Error::ReadOnlyStorage`.
+
+use crate::error::{Error, Result};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::{OnceLock, RwLock};
+use tauri::{AppHandle, Emitter};
+
+pub const READ_ONLY_EVENT: &str = "storage-read-only";
+
+const SQLITE_READONLY: i32 = 8;
+const SQLITE_FULL: i32 = 13;
+
+static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+static READ_ONLY_CAUSE: RwLock<Option<String>> = RwLock::new(None);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageStatus {
+    pub read_only: bool,
+    pub cause: Option<String>,
+}
+
+pub fn init(app: AppHandle) {
+    let _ = APP_HANDLE.set(app);
+}
+
+pub fn status() -> StorageStatus {
+    let cause = READ_ONLY_CAUSE.read().ok().and_then(|c| c.clone());
+    StorageStatus {
+        read_only: cause.is_some(),
+        cause,
+    }
+}
+
+pub fn is_read_only() -> bool {
+    status().read_only
+}
+
+/// Latches the session into read-only mode and tells the UI. Only the first
+/// report per vault emits an event.
+pub fn mark_read_only(cause: &str) {
+    let newly_set = match READ_ONLY_CAUSE.write() {
+        Ok(mut guard) => {
+            let newly_set = guard.is_none();
+            if newly_set {
+                *guard = Some(cause.to_string());
+            }
+            newly_set
+        }
+        Err(_) => false,
+    };
+
+    if newly_set {
+        eprintln!("[Storage] Vault is read-only: {}", cause);
+        if let Some(app) = APP_HANDLE.get() {
+            let _ = app.emit(READ_ONLY_EVENT, status());
+        }
+    }
+}
+
+pub fn clear_read_only() {
+    if let Ok(mut guard) = READ_ONLY_CAUSE.write() {
+        *guard = None;
+    }
+}
+
+pub fn ensure_writable() -> Result<()> {
+    match status().cause {
+        Some(cause) => Err(Error::ReadOnlyStorage(cause)),
+        None => Ok(()),
+    }
+}
+
+pub fn read_only_cause_sqlx(err: &sqlx::Error) -> Option<String> {
+    let code = err.as_database_error()?.code()?.parse::<i32>().ok()?;
+    // Extended result codes keep the primary code in the low byte.
+    match code & 0xff {
+        SQLITE_READONLY => Some("The vault file is read-only".to_string()),
+        SQLITE_FULL => Some("The disk is full".to_string()),
+        _ => None,
+    }
+}
+
+pub fn read_only_cause_io(err: &std::io::Error) -> Option<String> {
+    match err.kind() {
+        std::io::ErrorKind::StorageFull | std::io::ErrorKind::QuotaExceeded => {
+            Some("The disk is full".to_string())
+        }
+        std::io::ErrorKind::ReadOnlyFilesystem => {
+            Some("The vault is on a read-only file system".to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Checks that the vault file and its directory (which holds the WAL and
+/// journal files) can be written. Returns the reason when they cannot.
+pub async fn probe_writable(db_path: &Path) -> Option<String> {
+    let metadata = tokio::fs::metadata(db_path).await.ok()?;
+    if metadata.permissions().readonly() {
+        return Some("The vault file is read-only".to_string());
+    }
+
+    if let Err(e) = tokio::fs::OpenOptions::new()
+        .write(true)
+        .open(db_path)
+        .await
+    {
+        return Some(
+            read_only_cause_io(&e)
+                .unwrap_or_else(|| format!("The vault file cannot be opened for writing: {}", e)),
+        );
+    }
+
+    let dir = db_path.parent().unwrap_or_else(|| Path::new("."));
+    let probe = dir.join(format!(".pulsar-write-probe-{}", std::process::id()));
+    match tokio::fs::write(&probe, b"").await {
+        Ok(()) => {
+            let _ = tokio::fs::remove_file(&probe).await;
+            None
+        }
+        Err(e) => Some(
+            read_only_cause_io(&e)
+                .unwrap_or_else(|| format!("The vault directory cannot be written to: {}", e)),
+        ),
+    }
+}
+
+/// Re-evaluates the storage state for `db_path`, clearing a previous
+/// read-only latch when the vault has become writable again.
+pub async fn refresh(db_path: &Path) -> StorageStatus {
+    clear_read_only();
+    if let Some(cause) = probe_writable(db_path).await {
+        mark_read_only(&cause);
+    }
+    status()
+}
+
+#[tauri::command]
+pub async fn get_storage_status() -> Result<StorageStatus> {
+    Ok(status())
+}
+
+#[tauri::command]
+pub async fn recheck_storage(
+    state: tauri::State<'_, crate::state::AppState>,
+) -> Result<StorageStatus> {
+    let db_path = state.db_path.lock().await.clone();
+    match db_path {
+        Some(path) => Ok(refresh(&path).await),
+        None => Err(Error::VaultNotLoaded),
+    }
+}
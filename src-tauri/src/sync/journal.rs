@@ -0,0 +1,191 @@
+//! The change journal: an append-only log of every item creation, edit and
+//! deletion, numbered by a vault-wide revision (`seq`). Triggers on the item
+//! revision tables (migration 38) write it, so every command that changes an
+//! item is recorded without having to remember to.
+//!
+//! An entry holds only the item's sync id, its per-item revision and the
+//! kind of change. What changed is read from the item's encrypted row when
+//! the delta is transferred, so the journal adds no plaintext to the vault.
+//! Consumers remember the last revision they saw and ask for the changes
+//! after it.
+
+use crate::db::utils::UnlockedVault;
+use crate::error::{Error, Result};
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqliteExecutor};
+use tauri::State;
+
+const DEFAULT_PAGE_SIZE: u32 = 1000;
+const MAX_PAGE_SIZE: u32 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeOperation {
+    Create,
+    Update,
+    Delete,
+}
+
+impl ChangeOperation {
+    fn parse(value: &str) -> Result<Self> {
+        match value {
+            "create" => Ok(Self::Create),
+            "update" => Ok(Self::Update),
+            "delete" => Ok(Self::Delete),
+            other => Err(Error::Internal(format!(
+                "Unknown change journal operation '{}'",
+                other
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry {
+    pub seq: i64,
+    pub sync_id: String,
+    /// The item's own revision after the change.
+    pub revision: i64,
+    pub operation: ChangeOperation,
+    pub recorded_at: String,
+    /// The item's id in this vault, unless it has since been deleted.
+    pub item_id: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeJournalPage {
+    pub entries: Vec<JournalEntry>,
+    /// The vault's current revision; pass it as `since` next time.
+    pub latest_revision: i64,
+    /// Set when more entries follow the last one returned.
+    pub has_more: bool,
+}
+
+/// The vault's current revision: the number of the latest journal entry, or
+/// 0 for a vault without any.
+pub(crate) async fn latest_revision<'e, E>(executor: E) -> Result<i64>
+where
+    E: SqliteExecutor<'e>,
+{
+    Ok(
+        sqlx::query_scalar::<_, Option<i64>>("SELECT MAX(seq) FROM change_journal")
+            .fetch_one(executor)
+            .await?
+            .unwrap_or(0),
+    )
+}
+
+/// Journal entries after `since`, oldest first, at most `limit` of them.
+pub(crate) async fn entries_since<'e, E>(
+    executor: E,
+    since: i64,
+    limit: u32,
+) -> Result<Vec<JournalEntry>>
+where
+    E: SqliteExecutor<'e>,
+{
+    sqlx::query(
+        "SELECT j.seq, j.sync_id, j.revision, j.operation, j.recorded_at, r.item_id \
+         FROM change_journal j LEFT JOIN item_revisions r ON r.sync_id = j.sync_id \
+         WHERE j.seq > ? ORDER BY j.seq LIMIT ?",
+    )
+    .bind(since)
+    .bind(i64::from(limit))
+    .fetch_all(executor)
+    .await?
+    .into_iter()
+    .map(|row| {
+        Ok(JournalEntry {
+            seq: row.get("seq"),
+            sync_id: row.get("sync_id"),
+            revision: row.get("revision"),
+            operation: ChangeOperation::parse(row.get("operation"))?,
+            recorded_at: row.get("recorded_at"),
+            item_id: row.get("item_id"),
+        })
+    })
+    .collect()
+}
+
+/// The net change to each item since `since`: the latest entry per sync id,
+/// oldest first. An item created and deleted in between is left out when
+/// `since` predates its creation, as the other side never saw it.
+pub(crate) async fn net_changes_since<'e, E>(executor: E, since: i64) -> Result<Vec<JournalEntry>>
+where
+    E: SqliteExecutor<'e>,
+{
+    let rows = sqlx::query(
+        "SELECT j.seq, j.sync_id, j.revision, j.operation, j.recorded_at, r.item_id, \
+         EXISTS (SELECT 1 FROM change_journal c WHERE c.sync_id = j.sync_id AND c.seq > ? \
+                 AND c.seq < j.seq AND c.operation = 'create') AS created_after \
+         FROM change_journal j LEFT JOIN item_revisions r ON r.sync_id = j.sync_id \
+         WHERE j.seq = (SELECT MAX(seq) FROM change_journal m WHERE m.sync_id = j.sync_id) \
+         AND j.seq > ? ORDER BY j.seq",
+    )
+    .bind(since)
+    .bind(since)
+    .fetch_all(executor)
+    .await?;
+
+    let mut entries = Vec::with_capacity(rows.len());
+    for row in rows {
+        let operation = ChangeOperation::parse(row.get("operation"))?;
+        let created_after: bool = row.get("created_after");
+        if operation == ChangeOperation::Delete && created_after {
+            continue;
+        }
+        entries.push(JournalEntry {
+            seq: row.get("seq"),
+            sync_id: row.get("sync_id"),
+            revision: row.get("revision"),
+            operation,
+            recorded_at: row.get("recorded_at"),
+            item_id: row.get("item_id"),
+        });
+    }
+    Ok(entries)
+}
+
+/// Drops entries superseded by a later one for the same item. The net
+/// change since any revision stays the same, since only the latest entry
+/// per item matters for it, except that a creation followed by a deletion
+/// collapses into the deletion.
+pub(crate) async fn compact<'e, E>(executor: E) -> Result<u64>
+where
+    E: SqliteExecutor<'e>,
+{
+    Ok(sqlx::query(
+        "DELETE FROM change_journal WHERE seq < \
+         (SELECT MAX(seq) FROM change_journal m WHERE m.sync_id = change_journal.sync_id)",
+    )
+    .execute(executor)
+    .await?
+    .rows_affected())
+}
+
+/// Lists journal entries after revision `since`, for a client that keeps
+/// its own copy of the vault's changes.
+#[tauri::command]
+pub async fn get_change_journal(
+    state: State<'_, AppState>,
+    since: i64,
+    limit: Option<u32>,
+) -> Result<ChangeJournalPage> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let vault = UnlockedVault::acquire(&state).await?;
+    let mut tx = vault.pool().begin().await?;
+    let mut entries = entries_since(&mut *tx, since.max(0), limit + 1).await?;
+    let latest_revision = latest_revision(&mut *tx).await?;
+    tx.commit().await?;
+
+    let has_more = entries.len() > limit as usize;
+    entries.truncate(limit as usize);
+    Ok(ChangeJournalPage {
+        entries,
+        latest_revision,
+        has_more,
+    })
+}
@@ -0,0 +1,727 @@
+//! Reconciles two copies of the same vault, such as the conflicted copy a
+//! file sync service leaves next to the original.
+//!
+//! Every item carries a stable sync id and a revision counter, both kept up
+//! to date by triggers (migration 37); deleting an item leaves a tombstone.
+//! The revisions seen at the last sync with a copy are the base of a
+//! three-way merge: a side that changed since then wins over one that did
+//! not, an edit wins over a deletion, and items both sides edited
+//! differently are staged as conflicts for `resolve_conflict` rather than
+//! one copy overwriting the other. Attachments are not copied.
+
+use crate::auth::metadata::get_vault_id;
+use crate::db::attachments::attachments_dir_for;
+use crate::db::conflicts::{same_content, stage_conflict};
+use crate::db::passwords::{ensure_item_writable, fetch_password_item, store_synced_item};
+use crate::db::utils::{CryptoHelper, UnlockedVault};
+use crate::error::{Error, Result};
+use crate::state::AppState;
+use crate::types::PasswordItem;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, Sqlite, SqlitePool, Transaction};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tauri::State;
+use tokio::fs;
+
+pub mod journal;
+pub mod patch;
+pub mod s3;
+pub mod webdav;
+
+/// Stands in for the local revision of an item staged as a conflict, so the
+/// local side counts as changed and the outcome is copied over once the
+/// conflict is resolved.
+const CONFLICT_REVISION: i64 = -1;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncSummary {
+    /// Items copied from the other vault into this one.
+    pub pulled: usize,
+    /// Items copied from this vault into the other one.
+    pub pushed: usize,
+    pub deleted_local: usize,
+    pub deleted_remote: usize,
+    pub conflicts: usize,
+    pub unchanged: usize,
+}
+
+impl SyncSummary {
+    /// Whether the merge wrote anything into the other copy.
+    pub(crate) fn changed_remote(&self) -> bool {
+        self.pushed > 0 || self.deleted_remote > 0
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ItemState {
+    /// `None` for a deleted item.
+    item_id: Option<i64>,
+    revision: i64,
+}
+
+async fn load_states(tx: &mut Transaction<'_, Sqlite>) -> Result<HashMap<String, ItemState>> {
+    let mut states = HashMap::new();
+    for row in sqlx::query("SELECT sync_id, revision FROM sync_tombstones")
+        .fetch_all(tx.as_mut())
+        .await?
+    {
+        states.insert(
+            row.get("sync_id"),
+            ItemState {
+                item_id: None,
+                revision: row.get("revision"),
+            },
+        );
+    }
+    for row in sqlx::query("SELECT sync_id, item_id, revision FROM item_revisions")
+        .fetch_all(tx.as_mut())
+        .await?
+    {
+        states.insert(
+            row.get("sync_id"),
+            ItemState {
+                item_id: Some(row.get("item_id")),
+                revision: row.get("revision"),
+            },
+        );
+    }
+    Ok(states)
+}
+
+/// What the merge does with one item, given its state on each side and
+/// the revisions recorded at the last sync.
+#[derive(Debug, PartialEq, Eq)]
+enum MergeAction {
+    Unchanged,
+    /// Copy local item `from` over `target` in the other vault, or into a
+    /// new row there.
+    Push {
+        from: i64,
+        target: Option<i64>,
+    },
+    /// Copy remote item `from` over local `target`, or into a new row.
+    Pull {
+        from: i64,
+        target: Option<i64>,
+    },
+    DeleteRemote(i64),
+    DeleteLocal(i64),
+    /// Both sides changed the item; compare the two versions.
+    Compare {
+        local: i64,
+        remote: i64,
+    },
+}
+
+fn merge_action(
+    base: Option<(i64, i64)>,
+    local: Option<ItemState>,
+    remote: Option<ItemState>,
+) -> MergeAction {
+    let (base_local, base_remote) = match base {
+        Some((bl, br)) => (Some(bl), Some(br)),
+        None => (None, None),
+    };
+    let local_changed = local.is_some_and(|s| Some(s.revision) != base_local);
+    let remote_changed = remote.is_some_and(|s| Some(s.revision) != base_remote);
+    let l_id = local.and_then(|s| s.item_id);
+    let r_id = remote.and_then(|s| s.item_id);
+
+    match (local_changed, remote_changed) {
+        (false, false) => MergeAction::Unchanged,
+        (true, false) => match (l_id, r_id) {
+            (Some(from), target) => MergeAction::Push { from, target },
+            (None, Some(target)) => MergeAction::DeleteRemote(target),
+            (None, None) => MergeAction::Unchanged,
+        },
+        (false, true) => match (r_id, l_id) {
+            (Some(from), target) => MergeAction::Pull { from, target },
+            (None, Some(target)) => MergeAction::DeleteLocal(target),
+            (None, None) => MergeAction::Unchanged,
+        },
+        // An edit wins over a deletion on the other side.
+        (true, true) => match (l_id, r_id) {
+            (None, None) => MergeAction::Unchanged,
+            (Some(from), None) => MergeAction::Push { from, target: None },
+            (None, Some(from)) => MergeAction::Pull { from, target: None },
+            (Some(local), Some(remote)) => MergeAction::Compare { local, remote },
+        },
+    }
+}
+
+async fn load_state(tx: &mut Transaction<'_, Sqlite>, sync_id: &str) -> Result<Option<i64>> {
+    let live: Option<i64> =
+        sqlx::query_scalar("SELECT revision FROM item_revisions WHERE sync_id = ?")
+            .bind(sync_id)
+            .fetch_optional(tx.as_mut())
+            .await?;
+    if live.is_some() {
+        return Ok(live);
+    }
+    Ok(
+        sqlx::query_scalar("SELECT revision FROM sync_tombstones WHERE sync_id = ?")
+            .bind(sync_id)
+            .fetch_optional(tx.as_mut())
+            .await?,
+    )
+}
+
+/// Writes `item` over `target`, or inserts it under `sync_id` when the side
+/// has no live copy. Returns the item's id on that side. A read-only target
+/// only takes different content from a copy that clears the flag, i.e. one
+/// unlocked on the other side.
+async fn write_item(
+    tx: &mut Transaction<'_, Sqlite>,
+    helper: &CryptoHelper,
+    key: &[u8],
+    target: Option<i64>,
+    sync_id: &str,
+    item: &PasswordItem,
+    now: &str,
+) -> Result<i64> {
+    if let Some(id) = target {
+        if item.read_only && !same_content(&fetch_item(tx, helper, id).await?, item)? {
+            ensure_item_writable(tx.as_mut(), id).await?;
+        }
+        return store_synced_item(tx, helper, key, Some(id), item, now).await;
+    }
+    let id = store_synced_item(tx, helper, key, None, item, now).await?;
+    sqlx::query("UPDATE item_revisions SET sync_id = ? WHERE item_id = ?")
+        .bind(sync_id)
+        .bind(id)
+        .execute(tx.as_mut())
+        .await?;
+    sqlx::query("DELETE FROM sync_tombstones WHERE sync_id = ?")
+        .bind(sync_id)
+        .execute(tx.as_mut())
+        .await?;
+    Ok(id)
+}
+
+/// Deletes an item and the rows that belong to it. Returns the ids of its
+/// attachments, whose files are removed once the transaction commits.
+/// Read-only items are refused; the merge copies them back instead.
+async fn delete_item(tx: &mut Transaction<'_, Sqlite>, id: i64) -> Result<Vec<i64>> {
+    ensure_item_writable(tx.as_mut(), id).await?;
+    let attachments: Vec<i64> = sqlx::query_scalar("SELECT id FROM attachments WHERE item_id = ?")
+        .bind(id)
+        .fetch_all(tx.as_mut())
+        .await?;
+    for table in [
+        "attachments",
+        "item_tags",
+        "search_trigrams",
+        "item_drafts",
+        "item_conflicts",
+        "note_history",
+    ] {
+        sqlx::query(&format!("DELETE FROM {} WHERE item_id = ?", table))
+            .bind(id)
+            .execute(tx.as_mut())
+            .await?;
+    }
+    sqlx::query("DELETE FROM password_items WHERE id = ?")
+        .bind(id)
+        .execute(tx.as_mut())
+        .await?;
+    Ok(attachments)
+}
+
+async fn is_read_only(tx: &mut Transaction<'_, Sqlite>, id: i64) -> Result<bool> {
+    let read_only: Option<bool> =
+        sqlx::query_scalar("SELECT read_only FROM password_items WHERE id = ?")
+            .bind(id)
+            .fetch_optional(tx.as_mut())
+            .await?;
+    Ok(read_only == Some(true))
+}
+
+async fn fetch_item(
+    tx: &mut Transaction<'_, Sqlite>,
+    helper: &CryptoHelper,
+    id: i64,
+) -> Result<PasswordItem> {
+    fetch_password_item(tx.as_mut(), helper, id)
+        .await?
+        .ok_or_else(|| Error::Internal(format!("Item {} disappeared during sync", id)))
+}
+
+async fn remove_attachment_files(db_path: &Path, ids: &[i64]) {
+    let Ok(dir) = attachments_dir_for(db_path) else {
+        return;
+    };
+    for id in ids {
+        let _ = fs::remove_file(dir.join(id.to_string())).await;
+    }
+}
+
+/// Item ids in this vault the merge changed, for change events.
+#[derive(Debug, Default)]
+pub(crate) struct LocalChanges {
+    pub created: Vec<i64>,
+    pub updated: Vec<i64>,
+    pub deleted: Vec<i64>,
+}
+
+/// Revisions both sides agree on after a merge, as
+/// `(sync_id, local_revision, remote_revision)`.
+pub(crate) type SyncBase = Vec<(String, i64, i64)>;
+
+pub(crate) struct SyncOutcome {
+    pub summary: SyncSummary,
+    pub changes: LocalChanges,
+    pub base: SyncBase,
+}
+
+impl SyncOutcome {
+    pub(crate) async fn record_base(&self, local: &SqlitePool, peer_id: &str) -> Result<()> {
+        record_base(local, peer_id, &self.base).await
+    }
+}
+
+/// Records a merge as the base of the next sync with `peer_id`. Call only
+/// once the other copy is safely stored: recording a base the other copy
+/// never received would make its older items look like new edits.
+pub(crate) async fn record_base(local: &SqlitePool, peer_id: &str, base: &SyncBase) -> Result<()> {
+    let mut tx = local.begin().await?;
+    for (sync_id, local_revision, remote_revision) in base {
+        sqlx::query(
+            "INSERT OR REPLACE INTO sync_base (peer_id, sync_id, local_revision, remote_revision) VALUES (?, ?, ?, ?)",
+        )
+        .bind(peer_id)
+        .bind(sync_id)
+        .bind(local_revision)
+        .bind(remote_revision)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Merges the vault at `local_path` with the copy at `remote_path`, both
+/// opened with `key`, writing the result into both. `peer_id` names the
+/// other copy, so each copy synced with has its own base; the caller records
+/// it with `SyncOutcome::record_base`.
+pub(crate) async fn sync_vaults(
+    local: &SqlitePool,
+    local_path: &Path,
+    remote: &SqlitePool,
+    remote_path: &Path,
+    peer_id: &str,
+    key: &[u8],
+    source: &str,
+) -> Result<SyncOutcome> {
+    let helper = CryptoHelper::new(key)?;
+    let now = Utc::now().to_rfc3339();
+    let mut summary = SyncSummary::default();
+    let mut changes = LocalChanges::default();
+    let mut next_base = Vec::new();
+    let mut local_files = Vec::new();
+    let mut remote_files = Vec::new();
+
+    let mut ltx = local.begin().await?;
+    let mut rtx = remote.begin().await?;
+
+    let local_states = load_states(&mut ltx).await?;
+    let remote_states = load_states(&mut rtx).await?;
+    let base: HashMap<String, (i64, i64)> = sqlx::query(
+        "SELECT sync_id, local_revision, remote_revision FROM sync_base WHERE peer_id = ?",
+    )
+    .bind(peer_id)
+    .fetch_all(ltx.as_mut())
+    .await?
+    .into_iter()
+    .map(|row| {
+        (
+            row.get("sync_id"),
+            (row.get("local_revision"), row.get("remote_revision")),
+        )
+    })
+    .collect();
+    let pending: HashSet<i64> = sqlx::query_scalar("SELECT item_id FROM item_conflicts")
+        .fetch_all(ltx.as_mut())
+        .await?
+        .into_iter()
+        .collect();
+
+    let sync_ids: BTreeSet<&String> = local_states.keys().chain(remote_states.keys()).collect();
+    for sync_id in sync_ids {
+        let l = local_states.get(sync_id).copied();
+        let r = remote_states.get(sync_id).copied();
+        if l.and_then(|s| s.item_id)
+            .is_some_and(|id| pending.contains(&id))
+        {
+            continue;
+        }
+        let mut conflict = false;
+
+        match merge_action(base.get(sync_id).copied(), l, r) {
+            MergeAction::Unchanged => summary.unchanged += 1,
+            MergeAction::Push { from, target } => {
+                let item = fetch_item(&mut ltx, &helper, from).await?;
+                write_item(&mut rtx, &helper, key, target, sync_id, &item, &now).await?;
+                summary.pushed += 1;
+            }
+            MergeAction::Pull { from, target } => {
+                let item = fetch_item(&mut rtx, &helper, from).await?;
+                let local_id =
+                    write_item(&mut ltx, &helper, key, target, sync_id, &item, &now).await?;
+                if target.is_some() {
+                    changes.updated.push(local_id);
+                } else {
+                    changes.created.push(local_id);
+                }
+                summary.pulled += 1;
+            }
+            // A read-only item outlives the deletion, like an edit.
+            MergeAction::DeleteRemote(target) if is_read_only(&mut rtx, target).await? => {
+                let item = fetch_item(&mut rtx, &helper, target).await?;
+                let local_id =
+                    write_item(&mut ltx, &helper, key, None, sync_id, &item, &now).await?;
+                changes.created.push(local_id);
+                summary.pulled += 1;
+            }
+            MergeAction::DeleteRemote(target) => {
+                remote_files.extend(delete_item(&mut rtx, target).await?);
+                summary.deleted_remote += 1;
+            }
+            MergeAction::DeleteLocal(target) if is_read_only(&mut ltx, target).await? => {
+                let item = fetch_item(&mut ltx, &helper, target).await?;
+                write_item(&mut rtx, &helper, key, None, sync_id, &item, &now).await?;
+                summary.pushed += 1;
+            }
+            MergeAction::DeleteLocal(target) => {
+                local_files.extend(delete_item(&mut ltx, target).await?);
+                changes.deleted.push(target);
+                summary.deleted_local += 1;
+            }
+            MergeAction::Compare { local, remote } => {
+                let local_item = fetch_item(&mut ltx, &helper, local).await?;
+                let remote_item = fetch_item(&mut rtx, &helper, remote).await?;
+                if same_content(&local_item, &remote_item)? {
+                    summary.unchanged += 1;
+                } else {
+                    stage_conflict(&mut ltx, &helper, local, source, &remote_item).await?;
+                    conflict = true;
+                    summary.conflicts += 1;
+                }
+            }
+        }
+
+        let local_revision = if conflict {
+            Some(CONFLICT_REVISION)
+        } else {
+            load_state(&mut ltx, sync_id).await?
+        };
+        let remote_revision = load_state(&mut rtx, sync_id).await?;
+        if let (Some(lr), Some(rr)) = (local_revision, remote_revision) {
+            next_base.push((sync_id.clone(), lr, rr));
+        }
+    }
+
+    let _ = crate::db::activity::log_activity_impl(
+        ltx.as_mut(),
+        key,
+        "vault_synced",
+        None,
+        None,
+        Some(&format!(
+            "Synced with {}: {} pulled, {} pushed, {} deleted here, {} deleted there, {} conflicts",
+            source,
+            summary.pulled,
+            summary.pushed,
+            summary.deleted_local,
+            summary.deleted_remote,
+            summary.conflicts
+        )),
+    )
+    .await;
+
+    // The base is recorded separately, so whichever commit fails, the next
+    // sync compares against the old base and at worst repeats work.
+    rtx.commit().await?;
+    ltx.commit().await?;
+
+    remove_attachment_files(remote_path, &remote_files).await;
+    remove_attachment_files(local_path, &local_files).await;
+    Ok(SyncOutcome {
+        summary,
+        changes,
+        base: next_base,
+    })
+}
+
+/// Opens another copy of the open vault with the open vault's key, bringing
+/// its schema up to date. With `create_if_missing`, a missing copy starts out
+/// as an empty vault.
+pub(crate) async fn open_vault_copy(
+    path: &Path,
+    key: &[u8],
+    create_if_missing: bool,
+) -> Result<SqlitePool> {
+    let pool = crate::db::init_db_lazy(path, Some(key), create_if_missing)
+        .await
+        .map_err(Error::Internal)?;
+    let opened = async {
+        sqlx::query("SELECT COUNT(*) FROM sqlite_master")
+            .execute(&pool)
+            .await
+            .map_err(|_| {
+                Error::Validation(
+                    "The other copy could not be opened with this vault's key.".to_string(),
+                )
+            })?;
+        crate::db::migrations::ensure_supported(&pool).await?;
+        crate::db::migrations::MIGRATOR
+            .run(&pool)
+            .await
+            .map_err(|e| Error::Database(e.into()))
+    }
+    .await;
+    if let Err(e) = opened {
+        pool.close().await;
+        return Err(e);
+    }
+    Ok(pool)
+}
+
+/// A private directory holding a downloaded copy of the vault, removed with
+/// everything in it when dropped.
+pub(crate) struct ScratchCopy {
+    dir: PathBuf,
+}
+
+impl ScratchCopy {
+    pub(crate) async fn new() -> Result<Self> {
+        let dir = std::env::temp_dir().join(format!(
+            "pulsar-sync-{}",
+            hex::encode(rand::random::<[u8; 16]>())
+        ));
+        let mut builder = fs::DirBuilder::new();
+        #[cfg(unix)]
+        {
+            builder.mode(0o700);
+        }
+        builder.create(&dir).await?;
+        Ok(Self { dir })
+    }
+
+    pub(crate) fn path(&self) -> PathBuf {
+        self.dir.join("remote.psec")
+    }
+}
+
+impl Drop for ScratchCopy {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Merges the open vault with a downloaded copy, or with a new empty copy
+/// when `existed` is false, and leaves the copy's file self-contained for
+/// upload. Upload it when `!existed || outcome.summary.changed_remote()`,
+/// then record the outcome's base.
+pub(crate) async fn merge_with_copy(
+    vault: &UnlockedVault,
+    local_path: &Path,
+    copy: &ScratchCopy,
+    existed: bool,
+    peer_id: &str,
+    source: &str,
+) -> Result<SyncOutcome> {
+    let path = copy.path();
+    let remote = open_vault_copy(&path, vault.key(), !existed).await?;
+    let result = async {
+        let outcome = sync_vaults(
+            vault.pool(),
+            local_path,
+            &remote,
+            &path,
+            peer_id,
+            vault.key(),
+            source,
+        )
+        .await?;
+        crate::db::wal::checkpoint(&remote, crate::db::wal::CheckpointMode::Truncate).await?;
+        Ok(outcome)
+    }
+    .await;
+    remote.close().await;
+    result
+}
+
+pub(crate) fn emit_local_changes(app: &tauri::AppHandle, changes: LocalChanges) {
+    crate::events::items_created(app, changes.created);
+    crate::events::items_updated(app, changes.updated);
+    crate::events::items_deleted(app, changes.deleted);
+}
+
+/// Merges the open vault with another copy of it at `path`, for example the
+/// conflicted copy left by a file sync service. Changes flow both ways;
+/// items edited differently in both are listed by `list_pending_conflicts`.
+#[tauri::command]
+pub async fn sync_with_vault_copy(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    path: PathBuf,
+) -> Result<SyncSummary> {
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let local_path = state
+        .db_path
+        .lock()
+        .await
+        .clone()
+        .ok_or(Error::VaultNotLoaded)?;
+    let path = fs::canonicalize(&path).await?;
+    if path == fs::canonicalize(&local_path).await? {
+        return Err(Error::Validation(
+            "Choose a different copy of the vault to sync with.".to_string(),
+        ));
+    }
+
+    let peer_id = get_vault_id(&path);
+    let remote = open_vault_copy(&path, vault.key(), false).await?;
+    let result = sync_vaults(
+        vault.pool(),
+        &local_path,
+        &remote,
+        &path,
+        &peer_id,
+        vault.key(),
+        "file_sync",
+    )
+    .await;
+    remote.close().await;
+    let outcome = result?;
+    outcome.record_base(vault.pool(), &peer_id).await?;
+
+    emit_local_changes(&app, outcome.changes);
+    Ok(outcome.summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn live(item_id: i64, revision: i64) -> Option<ItemState> {
+        Some(ItemState {
+            item_id: Some(item_id),
+            revision,
+        })
+    }
+
+    fn deleted(revision: i64) -> Option<ItemState> {
+        Some(ItemState {
+            item_id: None,
+            revision,
+        })
+    }
+
+    #[test]
+    fn test_untouched_items_are_left_alone() {
+        assert_eq!(
+            merge_action(Some((3, 5)), live(1, 3), live(2, 5)),
+            MergeAction::Unchanged
+        );
+        assert_eq!(
+            merge_action(Some((3, 5)), deleted(3), deleted(5)),
+            MergeAction::Unchanged
+        );
+    }
+
+    #[test]
+    fn test_new_items_are_copied_to_the_other_side() {
+        assert_eq!(
+            merge_action(None, live(1, 1), None),
+            MergeAction::Push {
+                from: 1,
+                target: None
+            }
+        );
+        assert_eq!(
+            merge_action(None, None, live(2, 1)),
+            MergeAction::Pull {
+                from: 2,
+                target: None
+            }
+        );
+    }
+
+    #[test]
+    fn test_the_side_that_changed_wins() {
+        assert_eq!(
+            merge_action(Some((3, 5)), live(1, 4), live(2, 5)),
+            MergeAction::Push {
+                from: 1,
+                target: Some(2)
+            }
+        );
+        assert_eq!(
+            merge_action(Some((3, 5)), live(1, 3), live(2, 6)),
+            MergeAction::Pull {
+                from: 2,
+                target: Some(1)
+            }
+        );
+    }
+
+    #[test]
+    fn test_deletions_spread_to_untouched_copies() {
+        assert_eq!(
+            merge_action(Some((3, 5)), deleted(4), live(2, 5)),
+            MergeAction::DeleteRemote(2)
+        );
+        assert_eq!(
+            merge_action(Some((3, 5)), live(1, 3), deleted(6)),
+            MergeAction::DeleteLocal(1)
+        );
+    }
+
+    #[test]
+    fn test_an_edit_wins_over_a_deletion() {
+        assert_eq!(
+            merge_action(Some((3, 5)), deleted(4), live(2, 6)),
+            MergeAction::Pull {
+                from: 2,
+                target: None
+            }
+        );
+        assert_eq!(
+            merge_action(Some((3, 5)), live(1, 4), deleted(6)),
+            MergeAction::Push {
+                from: 1,
+                target: None
+            }
+        );
+    }
+
+    #[test]
+    fn test_items_changed_on_both_sides_are_compared() {
+        assert_eq!(
+            merge_action(Some((3, 5)), live(1, 4), live(2, 6)),
+            MergeAction::Compare {
+                local: 1,
+                remote: 2
+            }
+        );
+        assert_eq!(
+            merge_action(Some((3, 5)), deleted(4), deleted(6)),
+            MergeAction::Unchanged
+        );
+    }
+
+    #[test]
+    fn test_items_without_a_base_count_as_changed() {
+        assert_eq!(
+            merge_action(None, live(1, 1), live(2, 1)),
+            MergeAction::Compare {
+                local: 1,
+                remote: 2
+            }
+        );
+    }
+}
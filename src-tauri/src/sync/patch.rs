@@ -0,0 +1,331 @@
+//! Patch files for syncing by hand with machines that have no network
+//! connection. `export_changes_since` writes the net change to each item
+//! since a journal revision into a passphrase-encrypted file;
+//! `apply_change_patch` merges such a file into the open vault using the
+//! same three-way rules as `sync_vaults`, so items edited on both sides are
+//! staged as conflicts. Each exporting device and vault counts as its own
+//! peer with its own base, which makes applying a patch twice harmless.
+//! Attachments are not included.
+
+use crate::auth::metadata::get_vault_id;
+use crate::crypto::{open_with_passphrase, seal_with_passphrase};
+use crate::db::conflicts::{same_content, stage_conflict};
+use crate::db::utils::{CryptoHelper, UnlockedVault};
+use crate::error::{Error, Result};
+use crate::file_dialog::pick_save_file;
+use crate::limits;
+use crate::state::AppState;
+use crate::sync::journal::{latest_revision, net_changes_since, ChangeOperation};
+use crate::sync::{
+    delete_item, emit_local_changes, fetch_item, load_state, record_base, remove_attachment_files,
+    write_item, LocalChanges, SyncSummary, CONFLICT_REVISION,
+};
+use crate::types::{ExportPayload, PasswordItem};
+use crate::utils::write_sensitive_bytes;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, Sqlite, Transaction};
+use std::path::PathBuf;
+use tauri::{AppHandle, State, Window};
+use zeroize::Zeroizing;
+
+const PATCH_FORMAT: &str = "pulsar-change-patch";
+const PATCH_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PatchChange {
+    sync_id: String,
+    /// The item's revision in the exporting vault.
+    revision: i64,
+    operation: ChangeOperation,
+    /// The item as it is now; `None` for a deletion.
+    item: Option<PasswordItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChangePatch {
+    format: String,
+    version: u32,
+    /// Identifies the exporting device and vault.
+    source: String,
+    from_revision: i64,
+    to_revision: i64,
+    created_at: String,
+    changes: Vec<PatchChange>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangePatchExport {
+    pub path: String,
+    pub from_revision: i64,
+    /// Export from this revision next time.
+    pub to_revision: i64,
+    pub changes: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangePatchResult {
+    pub summary: SyncSummary,
+    pub from_revision: i64,
+    /// The exporting vault's revision the patch brings this vault up to.
+    pub to_revision: i64,
+}
+
+fn patch_source(db_path: &std::path::Path) -> Result<String> {
+    Ok(format!(
+        "patch:{}:{}",
+        crate::security::current_device_id()?,
+        get_vault_id(db_path)
+    ))
+}
+
+async fn local_item_id(tx: &mut Transaction<'_, Sqlite>, sync_id: &str) -> Result<Option<i64>> {
+    Ok(
+        sqlx::query_scalar("SELECT item_id FROM item_revisions WHERE sync_id = ?")
+            .bind(sync_id)
+            .fetch_optional(tx.as_mut())
+            .await?,
+    )
+}
+
+/// Writes the changes made to the vault after journal revision `revision`
+/// into a file chosen by the user, encrypted with `passphrase`.
+#[tauri::command]
+pub async fn export_changes_since(
+    window: Window,
+    state: State<'_, AppState>,
+    revision: i64,
+    passphrase: String,
+) -> Result<ChangePatchExport> {
+    let passphrase = Zeroizing::new(passphrase);
+    if passphrase.is_empty() {
+        return Err(Error::Validation(
+            "A passphrase is required to export changes.".to_string(),
+        ));
+    }
+    crate::auth::reverify::require_fresh(
+        &state,
+        crate::auth::reverify::SensitiveOperation::ExportVault,
+    )
+    .await?;
+    let from_revision = revision.max(0);
+    let path = PathBuf::from(pick_save_file(window).await?);
+
+    let vault = UnlockedVault::acquire(&state).await?;
+    let db_path = state
+        .db_path
+        .lock()
+        .await
+        .clone()
+        .ok_or(Error::VaultNotLoaded)?;
+    let helper = CryptoHelper::new(vault.key())?;
+
+    let mut tx = vault.pool().begin().await?;
+    let to_revision = latest_revision(&mut *tx).await?;
+    if from_revision > to_revision {
+        return Err(Error::Validation(format!(
+            "This vault is only at revision {}.",
+            to_revision
+        )));
+    }
+    let mut changes = Vec::new();
+    for entry in net_changes_since(&mut *tx, from_revision).await? {
+        let item = match (entry.operation, entry.item_id) {
+            (ChangeOperation::Delete, _) => None,
+            (_, Some(id)) => Some(fetch_item(&mut tx, &helper, id).await?),
+            (_, None) => continue,
+        };
+        changes.push(PatchChange {
+            sync_id: entry.sync_id,
+            revision: entry.revision,
+            operation: entry.operation,
+            item,
+        });
+    }
+    tx.commit().await?;
+
+    let patch = ChangePatch {
+        format: PATCH_FORMAT.to_string(),
+        version: PATCH_VERSION,
+        source: patch_source(&db_path)?,
+        from_revision,
+        to_revision,
+        created_at: Utc::now().to_rfc3339(),
+        changes,
+    };
+    let change_count = patch.changes.len();
+    let plaintext = Zeroizing::new(serde_json::to_vec(&patch)?);
+    let payload = tauri::async_runtime::spawn_blocking(move || {
+        seal_with_passphrase(&plaintext, passphrase.as_str())
+    })
+    .await
+    .map_err(|e| Error::Internal(format!("Runtime error: {}", e)))??;
+
+    write_sensitive_bytes(&path, &serde_json::to_vec(&payload)?).await?;
+
+    Ok(ChangePatchExport {
+        path: path.display().to_string(),
+        from_revision,
+        to_revision,
+        changes: change_count,
+    })
+}
+
+/// Merges a patch file written by `export_changes_since` into the open
+/// vault. Items changed both here and in the patch are staged as conflicts.
+#[tauri::command]
+pub async fn apply_change_patch(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: PathBuf,
+    passphrase: String,
+) -> Result<ChangePatchResult> {
+    let passphrase = Zeroizing::new(passphrase);
+    limits::check_file_size("Patch file", &path, limits::MAX_IMPORT_FILE_SIZE).await?;
+    let bytes = tokio::fs::read(&path).await?;
+    let payload: ExportPayload = serde_json::from_slice(&bytes)
+        .map_err(|_| Error::Validation("The file is not a Pulsar change patch.".to_string()))?;
+    let plaintext = tauri::async_runtime::spawn_blocking(move || {
+        open_with_passphrase(&payload, passphrase.as_str())
+    })
+    .await
+    .map_err(|e| Error::Internal(format!("Runtime error: {}", e)))??;
+    let patch: ChangePatch = serde_json::from_slice(&plaintext)
+        .map_err(|_| Error::Validation("The file is not a Pulsar change patch.".to_string()))?;
+    if patch.format != PATCH_FORMAT || patch.version != PATCH_VERSION {
+        return Err(Error::Validation(format!(
+            "Unsupported change patch version {}",
+            patch.version
+        )));
+    }
+    for item in patch.changes.iter().filter_map(|c| c.item.as_ref()) {
+        limits::check_password_item(item)?;
+    }
+
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let local_path = state
+        .db_path
+        .lock()
+        .await
+        .clone()
+        .ok_or(Error::VaultNotLoaded)?;
+    if patch.source == patch_source(&local_path)? {
+        return Err(Error::Validation(
+            "This patch was exported from this vault.".to_string(),
+        ));
+    }
+
+    let key = vault.key();
+    let helper = CryptoHelper::new(key)?;
+    let now = Utc::now().to_rfc3339();
+    let mut summary = SyncSummary::default();
+    let mut changes = LocalChanges::default();
+    let mut next_base = Vec::new();
+    let mut removed_files = Vec::new();
+
+    let mut tx = vault.pool().begin().await?;
+    for change in &patch.changes {
+        let sync_id = change.sync_id.as_str();
+        let l_id = local_item_id(&mut tx, sync_id).await?;
+        if let Some(id) = l_id {
+            let pending: bool = sqlx::query_scalar(
+                "SELECT EXISTS (SELECT 1 FROM item_conflicts WHERE item_id = ?)",
+            )
+            .bind(id)
+            .fetch_one(tx.as_mut())
+            .await?;
+            if pending {
+                continue;
+            }
+        }
+        let base = sqlx::query(
+            "SELECT local_revision, remote_revision FROM sync_base WHERE peer_id = ? AND sync_id = ?",
+        )
+        .bind(&patch.source)
+        .bind(sync_id)
+        .fetch_optional(tx.as_mut())
+        .await?
+        .map(|row| {
+            (
+                row.get::<i64, _>("local_revision"),
+                row.get::<i64, _>("remote_revision"),
+            )
+        });
+        let local_revision = load_state(&mut tx, sync_id).await?;
+        let local_changed =
+            local_revision.is_some() && local_revision != base.map(|(local, _)| local);
+        let remote_changed = Some(change.revision) != base.map(|(_, remote)| remote);
+        let mut conflict = false;
+
+        match (local_changed, remote_changed, l_id, &change.item) {
+            (_, false, _, _) => summary.unchanged += 1,
+            (false, true, target, Some(item)) | (true, true, target @ None, Some(item)) => {
+                let id = write_item(&mut tx, &helper, key, target, sync_id, item, &now).await?;
+                if target.is_some() {
+                    changes.updated.push(id);
+                } else {
+                    changes.created.push(id);
+                }
+                summary.pulled += 1;
+            }
+            (false, true, Some(id), None) => {
+                removed_files.extend(delete_item(&mut tx, id).await?);
+                changes.deleted.push(id);
+                summary.deleted_local += 1;
+            }
+            (true, true, Some(id), Some(item)) => {
+                let local_item = fetch_item(&mut tx, &helper, id).await?;
+                if same_content(&local_item, item)? {
+                    summary.unchanged += 1;
+                } else {
+                    stage_conflict(&mut tx, &helper, id, "change_patch", item).await?;
+                    conflict = true;
+                    summary.conflicts += 1;
+                }
+            }
+            // Deleted on both sides, or edited here and deleted there: an
+            // edit wins over a deletion.
+            (_, true, _, None) => summary.unchanged += 1,
+        }
+
+        let local_revision = if conflict {
+            Some(CONFLICT_REVISION)
+        } else {
+            load_state(&mut tx, sync_id).await?
+        };
+        if let Some(local_revision) = local_revision {
+            next_base.push((change.sync_id.clone(), local_revision, change.revision));
+        }
+    }
+
+    let _ = crate::db::activity::log_activity_impl(
+        tx.as_mut(),
+        key,
+        "change_patch_applied",
+        None,
+        None,
+        Some(&format!(
+            "Applied changes {} to {}: {} pulled, {} deleted, {} conflicts",
+            patch.from_revision,
+            patch.to_revision,
+            summary.pulled,
+            summary.deleted_local,
+            summary.conflicts
+        )),
+    )
+    .await;
+    tx.commit().await?;
+    record_base(vault.pool(), &patch.source, &next_base).await?;
+
+    remove_attachment_files(&local_path, &removed_files).await;
+    emit_local_changes(&app, changes);
+    Ok(ChangePatchResult {
+        summary,
+        from_revision: patch.from_revision,
+        to_revision: patch.to_revision,
+    })
+}
@@ -0,0 +1,448 @@
+//! Sync through a WebDAV server such as Nextcloud or ownCloud. The server
+//! holds a copy of the vault, encrypted with the vault's key like the vault
+//! itself. Each run downloads it, merges it with the open vault, and uploads
+//! it again if the merge changed it, using the ETag so that a copy another
+//! device uploaded in the meantime is merged on the next run rather than
+//! overwritten. The connection settings, including the password, are stored
+//! encrypted in the vault.
+//!
+//! With a pinned certificate, every response is checked against the pinned
+//! SHA-256 fingerprint of the server's certificate, and an unauthenticated
+//! request checks it before the credentials are sent.
+
+use crate::db::utils::UnlockedVault;
+use crate::encryption::{decrypt, encrypt};
+use crate::error::{Error, Result};
+use crate::limits;
+use crate::state::AppState;
+use crate::sync::{emit_local_changes, merge_with_copy, ScratchCopy, SyncSummary};
+use chrono::{DateTime, Utc};
+use reqwest::header::{ETAG, IF_MATCH, IF_NONE_MATCH};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use zeroize::Zeroizing;
+
+const CONFIG_KEY: &str = "sync_webdav";
+const DEFAULT_INTERVAL_MINUTES: u32 = 30;
+const MIN_INTERVAL_MINUTES: u32 = 5;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WebDavConfig {
+    pub enabled: bool,
+    /// URL of the vault copy on the server, e.g.
+    /// `https://cloud.example.com/remote.php/dav/files/me/Pulsar/vault.psec`.
+    pub url: String,
+    pub username: String,
+    pub interval_minutes: u32,
+    /// Hex SHA-256 fingerprint of the server's certificate.
+    pub pinned_certificate_sha256: Option<String>,
+}
+
+impl Default for WebDavConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: String::new(),
+            username: String::new(),
+            interval_minutes: DEFAULT_INTERVAL_MINUTES,
+            pinned_certificate_sha256: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebDavSyncStatus {
+    pub configured: bool,
+    pub enabled: bool,
+    pub running: bool,
+    pub last_sync: Option<String>,
+    pub last_summary: Option<SyncSummary>,
+    /// Set when the latest attempt failed.
+    pub last_error: Option<String>,
+}
+
+/// What is persisted, encrypted, in the vault's configuration table.
+#[derive(Serialize, Deserialize, Default)]
+struct StoredWebDav {
+    config: WebDavConfig,
+    #[serde(default)]
+    password: Option<String>,
+    #[serde(default)]
+    last_sync: Option<String>,
+    #[serde(default)]
+    last_summary: Option<SyncSummary>,
+    #[serde(default)]
+    last_error: Option<String>,
+}
+
+async fn load_stored(pool: &SqlitePool, key: &[u8]) -> Result<Option<StoredWebDav>> {
+    let value: Option<String> = sqlx::query_scalar("SELECT value FROM configuration WHERE key = ?")
+        .bind(CONFIG_KEY)
+        .fetch_optional(pool)
+        .await?;
+    match value {
+        Some(enc) => {
+            let json = Zeroizing::new(decrypt(&enc, key)?);
+            Ok(Some(serde_json::from_str(&json)?))
+        }
+        None => Ok(None),
+    }
+}
+
+async fn save_stored(pool: &SqlitePool, key: &[u8], stored: &StoredWebDav) -> Result<()> {
+    let json = Zeroizing::new(serde_json::to_string(stored)?);
+    sqlx::query("INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)")
+        .bind(CONFIG_KEY)
+        .bind(encrypt(&json, key)?)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+fn normalize_fingerprint(fingerprint: &str) -> Result<String> {
+    let hex: String = fingerprint
+        .chars()
+        .filter(|c| *c != ':' && !c.is_whitespace())
+        .collect::<String>()
+        .to_lowercase();
+    if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(Error::Validation(
+            "The certificate fingerprint must be a SHA-256 hash in hex.".to_string(),
+        ));
+    }
+    Ok(hex)
+}
+
+fn network_error(e: reqwest::Error) -> Error {
+    Error::Network(e.to_string())
+}
+
+fn http_client(pinned: bool) -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .https_only(true)
+        .tls_info(pinned)
+        .timeout(REQUEST_TIMEOUT)
+        .user_agent(concat!("Pulsar/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(network_error)
+}
+
+fn check_pin(response: &reqwest::Response, pin: Option<&str>) -> Result<()> {
+    let Some(pin) = pin else {
+        return Ok(());
+    };
+    let certificate = response
+        .extensions()
+        .get::<reqwest::tls::TlsInfo>()
+        .and_then(|info| info.peer_certificate())
+        .ok_or_else(|| Error::Network("The server did not present a certificate.".to_string()))?;
+    if hex::encode(Sha256::digest(certificate)) != pin {
+        return Err(Error::Network(
+            "The server's certificate does not match the pinned fingerprint.".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Downloads the server's copy into `dest`. Returns `None` when there is no
+/// copy yet, and otherwise its ETag, if the server sent one.
+async fn download(
+    client: &reqwest::Client,
+    stored: &StoredWebDav,
+    password: &str,
+    dest: &Path,
+) -> Result<Option<Option<String>>> {
+    let pin = stored.config.pinned_certificate_sha256.as_deref();
+    let mut response = client
+        .get(&stored.config.url)
+        .basic_auth(&stored.config.username, Some(password))
+        .send()
+        .await
+        .map_err(network_error)?;
+    check_pin(&response, pin)?;
+    match response.status() {
+        StatusCode::NOT_FOUND => return Ok(None),
+        status if !status.is_success() => {
+            return Err(Error::Network(format!(
+                "The WebDAV server answered {} to the download.",
+                status
+            )))
+        }
+        _ => {}
+    }
+    let etag = response
+        .headers()
+        .get(ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+
+    let mut file = fs::File::create(dest).await?;
+    while let Some(chunk) = response.chunk().await.map_err(network_error)? {
+        file.write_all(&chunk).await?;
+    }
+    file.sync_all().await?;
+    Ok(Some(etag))
+}
+
+/// Uploads `source` over the server's copy, provided the copy is still the
+/// one downloaded: `download` is what `download` returned. Servers that send
+/// no ETag get an unconditional upload.
+async fn upload(
+    client: &reqwest::Client,
+    stored: &StoredWebDav,
+    password: &str,
+    source: &Path,
+    download: Option<&Option<String>>,
+) -> Result<()> {
+    let mut request = client
+        .put(&stored.config.url)
+        .basic_auth(&stored.config.username, Some(password))
+        .body(fs::read(source).await?);
+    request = match download {
+        Some(Some(etag)) => request.header(IF_MATCH, etag),
+        Some(None) => request,
+        None => request.header(IF_NONE_MATCH, "*"),
+    };
+    let response = request.send().await.map_err(network_error)?;
+    check_pin(
+        &response,
+        stored.config.pinned_certificate_sha256.as_deref(),
+    )?;
+    match response.status() {
+        StatusCode::PRECONDITION_FAILED => Err(Error::Network(
+            "The vault on the server changed during the sync. It will be merged on the next run."
+                .to_string(),
+        )),
+        status if status.is_success() => Ok(()),
+        status => Err(Error::Network(format!(
+            "The WebDAV server answered {} to the upload.",
+            status
+        ))),
+    }
+}
+
+async fn run_sync_inner(app: &AppHandle, state: &AppState) -> Result<SyncSummary> {
+    let vault = UnlockedVault::acquire_writable(state).await?;
+    let local_path = state
+        .db_path
+        .lock()
+        .await
+        .clone()
+        .ok_or(Error::VaultNotLoaded)?;
+    let stored = load_stored(vault.pool(), vault.key())
+        .await?
+        .ok_or_else(|| Error::Validation("WebDAV sync is not set up.".to_string()))?;
+    let password = Zeroizing::new(
+        stored
+            .password
+            .clone()
+            .ok_or_else(|| Error::Validation("WebDAV sync has no password.".to_string()))?,
+    );
+
+    let pin = stored.config.pinned_certificate_sha256.as_deref();
+    let client = http_client(pin.is_some())?;
+    if pin.is_some() {
+        let probe = client
+            .head(&stored.config.url)
+            .send()
+            .await
+            .map_err(network_error)?;
+        check_pin(&probe, pin)?;
+    }
+
+    let copy = ScratchCopy::new().await?;
+    let etag = download(&client, &stored, &password, &copy.path()).await?;
+    let existed = etag.is_some();
+    let peer_id = format!("webdav:{}", stored.config.url);
+    let outcome = merge_with_copy(&vault, &local_path, &copy, existed, &peer_id, "webdav").await?;
+
+    if !existed || outcome.summary.changed_remote() {
+        upload(&client, &stored, &password, &copy.path(), etag.as_ref()).await?;
+    }
+    outcome.record_base(vault.pool(), &peer_id).await?;
+
+    emit_local_changes(app, outcome.changes);
+    Ok(outcome.summary)
+}
+
+/// Runs a sync and records how it went.
+pub(crate) async fn run_sync(app: &AppHandle, state: &AppState) -> Result<SyncSummary> {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return Err(Error::Validation(
+            "A WebDAV sync is already running.".to_string(),
+        ));
+    }
+    let result = run_sync_inner(app, state).await;
+    RUNNING.store(false, Ordering::SeqCst);
+
+    let recorded = async {
+        let vault = UnlockedVault::acquire_writable(state).await?;
+        let Some(mut stored) = load_stored(vault.pool(), vault.key()).await? else {
+            return Ok(());
+        };
+        match &result {
+            Ok(summary) => {
+                stored.last_sync = Some(Utc::now().to_rfc3339());
+                stored.last_summary = Some(summary.clone());
+                stored.last_error = None;
+            }
+            Err(e) => stored.last_error = Some(e.to_string()),
+        }
+        save_stored(vault.pool(), vault.key(), &stored).await
+    }
+    .await;
+    if let Err(e) = recorded {
+        eprintln!("[WebDavSync] Failed to record sync status: {}", e);
+    }
+    result
+}
+
+/// Syncs if WebDAV sync is enabled and the interval has passed.
+pub(crate) async fn run_if_due(app: &AppHandle) -> Result<()> {
+    if crate::storage::is_read_only() {
+        return Ok(());
+    }
+    let state = app.state::<AppState>();
+    let stored = {
+        let Ok(vault) = UnlockedVault::acquire(&state).await else {
+            return Ok(());
+        };
+        load_stored(vault.pool(), vault.key()).await?
+    };
+    let Some(stored) = stored.filter(|s| s.config.enabled) else {
+        return Ok(());
+    };
+    let interval = chrono::Duration::minutes(i64::from(stored.config.interval_minutes));
+    let due = stored
+        .last_sync
+        .as_deref()
+        .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+        .is_none_or(|at| Utc::now() - at.with_timezone(&Utc) >= interval);
+    if due {
+        run_sync(app, &state).await?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebDavSettings {
+    pub config: WebDavConfig,
+    pub has_password: bool,
+}
+
+#[tauri::command]
+pub async fn get_webdav_sync_config(state: State<'_, AppState>) -> Result<WebDavSettings> {
+    let vault = UnlockedVault::acquire(&state).await?;
+    let stored = load_stored(vault.pool(), vault.key())
+        .await?
+        .unwrap_or_default();
+    Ok(WebDavSettings {
+        has_password: stored.password.is_some(),
+        config: stored.config,
+    })
+}
+
+/// Saves the WebDAV settings. `password` is write-only: `None` keeps the
+/// stored password, an empty string removes it.
+#[tauri::command]
+pub async fn set_webdav_sync_config(
+    state: State<'_, AppState>,
+    mut config: WebDavConfig,
+    password: Option<String>,
+) -> Result<()> {
+    limits::check_len("WebDAV URL", &config.url, limits::MAX_SHORT_FIELD_LEN)?;
+    limits::check_len("WebDAV username", &config.username, limits::MAX_TITLE_LEN)?;
+    limits::check_opt_len(
+        "WebDAV password",
+        password.as_deref(),
+        limits::MAX_PASSWORD_LEN,
+    )?;
+    config.url = config.url.trim().to_string();
+    let url = url::Url::parse(&config.url)
+        .map_err(|e| Error::Validation(format!("Invalid WebDAV URL: {}", e)))?;
+    if url.scheme() != "https" {
+        return Err(Error::Validation(
+            "The WebDAV server must be reached over HTTPS.".to_string(),
+        ));
+    }
+    if config.interval_minutes < MIN_INTERVAL_MINUTES {
+        return Err(Error::Validation(format!(
+            "WebDAV sync can run at most every {} minutes.",
+            MIN_INTERVAL_MINUTES
+        )));
+    }
+    config.pinned_certificate_sha256 = config
+        .pinned_certificate_sha256
+        .as_deref()
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(normalize_fingerprint)
+        .transpose()?;
+
+    let vault = UnlockedVault::acquire_writable(&state).await?;
+    let previous = load_stored(vault.pool(), vault.key())
+        .await?
+        .unwrap_or_default();
+    let password = match password {
+        Some(p) if p.is_empty() => None,
+        Some(p) => Some(p),
+        None => previous.password,
+    };
+    if config.enabled && password.is_none() {
+        return Err(Error::Validation(
+            "WebDAV sync needs a password.".to_string(),
+        ));
+    }
+
+    save_stored(
+        vault.pool(),
+        vault.key(),
+        &StoredWebDav {
+            config,
+            password,
+            last_sync: previous.last_sync,
+            last_summary: previous.last_summary,
+            last_error: previous.last_error,
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn get_webdav_sync_status(state: State<'_, AppState>) -> Result<WebDavSyncStatus> {
+    let vault = UnlockedVault::acquire(&state).await?;
+    let stored = load_stored(vault.pool(), vault.key()).await?;
+    let configured = stored.is_some();
+    let stored = stored.unwrap_or_default();
+    Ok(WebDavSyncStatus {
+        configured,
+        enabled: stored.config.enabled,
+        running: RUNNING.load(Ordering::SeqCst),
+        last_sync: stored.last_sync,
+        last_summary: stored.last_summary,
+        last_error: stored.last_error,
+    })
+}
+
+/// Syncs right away, whether or not scheduled sync is enabled.
+#[tauri::command]
+pub async fn run_webdav_sync_now(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<SyncSummary> {
+    run_sync(&app, &state).await
+}
@@ -6,7 +6,7 @@ use rand::Rng;
 use tauri::State;
 use totp_rs::{Algorithm, Secret, TOTP};
 
-fn build_totp(secret_b32: &str) -> Result<TOTP> {
+pub fn build_totp(secret_b32: &str) -> Result<TOTP> {
     let secret = Secret::Encoded(secret_b32.to_string());
     let secret_bytes = secret.to_bytes().map_err(|e| Error::Totp(e.to_string()))?;
 
@@ -0,0 +1,258 @@
+//! Bulk export of stored TOTP secrets into the backup formats of dedicated
+//! authenticator apps (Aegis encrypted vaults and andOTP encrypted backups).
+
+use crate::db::get_password_items_impl;
+use crate::db::utils::UnlockedVault;
+use crate::error::{Error, Result};
+use crate::state::AppState;
+use crate::utils::write_sensitive_bytes;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::{engine::general_purpose, Engine as _};
+use rand::rngs::OsRng;
+use rand::{Rng, RngCore};
+use serde_json::json;
+use std::path::PathBuf;
+use tauri::State;
+use zeroize::Zeroizing;
+
+const GCM_TAG_LEN: usize = 16;
+
+// Aegis uses scrypt with N = 2^15, r = 8, p = 1 for password slots.
+const AEGIS_SCRYPT_LOG_N: u8 = 15;
+const AEGIS_SCRYPT_R: u32 = 8;
+const AEGIS_SCRYPT_P: u32 = 1;
+
+// andOTP derives its key with PBKDF2-HMAC-SHA1 and a randomised iteration
+// count, stored in clear at the start of the backup.
+const ANDOTP_MIN_ITERATIONS: u32 = 140_000;
+const ANDOTP_MAX_ITERATIONS: u32 = 160_000;
+const ANDOTP_SALT_LEN: usize = 12;
+
+struct TotpEntry {
+    issuer: String,
+    label: String,
+    secret: Zeroizing<String>,
+}
+
+fn random_uuid() -> String {
+    let mut bytes = rand::random::<[u8; 16]>();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = hex::encode(bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+/// Encrypts with AES-256-GCM and returns `(ciphertext, tag)` separately, the
+/// way both formats store them.
+fn seal(key: &[u8], nonce: &[u8; 12], plaintext: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+    let cipher = Aes256Gcm::new_from_slice(key)
+        .map_err(|e| Error::Encryption(format!("Invalid key: {}", e)))?;
+    let mut sealed = cipher
+        .encrypt(Nonce::from_slice(nonce), plaintext)
+        .map_err(|e| Error::Encryption(format!("Encryption failed: {}", e)))?;
+    let tag = sealed.split_off(sealed.len() - GCM_TAG_LEN);
+    Ok((sealed, tag))
+}
+
+fn build_aegis(entries: &[TotpEntry], passphrase: &str) -> Result<Vec<u8>> {
+    let db_entries: Vec<_> = entries
+        .iter()
+        .map(|entry| {
+            json!({
+                "type": "totp",
+                "uuid": random_uuid(),
+                "name": entry.label,
+                "issuer": entry.issuer,
+                "group": null,
+                "note": "",
+                "favorite": false,
+                "icon": null,
+                "info": {
+                    "secret": entry.secret.as_str(),
+                    "algo": "SHA1",
+                    "digits": 6,
+                    "period": 30
+                }
+            })
+        })
+        .collect();
+    let db = Zeroizing::new(serde_json::to_vec(&json!({
+        "version": 2,
+        "entries": db_entries
+    }))?);
+
+    let master_key = Zeroizing::new(rand::random::<[u8; 32]>());
+    let mut salt = [0u8; 32];
+    OsRng.fill_bytes(&mut salt);
+
+    let params = scrypt::Params::new(AEGIS_SCRYPT_LOG_N, AEGIS_SCRYPT_R, AEGIS_SCRYPT_P, 32)
+        .map_err(|e| Error::Internal(e.to_string()))?;
+    let mut slot_key = Zeroizing::new([0u8; 32]);
+    scrypt::scrypt(passphrase.as_bytes(), &salt, &params, &mut slot_key[..])
+        .map_err(|e| Error::Internal(format!("KDF failed: {}", e)))?;
+
+    let slot_nonce = rand::random::<[u8; 12]>();
+    let (wrapped_key, slot_tag) = seal(&slot_key[..], &slot_nonce, &master_key[..])?;
+
+    let db_nonce = rand::random::<[u8; 12]>();
+    let (db_ciphertext, db_tag) = seal(&master_key[..], &db_nonce, &db)?;
+
+    let vault = json!({
+        "version": 1,
+        "header": {
+            "slots": [{
+                "type": 1,
+                "uuid": random_uuid(),
+                "key": hex::encode(wrapped_key),
+                "key_params": {
+                    "nonce": hex::encode(slot_nonce),
+                    "tag": hex::encode(slot_tag)
+                },
+                "n": 1u32 << AEGIS_SCRYPT_LOG_N,
+                "r": AEGIS_SCRYPT_R,
+                "p": AEGIS_SCRYPT_P,
+                "salt": hex::encode(salt),
+                "repaired": true,
+                "is_backup": false
+            }],
+            "params": {
+                "nonce": hex::encode(db_nonce),
+                "tag": hex::encode(db_tag)
+            }
+        },
+        "db": general_purpose::STANDARD.encode(db_ciphertext)
+    });
+    Ok(serde_json::to_vec_pretty(&vault)?)
+}
+
+fn build_andotp(entries: &[TotpEntry], passphrase: &str) -> Result<Vec<u8>> {
+    let accounts: Vec<_> = entries
+        .iter()
+        .map(|entry| {
+            json!({
+                "secret": entry.secret.as_str(),
+                "issuer": entry.issuer,
+                "label": entry.label,
+                "digits": 6,
+                "type": "TOTP",
+                "algorithm": "SHA1",
+                "thumbnail": "Default",
+                "last_used": 0,
+                "used_frequency": 0,
+                "period": 30,
+                "tags": []
+            })
+        })
+        .collect();
+    let plaintext = Zeroizing::new(serde_json::to_vec(&accounts)?);
+
+    let iterations = OsRng.gen_range(ANDOTP_MIN_ITERATIONS..=ANDOTP_MAX_ITERATIONS);
+    let mut salt = [0u8; ANDOTP_SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let mut key = Zeroizing::new([0u8; 32]);
+    pbkdf2::pbkdf2_hmac::<sha1::Sha1>(passphrase.as_bytes(), &salt, iterations, &mut key[..]);
+
+    let nonce = rand::random::<[u8; 12]>();
+    let (ciphertext, tag) = seal(&key[..], &nonce, &plaintext)?;
+
+    // iterations (big endian) || salt || nonce || ciphertext || tag
+    let mut out = Vec::with_capacity(4 + salt.len() + nonce.len() + ciphertext.len() + tag.len());
+    out.extend_from_slice(&iterations.to_be_bytes());
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    out.extend_from_slice(&tag);
+    Ok(out)
+}
+
+/// Writes every stored TOTP secret to `path` as an encrypted `aegis` vault or
+/// `andotp` backup, protected by `passphrase`. Returns the number of exported
+/// secrets.
+#[tauri::command]
+pub async fn export_totp_secrets(
+    state: State<'_, AppState>,
+    format: String,
+    path: String,
+    passphrase: String,
+) -> Result<usize> {
+    crate::auth::reverify::require_fresh(
+        &state,
+        crate::auth::reverify::SensitiveOperation::ViewLoginTotpSecret,
+    )
+    .await?;
+    let passphrase = Zeroizing::new(passphrase);
+    if passphrase.is_empty() {
+        return Err(Error::Validation(
+            "A passphrase is required to export TOTP secrets.".to_string(),
+        ));
+    }
+    let format = format.to_lowercase();
+    if !matches!(format.as_str(), "aegis" | "andotp") {
+        return Err(Error::Validation(format!(
+            "Unsupported TOTP export format '{}'",
+            format
+        )));
+    }
+
+    let vault = UnlockedVault::acquire(&state).await?;
+
+    let items = get_password_items_impl(vault.pool(), vault.key()).await?;
+    let entries: Vec<TotpEntry> = items
+        .into_iter()
+        .filter_map(|item| {
+            let secret = item.totp_secret.as_ref().map(|s| s.as_str().trim())?;
+            let secret = crate::importers::normalize_totp(secret)?;
+            Some(TotpEntry {
+                label: item
+                    .username
+                    .filter(|u| !u.trim().is_empty())
+                    .unwrap_or_else(|| item.title.clone()),
+                issuer: item.title,
+                secret: Zeroizing::new(secret),
+            })
+        })
+        .collect();
+
+    if entries.is_empty() {
+        return Err(Error::Validation(
+            "No items with a TOTP secret to export.".to_string(),
+        ));
+    }
+
+    let count = entries.len();
+    let format_clone = format.clone();
+    let bytes = tauri::async_runtime::spawn_blocking(move || match format_clone.as_str() {
+        "aegis" => build_aegis(&entries, &passphrase),
+        _ => build_andotp(&entries, &passphrase),
+    })
+    .await
+    .map_err(|e| Error::Internal(format!("Runtime error: {}", e)))??;
+
+    let path = PathBuf::from(path);
+    write_sensitive_bytes(&path, &bytes).await?;
+
+    let _ = crate::db::activity::log_activity_impl(
+        vault.pool(),
+        vault.key(),
+        "totp_secrets_exported",
+        None,
+        None,
+        Some(&format!(
+            "Exported {} TOTP secrets ({}) to {}",
+            count,
+            format,
+            path.display()
+        )),
+    )
+    .await;
+
+    Ok(count)
+}
@@ -1,4 +1,4 @@
-use crate::types::vault::{Button, PasswordItem, RecipientKey};
+use crate::types::vault::{Button, ItemTemplate, Palette, PasswordItem, RecipientKey};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -23,4 +23,8 @@ pub struct VaultBackupSnapshot {
     pub recipient_keys: Vec<RecipientKey>,
     #[serde(default)]
     pub attachments: Vec<VaultBackupAttachment>,
+    #[serde(default)]
+    pub palettes: Vec<Palette>,
+    #[serde(default)]
+    pub templates: Vec<ItemTemplate>,
 }
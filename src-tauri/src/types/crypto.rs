@@ -6,6 +6,10 @@ pub struct ExportPayload {
     pub salt_b64: String,
     pub nonce_b64: String,
     pub ciphertext_b64: String,
+    /// RFC 3339 time after which imports refuse the payload. Bound into the
+    /// AAD when set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -19,4 +23,6 @@ pub struct PubKeyExportPayload {
     pub salt_b64: String,
     pub nonce_b64: String,
     pub ciphertext_b64: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
 }
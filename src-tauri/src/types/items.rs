@@ -0,0 +1,100 @@
+use crate::types::secret::SecretString;
+use serde::{Deserialize, Serialize};
+
+/// Typed payload for categories that have a fixed schema. Stored encrypted
+/// alongside the generic item columns; `category` stays the source of truth
+/// for which variant an item carries.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ItemDetails {
+    Card(CardDetails),
+    Identity(IdentityDetails),
+    Wifi(WifiDetails),
+    BankAccount(BankAccountDetails),
+}
+
+impl ItemDetails {
+    pub fn category(&self) -> &'static str {
+        match self {
+            ItemDetails::Card(_) => "card",
+            ItemDetails::Identity(_) => "identity",
+            ItemDetails::Wifi(_) => "wifi",
+            ItemDetails::BankAccount(_) => "bank_account",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct CardDetails {
+    pub cardholder_name: Option<String>,
+    pub number: Option<SecretString>,
+    pub brand: Option<String>,
+    pub expiry_month: Option<u8>,
+    pub expiry_year: Option<u16>,
+    pub security_code: Option<SecretString>,
+    pub pin: Option<SecretString>,
+    pub billing_address: Option<PostalAddress>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct IdentityDetails {
+    pub honorific: Option<String>,
+    pub first_name: Option<String>,
+    pub middle_name: Option<String>,
+    pub last_name: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    /// ISO 8601 date (`YYYY-MM-DD`).
+    pub birth_date: Option<String>,
+    pub company: Option<String>,
+    pub job_title: Option<String>,
+    pub address: Option<PostalAddress>,
+    pub national_id: Option<SecretString>,
+    pub passport_number: Option<SecretString>,
+    pub license_number: Option<SecretString>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct PostalAddress {
+    pub street: Option<String>,
+    pub city: Option<String>,
+    pub region: Option<String>,
+    pub postal_code: Option<String>,
+    pub country: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WifiSecurity {
+    #[default]
+    Wpa,
+    Wep,
+    None,
+}
+
+/// The network password is the item's own `password` so it shares the reveal,
+/// copy and history handling of every other secret.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct WifiDetails {
+    pub ssid: String,
+    pub security: WifiSecurity,
+    pub hidden: bool,
+}
+
+/// Account identifiers are checked on save: the IBAN's mod-97 checksum, the
+/// BIC's layout and the ABA routing number's checksum.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct BankAccountDetails {
+    pub bank_name: Option<String>,
+    pub account_holder: Option<String>,
+    pub account_number: Option<SecretString>,
+    pub iban: Option<SecretString>,
+    pub bic: Option<String>,
+    /// US ABA routing transit number.
+    pub routing_number: Option<String>,
+}
@@ -1,9 +1,11 @@
 pub mod backup;
 pub mod crypto;
+pub mod items;
 pub mod secret;
 pub mod vault;
 
 pub use backup::*;
 pub use crypto::*;
+pub use items::*;
 pub use secret::*;
 pub use vault::*;
@@ -1,5 +1,7 @@
+use crate::types::items::ItemDetails;
 use crate::types::secret::SecretString;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use validator::Validate;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -8,6 +10,67 @@ pub struct Button {
     pub text: String,
     pub icon: String,
     pub color: String,
+    #[serde(default)]
+    pub parent_id: Option<i64>,
+}
+
+/// A tag with its nested tags. `item_count` counts items tagged with this tag
+/// directly, `subtree_item_count` the distinct items anywhere below it.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TagTreeNode {
+    #[serde(flatten)]
+    pub tag: Button,
+    pub item_count: i64,
+    pub subtree_item_count: i64,
+    pub children: Vec<TagTreeNode>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Palette {
+    pub id: i64,
+    pub name: String,
+    pub colors: Vec<String>,
+}
+
+/// Reusable field layout for new items. Field values act as defaults.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ItemTemplate {
+    pub id: i64,
+    pub name: String,
+    pub category: String,
+    pub custom_fields: Vec<CustomField>,
+    pub field_order: Option<Vec<String>>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// An in-progress item edit. `data` is the editor state as JSON; `item_id` is
+/// the item being edited, or `None` for an item that has not been saved yet.
+/// A draft with `id` 0 is new.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ItemDraft {
+    #[serde(default)]
+    pub id: i64,
+    pub item_id: Option<i64>,
+    pub data: String,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub updated_at: String,
+}
+
+/// Two diverging versions of an item awaiting a decision. `source` names what
+/// brought in the incoming version, e.g. `backup_merge`.
+#[derive(Debug, Serialize, Clone)]
+pub struct ItemConflict {
+    pub id: i64,
+    pub item_id: i64,
+    pub source: String,
+    pub local: PasswordItem,
+    pub incoming: PasswordItem,
+    pub detected_at: String,
+    pub expires_at: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -40,10 +103,19 @@ pub struct PasswordItemOverview {
     pub created_at: String,
     pub updated_at: String,
     pub color: Option<String>,
+    #[serde(default)]
+    pub tag_colors: HashMap<String, String>,
+    /// Search relevance, set only on results of a text search.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Validate)]
-#[validate(schema(function = "crate::db::validation::validate_password_item_fields"))]
+#[validate(context = crate::db::vault_settings::PasswordPolicy)]
+#[validate(schema(
+    function = "crate::db::validation::validate_password_item_fields",
+    use_context
+))]
 pub struct PasswordItem {
     pub id: i64,
     pub category: String,
@@ -62,6 +134,96 @@ pub struct PasswordItem {
     pub custom_fields: Vec<CustomField>,
     pub field_order: Option<Vec<String>>,
     pub attachments: Option<Vec<Attachment>>,
+    #[serde(default)]
+    pub autotype_patterns: Option<Vec<String>>,
+    #[serde(default)]
+    pub details: Option<ItemDetails>,
+    /// Removes the stored details on update, where `details: None` keeps them.
+    #[serde(default, skip_serializing)]
+    pub clear_details: bool,
+    /// Set on items that must not be edited or deleted until the flag is
+    /// cleared with `clear_password_item_read_only`. Ignored on save.
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+/// Position in the `(updated_at DESC, id DESC)` listing order. Pass the
+/// `next_cursor` of one page to get the next.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OverviewCursor {
+    pub updated_at: String,
+    pub id: i64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct OverviewPage {
+    pub items: Vec<PasswordItemOverview>,
+    /// `None` on the last page.
+    pub next_cursor: Option<OverviewCursor>,
+}
+
+/// Structured filters for `search_password_items`. Every set filter must
+/// match. Dates are RFC 3339 timestamps or `YYYY-MM-DD`; `*_after` bounds are
+/// inclusive and `*_before` bounds exclusive.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchFilters {
+    /// Item categories (`login`, `card`, ...). Categories are encrypted, so
+    /// this is applied after decryption.
+    pub categories: Option<Vec<String>>,
+    pub created_after: Option<String>,
+    pub created_before: Option<String>,
+    pub updated_after: Option<String>,
+    pub updated_before: Option<String>,
+    pub has_attachment: Option<bool>,
+    pub has_totp: Option<bool>,
+    /// Items not modified for at least this many days. The item's last update
+    /// stands in for the password change date.
+    pub password_older_than_days: Option<u32>,
+    pub color: Option<String>,
+    /// A tag acting as a folder; items tagged with it or any nested tag match.
+    pub folder_id: Option<i64>,
+}
+
+/// Subset of a `PasswordItem` returned by `get_item_fields`. Only the
+/// requested fields are decrypted and serialized.
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct PasswordItemFields {
+    pub id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub img: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes: Option<SecretString>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<SecretString>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub totp_secret: Option<SecretString>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_fields: Option<Vec<CustomField>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field_order: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub autotype_patterns: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<ItemDetails>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -0,0 +1,151 @@
+//! Username generation, so a new account can get a unique username along
+//! with its password. Names are drawn with the OS random source; none of
+//! the styles try to look like a real person.
+
+use crate::error::{Error, Result};
+use crate::limits;
+use rand::rngs::OsRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::Deserialize;
+
+const ADJECTIVES: &[&str] = &[
+    "amber", "ancient", "brave", "bright", "calm", "clever", "cosmic", "crimson", "curious",
+    "daring", "eager", "electric", "fancy", "fierce", "frosty", "gentle", "golden", "happy",
+    "hidden", "humble", "icy", "jolly", "keen", "lively", "lucky", "lunar", "mellow", "mighty",
+    "misty", "noble", "odd", "polar", "proud", "quick", "quiet", "rapid", "rustic", "silent",
+    "silver", "sleepy", "solar", "spicy", "steady", "stormy", "swift", "tidy", "vivid", "wild",
+    "windy", "witty",
+];
+
+const NOUNS: &[&str] = &[
+    "anchor", "badger", "beacon", "bison", "canyon", "cedar", "comet", "coral", "falcon", "fern",
+    "fjord", "fox", "galaxy", "glacier", "harbor", "hawk", "heron", "island", "jaguar", "lantern",
+    "lynx", "maple", "meadow", "meteor", "moose", "nebula", "oak", "orbit", "otter", "owl",
+    "panda", "pebble", "pine", "planet", "prairie", "quartz", "raven", "reef", "river", "rocket",
+    "sparrow", "spruce", "summit", "thunder", "tiger", "tundra", "valley", "walrus", "willow",
+    "wolf",
+];
+
+const DEFAULT_WORDS: u8 = 2;
+const MAX_WORDS: u8 = 6;
+const DEFAULT_DIGITS: u8 = 4;
+const MAX_DIGITS: u8 = 9;
+const DEFAULT_TAG_LEN: u8 = 8;
+const MAX_TAG_LEN: u8 = 32;
+const TAG_ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum UsernameStyle {
+    /// Random words joined by `separator`, e.g. `misty-otter`.
+    #[serde(rename_all = "camelCase")]
+    RandomWords {
+        words: Option<u8>,
+        separator: Option<String>,
+    },
+    /// A name followed by random digits, e.g. `alex4821`. Without a name, a
+    /// random word is used.
+    #[serde(rename_all = "camelCase")]
+    NameNumber {
+        name: Option<String>,
+        digits: Option<u8>,
+    },
+    /// A plus-addressed variant of `email` for catch-all inboxes, e.g.
+    /// `alex+k3x9q2ab@example.com`.
+    #[serde(rename_all = "camelCase")]
+    CatchAllEmail { email: String, length: Option<u8> },
+}
+
+fn pick(words: &[&'static str]) -> &'static str {
+    words.choose(&mut OsRng).copied().unwrap_or_default()
+}
+
+fn random_words(words: u8, separator: &str) -> String {
+    (0..words)
+        .map(|i| {
+            if i + 1 == words {
+                pick(NOUNS)
+            } else {
+                pick(ADJECTIVES)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+fn random_digits(digits: u8) -> String {
+    (0..digits)
+        .map(|_| char::from(b'0' + OsRng.gen_range(0..10)))
+        .collect()
+}
+
+fn random_tag(length: u8) -> String {
+    (0..length)
+        .map(|_| char::from(TAG_ALPHABET[OsRng.gen_range(0..TAG_ALPHABET.len())]))
+        .collect()
+}
+
+fn check_range(what: &str, value: u8, max: u8) -> Result<u8> {
+    if value == 0 || value > max {
+        return Err(Error::Validation(format!(
+            "{} must be between 1 and {}.",
+            what, max
+        )));
+    }
+    Ok(value)
+}
+
+fn name_number(name: Option<&str>, digits: u8) -> Result<String> {
+    let name: String = match name.map(str::trim).filter(|n| !n.is_empty()) {
+        Some(name) => name
+            .chars()
+            .filter(|c| c.is_alphanumeric() || matches!(c, '.' | '_' | '-'))
+            .flat_map(char::to_lowercase)
+            .collect(),
+        None => pick(NOUNS).to_string(),
+    };
+    if name.is_empty() {
+        return Err(Error::Validation(
+            "The name has no characters usable in a username.".to_string(),
+        ));
+    }
+    Ok(format!("{}{}", name, random_digits(digits)))
+}
+
+fn catch_all_email(email: &str, length: u8) -> Result<String> {
+    let (local, domain) = email
+        .trim()
+        .rsplit_once('@')
+        .filter(|(local, domain)| !local.is_empty() && domain.contains('.'))
+        .ok_or_else(|| Error::Validation("Enter an email address like name@example.com.".into()))?;
+    // An address that is already plus-addressed keeps its base.
+    let base = local.split_once('+').map_or(local, |(base, _)| base);
+    Ok(format!("{}+{}@{}", base, random_tag(length), domain))
+}
+
+#[tauri::command]
+pub fn generate_username(style: UsernameStyle) -> Result<String> {
+    match style {
+        UsernameStyle::RandomWords { words, separator } => {
+            let words = check_range("Word count", words.unwrap_or(DEFAULT_WORDS), MAX_WORDS)?;
+            let separator = separator.unwrap_or_else(|| "-".to_string());
+            if separator.chars().count() > 3 {
+                return Err(Error::Validation(
+                    "The separator can be at most 3 characters.".to_string(),
+                ));
+            }
+            Ok(random_words(words, &separator))
+        }
+        UsernameStyle::NameNumber { name, digits } => {
+            limits::check_opt_len("Name", name.as_deref(), limits::MAX_TITLE_LEN)?;
+            let digits = check_range("Digit count", digits.unwrap_or(DEFAULT_DIGITS), MAX_DIGITS)?;
+            name_number(name.as_deref(), digits)
+        }
+        UsernameStyle::CatchAllEmail { email, length } => {
+            limits::check_len("Email", &email, limits::MAX_TITLE_LEN)?;
+            let length = check_range("Tag length", length.unwrap_or(DEFAULT_TAG_LEN), MAX_TAG_LEN)?;
+            catch_all_email(&email, length)
+        }
+    }
+}
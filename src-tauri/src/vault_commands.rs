@@ -2,7 +2,9 @@ use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::time::UNIX_EPOCH;
 
+use crate::auth::types::VaultDisplay;
 use crate::db::init_db_lazy;
+use crate::db::utils::UnlockedVault;
 use crate::error::{Error, Result};
 use crate::state::AppState;
 use serde::{Deserialize, Serialize};
@@ -22,9 +24,34 @@ pub async fn get_active_db_path(state: State<'_, AppState>) -> Result<Option<Str
     Ok(guard.as_ref().map(|p| p.to_string_lossy().to_string()))
 }
 
+/// Closes the active vault and opens `db_path`. Waits for a rekey or
+/// in-flight writes to finish, then locks the old vault the same way `lock`
+/// does, unless its "relock on switch" setting is off, in which case its key
+/// is parked. A parked key for `db_path` unlocks it right away.
 #[tauri::command]
-pub async fn switch_database(db_path: PathBuf, app_state: State<'_, AppState>) -> Result<()> {
-    let _rekey_lock = app_state.rekey.lock().await;
+pub async fn switch_database(
+    app: tauri::AppHandle,
+    db_path: PathBuf,
+    app_state: State<'_, AppState>,
+) -> Result<()> {
+    let _rekey_lock =
+        tokio::time::timeout(std::time::Duration::from_secs(15), app_state.rekey.lock())
+            .await
+            .map_err(|_| Error::Internal("Vault is busy. Please try again.".to_string()))?;
+
+    let buffered_elsewhere = matches!(
+        app_state.write_buffer.lock().await.as_ref(),
+        Some(buffer) if buffer.remote_path != db_path
+    );
+    if buffered_elsewhere {
+        crate::write_buffer::ensure_flushed(&app_state).await?;
+        if let Some(mut buffer) = app_state.write_buffer.lock().await.take() {
+            if let Some(task) = buffer.flush_task.take() {
+                task.abort();
+            }
+            let _ = tokio::fs::remove_file(&buffer.local_path).await;
+        }
+    }
 
     {
         let path_guard = app_state.db_path.lock().await;
@@ -38,12 +65,37 @@ pub async fn switch_database(db_path: PathBuf, app_state: State<'_, AppState>) -
         }
     }
 
-    {
-        let mut guard = app_state.db.lock().await;
-        if let Some(old_pool) = guard.take() {
-            old_pool.close().await;
+    let old_path = app_state.db_path.lock().await.clone();
+    let old_key = app_state.key.lock().await.clone();
+    let had_pending = app_state.pending_key.lock().await.is_some();
+    if let (Some(old_path), Some(old_key)) = (old_path, old_key.as_ref()) {
+        let relock = load_stored_settings(&app)
+            .await
+            .ok()
+            .and_then(|s| {
+                s.vault_settings_by_id
+                    .get(old_path.to_string_lossy().as_ref())
+                    .map(|v| v.relock_on_switch)
+            })
+            .unwrap_or(true);
+        if !relock {
+            app_state
+                .parked_keys
+                .lock()
+                .await
+                .insert(old_path, old_key.clone());
         }
     }
+    let was_unlocked = old_key.is_some();
+    drop(old_key);
+
+    crate::write_buffer::flush_before_close(&app_state).await;
+    crate::auth::commands::store_item_count_hint(&app_state).await;
+    crate::vault_watch::unwatch();
+    app_state.clear_overview_cache();
+    crate::auth::reverify::clear(&app_state).await;
+    crate::sessions::end(&app_state).await;
+    crate::secure_temp::wipe_all().await;
 
     {
         let mut kg = app_state.key.lock().await;
@@ -57,6 +109,18 @@ pub async fn switch_database(db_path: PathBuf, app_state: State<'_, AppState>) -
         }
     }
 
+    // Closing waits for connections still in use, so a running transaction
+    // finishes before the vault changes.
+    {
+        let mut guard = app_state.db.lock().await;
+        if let Some(old_pool) = guard.take() {
+            old_pool.close().await;
+        }
+    }
+
+    if was_unlocked || had_pending {
+        crate::events::vault_locked(&app);
+    }
     tokio::time::sleep(std::time::Duration::from_millis(100)).await;
 
     match init_db_lazy(&db_path, None, true).await {
@@ -77,6 +141,15 @@ pub async fn switch_database(db_path: PathBuf, app_state: State<'_, AppState>) -
         }
     };
 
+    crate::storage::refresh(&db_path).await;
+
+    let parked = app_state.parked_keys.lock().await.remove(&db_path);
+    if let Some(key) = parked {
+        if let Err(e) = crate::auth::commands::finalize_unlock(&app_state, key.as_slice()).await {
+            eprintln!("Failed to reopen {}: {}", db_path.display(), e);
+        }
+    }
+
     Ok(())
 }
 
@@ -87,6 +160,15 @@ pub struct StoredVaultSettings {
     pub totp: bool,
     pub backups: bool,
     pub compression: bool,
+    /// Lock the vault when switching to another one. When off, its key is
+    /// kept in locked memory until the app locks, so switching back does not
+    /// ask for the password again.
+    #[serde(rename = "relockOnSwitch")]
+    pub relock_on_switch: bool,
+    /// Shown in the vault switcher, including while the vault is locked.
+    pub icon: Option<String>,
+    pub color: Option<String>,
+    pub description: Option<String>,
 }
 
 impl Default for StoredVaultSettings {
@@ -96,6 +178,10 @@ impl Default for StoredVaultSettings {
             totp: true,
             backups: false,
             compression: false,
+            relock_on_switch: true,
+            icon: None,
+            color: None,
+            description: None,
         }
     }
 }
@@ -103,23 +189,43 @@ impl Default for StoredVaultSettings {
 #[derive(Debug, Deserialize, Default)]
 #[serde(default)]
 struct StoredAppSettings {
-    #[serde(default)]
+    #[serde(default, rename = "recentDatabases")]
     recent_databases: Vec<String>,
     #[serde(default, rename = "vaultSettingsById")]
     vault_settings_by_id: HashMap<String, StoredVaultSettings>,
 }
 
+/// Unpinned vaults kept in the recent list, most recent first.
+const MAX_RECENT_VAULTS: usize = 10;
+
+/// Serializes read-modify-write updates of the stored app settings.
+static SETTINGS_UPDATE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentVaults {
+    /// Pinned vaults first, then the others by when they were last opened.
+    pub recent: Vec<String>,
+    pub pinned: Vec<String>,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct VaultInfo {
     pub id: String,
     pub path: String,
     pub name: String,
+    pub icon: Option<String>,
+    pub color: Option<String>,
+    pub description: Option<String>,
     pub status: String,
     pub encrypted: bool,
     pub size_bytes: Option<u64>,
     pub modified_at: Option<i64>,
     pub item_count: Option<u64>,
+    /// Set when `item_count` comes from the count recorded at the last lock
+    /// rather than from the open vault.
+    pub item_count_is_hint: bool,
     pub settings: StoredVaultSettings,
 }
 
@@ -236,7 +342,25 @@ pub async fn list_vaults(
             .cloned()
             .unwrap_or_default();
 
-        let display_name = if settings.name.trim().is_empty() {
+        // Identity stored with the vault wins over device-local settings so a
+        // synced vault looks the same on every machine.
+        let vault_meta = crate::auth::metadata::read_password_metadata(&path)
+            .await
+            .ok()
+            .flatten();
+        let count_hint = vault_meta
+            .as_ref()
+            .and_then(|meta| meta.item_count_hint.as_ref())
+            .map(|hint| hint.count);
+        let vault_display = vault_meta.and_then(|meta| meta.display).unwrap_or_default();
+
+        let display_name = if let Some(name) = vault_display
+            .name
+            .as_ref()
+            .filter(|name| !name.trim().is_empty())
+        {
+            name.clone()
+        } else if settings.name.trim().is_empty() {
             path.file_stem()
                 .and_then(|stem| stem.to_str())
                 .unwrap_or("Vault")
@@ -264,20 +388,514 @@ pub async fn list_vaults(
             .await
             .unwrap_or(false);
 
-        let item_count = resolve_item_count(active_pool.clone(), is_active && is_unlocked).await;
+        let live_count = resolve_item_count(active_pool.clone(), is_active && is_unlocked).await;
+        let item_count_is_hint = live_count.is_none() && count_hint.is_some();
+        let item_count = live_count.or(count_hint);
 
         results.push(VaultInfo {
             id: path_str.clone(),
             path: path_str,
             name: display_name,
+            icon: vault_display.icon.or_else(|| settings.icon.clone()),
+            color: vault_display.color.or_else(|| settings.color.clone()),
+            description: vault_display
+                .description
+                .or_else(|| settings.description.clone()),
             status: status.to_string(),
             encrypted,
             size_bytes,
             modified_at,
             item_count,
+            item_count_is_hint,
             settings,
         });
     }
 
     Ok(results)
 }
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultSearchResult {
+    pub vault_path: String,
+    pub vault_name: String,
+    pub item: crate::types::PasswordItemOverview,
+}
+
+/// Searches every unlocked vault and tags each match with its vault. Only
+/// one vault can be unlocked at a time, so this currently covers the active
+/// vault; locked and inactive vaults are skipped rather than failing.
+#[tauri::command]
+pub async fn search_all_vaults(
+    state: State<'_, AppState>,
+    query: String,
+    limit: Option<u32>,
+) -> Result<Vec<VaultSearchResult>> {
+    let Some(path) = state.db_path.lock().await.clone() else {
+        return Ok(Vec::new());
+    };
+    if state.key.lock().await.is_none() {
+        return Ok(Vec::new());
+    }
+
+    let items =
+        crate::db::search_password_items(state, query, None, None, limit, None, None, None, None)
+            .await?;
+
+    let vault_name = crate::auth::metadata::read_password_metadata(&path)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|meta| meta.display)
+        .and_then(|display| display.name)
+        .filter(|name| !name.trim().is_empty())
+        .unwrap_or_else(|| {
+            path.file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or("Vault")
+                .to_string()
+        });
+    let vault_path = path.to_string_lossy().to_string();
+
+    Ok(items
+        .into_iter()
+        .map(|item| VaultSearchResult {
+            vault_path: vault_path.clone(),
+            vault_name: vault_name.clone(),
+            item,
+        })
+        .collect())
+}
+
+async fn ensure_vault_exists(path: &str) -> Result<()> {
+    if tokio::fs::try_exists(path).await.unwrap_or(false) {
+        Ok(())
+    } else {
+        Err(Error::Validation(format!("Vault file not found: {}", path)))
+    }
+}
+
+async fn load_settings_value(app_handle: &tauri::AppHandle) -> Result<serde_json::Value> {
+    let raw = crate::settings::get_all_settings_internal(app_handle).await?;
+    Ok(match raw {
+        Some(raw) => match serde_json::from_str::<serde_json::Value>(&raw)? {
+            // Older frontends stored the settings JSON as a JSON string.
+            serde_json::Value::String(inner) => serde_json::from_str(&inner)?,
+            value => value,
+        },
+        None => serde_json::json!({}),
+    })
+}
+
+/// The stored recent and pinned lists.
+fn recent_lists(settings: &serde_json::Value) -> (Vec<String>, Vec<String>) {
+    let read_list = |key: &str| -> Vec<String> {
+        settings
+            .get(key)
+            .cloned()
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default()
+    };
+    (read_list("recentDatabases"), read_list("pinnedVaults"))
+}
+
+/// Applies `update` to the stored recent and pinned vault lists and saves
+/// them back into the app settings, keeping pinned vaults at the front.
+async fn update_recent_vaults(
+    app_handle: &tauri::AppHandle,
+    update: impl FnOnce(&mut Vec<String>, &mut Vec<String>),
+) -> Result<RecentVaults> {
+    let _guard = SETTINGS_UPDATE_LOCK.lock().await;
+    let mut settings = load_settings_value(app_handle).await?;
+    let (mut recent, mut pinned) = recent_lists(&settings);
+
+    update(&mut recent, &mut pinned);
+
+    let mut seen = HashSet::new();
+    pinned.retain(|path| seen.insert(path.clone()));
+    recent.retain(|path| seen.insert(path.clone()));
+    recent.truncate(MAX_RECENT_VAULTS);
+    let ordered: Vec<String> = pinned.iter().cloned().chain(recent).collect();
+
+    let object = settings
+        .as_object_mut()
+        .ok_or_else(|| Error::Internal("Stored settings are not an object".to_string()))?;
+    object.insert("recentDatabases".to_string(), serde_json::json!(ordered));
+    object.insert("pinnedVaults".to_string(), serde_json::json!(pinned));
+    crate::settings::set_all_settings(app_handle.clone(), serde_json::to_string(&settings)?)
+        .await?;
+
+    Ok(RecentVaults {
+        recent: ordered,
+        pinned,
+    })
+}
+
+#[tauri::command]
+pub async fn get_recent_vaults(app_handle: tauri::AppHandle) -> Result<RecentVaults> {
+    let (recent, pinned) = recent_lists(&load_settings_value(&app_handle).await?);
+    Ok(RecentVaults { recent, pinned })
+}
+
+/// Moves `path` to the front of the recent list, dropping entries whose
+/// files no longer exist.
+#[tauri::command]
+pub async fn add_recent_vault(app_handle: tauri::AppHandle, path: String) -> Result<RecentVaults> {
+    ensure_vault_exists(&path).await?;
+    let (stored, _) = recent_lists(&load_settings_value(&app_handle).await?);
+    let mut missing = HashSet::new();
+    for existing in &stored {
+        if !tokio::fs::try_exists(existing).await.unwrap_or(true) {
+            missing.insert(existing.clone());
+        }
+    }
+    update_recent_vaults(&app_handle, |recent, pinned| {
+        recent.retain(|p| p != &path && !missing.contains(p));
+        pinned.retain(|p| !missing.contains(p));
+        recent.insert(0, path);
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn remove_recent_vault(
+    app_handle: tauri::AppHandle,
+    path: String,
+) -> Result<RecentVaults> {
+    update_recent_vaults(&app_handle, |recent, pinned| {
+        recent.retain(|p| p != &path);
+        pinned.retain(|p| p != &path);
+    })
+    .await
+}
+
+/// Pinned vaults stay at the top of the list and are never trimmed or
+/// cleared.
+#[tauri::command]
+pub async fn pin_vault(
+    app_handle: tauri::AppHandle,
+    path: String,
+    pinned: bool,
+) -> Result<RecentVaults> {
+    if pinned {
+        ensure_vault_exists(&path).await?;
+    }
+    update_recent_vaults(&app_handle, |recent, pinned_paths| {
+        if pinned {
+            if !pinned_paths.contains(&path) {
+                pinned_paths.push(path);
+            }
+        } else if let Some(index) = pinned_paths.iter().position(|p| p == &path) {
+            pinned_paths.remove(index);
+            recent.insert(0, path);
+        }
+    })
+    .await
+}
+
+/// Forgets every vault that is not pinned.
+#[tauri::command]
+pub async fn clear_recent_vaults(app_handle: tauri::AppHandle) -> Result<RecentVaults> {
+    update_recent_vaults(&app_handle, |recent, _| recent.clear()).await
+}
+
+fn normalize_display_text(
+    field: &str,
+    value: Option<String>,
+    max: usize,
+) -> Result<Option<String>> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    crate::limits::check_len(field, &value, max)?;
+    let trimmed = value.trim();
+    Ok((!trimmed.is_empty()).then(|| trimmed.to_string()))
+}
+
+fn normalize_display_color(color: Option<String>) -> Result<Option<String>> {
+    match color.as_deref().map(str::trim) {
+        Some(c) if !c.is_empty() => Ok(Some(crate::db::palettes::normalize_color(c)?)),
+        _ => Ok(None),
+    }
+}
+
+/// Applies `update` to the display stored in the open vault and writes it
+/// back to its configuration table and `.meta.json` sidecar.
+async fn update_stored_display(
+    state: &State<'_, AppState>,
+    update: impl FnOnce(&mut VaultDisplay),
+) -> Result<VaultDisplay> {
+    let vault = UnlockedVault::acquire_writable(state).await?;
+    let db_path = state
+        .db_path
+        .lock()
+        .await
+        .clone()
+        .ok_or(Error::VaultNotLoaded)?;
+
+    let mut display = crate::auth::load_vault_display_from_db(vault.pool())
+        .await?
+        .unwrap_or_default();
+    update(&mut display);
+
+    sqlx::query("INSERT OR REPLACE INTO configuration (key, value) VALUES (?, ?)")
+        .bind("vault_display")
+        .bind(serde_json::to_string(&display)?)
+        .execute(vault.pool())
+        .await?;
+
+    if let Some(mut meta) = crate::auth::metadata::read_password_metadata(&db_path).await? {
+        meta.display = Some(display.clone());
+        crate::auth::metadata::write_password_metadata(&db_path, &meta, Some(vault.key())).await?;
+    }
+
+    Ok(display)
+}
+
+/// Stores the vault's display name, icon and color with the vault itself, in
+/// its `.meta.json` sidecar and its configuration table. Passing `None` (or an
+/// empty string) clears a value.
+#[tauri::command]
+pub async fn update_vault_display(
+    state: State<'_, AppState>,
+    name: Option<String>,
+    icon: Option<String>,
+    color: Option<String>,
+) -> Result<VaultDisplay> {
+    let name = normalize_display_text("Vault name", name, crate::limits::MAX_TITLE_LEN)?;
+    let icon = normalize_display_text("Vault icon", icon, crate::limits::MAX_SHORT_FIELD_LEN)?;
+    let color = normalize_display_color(color)?;
+    update_stored_display(&state, |display| {
+        display.name = name;
+        display.icon = icon;
+        display.color = color;
+    })
+    .await
+}
+
+/// Sets the icon, accent color and description the vault switcher shows for
+/// the vault at `path`. They are kept in the app settings so they show while
+/// the vault is locked, and copied into the vault itself when it is the open,
+/// unlocked one. Passing `None` (or an empty string) clears a value.
+#[tauri::command]
+pub async fn update_vault_metadata(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    icon: Option<String>,
+    color: Option<String>,
+    description: Option<String>,
+) -> Result<StoredVaultSettings> {
+    let icon = normalize_display_text("Vault icon", icon, crate::limits::MAX_SHORT_FIELD_LEN)?;
+    let color = normalize_display_color(color)?;
+    let description = normalize_display_text(
+        "Vault description",
+        description,
+        crate::limits::MAX_SHORT_FIELD_LEN,
+    )?;
+
+    let stored = {
+        let _guard = SETTINGS_UPDATE_LOCK.lock().await;
+        let mut settings = load_settings_value(&app_handle).await?;
+        let object = settings
+            .as_object_mut()
+            .ok_or_else(|| Error::Internal("Stored settings are not an object".to_string()))?;
+        let by_id = object
+            .entry("vaultSettingsById")
+            .or_insert_with(|| serde_json::json!({}))
+            .as_object_mut()
+            .ok_or_else(|| {
+                Error::Internal("Stored vault settings are not an object".to_string())
+            })?;
+        let entry = by_id
+            .entry(path.clone())
+            .or_insert_with(|| serde_json::json!({}));
+        let fields = entry.as_object_mut().ok_or_else(|| {
+            Error::Internal("Stored vault settings are not an object".to_string())
+        })?;
+        fields.insert("icon".to_string(), serde_json::json!(icon));
+        fields.insert("color".to_string(), serde_json::json!(color));
+        fields.insert("description".to_string(), serde_json::json!(description));
+        let stored: StoredVaultSettings = serde_json::from_value(entry.clone())?;
+
+        crate::settings::set_all_settings(app_handle.clone(), serde_json::to_string(&settings)?)
+            .await?;
+        stored
+    };
+
+    let is_active = state.db_path.lock().await.as_deref() == Some(Path::new(&path));
+    let is_unlocked = state.key.lock().await.is_some();
+    if is_active && is_unlocked {
+        update_stored_display(&state, |display| {
+            display.icon = stored.icon.clone();
+            display.color = stored.color.clone();
+            display.description = stored.description.clone();
+        })
+        .await?;
+    }
+
+    Ok(stored)
+}
+
+const VAULT_EXTENSION: &str = "psec";
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+const MAX_SCAN_DEPTH: usize = 4;
+const MAX_SCAN_ENTRIES: usize = 50_000;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveredVault {
+    pub path: String,
+    pub name: String,
+    /// `metadata` when a Pulsar `.meta.json` sibling was found, `header` when
+    /// only the file itself looks like a vault.
+    pub detected_by: String,
+    pub size_bytes: u64,
+    pub modified_at: Option<i64>,
+    pub known: bool,
+}
+
+async fn has_pulsar_metadata(path: &Path) -> bool {
+    crate::auth::metadata::read_password_metadata(path)
+        .await
+        .ok()
+        .flatten()
+        .is_some()
+}
+
+/// SQLCipher encrypts the whole file including the header, so a `.psec` file
+/// that is page-aligned and does not start with the plain SQLite header is
+/// treated as a candidate vault.
+async fn looks_like_sqlcipher(path: &Path, size: u64) -> bool {
+    use tokio::io::AsyncReadExt;
+
+    if size < 1024 || size % 512 != 0 {
+        return false;
+    }
+
+    let mut header = [0u8; 16];
+    match tokio::fs::File::open(path).await {
+        Ok(mut file) => {
+            if file.read_exact(&mut header).await.is_err() {
+                return false;
+            }
+        }
+        Err(_) => return false,
+    }
+
+    &header != SQLITE_HEADER
+}
+
+async fn inspect_candidate(path: &Path, known: &HashSet<String>) -> Option<DiscoveredVault> {
+    let name = path.file_name()?.to_str()?;
+    if name.ends_with(".meta.json") || name.ends_with(".tmp") {
+        return None;
+    }
+
+    let metadata = tokio::fs::metadata(path).await.ok()?;
+    if !metadata.is_file() {
+        return None;
+    }
+
+    let detected_by = if has_pulsar_metadata(path).await {
+        "metadata"
+    } else if path.extension().and_then(|e| e.to_str()) == Some(VAULT_EXTENSION)
+        && looks_like_sqlcipher(path, metadata.len()).await
+    {
+        "header"
+    } else {
+        return None;
+    };
+
+    let path_str = path.to_string_lossy().to_string();
+    let modified_at = metadata.modified().ok().and_then(|mtime| {
+        mtime
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|duration| duration.as_millis() as i64)
+    });
+
+    Some(DiscoveredVault {
+        known: known.contains(&path_str),
+        name: path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("Vault")
+            .to_string(),
+        path: path_str,
+        detected_by: detected_by.to_string(),
+        size_bytes: metadata.len(),
+        modified_at,
+    })
+}
+
+/// Walks the given directories (up to `MAX_SCAN_DEPTH` levels, skipping hidden
+/// directories and symlinks) looking for vault files. Discovered vaults are
+/// only reported; adding them to the vault list is left to the caller.
+#[tauri::command]
+pub async fn scan_for_vaults(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    directories: Vec<String>,
+) -> Result<Vec<DiscoveredVault>> {
+    crate::limits::check_count(
+        "Directories",
+        directories.len(),
+        crate::limits::MAX_SCAN_DIRECTORIES,
+    )?;
+
+    let stored_settings = load_stored_settings(&app_handle).await?;
+    let active_path = { state.db_path.lock().await.clone() };
+    let known: HashSet<String> = gather_ordered_paths(&stored_settings, &active_path)
+        .into_iter()
+        .collect();
+
+    let mut results = Vec::new();
+    let mut seen = HashSet::new();
+    let mut visited = 0usize;
+    let mut queue: Vec<(PathBuf, usize)> = directories
+        .iter()
+        .map(|dir| (PathBuf::from(dir), 0))
+        .collect();
+
+    while let Some((dir, depth)) = queue.pop() {
+        let mut entries = match tokio::fs::read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(err) => {
+                eprintln!("Failed to scan {}: {}", dir.display(), err);
+                continue;
+            }
+        };
+
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            visited += 1;
+            if visited > MAX_SCAN_ENTRIES {
+                return Ok(results);
+            }
+
+            let Ok(file_type) = entry.file_type().await else {
+                continue;
+            };
+            let path = entry.path();
+
+            if file_type.is_dir() {
+                let hidden = entry.file_name().to_string_lossy().starts_with('.');
+                if !hidden && depth < MAX_SCAN_DEPTH {
+                    queue.push((path, depth + 1));
+                }
+                continue;
+            }
+
+            if !file_type.is_file() || !seen.insert(path.clone()) {
+                continue;
+            }
+
+            if let Some(found) = inspect_candidate(&path, &known).await {
+                results.push(found);
+            }
+        }
+    }
+
+    results.sort_by(|a, b| a.known.cmp(&b.known).then_with(|| a.path.cmp(&b.path)));
+    Ok(results)
+}
@@ -0,0 +1,161 @@
+//! Watches the open vault file for changes made by another process, such as a
+//! sync client (Dropbox, OneDrive) replacing it with a copy from another
+//! machine. SQLite keeps using the file it opened, so writing on after such a
+//! change would silently drop one side's edits or corrupt the vault. Once a
+//! change is seen, writes are refused until `reload_vault` reopens the file.
+
+use crate::error::{Error, Result};
+use crate::state::AppState;
+use notify::event::ModifyKind;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+pub const EXTERNALLY_MODIFIED_EVENT: &str = "vault:externally-modified";
+
+/// Changes this soon after one of our own writes are attributed to this
+/// process (SQLite commits and checkpoints, deferred-write flushes) rather
+/// than to another one.
+const LOCAL_WRITE_GRACE: Duration = Duration::from_secs(5);
+
+static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+static WATCHER: Mutex<Option<RecommendedWatcher>> = Mutex::new(None);
+static LAST_LOCAL_WRITE: Mutex<Option<Instant>> = Mutex::new(None);
+static MODIFIED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalModification {
+    pub path: String,
+    /// `replaced`, `removed` or `modified`.
+    pub kind: &'static str,
+}
+
+pub fn init(app: AppHandle) {
+    let _ = APP_HANDLE.set(app);
+}
+
+/// Records that this process is about to write the vault file.
+pub fn note_local_write() {
+    if let Ok(mut last) = LAST_LOCAL_WRITE.lock() {
+        *last = Some(Instant::now());
+    }
+}
+
+fn recently_written() -> bool {
+    LAST_LOCAL_WRITE
+        .lock()
+        .ok()
+        .and_then(|last| *last)
+        .is_some_and(|at| at.elapsed() < LOCAL_WRITE_GRACE)
+}
+
+/// Rekeys, restores and database switches rewrite the file themselves while
+/// holding the rekey lock.
+fn local_rewrite_in_progress() -> bool {
+    APP_HANDLE
+        .get()
+        .is_some_and(|app| app.state::<AppState>().rekey.try_lock().is_err())
+}
+
+fn classify(event: &Event) -> Option<&'static str> {
+    match event.kind {
+        EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_)) => Some("replaced"),
+        EventKind::Remove(_) => Some("removed"),
+        EventKind::Modify(ModifyKind::Data(_)) | EventKind::Modify(ModifyKind::Any) => {
+            Some("modified")
+        }
+        _ => None,
+    }
+}
+
+fn handle_event(vault_path: &Path, event: Event) {
+    let file_name = vault_path.file_name();
+    if !event.paths.iter().any(|path| path.file_name() == file_name) {
+        return;
+    }
+    let Some(kind) = classify(&event) else {
+        return;
+    };
+    if recently_written() || local_rewrite_in_progress() || MODIFIED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    eprintln!(
+        "[VaultWatch] {} was {} by another process",
+        vault_path.display(),
+        kind
+    );
+    if let Some(app) = APP_HANDLE.get() {
+        let _ = app.emit(
+            EXTERNALLY_MODIFIED_EVENT,
+            ExternalModification {
+                path: vault_path.to_string_lossy().to_string(),
+                kind,
+            },
+        );
+    }
+}
+
+/// Starts watching `db_path`, replacing any previous watch. The directory is
+/// watched rather than the file so replacements by rename are seen too.
+pub fn watch(db_path: &Path) {
+    unwatch();
+
+    let vault_path = db_path
+        .canonicalize()
+        .unwrap_or_else(|_| db_path.to_path_buf());
+    let Some(dir) = vault_path.parent().map(Path::to_path_buf) else {
+        return;
+    };
+
+    let target = vault_path.clone();
+    let watcher = notify::recommended_watcher(move |res: notify::Result<Event>| match res {
+        Ok(event) => handle_event(&target, event),
+        Err(e) => eprintln!("[VaultWatch] Watch error: {}", e),
+    });
+    let mut watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            eprintln!("[VaultWatch] Failed to create watcher: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+        eprintln!("[VaultWatch] Failed to watch {}: {}", dir.display(), e);
+        return;
+    }
+
+    if let Ok(mut guard) = WATCHER.lock() {
+        *guard = Some(watcher);
+    }
+}
+
+/// Stops watching and forgets any detected modification.
+pub fn unwatch() {
+    if let Ok(mut guard) = WATCHER.lock() {
+        guard.take();
+    }
+    MODIFIED.store(false, Ordering::SeqCst);
+}
+
+pub fn is_externally_modified() -> bool {
+    MODIFIED.load(Ordering::SeqCst)
+}
+
+/// Refuses writes to a vault whose file changed underneath the open pool.
+pub fn ensure_current() -> Result<()> {
+    if is_externally_modified() {
+        return Err(Error::VaultModifiedExternally);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_vault_modified_externally() -> Result<bool> {
+    Ok(is_externally_modified())
+}
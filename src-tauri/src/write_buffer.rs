@@ -0,0 +1,343 @@
+//! Deferred writes for vaults on flaky storage such as network shares. While
+//! enabled, the vault is served from a local working copy (still SQLCipher
+//! encrypted) and changes are flushed to the real vault file in batches,
+//! retrying transient failures instead of surfacing them mid-edit.
+
+use crate::error::{Error, Result};
+use crate::state::AppState;
+use chrono::Utc;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{Manager, State};
+use zeroize::Zeroize;
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+const FLUSH_ATTEMPTS: u32 = 5;
+const FLUSH_BACKOFF_BASE_MS: u64 = 500;
+
+static FLUSH_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+pub struct WriteBuffer {
+    pub remote_path: PathBuf,
+    pub local_path: PathBuf,
+    pub dirty: bool,
+    pub last_flush_at: Option<String>,
+    pub last_error: Option<String>,
+    pub flush_task: Option<tokio::task::JoinHandle<()>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteBufferStatus {
+    pub enabled: bool,
+    pub pending_writes: bool,
+    pub last_flush_at: Option<String>,
+    pub last_error: Option<String>,
+}
+
+async fn status_of(state: &AppState) -> WriteBufferStatus {
+    match state.write_buffer.lock().await.as_ref() {
+        Some(buffer) => WriteBufferStatus {
+            enabled: true,
+            pending_writes: buffer.dirty,
+            last_flush_at: buffer.last_flush_at.clone(),
+            last_error: buffer.last_error.clone(),
+        },
+        None => WriteBufferStatus {
+            enabled: false,
+            pending_writes: false,
+            last_flush_at: None,
+            last_error: None,
+        },
+    }
+}
+
+/// Called before every vault mutation so the next flush picks it up.
+pub async fn mark_dirty(state: &AppState) {
+    crate::vault_watch::note_local_write();
+    if let Some(buffer) = state.write_buffer.lock().await.as_mut() {
+        buffer.dirty = true;
+    }
+}
+
+/// Path the connection pool should open for `db_path`: the local working copy
+/// while writes are buffered for that vault, otherwise the vault itself.
+pub async fn pool_path(state: &AppState, db_path: &Path) -> PathBuf {
+    match state.write_buffer.lock().await.as_ref() {
+        Some(buffer) if buffer.remote_path == db_path => buffer.local_path.clone(),
+        _ => db_path.to_path_buf(),
+    }
+}
+
+fn working_copy_path(app: &tauri::AppHandle, remote: &Path) -> Result<PathBuf> {
+    let dir = app
+        .path()
+        .app_local_data_dir()
+        .map_err(|e| Error::Internal(format!("Failed to resolve app data dir: {e}")))?
+        .join("write-buffer");
+    let digest = Sha256::digest(remote.to_string_lossy().as_bytes());
+    Ok(dir.join(format!("{}.psec", hex::encode(&digest[..16]))))
+}
+
+/// Writes a consistent, encrypted copy of the open vault to `target`.
+async fn export_snapshot(pool: &sqlx::SqlitePool, key: &[u8], target: &Path) -> Result<()> {
+    if let Some(parent) = target.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let _ = tokio::fs::remove_file(target).await;
+
+    let mut hex_key = hex::encode(key);
+    let mut conn = pool.acquire().await?;
+    let attached = crate::auth::commands::attach_encrypted_db(&mut conn, target, &hex_key).await;
+    hex_key.zeroize();
+    attached?;
+
+    let exported = sqlx::query("SELECT sqlcipher_export('encrypted')")
+        .execute(&mut *conn)
+        .await;
+    sqlx::query("DETACH DATABASE encrypted")
+        .execute(&mut *conn)
+        .await?;
+    exported?;
+    Ok(())
+}
+
+/// Copies `snapshot` next to `remote` and renames it into place so readers of
+/// the vault never observe a partially written file.
+async fn publish_snapshot(snapshot: &Path, remote: &Path) -> Result<()> {
+    let file_name = remote
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("vault.psec");
+    let tmp = remote.with_file_name(format!(".{}.flush-tmp", file_name));
+
+    tokio::fs::copy(snapshot, &tmp).await?;
+    tokio::fs::File::open(&tmp).await?.sync_all().await?;
+    if let Err(e) = tokio::fs::rename(&tmp, remote).await {
+        let _ = tokio::fs::remove_file(&tmp).await;
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+async fn flush_inner(state: &AppState) -> Result<()> {
+    let _flush_guard = FLUSH_LOCK.lock().await;
+
+    let (remote, local) = {
+        let mut guard = state.write_buffer.lock().await;
+        let Some(buffer) = guard.as_mut() else {
+            return Ok(());
+        };
+        if !buffer.dirty {
+            return Ok(());
+        }
+        // Cleared before the snapshot so writes made while flushing are kept
+        // for the next batch.
+        buffer.dirty = false;
+        (buffer.remote_path.clone(), buffer.local_path.clone())
+    };
+
+    let result = async {
+        let key = state.key.lock().await.clone().ok_or(Error::VaultLocked)?;
+        let pool = state.db.lock().await.clone().ok_or(Error::VaultNotLoaded)?;
+        let snapshot = local.with_extension("snapshot");
+        export_snapshot(&pool, key.as_slice(), &snapshot).await?;
+
+        let mut attempt = 0;
+        let published = loop {
+            crate::vault_watch::note_local_write();
+            match publish_snapshot(&snapshot, &remote).await {
+                Ok(()) => break Ok(()),
+                Err(e) if attempt + 1 < FLUSH_ATTEMPTS => {
+                    eprintln!("[WriteBuffer] Flush attempt {} failed: {}", attempt + 1, e);
+                    let backoff = FLUSH_BACKOFF_BASE_MS * 2u64.pow(attempt);
+                    tokio::time::sleep(Duration::from_millis(backoff)).await;
+                    attempt += 1;
+                }
+                Err(e) => break Err(e),
+            }
+        };
+        let _ = tokio::fs::remove_file(&snapshot).await;
+        published
+    }
+    .await;
+
+    if let Some(buffer) = state.write_buffer.lock().await.as_mut() {
+        match &result {
+            Ok(()) => {
+                buffer.last_flush_at = Some(Utc::now().to_rfc3339());
+                buffer.last_error = None;
+            }
+            Err(e) => {
+                buffer.dirty = true;
+                buffer.last_error = Some(e.to_string());
+            }
+        }
+    }
+
+    result
+}
+
+fn spawn_flush_loop(state: AppState, remote: PathBuf) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(FLUSH_INTERVAL).await;
+
+            let active = matches!(
+                state.write_buffer.lock().await.as_ref(),
+                Some(buffer) if buffer.remote_path == remote
+            );
+            if !active {
+                break;
+            }
+            if state.key.lock().await.is_none() {
+                continue;
+            }
+            if let Err(e) = flush_inner(&state).await {
+                eprintln!("[WriteBuffer] Deferred flush failed: {}", e);
+            }
+        }
+    })
+}
+
+async fn reopen_pool(state: &AppState, path: &Path, key: &[u8]) -> Result<()> {
+    if let Some(pool) = state.db.lock().await.take() {
+        pool.close().await;
+    }
+    let pool = crate::db::init_db_lazy(path, Some(key), false)
+        .await
+        .map_err(Error::Internal)?;
+    *state.db.lock().await = Some(pool);
+    Ok(())
+}
+
+/// Flushes outstanding changes and serves the vault directly from its file
+/// again. Operations that rewrite the vault file call this first.
+pub async fn suspend(state: &AppState) -> Result<()> {
+    if state.write_buffer.lock().await.is_none() {
+        return Ok(());
+    }
+
+    flush_inner(state).await?;
+
+    let Some(mut buffer) = state.write_buffer.lock().await.take() else {
+        return Ok(());
+    };
+    if let Some(task) = buffer.flush_task.take() {
+        task.abort();
+    }
+
+    if let Some(key) = state.key.lock().await.clone() {
+        reopen_pool(state, &buffer.remote_path, key.as_slice()).await?;
+    }
+    let _ = tokio::fs::remove_file(&buffer.local_path).await;
+    Ok(())
+}
+
+/// Tries to flush before the vault is locked or switched. Changes that cannot
+/// be flushed stay in the working copy and are picked up again on unlock.
+pub async fn flush_before_close(state: &AppState) {
+    if state.write_buffer.lock().await.is_none() {
+        return;
+    }
+    if let Err(e) = flush_inner(state).await {
+        eprintln!("[WriteBuffer] Flush before close failed: {}", e);
+    }
+}
+
+/// Stops buffering without flushing so the vault is served from its file
+/// again. Fails instead when buffered changes would be lost.
+pub async fn discard(state: &AppState) -> Result<()> {
+    let mut guard = state.write_buffer.lock().await;
+    if let Some(buffer) = guard.as_ref().filter(|buffer| buffer.dirty) {
+        return Err(Error::Validation(format!(
+            "{} has changes that have not been written to the vault file yet.",
+            buffer.remote_path.display()
+        )));
+    }
+    let Some(mut buffer) = guard.take() else {
+        return Ok(());
+    };
+    drop(guard);
+
+    if let Some(task) = buffer.flush_task.take() {
+        task.abort();
+    }
+    let _ = tokio::fs::remove_file(&buffer.local_path).await;
+    Ok(())
+}
+
+/// Refuses to leave a vault whose buffered changes never reached its file.
+pub async fn ensure_flushed(state: &AppState) -> Result<()> {
+    match state.write_buffer.lock().await.as_ref() {
+        Some(buffer) if buffer.dirty => Err(Error::Validation(format!(
+            "{} has changes that have not been written to the vault file yet. Unlock it and flush pending writes first.",
+            buffer.remote_path.display()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub async fn set_deferred_writes(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    enabled: bool,
+) -> Result<WriteBufferStatus> {
+    let _rekey_lock = state.rekey.lock().await;
+
+    if !enabled {
+        suspend(&state).await?;
+        return Ok(status_of(&state).await);
+    }
+
+    let db_path = state
+        .db_path
+        .lock()
+        .await
+        .clone()
+        .ok_or(Error::VaultNotLoaded)?;
+    if state.write_buffer.lock().await.is_some() {
+        return Ok(status_of(&state).await);
+    }
+
+    crate::storage::ensure_writable()?;
+    let key = state.key.lock().await.clone().ok_or(Error::VaultLocked)?;
+    let pool = state.db.lock().await.clone().ok_or(Error::VaultNotLoaded)?;
+
+    let local_path = working_copy_path(&app, &db_path)?;
+    export_snapshot(&pool, key.as_slice(), &local_path).await?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ =
+            tokio::fs::set_permissions(&local_path, std::fs::Permissions::from_mode(0o600)).await;
+    }
+
+    reopen_pool(&state, &local_path, key.as_slice()).await?;
+
+    let flush_task = spawn_flush_loop(state.inner().clone(), db_path.clone());
+    *state.write_buffer.lock().await = Some(WriteBuffer {
+        remote_path: db_path,
+        local_path,
+        dirty: false,
+        last_flush_at: None,
+        last_error: None,
+        flush_task: Some(flush_task),
+    });
+
+    Ok(status_of(&state).await)
+}
+
+#[tauri::command]
+pub async fn flush_pending_writes(state: State<'_, AppState>) -> Result<WriteBufferStatus> {
+    flush_inner(&state).await?;
+    Ok(status_of(&state).await)
+}
+
+#[tauri::command]
+pub async fn get_write_buffer_status(state: State<'_, AppState>) -> Result<WriteBufferStatus> {
+    Ok(status_of(&state).await)
+}
@@ -14,6 +14,7 @@ export interface AdvancedSettings {
   wipeConfirmationText: string;
   lockMemoryPages: boolean;
   secureMemoryAllocation: boolean;
+  sqlConsole: boolean;
 }
 
 export const defaultAdvancedSettings: AdvancedSettings = {
@@ -23,7 +24,8 @@ export const defaultAdvancedSettings: AdvancedSettings = {
   parallelism: 4,
   wipeConfirmationText: '',
   lockMemoryPages: true,
-  secureMemoryAllocation: true
+  secureMemoryAllocation: true,
+  sqlConsole: false
 };
 
 export interface AppearanceSettings {
@@ -51,6 +53,7 @@ export const defaultAppearanceSettings: AppearanceSettings = {
 export interface AutofillSettings {
   browserAutofill: boolean;
   globalAutotype: boolean;
+  globalAutotypeShortcut: string;
   osUnlock: boolean;
   perSiteConfirmation: boolean;
 }
@@ -58,6 +61,7 @@ export interface AutofillSettings {
 export const defaultAutofillSettings: AutofillSettings = {
   browserAutofill: false,
   globalAutotype: false,
+  globalAutotypeShortcut: 'CommandOrControl+Alt+A',
   osUnlock: true,
   perSiteConfirmation: true
 };
@@ -168,6 +172,12 @@ export interface SecuritySettings {
   clearClipboardOnCopy: boolean;
   clipboardClearTime: number;
   useTotp: boolean;
+  binaryIntegrityCheck: boolean;
+  faviconOfflineOnly: boolean;
+  attachmentScanner: string;
+  recordAccessContext: boolean;
+  /** Hide the app from screenshots, recordings and remote desktop. */
+  screenCaptureProtection: boolean;
 }
 
 export const defaultSecuritySettings: SecuritySettings = {
@@ -182,7 +192,12 @@ export const defaultSecuritySettings: SecuritySettings = {
   secureRAMHandling: true,
   clearClipboardOnCopy: false,
   clipboardClearTime: 0,
-  useTotp: false
+  useTotp: false,
+  binaryIntegrityCheck: true,
+  faviconOfflineOnly: false,
+  attachmentScanner: '',
+  recordAccessContext: false,
+  screenCaptureProtection: false
 };
 
 export interface VaultSettings {
@@ -190,17 +205,72 @@ export interface VaultSettings {
   totp: boolean;
   backups: boolean;
   compression: boolean;
+  deferredWrites: boolean;
+  /** Lock this vault when switching to another one. */
+  relockOnSwitch: boolean;
+  icon?: string | null;
+  color?: string | null;
+  description?: string | null;
 }
 
 export const defaultVaultSettings: VaultSettings = {
   name: 'New Vault',
   totp: true,
   backups: false,
-  compression: false
+  compression: false,
+  deferredWrites: false,
+  relockOnSwitch: true
 };
 
 export type VaultSettingsMap = Record<string, VaultSettings>;
 
+export interface PasswordPolicy {
+  minLength: number;
+  requireUppercase: boolean;
+  requireLowercase: boolean;
+  requireDigits: boolean;
+  requireSymbols: boolean;
+  maxAgeDays?: number;
+}
+
+export type SensitiveOperation =
+  | 'rotate_master_password'
+  | 'export_vault'
+  | 'view_login_totp_secret';
+
+/**
+ * Operations that need the master password or a biometric verified within
+ * the last `windowSeconds`; they fail with `VerificationRequired` otherwise.
+ */
+export interface ReverificationPolicy {
+  windowSeconds: number;
+  operations: SensitiveOperation[];
+}
+
+/**
+ * Preferences stored encrypted inside the vault file itself (see
+ * `get_vault_settings`). `null` falls back to the app-wide setting.
+ */
+export interface VaultScopedSettings {
+  clipboardClearSeconds: number | null;
+  autoLockMinutes: number | null;
+  defaultCategory: string | null;
+  passwordPolicy: PasswordPolicy | null;
+  reverification: ReverificationPolicy | null;
+  maxAttachmentSizeMb: number | null;
+  maxAttachmentsTotalMb: number | null;
+}
+
+export const defaultVaultScopedSettings: VaultScopedSettings = {
+  clipboardClearSeconds: null,
+  autoLockMinutes: null,
+  defaultCategory: null,
+  passwordPolicy: null,
+  reverification: null,
+  maxAttachmentSizeMb: null,
+  maxAttachmentsTotalMb: null
+};
+
 export const defaultVaultSettingsMap: VaultSettingsMap = {};
 
 export interface PasswordPreset {
@@ -344,6 +414,7 @@ export interface AllSettings {
   keybinds: Keybind[];
   passwordPresets: PasswordPreset[];
   recentDatabases: string[];
+  pinnedVaults?: string[];
   siteRules: SiteRule[];
   security: SecuritySettings;
   vaultSettingsById: VaultSettingsMap;
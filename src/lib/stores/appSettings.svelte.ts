@@ -121,7 +121,6 @@ class SettingsStore {
     try {
       const snapshot = $state.snapshot(this.state);
       await callBackend('set_all_settings', { settings: JSON.stringify(snapshot) });
-      await callBackend('apply_system_settings');
     } catch (error) {
       console.error('Failed to save settings:', error);
     } finally {
@@ -19,34 +19,39 @@ async function filterNonExistentDatabases(paths: string[]): Promise<string[]> {
   return existentPaths;
 }
 
+interface RecentVaults {
+  recent: string[];
+  pinned: string[];
+}
+
+// The backend owns the lists and has already saved them.
+function applyRecentVaults(result: RecentVaults) {
+  settings.state.recentDatabases = result.recent;
+  settings.state.pinnedVaults = result.pinned;
+}
+
 export async function addRecentDatabase(path: string) {
   try {
-    void callBackend('check_file_exists', { path })
-      .then((exists) => {
-        if (!exists) {
-          console.warn(`Recent path does not yet exist (will be trimmed later): ${path}`);
-        }
-      })
-      .catch(() => {});
-  } catch {
-    // ignore
+    applyRecentVaults(await callBackend<RecentVaults>('add_recent_vault', { path }));
+  } catch (e) {
+    console.warn(`Failed to add ${path} to the recent vaults`, e);
   }
+}
 
-  const current = settings.state.recentDatabases || [];
-  const filteredPaths = current.filter((p) => p !== path);
-  settings.state.recentDatabases = [path, ...filteredPaths].slice(0, 5);
-  settings.save();
+export async function removeRecentDatabase(path: string) {
+  try {
+    applyRecentVaults(await callBackend<RecentVaults>('remove_recent_vault', { path }));
+  } catch (e) {
+    console.warn(`Failed to remove ${path} from the recent vaults`, e);
+  }
 }
 
-export function removeRecentDatabase(path: string) {
-  const current = settings.state.recentDatabases || [];
-  settings.state.recentDatabases = current.filter((p) => p !== path);
-  settings.save();
+export async function pinRecentDatabase(path: string, pinned: boolean) {
+  applyRecentVaults(await callBackend<RecentVaults>('pin_vault', { path, pinned }));
 }
 
-export function clearRecentDatabases() {
-  settings.state.recentDatabases = [];
-  settings.save();
+export async function clearRecentDatabases() {
+  applyRecentVaults(await callBackend<RecentVaults>('clear_recent_vaults'));
 }
 
 export async function pruneRecentDatabases() {
@@ -6,9 +6,16 @@ export interface TagButton {
   text: string;
   icon: string;
   color: string;
+  parent_id?: number | null;
   count?: number;
 }
 
+export interface TagTreeNode extends TagButton {
+  itemCount: number;
+  subtreeItemCount: number;
+  children: TagTreeNode[];
+}
+
 export type TagInput = Omit<TagButton, 'id' | 'count'> & { id?: number };
 
 class TagStore {
@@ -51,7 +58,8 @@ class TagStore {
     await callBackend('save_button', {
       text: tag.text,
       icon: tag.icon,
-      color: tag.color
+      color: tag.color,
+      parentId: tag.parent_id ?? null
     });
     await this.refresh();
   }
@@ -61,7 +69,8 @@ class TagStore {
       id: tag.id,
       text: tag.text,
       icon: tag.icon,
-      color: tag.color
+      color: tag.color,
+      parentId: tag.parent_id ?? null
     });
     const index = this.#tags.findIndex((t) => t.id === tag.id);
     if (index !== -1) {
@@ -69,6 +78,10 @@ class TagStore {
     }
   }
 
+  async tree() {
+    return await callBackend<TagTreeNode[]>('get_tag_tree');
+  }
+
   async remove(id: number) {
     await callBackend('delete_button', { id });
     this.#tags = this.#tags.filter((t) => t.id !== id);
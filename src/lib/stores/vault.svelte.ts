@@ -95,7 +95,9 @@ class VaultStore {
       tagId: tagObj?.id ?? null,
       category: category === 'all' ? null : category,
       limit: this.#limit,
-      offset: this.#offset
+      offset: this.#offset,
+      includeSubtags: true,
+      maxTypos: 1
     });
   }
 
@@ -9,7 +9,7 @@ export interface Attachment {
 
 export interface PasswordItemOverview {
   id: number;
-  category: 'login' | 'card' | 'identity' | 'note';
+  category: 'login' | 'card' | 'identity' | 'note' | 'wifi';
   title: string;
   description: string | null;
   img: string | null;
@@ -19,6 +19,17 @@ export interface PasswordItemOverview {
   created_at: string;
   updated_at: string;
   color: string | null;
+  score?: number;
+}
+
+export interface OverviewCursor {
+  updated_at: string;
+  id: number;
+}
+
+export interface OverviewPage {
+  items: PasswordItemOverview[];
+  next_cursor: OverviewCursor | null;
 }
 
 export interface PasswordItem extends PasswordItemOverview {
@@ -28,6 +39,108 @@ export interface PasswordItem extends PasswordItemOverview {
   custom_fields: { name: string; value: string; field_type: string }[];
   field_order?: string[] | null;
   attachments?: Attachment[];
+  autotype_patterns?: string[] | null;
+  details?: ItemDetails | null;
+  /** Removes the stored details on update; a missing `details` keeps them. */
+  clear_details?: boolean;
+}
+
+export interface PostalAddress {
+  street?: string | null;
+  city?: string | null;
+  region?: string | null;
+  postal_code?: string | null;
+  country?: string | null;
+}
+
+export interface CardDetails {
+  kind: 'card';
+  cardholder_name?: string | null;
+  number?: string | null;
+  brand?: string | null;
+  expiry_month?: number | null;
+  expiry_year?: number | null;
+  security_code?: string | null;
+  pin?: string | null;
+  billing_address?: PostalAddress | null;
+}
+
+export interface IdentityDetails {
+  kind: 'identity';
+  honorific?: string | null;
+  first_name?: string | null;
+  middle_name?: string | null;
+  last_name?: string | null;
+  email?: string | null;
+  phone?: string | null;
+  birth_date?: string | null;
+  company?: string | null;
+  job_title?: string | null;
+  address?: PostalAddress | null;
+  national_id?: string | null;
+  passport_number?: string | null;
+  license_number?: string | null;
+}
+
+export interface ItemTemplate {
+  id: number;
+  name: string;
+  category: string;
+  custom_fields: { name: string; value: string; field_type: string }[];
+  field_order: string[] | null;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface WifiDetails {
+  kind: 'wifi';
+  ssid: string;
+  security: 'wpa' | 'wep' | 'none';
+  hidden?: boolean;
+}
+
+export type ItemDetails = CardDetails | IdentityDetails | WifiDetails;
+
+export interface ItemDraft {
+  id: number;
+  item_id: number | null;
+  data: string;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface ItemConflict {
+  id: number;
+  item_id: number;
+  source: string;
+  local: PasswordItem;
+  incoming: PasswordItem;
+  detected_at: string;
+  expires_at: string;
+}
+
+export type ConflictResolution =
+  | { choice: 'keep_local' }
+  | { choice: 'keep_incoming' }
+  | { choice: 'merged'; item: PasswordItem };
+
+export interface MergeSummary {
+  added: number;
+  unchanged: number;
+  conflicts: number;
+}
+
+export interface SearchFilters {
+  categories?: string[];
+  createdAfter?: string;
+  createdBefore?: string;
+  updatedAfter?: string;
+  updatedBefore?: string;
+  hasAttachment?: boolean;
+  hasTotp?: boolean;
+  passwordOlderThanDays?: number;
+  color?: string;
+  folderId?: number;
 }
 
 export interface ActivityEntry {
@@ -37,4 +150,46 @@ export interface ActivityEntry {
   itemTitle: string | null;
   details: string | null;
   createdAt: string;
+  context?: AccessContext;
+}
+
+export interface ActivityLogFilter {
+  eventTypes?: string[];
+  itemId?: number;
+  /** Inclusive RFC 3339 bounds. */
+  from?: string;
+  to?: string;
+  limit?: number;
+}
+
+export interface ActivityLogPage {
+  entries: ActivityEntry[];
+  nextCursor: number | null;
+}
+
+export interface ActivityLogVerification {
+  intact: boolean;
+  verifiedEntries: number;
+  unchainedEntries: number;
+  firstBrokenId: number | null;
+  truncated: boolean;
+  unattestedAnchor: boolean;
+}
+
+export type ActivityPeriod = 'week' | 'month' | 'quarter' | 'year';
+
+export interface ActivitySummary {
+  period: ActivityPeriod;
+  from: string;
+  to: string;
+  days: { date: string; counts: Record<string, number> }[];
+  totals: Record<string, number>;
+  mostEdited: { itemId: number; title: string | null; edits: number; deleted: boolean }[];
+  unlocks: { total: number; averagePerDay: number; busiestDay: string | null };
+}
+
+export interface AccessContext {
+  hostname: string | null;
+  osUser: string | null;
+  appVersion: string;
 }
@@ -60,6 +60,7 @@ export enum BackendErrorCode {
   Database = 'Database',
   VaultLocked = 'VaultLocked',
   VaultNotLoaded = 'VaultNotLoaded',
+  VaultTooNew = 'VaultTooNew',
   InvalidPassword = 'InvalidPassword',
   Validation = 'Validation',
   Internal = 'Internal'
@@ -0,0 +1,24 @@
+import { callBackend } from './backend';
+
+/**
+ * Encrypted, vault-scoped storage for frontend state that references vault
+ * contents. Values are JSON encoded.
+ */
+export const secureKv = {
+  async get<T>(ns: string, key: string): Promise<T | null> {
+    const raw = await callBackend<string | null>('secure_kv_get', { ns, key });
+    return raw === null ? null : (JSON.parse(raw) as T);
+  },
+
+  async set<T>(ns: string, key: string, value: T): Promise<void> {
+    await callBackend('secure_kv_set', { ns, key, value: JSON.stringify(value) });
+  },
+
+  async remove(ns: string, key: string): Promise<void> {
+    await callBackend('secure_kv_delete', { ns, key });
+  },
+
+  async keys(ns: string): Promise<string[]> {
+    return await callBackend<string[]>('secure_kv_keys', { ns });
+  }
+};
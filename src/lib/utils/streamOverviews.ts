@@ -0,0 +1,19 @@
+import { Channel } from '@tauri-apps/api/core';
+import type { PasswordItemOverview } from '../types/password';
+import { callBackend } from './backend';
+
+/**
+ * Loads every item overview in batches, newest first, calling `onBatch` as
+ * each batch is decrypted. Resolves with the total number of items.
+ */
+export async function streamPasswordOverviews(
+  onBatch: (items: PasswordItemOverview[]) => void,
+  batchSize?: number
+): Promise<number> {
+  const channel = new Channel<PasswordItemOverview[]>();
+  channel.onmessage = onBatch;
+  return await callBackend<number>('stream_password_overviews', {
+    onBatch: channel,
+    batchSize
+  });
+}
@@ -0,0 +1,81 @@
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+
+export const ITEM_CREATED_EVENT = 'item:created';
+export const ITEM_UPDATED_EVENT = 'item:updated';
+export const ITEM_DELETED_EVENT = 'item:deleted';
+export const TAG_CHANGED_EVENT = 'tag:changed';
+export const VAULT_LOCKED_EVENT = 'vault:locked';
+export const VAULT_EXTERNALLY_MODIFIED_EVENT = 'vault:externally-modified';
+
+export interface ItemsChangedPayload {
+  ids: number[];
+}
+
+export interface TagChangedPayload {
+  /** `null` when several tags may have changed. */
+  id: number | null;
+}
+
+/** The vault file changed on disk; writes fail until `reload_vault` runs. */
+export interface ExternalModificationPayload {
+  path: string;
+  kind: 'replaced' | 'removed' | 'modified';
+}
+
+export interface VaultEventHandlers {
+  onItemsCreated?: (ids: number[]) => void;
+  onItemsUpdated?: (ids: number[]) => void;
+  onItemsDeleted?: (ids: number[]) => void;
+  onTagChanged?: (id: number | null) => void;
+  onVaultLocked?: () => void;
+  onExternallyModified?: (payload: ExternalModificationPayload) => void;
+}
+
+/**
+ * Subscribes to the backend's change events. Resolves with a function that
+ * removes every listener it registered.
+ */
+export async function listenToVaultEvents(handlers: VaultEventHandlers): Promise<UnlistenFn> {
+  const unlisteners: UnlistenFn[] = [];
+  const {
+    onItemsCreated,
+    onItemsUpdated,
+    onItemsDeleted,
+    onTagChanged,
+    onVaultLocked,
+    onExternallyModified
+  } = handlers;
+
+  if (onItemsCreated) {
+    unlisteners.push(
+      await listen<ItemsChangedPayload>(ITEM_CREATED_EVENT, (e) => onItemsCreated(e.payload.ids))
+    );
+  }
+  if (onItemsUpdated) {
+    unlisteners.push(
+      await listen<ItemsChangedPayload>(ITEM_UPDATED_EVENT, (e) => onItemsUpdated(e.payload.ids))
+    );
+  }
+  if (onItemsDeleted) {
+    unlisteners.push(
+      await listen<ItemsChangedPayload>(ITEM_DELETED_EVENT, (e) => onItemsDeleted(e.payload.ids))
+    );
+  }
+  if (onTagChanged) {
+    unlisteners.push(
+      await listen<TagChangedPayload>(TAG_CHANGED_EVENT, (e) => onTagChanged(e.payload.id))
+    );
+  }
+  if (onVaultLocked) {
+    unlisteners.push(await listen(VAULT_LOCKED_EVENT, () => onVaultLocked()));
+  }
+  if (onExternallyModified) {
+    unlisteners.push(
+      await listen<ExternalModificationPayload>(VAULT_EXTERNALLY_MODIFIED_EVENT, (e) =>
+        onExternallyModified(e.payload)
+      )
+    );
+  }
+
+  return () => unlisteners.forEach((unlisten) => unlisten());
+}